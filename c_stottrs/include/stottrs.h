@@ -0,0 +1,41 @@
+#ifndef STOTTRS_H
+#define STOTTRS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Arrow C data interface, see https://arrow.apache.org/docs/format/CDataInterface.html */
+struct ArrowSchema;
+struct ArrowArray;
+
+typedef struct StottrsMapping StottrsMapping;
+
+/* The error message of the last failed call on this thread, or NULL. Owned by the library.
+   Panics inside the library are caught and reported as failed calls. */
+const char *stottrs_last_error(void);
+
+/* Creates a mapping from a stOTTR document, returns NULL on failure. */
+StottrsMapping *stottrs_mapping_from_str(const char *document);
+
+void stottrs_mapping_free(StottrsMapping *mapping);
+
+/* Expands the template with a record batch exported as a struct array with one field per column.
+   The array is moved into the library. Returns 0 on success and -1 on failure. */
+int stottrs_expand(StottrsMapping *mapping, const char *template_name, struct ArrowArray *array,
+                   const struct ArrowSchema *schema);
+
+/* Serializes the triples as N-Triples into a buffer that must be freed with stottrs_buffer_free.
+   Returns 0 on success and -1 on failure. */
+int stottrs_write_n_triples(StottrsMapping *mapping, uint8_t **buffer, size_t *length);
+
+void stottrs_buffer_free(uint8_t *buffer, size_t length);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,181 @@
+//! C interface to stOTTRs, see include/stottrs.h.
+//! Functions returning a status return 0 on success and -1 on failure,
+//! in which case the error message is available from stottrs_last_error until the next call on the same thread.
+//! Panics are caught and reported in the same way, as they must not unwind into C.
+use polars_core::prelude::{DataFrame, Series};
+use polars_core::utils::arrow::array::StructArray;
+use polars_core::utils::arrow::ffi;
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use stottrs::mapping::Mapping;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| Err(panic_message(payload)))
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        format!("Panic: {}", message)
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        format!("Panic: {}", message)
+    } else {
+        "Panic".to_string()
+    }
+}
+
+unsafe fn str_from_c<'a>(s: *const c_char, name: &str) -> Result<&'a str, String> {
+    if s.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|x| format!("{} is not valid UTF-8: {}", name, x))
+}
+
+/// The error message of the last failed call on this thread, or null. Owned by the library.
+#[no_mangle]
+pub extern "C" fn stottrs_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| {
+        e.borrow()
+            .as_ref()
+            .map(|x| x.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// Creates a mapping from a stOTTR document, returns null on failure.
+/// The mapping must be freed with stottrs_mapping_free.
+/// # Safety
+/// The document must be a null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn stottrs_mapping_from_str(document: *const c_char) -> *mut Mapping {
+    let result = catch_panic(|| {
+        let document = str_from_c(document, "document")?;
+        Mapping::from_str(document, None).map_err(|x| format!("{}", x))
+    });
+    match result {
+        Ok(mapping) => Box::into_raw(Box::new(mapping)),
+        Err(e) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// # Safety
+/// The mapping must come from stottrs_mapping_from_str and not be used after it is freed.
+#[no_mangle]
+pub unsafe extern "C" fn stottrs_mapping_free(mapping: *mut Mapping) {
+    if !mapping.is_null() {
+        drop(Box::from_raw(mapping));
+    }
+}
+
+/// Expands the template with a record batch given with the Arrow C data interface,
+/// as a struct array with one field per column.
+/// The array is moved into the library, its release callback is called when the data is no longer used.
+/// # Safety
+/// The template must be a null terminated string, and the array and schema must follow the Arrow C data interface.
+#[no_mangle]
+pub unsafe extern "C" fn stottrs_expand(
+    mapping: *mut Mapping,
+    template: *const c_char,
+    array: *mut ffi::ArrowArray,
+    schema: *const ffi::ArrowSchema,
+) -> c_int {
+    if mapping.is_null() || array.is_null() || schema.is_null() {
+        set_last_error("mapping, array and schema must not be null".to_string());
+        return -1;
+    }
+    let array = ptr::replace(array, ffi::ArrowArray::empty());
+    let result = catch_panic(|| {
+        let template = str_from_c(template, "template")?;
+        let df = record_batch_to_df(array, &*schema)?;
+        (*mapping)
+            .expand(template, df, Default::default())
+            .map_err(|x| format!("{}", x))
+    });
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            set_last_error(e);
+            -1
+        }
+    }
+}
+
+/// Serializes the triples of the mapping as N-Triples.
+/// On success, the buffer and its length are written to the out parameters,
+/// the buffer must be freed with stottrs_buffer_free.
+/// # Safety
+/// The mapping must come from stottrs_mapping_from_str.
+#[no_mangle]
+pub unsafe extern "C" fn stottrs_write_n_triples(
+    mapping: *mut Mapping,
+    buffer: *mut *mut u8,
+    length: *mut usize,
+) -> c_int {
+    if mapping.is_null() || buffer.is_null() || length.is_null() {
+        set_last_error("mapping, buffer and length must not be null".to_string());
+        return -1;
+    }
+    let result = catch_panic(|| {
+        let mut out = vec![];
+        (*mapping)
+            .write_n_triples(&mut out)
+            .map_err(|x| format!("{}", x))?;
+        Ok(out.into_boxed_slice())
+    });
+    match result {
+        Ok(out) => {
+            *length = out.len();
+            *buffer = Box::into_raw(out) as *mut u8;
+            0
+        }
+        Err(e) => {
+            set_last_error(e);
+            -1
+        }
+    }
+}
+
+/// # Safety
+/// The buffer and length must come from stottrs_write_n_triples.
+#[no_mangle]
+pub unsafe extern "C" fn stottrs_buffer_free(buffer: *mut u8, length: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, length)));
+    }
+}
+
+unsafe fn record_batch_to_df(
+    array: ffi::ArrowArray,
+    schema: &ffi::ArrowSchema,
+) -> Result<DataFrame, String> {
+    let field = ffi::import_field_from_c(schema).map_err(|x| format!("{}", x))?;
+    let array = ffi::import_array_from_c(array, field.data_type).map_err(|x| format!("{}", x))?;
+    let struct_array = array
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .ok_or_else(|| "The record batch must be a struct array".to_string())?;
+    let mut columns = vec![];
+    for (f, values) in struct_array.fields().iter().zip(struct_array.values()) {
+        let series =
+            Series::try_from((f.name.as_str(), values.clone())).map_err(|x| format!("{}", x))?;
+        columns.push(series);
+    }
+    DataFrame::new(columns).map_err(|x| format!("{}", x))
+}
@@ -0,0 +1,68 @@
+use polars_core::prelude::{DataFrame, Series};
+use polars_core::utils::arrow::array::StructArray;
+use polars_core::utils::arrow::datatypes::{DataType as ArrowDataType, Field};
+use polars_core::utils::arrow::ffi;
+use rstest::*;
+use std::ffi::CString;
+use std::ptr;
+use stottrs_c::{
+    stottrs_buffer_free, stottrs_expand, stottrs_last_error, stottrs_mapping_free,
+    stottrs_mapping_from_str, stottrs_write_n_triples,
+};
+
+fn export_record_batch() -> (ffi::ArrowArray, ffi::ArrowSchema) {
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([1i64]);
+    number.rename("number");
+    let df = DataFrame::new(vec![thing, number]).unwrap().agg_chunks();
+    let fields: Vec<Field> = df
+        .get_columns()
+        .iter()
+        .map(|s| Field::new(s.name(), s.to_arrow(0).data_type().clone(), true))
+        .collect();
+    let values = df.get_columns().iter().map(|s| s.to_arrow(0)).collect();
+    let struct_array = StructArray::new(ArrowDataType::Struct(fields.clone()), values, None);
+    let array = ffi::export_array_to_c(Box::new(struct_array));
+    let schema = ffi::export_field_to_c(&Field::new("", ArrowDataType::Struct(fields), false));
+    (array, schema)
+}
+
+#[rstest]
+fn test_expand_and_write_n_triples() {
+    let t_str = CString::new(
+        r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#,
+    )
+    .unwrap();
+    let (mut array, schema) = export_record_batch();
+    unsafe {
+        let mapping = stottrs_mapping_from_str(t_str.as_ptr());
+        assert!(!mapping.is_null());
+        let template = CString::new("http://example.net/ns#ExampleTemplate").unwrap();
+        let status = stottrs_expand(mapping, template.as_ptr(), &mut array, &schema);
+        assert_eq!(status, 0);
+
+        let mut buffer = ptr::null_mut();
+        let mut length = 0;
+        assert_eq!(stottrs_write_n_triples(mapping, &mut buffer, &mut length), 0);
+        let n_triples = std::str::from_utf8(std::slice::from_raw_parts(buffer, length)).unwrap();
+        assert_eq!(
+            n_triples.trim(),
+            "<http://example.net/ns#A> <http://example.net/ns#hasNumber> \"1\"^^<http://www.w3.org/2001/XMLSchema#long> ."
+        );
+        stottrs_buffer_free(buffer, length);
+
+        let unknown = CString::new("http://example.net/ns#Unknown").unwrap();
+        let (mut array, schema) = export_record_batch();
+        assert_eq!(stottrs_expand(mapping, unknown.as_ptr(), &mut array, &schema), -1);
+        assert!(!stottrs_last_error().is_null());
+        stottrs_mapping_free(mapping);
+    }
+}
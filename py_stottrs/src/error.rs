@@ -59,6 +59,7 @@ impl std::convert::From<PyMapperError> for PyErr {
                 }
                 MapperError::TypingError(t) => TypingErrorException::new_err(format!("{}", t)),
                 MapperError::MappingError(m) => MappingErrorException::new_err(format!("{}", m)),
+                MapperError::WottrError(w) => WottrErrorException::new_err(format!("{}", w)),
             },
             PyMapperError::Arrow(err) => ArrowErrorException::new_err(format!("{:?}", err)),
             PyMapperError::IOError(err) => IOErrorException::new_err(format!("{}", err)),
@@ -73,3 +74,4 @@ create_exception!(exceptions, ResolutionErrorException, PyException);
 create_exception!(exceptions, TypingErrorException, PyException);
 create_exception!(exceptions, ArrowErrorException, PyException);
 create_exception!(exceptions, MappingErrorException, PyException);
+create_exception!(exceptions, WottrErrorException, PyException);
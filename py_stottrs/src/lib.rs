@@ -5,19 +5,37 @@ mod error;
 use crate::error::PyMapperError;
 use arrow_python_utils::to_rust::polars_df_to_rust_df;
 
-use stottrs::document::document_from_str;
+use stottrs::document::{document_from_str, wottr_document_from_str};
 use stottrs::errors::MapperError;
 use stottrs::mapping::ExpandOptions as RustExpandOptions;
 use stottrs::mapping::Mapping as InnerMapping;
+use stottrs::mapping::TemplateKey;
+use stottrs::mapping::{
+    Coercion, CoercionPolicy, DerivedId, DerivedIdFormat, DuplicateColumns, ExpansionStrategy,
+    Sample, SampleSize, SanitizePolicy, ValidationMode,
+};
+use stottrs::mapping::hierarchy::Hierarchy;
+use stottrs::mapping::units::{UnitAnnotation, UnitMode};
+use stottrs::mapping::wide_to_long::WideToLong;
 use stottrs::templates::TemplateDataset;
 use pyo3::basic::CompareOp;
 use pyo3::prelude::PyModule;
 use pyo3::*;
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::Duration;
 use std::fs::File;
 use arrow_python_utils::to_python::{df_to_py_df, df_vec_to_py_df_list};
 use oxrdf::NamedNode;
+use stottrs::triplestore::deduplication::DeduplicationStrategy;
+use stottrs::triplestore::export_size::ExportFormat;
+use stottrs::triplestore::incremental_export::ExportWatermark as InnerExportWatermark;
+use stottrs::triplestore::iri_validation::IriValidation;
+use stottrs::triplestore::ntriples_write::{Compression, NTriplesWriteOptions, DEFAULT_CHUNK_SIZE};
+use stottrs::triplestore::recovery::RecoveryAction;
+use stottrs::triplestore::Triplestore;
+use stottrs::triplestore::tenancy::tenant_graph;
+use stottrs::triplestore::sparql::sparql_results::SparqlResultsFormat;
 use stottrs::triplestore::sparql::QueryResult;
 
 #[pyclass]
@@ -153,24 +171,60 @@ impl Triple {
     }
 }
 
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct ExportWatermark {
+    inner: InnerExportWatermark,
+}
+
 #[pyclass]
 pub struct Mapping {
     inner: InnerMapping,
 }
 
 
+const DEFAULT_QUERY_CURSOR_TTL_SECONDS: f64 = 300.0;
+
 #[derive(Debug, Clone)]
 pub struct ExpandOptions {
     pub language_tags: Option<HashMap<String, String>>,
+    pub language_tag_columns: Option<HashMap<String, String>>,
     pub unique_subsets: Option<Vec<Vec<String>>>,
-    pub caching_folder: Option<String>
+    pub caching_folder: Option<String>,
+    pub graph: Option<String>,
+    pub sample: Option<Sample>,
+    pub expand_prefixed_names: bool,
+    pub annotation_graph: Option<String>,
+    pub sanitize_strings: Option<SanitizePolicy>,
+    pub row_limit: Option<usize>,
+    pub derived_ids: Option<HashMap<String, DerivedId>>,
+    pub validation: ValidationMode,
+    pub coercion: Option<Coercion>,
+    pub duplicate_columns: DuplicateColumns,
+    pub strategy: ExpansionStrategy,
+    pub trust_schema_cache: bool,
 }
 
 impl ExpandOptions {
     fn to_rust_expand_options(self) -> RustExpandOptions {
         RustExpandOptions {
             language_tags: self.language_tags,
+            language_tag_columns: self.language_tag_columns,
             unique_subsets: self.unique_subsets,
+            graph: self.graph,
+            sample: self.sample,
+            expand_prefixed_names: self.expand_prefixed_names,
+            annotation_graph: self.annotation_graph,
+            sanitize_strings: self.sanitize_strings,
+            row_limit: self.row_limit,
+            backpressure: None,
+            derived_ids: self.derived_ids,
+            chunk_policy: None,
+            validation: self.validation,
+            coercion: self.coercion,
+            duplicate_columns: self.duplicate_columns,
+            strategy: self.strategy,
+            trust_schema_cache: self.trust_schema_cache,
         }
     }
 }
@@ -178,7 +232,7 @@ impl ExpandOptions {
 #[pymethods]
 impl Mapping {
     #[new]
-    pub fn new(documents: Option<Vec<&str>>, caching_folder:Option<String>) -> PyResult<Mapping> {
+    pub fn new(documents: Option<Vec<&str>>, caching_folder:Option<String>, wottr_documents: Option<Vec<&str>>) -> PyResult<Mapping> {
         let mut parsed_documents = vec![];
         if let Some(documents) = documents {
             for ds in documents {
@@ -186,6 +240,12 @@ impl Mapping {
                 parsed_documents.push(parsed_doc);
             }
         }
+        if let Some(wottr_documents) = wottr_documents {
+            for ds in wottr_documents {
+                let parsed_doc = wottr_document_from_str(ds).map_err(PyMapperError::from)?;
+                parsed_documents.push(parsed_doc);
+            }
+        }
         let template_dataset = TemplateDataset::new(parsed_documents)
             .map_err(MapperError::from)
             .map_err(PyMapperError::from)?;
@@ -200,18 +260,141 @@ impl Mapping {
         df: &PyAny,
         unique_subset: Option<Vec<String>>,
         language_tags: Option<HashMap<String, String>>,
-        caching_folder: Option<String>
+        caching_folder: Option<String>,
+        graph: Option<String>,
+        sample_fraction: Option<f64>,
+        sample_rows: Option<usize>,
+        sample_seed: Option<u64>,
+        expand_prefixed_names: Option<bool>,
+        annotation_graph: Option<String>,
+        sanitize_strings: Option<String>,
+        row_limit: Option<usize>,
+        derived_ids: Option<HashMap<String, (Vec<String>, String)>>,
+        derived_id_format: Option<String>,
+        language_tag_columns: Option<HashMap<String, String>>,
+        validation: Option<String>,
+        coercion: Option<String>,
+        coercion_columns: Option<HashMap<String, String>>,
+        duplicate_columns: Option<String>,
+        duplicate_column_selection: Option<HashMap<String, usize>>,
+        strategy: Option<String>,
+        trust_schema_cache: Option<bool>,
     ) -> PyResult<Option<PyObject>> {
         let df = polars_df_to_rust_df(&df)?;
+        let validation = match validation.as_deref() {
+            None | Some("lenient") => ValidationMode::Lenient,
+            Some("strict") => ValidationMode::Strict,
+            Some(v) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown validation mode {}, expected lenient or strict",
+                    v
+                )))
+            }
+        };
+        let strategy = match strategy.as_deref() {
+            None | Some("full") => ExpansionStrategy::Full,
+            Some("optional_cohorts") => ExpansionStrategy::OptionalCohorts,
+            Some(s) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown strategy {}, expected full or optional_cohorts",
+                    s
+                )))
+            }
+        };
+        let coercion = if coercion.is_some() || coercion_columns.is_some() {
+            let mut columns = HashMap::new();
+            for (c, p) in coercion_columns.unwrap_or_default() {
+                columns.insert(c, coercion_policy(&p)?);
+            }
+            Some(Coercion {
+                policy: coercion_policy(coercion.as_deref().unwrap_or("never"))?,
+                columns,
+            })
+        } else {
+            None
+        };
+        let duplicate_columns = match (duplicate_columns.as_deref(), duplicate_column_selection) {
+            (None | Some("select"), Some(selection)) => DuplicateColumns::Select(selection),
+            (None | Some("error"), None) => DuplicateColumns::Error,
+            (Some("suffix"), None) => DuplicateColumns::Suffix,
+            (Some(d), _) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unsupported duplicate_columns {}, expected error or suffix, or select with a duplicate_column_selection",
+                    d
+                )))
+            }
+        };
+        let format = match derived_id_format.as_deref() {
+            None | Some("ulid") => DerivedIdFormat::Ulid,
+            Some("uuid") => DerivedIdFormat::Uuid,
+            Some(f) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown derived_id_format {}, expected ulid or uuid",
+                    f
+                )))
+            }
+        };
+        let derived_ids = derived_ids.map(|m| {
+            m.into_iter()
+                .map(|(parameter, (from_columns, prefix))| {
+                    (
+                        parameter,
+                        DerivedId {
+                            from_columns,
+                            prefix,
+                            format,
+                        },
+                    )
+                })
+                .collect()
+        });
+        let sanitize_strings = match sanitize_strings.as_deref() {
+            None => None,
+            Some("error") => Some(SanitizePolicy::Error),
+            Some("clean") => Some(SanitizePolicy::Clean),
+            Some(p) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown sanitize_strings policy {}, expected error or clean",
+                    p
+                )))
+            }
+        };
         let unique_subsets = if let Some(unique_subset) = unique_subset {
             Some(vec![unique_subset.into_iter().collect()])
         } else {
             None
         };
+        let seed = sample_seed.unwrap_or(0);
+        let sample = if let Some(fraction) = sample_fraction {
+            Some(Sample {
+                size: SampleSize::Fraction(fraction),
+                seed,
+            })
+        } else if let Some(rows) = sample_rows {
+            Some(Sample {
+                size: SampleSize::Rows(rows),
+                seed,
+            })
+        } else {
+            None
+        };
         let options = ExpandOptions {
             language_tags,
+            language_tag_columns,
             unique_subsets,
-            caching_folder
+            caching_folder,
+            graph,
+            sample,
+            expand_prefixed_names: expand_prefixed_names.unwrap_or(false),
+            annotation_graph,
+            sanitize_strings,
+            row_limit,
+            derived_ids,
+            validation,
+            coercion,
+            duplicate_columns,
+            strategy,
+            trust_schema_cache: trust_schema_cache.unwrap_or(false),
         };
 
         let mut _report = self
@@ -222,6 +405,310 @@ impl Mapping {
         Ok(None)
     }
 
+    /// Returns the problems with the columns that would make an expansion fail, without expanding.
+    pub fn validate(
+        &self,
+        template: &str,
+        df: &PyAny,
+        language_tags: Option<HashMap<String, String>>,
+        language_tag_columns: Option<HashMap<String, String>>,
+    ) -> PyResult<Vec<String>> {
+        let df = polars_df_to_rust_df(&df)?;
+        let options = RustExpandOptions {
+            language_tags,
+            language_tag_columns,
+            ..Default::default()
+        };
+        let report = self
+            .inner
+            .validate(template, &df, &options)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(report.errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    /// Returns the missing columns, the extra columns and the type mismatches as (column, data type, stottr type),
+    /// checked with the default expand options without expanding.
+    pub fn validate_dataframe(
+        &self,
+        template: &str,
+        df: &PyAny,
+    ) -> PyResult<(Vec<String>, Vec<String>, Vec<(String, String, String)>)> {
+        let df = polars_df_to_rust_df(&df)?;
+        let report = self
+            .inner
+            .validate_dataframe(template, &df)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        let type_mismatches = report
+            .type_mismatches
+            .into_iter()
+            .map(|m| (m.column, m.data_type.to_string(), m.ptype.to_string()))
+            .collect();
+        Ok((report.missing_columns, report.extra_columns, type_mismatches))
+    }
+
+    /// Makes the blank nodes and generated names of expansions reproducible, see stottrs::mapping::Mapping::set_seed.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.inner.set_seed(seed);
+    }
+
+    /// Declares the QUDT unit of a numeric parameter, emitted as qudt:unit triples or, with as_datatype, as the datatype of the values.
+    pub fn declare_unit(
+        &mut self,
+        template: &str,
+        parameter: &str,
+        unit: &str,
+        as_datatype: Option<bool>,
+    ) -> PyResult<()> {
+        let unit = NamedNode::new(unit)
+            .map_err(|e| exceptions::PyValueError::new_err(e.to_string()))?;
+        let mode = if as_datatype.unwrap_or(false) {
+            UnitMode::Datatype
+        } else {
+            UnitMode::UnitTriple
+        };
+        self.inner
+            .declare_unit(template, parameter, UnitAnnotation { unit, mode })
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(())
+    }
+
+    pub fn expand_from_csv(
+        &mut self,
+        template: &str,
+        path: &str,
+        unique_subset: Option<Vec<String>>,
+        language_tags: Option<HashMap<String, String>>,
+        caching_folder: Option<String>,
+        graph: Option<String>,
+        expand_prefixed_names: Option<bool>,
+        annotation_graph: Option<String>,
+    ) -> PyResult<Option<PyObject>> {
+        let unique_subsets = if let Some(unique_subset) = unique_subset {
+            Some(vec![unique_subset.into_iter().collect()])
+        } else {
+            None
+        };
+        let options = ExpandOptions {
+            language_tags,
+            language_tag_columns: None,
+            unique_subsets,
+            caching_folder,
+            graph,
+            sample: None,
+            expand_prefixed_names: expand_prefixed_names.unwrap_or(false),
+            annotation_graph,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
+        };
+
+        let mut _report = self
+            .inner
+            .expand_from_csv(template, path, options.to_rust_expand_options())
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(None)
+    }
+
+    pub fn expand_from_parquet(
+        &mut self,
+        template: &str,
+        path_or_glob: &str,
+        unique_subset: Option<Vec<String>>,
+        language_tags: Option<HashMap<String, String>>,
+        caching_folder: Option<String>,
+        graph: Option<String>,
+        expand_prefixed_names: Option<bool>,
+        annotation_graph: Option<String>,
+    ) -> PyResult<Option<PyObject>> {
+        let unique_subsets = if let Some(unique_subset) = unique_subset {
+            Some(vec![unique_subset.into_iter().collect()])
+        } else {
+            None
+        };
+        let options = ExpandOptions {
+            language_tags,
+            language_tag_columns: None,
+            unique_subsets,
+            caching_folder,
+            graph,
+            sample: None,
+            expand_prefixed_names: expand_prefixed_names.unwrap_or(false),
+            annotation_graph,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
+        };
+
+        let mut _report = self
+            .inner
+            .expand_from_parquet(template, path_or_glob, options.to_rust_expand_options())
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(None)
+    }
+
+    pub fn expand_from_ipc(
+        &mut self,
+        template: &str,
+        path_or_glob: &str,
+        unique_subset: Option<Vec<String>>,
+        language_tags: Option<HashMap<String, String>>,
+        caching_folder: Option<String>,
+        graph: Option<String>,
+        expand_prefixed_names: Option<bool>,
+        annotation_graph: Option<String>,
+    ) -> PyResult<Option<PyObject>> {
+        let unique_subsets = if let Some(unique_subset) = unique_subset {
+            Some(vec![unique_subset.into_iter().collect()])
+        } else {
+            None
+        };
+        let options = ExpandOptions {
+            language_tags,
+            language_tag_columns: None,
+            unique_subsets,
+            caching_folder,
+            graph,
+            sample: None,
+            expand_prefixed_names: expand_prefixed_names.unwrap_or(false),
+            annotation_graph,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
+        };
+
+        let mut _report = self
+            .inner
+            .expand_from_ipc(template, path_or_glob, options.to_rust_expand_options())
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(None)
+    }
+
+    pub fn expand_wide(
+        &mut self,
+        df: &PyAny,
+        id_column: String,
+        timestamp_column: String,
+        templates: Option<HashMap<String, String>>,
+        predicate_prefix: Option<String>,
+        language_tags: Option<HashMap<String, String>>,
+        caching_folder: Option<String>,
+        graph: Option<String>,
+    ) -> PyResult<Option<PyObject>> {
+        let df = polars_df_to_rust_df(&df)?;
+        let wide_to_long = WideToLong {
+            id_column,
+            timestamp_column,
+            templates: templates.unwrap_or_default(),
+            predicate_prefix,
+        };
+        let options = ExpandOptions {
+            language_tags,
+            language_tag_columns: None,
+            unique_subsets: None,
+            caching_folder,
+            graph,
+            sample: None,
+            expand_prefixed_names: false,
+            annotation_graph: None,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
+        };
+
+        let mut _report = self
+            .inner
+            .expand_wide(df, &wide_to_long, options.to_rust_expand_options())
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(None)
+    }
+
+    pub fn expand_hierarchy(
+        &mut self,
+        df: &PyAny,
+        path_column: String,
+        iri_prefix: String,
+        separator: Option<String>,
+        predicate_prefix: Option<String>,
+        graph: Option<String>,
+    ) -> PyResult<Option<PyObject>> {
+        let df = polars_df_to_rust_df(&df)?;
+        let hierarchy = Hierarchy {
+            path_column,
+            separator: separator.unwrap_or("/".to_string()),
+            iri_prefix,
+            predicate_prefix,
+        };
+        let options = ExpandOptions {
+            language_tags: None,
+            language_tag_columns: None,
+            unique_subsets: None,
+            caching_folder: None,
+            graph,
+            sample: None,
+            expand_prefixed_names: false,
+            annotation_graph: None,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
+        };
+
+        let mut _report = self
+            .inner
+            .expand_hierarchy(df, &hierarchy, options.to_rust_expand_options())
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(None)
+    }
+
+    pub fn declare_key(
+        &mut self,
+        template: &str,
+        unique_subsets: Vec<Vec<String>>,
+        globally_unique: Option<bool>,
+    ) -> PyResult<()> {
+        let key = TemplateKey {
+            unique_subsets,
+            globally_unique: globally_unique.unwrap_or(false),
+        };
+        self.inner
+            .declare_key(template, key)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(())
+    }
+
     pub fn expand_default(
         &mut self,
         df: &PyAny,
@@ -235,8 +722,21 @@ impl Mapping {
         let df = polars_df_to_rust_df(&df)?;
         let options = ExpandOptions {
             language_tags,
+            language_tag_columns: None,
             unique_subsets:Some(vec![vec![primary_key_column.clone()]]),
             caching_folder,
+            graph: None,
+            sample: None,
+            expand_prefixed_names: false,
+            annotation_graph: None,
+            sanitize_strings: None,
+            row_limit: None,
+            derived_ids: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
         };
 
         let fk_cols = if let Some(fk_cols) = foreign_key_columns {
@@ -263,18 +763,135 @@ impl Mapping {
             QueryResult::Select(df) => {
                 df_to_py_df(df, py)
             }
-            QueryResult::Construct(dfs) => {
+            QueryResult::Construct(dfs) | QueryResult::Describe(dfs) => {
+                let dfs = dfs.into_iter().map(|(df,_)|df).collect();
+                Ok(df_vec_to_py_df_list(dfs,py)?.into())
+            }
+        }
+    }
+
+    pub fn query_tenant(&mut self, py: Python<'_>, tenant: String, query: String) -> PyResult<PyObject> {
+        let res = self
+            .inner
+            .triplestore
+            .query_tenant(&tenant, &query)
+            .map_err(PyMapperError::from)?;
+        match res {
+            QueryResult::Select(df) => {
+                df_to_py_df(df, py)
+            }
+            QueryResult::Construct(dfs) | QueryResult::Describe(dfs) => {
                 let dfs = dfs.into_iter().map(|(df,_)|df).collect();
                 Ok(df_vec_to_py_df_list(dfs,py)?.into())
             }
         }
     }
 
+    #[staticmethod]
+    pub fn tenant_graph(tenant: &str) -> String {
+        tenant_graph(tenant)
+    }
+
+    #[staticmethod]
+    pub fn recover_caching_folder(caching_folder: &str, quarantine: Option<bool>) -> PyResult<Vec<String>> {
+        let action = if quarantine.unwrap_or(false) {
+            RecoveryAction::Quarantine
+        } else {
+            RecoveryAction::Remove
+        };
+        let recovered = Triplestore::recover(caching_folder, action)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(recovered)
+    }
+
+    pub fn tenants(&self) -> Vec<String> {
+        self.inner.triplestore.tenants()
+    }
+
+    pub fn set_tenant_quota(&mut self, tenant: &str, max_triples: usize) {
+        self.inner.triplestore.set_tenant_quota(tenant, max_triples);
+    }
+
+    pub fn remove_tenant_quota(&mut self, tenant: &str) {
+        self.inner.triplestore.remove_tenant_quota(tenant);
+    }
+
+    pub fn tenant_triple_count(&self, tenant: &str) -> usize {
+        self.inner.triplestore.tenant_triple_count(tenant)
+    }
+
+    pub fn remove_tenant(&mut self, tenant: &str) -> bool {
+        self.inner.triplestore.remove_tenant(tenant)
+    }
+
+    pub fn write_tenant_ntriples(&mut self, tenant: &str, path: &str) -> PyResult<()> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        self.inner
+            .triplestore
+            .write_tenant_n_triples(tenant, &mut actual_file)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?;
+        Ok(())
+    }
+
+    pub fn open_query_cursor(&mut self, query: String, ttl_seconds: Option<f64>) -> PyResult<String> {
+        let ttl = Duration::from_secs_f64(ttl_seconds.unwrap_or(DEFAULT_QUERY_CURSOR_TTL_SECONDS));
+        let handle = self
+            .inner
+            .triplestore
+            .open_query_cursor(&query, ttl)
+            .map_err(PyMapperError::from)?;
+        Ok(handle)
+    }
+
+    pub fn fetch_query_page(
+        &mut self,
+        py: Python<'_>,
+        handle: String,
+        offset: usize,
+        limit: usize,
+    ) -> PyResult<(PyObject, usize)> {
+        let page = self
+            .inner
+            .triplestore
+            .fetch_query_page(&handle, offset, limit)
+            .map_err(PyMapperError::from)?;
+        Ok((df_to_py_df(page.df, py)?, page.total_rows))
+    }
+
+    pub fn close_query_cursor(&mut self, handle: String) -> bool {
+        self.inner.triplestore.close_query_cursor(&handle)
+    }
+
+    pub fn predicates_produced(&self) -> HashMap<String, HashSet<String>> {
+        self.inner.template_dataset().predicates_produced()
+    }
+
+    pub fn templates_producing(&self, predicate: String) -> Vec<String> {
+        self.inner.template_dataset().templates_producing(&predicate)
+    }
+
+    pub fn find_mixed_object_types(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let df = self
+            .inner
+            .triplestore
+            .find_mixed_object_types()
+            .map_err(|x| PyMapperError::MapperError(MapperError::MappingError(x)))?;
+        df_to_py_df(df, py)
+    }
+
     pub fn construct_update(&mut self, query:String) -> PyResult<()> {
         self.inner.triplestore.construct_update(&query).map_err(PyMapperError::from)?;
         Ok(())
     }
 
+    pub fn deconstruct_update(&mut self, query:String, graph: Option<String>) -> PyResult<()> {
+        self.inner.triplestore.deconstruct_update_in_graph(&query, graph.as_deref()).map_err(PyMapperError::from)?;
+        Ok(())
+    }
+
     pub fn to_triples(&mut self) -> PyResult<Vec<Triple>> {
         let mut triples = vec![];
 
@@ -283,7 +900,7 @@ impl Mapping {
                 TripleSubject {
                     iri: None,
                     blank_node: Some(BlankNode {
-                        name: s.to_string(),
+                        name: s.strip_prefix("_:").unwrap().to_string(),
                     }),
                 }
             } else {
@@ -298,7 +915,7 @@ impl Mapping {
                 TripleObject {
                     iri: None,
                     blank_node: Some(BlankNode {
-                        name: s.to_string(),
+                        name: s.strip_prefix("_:").unwrap().to_string(),
                     }),
                     literal: None,
                 }
@@ -391,26 +1008,194 @@ impl Mapping {
         Ok(triples)
     }
 
-    pub fn write_ntriples(&mut self, path:&str) -> PyResult<()> {
+    pub fn write_ntriples(&mut self, path:&str, chunk_size: Option<usize>, gzip: Option<bool>) -> PyResult<()> {
         let path_buf = PathBuf::from(path);
         let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
-        self.inner.write_n_triples(&mut actual_file).unwrap();
+        let options = NTriplesWriteOptions {
+            chunk_size: chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE),
+            compression: if gzip == Some(true) { Compression::Gzip } else { Compression::None },
+        };
+        self.inner.write_n_triples_with_options(&mut actual_file, &options).map_err(|x|PyMapperError::MapperError(x))?;
         Ok(())
     }
 
+    pub fn write_ntriples_since(&mut self, path:&str, watermark: Option<ExportWatermark>) -> PyResult<ExportWatermark> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        let watermark = watermark.unwrap_or_default();
+        let inner = self.inner.write_n_triples_since(&mut actual_file, &watermark.inner).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(ExportWatermark { inner })
+    }
+
+    pub fn write_nquads(&mut self, path:&str) -> PyResult<()> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        self.inner.write_n_quads(&mut actual_file).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    pub fn write_trig(&mut self, path:&str) -> PyResult<()> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        self.inner.write_trig(&mut actual_file).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    pub fn set_harmonize_dtypes(&mut self, harmonize_dtypes: bool) {
+        self.inner.triplestore.set_harmonize_dtypes(harmonize_dtypes);
+    }
+
+    pub fn set_collapse_language_tags(&mut self, collapse_language_tags: bool) {
+        self.inner
+            .triplestore
+            .set_collapse_language_tags(collapse_language_tags);
+    }
+
+    /// Triples with invalid IRIs are not added, see stottrs::triplestore::iri_validation::IriValidation.
+    /// With a predicate, the validation applies to the triples of the predicate only.
+    pub fn set_iri_validation(
+        &mut self,
+        schemes: Option<Vec<String>>,
+        max_length: Option<usize>,
+        predicate: Option<&str>,
+    ) {
+        let validation = IriValidation {
+            schemes: schemes.unwrap_or_default(),
+            max_length,
+        };
+        if let Some(predicate) = predicate {
+            self.inner
+                .triplestore
+                .set_predicate_iri_validation(predicate, validation);
+        } else {
+            self.inner.triplestore.set_iri_validation(Some(validation));
+        }
+    }
+
+    /// The triples not added because of invalid IRIs since the last call, with the violated position and the violation.
+    pub fn take_iri_violations(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        df_to_py_df(self.inner.triplestore.take_iri_violations(), py)
+    }
+
+    pub fn set_incremental_deduplication(&mut self, incremental: bool) {
+        let deduplication_strategy = if incremental {
+            DeduplicationStrategy::Incremental
+        } else {
+            DeduplicationStrategy::Full
+        };
+        self.inner
+            .triplestore
+            .set_deduplication_strategy(deduplication_strategy);
+    }
+
+    pub fn write_turtle(&mut self, path:&str) -> PyResult<()> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        self.inner.write_turtle(&mut actual_file).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    pub fn construct_to_file(&mut self, query: &str, path: &str) -> PyResult<()> {
+        let path_buf = PathBuf::from(path);
+        let mut actual_file = File::create(path_buf.as_path()).map_err(|x|PyMapperError::IOError(x))?;
+        self.inner
+            .triplestore
+            .construct_to_writer(query, &mut actual_file)
+            .map_err(PyMapperError::from)?;
+        Ok(())
+    }
+
+    pub fn query_to_sparql_results(&mut self, query: &str, format: Option<&str>) -> PyResult<String> {
+        let format = match format {
+            None | Some("json") => SparqlResultsFormat::Json,
+            Some("xml") => SparqlResultsFormat::Xml,
+            Some("csv") => SparqlResultsFormat::Csv,
+            Some("tsv") => SparqlResultsFormat::Tsv,
+            Some(f) => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown results format {}, expected json, xml, csv or tsv",
+                    f
+                )))
+            }
+        };
+        let mut buffer = vec![];
+        self.inner
+            .triplestore
+            .query_to_sparql_results(query, format, &mut buffer)
+            .map_err(PyMapperError::from)?;
+        Ok(String::from_utf8(buffer).unwrap())
+    }
+
+    pub fn estimate_export_size(&self, format: &str) -> PyResult<usize> {
+        let format = match format {
+            "ntriples" => ExportFormat::NTriples,
+            "nquads" => ExportFormat::NQuads,
+            "trig" => ExportFormat::TriG,
+            "turtle" => ExportFormat::Turtle,
+            "native_parquet" => ExportFormat::NativeParquet,
+            "ipc" => ExportFormat::Ipc,
+            _ => {
+                return Err(exceptions::PyValueError::new_err(format!(
+                    "Unknown export format {}, expected one of ntriples, nquads, trig, turtle, native_parquet or ipc",
+                    format
+                )))
+            }
+        };
+        Ok(self
+            .inner
+            .triplestore
+            .estimate_export_size(format)
+            .map_err(MapperError::from)
+            .map_err(PyMapperError::from)?)
+    }
+
     pub fn write_native_parquet(&mut self, path:&str) -> PyResult<()> {
         self.inner.write_native_parquet(path).map_err(|x|PyMapperError::MapperError(x))?;
         Ok(())
     }
+
+    pub fn read_native_parquet(&mut self, path:&str) -> PyResult<()> {
+        self.inner.read_native_parquet(path).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    pub fn write_ipc(&mut self, path:&str) -> PyResult<()> {
+        self.inner.write_ipc(path).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    /// Saves the templates, triples and options of the mapping to the folder, see stottrs::mapping::Mapping::save_project.
+    pub fn save_project(&mut self, path:&str) -> PyResult<()> {
+        self.inner.save_project(path).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(())
+    }
+
+    #[staticmethod]
+    pub fn load_project(path:&str) -> PyResult<Mapping> {
+        let inner = InnerMapping::load_project(path).map_err(|x|PyMapperError::MapperError(x))?;
+        Ok(Mapping { inner })
+    }
 }
 
 #[pymodule]
 fn stottrs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<Mapping>()?;
+    m.add_class::<ExportWatermark>()?;
 
     Ok(())
 }
 
+fn coercion_policy(policy: &str) -> PyResult<CoercionPolicy> {
+    match policy {
+        "never" => Ok(CoercionPolicy::Never),
+        "cast" => Ok(CoercionPolicy::Cast),
+        p => Err(exceptions::PyValueError::new_err(format!(
+            "Unknown coercion policy {}, expected never or cast",
+            p
+        ))),
+    }
+}
+
 fn is_blank_node(s: &str) -> bool {
     s.starts_with("_:")
 }
@@ -317,4 +317,98 @@ def test_simple_update_construct_query(windpower_mapping):
     expected_something_df = pl.scan_csv(filename_something).sort(["a"]).collect()
     pl.testing.assert_frame_equal(something, expected_something_df)
     expected_nothing_df = pl.scan_csv(filename_nothing).sort(["a"]).collect()
-    pl.testing.assert_frame_equal(nothing, expected_nothing_df)
\ No newline at end of file
+    pl.testing.assert_frame_equal(nothing, expected_nothing_df)
+def test_count_distinct_query(windpower_mapping):
+    df = windpower_mapping.query("SELECT ?a ?b WHERE {?a a ?b}")
+    count_df = windpower_mapping.query("""
+    SELECT (COUNT(DISTINCT ?b) AS ?n_b) (COUNT(?b) AS ?n) (COUNT(DISTINCT *) AS ?n_rows)
+    (<https://github.com/magbak/stOTTRs/Functions#approxCountDistinct>(DISTINCT ?a) AS ?approx_n_a)
+    WHERE {?a a ?b}
+    """)
+    assert count_df.get_column("n_b")[0] == df.get_column("b").n_unique()
+    assert count_df.get_column("n")[0] == df.height
+    assert count_df.get_column("n_rows")[0] == df.unique().height
+    approx_n_a = count_df.get_column("approx_n_a")[0]
+    n_a = df.get_column("a").n_unique()
+    assert abs(approx_n_a - n_a) <= max(1, 0.05 * n_a)
+
+def test_class_hierarchy_property_path_query():
+    doc = """
+    @prefix ex:<http://example.net/ns#>.
+    @prefix rdf:<http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+    @prefix rdfs:<http://www.w3.org/2000/01/rdf-schema#>.
+
+    ex:SubClass [?Sub, ?Super] :: {
+        ottr:Triple(?Sub, rdfs:subClassOf, ?Super)
+    } .
+    ex:Typed [?Thing, ?Class] :: {
+        ottr:Triple(?Thing, rdf:type, ?Class)
+    } .
+    """
+    ex = "http://example.net/ns#"
+    mapping = Mapping([doc])
+    mapping.expand(ex + "SubClass", pl.DataFrame({"Sub": [ex + "Turbine", ex + "WindTurbine"],
+                                                  "Super": [ex + "Equipment", ex + "Turbine"]}))
+    mapping.expand(ex + "Typed", pl.DataFrame({"Thing": [ex + "wt1", ex + "pump1"],
+                                               "Class": [ex + "WindTurbine", ex + "Pump"]}))
+    df = mapping.query("""
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?thing WHERE {
+        ?thing a/rdfs:subClassOf* ex:Equipment .
+    }""")
+    assert df.get_column("thing").cast(pl.Utf8).to_list() == [ex + "wt1"]
+    df = mapping.query("""
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?super WHERE {
+        ex:WindTurbine (rdfs:subClassOf|^rdfs:subClassOf)+ ?super .
+    }""")
+    assert set(df.get_column("super").cast(pl.Utf8).to_list()) == {ex + "WindTurbine", ex + "Turbine", ex + "Equipment"}
+
+def test_group_concat_and_sample_query(windpower_mapping):
+    df = windpower_mapping.query("""
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?b (GROUP_CONCAT(?label; SEPARATOR=",") AS ?labels) (SAMPLE(?a) AS ?some_a) WHERE {
+        ?a a ?b .
+        ?a rdfs:label ?label .
+    } GROUP BY ?b
+    """)
+    labels_df = windpower_mapping.query("""
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?a ?b ?label WHERE {
+        ?a a ?b .
+        ?a rdfs:label ?label .
+    }
+    """)
+    assert df.height == labels_df.get_column("b").n_unique()
+    for row in df.iter_rows(named=True):
+        expected = labels_df.filter(pl.col("b") == row["b"])
+        assert sorted(row["labels"].split(",")) == sorted(expected.get_column("label").to_list())
+        assert row["some_a"] in expected.get_column("a").to_list()
+
+def test_distinct_aggregates_and_having_query(windpower_mapping):
+    df = windpower_mapping.query("""
+    PREFIX ct:<https://github.com/magbak/chrontext#>
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?label (SUM(DISTINCT ?v) AS ?sum_distinct) (SUM(?v) AS ?sum) (COUNT(DISTINCT ?v) AS ?n_distinct) WHERE {
+        ?p ct:hasStaticProperty ?n .
+        ?n rdfs:label ?label .
+        ?n ct:hasStaticValue ?v .
+    } GROUP BY ?label
+    HAVING (COUNT(?v) > 1)
+    """)
+    assert df.height == 1
+    assert df.get_column("sum_distinct")[0] == 30_000_000
+    assert df.get_column("sum")[0] > 30_000_000
+    assert df.get_column("n_distinct")[0] == 3
+    empty_df = windpower_mapping.query("""
+    PREFIX ct:<https://github.com/magbak/chrontext#>
+    PREFIX rdfs:<http://www.w3.org/2000/01/rdf-schema#>
+    SELECT ?label WHERE {
+        ?n rdfs:label ?label .
+        ?n ct:hasStaticValue ?v .
+    } GROUP BY ?label
+    HAVING (COUNT(DISTINCT ?v) > 3)
+    """)
+    assert empty_df.height == 0
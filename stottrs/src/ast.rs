@@ -62,10 +62,17 @@ impl Display for Signature {
                 write!(f, ", ")?;
             }
         }
-        if let Some(_) = self.annotation_list {
-            todo!();
+        write!(f, " ]")?;
+        if let Some(annotation_list) = &self.annotation_list {
+            for (idx, a) in annotation_list.iter().enumerate() {
+                write!(f, " @@")?;
+                std::fmt::Display::fmt(&a.instance, f)?;
+                if idx + 1 != annotation_list.len() {
+                    write!(f, ",")?;
+                }
+            }
         }
-        write!(f, " ]")
+        Ok(())
     }
 }
 
@@ -218,15 +225,15 @@ pub struct StottrLiteral {
 
 impl Display for StottrLiteral {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        if let Some(dt) = &self.data_type_iri {
+        if let Some(lang_tag) = &self.language {
+            write!(f, "\"{}\"@{}", &self.value, lang_tag)
+        } else if let Some(dt) = &self.data_type_iri {
             let dt_ref = dt.as_ref();
             if dt_ref == xsd::INTEGER {
                 write!(f, "{}", self.value)
             } else {
                 write!(f, "\"{}\"^^{}", self.value, dt)
             }
-        } else if let Some(lang_tag) = &self.language{
-            write!(f, "\"{}\"@{}", &self.value, lang_tag)
         } else {
             write!(f, "\"{}\"", &self.value)
         }
@@ -266,12 +273,17 @@ pub struct Annotation {
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct Argument {
+    /// The parameter the argument is given for, when it is given by name as in ex:T(?label = "a").
+    pub parameter: Option<String>,
     pub list_expand: bool,
     pub term: StottrTerm,
 }
 
 impl Display for Argument {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(parameter) = &self.parameter {
+            write!(f, "?{} = ", parameter)?;
+        }
         if self.list_expand {
             write!(f, "++ ")?;
         }
@@ -312,6 +324,47 @@ pub enum StottrTerm {
     Variable(StottrVariable),
     ConstantTerm(ConstantTerm),
     List(Vec<StottrTerm>),
+    /// Extension to OTTR, an argument derived from other terms, e.g. ?b * 2
+    Expression(Box<StottrExpression>),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct StottrExpression {
+    pub left: StottrTerm,
+    pub operator: ExpressionOperator,
+    pub right: StottrTerm,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum ExpressionOperator {
+    Multiply,
+    Divide,
+    Add,
+    Subtract,
+    Concat,
+}
+
+impl ExpressionOperator {
+    //Operators with higher precedence are applied first
+    pub fn precedence(&self) -> u8 {
+        match self {
+            ExpressionOperator::Multiply | ExpressionOperator::Divide => 2,
+            ExpressionOperator::Add | ExpressionOperator::Subtract => 1,
+            ExpressionOperator::Concat => 0,
+        }
+    }
+}
+
+impl Display for ExpressionOperator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpressionOperator::Multiply => write!(f, "*"),
+            ExpressionOperator::Divide => write!(f, "/"),
+            ExpressionOperator::Add => write!(f, "+"),
+            ExpressionOperator::Subtract => write!(f, "-"),
+            ExpressionOperator::Concat => write!(f, "||"),
+        }
+    }
 }
 
 impl Display for StottrTerm {
@@ -333,6 +386,9 @@ impl Display for StottrTerm {
                 }
                 write!(f, ")")
             }
+            StottrTerm::Expression(e) => {
+                write!(f, "{} {} {}", e.left, e.operator, e.right)
+            }
         }
     }
 }
@@ -342,6 +398,7 @@ pub struct StottrDocument {
     pub directives: Vec<Directive>,
     pub statements: Vec<Statement>,
     pub prefix_map: HashMap<String, NamedNode>,
+    pub base_iri: Option<NamedNode>,
 }
 
 #[test]
@@ -368,7 +425,7 @@ fn test_display_easy_template() {
             list_expander: None,
             template_name: NamedNode::new(OTTR_TRIPLE).unwrap(),
             prefixed_template_name: "ottr:Triple".to_string(),
-            argument_list: vec![Argument { list_expand: false, term: StottrTerm::Variable(StottrVariable { name: "myVar".to_string() }) }],
+            argument_list: vec![Argument { parameter: None, list_expand: false, term: StottrTerm::Variable(StottrVariable { name: "myVar".to_string() }) }],
         }
     ] } ;
 
@@ -7,9 +7,39 @@ pub const RDFS_PREFIX: &str = "rdfs";
 pub const RDFS_PREFIX_IRI: &str = "http://www.w3.org/2000/01/rdf-schema#";
 pub const XSD_PREFIX: &str = "xsd";
 pub const XSD_PREFIX_IRI: &str = "http://www.w3.org/2001/XMLSchema#";
+pub const XSD_DATE_FORMAT: &str = "%Y-%m-%d";
 pub const XSD_DATETIME_WITHOUT_TZ_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
 pub const XSD_DATETIME_WITH_TZ_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f%:z";
 pub const BLANK_NODE_IRI: &str = "BLANK_NODE_IRI";
 pub const NONE_IRI: &str = "NONE_IRI";
 pub const DEFAULT_PREDICATE_URI_PREFIX: &str = "https://github.com/magbak/stOTTRs/Predicates#";
 pub const DEFAULT_TEMPLATE_PREFIX: &str = "default:";
+pub const OTTR_OF: &str = "http://ns.ottr.xyz/0.4/of";
+pub const OTTR_VALUES: &str = "http://ns.ottr.xyz/0.4/values";
+pub const OTTR_ARGUMENTS: &str = "http://ns.ottr.xyz/0.4/arguments";
+pub const OTTR_VALUE: &str = "http://ns.ottr.xyz/0.4/value";
+pub const OTTR_MODIFIER: &str = "http://ns.ottr.xyz/0.4/modifier";
+pub const OTTR_LIST_EXPAND: &str = "http://ns.ottr.xyz/0.4/listExpand";
+pub const OTTR_CROSS: &str = "http://ns.ottr.xyz/0.4/cross";
+pub const OTTR_ZIP_MIN: &str = "http://ns.ottr.xyz/0.4/zipMin";
+pub const OTTR_ZIP_MAX: &str = "http://ns.ottr.xyz/0.4/zipMax";
+pub const OTTR_NONE: &str = "http://ns.ottr.xyz/0.4/none";
+pub const OTTR_TEMPLATE: &str = "http://ns.ottr.xyz/0.4/Template";
+pub const OTTR_SIGNATURE: &str = "http://ns.ottr.xyz/0.4/Signature";
+pub const OTTR_BASE_TEMPLATE: &str = "http://ns.ottr.xyz/0.4/BaseTemplate";
+pub const OTTR_PARAMETERS: &str = "http://ns.ottr.xyz/0.4/parameters";
+pub const OTTR_PATTERN: &str = "http://ns.ottr.xyz/0.4/pattern";
+pub const OTTR_ANNOTATION: &str = "http://ns.ottr.xyz/0.4/annotation";
+pub const OTTR_VARIABLE: &str = "http://ns.ottr.xyz/0.4/variable";
+pub const OTTR_TYPE: &str = "http://ns.ottr.xyz/0.4/type";
+pub const OTTR_DEFAULT: &str = "http://ns.ottr.xyz/0.4/default";
+pub const OTTR_OPTIONAL: &str = "http://ns.ottr.xyz/0.4/optional";
+pub const OTTR_NON_BLANK: &str = "http://ns.ottr.xyz/0.4/nonBlank";
+pub const OTTR_LIST: &str = "http://ns.ottr.xyz/0.4/List";
+pub const OTTR_NE_LIST: &str = "http://ns.ottr.xyz/0.4/NEList";
+pub const OTTR_LUB: &str = "http://ns.ottr.xyz/0.4/LUB";
+//Aggregate estimating the number of distinct values, called as approxCountDistinct(DISTINCT ?x).
+//Without DISTINCT the call is parsed as a function, which is not supported.
+pub const APPROX_COUNT_DISTINCT: &str = "https://github.com/magbak/stOTTRs/Functions#approxCountDistinct";
+pub const RESERVED_COLUMN_PREFIX: &str = "_:";
+pub const QUDT_UNIT: &str = "http://qudt.org/schema/qudt/unit";
@@ -2,6 +2,7 @@ use crate::ast::StottrDocument;
 use crate::errors::MapperError;
 use crate::parsing::whole_stottr_doc;
 use crate::resolver::resolve_document;
+use crate::wottr::document_from_wottr_str;
 use std::fs::read_to_string;
 use std::path::Path;
 
@@ -14,3 +15,12 @@ pub fn document_from_file<P: AsRef<Path>>(p: P) -> Result<StottrDocument, Mapper
     let s = read_to_string(p)?;
     document_from_str(&s)
 }
+
+pub fn wottr_document_from_str(s: &str) -> Result<StottrDocument, MapperError> {
+    document_from_wottr_str(s).map_err(MapperError::from)
+}
+
+pub fn wottr_document_from_file<P: AsRef<Path>>(p: P) -> Result<StottrDocument, MapperError> {
+    let s = read_to_string(p)?;
+    wottr_document_from_str(&s)
+}
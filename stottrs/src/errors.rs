@@ -2,6 +2,7 @@ use crate::mapping::errors::MappingError;
 use crate::parsing::errors::ParsingError;
 use crate::resolver::ResolutionError;
 use crate::templates::TypingError;
+use crate::wottr::errors::WottrError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -16,4 +17,6 @@ pub enum MapperError {
     TypingError(#[from] TypingError),
     #[error(transparent)]
     MappingError(#[from] MappingError),
+    #[error(transparent)]
+    WottrError(#[from] WottrError),
 }
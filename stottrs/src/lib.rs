@@ -21,5 +21,6 @@ pub mod errors;
 pub mod mapping;
 pub mod templates;
 pub mod triplestore;
+pub mod wottr;
 pub(crate) mod literals;
 pub(crate) mod io_funcs;
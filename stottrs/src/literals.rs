@@ -1,5 +1,5 @@
 use std::str::FromStr;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use oxrdf::{NamedNode};
 use oxrdf::vocab::xsd;
 use polars_core::datatypes::TimeUnit;
@@ -17,7 +17,19 @@ pub(crate) fn sparql_literal_to_any_value(value: &String, datatype: &Option<Name
         } else if datatype == xsd::UNSIGNED_LONG {
             let u = u64::from_str(value).expect("Integer parsing error");
             AnyValue::from(u)
-        } else if datatype == xsd::INTEGER {
+        } else if datatype == xsd::UNSIGNED_SHORT {
+            let u = u16::from_str(value).expect("Integer parsing error");
+            AnyValue::from(u)
+        } else if datatype == xsd::UNSIGNED_BYTE {
+            let u = u8::from_str(value).expect("Integer parsing error");
+            AnyValue::from(u)
+        } else if datatype == xsd::NON_NEGATIVE_INTEGER || datatype == xsd::POSITIVE_INTEGER {
+            let u = u64::from_str(value).expect("Integer parsing error");
+            AnyValue::from(u)
+        } else if datatype == xsd::INTEGER
+            || datatype == xsd::NON_POSITIVE_INTEGER
+            || datatype == xsd::NEGATIVE_INTEGER
+        {
             let i = i64::from_str(value).expect("Integer parsing error");
             AnyValue::from(i)
         } else if datatype == xsd::LONG {
@@ -26,6 +38,12 @@ pub(crate) fn sparql_literal_to_any_value(value: &String, datatype: &Option<Name
         } else if datatype == xsd::INT {
             let i = i32::from_str(value).expect("Integer parsing error");
             AnyValue::from(i)
+        } else if datatype == xsd::SHORT {
+            let i = i16::from_str(value).expect("Integer parsing error");
+            AnyValue::from(i)
+        } else if datatype == xsd::BYTE {
+            let i = i8::from_str(value).expect("Integer parsing error");
+            AnyValue::from(i)
         } else if datatype == xsd::DOUBLE {
             let d = f64::from_str(value).expect("Integer parsing error");
             AnyValue::from(d)
@@ -35,7 +53,13 @@ pub(crate) fn sparql_literal_to_any_value(value: &String, datatype: &Option<Name
         } else if datatype == xsd::BOOLEAN {
             let b = bool::from_str(value).expect("Boolean parsing error");
             AnyValue::Boolean(b)
-        } else if datatype == xsd::DATE_TIME {
+        } else if datatype == xsd::DATE {
+            let d = NaiveDate::from_str(value).expect("Date parsing error");
+            AnyValue::Date((d - NaiveDate::from_ymd(1970, 1, 1)).num_days() as i32)
+        } else if datatype == xsd::DURATION || datatype == xsd::DAY_TIME_DURATION {
+            let nanos = parse_xsd_duration(value).expect("Duration parsing error");
+            AnyValue::Duration(nanos, TimeUnit::Nanoseconds)
+        } else if datatype == xsd::DATE_TIME || datatype == xsd::DATE_TIME_STAMP {
             let dt_without_tz = value.parse::<NaiveDateTime>();
             if let Ok(dt) = dt_without_tz {
                 AnyValue::Datetime(dt.timestamp_nanos(), TimeUnit::Nanoseconds, &None)
@@ -58,4 +82,109 @@ pub(crate) fn sparql_literal_to_any_value(value: &String, datatype: &Option<Name
         (AnyValue::Utf8Owned(value.into()), xsd::STRING.into_owned())
     };
     return (anyv.into_static().unwrap(), dt)
-}
\ No newline at end of file
+}
+
+//Durations with years or months do not have a fixed length, and are not supported
+pub(crate) fn parse_xsd_duration(value: &str) -> Option<i64> {
+    let (negative, rest) = if let Some(rest) = value.strip_prefix('-') {
+        (true, rest)
+    } else {
+        (false, value)
+    };
+    let rest = rest.strip_prefix('P')?;
+    let (date_part, time_part) = if let Some((d, t)) = rest.split_once('T') {
+        if t.is_empty() {
+            return None;
+        }
+        (d, t)
+    } else {
+        (rest, "")
+    };
+    if date_part.is_empty() && time_part.is_empty() {
+        return None;
+    }
+    let mut nanos: i64 = 0;
+    for (number, designator) in duration_components(date_part)? {
+        let n = i64::from_str(number).ok()?;
+        match designator {
+            'Y' | 'M' if n == 0 => {}
+            'D' => nanos = nanos.checked_add(n.checked_mul(86_400_000_000_000)?)?,
+            _ => return None,
+        }
+    }
+    for (number, designator) in duration_components(time_part)? {
+        let n = match designator {
+            'H' => i64::from_str(number).ok()?.checked_mul(3_600_000_000_000)?,
+            'M' => i64::from_str(number).ok()?.checked_mul(60_000_000_000)?,
+            'S' => parse_seconds_as_nanos(number)?,
+            _ => return None,
+        };
+        nanos = nanos.checked_add(n)?;
+    }
+    Some(if negative { -nanos } else { nanos })
+}
+
+fn duration_components(s: &str) -> Option<Vec<(&str, char)>> {
+    let mut components = vec![];
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c.is_ascii_alphabetic() {
+            if i == start {
+                return None;
+            }
+            components.push((&s[start..i], c));
+            start = i + 1;
+        }
+    }
+    if start != s.len() {
+        return None;
+    }
+    Some(components)
+}
+
+fn parse_seconds_as_nanos(s: &str) -> Option<i64> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if fraction.len() > 9 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let whole_nanos = i64::from_str(whole).ok()?.checked_mul(1_000_000_000)?;
+    let fraction_nanos = if fraction.is_empty() {
+        0
+    } else {
+        i64::from_str(&format!("{:0<9}", fraction)).ok()?
+    };
+    whole_nanos.checked_add(fraction_nanos)
+}
+
+pub(crate) fn format_xsd_duration(nanos: i64) -> String {
+    let mut out = String::new();
+    if nanos < 0 {
+        out.push('-');
+    }
+    let nanos = nanos.unsigned_abs();
+    let days = nanos / 86_400_000_000_000;
+    let hours = (nanos / 3_600_000_000_000) % 24;
+    let minutes = (nanos / 60_000_000_000) % 60;
+    let seconds = (nanos / 1_000_000_000) % 60;
+    let fraction = nanos % 1_000_000_000;
+    out.push('P');
+    if days > 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if hours > 0 || minutes > 0 || seconds > 0 || fraction > 0 || days == 0 {
+        out.push('T');
+        if hours > 0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0 {
+            out.push_str(&format!("{}M", minutes));
+        }
+        if fraction > 0 {
+            let fraction = format!("{:09}", fraction);
+            out.push_str(&format!("{}.{}S", seconds, fraction.trim_end_matches('0')));
+        } else if seconds > 0 || (hours == 0 && minutes == 0) {
+            out.push_str(&format!("{}S", seconds));
+        }
+    }
+    out
+}
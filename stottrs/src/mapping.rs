@@ -1,44 +1,247 @@
+pub mod backpressure;
+pub mod chunking;
+mod coercion;
+mod cohorts;
 mod constant_terms;
+pub mod coverage;
 pub mod default;
+mod derived_ids;
+mod duplicate_columns;
 pub mod errors;
+mod ground_instances;
+pub mod hierarchy;
+mod join_hints;
+mod language_tag_columns;
+mod prefixed_names;
+mod project;
+mod sampling;
+mod sanitize;
+mod schema_cache;
+mod seed;
+pub mod units;
+pub mod validation;
 mod validation_inference;
+pub mod wide_to_long;
 
 use crate::ast::{
-    ConstantLiteral, ConstantTerm, Instance, ListExpanderType, PType, Signature, StottrTerm,
-    Template,
+    ConstantLiteral, ConstantTerm, ExpressionOperator, Instance, ListExpanderType, PType,
+    Parameter, Signature, StottrTerm, StottrVariable, Template,
 };
-use crate::constants::OTTR_TRIPLE;
-use crate::document::document_from_str;
+use crate::constants::{OTTR_TRIPLE, QUDT_UNIT};
+use crate::document::{document_from_str, wottr_document_from_str};
 use crate::errors::MapperError;
 use crate::io_funcs::create_folder_if_not_exists;
+use crate::mapping::backpressure::{after_stage, before_stage, BackpressureHook, ExpansionStage};
+use crate::mapping::chunking::ChunkPolicy;
+use crate::mapping::coercion::{cast_custom_datatypes, coerce_columns};
+use crate::mapping::cohorts::{optional_cohorts, Cohort};
 use crate::mapping::constant_terms::constant_to_expr;
+use crate::mapping::coverage::{triple_rows, Coverage};
+use crate::mapping::derived_ids::add_derived_id_columns;
+use crate::mapping::duplicate_columns::resolve_duplicate_columns;
 use crate::mapping::errors::MappingError;
+use crate::mapping::join_hints::template_join_hints;
+use crate::mapping::language_tag_columns::{add_language_tag_columns, language_tag_column_name};
+use crate::mapping::prefixed_names::expand_prefixed_names;
+use crate::mapping::sampling::sample_df;
+use crate::mapping::sanitize::{merge_sanitized_columns, sanitize_strings};
+use crate::mapping::schema_cache::SchemaCacheEntry;
+use crate::mapping::seed::blank_node_call_id;
+use crate::mapping::units::{UnitAnnotation, UnitMode};
+use crate::mapping::validation_inference::{
+    fill_default_values, infer_rdf_node_type, validate_non_blank_constant,
+    validate_non_blank_series, INTEGER_DATATYPES,
+};
 use crate::templates::TemplateDataset;
-use crate::triplestore::{TripleType, TriplesToAdd, Triplestore};
+use crate::triplestore::incremental_export::ExportWatermark;
+use crate::triplestore::ntriples_write::{NTriplesLines, NTriplesWriteOptions, DEFAULT_CHUNK_SIZE};
+use crate::triplestore::serializer::SerializationFormat;
+use crate::triplestore::tenancy::tenant_graph;
+use crate::triplestore::{
+    merge_triple_counts, TripleCountDelta, TripleType, TriplesToAdd, Triplestore,
+    LANGUAGE_TAG_COLUMN,
+};
 use log::debug;
 use oxrdf::vocab::xsd;
 use oxrdf::{NamedNode, NamedNodeRef, Triple};
-use polars::lazy::prelude::{col, Expr};
-use polars::prelude::{DataFrame, IntoLazy, PolarsError};
+use polars::io::mmap::MmapBytesReader;
+use polars::lazy::prelude::{col, concat_str, lit, when, Expr, LiteralValue};
+use polars::prelude::{
+    CsvReader, DataFrame, DataType, IdxSize, IntoLazy, LazyFrame, ParallelStrategy,
+    ScanArgsIpc, ScanArgsParquet, SerReader,
+};
+use polars_core::prelude::NamedFrom;
 use polars_core::series::Series;
 use rayon::iter::ParallelDrainRange;
 use rayon::iter::ParallelIterator;
-use std::cmp::min;
 use std::collections::HashMap;
+use std::fs::File;
 use std::error::Error;
 use std::io::Write;
 use std::path::Path;
-use std::time::Instant;
-use uuid::Uuid;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const FILE_BATCH_ROWS: usize = 100_000;
+const DEFAULT_CHUNK_BYTES: usize = 50_000_000;
 
 pub struct Mapping {
     template_dataset: TemplateDataset,
     pub triplestore: Triplestore,
+    template_keys: HashMap<String, TemplateKey>,
+    template_units: HashMap<String, HashMap<String, UnitAnnotation>>,
+    seed: Option<u64>,
+    generated_uuids: u64,
+    schema_cache: HashMap<String, SchemaCacheEntry>,
 }
 
+#[derive(Clone)]
 pub struct ExpandOptions {
     pub language_tags: Option<HashMap<String, String>>,
+    /// Per-row language tags of string columns, keyed by the column, given as the name of a column of BCP47 tags.
+    /// Rows with a null tag get the tag in language_tags for the column, if any.
+    pub language_tag_columns: Option<HashMap<String, String>>,
     pub unique_subsets: Option<Vec<Vec<String>>>,
+    pub graph: Option<String>,
+    pub sample: Option<Sample>,
+    /// Expand prefixed names such as ex:thing1 in IRI columns using the prefixes of the templates.
+    pub expand_prefixed_names: bool,
+    /// When set, the annotations of the expanded templates are expanded into this named graph.
+    pub annotation_graph: Option<String>,
+    /// When set, string columns are checked for invalid UTF-8 and disallowed control characters.
+    pub sanitize_strings: Option<SanitizePolicy>,
+    /// At most this many rows are expanded in a call, the expansion stops when the limit is reached.
+    pub row_limit: Option<usize>,
+    /// Called before and after the heavy stages of the expansion, and may stop it.
+    pub backpressure: Option<Arc<dyn BackpressureHook>>,
+    /// Columns of IRIs generated from the values of other columns, keyed by the parameter they are generated for.
+    pub derived_ids: Option<HashMap<String, DerivedId>>,
+    /// The rows are expanded and stored in chunks of this size.
+    /// Defaults to chunks of about 50MB when there is a caching folder, and no chunking otherwise.
+    pub chunk_policy: Option<ChunkPolicy>,
+    /// How null arguments of non-optional parameters of the templates used by the template are handled.
+    pub validation: ValidationMode,
+    /// When set, columns with a data type that differs from the type of their parameter may be cast to it.
+    pub coercion: Option<Coercion>,
+    /// How columns with the same name in the input are handled, e.g. after joins.
+    pub duplicate_columns: DuplicateColumns,
+    /// How the rows are split before they are expanded.
+    pub strategy: ExpansionStrategy,
+    /// When set, rows with the same column names and types as the rows last expanded with the template are not validated,
+    /// and the typing of their columns is reused. Meant for streaming batches from a trusted source, as nulls in
+    /// non-optional columns and blank nodes in non-blank columns are then not detected.
+    pub trust_schema_cache: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ValidationMode {
+    /// Instances with null arguments for non-optional parameters are skipped, as in OTTR.
+    #[default]
+    Lenient,
+    /// Null arguments for non-optional parameters, including those of ottr:Triple, are errors.
+    Strict,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ExpansionStrategy {
+    /// All rows are expanded together.
+    #[default]
+    Full,
+    /// The rows are split into cohorts by which arguments of optional parameters are null, and each cohort is expanded
+    /// with the null columns as none arguments. Instances that would ignore every row of a cohort are then skipped
+    /// instead of being expanded and dropped, which helps templates with many optional parameters that are mostly null.
+    /// Only used with lenient validation.
+    OptionalCohorts,
+}
+
+/// Sample of the input rows that is expanded instead of the full input, useful when developing mappings.
+/// The same seed gives the same sample of the same input.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub size: SampleSize,
+    pub seed: u64,
+}
+
+#[derive(Clone, Debug)]
+pub enum SampleSize {
+    /// Each row is kept with the given probability.
+    Fraction(f64),
+    /// At most this many rows, chosen uniformly using reservoir sampling.
+    Rows(usize),
+}
+
+/// What to do with strings that are not valid UTF-8 or contain control characters that are not allowed.
+/// Tabs, line feeds and carriage returns are allowed in literals, but no control characters are allowed in IRIs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SanitizePolicy {
+    Error,
+    /// Invalid bytes are replaced by the replacement character and control characters are removed.
+    Clean,
+}
+
+/// Whether columns are cast to the data types of their parameters, e.g. an Int32 column to Int64 for xsd:long,
+/// or a Utf8 column of numbers to Int64, before they are validated.
+#[derive(Clone, Debug, Default)]
+pub struct Coercion {
+    pub policy: CoercionPolicy,
+    /// Policies of particular columns, used instead of the policy.
+    pub columns: HashMap<String, CoercionPolicy>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CoercionPolicy {
+    /// Columns are used as they are, and mismatches are errors.
+    #[default]
+    Never,
+    /// Columns are cast, values that can not be cast are errors.
+    Cast,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum DuplicateColumns {
+    /// Duplicated column names are errors.
+    #[default]
+    Error,
+    /// The first column with a name keeps it, the others get the suffixes _1, _2 and so on.
+    Suffix,
+    /// The column to keep for each duplicated name, counted from 0 in the order of the columns.
+    /// The other columns with the name are dropped, and names that are not selected are errors.
+    Select(HashMap<String, usize>),
+}
+
+/// Rows of a column with malformed strings, counted from the first row of the input after sampling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SanitizedColumn {
+    pub column: String,
+    pub rows: Vec<usize>,
+}
+
+/// IRI for an intermediate node, such as the node of an n-ary relation, derived from a composite of other columns.
+/// The same prefix and values give the same IRI in every run, unlike blank nodes or random UUIDs.
+/// Rows with a null in any of the columns get a null IRI.
+#[derive(Clone, Debug)]
+pub struct DerivedId {
+    pub from_columns: Vec<String>,
+    /// The IRI is the prefix followed by the id.
+    pub prefix: String,
+    pub format: DerivedIdFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DerivedIdFormat {
+    /// Name based UUID (version 5).
+    Uuid,
+    /// The bits of the name based UUID in the 26 character ULID encoding, the timestamp part is not meaningful.
+    Ulid,
+}
+
+/// Key declared for a template, used by all calls to expand the template without unique_subsets.
+#[derive(Clone, Debug)]
+pub struct TemplateKey {
+    /// Each subset is a composite key of columns that is unique in each call.
+    pub unique_subsets: Vec<Vec<String>>,
+    /// When the keys are unique across all calls, triples from different calls are not deduplicated.
+    pub globally_unique: bool,
 }
 
 struct OTTRTripleInstance {
@@ -46,6 +249,7 @@ struct OTTRTripleInstance {
     dynamic_columns: HashMap<String, PrimitiveColumn>,
     static_columns: HashMap<String, StaticColumn>,
     has_unique_subset: bool,
+    pattern_path: Vec<(String, usize)>,
 }
 
 #[derive(Clone)]
@@ -58,7 +262,22 @@ impl Default for ExpandOptions {
     fn default() -> Self {
         ExpandOptions {
             language_tags: None,
+            language_tag_columns: None,
             unique_subsets: None,
+            graph: None,
+            sample: None,
+            expand_prefixed_names: false,
+            annotation_graph: None,
+            sanitize_strings: None,
+            row_limit: None,
+            backpressure: None,
+            derived_ids: None,
+            chunk_policy: None,
+            validation: ValidationMode::Lenient,
+            coercion: None,
+            duplicate_columns: DuplicateColumns::Error,
+            strategy: ExpansionStrategy::Full,
+            trust_schema_cache: false,
         }
     }
 }
@@ -67,6 +286,9 @@ impl Default for ExpandOptions {
 pub struct PrimitiveColumn {
     pub rdf_node_type: RDFNodeType,
     pub language_tag: Option<String>,
+    /// The column is followed by a column of per-row language tags.
+    pub has_language_tag_column: bool,
+    pub unit: Option<UnitAnnotation>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -75,6 +297,15 @@ pub enum RDFNodeType {
     BlankNode,
     Literal(NamedNode),
     None,
+    /// Values of several types in query solutions, stored as lexical forms with a column tagging the type of each value.
+    MultiType,
+}
+
+/// The datatypes of numeric literals, which are compared by their values.
+pub(crate) fn numeric_datatypes() -> impl Iterator<Item = NamedNodeRef<'static>> {
+    INTEGER_DATATYPES
+        .into_iter()
+        .chain([xsd::DECIMAL, xsd::DOUBLE, xsd::FLOAT])
 }
 
 impl RDFNodeType {
@@ -95,8 +326,16 @@ impl RDFNodeType {
         self.is_lit_type(xsd::FLOAT)
     }
 
+    pub fn is_integer(&self) -> bool {
+        INTEGER_DATATYPES.iter().any(|dt| self.is_lit_type(*dt))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        numeric_datatypes().any(|dt| self.is_lit_type(dt))
+    }
+
     pub(crate) fn find_triple_type(&self) -> TripleType {
-        let triple_type = if let RDFNodeType::IRI = self {
+        let triple_type = if let RDFNodeType::IRI | RDFNodeType::BlankNode = self {
             TripleType::ObjectProperty
         } else if let RDFNodeType::Literal(lit) = self {
             if lit.as_ref() == xsd::STRING {
@@ -111,8 +350,64 @@ impl RDFNodeType {
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct MappingReport {}
+#[derive(Debug, PartialEq, Default)]
+pub struct MappingReport {
+    /// Rows given and added for each predicate, rows with nulls and duplicates are not added.
+    pub triple_counts: Vec<TripleCountDelta>,
+    /// Rows with malformed strings in each column, when strings are sanitized.
+    pub sanitized_columns: Vec<SanitizedColumn>,
+    /// Rows that were expanded, after sampling.
+    pub rows_expanded: usize,
+    /// Set when the expansion stopped before all rows were expanded, the triples of the expanded rows are kept.
+    pub stopped: Option<StopReason>,
+    /// Triples produced by the templates used in the call and by the instances in their pattern lists.
+    pub coverage: Coverage,
+    /// Rows each template used in the call was instantiated with.
+    pub template_rows: Vec<TemplateRows>,
+    /// Wall time of the call, including storing the triples.
+    pub elapsed: Duration,
+}
+
+/// Rows a template was instantiated with, counting every use of the template in the call.
+/// Instances in pattern lists that are ignored, e.g. because of a none argument, do not count.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateRows {
+    pub template: String,
+    pub rows: usize,
+}
+
+pub(crate) fn merge_template_rows(template_rows: &mut Vec<TemplateRows>, new_rows: Vec<TemplateRows>) {
+    for r in new_rows {
+        if let Some(existing) = template_rows.iter_mut().find(|x| x.template == r.template) {
+            existing.rows += r.rows;
+        } else {
+            template_rows.push(r);
+        }
+    }
+    template_rows.sort_by(|x, y| x.template.cmp(&y.template));
+}
+
+/// Why an expansion stopped before all rows were expanded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StopReason {
+    RowLimit,
+    Backpressure(ExpansionStage),
+}
+
+impl MappingReport {
+    //Rows of the other report are offset by the rows processed before it
+    fn merge(&mut self, other: MappingReport, row_offset: usize) {
+        merge_triple_counts(&mut self.triple_counts, other.triple_counts);
+        merge_sanitized_columns(&mut self.sanitized_columns, other.sanitized_columns, row_offset);
+        self.rows_expanded += other.rows_expanded;
+        self.coverage.merge(other.coverage);
+        merge_template_rows(&mut self.template_rows, other.template_rows);
+        self.elapsed += other.elapsed;
+        if self.stopped.is_none() {
+            self.stopped = other.stopped;
+        }
+    }
+}
 
 impl Mapping {
     pub fn new(template_dataset: &TemplateDataset, caching_folder: Option<String>) -> Mapping {
@@ -122,6 +417,11 @@ impl Mapping {
         Mapping {
             template_dataset: template_dataset.clone(),
             triplestore: Triplestore::new(caching_folder),
+            template_keys: HashMap::new(),
+            template_units: HashMap::new(),
+            seed: None,
+            generated_uuids: 0,
+            schema_cache: HashMap::new(),
         }
     }
 
@@ -147,6 +447,12 @@ impl Mapping {
         Ok(Mapping::new(&dataset, caching_folder))
     }
 
+    pub fn from_wottr_str(s: &str, caching_folder: Option<String>) -> Result<Mapping, Box<dyn Error>> {
+        let doc = wottr_document_from_str(s)?;
+        let dataset = TemplateDataset::new(vec![doc])?;
+        Ok(Mapping::new(&dataset, caching_folder))
+    }
+
     pub fn from_strs(
         ss: Vec<&str>,
         caching_folder: Option<String>,
@@ -160,11 +466,66 @@ impl Mapping {
         Ok(Mapping::new(&dataset, caching_folder))
     }
 
-    pub fn write_n_triples(&mut self, buffer: &mut dyn Write) -> Result<(), PolarsError> {
+    pub fn write_n_triples(&mut self, buffer: &mut dyn Write) -> Result<(), MapperError> {
         self.triplestore
-            .write_n_triples_all_dfs(buffer, 1024)
-            .unwrap();
-        Ok(())
+            .write_n_triples_all_dfs(buffer, DEFAULT_CHUNK_SIZE)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    pub fn write_n_triples_with_options(
+        &mut self,
+        buffer: &mut dyn Write,
+        options: &NTriplesWriteOptions,
+    ) -> Result<(), MapperError> {
+        self.triplestore
+            .write_n_triples_with_options(buffer, options)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    pub fn n_triples_lines(&mut self, chunk_size: usize) -> Result<NTriplesLines<'_>, MapperError> {
+        self.triplestore
+            .n_triples_lines(chunk_size)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    /// Writes the triples added since the watermark, starting from ExportWatermark::default() to write all triples.
+    pub fn write_n_triples_since(
+        &mut self,
+        buffer: &mut dyn Write,
+        watermark: &ExportWatermark,
+    ) -> Result<ExportWatermark, MapperError> {
+        self.triplestore
+            .write_n_triples_since(buffer, watermark, DEFAULT_CHUNK_SIZE)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    pub fn write_n_quads(&mut self, buffer: &mut dyn Write) -> Result<(), MapperError> {
+        self.triplestore
+            .write_n_quads_all_dfs(buffer, 1024)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    pub fn write_trig(&mut self, buffer: &mut dyn Write) -> Result<(), MapperError> {
+        self.triplestore
+            .write_trig_all_dfs(buffer, 1024)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    pub fn write_turtle(&mut self, buffer: &mut dyn Write) -> Result<(), MapperError> {
+        self.triplestore
+            .write_turtle_all_dfs(buffer, 1024, &self.template_dataset.prefix_map)
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
+    /// Writes the triples to the buffer in the format, see Triplestore::write.
+    pub fn write(
+        &mut self,
+        format: &SerializationFormat,
+        buffer: &mut dyn Write,
+    ) -> Result<(), MapperError> {
+        self.triplestore
+            .write(format, buffer)
+            .map_err(|x| MapperError::MappingError(x))
     }
 
     pub fn write_native_parquet(&mut self, path: &str) -> Result<(), MapperError> {
@@ -173,10 +534,32 @@ impl Mapping {
             .map_err(|x| MapperError::MappingError(x))
     }
 
+    /// Replaces the triples of the mapping with the triples written to the folder by write_native_parquet.
+    pub fn read_native_parquet(&mut self, path: &str) -> Result<(), MapperError> {
+        self.triplestore = Triplestore::from_native_parquet(Path::new(path))
+            .map_err(|x| MapperError::MappingError(x))?;
+        Ok(())
+    }
+
+    pub fn write_ipc(&mut self, path: &str) -> Result<(), MapperError> {
+        self.triplestore
+            .write_ipc(Path::new(path))
+            .map_err(|x| MapperError::MappingError(x))
+    }
+
     pub fn export_oxrdf_triples(&mut self) -> Result<Vec<Triple>, MappingError> {
         self.triplestore.export_oxrdf_triples()
     }
 
+    /// The base IRI of the stOTTR documents, used to resolve relative IRIs in templates and columns.
+    pub fn base_iri(&self) -> Option<&NamedNode> {
+        self.template_dataset.base_iri.as_ref()
+    }
+
+    pub fn template_dataset(&self) -> &TemplateDataset {
+        &self.template_dataset
+    }
+
     fn resolve_template(&self, s: &str) -> Result<&Template, MappingError> {
         if let Some(t) = self.template_dataset.get(s) {
             return Ok(t);
@@ -200,6 +583,36 @@ impl Mapping {
         Err(MappingError::TemplateNotFound(s.to_string()))
     }
 
+    pub fn upsert_template(&mut self, template: Template) -> Result<(), MappingError> {
+        //Type inference may change the parameters of any template
+        self.schema_cache.clear();
+        self.template_dataset
+            .upsert_template(template)
+            .map_err(|x| MappingError::TemplateTypingError(x))
+    }
+
+    pub fn declare_key(&mut self, template: &str, key: TemplateKey) -> Result<(), MappingError> {
+        let target_template = self.resolve_template(template)?;
+        for subset in &key.unique_subsets {
+            for c in subset {
+                if !target_template
+                    .signature
+                    .parameter_list
+                    .iter()
+                    .any(|p| &p.stottr_variable.name == c)
+                {
+                    return Err(MappingError::UnknownKeyColumn(
+                        target_template.signature.template_name.as_str().to_string(),
+                        c.clone(),
+                    ));
+                }
+            }
+        }
+        let template_name = target_template.signature.template_name.as_str().to_string();
+        self.template_keys.insert(template_name, key);
+        Ok(())
+    }
+
     pub fn expand(
         &mut self,
         template: &str,
@@ -208,81 +621,477 @@ impl Mapping {
     ) -> Result<MappingReport, MappingError> {
         let now = Instant::now();
         let target_template = self.resolve_template(template)?.clone();
+        let call_uuid = self.new_uuid().to_string();
+        let mut report = self.expand_df(&target_template, df, &options, &call_uuid, 0)?;
+        debug!("Expansion took {} seconds", now.elapsed().as_secs_f32());
+        self.expand_annotations(&target_template, options, &mut report)?;
+        report.elapsed = now.elapsed();
+        Ok(report)
+    }
+
+    /// Expands the template into the graph of the tenant. The quota of the tenant is checked
+    /// each time triples are stored, so chunks stored before the quota is exceeded are kept.
+    pub fn expand_for_tenant(
+        &mut self,
+        tenant: &str,
+        template: &str,
+        df: DataFrame,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let options = ExpandOptions {
+            graph: Some(tenant_graph(tenant)),
+            ..options
+        };
+        self.expand(template, df, options)
+    }
+
+    /// Expands the template with the rows of a CSV file with a header, reading the file in batches
+    /// so that it never has to fit in memory at once. Sampling is applied to each batch.
+    pub fn expand_from_csv<P: AsRef<Path>>(
+        &mut self,
+        template: &str,
+        path: P,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let now = Instant::now();
+        let target_template = self.resolve_template(template)?.clone();
+        let call_uuid = self.new_uuid().to_string();
+        let file = File::open(path.as_ref()).map_err(|x| MappingError::ReadCsvError(x.into()))?;
+        let mut batched_reader = CsvReader::new(Box::new(file) as Box<dyn MmapBytesReader>)
+            .has_header(true)
+            .with_chunk_size(FILE_BATCH_ROWS)
+            .batched(None)
+            .map_err(|x| MappingError::ReadCsvError(x))?;
+        let mut report = MappingReport::default();
+        let mut n_rows = 0;
+        while let Some(dfs) = batched_reader
+            .next_batches(1)
+            .map_err(|x| MappingError::ReadCsvError(x))?
+        {
+            for (_, df) in dfs {
+                let height = df.height();
+                let batch_report = self.expand_df(
+                    &target_template,
+                    df,
+                    &options,
+                    &call_uuid,
+                    report.rows_expanded,
+                )?;
+                report.merge(batch_report, n_rows);
+                n_rows += height;
+                debug!("Finished processing {} rows from CSV", n_rows);
+                if report.stopped.is_some() {
+                    break;
+                }
+            }
+            if report.stopped.is_some() {
+                break;
+            }
+        }
+        debug!("Expansion from CSV took {} seconds", now.elapsed().as_secs_f32());
+        self.expand_annotations(&target_template, options, &mut report)?;
+        report.elapsed = now.elapsed();
+        Ok(report)
+    }
+
+    /// Expands the template with the rows of a parquet file, or of all files matching a glob pattern.
+    /// The files are scanned lazily and expanded in batches, so they never have to fit in memory at once.
+    /// Sampling is applied to each batch.
+    pub fn expand_from_parquet(
+        &mut self,
+        template: &str,
+        path_or_glob: &str,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let now = Instant::now();
+        let target_template = self.resolve_template(template)?.clone();
+        let call_uuid = self.new_uuid().to_string();
+        let lf = LazyFrame::scan_parquet(
+            path_or_glob,
+            ScanArgsParquet {
+                n_rows: None,
+                cache: false,
+                parallel: ParallelStrategy::Auto,
+                rechunk: false,
+                row_count: None,
+                low_memory: true,
+            },
+        )
+        .map_err(|x| MappingError::ReadParquetError(x))?;
+        let mut report = self.expand_lazy_frame(&target_template, lf, &options, &call_uuid)?;
+        debug!(
+            "Expansion from parquet took {} seconds",
+            now.elapsed().as_secs_f32()
+        );
+        self.expand_annotations(&target_template, options, &mut report)?;
+        report.elapsed = now.elapsed();
+        Ok(report)
+    }
+
+    /// Expands the template with the rows of an Arrow IPC (Feather v2) file, or of all files matching a glob pattern.
+    /// The files are scanned lazily and expanded in batches. Sampling is applied to each batch.
+    pub fn expand_from_ipc(
+        &mut self,
+        template: &str,
+        path_or_glob: &str,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let now = Instant::now();
+        let target_template = self.resolve_template(template)?.clone();
+        let call_uuid = self.new_uuid().to_string();
+        let lf = LazyFrame::scan_ipc(path_or_glob, ScanArgsIpc::default())
+            .map_err(|x| MappingError::ReadIpcError(x))?;
+        let mut report = self.expand_lazy_frame(&target_template, lf, &options, &call_uuid)?;
+        debug!(
+            "Expansion from IPC took {} seconds",
+            now.elapsed().as_secs_f32()
+        );
+        self.expand_annotations(&target_template, options, &mut report)?;
+        report.elapsed = now.elapsed();
+        Ok(report)
+    }
+
+    fn expand_lazy_frame(
+        &mut self,
+        target_template: &Template,
+        lf: LazyFrame,
+        options: &ExpandOptions,
+        call_uuid: &String,
+    ) -> Result<MappingReport, MappingError> {
+        let mut report = MappingReport::default();
+        let mut offset = 0;
+        loop {
+            let df = lf
+                .clone()
+                .slice(offset, FILE_BATCH_ROWS as IdxSize)
+                .collect()
+                .map_err(|x| MappingError::ReadScannedFileError(x))?;
+            let height = df.height();
+            if height == 0 {
+                break;
+            }
+            let batch_report =
+                self.expand_df(target_template, df, options, call_uuid, report.rows_expanded)?;
+            report.merge(batch_report, offset as usize);
+            offset += height as i64;
+            debug!("Finished processing {} rows", offset);
+            if height < FILE_BATCH_ROWS || report.stopped.is_some() {
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    fn expand_df(
+        &mut self,
+        target_template: &Template,
+        df: DataFrame,
+        options: &ExpandOptions,
+        call_uuid: &String,
+        rows_expanded_before: usize,
+    ) -> Result<MappingReport, MappingError> {
         let target_template_name = target_template.signature.template_name.as_str().to_string();
+        let df = resolve_duplicate_columns(df, &options.duplicate_columns)?;
+        let df = if let Some(sample) = &options.sample {
+            sample_df(&df, sample)?
+        } else {
+            df
+        };
+        let mut stopped = None;
+        let df = if let Some(row_limit) = options.row_limit {
+            let remaining = row_limit.saturating_sub(rows_expanded_before);
+            if df.height() > remaining {
+                stopped = Some(StopReason::RowLimit);
+                df.slice(0, remaining)
+            } else {
+                df
+            }
+        } else {
+            df
+        };
+        let df = if let Some(derived_ids) = &options.derived_ids {
+            add_derived_id_columns(df, derived_ids)?
+        } else {
+            df
+        };
+        let df = if let Some(language_tag_columns) = &options.language_tag_columns {
+            add_language_tag_columns(df, language_tag_columns)?
+        } else {
+            df
+        };
+        let df = fill_default_values(&target_template.signature, df)?;
+        let df = if let Some(coercion) = &options.coercion {
+            coerce_columns(df, &target_template.signature, coercion)?
+        } else {
+            df
+        };
+        let df = cast_custom_datatypes(
+            df,
+            &target_template.signature,
+            &self.triplestore.custom_datatypes,
+        )?;
         let columns =
-            self.validate_infer_dataframe_columns(&target_template.signature, &df, &options)?;
-        let ExpandOptions {
-            language_tags: _,
-            unique_subsets: unique_subsets_opt,
-        } = options;
-        let unique_subsets = if let Some(unique_subsets) = unique_subsets_opt {
-            unique_subsets
+            self.validate_infer_columns_cached(&target_template.signature, &df, options)?;
+        let (df, sanitized_columns) = if let Some(policy) = &options.sanitize_strings {
+            sanitize_strings(df, &columns, policy)?
+        } else {
+            (df, vec![])
+        };
+        let df = if options.expand_prefixed_names {
+            expand_prefixed_names(
+                df,
+                &columns,
+                &self.template_dataset.prefix_map,
+                &self.template_dataset.base_iri,
+            )?
+        } else {
+            df
+        };
+        let declared_key = self.template_keys.get(&target_template_name);
+        let globally_unique = declared_key.map(|k| k.globally_unique).unwrap_or(false);
+        let unique_subsets = if let Some(unique_subsets) = &options.unique_subsets {
+            unique_subsets.clone()
+        } else if let Some(key) = declared_key {
+            key.unique_subsets.clone()
         } else {
             vec![]
         };
-        let call_uuid = Uuid::new_v4().to_string();
+        let now = Instant::now();
+        let mut triple_counts = vec![];
+        let mut rows_expanded = 0;
+        let mut coverage = Coverage::new(&self.template_dataset, &target_template_name);
+        let mut template_rows = vec![];
+        self.triplestore
+            .add_join_hints(template_join_hints(&self.template_dataset, &target_template_name));
 
         if let Some(caching_folder) = &self.triplestore.caching_folder {
             create_folder_if_not_exists(Path::new(&caching_folder))?;
-            let n_50_mb = (df.estimated_size() / 50_000_000) + 1;
-            let chunk_size = df.height() / n_50_mb;
-            let mut offset = 0i64;
-            loop {
-                let to_row = min(df.height(), offset as usize + chunk_size);
-                let df_slice = df.slice_par(offset, to_row);
-                offset += chunk_size as i64;
-                let result_vec = self._expand(
+        }
+        let chunk_policy = if options.chunk_policy.is_some() {
+            options.chunk_policy
+        } else if self.triplestore.caching_folder.is_some() {
+            Some(ChunkPolicy::Bytes(DEFAULT_CHUNK_BYTES))
+        } else {
+            None
+        };
+        if let Some(chunk_policy) = chunk_policy {
+            for df_slice in chunk_policy.split(&df) {
+                let slice_height = df_slice.height();
+                let (counts, chunk_stopped) = self.expand_chunk(
                     &target_template_name,
                     df_slice,
                     columns.clone(),
-                    HashMap::new(),
                     unique_subsets.clone(),
+                    call_uuid,
+                    globally_unique,
+                    options,
+                    &mut coverage,
+                    &mut template_rows,
                 )?;
-                self.process_results(result_vec, &call_uuid)?;
-                debug!("Finished processing {} rows", to_row);
-                if offset >= df.height() as i64 {
+                merge_triple_counts(&mut triple_counts, counts);
+                if chunk_stopped.is_some() {
+                    stopped = chunk_stopped;
                     break;
                 }
+                rows_expanded += slice_height;
+                debug!("Finished processing {} rows", rows_expanded);
             }
         } else {
-            let result_vec = self._expand(
+            let height = df.height();
+            let (counts, chunk_stopped) = self.expand_chunk(
                 &target_template_name,
                 df,
                 columns,
-                HashMap::new(),
                 unique_subsets,
+                call_uuid,
+                globally_unique,
+                options,
+                &mut coverage,
+                &mut template_rows,
             )?;
-            self.process_results(result_vec, &call_uuid)?;
-            debug!("Expansion took {} seconds", now.elapsed().as_secs_f32());
+            triple_counts = counts;
+            if chunk_stopped.is_some() {
+                stopped = chunk_stopped;
+            } else {
+                rows_expanded = height;
+            }
         }
-        Ok(MappingReport {})
+        Ok(MappingReport {
+            triple_counts,
+            sanitized_columns,
+            rows_expanded,
+            stopped,
+            coverage,
+            template_rows,
+            elapsed: now.elapsed(),
+        })
+    }
+
+    //Expands a chunk of rows and stores the triples, unless the backpressure hook stops the expansion before a stage
+    fn expand_chunk(
+        &mut self,
+        target_template_name: &str,
+        df: DataFrame,
+        columns: HashMap<String, PrimitiveColumn>,
+        unique_subsets: Vec<Vec<String>>,
+        call_uuid: &String,
+        globally_unique: bool,
+        options: &ExpandOptions,
+        coverage: &mut Coverage,
+        template_rows: &mut Vec<TemplateRows>,
+    ) -> Result<(Vec<TripleCountDelta>, Option<StopReason>), MappingError> {
+        let backpressure = &options.backpressure;
+        let rows = df.height();
+        let stage = ExpansionStage::Expand;
+        if !before_stage(backpressure, &stage, rows) {
+            return Ok((vec![], Some(StopReason::Backpressure(stage))));
+        }
+        let expansion_id = self.new_uuid().simple().to_string();
+        let cohorts = if options.strategy == ExpansionStrategy::OptionalCohorts
+            && options.validation == ValidationMode::Lenient
+        {
+            let signature = &self
+                .template_dataset
+                .get(target_template_name)
+                .unwrap()
+                .signature;
+            optional_cohorts(df, signature, columns)
+        } else {
+            vec![Cohort {
+                df,
+                dynamic_columns: columns,
+                static_columns: HashMap::new(),
+            }]
+        };
+        let mut result_vec = vec![];
+        for (cohort_index, cohort) in cohorts.into_iter().enumerate() {
+            //Blank nodes are labelled by row number, so each cohort needs its own expansion id
+            let cohort_expansion_id = if cohort_index == 0 {
+                expansion_id.clone()
+            } else {
+                format!("{} {}", expansion_id, cohort_index)
+            };
+            let cohort_result = self._expand(
+                target_template_name,
+                cohort.df,
+                cohort.dynamic_columns,
+                cohort.static_columns,
+                unique_subsets.clone(),
+                vec![],
+                options.validation,
+                &cohort_expansion_id,
+            );
+            let (cohort_result_vec, expanded_template_rows) = match cohort_result {
+                Ok(r) => r,
+                Err(e) => {
+                    after_stage(backpressure, &stage, rows);
+                    return Err(e);
+                }
+            };
+            result_vec.extend(cohort_result_vec);
+            merge_template_rows(template_rows, expanded_template_rows);
+        }
+        after_stage(backpressure, &stage, rows);
+
+        let stage = ExpansionStage::StoreTriples;
+        if !before_stage(backpressure, &stage, rows) {
+            return Ok((vec![], Some(StopReason::Backpressure(stage))));
+        }
+        let counts = self.process_results(
+            result_vec,
+            call_uuid,
+            &options.graph,
+            globally_unique,
+            coverage,
+        );
+        after_stage(backpressure, &stage, rows);
+        Ok((counts?, None))
+    }
+
+    fn expand_annotations(
+        &mut self,
+        target_template: &Template,
+        options: ExpandOptions,
+        report: &mut MappingReport,
+    ) -> Result<(), MappingError> {
+        if let Some(annotation_graph) = options.annotation_graph {
+            let annotation_instances =
+                self.annotation_instances(target_template.signature.template_name.as_str());
+            if !annotation_instances.is_empty() {
+                let annotation_report =
+                    self.expand_instances_in_graph(annotation_instances, Some(annotation_graph))?;
+                merge_triple_counts(&mut report.triple_counts, annotation_report.triple_counts);
+            }
+        }
+        Ok(())
+    }
+
+    //Annotation instances of the template and the templates it uses
+    fn annotation_instances(&self, template_name: &str) -> Vec<Instance> {
+        let mut instances = vec![];
+        let mut visited = vec![template_name.to_string()];
+        let mut queue = vec![template_name.to_string()];
+        while let Some(name) = queue.pop() {
+            if let Some(template) = self.template_dataset.get(&name) {
+                if let Some(annotation_list) = &template.signature.annotation_list {
+                    instances.extend(annotation_list.iter().map(|a| a.instance.clone()));
+                }
+                for i in &template.pattern_list {
+                    let used = i.template_name.as_str().to_string();
+                    if !visited.contains(&used) {
+                        visited.push(used.clone());
+                        queue.push(used);
+                    }
+                }
+            }
+        }
+        instances
     }
 
     fn _expand(
         &self,
         name: &str,
         mut df: DataFrame,
-        dynamic_columns: HashMap<String, PrimitiveColumn>,
+        mut dynamic_columns: HashMap<String, PrimitiveColumn>,
         static_columns: HashMap<String, StaticColumn>,
         unique_subsets: Vec<Vec<String>>,
-    ) -> Result<Vec<OTTRTripleInstance>, MappingError> {
+        pattern_path: Vec<(String, usize)>,
+        validation: ValidationMode,
+        expansion_id: &str,
+    ) -> Result<(Vec<OTTRTripleInstance>, Vec<TemplateRows>), MappingError> {
         //At this point, the lf should have columns with names appropriate for the template to be instantiated (named_node).
         if let Some(template) = self.template_dataset.get(name) {
             if template.signature.template_name.as_str() == OTTR_TRIPLE {
-                Ok(vec![OTTRTripleInstance {
-                    df,
-                    dynamic_columns,
-                    static_columns,
-                    has_unique_subset: !unique_subsets.is_empty(),
-                }])
+                Ok((
+                    vec![OTTRTripleInstance {
+                        df,
+                        dynamic_columns,
+                        static_columns,
+                        has_unique_subset: !unique_subsets.is_empty(),
+                        pattern_path,
+                    }],
+                    vec![],
+                ))
             } else {
+                self.add_units(name, &mut dynamic_columns);
+                let mut template_rows = vec![TemplateRows {
+                    template: name.to_string(),
+                    rows: df.height(),
+                }];
+                let fresh_pattern_list = add_fresh_blank_node_columns(
+                    &mut df,
+                    &template.pattern_list,
+                    &mut dynamic_columns,
+                    &blank_node_call_id(expansion_id, &pattern_path, name),
+                );
+                let pattern_list = fresh_pattern_list.as_ref().unwrap_or(&template.pattern_list);
                 let mut series_map: HashMap<String, Series> = df
                     .get_columns_mut()
                     .drain(..)
                     .map(|x| (x.name().to_string(), x))
                     .collect();
                 let number_of_series_map =
-                    get_number_per_series_map(&template.pattern_list, &dynamic_columns);
+                    get_number_per_series_map(pattern_list, &dynamic_columns);
                 let mut series_keys: Vec<&String> = number_of_series_map.keys().collect();
                 series_keys.sort();
 
@@ -302,10 +1111,13 @@ impl Mapping {
                     cloned_series_map.get_mut(&k).unwrap().push(ser);
                 }
                 let mut expand_params_vec = vec![];
-                for i in &template.pattern_list {
+                for (pattern_index, i) in pattern_list.iter().enumerate() {
                     let mut instance_series = vec![];
                     let vs = get_variable_names(i);
                     for v in vs {
+                        if !dynamic_columns.contains_key(v) {
+                            continue;
+                        }
                         let mut found = false;
                         if let Some(series_vec) = cloned_series_map.get_mut(v) {
                             if let Some(series) = series_vec.pop() {
@@ -316,17 +1128,31 @@ impl Mapping {
                         if !found {
                             instance_series.push(series_map.remove(v).unwrap());
                         }
+                        if let Some(c) = dynamic_columns.get(v) {
+                            if c.has_language_tag_column {
+                                let tag_series =
+                                    series_map.get(&language_tag_column_name(v)).unwrap();
+                                instance_series.push(tag_series.clone());
+                            }
+                        }
                     }
-                    expand_params_vec.push((i, DataFrame::new(instance_series).unwrap()));
+                    expand_params_vec.push((
+                        pattern_index,
+                        i,
+                        DataFrame::new(instance_series).unwrap(),
+                    ));
                 }
 
                 debug!("Cloning args took {} seconds", now.elapsed().as_secs_f64());
 
-                let results: Vec<Result<Vec<OTTRTripleInstance>, MappingError>> = expand_params_vec
+                let results: Vec<Result<(Vec<OTTRTripleInstance>, Vec<TemplateRows>), MappingError>> = expand_params_vec
                     .par_drain(..)
-                    .map(|(i, df)| {
+                    .map(|(pattern_index, i, df)| {
                         let target_template =
                             self.template_dataset.get(i.template_name.as_str()).unwrap();
+                        if is_ignored_instance(i, &target_template.signature, &static_columns) {
+                            return Ok((vec![], vec![]));
+                        }
                         let (
                             instance_df,
                             instance_dynamic_columns,
@@ -339,23 +1165,31 @@ impl Mapping {
                             &dynamic_columns,
                             &static_columns,
                             &unique_subsets,
+                            validation,
                         )?;
 
+                        let mut instance_pattern_path = pattern_path.clone();
+                        instance_pattern_path.push((name.to_string(), pattern_index));
                         self._expand(
                             i.template_name.as_str(),
                             instance_df,
                             instance_dynamic_columns,
                             instance_static_columns,
                             new_unique_subsets,
+                            instance_pattern_path,
+                            validation,
+                            expansion_id,
                         )
                     })
                     .collect();
                 let mut results_ok = vec![];
                 for r in results {
-                    results_ok.push(r?)
+                    let (instances, instance_template_rows) = r?;
+                    results_ok.push(instances);
+                    merge_template_rows(&mut template_rows, instance_template_rows);
                 }
 
-                Ok(flatten(results_ok))
+                Ok((flatten(results_ok), template_rows))
             }
         } else {
             Err(MappingError::TemplateNotFound(name.to_string()))
@@ -366,48 +1200,122 @@ impl Mapping {
         &mut self,
         mut result_vec: Vec<OTTRTripleInstance>,
         call_uuid: &String,
-    ) -> Result<(), MappingError> {
+        graph: &Option<String>,
+        globally_unique: bool,
+        coverage: &mut Coverage,
+    ) -> Result<Vec<TripleCountDelta>, MappingError> {
         let now = Instant::now();
         let triples: Vec<
-            Result<(DataFrame, RDFNodeType, Option<String>, Option<String>, bool), MappingError>,
+            Result<
+                (
+                    Vec<(DataFrame, RDFNodeType, Option<String>, Option<String>, bool)>,
+                    Vec<(String, usize)>,
+                ),
+                MappingError,
+            >,
         > = result_vec
             .par_drain(..)
-            .map(|i| create_triples(i))
+            .map(|mut i| {
+                let pattern_path = std::mem::take(&mut i.pattern_path);
+                create_triples(i).map(|t| (t, pattern_path))
+            })
             .collect();
         let mut ok_triples = vec![];
         for t in triples {
             ok_triples.push(t?);
         }
         let mut all_triples_to_add = vec![];
-        for (df, rdf_node_type, language_tag, verb, has_unique_subset) in ok_triples {
-            all_triples_to_add.push(TriplesToAdd {
-                df,
-                object_type: rdf_node_type,
-                language_tag,
-                static_verb_column: verb,
-                has_unique_subset,
-            });
+        for (instance_triples, pattern_path) in ok_triples {
+            for (df, rdf_node_type, language_tag, verb, has_unique_subset) in instance_triples {
+                coverage.count_triples(&pattern_path, triple_rows(&df));
+                all_triples_to_add.push(TriplesToAdd {
+                    df,
+                    object_type: rdf_node_type,
+                    language_tag,
+                    static_verb_column: verb,
+                    has_unique_subset,
+                    globally_unique,
+                    graph: graph.clone(),
+                });
+            }
         }
-        self.triplestore
+        let triple_counts = self
+            .triplestore
             .add_triples_vec(all_triples_to_add, call_uuid)?;
 
         debug!(
             "Result processing took {} seconds",
             now.elapsed().as_secs_f32()
         );
-        Ok(())
+        Ok(triple_counts)
     }
 }
 
+//Blank nodes in the pattern list are fresh for each instance, i.e. for each row of the df.
+//They are added as columns, and the pattern list is rewritten to use these columns.
+fn add_fresh_blank_node_columns(
+    df: &mut DataFrame,
+    pattern_list: &Vec<Instance>,
+    dynamic_columns: &mut HashMap<String, PrimitiveColumn>,
+    call_id: &str,
+) -> Option<Vec<Instance>> {
+    let mut blank_nodes = vec![];
+    for i in pattern_list {
+        for a in &i.argument_list {
+            if let StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::BlankNode(bn))) =
+                &a.term
+            {
+                if !blank_nodes.contains(bn) {
+                    blank_nodes.push(bn.clone());
+                }
+            }
+        }
+    }
+    if blank_nodes.is_empty() {
+        return None;
+    }
+    for bn in &blank_nodes {
+        let column_name = format!("_:{}", bn.as_str());
+        let labels: Vec<String> = (0..df.height())
+            .map(|i| format!("_:{}_{}_{}", bn.as_str(), call_id, i))
+            .collect();
+        df.with_column(Series::new(&column_name, labels)).unwrap();
+        dynamic_columns.insert(
+            column_name,
+            PrimitiveColumn {
+                rdf_node_type: RDFNodeType::BlankNode,
+                language_tag: None,
+                has_language_tag_column: false,
+                unit: None,
+            },
+        );
+    }
+    let mut new_pattern_list = pattern_list.clone();
+    for i in &mut new_pattern_list {
+        for a in &mut i.argument_list {
+            if let StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::BlankNode(bn))) =
+                &a.term
+            {
+                let name = format!("_:{}", bn.as_str());
+                a.term = StottrTerm::Variable(StottrVariable { name });
+            }
+        }
+    }
+    Some(new_pattern_list)
+}
+
 fn get_number_per_series_map(
     instances: &Vec<Instance>,
     dynamic_columns: &HashMap<String, PrimitiveColumn>,
 ) -> HashMap<String, u16> {
     let mut out_map: HashMap<String, u16> =
         dynamic_columns.keys().map(|k| (k.clone(), 0)).collect();
+    //Variables of static columns, e.g. parameters given a default value, have no series
     for i in instances {
         for v in get_variable_names(i) {
-            *out_map.get_mut(v).unwrap() += 1;
+            if let Some(n) = out_map.get_mut(v) {
+                *n += 1;
+            }
         }
     }
     out_map
@@ -416,23 +1324,39 @@ fn get_number_per_series_map(
 fn get_variable_names(i: &Instance) -> Vec<&String> {
     let mut out_vars = vec![];
     for a in &i.argument_list {
-        if let StottrTerm::Variable(v) = &a.term {
-            out_vars.push(&v.name);
-        } else if let StottrTerm::List(..) = &a.term {
+        push_variable_names(&a.term, &mut out_vars);
+    }
+    out_vars
+}
+
+fn push_variable_names<'a>(term: &'a StottrTerm, out_vars: &mut Vec<&'a String>) {
+    match term {
+        StottrTerm::Variable(v) => {
+            if !out_vars.contains(&&v.name) {
+                out_vars.push(&v.name);
+            }
+        }
+        StottrTerm::Expression(e) => {
+            push_variable_names(&e.left, out_vars);
+            push_variable_names(&e.right, out_vars);
+        }
+        StottrTerm::ConstantTerm(_) => {}
+        StottrTerm::List(..) => {
             todo!();
         }
     }
-    out_vars
 }
 
+//Triples with a value with a unit as object are followed by the unit triples of their subjects
 fn create_triples(
     i: OTTRTripleInstance,
-) -> Result<(DataFrame, RDFNodeType, Option<String>, Option<String>, bool), MappingError> {
+) -> Result<Vec<(DataFrame, RDFNodeType, Option<String>, Option<String>, bool)>, MappingError> {
     let OTTRTripleInstance {
         df,
         mut dynamic_columns,
         static_columns,
         has_unique_subset,
+        ..
     } = i;
 
     let mut expressions = vec![];
@@ -465,17 +1389,38 @@ fn create_triples(
         lf = lf.with_column(e);
     }
 
+    let PrimitiveColumn {
+        rdf_node_type,
+        language_tag,
+        has_language_tag_column,
+        unit,
+    } = dynamic_columns.remove("object").unwrap();
     let mut keep_cols = vec![col("subject"), col("object")];
     if verb.is_none() {
         keep_cols.push(col("verb"));
     }
+    if has_language_tag_column {
+        keep_cols.push(col(&language_tag_column_name("object")).alias(LANGUAGE_TAG_COLUMN));
+    }
     lf = lf.select(keep_cols.as_slice());
     let df = lf.collect().expect("Collect problem");
-    let PrimitiveColumn {
-        rdf_node_type,
-        language_tag,
-    } = dynamic_columns.remove("object").unwrap();
-    Ok((df, rdf_node_type, language_tag, verb, has_unique_subset))
+    let mut triples = vec![];
+    if let Some(UnitAnnotation {
+        unit,
+        mode: UnitMode::UnitTriple,
+    }) = unit
+    {
+        let unit_df = df
+            .clone()
+            .lazy()
+            .filter(col("object").is_not_null())
+            .select([col("subject"), lit(unit.as_str()).alias("object")])
+            .collect()
+            .expect("Collect problem");
+        triples.push((unit_df, RDFNodeType::IRI, None, Some(QUDT_UNIT.to_string()), false));
+    }
+    triples.push((df, rdf_node_type, language_tag, verb, has_unique_subset));
+    Ok(triples)
 }
 
 fn create_dynamic_expression_from_static(
@@ -487,6 +1432,8 @@ fn create_dynamic_expression_from_static(
     let mapped_column = PrimitiveColumn {
         rdf_node_type,
         language_tag,
+        has_language_tag_column: false,
+        unit: None,
     };
     expr = expr.alias(column_name);
     Ok((expr, mapped_column))
@@ -499,6 +1446,7 @@ fn create_remapped(
     dynamic_columns: &HashMap<String, PrimitiveColumn>,
     constant_columns: &HashMap<String, StaticColumn>,
     unique_subsets: &Vec<Vec<String>>,
+    validation: ValidationMode,
 ) -> Result<
     (
         DataFrame,
@@ -514,8 +1462,13 @@ fn create_remapped(
     let mut existing = vec![];
     let mut new = vec![];
     let mut new_dynamic_from_constant = vec![];
+    let mut non_optional = vec![];
+    let is_triple = signature.template_name.as_str() == OTTR_TRIPLE;
     let mut to_expand = vec![];
     let mut expressions = vec![];
+    let mut derived_expressions = vec![];
+    let mut language_tag_aliases = vec![];
+    let mut new_language_tag_columns = vec![];
     for (original, target) in instance
         .argument_list
         .iter()
@@ -525,22 +1478,91 @@ fn create_remapped(
         if original.list_expand {
             to_expand.push(target_colname.clone());
         }
+        let default_term = default_constant_term(target);
         match &original.term {
             StottrTerm::Variable(v) => {
                 if let Some(c) = dynamic_columns.get(&v.name) {
-                    existing.push(&v.name);
+                    if target.non_blank {
+                        validate_non_blank_series(df.column(&v.name).unwrap(), target_colname)?;
+                    }
+                    existing.push(v.name.clone());
                     new.push(target_colname);
                     new_dynamic_columns.insert(target_colname.clone(), c.clone());
+                    if c.has_language_tag_column {
+                        language_tag_aliases.push((
+                            language_tag_column_name(&v.name),
+                            language_tag_column_name(target_colname),
+                        ));
+                        new_language_tag_columns.push(language_tag_column_name(target_colname));
+                    }
+                    //Rows with none for a non-optional parameter are ignored, ottr:Triple drops these when triples are added
+                    if !target.optional && (!is_triple || validation == ValidationMode::Strict) {
+                        non_optional.push(target_colname);
+                    }
+                    if let Some(default_term) = default_term {
+                        let (default_expr, _, _, default_language_tag) =
+                            constant_to_expr(default_term, &None)?;
+                        if let Some(tag) = default_language_tag {
+                            let tag_colname = language_tag_column_name(target_colname);
+                            let existing_tags = if c.has_language_tag_column {
+                                col(&tag_colname)
+                            } else {
+                                new_language_tag_columns.push(tag_colname.clone());
+                                new_dynamic_columns
+                                    .get_mut(target_colname)
+                                    .unwrap()
+                                    .has_language_tag_column = true;
+                                Expr::Literal(LiteralValue::Null).cast(DataType::Utf8)
+                            };
+                            expressions.push(
+                                when(col(target_colname).is_null())
+                                    .then(lit(tag))
+                                    .otherwise(existing_tags)
+                                    .alias(&tag_colname),
+                            );
+                        }
+                        expressions.push(
+                            when(col(target_colname).is_null())
+                                .then(default_expr)
+                                .otherwise(col(target_colname))
+                                .alias(target_colname),
+                        );
+                    }
                 } else if let Some(c) = constant_columns.get(&v.name) {
-                    new_constant_columns.insert(target_colname.clone(), c.clone());
+                    if target.non_blank {
+                        validate_non_blank_constant(&c.constant_term, target_colname)?;
+                    }
+                    let static_column = match default_term {
+                        Some(default_term) if is_none_term(&c.constant_term) => StaticColumn {
+                            constant_term: default_term.clone(),
+                            ptype: None,
+                        },
+                        _ => c.clone(),
+                    };
+                    new_constant_columns.insert(target_colname.clone(), static_column);
                 } else {
                     return Err(MappingError::UnknownVariableError(v.name.clone()));
                 }
             }
+            StottrTerm::ConstantTerm(ct) if is_none_term(ct) && default_term.is_some() => {
+                let static_column = StaticColumn {
+                    constant_term: default_term.unwrap().clone(),
+                    ptype: None,
+                };
+                new_constant_columns.insert(target_colname.clone(), static_column);
+            }
             StottrTerm::ConstantTerm(ct) => {
+                if target.non_blank {
+                    validate_non_blank_constant(ct, target_colname)?;
+                }
                 if original.list_expand {
+                    //The elements of the list must match the type of the parameter
+                    let list_ptype = target
+                        .ptype
+                        .as_ref()
+                        .map(|p| PType::ListType(Box::new(p.clone())));
                     let (expr, primitive_column) =
-                        create_dynamic_expression_from_static(target_colname, ct, &target.ptype)?;
+                        create_dynamic_expression_from_static(target_colname, ct, &list_ptype)?;
                     expressions.push(expr);
                     new_dynamic_columns.insert(target_colname.clone(), primitive_column);
                     new_dynamic_from_constant.push(target_colname);
@@ -552,26 +1574,81 @@ fn create_remapped(
                     new_constant_columns.insert(target_colname.clone(), static_column);
                 }
             }
+            StottrTerm::Expression(_) => {
+                let (expr, rdf_node_type) =
+                    expression_to_expr(&original.term, dynamic_columns, constant_columns)?;
+                //Derived columns are computed before renaming, as they refer to the original names
+                let derived_colname = format!("_:expr:{}", target_colname);
+                derived_expressions.push(expr.alias(&derived_colname));
+                existing.push(derived_colname);
+                new.push(target_colname);
+                let rdf_node_type = if let Some(ptype) = &target.ptype {
+                    infer_rdf_node_type(ptype)
+                } else {
+                    rdf_node_type
+                };
+                new_dynamic_columns.insert(
+                    target_colname.clone(),
+                    PrimitiveColumn {
+                        rdf_node_type,
+                        language_tag: None,
+                        has_language_tag_column: false,
+                        unit: None,
+                    },
+                );
+                if !target.optional && (!is_triple || validation == ValidationMode::Strict) {
+                    non_optional.push(target_colname);
+                }
+            }
             StottrTerm::List(_) => {
                 todo!()
             }
         }
     }
     let mut lf = df.lazy();
+    for expr in derived_expressions {
+        lf = lf.with_column(expr);
+    }
 
+    //Columns are selected under their new names instead of being renamed, as a column may be the argument of several
+    //parameters, and the new name of a column may be the old name of another, e.g. when arguments are swapped
+    let aliased: Vec<Expr> = existing
+        .iter()
+        .zip(new.iter())
+        .map(|(e, n)| col(e).alias(n))
+        .chain(language_tag_aliases.iter().map(|(e, n)| col(e).alias(n)))
+        .collect();
     // TODO: Remove workaround likely bug in Pola.rs 0.25.1
-    lf = lf
-        .rename(existing.as_slice(), new.as_slice())
-        .collect()
-        .unwrap()
-        .lazy();
+    lf = lf.select(aliased.as_slice()).collect().unwrap().lazy();
 
     for expr in expressions {
         lf = lf.with_column(expr);
     }
+    if !non_optional.is_empty() {
+        if validation == ValidationMode::Strict {
+            let df = lf.collect().expect("Collect problem");
+            for c in &non_optional {
+                let is_null = df.column(c).unwrap().is_null();
+                if is_null.any() {
+                    let i = new.iter().position(|n| n == c).unwrap();
+                    return Err(MappingError::NullArgumentForNonOptionalParameter(
+                        existing[i].clone(),
+                        signature.template_name.as_str().to_string(),
+                        c.to_string(),
+                        df.filter(&is_null).unwrap(),
+                    ));
+                }
+            }
+            lf = df.lazy();
+        } else {
+            lf = lf.drop_nulls(Some(non_optional.iter().map(|x| col(x)).collect()));
+        }
+    }
     let new_column_expressions: Vec<Expr> = new
         .iter()
         .chain(new_dynamic_from_constant.iter())
+        .map(|x| *x)
+        .chain(new_language_tag_columns.iter())
         .map(|x| col(x))
         .collect();
     lf = lf.select(new_column_expressions.as_slice());
@@ -596,11 +1673,11 @@ fn create_remapped(
         //Todo: List expanders for constant terms..
     } else {
         for unique_subset in unique_subsets {
-            if unique_subset.iter().all(|x| existing.contains(&x)) {
+            if unique_subset.iter().all(|x| existing.contains(x)) {
                 let mut new_subset = vec![];
                 for x in unique_subset.iter() {
                     new_subset.push(
-                        new.get(existing.iter().position(|e| e == &x).unwrap())
+                        new.get(existing.iter().position(|e| e == x).unwrap())
                             .unwrap()
                             .to_string(),
                     );
@@ -622,6 +1699,115 @@ fn create_remapped(
     ))
 }
 
+//Compiles an expression argument to a Polars expression over the columns of the calling template
+fn expression_to_expr(
+    term: &StottrTerm,
+    dynamic_columns: &HashMap<String, PrimitiveColumn>,
+    constant_columns: &HashMap<String, StaticColumn>,
+) -> Result<(Expr, RDFNodeType), MappingError> {
+    match term {
+        StottrTerm::Variable(v) => {
+            if let Some(c) = dynamic_columns.get(&v.name) {
+                Ok((col(&v.name), c.rdf_node_type.clone()))
+            } else if let Some(c) = constant_columns.get(&v.name) {
+                let (expr, _, rdf_node_type, _) = constant_to_expr(&c.constant_term, &c.ptype)?;
+                Ok((expr, rdf_node_type))
+            } else {
+                Err(MappingError::UnknownVariableError(v.name.clone()))
+            }
+        }
+        StottrTerm::ConstantTerm(ct) => {
+            let (expr, _, rdf_node_type, _) = constant_to_expr(ct, &None)?;
+            Ok((expr, rdf_node_type))
+        }
+        StottrTerm::Expression(e) => {
+            let (left, left_type) = expression_to_expr(&e.left, dynamic_columns, constant_columns)?;
+            let (right, right_type) =
+                expression_to_expr(&e.right, dynamic_columns, constant_columns)?;
+            Ok(match e.operator {
+                ExpressionOperator::Concat => (
+                    concat_str([left.cast(DataType::Utf8), right.cast(DataType::Utf8)], ""),
+                    RDFNodeType::Literal(xsd::STRING.into_owned()),
+                ),
+                ExpressionOperator::Divide => (
+                    left.cast(DataType::Float64) / right.cast(DataType::Float64),
+                    RDFNodeType::Literal(xsd::DOUBLE.into_owned()),
+                ),
+                op => {
+                    let is_floating = |t: &RDFNodeType| {
+                        t.is_float()
+                            || t.is_lit_type(xsd::DOUBLE)
+                            || t.is_lit_type(xsd::DECIMAL)
+                    };
+                    let (left, right, rdf_node_type) =
+                        if is_floating(&left_type) || is_floating(&right_type) {
+                            (
+                                left.cast(DataType::Float64),
+                                right.cast(DataType::Float64),
+                                RDFNodeType::Literal(xsd::DOUBLE.into_owned()),
+                            )
+                        } else {
+                            (left, right, left_type)
+                        };
+                    let expr = match op {
+                        ExpressionOperator::Add => left + right,
+                        ExpressionOperator::Subtract => left - right,
+                        _ => left * right,
+                    };
+                    (expr, rdf_node_type)
+                }
+            })
+        }
+        StottrTerm::List(_) => Err(MappingError::UnsupportedExpressionTerm(term.clone())),
+    }
+}
+
+//An instance is ignored when none is given to a non-optional parameter without a default value
+fn is_ignored_instance(
+    instance: &Instance,
+    signature: &Signature,
+    constant_columns: &HashMap<String, StaticColumn>,
+) -> bool {
+    if signature.template_name.as_str() == OTTR_TRIPLE {
+        return false;
+    }
+    for (original, target) in instance
+        .argument_list
+        .iter()
+        .zip(signature.parameter_list.iter())
+    {
+        if target.optional || default_constant_term(target).is_some() {
+            continue;
+        }
+        let is_none = match &original.term {
+            StottrTerm::Variable(v) => constant_columns
+                .get(&v.name)
+                .map(|c| is_none_term(&c.constant_term))
+                .unwrap_or(false),
+            StottrTerm::ConstantTerm(ct) => is_none_term(ct),
+            StottrTerm::List(_) | StottrTerm::Expression(_) => false,
+        };
+        if is_none {
+            return true;
+        }
+    }
+    false
+}
+
+//Arguments that are none are replaced by the default value of the parameter, if it has one
+fn default_constant_term(parameter: &Parameter) -> Option<&ConstantTerm> {
+    if let Some(default_value) = &parameter.default_value {
+        if !is_none_term(&default_value.constant_term) {
+            return Some(&default_value.constant_term);
+        }
+    }
+    None
+}
+
+fn is_none_term(constant_term: &ConstantTerm) -> bool {
+    matches!(constant_term, ConstantTerm::Constant(ConstantLiteral::None))
+}
+
 //From: https://users.rust-lang.org/t/flatten-a-vec-vec-t-to-a-vec-t/24526/3
 fn flatten<T>(nested: Vec<Vec<T>>) -> Vec<T> {
     nested.into_iter().flatten().collect()
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+/// The stages of an expansion that use the most memory and CPU.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpansionStage {
+    /// Instantiating the template with a chunk of rows.
+    Expand,
+    /// Adding the triples from a chunk of rows to the triplestore.
+    StoreTriples,
+}
+
+/// Hook that is called before and after the heavy stages of an expansion,
+/// so that services embedding the mapper can limit how much work runs at once.
+pub trait BackpressureHook: Send + Sync {
+    /// Blocks until the stage may run on the given number of rows, e.g. by acquiring a semaphore permit.
+    /// Returning false stops the expansion, keeping the triples stored so far.
+    fn before_stage(&self, stage: &ExpansionStage, rows: usize) -> bool;
+
+    /// Called when a stage that was allowed to run is done, e.g. to release a permit.
+    fn after_stage(&self, _stage: &ExpansionStage, _rows: usize) {}
+}
+
+pub(crate) fn before_stage(
+    hook: &Option<Arc<dyn BackpressureHook>>,
+    stage: &ExpansionStage,
+    rows: usize,
+) -> bool {
+    if let Some(hook) = hook {
+        hook.before_stage(stage, rows)
+    } else {
+        true
+    }
+}
+
+pub(crate) fn after_stage(
+    hook: &Option<Arc<dyn BackpressureHook>>,
+    stage: &ExpansionStage,
+    rows: usize,
+) {
+    if let Some(hook) = hook {
+        hook.after_stage(stage, rows)
+    }
+}
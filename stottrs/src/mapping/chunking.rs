@@ -0,0 +1,67 @@
+use polars_core::frame::DataFrame;
+
+/// How a DataFrame is split into chunks that are expanded or written one at a time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChunkPolicy {
+    /// Chunks of at most this many rows.
+    Rows(usize),
+    /// Chunks of about this many bytes, using the estimated size of the DataFrame.
+    Bytes(usize),
+}
+
+impl ChunkPolicy {
+    /// The offset and length of each chunk of a DataFrame with the height and estimated size.
+    /// The chunks cover all rows without overlapping, and there is a single empty chunk if there are no rows.
+    pub fn chunk_ranges(&self, height: usize, estimated_size: usize) -> Vec<(usize, usize)> {
+        let chunk_rows = match self {
+            ChunkPolicy::Rows(rows) => *rows,
+            ChunkPolicy::Bytes(bytes) => {
+                let n_chunks = estimated_size / (*bytes).max(1) + 1;
+                //Rounding up, so that there is no small chunk with the remaining rows
+                height / n_chunks + usize::from(height % n_chunks != 0)
+            }
+        }
+        .max(1);
+        if height == 0 {
+            return vec![(0, 0)];
+        }
+        (0..height)
+            .step_by(chunk_rows)
+            .map(|offset| (offset, chunk_rows.min(height - offset)))
+            .collect()
+    }
+
+    pub(crate) fn split(&self, df: &DataFrame) -> Vec<DataFrame> {
+        self.chunk_ranges(df.height(), df.estimated_size())
+            .into_iter()
+            .map(|(offset, len)| df.slice_par(offset as i64, len))
+            .collect()
+    }
+}
+
+#[test]
+fn test_chunk_ranges_by_rows() {
+    assert_eq!(
+        ChunkPolicy::Rows(4).chunk_ranges(10, 0),
+        vec![(0, 4), (4, 4), (8, 2)]
+    );
+    assert_eq!(ChunkPolicy::Rows(5).chunk_ranges(10, 0), vec![(0, 5), (5, 5)]);
+    assert_eq!(ChunkPolicy::Rows(20).chunk_ranges(10, 0), vec![(0, 10)]);
+    assert_eq!(ChunkPolicy::Rows(0).chunk_ranges(2, 0), vec![(0, 1), (1, 1)]);
+    assert_eq!(ChunkPolicy::Rows(4).chunk_ranges(0, 0), vec![(0, 0)]);
+}
+
+#[test]
+fn test_chunk_ranges_by_bytes() {
+    assert_eq!(ChunkPolicy::Bytes(100).chunk_ranges(10, 50), vec![(0, 10)]);
+    assert_eq!(
+        ChunkPolicy::Bytes(100).chunk_ranges(10, 250),
+        vec![(0, 4), (4, 4), (8, 2)]
+    );
+    //More chunks than rows gives chunks of a single row
+    assert_eq!(
+        ChunkPolicy::Bytes(1).chunk_ranges(3, 1000),
+        vec![(0, 1), (1, 1), (2, 1)]
+    );
+    assert_eq!(ChunkPolicy::Bytes(0).chunk_ranges(2, 10), vec![(0, 1), (1, 1)]);
+}
@@ -0,0 +1,150 @@
+use crate::ast::{PType, Signature};
+use crate::mapping::errors::MappingError;
+use crate::mapping::{Coercion, CoercionPolicy};
+use crate::triplestore::custom_datatypes::CustomDatatype;
+use oxrdf::vocab::{rdf, xsd};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{DataType, TimeUnit};
+use std::collections::HashMap;
+
+pub(crate) fn coerce_columns(
+    mut df: DataFrame,
+    signature: &Signature,
+    coercion: &Coercion,
+) -> Result<DataFrame, MappingError> {
+    for parameter in &signature.parameter_list {
+        let column_name = &parameter.stottr_variable.name;
+        let policy = coercion
+            .columns
+            .get(column_name)
+            .unwrap_or(&coercion.policy);
+        if *policy == CoercionPolicy::Never {
+            continue;
+        }
+        let (series, ptype) = match (df.column(column_name), &parameter.ptype) {
+            (Ok(series), Some(ptype)) => (series, ptype),
+            _ => continue,
+        };
+        let target = if let Some(target) = ptype_to_polars_datatype(ptype) {
+            target
+        } else {
+            continue;
+        };
+        if matches_datatype(series.dtype(), &target) {
+            continue;
+        }
+        let cast = series.strict_cast(&target).map_err(|_| {
+            MappingError::ColumnCoercionFailed(
+                column_name.clone(),
+                series.dtype().clone(),
+                ptype.clone(),
+            )
+        })?;
+        df.with_column(cast).unwrap();
+    }
+    Ok(df)
+}
+
+//Columns of parameters with a registered datatype are stored as the dtype of the datatype
+pub(crate) fn cast_custom_datatypes(
+    mut df: DataFrame,
+    signature: &Signature,
+    custom_datatypes: &HashMap<String, CustomDatatype>,
+) -> Result<DataFrame, MappingError> {
+    if custom_datatypes.is_empty() {
+        return Ok(df);
+    }
+    for parameter in &signature.parameter_list {
+        let column_name = &parameter.stottr_variable.name;
+        let (series, ptype, custom_datatype) = match (df.column(column_name), &parameter.ptype) {
+            (Ok(series), Some(ptype @ PType::BasicType(b, _))) => {
+                if let Some(custom_datatype) = custom_datatypes.get(b.as_str()) {
+                    (series, ptype, custom_datatype)
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        if series.dtype() == &custom_datatype.dtype {
+            continue;
+        }
+        let cast = series.strict_cast(&custom_datatype.dtype).map_err(|_| {
+            MappingError::ColumnCoercionFailed(
+                column_name.clone(),
+                series.dtype().clone(),
+                ptype.clone(),
+            )
+        })?;
+        df.with_column(cast).unwrap();
+    }
+    Ok(df)
+}
+
+//Datetimes and durations of any time unit are accepted
+fn matches_datatype(datatype: &DataType, target: &DataType) -> bool {
+    match (datatype, target) {
+        (DataType::Datetime(_, tz), DataType::Datetime(_, target_tz)) => {
+            tz.is_some() == target_tz.is_some()
+        }
+        (DataType::Duration(_), DataType::Duration(_)) => true,
+        (DataType::Categorical(_), DataType::Utf8) => true,
+        (DataType::List(inner), DataType::List(target_inner)) => {
+            matches_datatype(inner, target_inner)
+        }
+        _ => datatype == target,
+    }
+}
+
+//Types that have no natural polars data type, such as custom datatypes, are not coerced
+fn ptype_to_polars_datatype(ptype: &PType) -> Option<DataType> {
+    match ptype {
+        PType::BasicType(b, _) => {
+            let b = b.as_ref();
+            let datatype = if b == xsd::BOOLEAN {
+                DataType::Boolean
+            } else if b == xsd::UNSIGNED_BYTE {
+                DataType::UInt8
+            } else if b == xsd::UNSIGNED_SHORT {
+                DataType::UInt16
+            } else if b == xsd::UNSIGNED_INT {
+                DataType::UInt32
+            } else if b == xsd::UNSIGNED_LONG
+                || b == xsd::NON_NEGATIVE_INTEGER
+                || b == xsd::POSITIVE_INTEGER
+            {
+                DataType::UInt64
+            } else if b == xsd::BYTE {
+                DataType::Int8
+            } else if b == xsd::SHORT {
+                DataType::Int16
+            } else if b == xsd::INT {
+                DataType::Int32
+            } else if b == xsd::LONG
+                || b == xsd::INTEGER
+                || b == xsd::NON_POSITIVE_INTEGER
+                || b == xsd::NEGATIVE_INTEGER
+            {
+                DataType::Int64
+            } else if b == xsd::FLOAT {
+                DataType::Float32
+            } else if b == xsd::DOUBLE || b == xsd::DECIMAL {
+                DataType::Float64
+            } else if b == xsd::STRING || b == xsd::ANY_URI || b == rdf::LANG_STRING {
+                DataType::Utf8
+            } else if b == xsd::DATE {
+                DataType::Date
+            } else if b == xsd::DATE_TIME {
+                DataType::Datetime(TimeUnit::Microseconds, None)
+            } else if b == xsd::DURATION {
+                DataType::Duration(TimeUnit::Microseconds)
+            } else {
+                return None;
+            };
+            Some(datatype)
+        }
+        PType::LUBType(inner) | PType::ListType(inner) | PType::NEListType(inner) => {
+            ptype_to_polars_datatype(inner).map(|dt| DataType::List(Box::new(dt)))
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::{PrimitiveColumn, StaticColumn};
+use crate::ast::{ConstantLiteral, ConstantTerm, Signature};
+use crate::constants::RESERVED_COLUMN_PREFIX;
+use crate::mapping::language_tag_columns::language_tag_column_name;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::IntoSeries;
+use std::collections::HashMap;
+
+pub(super) struct Cohort {
+    pub(super) df: DataFrame,
+    pub(super) dynamic_columns: HashMap<String, PrimitiveColumn>,
+    pub(super) static_columns: HashMap<String, StaticColumn>,
+}
+
+//Splits the rows by which columns of optional parameters are null. The columns that are null in a cohort
+//are given as none arguments, so that the instances ignoring them are skipped.
+pub(super) fn optional_cohorts(
+    df: DataFrame,
+    signature: &Signature,
+    dynamic_columns: HashMap<String, PrimitiveColumn>,
+) -> Vec<Cohort> {
+    let optional_columns: Vec<&String> = signature
+        .parameter_list
+        .iter()
+        .filter(|p| p.optional && dynamic_columns.contains_key(&p.stottr_variable.name))
+        .map(|p| &p.stottr_variable.name)
+        .collect();
+    //The rows are grouped on a column telling whether each optional column is null
+    let mask_columns: Vec<String> = (0..optional_columns.len())
+        .map(|i| format!("{}is_null_{}", RESERVED_COLUMN_PREFIX, i))
+        .collect();
+    let mut masked_df = df.clone();
+    let mut any_null = false;
+    for (c, m) in optional_columns.iter().zip(mask_columns.iter()) {
+        let mut mask = df.column(c).unwrap().is_null();
+        any_null = any_null || mask.any();
+        mask.rename(m);
+        masked_df.with_column(mask.into_series()).unwrap();
+    }
+    if !any_null {
+        return vec![Cohort {
+            df,
+            dynamic_columns,
+            static_columns: HashMap::new(),
+        }];
+    }
+
+    let mut cohorts = vec![];
+    for mut cohort_df in masked_df.partition_by_stable(mask_columns.clone()).unwrap() {
+        let key: Vec<bool> = mask_columns
+            .iter()
+            .map(|m| cohort_df.column(m).unwrap().bool().unwrap().get(0).unwrap())
+            .collect();
+        for m in &mask_columns {
+            let _ = cohort_df.drop_in_place(m).unwrap();
+        }
+        let mut cohort_dynamic_columns = dynamic_columns.clone();
+        let mut static_columns = HashMap::new();
+        let null_columns: Vec<&String> = optional_columns
+            .iter()
+            .zip(key.iter())
+            .filter(|(_, is_null)| **is_null)
+            .map(|(c, _)| *c)
+            .collect();
+        //A data frame without columns has no rows, so the last column is kept
+        if null_columns.len() == cohort_df.width() {
+            cohorts.push(Cohort {
+                df: cohort_df,
+                dynamic_columns: cohort_dynamic_columns,
+                static_columns,
+            });
+            continue;
+        }
+        for c in null_columns {
+            let column = cohort_dynamic_columns.remove(c).unwrap();
+            let _ = cohort_df.drop_in_place(c).unwrap();
+            if column.has_language_tag_column {
+                let _ = cohort_df.drop_in_place(&language_tag_column_name(c)).unwrap();
+            }
+            static_columns.insert(
+                c.clone(),
+                StaticColumn {
+                    constant_term: ConstantTerm::Constant(ConstantLiteral::None),
+                    ptype: None,
+                },
+            );
+        }
+        cohorts.push(Cohort {
+            df: cohort_df,
+            dynamic_columns: cohort_dynamic_columns,
+            static_columns,
+        });
+    }
+    cohorts
+}
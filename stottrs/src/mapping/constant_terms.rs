@@ -1,9 +1,9 @@
 use std::ops::Deref;
 use oxrdf::NamedNode;
-use oxrdf::vocab::xsd;
+use oxrdf::vocab::{rdf, xsd};
 use polars::prelude::{concat_lst, Expr, LiteralValue, SpecialEq};
 use polars_core::datatypes::{DataType};
-use polars_core::prelude::{AnyValue, IntoSeries, Series};
+use polars_core::prelude::{AnyValue, IntoSeries, NamedFrom, Series};
 use crate::ast::{ConstantLiteral, ConstantTerm, PType};
 use crate::constants::{BLANK_NODE_IRI, NONE_IRI};
 use crate::mapping::errors::MappingError;
@@ -23,7 +23,7 @@ pub fn constant_to_expr(
                 None,
             ),
             ConstantLiteral::BlankNode(bn) => (
-                Expr::Literal(LiteralValue::Utf8(bn.as_str().to_string())),
+                Expr::Literal(LiteralValue::Utf8(format!("_:{}", bn.as_str()))),
                 PType::BasicType(NamedNode::new_unchecked(BLANK_NODE_IRI), BLANK_NODE_IRI.to_string()),
                 RDFNodeType::BlankNode,
                 None
@@ -44,9 +44,15 @@ pub fn constant_to_expr(
                 } else {
                     None
                 };
+                //Language tagged literals have the type rdf:langString, and are stored as strings with a language tag
+                let ptype = if language_tag.is_some() {
+                    PType::BasicType(rdf::LANG_STRING.into_owned(), "rdf:langString".to_string())
+                } else {
+                    PType::BasicType(lit.data_type_iri.as_ref().unwrap().clone(), lit.data_type_iri.as_ref().unwrap().to_string())
+                };
                 (
                     Expr::Literal(LiteralValue::Series(SpecialEq::new(value_series))),
-                    PType::BasicType(lit.data_type_iri.as_ref().unwrap().clone(), lit.data_type_iri.as_ref().unwrap().to_string()),
+                    ptype,
                     RDFNodeType::Literal(dt),
                     language_tag
                 )
@@ -62,19 +68,27 @@ pub fn constant_to_expr(
             let mut expressions = vec![];
             let mut last_ptype = None;
             let mut last_rdf_node_type = None;
+            let mut last_language_tag = None;
+            let inner_ptype_opt = match ptype_opt {
+                Some(PType::ListType(p)) | Some(PType::NEListType(p)) => Some(p.deref().clone()),
+                _ => None,
+            };
             for ct in inner {
-                let (constant_expr, actual_ptype, rdf_node_type, language_tag) = constant_to_expr(ct, ptype_opt)?;
-                if language_tag.is_some() {
-                    todo!()
-                }
+                let (constant_expr, actual_ptype, rdf_node_type, language_tag) = constant_to_expr(ct, &inner_ptype_opt)?;
                 if last_ptype.is_none() {
                     last_ptype = Some(actual_ptype);
-                } else if last_ptype.as_ref().unwrap() != &actual_ptype {
+                    last_language_tag = language_tag;
+                } else if !same_ptype(last_ptype.as_ref().unwrap(), &actual_ptype) {
                     return Err(MappingError::ConstantListHasInconsistentPType(
                         constant_term.clone(),
                         last_ptype.as_ref().unwrap().clone(),
                         actual_ptype.clone(),
                     ));
+                } else if last_language_tag != language_tag {
+                    //The language tag is stored per column, so the elements of a list must share it
+                    return Err(MappingError::ConstantListHasInconsistentLanguageTags(
+                        constant_term.clone(),
+                    ));
                 }
                 last_rdf_node_type = Some(rdf_node_type);
                 expressions.push(constant_expr);
@@ -100,6 +114,28 @@ pub fn constant_to_expr(
                     first.append(s).unwrap();
                 }
                 let out_series = first.to_list().unwrap().into_series();
+                (
+                    Expr::Literal(LiteralValue::Series(SpecialEq::new(out_series))),
+                    out_ptype,
+                    out_rdf_node_type,
+                    last_language_tag
+                )
+            } else if expressions
+                .iter()
+                .all(|ex| matches!(ex, Expr::Literal(LiteralValue::Utf8(_))))
+            {
+                //IRIs and blank nodes, e.g. a list of predicates
+                let values: Vec<&str> = expressions
+                    .iter()
+                    .map(|ex| {
+                        if let Expr::Literal(LiteralValue::Utf8(s)) = ex {
+                            s.as_str()
+                        } else {
+                            panic!("Should never happen")
+                        }
+                    })
+                    .collect();
+                let out_series = Series::new("literal", values).to_list().unwrap().into_series();
                 (
                     Expr::Literal(LiteralValue::Series(SpecialEq::new(out_series))),
                     out_ptype,
@@ -112,7 +148,12 @@ pub fn constant_to_expr(
         }
     };
     if let Some(ptype_in) = ptype_opt {
-        if ptype_in != &ptype {
+        let ptype_in = if let PType::NEListType(p) = ptype_in {
+            PType::ListType(p.clone())
+        } else {
+            ptype_in.clone()
+        };
+        if !same_ptype(&ptype_in, &ptype) {
             return Err(MappingError::ConstantDoesNotMatchDataType(
                 constant_term.clone(),
                 ptype_in.clone(),
@@ -121,4 +162,15 @@ pub fn constant_to_expr(
         }
     }
     Ok((expr, ptype, rdf_node_type, language_tag))
-}
\ No newline at end of file
+}
+
+//The names of the types are how they were written, e.g. xsd:string or the full IRI, so only the IRIs are compared
+pub(crate) fn same_ptype(left: &PType, right: &PType) -> bool {
+    match (left, right) {
+        (PType::BasicType(l, _), PType::BasicType(r, _)) => l == r,
+        (PType::LUBType(l), PType::LUBType(r))
+        | (PType::ListType(l), PType::ListType(r))
+        | (PType::NEListType(l), PType::NEListType(r)) => same_ptype(l, r),
+        _ => false,
+    }
+}
@@ -0,0 +1,144 @@
+use crate::constants::OTTR_TRIPLE;
+use crate::templates::TemplateDataset;
+use crate::triplestore::LANGUAGE_TAG_COLUMN;
+use polars_core::prelude::DataFrame;
+
+/// Which templates and which instances in their pattern lists produced triples in an expand call.
+/// Templates and instances that produced no triples, e.g. because the rows had nulls for their parameters,
+/// are silently dead branches of the mapping for the input.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Coverage {
+    pub templates: Vec<TemplateCoverage>,
+    pub patterns: Vec<PatternCoverage>,
+}
+
+/// Triples produced by a template, counting every use of the template in the call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateCoverage {
+    pub template: String,
+    pub triples: usize,
+}
+
+/// Triples produced by an instance in the pattern list of a template, counting every use of the template in the call.
+/// Triples are counted before duplicates are removed, rows with nulls are not counted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternCoverage {
+    pub template: String,
+    /// The position of the instance in the pattern list.
+    pub pattern_index: usize,
+    /// The template the instance calls.
+    pub instance_template: String,
+    pub triples: usize,
+}
+
+impl Coverage {
+    //All templates used by the template, and all instances in their pattern lists, with no triples
+    pub(crate) fn new(template_dataset: &TemplateDataset, template_name: &str) -> Coverage {
+        let mut coverage = Coverage::default();
+        let mut visited = vec![template_name.to_string()];
+        let mut queue = vec![template_name.to_string()];
+        while let Some(name) = queue.pop() {
+            if name == OTTR_TRIPLE {
+                continue;
+            }
+            if let Some(template) = template_dataset.get(&name) {
+                coverage.templates.push(TemplateCoverage {
+                    template: name.clone(),
+                    triples: 0,
+                });
+                for (pattern_index, i) in template.pattern_list.iter().enumerate() {
+                    let used = i.template_name.as_str().to_string();
+                    coverage.patterns.push(PatternCoverage {
+                        template: name.clone(),
+                        pattern_index,
+                        instance_template: used.clone(),
+                        triples: 0,
+                    });
+                    if !visited.contains(&used) {
+                        visited.push(used.clone());
+                        queue.push(used);
+                    }
+                }
+            }
+        }
+        coverage.sort();
+        coverage
+    }
+
+    /// The templates that produced no triples.
+    pub fn dead_templates(&self) -> Vec<&TemplateCoverage> {
+        self.templates.iter().filter(|t| t.triples == 0).collect()
+    }
+
+    /// The instances in pattern lists that produced no triples.
+    pub fn dead_patterns(&self) -> Vec<&PatternCoverage> {
+        self.patterns.iter().filter(|p| p.triples == 0).collect()
+    }
+
+    //The pattern path has the template and the position in its pattern list of each instance leading to the triples
+    pub(crate) fn count_triples(&mut self, pattern_path: &[(String, usize)], triples: usize) {
+        let mut counted_templates: Vec<&String> = vec![];
+        for (template, pattern_index) in pattern_path {
+            if let Some(p) = self
+                .patterns
+                .iter_mut()
+                .find(|p| &p.template == template && p.pattern_index == *pattern_index)
+            {
+                p.triples += triples;
+            }
+            //A template is counted once even if it is used again further down the path
+            if !counted_templates.contains(&template) {
+                counted_templates.push(template);
+                if let Some(t) = self.templates.iter_mut().find(|t| &t.template == template) {
+                    t.triples += triples;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn merge(&mut self, other: Coverage) {
+        for t in other.templates {
+            if let Some(existing) = self.templates.iter_mut().find(|x| x.template == t.template) {
+                existing.triples += t.triples;
+            } else {
+                self.templates.push(t);
+            }
+        }
+        for p in other.patterns {
+            if let Some(existing) = self
+                .patterns
+                .iter_mut()
+                .find(|x| x.template == p.template && x.pattern_index == p.pattern_index)
+            {
+                existing.triples += p.triples;
+            } else {
+                self.patterns.push(p);
+            }
+        }
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.templates.sort_by(|x, y| x.template.cmp(&y.template));
+        self.patterns.sort_by(|x, y| {
+            x.template
+                .cmp(&y.template)
+                .then(x.pattern_index.cmp(&y.pattern_index))
+        });
+    }
+}
+
+//Rows of the triples with no nulls, as rows with nulls are dropped when the triples are added
+pub(crate) fn triple_rows(df: &DataFrame) -> usize {
+    let mask = df
+        .get_columns()
+        .iter()
+        .filter(|c| c.name() != LANGUAGE_TAG_COLUMN)
+        .map(|c| c.is_not_null())
+        .reduce(|a, b| &a & &b);
+    if let Some(mask) = mask {
+        (&mask).into_iter().filter(|b| *b == Some(true)).count()
+    } else {
+        df.height()
+    }
+}
@@ -8,7 +8,6 @@ use oxrdf::{NamedNode};
 use polars::prelude::{col, IntoLazy};
 use polars_core::frame::DataFrame;
 use polars_core::prelude::DataType;
-use uuid::Uuid;
 use crate::mapping::ExpandOptions;
 
 impl Mapping {
@@ -107,12 +106,14 @@ impl Mapping {
                     prefixed_template_name: "ottr:Triple".to_string(),
                     argument_list: vec![
                         Argument {
+                            parameter: None,
                             list_expand: false,
                             term: StottrTerm::Variable(StottrVariable {
                                 name: pk_col.clone(),
                             }),
                         },
                         Argument {
+                            parameter: None,
                             list_expand: false,
                             term: StottrTerm::ConstantTerm(ConstantTerm::Constant(
                                 ConstantLiteral::IRI(
@@ -121,6 +122,7 @@ impl Mapping {
                             )),
                         },
                         Argument {
+                            parameter: None,
                             list_expand: list_expander.is_some(),
                             term: StottrTerm::Variable(StottrVariable { name: c.clone() }),
                         },
@@ -129,7 +131,7 @@ impl Mapping {
             }
         }
 
-        let template_uuid = Uuid::new_v4().to_string();
+        let template_uuid = self.new_uuid().to_string();
         let template_name =format!(
                     "{}{}",use_template_prefix,
                     &template_uuid
@@ -143,7 +145,7 @@ impl Mapping {
             },
             pattern_list: patterns,
         };
-        self.template_dataset.templates.push(template.clone());
+        self.upsert_template(template.clone())?;
         self.expand(template_name.as_str(), df, options)?;
         Ok(template)
     }
@@ -0,0 +1,87 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::{DerivedId, DerivedIdFormat};
+use polars_core::prelude::{DataFrame, DataType, IntoSeries, Utf8Chunked};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const CROCKFORD_BASE32: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+//Separates the values of the columns so that e.g. ("ab", "c") and ("a", "bc") give different ids
+const VALUE_SEPARATOR: char = '\u{1f}';
+
+pub(crate) fn add_derived_id_columns(
+    mut df: DataFrame,
+    derived_ids: &HashMap<String, DerivedId>,
+) -> Result<DataFrame, MappingError> {
+    let mut parameters: Vec<&String> = derived_ids.keys().collect();
+    parameters.sort();
+    for parameter in parameters {
+        if df.get_column_names().contains(&parameter.as_str()) {
+            return Err(MappingError::DerivedIdColumnExists(parameter.clone()));
+        }
+        let derived_id = derived_ids.get(parameter).unwrap();
+        let mut source_series = vec![];
+        for c in &derived_id.from_columns {
+            let series = df.column(c).map_err(|_| {
+                MappingError::MissingDerivedIdSourceColumn(parameter.clone(), c.clone())
+            })?;
+            let series = match series.dtype() {
+                DataType::List(_) | DataType::Struct(_) => {
+                    return Err(MappingError::DerivedIdSourceColumnNotSupported(
+                        c.clone(),
+                        series.dtype().clone(),
+                    ))
+                }
+                _ => series.cast(&DataType::Utf8).map_err(|_| {
+                    MappingError::DerivedIdSourceColumnNotSupported(
+                        c.clone(),
+                        series.dtype().clone(),
+                    )
+                })?,
+            };
+            source_series.push(series);
+        }
+        let mut source_iters: Vec<_> = source_series
+            .iter()
+            .map(|s| s.utf8().unwrap().into_iter())
+            .collect();
+        let mut ids: Utf8Chunked = (0..df.height())
+            .map(|_| {
+                let mut name = derived_id.prefix.clone();
+                let mut has_null = false;
+                for values in source_iters.iter_mut() {
+                    name.push(VALUE_SEPARATOR);
+                    match values.next().unwrap() {
+                        Some(v) => name.push_str(v),
+                        None => has_null = true,
+                    }
+                }
+                if has_null {
+                    return None;
+                }
+                Some(format!(
+                    "{}{}",
+                    derived_id.prefix,
+                    derived_id_string(&name, &derived_id.format)
+                ))
+            })
+            .collect();
+        ids.rename(parameter);
+        df.with_column(ids.into_series()).unwrap();
+    }
+    Ok(df)
+}
+
+fn derived_id_string(name: &str, format: &DerivedIdFormat) -> String {
+    let uuid = Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes());
+    match format {
+        DerivedIdFormat::Uuid => uuid.hyphenated().to_string(),
+        DerivedIdFormat::Ulid => encode_ulid(uuid.as_u128()),
+    }
+}
+
+//ULIDs are 128 bits in 26 characters of 5 bits, the first character has the 3 most significant bits
+fn encode_ulid(value: u128) -> String {
+    (0..26)
+        .map(|i| CROCKFORD_BASE32[((value >> (125 - 5 * i)) & 0x1f) as usize] as char)
+        .collect()
+}
@@ -0,0 +1,85 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::DuplicateColumns;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::Series;
+use std::collections::{HashMap, HashSet};
+
+//Data frames from other libraries are not necessarily checked for duplicate names, so this is done before expanding
+pub(crate) fn resolve_duplicate_columns(
+    df: DataFrame,
+    duplicate_columns: &DuplicateColumns,
+) -> Result<DataFrame, MappingError> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for s in df.get_columns() {
+        *counts.entry(s.name()).or_insert(0) += 1;
+    }
+    let mut duplicated: Vec<String> = df
+        .get_columns()
+        .iter()
+        .map(|s| s.name())
+        .filter(|n| *counts.get(n).unwrap() > 1)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .map(|n| n.to_string())
+        .collect();
+    if duplicated.is_empty() {
+        return Ok(df);
+    }
+    duplicated.sort();
+    let columns = match duplicate_columns {
+        DuplicateColumns::Error => return Err(MappingError::DuplicateColumns(duplicated)),
+        DuplicateColumns::Suffix => {
+            let mut names: HashSet<String> = counts.keys().map(|n| n.to_string()).collect();
+            let mut seen = HashSet::new();
+            let mut columns = vec![];
+            for s in df.get_columns() {
+                let mut s = s.clone();
+                if !seen.insert(s.name().to_string()) {
+                    let name = s.name().to_string();
+                    let mut i = 1;
+                    while names.contains(&format!("{}_{}", name, i)) {
+                        i += 1;
+                    }
+                    let new_name = format!("{}_{}", name, i);
+                    s.rename(&new_name);
+                    names.insert(new_name);
+                }
+                columns.push(s);
+            }
+            columns
+        }
+        DuplicateColumns::Select(selection) => {
+            let unselected: Vec<String> = duplicated
+                .iter()
+                .filter(|n| !selection.contains_key(*n))
+                .cloned()
+                .collect();
+            if !unselected.is_empty() {
+                return Err(MappingError::DuplicateColumns(unselected));
+            }
+            for (name, selected) in selection {
+                if let Some(count) = counts.get(name.as_str()) {
+                    if selected >= count {
+                        return Err(MappingError::InvalidDuplicateColumnSelection(
+                            name.clone(),
+                            *selected,
+                            *count,
+                        ));
+                    }
+                }
+            }
+            let mut positions: HashMap<String, usize> = HashMap::new();
+            let mut columns: Vec<Series> = vec![];
+            for s in df.get_columns() {
+                let name = s.name().to_string();
+                let position = positions.entry(name.clone()).or_insert(0);
+                if selection.get(&name).map(|selected| *selected == *position).unwrap_or(true) {
+                    columns.push(s.clone());
+                }
+                *position += 1;
+            }
+            columns
+        }
+    };
+    Ok(DataFrame::new(columns).unwrap())
+}
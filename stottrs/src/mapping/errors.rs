@@ -1,4 +1,6 @@
-use crate::ast::{ConstantTerm, PType};
+use crate::ast::{ConstantTerm, PType, StottrTerm};
+use crate::templates::TypingError;
+use crate::triplestore::harmonize::SchemaMismatch;
 use oxrdf::IriParseError;
 use polars_core::frame::DataFrame;
 use polars_core::prelude::{DataType, Series};
@@ -25,16 +27,53 @@ pub enum MappingError {
     PTypeNotSupported(String, PType),
     UnknownTimeZoneError(String),
     UnknownVariableError(String),
+    UnsupportedExpressionTerm(StottrTerm),
     ConstantDoesNotMatchDataType(ConstantTerm, PType, PType),
     ConstantListHasInconsistentPType(ConstantTerm, PType, PType),
+    ConstantListHasInconsistentLanguageTags(ConstantTerm),
+    MissingLanguageTagForLangString(String),
     NoTemplateForTemplateNameFromPrefix(String),
     FileCreateIOError(io::Error),
     FolderCreateIOError(io::Error),
     WriteParquetError(PolarsError),
     ReadParquetError(PolarsError),
+    ReadCsvError(PolarsError),
+    MalformedStrings(String, Vec<usize>),
+    ReadIpcError(PolarsError),
+    ReadScannedFileError(PolarsError),
+    WriteIpcError(PolarsError),
     PathDoesNotExist(String),
     WriteNTriplesError(io::Error),
-    RemoveParquetFileError(io::Error)
+    RemoveParquetFileError(io::Error),
+    ManifestIOError(io::Error),
+    InstanceArgumentCountMismatch(String, usize, usize),
+    NonConstantInstanceArgument(StottrTerm),
+    UnsupportedInstanceArgument(ConstantTerm),
+    ChunkSchemaMismatch(String, Vec<SchemaMismatch>),
+    UnknownKeyColumn(String, String),
+    TemplateTypingError(TypingError),
+    InvalidSampleFraction(f64),
+    UnknownPrefixInColumn(String, Vec<String>),
+    UnresolvableRelativeIRIInColumn(String, Vec<String>),
+    DerivedIdColumnExists(String),
+    MissingDerivedIdSourceColumn(String, String),
+    DerivedIdSourceColumnNotSupported(String, DataType),
+    MissingLanguageTagColumn(String, String),
+    LanguageTagColumnNotString(String, DataType),
+    InvalidLanguageTagsInColumn(String, Vec<String>),
+    LanguageTagColumnForNonStringColumn(String, PType),
+    TenantQuotaExceeded(String, usize, usize),
+    UnknownUnitParameter(String, String),
+    UnitForNonNumericParameter(String, String, PType),
+    NullArgumentForNonOptionalParameter(String, String, String, DataFrame),
+    ColumnCoercionFailed(String, DataType, PType),
+    DuplicateColumns(Vec<String>),
+    InvalidDuplicateColumnSelection(String, usize, usize),
+    ProjectIOError(io::Error),
+    InvalidProjectOptions(String),
+    ProjectSettingNotSupported(String),
+    InvalidPredicateValue(String),
+    NamedGraphsNotSupported(String),
 }
 
 impl Display for MappingError {
@@ -59,6 +98,45 @@ impl Display for MappingError {
                     col, nullkey
                 )
             }
+            MappingError::DuplicateColumns(columns) => {
+                write!(f, "Input has several columns named {}", columns.join(", "))
+            }
+            MappingError::ProjectIOError(e) => {
+                write!(f, "Reading or writing project files resulted in an error: {}", e)
+            }
+            MappingError::InvalidProjectOptions(line) => {
+                write!(f, "Could not read project option: {}", line)
+            }
+            MappingError::ProjectSettingNotSupported(setting) => {
+                write!(f, "The project can not be saved, as {} can not be written to the project options", setting)
+            }
+            MappingError::InvalidPredicateValue(value) => {
+                write!(f, "Predicate value {} is not an IRI", value)
+            }
+            MappingError::NamedGraphsNotSupported(format) => {
+                write!(f, "The store has named graphs, which can not be written as {}, use TriG or N-Quads instead", format)
+            }
+            MappingError::InvalidDuplicateColumnSelection(column, selected, count) => {
+                write!(
+                    f,
+                    "Column {} was selected at position {}, but there are {} columns with the name",
+                    column, selected, count
+                )
+            }
+            MappingError::ColumnCoercionFailed(col, datatype, ptype) => {
+                write!(
+                    f,
+                    "Column {} with datatype {} could not be cast to the type {} of its parameter",
+                    col, datatype, ptype
+                )
+            }
+            MappingError::NullArgumentForNonOptionalParameter(col, template, parameter, rows) => {
+                write!(
+                    f,
+                    "Column {} has null values as argument of non-optional parameter {} of template {} in rows: {}",
+                    col, parameter, template, rows
+                )
+            }
             MappingError::NonBlankColumnHasBlankNode(col, blanks) => {
                 write!(f, "Non-blank column {} has blanks {}", col, blanks)
             }
@@ -92,6 +170,9 @@ impl Display for MappingError {
             MappingError::UnknownTimeZoneError(tz) => {
                 write!(f, "Unknown time zone {}", tz)
             }
+            MappingError::UnsupportedExpressionTerm(t) => {
+                write!(f, "Term {} is not supported in expressions", t)
+            }
             MappingError::UnknownVariableError(v) => {
                 write!(
                     f,
@@ -113,6 +194,20 @@ impl Display for MappingError {
                     constant_term, prev, next
                 )
             }
+            MappingError::ConstantListHasInconsistentLanguageTags(constant_term) => {
+                write!(
+                    f,
+                    "Constant term {:?} has elements with different language tags",
+                    constant_term
+                )
+            }
+            MappingError::MissingLanguageTagForLangString(c) => {
+                write!(
+                    f,
+                    "Column {} has the stottr datatype rdf:langString, but no language tags are given for it",
+                    c
+                )
+            }
             MappingError::InvalidTemplateNameError(t) => {
                 write!(f, "Invalid template name {}", t)
             }
@@ -153,12 +248,134 @@ impl Display for MappingError {
             MappingError::ReadParquetError(p) => {
                 write!(f, "Reading parquet file resulted in an error: {:?}", p)
             }
+            MappingError::MalformedStrings(c, rows) => {
+                write!(
+                    f,
+                    "Column {} has strings with invalid UTF-8 or disallowed control characters in rows {:?}",
+                    c, rows
+                )
+            }
+            MappingError::ReadCsvError(p) => {
+                write!(f, "Reading CSV file resulted in an error: {:?}", p)
+            }
+            MappingError::ReadIpcError(p) => {
+                write!(f, "Reading IPC file resulted in an error: {:?}", p)
+            }
+            MappingError::ReadScannedFileError(p) => {
+                write!(f, "Reading a batch of rows from file resulted in an error: {:?}", p)
+            }
+            MappingError::WriteIpcError(p) => {
+                write!(f, "Writing IPC file resulted in an error: {:?}", p)
+            }
             MappingError::WriteNTriplesError(e) => {
                 write!(f, "Error writing NTriples {}", e)
             }
             MappingError::RemoveParquetFileError(e) => {
                 write!(f, "Error removing parquet file {}", e)
             }
+            MappingError::ManifestIOError(e) => {
+                write!(f, "Error with the manifest of parquet files being written {}", e)
+            }
+            MappingError::InstanceArgumentCountMismatch(template, given, expected) => {
+                write!(
+                    f,
+                    "Instance of {} has {} arguments, but expected {}",
+                    template, given, expected
+                )
+            }
+            MappingError::NonConstantInstanceArgument(term) => {
+                write!(f, "Instance argument {} is not a constant", term)
+            }
+            MappingError::UnsupportedInstanceArgument(constant_term) => {
+                write!(f, "Instance argument {} is not supported", constant_term)
+            }
+            MappingError::ChunkSchemaMismatch(predicate, mismatches) => {
+                write!(f, "Chunks of predicate {} have different schemas:", predicate)?;
+                for m in mismatches {
+                    write!(
+                        f,
+                        " chunk {} column {} has data type {} but expected {};",
+                        m.chunk, m.column, m.found, m.expected
+                    )?;
+                }
+                write!(f, " consider enabling data type harmonization")
+            }
+            MappingError::TemplateTypingError(e) => {
+                write!(f, "Template typing error: {}", e)
+            }
+            MappingError::UnknownKeyColumn(template, c) => {
+                write!(f, "Key column {} is not a parameter of template {}", c, template)
+            }
+            MappingError::UnknownUnitParameter(template, p) => {
+                write!(f, "Unit declared for {}, which is not a parameter of template {}", p, template)
+            }
+            MappingError::UnitForNonNumericParameter(template, p, ptype) => {
+                write!(f, "Unit declared for parameter {} of template {} with non-numeric type {}", p, template, ptype)
+            }
+            MappingError::InvalidSampleFraction(fraction) => {
+                write!(f, "Sample fraction {} is not between 0 and 1", fraction)
+            }
+            MappingError::UnknownPrefixInColumn(column, prefixes) => {
+                write!(
+                    f,
+                    "Column {} has prefixed names with unknown prefixes: {}",
+                    column,
+                    prefixes.join(", ")
+                )
+            }
+            MappingError::UnresolvableRelativeIRIInColumn(column, iris) => {
+                write!(
+                    f,
+                    "Column {} has relative IRIs that can not be resolved against the base: {}",
+                    column,
+                    iris.join(", ")
+                )
+            }
+            MappingError::DerivedIdColumnExists(c) => {
+                write!(f, "Column {} is derived from other columns, but is also in the input", c)
+            }
+            MappingError::MissingDerivedIdSourceColumn(c, source) => {
+                write!(f, "Column {} is derived from column {} which is not in the input", c, source)
+            }
+            MappingError::DerivedIdSourceColumnNotSupported(source, datatype) => {
+                write!(
+                    f,
+                    "Column {} with data type {} can not be used to derive ids",
+                    source, datatype
+                )
+            }
+            MappingError::MissingLanguageTagColumn(c, tag_column) => {
+                write!(f, "Column {} has language tags in column {} which is not in the input", c, tag_column)
+            }
+            MappingError::LanguageTagColumnNotString(tag_column, datatype) => {
+                write!(
+                    f,
+                    "Column {} of language tags had datatype {}, expected strings",
+                    tag_column, datatype
+                )
+            }
+            MappingError::InvalidLanguageTagsInColumn(tag_column, tags) => {
+                write!(
+                    f,
+                    "Column {} has invalid language tags: {}",
+                    tag_column,
+                    tags.join(", ")
+                )
+            }
+            MappingError::LanguageTagColumnForNonStringColumn(c, ptype) => {
+                write!(
+                    f,
+                    "Column {} has language tags, but the stottr datatype {} is not xsd:string",
+                    c, ptype
+                )
+            }
+            MappingError::TenantQuotaExceeded(tenant, quota, triples) => {
+                write!(
+                    f,
+                    "Tenant {} would have {} triples, which exceeds its quota of {}",
+                    tenant, triples, quota
+                )
+            }
         }
     }
 }
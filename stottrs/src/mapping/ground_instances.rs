@@ -0,0 +1,249 @@
+use super::{merge_template_rows, ExpandOptions, Mapping, MappingReport};
+use crate::ast::{
+    Argument, ConstantLiteral, ConstantTerm, Instance, PType, Parameter, Signature, StottrTerm,
+    StottrVariable, Template,
+};
+use crate::constants::DEFAULT_TEMPLATE_PREFIX;
+use crate::errors::MapperError;
+use crate::literals::sparql_literal_to_any_value;
+use crate::mapping::coverage::Coverage;
+use crate::mapping::errors::MappingError;
+use crate::mapping::language_tag_columns::language_tag_column_name;
+use crate::triplestore::merge_triple_counts;
+use crate::wottr::{instances_from_triples, triples_from_turtle_str};
+use oxrdf::vocab::xsd;
+use oxrdf::{NamedNode, Triple};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{AnyValue, DataType, NamedFrom, Series};
+use std::collections::HashMap;
+use std::time::Duration;
+
+impl Mapping {
+    pub fn expand_turtle_instances(&mut self, s: &str) -> Result<MappingReport, MapperError> {
+        let triples = triples_from_turtle_str(s)?;
+        self.expand_rdf_instances(&triples)
+    }
+
+    pub fn expand_rdf_instances(&mut self, triples: &Vec<Triple>) -> Result<MappingReport, MapperError> {
+        let instances = instances_from_triples(triples)?;
+        Ok(self.expand_instances(instances)?)
+    }
+
+    pub fn expand_instances(&mut self, instances: Vec<Instance>) -> Result<MappingReport, MappingError> {
+        self.expand_instances_in_graph(instances, None)
+    }
+
+    pub(crate) fn expand_instances_in_graph(
+        &mut self,
+        instances: Vec<Instance>,
+        graph: Option<String>,
+    ) -> Result<MappingReport, MappingError> {
+        //Instances that call the same template in the same way are expanded together
+        let mut keys = vec![];
+        let mut instance_groups: HashMap<String, Vec<Instance>> = HashMap::new();
+        for i in instances {
+            let mut key = i.template_name.as_str().to_string();
+            if let Some(le) = &i.list_expander {
+                key.push_str(&format!(" {}", le));
+            }
+            for a in &i.argument_list {
+                key.push_str(if a.list_expand { " ++" } else { " _" });
+            }
+            if let Some(v) = instance_groups.get_mut(&key) {
+                v.push(i);
+            } else {
+                keys.push(key.clone());
+                instance_groups.insert(key, vec![i]);
+            }
+        }
+        let mut triple_counts = vec![];
+        let mut coverage = Coverage::default();
+        let mut template_rows = vec![];
+        let mut elapsed = Duration::default();
+        for k in keys {
+            let instances = instance_groups.remove(&k).unwrap();
+            let report = self.expand_instance_group(instances, graph.clone())?;
+            merge_triple_counts(&mut triple_counts, report.triple_counts);
+            coverage.merge(report.coverage);
+            merge_template_rows(&mut template_rows, report.template_rows);
+            elapsed += report.elapsed;
+        }
+        Ok(MappingReport {
+            triple_counts,
+            coverage,
+            template_rows,
+            elapsed,
+            ..Default::default()
+        })
+    }
+
+    fn expand_instance_group(
+        &mut self,
+        instances: Vec<Instance>,
+        graph: Option<String>,
+    ) -> Result<MappingReport, MappingError> {
+        let first = instances.first().unwrap();
+        let target_template = self
+            .resolve_template(first.template_name.as_str())?
+            .clone();
+        let target_parameters = &target_template.signature.parameter_list;
+        for i in &instances {
+            if i.argument_list.len() != target_parameters.len() {
+                return Err(MappingError::InstanceArgumentCountMismatch(
+                    i.template_name.as_str().to_string(),
+                    i.argument_list.len(),
+                    target_parameters.len(),
+                ));
+            }
+        }
+
+        let mut series_vec = vec![];
+        let mut parameter_list = vec![];
+        let mut language_tag_columns = HashMap::new();
+        for (j, target_parameter) in target_parameters.iter().enumerate() {
+            let name = &target_parameter.stottr_variable.name;
+            let mut any_values = vec![];
+            let mut ptype = None;
+            let mut has_null = false;
+            let mut language_tags = vec![];
+            for i in &instances {
+                let constant_term = match &i.argument_list.get(j).unwrap().term {
+                    StottrTerm::ConstantTerm(ct) => ct,
+                    t => return Err(MappingError::NonConstantInstanceArgument(t.clone())),
+                };
+                let (any_value, value_ptype, language_tag) =
+                    constant_term_to_any_value(constant_term)?;
+                if let AnyValue::Null = any_value {
+                    has_null = true;
+                }
+                if let Some(value_ptype) = value_ptype {
+                    if ptype.is_none() {
+                        ptype = Some(value_ptype);
+                    } else if ptype.as_ref().unwrap() != &value_ptype {
+                        return Err(MappingError::ConstantListHasInconsistentPType(
+                            constant_term.clone(),
+                            ptype.as_ref().unwrap().clone(),
+                            value_ptype,
+                        ));
+                    }
+                }
+                language_tags.push(language_tag);
+                any_values.push(any_value);
+            }
+            series_vec.push(Series::from_any_values(name, &any_values).unwrap());
+            //The instances may have different language tags for the same parameter
+            if language_tags.iter().any(|t| t.is_some()) {
+                let tag_column = language_tag_column_name(name);
+                series_vec.push(Series::new(&tag_column, language_tags));
+                language_tag_columns.insert(name.clone(), tag_column);
+            }
+            parameter_list.push(Parameter {
+                optional: has_null,
+                non_blank: false,
+                ptype: if target_parameter.ptype.is_some() {
+                    target_parameter.ptype.clone()
+                } else {
+                    ptype
+                },
+                stottr_variable: StottrVariable { name: name.clone() },
+                default_value: None,
+            });
+        }
+
+        let template_uuid = self.new_uuid().to_string();
+        let template_name = format!("{}{}", DEFAULT_TEMPLATE_PREFIX, &template_uuid);
+        let template = Template {
+            signature: Signature {
+                template_name: NamedNode::new(template_name.clone()).unwrap(),
+                template_prefixed_name: format!("prefix:{}", template_uuid),
+                parameter_list,
+                annotation_list: None,
+            },
+            pattern_list: vec![Instance {
+                list_expander: first.list_expander.clone(),
+                template_name: target_template.signature.template_name.clone(),
+                prefixed_template_name: target_template.signature.template_prefixed_name.clone(),
+                argument_list: first
+                    .argument_list
+                    .iter()
+                    .zip(target_parameters.iter())
+                    .map(|(a, p)| Argument {
+                        parameter: None,
+                        list_expand: a.list_expand,
+                        term: StottrTerm::Variable(p.stottr_variable.clone()),
+                    })
+                    .collect(),
+            }],
+        };
+        self.template_dataset.templates.push(template);
+        let df = DataFrame::new(series_vec).unwrap();
+        let res = self.expand(
+            &template_name,
+            df,
+            ExpandOptions {
+                language_tag_columns: Some(language_tag_columns),
+                graph,
+                ..Default::default()
+            },
+        );
+        self.template_dataset
+            .templates
+            .retain(|t| t.signature.template_name.as_str() != template_name);
+        res
+    }
+}
+
+fn constant_term_to_any_value(
+    constant_term: &ConstantTerm,
+) -> Result<(AnyValue<'static>, Option<PType>, Option<String>), MappingError> {
+    match constant_term {
+        ConstantTerm::Constant(c) => match c {
+            ConstantLiteral::IRI(nn) => Ok((
+                AnyValue::Utf8Owned(nn.as_str().into()),
+                Some(PType::BasicType(
+                    xsd::ANY_URI.into_owned(),
+                    "xsd:anyURI".to_string(),
+                )),
+                None,
+            )),
+            ConstantLiteral::Literal(lit) => {
+                let dt = if let Some(dt) = &lit.data_type_iri {
+                    dt.clone()
+                } else {
+                    xsd::STRING.into_owned()
+                };
+                let (any_value, dt) = sparql_literal_to_any_value(&lit.value, &Some(dt));
+                Ok((
+                    any_value,
+                    Some(PType::BasicType(dt.clone(), dt.to_string())),
+                    lit.language.clone(),
+                ))
+            }
+            ConstantLiteral::None => Ok((AnyValue::Null, None, None)),
+            ConstantLiteral::BlankNode(_) => Err(MappingError::UnsupportedInstanceArgument(
+                constant_term.clone(),
+            )),
+        },
+        ConstantTerm::ConstantList(inner) => {
+            let mut any_values = vec![];
+            let mut inner_ptype = None;
+            for ct in inner {
+                let (any_value, ptype, _) = constant_term_to_any_value(ct)?;
+                if inner_ptype.is_none() {
+                    inner_ptype = ptype;
+                }
+                any_values.push(any_value);
+            }
+            let ser = if any_values.is_empty() {
+                Series::new_empty("", &DataType::Utf8)
+            } else {
+                Series::from_any_values("", &any_values).unwrap()
+            };
+            Ok((
+                AnyValue::List(ser),
+                inner_ptype.map(|x| PType::ListType(Box::new(x))),
+                None,
+            ))
+        }
+    }
+}
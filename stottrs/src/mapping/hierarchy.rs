@@ -0,0 +1,182 @@
+use super::{ExpandOptions, Mapping, MappingReport};
+use crate::ast::{
+    Argument, ConstantLiteral, ConstantTerm, Instance, PType, Parameter, Signature, StottrTerm,
+    StottrVariable, Template,
+};
+use crate::constants::{DEFAULT_PREDICATE_URI_PREFIX, DEFAULT_TEMPLATE_PREFIX, OTTR_TRIPLE};
+use crate::mapping::errors::MappingError;
+use crate::triplestore::sparql::lazy_expressions::encode_for_uri;
+use oxrdf::vocab::xsd;
+use oxrdf::NamedNode;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{DataType, NamedFrom, Series};
+use std::collections::HashSet;
+
+const NODE_COLUMN: &str = "node";
+const PARENT_COLUMN: &str = "parent";
+const NAME_COLUMN: &str = "name";
+const LEVEL_COLUMN: &str = "level";
+
+/// Describes a column of hierarchical identifiers such as the OPC UA browse path "/Site/Area/Unit/Tag",
+/// where each prefix of a path is a node of the hierarchy.
+pub struct Hierarchy {
+    pub path_column: String,
+    /// Separates the levels of a path, leading and repeated separators are ignored.
+    pub separator: String,
+    /// The IRI of a node is the prefix followed by its levels, percent-encoded and separated by slashes.
+    pub iri_prefix: String,
+    /// The predicates hasParent, name and level are given by this prefix and the name of the predicate.
+    pub predicate_prefix: Option<String>,
+}
+
+impl Hierarchy {
+    /// The IRI of the node with the path, or None if the path has no levels.
+    /// Other templates can refer to the nodes of the hierarchy using these IRIs.
+    pub fn node_iri(&self, path: &str) -> Option<String> {
+        let levels = self.levels(path);
+        if levels.is_empty() {
+            None
+        } else {
+            Some(self.iri(&levels))
+        }
+    }
+
+    fn levels<'a>(&self, path: &'a str) -> Vec<&'a str> {
+        path.split(self.separator.as_str())
+            .filter(|l| !l.is_empty())
+            .collect()
+    }
+
+    fn iri(&self, levels: &[&str]) -> String {
+        let encoded: Vec<String> = levels.iter().map(|l| encode_for_uri(l)).collect();
+        format!("{}{}", self.iri_prefix, encoded.join("/"))
+    }
+}
+
+impl Mapping {
+    /// Expands every node of the paths in the column, including the nodes of the levels above the paths,
+    /// with its name, its level counted from 1 at the top, and a hasParent triple to the node above it.
+    /// Nodes shared by several paths are only expanded once, rows where the path is null are skipped.
+    pub fn expand_hierarchy(
+        &mut self,
+        df: DataFrame,
+        hierarchy: &Hierarchy,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let nodes_df = hierarchy_nodes(&df, hierarchy)?;
+        let template_name = self.hierarchy_template(&hierarchy.predicate_prefix)?;
+        self.expand(&template_name, nodes_df, options)
+    }
+
+    fn hierarchy_template(&mut self, predicate_prefix: &Option<String>) -> Result<String, MappingError> {
+        let predicate_prefix = predicate_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_PREDICATE_URI_PREFIX);
+        let predicate = |name: &str| {
+            StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::IRI(
+                NamedNode::new_unchecked(format!("{}{}", predicate_prefix, name)),
+            )))
+        };
+        let variable = |name: &str| {
+            StottrTerm::Variable(StottrVariable {
+                name: name.to_string(),
+            })
+        };
+        let triple = |subject: StottrTerm, verb: StottrTerm, object: StottrTerm| Instance {
+            list_expander: None,
+            template_name: NamedNode::new_unchecked(OTTR_TRIPLE),
+            prefixed_template_name: "ottr:Triple".to_string(),
+            argument_list: [subject, verb, object]
+                .into_iter()
+                .map(|term| Argument {
+                    parameter: None,
+                    list_expand: false,
+                    term,
+                })
+                .collect(),
+        };
+        let parameter = |name: &str, optional: bool, ptype: PType| Parameter {
+            optional,
+            non_blank: false,
+            ptype: Some(ptype),
+            stottr_variable: StottrVariable {
+                name: name.to_string(),
+            },
+            default_value: None,
+        };
+        let iri_type = || PType::BasicType(xsd::ANY_URI.into_owned(), "xsd:anyURI".to_string());
+        let template_uuid = self.new_uuid().to_string();
+        let template_name = format!("{}{}", DEFAULT_TEMPLATE_PREFIX, &template_uuid);
+        let template = Template {
+            signature: Signature {
+                template_name: NamedNode::new(template_name.clone())?,
+                template_prefixed_name: format!("prefix:{}", template_uuid),
+                parameter_list: vec![
+                    parameter(NODE_COLUMN, false, iri_type()),
+                    //Nodes at the top have no parent
+                    parameter(PARENT_COLUMN, true, iri_type()),
+                    parameter(
+                        NAME_COLUMN,
+                        false,
+                        PType::BasicType(xsd::STRING.into_owned(), "xsd:string".to_string()),
+                    ),
+                    parameter(
+                        LEVEL_COLUMN,
+                        false,
+                        PType::BasicType(xsd::LONG.into_owned(), "xsd:long".to_string()),
+                    ),
+                ],
+                annotation_list: None,
+            },
+            pattern_list: vec![
+                triple(
+                    variable(NODE_COLUMN),
+                    predicate("hasParent"),
+                    variable(PARENT_COLUMN),
+                ),
+                triple(variable(NODE_COLUMN), predicate(NAME_COLUMN), variable(NAME_COLUMN)),
+                triple(variable(NODE_COLUMN), predicate(LEVEL_COLUMN), variable(LEVEL_COLUMN)),
+            ],
+        };
+        self.upsert_template(template)?;
+        Ok(template_name)
+    }
+}
+
+//One row for each node of the paths, with the IRI of the node and its parent, its name and level
+fn hierarchy_nodes(df: &DataFrame, hierarchy: &Hierarchy) -> Result<DataFrame, MappingError> {
+    let paths = df
+        .column(&hierarchy.path_column)
+        .map_err(|_| MappingError::MissingParameterColumn(hierarchy.path_column.clone()))?
+        .cast(&DataType::Utf8)
+        .unwrap();
+    let mut seen = HashSet::new();
+    let mut nodes = vec![];
+    let mut parents = vec![];
+    let mut names = vec![];
+    let mut levels = vec![];
+    for path in paths.utf8().unwrap().into_iter().flatten() {
+        let path_levels = hierarchy.levels(path);
+        for i in 0..path_levels.len() {
+            let node = hierarchy.iri(&path_levels[..i + 1]);
+            if !seen.insert(node.clone()) {
+                continue;
+            }
+            nodes.push(node);
+            parents.push(if i > 0 {
+                Some(hierarchy.iri(&path_levels[..i]))
+            } else {
+                None
+            });
+            names.push(path_levels[i].to_string());
+            levels.push((i + 1) as i64);
+        }
+    }
+    Ok(DataFrame::new(vec![
+        Series::new(NODE_COLUMN, nodes),
+        Series::new(PARENT_COLUMN, parents),
+        Series::new(NAME_COLUMN, names),
+        Series::new(LEVEL_COLUMN, levels),
+    ])
+    .unwrap())
+}
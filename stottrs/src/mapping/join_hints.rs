@@ -0,0 +1,133 @@
+use crate::ast::{ConstantLiteral, ConstantTerm, StottrTerm, Template};
+use crate::constants::OTTR_TRIPLE;
+use crate::templates::TemplateDataset;
+use crate::triplestore::join_hints::{JoinHint, TriplePosition};
+use std::collections::HashMap;
+
+//Templates nested deeper than this are not searched for join hints
+const MAX_NESTING_DEPTH: usize = 64;
+
+struct FlatTriple {
+    subject: Option<String>,
+    predicate: Option<String>,
+    object: Option<String>,
+}
+
+//The predicates that share nodes in the triples of the template, found by following the arguments of nested instances
+//down to the ottr:Triple instances
+pub(crate) fn template_join_hints(template_dataset: &TemplateDataset, template_name: &str) -> Vec<JoinHint> {
+    let template = if let Some(template) = template_dataset.get(template_name) {
+        template
+    } else {
+        return vec![];
+    };
+    let nodes = template
+        .signature
+        .parameter_list
+        .iter()
+        .map(|p| {
+            let name = &p.stottr_variable.name;
+            (name.clone(), Some(name.clone()))
+        })
+        .collect();
+    let mut flat_triples = vec![];
+    flatten_template(template_dataset, template, &nodes, "", 0, &mut flat_triples);
+
+    let mut join_hints = vec![];
+    for (i, left) in flat_triples.iter().enumerate() {
+        for right in &flat_triples[i + 1..] {
+            let (left_predicate, right_predicate) = match (&left.predicate, &right.predicate) {
+                (Some(l), Some(r)) => (l, r),
+                _ => continue,
+            };
+            for (left_node, left_position) in node_positions(left) {
+                for (right_node, right_position) in node_positions(right) {
+                    if left_node == right_node
+                        && (left_predicate, left_position) != (right_predicate, right_position)
+                    {
+                        let join_hint = JoinHint::new(
+                            left_predicate,
+                            left_position,
+                            right_predicate,
+                            right_position,
+                        );
+                        if !join_hints.contains(&join_hint) {
+                            join_hints.push(join_hint);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    join_hints.sort();
+    join_hints
+}
+
+//Nodes maps the variables of the template to the nodes they are bound to in the outermost template,
+//where a node is a parameter of the outermost template or a blank node of an instance, and None is a constant
+fn flatten_template(
+    template_dataset: &TemplateDataset,
+    template: &Template,
+    nodes: &HashMap<String, Option<String>>,
+    scope: &str,
+    depth: usize,
+    flat_triples: &mut Vec<FlatTriple>,
+) {
+    if depth > MAX_NESTING_DEPTH {
+        return;
+    }
+    for (pattern_index, instance) in template.pattern_list.iter().enumerate() {
+        let instance_scope = format!("{}/{}", scope, pattern_index);
+        let node = |term: &StottrTerm| match term {
+            StottrTerm::Variable(v) => nodes.get(&v.name).cloned().flatten(),
+            //Blank nodes in a pattern list are the same node in all instances of the list
+            StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::BlankNode(bn))) => {
+                Some(format!("{} _:{}", scope, bn.as_str()))
+            }
+            _ => None,
+        };
+        if instance.template_name.as_str() == OTTR_TRIPLE {
+            if instance.argument_list.len() != 3 {
+                continue;
+            }
+            let predicate = match &instance.argument_list[1].term {
+                StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::IRI(nn))) => {
+                    Some(nn.as_str().to_string())
+                }
+                _ => None,
+            };
+            flat_triples.push(FlatTriple {
+                subject: node(&instance.argument_list[0].term),
+                predicate,
+                object: node(&instance.argument_list[2].term),
+            });
+        } else if let Some(nested) = template_dataset.get(instance.template_name.as_str()) {
+            let nested_nodes = nested
+                .signature
+                .parameter_list
+                .iter()
+                .zip(instance.argument_list.iter())
+                .map(|(p, a)| (p.stottr_variable.name.clone(), node(&a.term)))
+                .collect();
+            flatten_template(
+                template_dataset,
+                nested,
+                &nested_nodes,
+                &instance_scope,
+                depth + 1,
+                flat_triples,
+            );
+        }
+    }
+}
+
+fn node_positions(flat_triple: &FlatTriple) -> Vec<(&String, TriplePosition)> {
+    let mut out = vec![];
+    if let Some(s) = &flat_triple.subject {
+        out.push((s, TriplePosition::Subject));
+    }
+    if let Some(o) = &flat_triple.object {
+        out.push((o, TriplePosition::Object));
+    }
+    out
+}
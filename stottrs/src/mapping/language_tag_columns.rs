@@ -0,0 +1,56 @@
+use crate::mapping::errors::MappingError;
+use oxrdf::Literal;
+use polars_core::prelude::{DataFrame, DataType};
+use std::collections::{BTreeSet, HashMap};
+
+//Per-row language tags follow the column they belong to through the templates under this prefix
+const LANGUAGE_TAG_COLUMN_PREFIX: &str = "_:lang:";
+
+pub(crate) fn language_tag_column_name(column: &str) -> String {
+    format!("{}{}", LANGUAGE_TAG_COLUMN_PREFIX, column)
+}
+
+pub(crate) fn is_language_tag_column_name(column: &str) -> bool {
+    column.starts_with(LANGUAGE_TAG_COLUMN_PREFIX)
+}
+
+//Copies the columns of tags to the columns that follow the tagged columns, the tags are checked to be BCP47 tags
+pub(crate) fn add_language_tag_columns(
+    mut df: DataFrame,
+    language_tag_columns: &HashMap<String, String>,
+) -> Result<DataFrame, MappingError> {
+    let mut columns: Vec<&String> = language_tag_columns.keys().collect();
+    columns.sort();
+    for c in columns {
+        let tag_column = language_tag_columns.get(c).unwrap();
+        let series = df
+            .column(tag_column)
+            .map_err(|_| MappingError::MissingLanguageTagColumn(c.clone(), tag_column.clone()))?;
+        let series = match series.dtype() {
+            DataType::Utf8 => series.clone(),
+            DataType::Categorical(_) | DataType::Null => series.cast(&DataType::Utf8).unwrap(),
+            dt => {
+                return Err(MappingError::LanguageTagColumnNotString(
+                    tag_column.clone(),
+                    dt.clone(),
+                ))
+            }
+        };
+        let mut invalid_tags = BTreeSet::new();
+        for tag in series.utf8().unwrap().into_iter().flatten() {
+            if Literal::new_language_tagged_literal("", tag).is_err() {
+                invalid_tags.insert(tag.to_string());
+            }
+        }
+        if !invalid_tags.is_empty() {
+            return Err(MappingError::InvalidLanguageTagsInColumn(
+                tag_column.clone(),
+                invalid_tags.into_iter().collect(),
+            ));
+        }
+        let mut series = series;
+        series.rename(&language_tag_column_name(c));
+        df.with_column(series).unwrap();
+    }
+    Ok(df)
+}
@@ -0,0 +1,93 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::{PrimitiveColumn, RDFNodeType};
+use crate::resolver::resolve_relative_iri;
+use oxrdf::NamedNode;
+use polars_core::prelude::{ChunkApply, DataFrame, DataType, IntoSeries};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+//Full IRIs with these schemes have no authority, and are not mistaken for prefixed names
+const NON_HIERARCHICAL_SCHEMES: [&str; 4] = ["urn", "mailto", "tag", "data"];
+
+//Expands prefixed names such as ex:thing1 in the IRI columns using the prefixes of the template dataset,
+//and resolves relative IRIs such as thing1 against the base of the template dataset when it is defined
+pub(crate) fn expand_prefixed_names(
+    mut df: DataFrame,
+    columns: &HashMap<String, PrimitiveColumn>,
+    prefix_map: &HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
+) -> Result<DataFrame, MappingError> {
+    let mut iri_columns: Vec<&String> = columns
+        .iter()
+        .filter(|(_, c)| c.rdf_node_type == RDFNodeType::IRI)
+        .map(|(k, _)| k)
+        .collect();
+    iri_columns.sort();
+    for c in iri_columns {
+        let series = df.column(c).unwrap();
+        let series = match series.dtype() {
+            DataType::Utf8 => series.clone(),
+            DataType::Categorical(_) => series.cast(&DataType::Utf8).unwrap(),
+            _ => continue,
+        };
+        let ca = series.utf8().unwrap();
+        let mut unknown_prefixes = BTreeSet::new();
+        let mut unresolvable = BTreeSet::new();
+        for s in ca.into_iter().flatten() {
+            if let Some((prefix, _)) = split_prefixed_name(s) {
+                if !prefix_map.contains_key(prefix) {
+                    unknown_prefixes.insert(prefix.to_string());
+                }
+            } else if let Some(base) = base_iri {
+                if is_relative_iri(s) && resolve_relative_iri(base, s).is_err() {
+                    unresolvable.insert(s.to_string());
+                }
+            }
+        }
+        if !unknown_prefixes.is_empty() {
+            return Err(MappingError::UnknownPrefixInColumn(
+                c.clone(),
+                unknown_prefixes.into_iter().collect(),
+            ));
+        }
+        if !unresolvable.is_empty() {
+            return Err(MappingError::UnresolvableRelativeIRIInColumn(
+                c.clone(),
+                unresolvable.into_iter().collect(),
+            ));
+        }
+        let mut expanded = ca.apply(|s| {
+            if let Some((prefix, local)) = split_prefixed_name(s) {
+                Cow::Owned(format!("{}{}", prefix_map.get(prefix).unwrap().as_str(), local))
+            } else if let (Some(base), true) = (base_iri, is_relative_iri(s)) {
+                Cow::Owned(resolve_relative_iri(base, s).unwrap().as_str().to_string())
+            } else {
+                Cow::Borrowed(s)
+            }
+        });
+        //Applying a function does not keep the name of the column
+        expanded.rename(c);
+        df.with_column(expanded.into_series()).unwrap();
+    }
+    Ok(df)
+}
+
+//Returns the prefix and local name when the string is a prefixed name, and not a full IRI or a blank node
+fn split_prefixed_name(s: &str) -> Option<(&str, &str)> {
+    let (prefix, local) = s.split_once(':')?;
+    if prefix == "_" || local.starts_with("//") || NON_HIERARCHICAL_SCHEMES.contains(&prefix) {
+        return None;
+    }
+    if !prefix
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return None;
+    }
+    Some((prefix, local))
+}
+
+//Blank nodes and full IRIs contain a colon, so the rest are relative IRIs
+fn is_relative_iri(s: &str) -> bool {
+    !s.contains(':')
+}
@@ -0,0 +1,181 @@
+use super::{Mapping, TemplateKey};
+use crate::document::document_from_str;
+use crate::errors::MapperError;
+use crate::io_funcs::create_folder_if_not_exists;
+use crate::mapping::errors::MappingError;
+use crate::mapping::units::{UnitAnnotation, UnitMode};
+use crate::templates::TemplateDataset;
+use crate::triplestore::deduplication::DeduplicationStrategy;
+use crate::triplestore::Triplestore;
+use oxrdf::NamedNode;
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+const PROJECT_TEMPLATES: &str = "templates.stottr";
+const PROJECT_TRIPLES: &str = "triples";
+const PROJECT_OPTIONS: &str = "options.tsv";
+
+impl Mapping {
+    /// Saves the templates, the triples and the options of the mapping to the folder, which is created if it does not exist.
+    /// The templates are written as stOTTR to templates.stottr, the triples as native parquet to the triples folder,
+    /// and the seed, keys, units and the settings of the triplestore to options.tsv.
+    /// Mappings with custom datatypes can not be saved, as their formatters can not be written.
+    pub fn save_project(&mut self, path: &str) -> Result<(), MapperError> {
+        let options = self.project_options()?;
+        let path = Path::new(path);
+        create_folder_if_not_exists(path)?;
+        let triples_path = path.join(PROJECT_TRIPLES);
+        create_folder_if_not_exists(&triples_path)?;
+        self.triplestore.write_native_parquet(&triples_path)?;
+        write(
+            path.join(PROJECT_TEMPLATES),
+            self.template_dataset.to_stottr_string(),
+        )
+        .map_err(MappingError::ProjectIOError)?;
+        write(path.join(PROJECT_OPTIONS), options)
+            .map_err(MappingError::ProjectIOError)?;
+        Ok(())
+    }
+
+    /// Loads a mapping saved by save_project, so that expansion can continue where it left off.
+    /// With a seed, the generated names continue from where they were when the project was saved.
+    /// If the mapping used a caching folder, the triples are written to it again.
+    pub fn load_project(path: &str) -> Result<Mapping, MapperError> {
+        let path = Path::new(path);
+        let templates = read_to_string(path.join(PROJECT_TEMPLATES))
+            .map_err(MappingError::ProjectIOError)?;
+        let doc = document_from_str(&templates)?;
+        let dataset = TemplateDataset::new(vec![doc])?;
+        let mut mapping = Mapping::new(&dataset, None);
+        mapping.triplestore = Triplestore::from_native_parquet(&path.join(PROJECT_TRIPLES))?;
+        let options = read_to_string(path.join(PROJECT_OPTIONS))
+            .map_err(MappingError::ProjectIOError)?;
+        let mut caching_folder = None;
+        for line in options.lines().filter(|l| !l.is_empty()) {
+            if let Some(folder) = line.strip_prefix("caching_folder\t") {
+                caching_folder = Some(folder.to_string());
+                continue;
+            }
+            mapping
+                .read_project_option(line)
+                .ok_or_else(|| MappingError::InvalidProjectOptions(line.to_string()))?;
+        }
+        if let Some(caching_folder) = caching_folder {
+            mapping
+                .triplestore
+                .move_to_caching_folder(&caching_folder)?;
+        }
+        Ok(mapping)
+    }
+
+    //One option per line, with tab separated values
+    fn project_options(&self) -> Result<String, MappingError> {
+        let mut lines = vec![];
+        if let Some(seed) = self.seed {
+            lines.push(format!("seed\t{}\t{}", seed, self.generated_uuids));
+        }
+        lines.push(format!(
+            "harmonize_dtypes\t{}",
+            self.triplestore.harmonize_dtypes
+        ));
+        lines.push(format!(
+            "collapse_language_tags\t{}",
+            self.triplestore.collapse_language_tags
+        ));
+        let deduplication_strategy = match self.triplestore.deduplication_strategy {
+            DeduplicationStrategy::Full => "full",
+            DeduplicationStrategy::Incremental => "incremental",
+        };
+        lines.push(format!("deduplication_strategy\t{}", deduplication_strategy));
+
+        let mut templates: Vec<&String> = self.template_keys.keys().collect();
+        templates.sort();
+        for template in templates {
+            let key = self.template_keys.get(template).unwrap();
+            let mut line = format!("key\t{}\t{}", template, key.globally_unique);
+            for subset in &key.unique_subsets {
+                line.push('\t');
+                line.push_str(&subset.join(","));
+            }
+            lines.push(line);
+        }
+        let mut templates: Vec<&String> = self.template_units.keys().collect();
+        templates.sort();
+        for template in templates {
+            let units = self.template_units.get(template).unwrap();
+            let mut parameters: Vec<&String> = units.keys().collect();
+            parameters.sort();
+            for parameter in parameters {
+                let unit = units.get(parameter).unwrap();
+                let mode = match unit.mode {
+                    UnitMode::UnitTriple => "unit_triple",
+                    UnitMode::Datatype => "datatype",
+                };
+                lines.push(format!(
+                    "unit\t{}\t{}\t{}\t{}",
+                    template,
+                    parameter,
+                    unit.unit.as_str(),
+                    mode
+                ));
+            }
+        }
+        lines.extend(self.triplestore.project_settings()?);
+        let mut options = lines.join("\n");
+        options.push('\n');
+        Ok(options)
+    }
+
+    fn read_project_option(&mut self, line: &str) -> Option<()> {
+        let values: Vec<&str> = line.split('\t').collect();
+        match values.as_slice() {
+            ["seed", seed, generated_uuids] => {
+                self.seed = Some(seed.parse().ok()?);
+                self.generated_uuids = generated_uuids.parse().ok()?;
+            }
+            ["harmonize_dtypes", value] => {
+                self.triplestore.set_harmonize_dtypes(value.parse().ok()?);
+            }
+            ["collapse_language_tags", value] => {
+                self.triplestore
+                    .set_collapse_language_tags(value.parse().ok()?);
+            }
+            ["deduplication_strategy", value] => {
+                let deduplication_strategy = match *value {
+                    "full" => DeduplicationStrategy::Full,
+                    "incremental" => DeduplicationStrategy::Incremental,
+                    _ => return None,
+                };
+                self.triplestore
+                    .set_deduplication_strategy(deduplication_strategy);
+            }
+            ["key", template, globally_unique, subsets @ ..] => {
+                let key = TemplateKey {
+                    unique_subsets: subsets
+                        .iter()
+                        .map(|s| s.split(',').map(|c| c.to_string()).collect())
+                        .collect(),
+                    globally_unique: globally_unique.parse().ok()?,
+                };
+                self.template_keys.insert(template.to_string(), key);
+            }
+            ["unit", template, parameter, unit, mode] => {
+                let mode = match *mode {
+                    "unit_triple" => UnitMode::UnitTriple,
+                    "datatype" => UnitMode::Datatype,
+                    _ => return None,
+                };
+                let unit = UnitAnnotation {
+                    unit: NamedNode::new(*unit).ok()?,
+                    mode,
+                };
+                self.template_units
+                    .entry(template.to_string())
+                    .or_default()
+                    .insert(parameter.to_string(), unit);
+            }
+            values => return self.triplestore.read_project_setting(values),
+        }
+        Some(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::{Sample, SampleSize};
+use polars_core::prelude::{DataFrame, IdxCa, IdxSize};
+use std::cmp::min;
+
+pub(crate) fn sample_df(df: &DataFrame, sample: &Sample) -> Result<DataFrame, MappingError> {
+    let mut rng = SplitMix64::new(sample.seed);
+    let height = df.height();
+    let idx: Vec<IdxSize> = match sample.size {
+        SampleSize::Fraction(fraction) => {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err(MappingError::InvalidSampleFraction(fraction));
+            }
+            (0..height)
+                .filter(|_| rng.next_f64() < fraction)
+                .map(|i| i as IdxSize)
+                .collect()
+        }
+        SampleSize::Rows(n) => {
+            //Reservoir sampling (Algorithm R), the rows keep their original order
+            let mut reservoir: Vec<IdxSize> = (0..min(n, height)).map(|i| i as IdxSize).collect();
+            for i in n..height {
+                let j = rng.next_below(i as u64 + 1) as usize;
+                if j < n {
+                    reservoir[j] = i as IdxSize;
+                }
+            }
+            reservoir.sort_unstable();
+            reservoir
+        }
+    };
+    let idx_ca = IdxCa::from_vec("idx", idx);
+    Ok(df.take(&idx_ca).unwrap())
+}
+
+//SplitMix64, we use our own generator so that samples do not change with dependency versions
+struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
@@ -0,0 +1,87 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::{PrimitiveColumn, RDFNodeType, SanitizePolicy, SanitizedColumn};
+use polars_core::prelude::{ChunkApply, DataFrame, DataType, IntoSeries};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+//Checks the string columns for strings that would give invalid N-Triples.
+//Arrays imported through the Arrow C data interface are not necessarily validated, so UTF-8 is checked as well.
+pub(crate) fn sanitize_strings(
+    mut df: DataFrame,
+    columns: &HashMap<String, PrimitiveColumn>,
+    policy: &SanitizePolicy,
+) -> Result<(DataFrame, Vec<SanitizedColumn>), MappingError> {
+    let mut column_names: Vec<&String> = columns.keys().collect();
+    column_names.sort();
+    let mut sanitized_columns = vec![];
+    for c in column_names {
+        let is_iri = columns.get(c).unwrap().rdf_node_type == RDFNodeType::IRI;
+        let series = df.column(c).unwrap();
+        let series = match series.dtype() {
+            DataType::Utf8 => series.clone(),
+            DataType::Categorical(_) => series.cast(&DataType::Utf8).unwrap(),
+            _ => continue,
+        };
+        let ca = series.utf8().unwrap();
+        let rows: Vec<usize> = ca
+            .into_iter()
+            .enumerate()
+            .filter(|(_, s)| s.map(|s| is_malformed(s, is_iri)).unwrap_or(false))
+            .map(|(i, _)| i)
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        match policy {
+            SanitizePolicy::Error => {
+                return Err(MappingError::MalformedStrings(c.clone(), rows));
+            }
+            SanitizePolicy::Clean => {
+                let mut cleaned = ca.apply(|s| {
+                    if is_malformed(s, is_iri) {
+                        Cow::Owned(clean(s, is_iri))
+                    } else {
+                        Cow::Borrowed(s)
+                    }
+                });
+                cleaned.rename(c);
+                df.with_column(cleaned.into_series()).unwrap();
+                sanitized_columns.push(SanitizedColumn {
+                    column: c.clone(),
+                    rows,
+                });
+            }
+        }
+    }
+    Ok((df, sanitized_columns))
+}
+
+fn is_malformed(s: &str, is_iri: bool) -> bool {
+    std::str::from_utf8(s.as_bytes()).is_err() || s.chars().any(|c| is_disallowed(c, is_iri))
+}
+
+fn is_disallowed(c: char, is_iri: bool) -> bool {
+    c.is_control() && (is_iri || !matches!(c, '\t' | '\n' | '\r'))
+}
+
+fn clean(s: &str, is_iri: bool) -> String {
+    String::from_utf8_lossy(s.as_bytes())
+        .chars()
+        .filter(|c| !is_disallowed(*c, is_iri))
+        .collect()
+}
+
+pub(crate) fn merge_sanitized_columns(
+    sanitized_columns: &mut Vec<SanitizedColumn>,
+    new_sanitized_columns: Vec<SanitizedColumn>,
+    row_offset: usize,
+) {
+    for mut c in new_sanitized_columns {
+        c.rows.iter_mut().for_each(|r| *r += row_offset);
+        if let Some(existing) = sanitized_columns.iter_mut().find(|x| x.column == c.column) {
+            existing.rows.extend(c.rows);
+        } else {
+            sanitized_columns.push(c);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::{ExpandOptions, Mapping, PrimitiveColumn};
+use crate::ast::Signature;
+use crate::mapping::errors::MappingError;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::DataType;
+use std::collections::HashMap;
+
+//The typing of the columns of the last validated frame of a template
+#[derive(Clone)]
+pub(crate) struct SchemaCacheEntry {
+    schema: Vec<(String, DataType)>,
+    language_tags: Option<HashMap<String, String>>,
+    columns: HashMap<String, PrimitiveColumn>,
+}
+
+impl Mapping {
+    /// Forgets the column typing kept for expansions with trust_schema_cache.
+    pub fn clear_schema_cache(&mut self) {
+        self.schema_cache.clear();
+    }
+
+    //With trust_schema_cache, a frame with the same column names and types as the last validated frame of the
+    //template is not validated again, and the typing of its columns is reused
+    pub(crate) fn validate_infer_columns_cached(
+        &mut self,
+        signature: &Signature,
+        df: &DataFrame,
+        options: &ExpandOptions,
+    ) -> Result<HashMap<String, PrimitiveColumn>, MappingError> {
+        if !options.trust_schema_cache {
+            return self.validate_infer_dataframe_columns(signature, df, options);
+        }
+        let template_name = signature.template_name.as_str();
+        let schema: Vec<(String, DataType)> = df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_string(), s.dtype().clone()))
+            .collect();
+        if let Some(entry) = self.schema_cache.get(template_name) {
+            if entry.schema == schema && entry.language_tags == options.language_tags {
+                return Ok(entry.columns.clone());
+            }
+        }
+        let columns = self.validate_infer_dataframe_columns(signature, df, options)?;
+        self.schema_cache.insert(
+            template_name.to_string(),
+            SchemaCacheEntry {
+                schema,
+                language_tags: options.language_tags.clone(),
+                columns: columns.clone(),
+            },
+        );
+        Ok(columns)
+    }
+}
@@ -0,0 +1,41 @@
+use super::Mapping;
+use uuid::Uuid;
+
+impl Mapping {
+    /// When set, the UUIDs of template names, calls and blank nodes generated by the mapping are derived from the seed,
+    /// so that expanding the same templates with the same data in the same order gives the same output.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        self.generated_uuids = 0;
+    }
+
+    pub(crate) fn new_uuid(&mut self) -> Uuid {
+        if let Some(seed) = self.seed {
+            self.generated_uuids += 1;
+            Uuid::new_v5(
+                &Uuid::NAMESPACE_OID,
+                format!("{}:{}", seed, self.generated_uuids).as_bytes(),
+            )
+        } else {
+            Uuid::new_v4()
+        }
+    }
+}
+
+//The blank nodes of an instance are named by the expansion and the position of the instance in it,
+//as instances are expanded in parallel
+pub(crate) fn blank_node_call_id(
+    expansion_id: &str,
+    pattern_path: &Vec<(String, usize)>,
+    template_name: &str,
+) -> String {
+    let mut name = expansion_id.to_string();
+    for (t, i) in pattern_path {
+        name.push_str(&format!(" {} {}", t, i));
+    }
+    name.push(' ');
+    name.push_str(template_name);
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes())
+        .simple()
+        .to_string()
+}
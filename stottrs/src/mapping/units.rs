@@ -0,0 +1,82 @@
+use super::{numeric_datatypes, Mapping, PrimitiveColumn, RDFNodeType};
+use crate::ast::PType;
+use crate::mapping::errors::MappingError;
+use oxrdf::NamedNode;
+use std::collections::HashMap;
+
+/// A QUDT unit of measure of the values of a numeric parameter, see https://qudt.org
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitAnnotation {
+    /// E.g. http://qudt.org/vocab/unit/DEG_C
+    pub unit: NamedNode,
+    pub mode: UnitMode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitMode {
+    /// For each triple with a value of the parameter as object, the triple (subject, qudt:unit, unit) is added.
+    /// The subject is then the quantity value, e.g. in ottr:Triple(?quantity, qudt:numericValue, ?value).
+    UnitTriple,
+    /// The values are literals with the unit as datatype.
+    Datatype,
+}
+
+impl Mapping {
+    /// Declares the unit of a parameter, used whenever the template is expanded, also when it is used by other templates.
+    pub fn declare_unit(
+        &mut self,
+        template: &str,
+        parameter: &str,
+        unit: UnitAnnotation,
+    ) -> Result<(), MappingError> {
+        let target_template = self.resolve_template(template)?;
+        let template_name = target_template.signature.template_name.as_str().to_string();
+        let p = target_template
+            .signature
+            .parameter_list
+            .iter()
+            .find(|p| p.stottr_variable.name == parameter)
+            .ok_or_else(|| {
+                MappingError::UnknownUnitParameter(template_name.clone(), parameter.to_string())
+            })?;
+        if let Some(ptype) = &p.ptype {
+            if !is_numeric(ptype) {
+                return Err(MappingError::UnitForNonNumericParameter(
+                    template_name,
+                    parameter.to_string(),
+                    ptype.clone(),
+                ));
+            }
+        }
+        self.template_units
+            .entry(template_name)
+            .or_default()
+            .insert(parameter.to_string(), unit);
+        Ok(())
+    }
+
+    //The units of the parameters of the template follow the columns into the templates it uses
+    pub(crate) fn add_units(
+        &self,
+        template_name: &str,
+        dynamic_columns: &mut HashMap<String, PrimitiveColumn>,
+    ) {
+        if let Some(units) = self.template_units.get(template_name) {
+            for (parameter, unit) in units {
+                if let Some(c) = dynamic_columns.get_mut(parameter) {
+                    if unit.mode == UnitMode::Datatype {
+                        c.rdf_node_type = RDFNodeType::Literal(unit.unit.clone());
+                    }
+                    c.unit = Some(unit.clone());
+                }
+            }
+        }
+    }
+}
+
+fn is_numeric(ptype: &PType) -> bool {
+    match ptype {
+        PType::BasicType(b, _) => numeric_datatypes().any(|n| n == b.as_ref()),
+        PType::LUBType(l) | PType::ListType(l) | PType::NEListType(l) => is_numeric(l),
+    }
+}
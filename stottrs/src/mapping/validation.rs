@@ -0,0 +1,116 @@
+use super::{ExpandOptions, Mapping};
+use crate::ast::PType;
+use crate::mapping::coercion::coerce_columns;
+use crate::mapping::derived_ids::add_derived_id_columns;
+use crate::mapping::duplicate_columns::resolve_duplicate_columns;
+use crate::mapping::errors::MappingError;
+use crate::mapping::language_tag_columns::add_language_tag_columns;
+use crate::mapping::validation_inference::{fill_default_values, validate_infer_columns};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::DataType;
+
+/// The problems that would make an expansion of the rows fail.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<MappingError>,
+    /// Parameters without a column, which are also among the errors.
+    pub missing_columns: Vec<String>,
+    /// Columns that are not parameters of the template, which are also among the errors.
+    pub extra_columns: Vec<String>,
+    /// Columns with a data type that does not fit the type of the parameter, which are also among the errors.
+    pub type_mismatches: Vec<TypeMismatch>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeMismatch {
+    pub column: String,
+    pub data_type: DataType,
+    pub ptype: PType,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Mapping {
+    /// Checks the columns of the rows against the signature of the template, i.e. missing and irrelevant columns,
+    /// data types, language tags, nulls for non-optional parameters and blank nodes for non-blank parameters,
+    /// without expanding the template. All problems are reported, where expand fails on the first one.
+    pub fn validate(
+        &self,
+        template: &str,
+        df: &DataFrame,
+        options: &ExpandOptions,
+    ) -> Result<ValidationReport, MappingError> {
+        let target_template = self.resolve_template(template)?;
+        let mut report = ValidationReport::default();
+        //The columns are prepared as in expand, problems here leave no columns to check
+        let df = resolve_duplicate_columns(df.clone(), &options.duplicate_columns);
+        let df = df.and_then(|df| {
+            if let Some(derived_ids) = &options.derived_ids {
+                add_derived_id_columns(df, derived_ids)
+            } else {
+                Ok(df)
+            }
+        });
+        let df = df.and_then(|df| {
+            if let Some(language_tag_columns) = &options.language_tag_columns {
+                add_language_tag_columns(df, language_tag_columns)
+            } else {
+                Ok(df)
+            }
+        });
+        let df = df.and_then(|df| fill_default_values(&target_template.signature, df));
+        let df = df.and_then(|df| {
+            if let Some(coercion) = &options.coercion {
+                coerce_columns(df, &target_template.signature, coercion)
+            } else {
+                Ok(df)
+            }
+        });
+        match df {
+            Ok(df) => {
+                let (_, errors) = validate_infer_columns(&target_template.signature, &df, options);
+                report.errors = errors;
+            }
+            Err(e) => report.errors.push(e),
+        }
+        report.summarize_errors();
+        Ok(report)
+    }
+
+    /// Checks the columns of the rows against the signature of the template with the default expand options,
+    /// so that pipelines can fail fast on missing and extra columns and type mismatches, see validate.
+    pub fn validate_dataframe(
+        &self,
+        template: &str,
+        df: &DataFrame,
+    ) -> Result<ValidationReport, MappingError> {
+        self.validate(template, df, &ExpandOptions::default())
+    }
+}
+
+impl ValidationReport {
+    fn summarize_errors(&mut self) {
+        for e in &self.errors {
+            match e {
+                MappingError::MissingParameterColumn(c) => self.missing_columns.push(c.clone()),
+                MappingError::ContainsIrrelevantColumns(cs) => {
+                    let mut cs = cs.clone();
+                    cs.sort();
+                    self.extra_columns.extend(cs);
+                }
+                MappingError::ColumnDataTypeMismatch(column, data_type, ptype) => {
+                    self.type_mismatches.push(TypeMismatch {
+                        column: column.clone(),
+                        data_type: data_type.clone(),
+                        ptype: ptype.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+}
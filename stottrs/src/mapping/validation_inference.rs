@@ -1,12 +1,17 @@
 use super::Mapping;
-use crate::ast::{PType, Parameter, Signature};
+use crate::ast::{ConstantLiteral, ConstantTerm, PType, Parameter, Signature};
+use crate::mapping::constant_terms::constant_to_expr;
 use crate::mapping::errors::MappingError;
-use crate::mapping::{ExpandOptions, PrimitiveColumn, RDFNodeType};
-use oxrdf::vocab::xsd;
-use oxrdf::NamedNode;
+use crate::mapping::language_tag_columns::{
+    is_language_tag_column_name, language_tag_column_name,
+};
+use crate::mapping::{default_constant_term, ExpandOptions, PrimitiveColumn, RDFNodeType};
+use oxrdf::vocab::{rdf, xsd};
+use oxrdf::{NamedNode, NamedNodeRef};
 use polars_core::export::rayon::prelude::ParallelIterator;
+use polars::prelude::{col, lit, when, Expr, IntoLazy, LiteralValue};
 use polars_core::frame::DataFrame;
-use polars_core::prelude::{DataType};
+use polars_core::prelude::{DataType, NamedFrom, Series};
 use std::collections::{HashMap, HashSet};
 use polars_core::datatypes::BooleanChunked;
 
@@ -17,45 +22,132 @@ impl Mapping {
         df: &DataFrame,
         options: &ExpandOptions,
     ) -> Result<HashMap<String, PrimitiveColumn>, MappingError> {
-        let mut df_columns = HashSet::new();
-        df_columns.extend(df.get_column_names().into_iter().map(|x| x.to_string()));
+        let (map, mut errors) = validate_infer_columns(signature, df, options);
+        if errors.is_empty() {
+            Ok(map)
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+}
 
-        let mut map = HashMap::new();
-        for parameter in &signature.parameter_list {
-            let variable_name = &parameter.stottr_variable.name;
-            if df_columns.contains(variable_name.as_str()) {
-                df_columns.remove(variable_name.as_str());
-                if !parameter.optional {
-                    validate_non_optional_parameter(&df, variable_name)?;
+//All problems with the columns are found, in the order of the parameters
+pub(crate) fn validate_infer_columns(
+    signature: &Signature,
+    df: &DataFrame,
+    options: &ExpandOptions,
+) -> (HashMap<String, PrimitiveColumn>, Vec<MappingError>) {
+    let mut df_columns = HashSet::new();
+    df_columns.extend(df.get_column_names().into_iter().map(|x| x.to_string()));
+
+    let mut map = HashMap::new();
+    let mut errors = vec![];
+    for parameter in &signature.parameter_list {
+        let variable_name = &parameter.stottr_variable.name;
+        if df_columns.contains(variable_name.as_str()) {
+            df_columns.remove(variable_name.as_str());
+            if !parameter.optional {
+                if let Err(e) = validate_non_optional_parameter(&df, variable_name) {
+                    errors.push(e);
                 }
-                if parameter.non_blank {
-                    //TODO handle blanks;
-                    validate_non_blank_parameter(&df, variable_name)?;
+            }
+            if parameter.non_blank {
+                if let Err(e) =
+                    validate_non_blank_series(df.column(variable_name).unwrap(), variable_name)
+                {
+                    errors.push(e);
                 }
-                let column_data_type = validate_infer_column_data_type(
-                    df,
-                    &parameter,
-                    variable_name,
-                    &options.language_tags
-                )?;
-
-                map.insert(
-                    variable_name.to_string(),
-                    column_data_type,
-                );
-            } else {
-                return Err(MappingError::MissingParameterColumn(
-                    variable_name.to_string(),
-                ));
             }
-        }
-        if !df_columns.is_empty() {
-            return Err(MappingError::ContainsIrrelevantColumns(
-                df_columns.iter().map(|x| x.to_string()).collect(),
+            //Tags are given in a column, or from a language tagged default value
+            let has_language_tag_column = df
+                .column(&language_tag_column_name(variable_name))
+                .is_ok();
+            match validate_infer_column_data_type(
+                df,
+                &parameter,
+                variable_name,
+                &options.language_tags,
+                has_language_tag_column,
+            ) {
+                Ok(column_data_type) => {
+                    map.insert(variable_name.to_string(), column_data_type);
+                }
+                Err(e) => errors.push(e),
+            }
+        } else {
+            errors.push(MappingError::MissingParameterColumn(
+                variable_name.to_string(),
             ));
         }
-        Ok(map)
     }
+    if let Some(language_tag_columns) = &options.language_tag_columns {
+        for tag_column in language_tag_columns.values() {
+            df_columns.remove(tag_column);
+        }
+    }
+    df_columns.retain(|c| !is_language_tag_column_name(c));
+    if !df_columns.is_empty() {
+        errors.push(MappingError::ContainsIrrelevantColumns(
+            df_columns.iter().map(|x| x.to_string()).collect(),
+        ));
+    }
+    (map, errors)
+}
+
+//Missing columns and null values are filled with the default values of the parameters
+pub(crate) fn fill_default_values(
+    signature: &Signature,
+    df: DataFrame,
+) -> Result<DataFrame, MappingError> {
+    let df_columns: HashSet<String> = df
+        .get_column_names()
+        .into_iter()
+        .map(|x| x.to_string())
+        .collect();
+    let mut expressions = vec![];
+    for parameter in &signature.parameter_list {
+        if let Some(default_term) = default_constant_term(parameter) {
+            let variable_name = &parameter.stottr_variable.name;
+            let (default_expr, _, _, default_language_tag) = constant_to_expr(default_term, &None)?;
+            //The rows that get a language tagged default value get its tag in the column of tags
+            if let Some(tag) = default_language_tag {
+                let tag_column = language_tag_column_name(variable_name);
+                if !df_columns.contains(variable_name) {
+                    expressions.push(lit(tag).alias(&tag_column));
+                } else if df.column(variable_name).unwrap().null_count() > 0 {
+                    let existing_tags = if df_columns.contains(&tag_column) {
+                        col(&tag_column)
+                    } else {
+                        Expr::Literal(LiteralValue::Null).cast(DataType::Utf8)
+                    };
+                    expressions.push(
+                        when(col(variable_name).is_null())
+                            .then(lit(tag))
+                            .otherwise(existing_tags)
+                            .alias(&tag_column),
+                    );
+                }
+            }
+            if !df_columns.contains(variable_name) {
+                expressions.push(default_expr.alias(variable_name));
+            } else if df.column(variable_name).unwrap().null_count() > 0 {
+                expressions.push(
+                    when(col(variable_name).is_null())
+                        .then(default_expr)
+                        .otherwise(col(variable_name))
+                        .alias(variable_name),
+                );
+            }
+        }
+    }
+    if expressions.is_empty() {
+        return Ok(df);
+    }
+    let mut lf = df.lazy();
+    for e in expressions {
+        lf = lf.with_column(e);
+    }
+    Ok(lf.collect().unwrap())
 }
 
 fn validate_infer_column_data_type(
@@ -63,6 +155,7 @@ fn validate_infer_column_data_type(
     parameter: &Parameter,
     column_name: &str,
     language_tag_map: &Option<HashMap<String,String>>,
+    has_language_tag_column: bool,
 ) -> Result<PrimitiveColumn, MappingError> {
     let series = dataframe.column(column_name).unwrap();
     let dtype = series.dtype();
@@ -74,6 +167,12 @@ fn validate_infer_column_data_type(
         target_ptype
     };
     let rdf_node_type = infer_rdf_node_type(&ptype);
+    if has_language_tag_column && !rdf_node_type.is_lit_type(xsd::STRING) {
+        return Err(MappingError::LanguageTagColumnForNonStringColumn(
+            column_name.to_string(),
+            ptype,
+        ));
+    }
     let language_tag = if let Some(map) = language_tag_map {
         if let Some(tag) = map.get(column_name) {
             Some(tag.clone())
@@ -83,14 +182,34 @@ fn validate_infer_column_data_type(
     } else {
         None
     };
-    Ok(PrimitiveColumn { rdf_node_type, language_tag })
+    if is_lang_string(&ptype) && language_tag.is_none() && !has_language_tag_column {
+        return Err(MappingError::MissingLanguageTagForLangString(
+            column_name.to_string(),
+        ));
+    }
+    Ok(PrimitiveColumn {
+        rdf_node_type,
+        language_tag,
+        has_language_tag_column,
+        unit: None,
+    })
+}
+
+fn is_lang_string(ptype: &PType) -> bool {
+    match ptype {
+        PType::BasicType(b, _) => b.as_ref() == rdf::LANG_STRING,
+        PType::LUBType(l) | PType::ListType(l) | PType::NEListType(l) => is_lang_string(l),
+    }
 }
 
-fn infer_rdf_node_type(ptype: &PType) -> RDFNodeType {
+pub(crate) fn infer_rdf_node_type(ptype: &PType) -> RDFNodeType {
     match ptype {
         PType::BasicType(b, _) => {
             if b.as_str() == xsd::ANY_URI {
                 RDFNodeType::IRI
+            } else if b.as_ref() == rdf::LANG_STRING {
+                //The language tag is stored next to the string
+                RDFNodeType::Literal(xsd::STRING.into_owned())
             } else {
                 RDFNodeType::Literal(b.clone())
             }
@@ -114,10 +233,17 @@ fn validate_non_optional_parameter(df: &DataFrame, column_name: &str) -> Result<
     }
 }
 
-fn validate_non_blank_parameter(df: &DataFrame, column_name: &str) -> Result<(), MappingError> {
-    let is_blank_node_mask: BooleanChunked = df
-        .column(column_name)
-        .unwrap()
+pub(crate) fn validate_non_blank_series(
+    series: &Series,
+    column_name: &str,
+) -> Result<(), MappingError> {
+    //Only strings can be blank nodes
+    let series = match series.dtype() {
+        DataType::Utf8 => series.clone(),
+        DataType::Categorical(_) => series.cast(&DataType::Utf8).unwrap(),
+        _ => return Ok(()),
+    };
+    let is_blank_node_mask: BooleanChunked = series
         .utf8()
         .map(move |x| {
             x.par_iter()
@@ -128,15 +254,33 @@ fn validate_non_blank_parameter(df: &DataFrame, column_name: &str) -> Result<(),
     if is_blank_node_mask.any() {
         return Err(MappingError::NonBlankColumnHasBlankNode(
             column_name.to_string(),
-            df.column(column_name)
-                .unwrap()
-                .filter(&is_blank_node_mask)
-                .unwrap(),
+            series.filter(&is_blank_node_mask).unwrap(),
         ));
     }
     Ok(())
 }
 
+pub(crate) fn validate_non_blank_constant(
+    constant_term: &ConstantTerm,
+    column_name: &str,
+) -> Result<(), MappingError> {
+    match constant_term {
+        ConstantTerm::Constant(ConstantLiteral::BlankNode(bn)) => {
+            Err(MappingError::NonBlankColumnHasBlankNode(
+                column_name.to_string(),
+                Series::new(column_name, [format!("_:{}", bn.as_str())]),
+            ))
+        }
+        ConstantTerm::Constant(_) => Ok(()),
+        ConstantTerm::ConstantList(inner) => {
+            for ct in inner {
+                validate_non_blank_constant(ct, column_name)?;
+            }
+            Ok(())
+        }
+    }
+}
+
 fn validate_datatype(
     column_name: &str,
     datatype: &DataType,
@@ -172,22 +316,67 @@ fn validate_datatype(
     }
 }
 
+//Integer and decimal literals can not be created from columns of other types
 fn validate_basic_datatype(column_name:&str, datatype: &DataType, rdf_datatype: &NamedNode) -> Result<(), MappingError> {
-    // match rdf_datatype.as_ref() {
-    //     xsd::INT => {
-    //         Ok(());
-    //
-    //     }
-    // }
-    Ok(())
+    let rdf_datatype_ref = rdf_datatype.as_ref();
+    let valid = if INTEGER_DATATYPES.contains(&rdf_datatype_ref) {
+        is_integer(datatype) || datatype == &DataType::Null
+    } else if rdf_datatype_ref == xsd::DECIMAL {
+        is_integer(datatype)
+            || matches!(datatype, DataType::Float32 | DataType::Float64 | DataType::Null)
+    } else {
+        true
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(MappingError::ColumnDataTypeMismatch(
+            column_name.to_string(),
+            datatype.clone(),
+            PType::BasicType(rdf_datatype.clone(), "".to_string()),
+        ))
+    }
 }
 
+pub(crate) const INTEGER_DATATYPES: [NamedNodeRef<'static>; 13] = [
+    xsd::INTEGER,
+    xsd::LONG,
+    xsd::INT,
+    xsd::SHORT,
+    xsd::BYTE,
+    xsd::NON_NEGATIVE_INTEGER,
+    xsd::POSITIVE_INTEGER,
+    xsd::NON_POSITIVE_INTEGER,
+    xsd::NEGATIVE_INTEGER,
+    xsd::UNSIGNED_LONG,
+    xsd::UNSIGNED_INT,
+    xsd::UNSIGNED_SHORT,
+    xsd::UNSIGNED_BYTE,
+];
+
+fn is_integer(datatype: &DataType) -> bool {
+    matches!(
+        datatype,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
 
 pub fn polars_datatype_to_xsd_datatype(datatype: &DataType) -> PType {
     let xsd_nn_ref = match datatype {
         DataType::Boolean => xsd::BOOLEAN,
+        DataType::UInt8 => xsd::UNSIGNED_BYTE,
+        DataType::UInt16 => xsd::UNSIGNED_SHORT,
         DataType::UInt32 => xsd::UNSIGNED_INT,
         DataType::UInt64 => xsd::UNSIGNED_LONG,
+        DataType::Int8 => xsd::BYTE,
+        DataType::Int16 => xsd::SHORT,
         DataType::Int32 => xsd::INT,
         DataType::Int64 => xsd::LONG,
         DataType::Float32 => xsd::FLOAT,
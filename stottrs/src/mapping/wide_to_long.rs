@@ -0,0 +1,200 @@
+use super::{ExpandOptions, Mapping, MappingReport};
+use crate::ast::{
+    Argument, ConstantLiteral, ConstantTerm, Instance, PType, Parameter, Signature, StottrTerm,
+    StottrVariable, Template,
+};
+use crate::constants::{DEFAULT_PREDICATE_URI_PREFIX, DEFAULT_TEMPLATE_PREFIX, OTTR_TRIPLE};
+use crate::mapping::errors::MappingError;
+use oxrdf::vocab::xsd;
+use oxrdf::{BlankNode, NamedNode};
+use polars::prelude::{col, lit, IntoLazy};
+use polars_core::frame::DataFrame;
+use std::collections::HashMap;
+
+const ID_COLUMN: &str = "id";
+const TIMESTAMP_COLUMN: &str = "timestamp";
+const PROPERTY_COLUMN: &str = "property";
+const VALUE_COLUMN: &str = "value";
+
+/// Describes a wide table with an id column, a timestamp column and one value column per property,
+/// such as a table of sensor readings.
+pub struct WideToLong {
+    pub id_column: String,
+    pub timestamp_column: String,
+    /// Template for each value column, called with the columns id, timestamp and value,
+    /// and property when the template has such a parameter.
+    pub templates: HashMap<String, String>,
+    /// Value columns without a template are expanded as data points of the predicate given by this prefix and the column name.
+    pub predicate_prefix: Option<String>,
+}
+
+impl Mapping {
+    /// Melts the wide table into one long table of (id, timestamp, property, value) for each value column,
+    /// and expands each of them with the template of the property.
+    /// The values keep the data types of their columns, rows where the value is null are skipped.
+    pub fn expand_wide(
+        &mut self,
+        df: DataFrame,
+        wide_to_long: &WideToLong,
+        options: ExpandOptions,
+    ) -> Result<MappingReport, MappingError> {
+        let mut report = MappingReport::default();
+        for (property, long_df) in melt_wide(df, wide_to_long)? {
+            let template_name = if let Some(t) = wide_to_long.templates.get(&property) {
+                t.clone()
+            } else {
+                self.data_point_template(&property, &wide_to_long.predicate_prefix)?
+            };
+            let parameters: Vec<String> = self
+                .resolve_template(&template_name)?
+                .signature
+                .parameter_list
+                .iter()
+                .map(|p| p.stottr_variable.name.clone())
+                .collect();
+            let long_df = if parameters.iter().any(|p| p == PROPERTY_COLUMN) {
+                long_df
+            } else {
+                long_df.drop(PROPERTY_COLUMN).unwrap()
+            };
+            //The row limit is shared by the long tables of all properties
+            let mut property_options = options.clone();
+            property_options.row_limit = options
+                .row_limit
+                .map(|l| l.saturating_sub(report.rows_expanded));
+            let mut property_report = self.expand(&template_name, long_df, property_options)?;
+            //Rows of sanitized columns are counted in the long table of the property
+            for c in &mut property_report.sanitized_columns {
+                if c.column == VALUE_COLUMN {
+                    c.column = property.clone();
+                }
+            }
+            report.merge(property_report, 0);
+            if report.stopped.is_some() {
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    //Data points of the property are blank nodes with the timestamp and value
+    fn data_point_template(
+        &mut self,
+        property: &str,
+        predicate_prefix: &Option<String>,
+    ) -> Result<String, MappingError> {
+        let predicate_prefix = predicate_prefix
+            .as_deref()
+            .unwrap_or(DEFAULT_PREDICATE_URI_PREFIX);
+        let predicate = |name: &str| {
+            StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::IRI(
+                NamedNode::new_unchecked(name),
+            )))
+        };
+        let data_point =
+            StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::BlankNode(
+                BlankNode::new_unchecked("dataPoint"),
+            )));
+        let variable = |name: &str| {
+            StottrTerm::Variable(StottrVariable {
+                name: name.to_string(),
+            })
+        };
+        let triple = |subject: StottrTerm, verb: StottrTerm, object: StottrTerm| Instance {
+            list_expander: None,
+            template_name: NamedNode::new_unchecked(OTTR_TRIPLE),
+            prefixed_template_name: "ottr:Triple".to_string(),
+            argument_list: [subject, verb, object]
+                .into_iter()
+                .map(|term| Argument {
+                    parameter: None,
+                    list_expand: false,
+                    term,
+                })
+                .collect(),
+        };
+        let parameter = |name: &str, ptype: Option<PType>| Parameter {
+            optional: false,
+            non_blank: false,
+            ptype,
+            stottr_variable: StottrVariable {
+                name: name.to_string(),
+            },
+            default_value: None,
+        };
+        let template_uuid = self.new_uuid().to_string();
+        let template_name = format!("{}{}", DEFAULT_TEMPLATE_PREFIX, &template_uuid);
+        let template = Template {
+            signature: Signature {
+                template_name: NamedNode::new(template_name.clone())?,
+                template_prefixed_name: format!("prefix:{}", template_uuid),
+                parameter_list: vec![
+                    parameter(
+                        ID_COLUMN,
+                        Some(PType::BasicType(
+                            xsd::ANY_URI.into_owned(),
+                            "xsd:anyURI".to_string(),
+                        )),
+                    ),
+                    parameter(TIMESTAMP_COLUMN, None),
+                    parameter(VALUE_COLUMN, None),
+                ],
+                annotation_list: None,
+            },
+            pattern_list: vec![
+                triple(
+                    variable(ID_COLUMN),
+                    predicate(&format!("{}{}", predicate_prefix, property)),
+                    data_point.clone(),
+                ),
+                triple(
+                    data_point.clone(),
+                    predicate(&format!("{}{}", predicate_prefix, TIMESTAMP_COLUMN)),
+                    variable(TIMESTAMP_COLUMN),
+                ),
+                triple(
+                    data_point,
+                    predicate(&format!("{}{}", predicate_prefix, VALUE_COLUMN)),
+                    variable(VALUE_COLUMN),
+                ),
+            ],
+        };
+        self.upsert_template(template)?;
+        Ok(template_name)
+    }
+}
+
+/// One long table with the columns id, timestamp, property and value for each value column of the wide table.
+pub fn melt_wide(
+    df: DataFrame,
+    wide_to_long: &WideToLong,
+) -> Result<Vec<(String, DataFrame)>, MappingError> {
+    for c in [&wide_to_long.id_column, &wide_to_long.timestamp_column] {
+        if df.column(c).is_err() {
+            return Err(MappingError::MissingParameterColumn(c.clone()));
+        }
+    }
+    let value_columns: Vec<String> = df
+        .get_column_names()
+        .into_iter()
+        .filter(|c| *c != wide_to_long.id_column && *c != wide_to_long.timestamp_column)
+        .map(|c| c.to_string())
+        .collect();
+    let mut long_dfs = vec![];
+    for c in value_columns {
+        let long_df = df
+            .clone()
+            .lazy()
+            .select([
+                col(&wide_to_long.id_column).alias(ID_COLUMN),
+                col(&wide_to_long.timestamp_column).alias(TIMESTAMP_COLUMN),
+                col(&c).alias(VALUE_COLUMN),
+            ])
+            .filter(col(VALUE_COLUMN).is_not_null())
+            .with_column(lit(c.as_str()).alias(PROPERTY_COLUMN))
+            .collect()
+            .unwrap();
+        long_dfs.push((c, long_df));
+    }
+    Ok(long_dfs)
+}
@@ -7,13 +7,13 @@ use nom::branch::alt;
 use nom::bytes::complete::{escaped, is_not, tag};
 use nom::character::complete::char as char_func;
 
-use crate::ast::{Directive, ListExpanderType, Prefix, StottrVariable};
+use crate::ast::{Directive, ExpressionOperator, ListExpanderType, Prefix, StottrVariable};
 use crate::parsing::parsing_ast::{
     PrefixedName, ResolvesToNamedNode, UnresolvedAnnotation, UnresolvedArgument,
     UnresolvedBaseTemplate, UnresolvedConstantLiteral, UnresolvedConstantTerm,
     UnresolvedDefaultValue, UnresolvedInstance, UnresolvedPType, UnresolvedParameter,
-    UnresolvedSignature, UnresolvedStatement, UnresolvedStottrDocument, UnresolvedStottrLiteral,
-    UnresolvedStottrTerm, UnresolvedTemplate,
+    UnresolvedSignature, UnresolvedStatement, UnresolvedStottrDocument, UnresolvedStottrExpression,
+    UnresolvedStottrLiteral, UnresolvedStottrTerm, UnresolvedTemplate,
 };
 use nom::character::complete::{alpha1, alphanumeric1, digit0, digit1, multispace0, one_of};
 use nom::combinator::opt;
@@ -185,22 +185,96 @@ fn argument_list(a: &str) -> IResult<&str, Vec<UnresolvedArgument>> {
 }
 
 fn argument(a: &str) -> IResult<&str, UnresolvedArgument> {
-    let (a, (_, list_expand, term, _)) =
-        tuple((multispace0, opt(list_expand), term, multispace0))(a)?;
+    let (a, (_, parameter, list_expand, term, _)) = tuple((
+        multispace0,
+        opt(named_argument_parameter),
+        opt(list_expand),
+        term,
+        multispace0,
+    ))(a)?;
     Ok((
         a,
         UnresolvedArgument {
+            parameter,
             list_expand: list_expand.is_some(),
             term,
         },
     ))
 }
 
+//Arguments may be given by the name of their parameter, as in ex:T(?label = "a")
+fn named_argument_parameter(n: &str) -> IResult<&str, String> {
+    let (n, (variable, _, _, _)) = tuple((variable, multispace0, tag("="), multispace0))(n)?;
+    Ok((n, variable.name))
+}
+
 fn term(t: &str) -> IResult<&str, UnresolvedStottrTerm> {
-    let (t, term) = alt((variable_as_term, constant_term_as_term, list_as_term))(t)?;
+    let (t, term) = alt((
+        expression_as_term,
+        variable_as_term,
+        constant_term_as_term,
+        list_as_term,
+    ))(t)?;
     Ok((t, term))
 }
 
+fn expression_as_term(e: &str) -> IResult<&str, UnresolvedStottrTerm> {
+    let (e, (first, rest)) = tuple((
+        expression_operand,
+        many1(tuple((
+            multispace0,
+            expression_operator,
+            multispace0,
+            expression_operand,
+        ))),
+    ))(e)?;
+    let mut operands = vec![first];
+    let mut operators = vec![];
+    for (_, operator, _, operand) in rest {
+        operators.push(operator);
+        operands.push(operand);
+    }
+    //Operators are left associative, and are combined in order of precedence
+    for precedence in [2, 1, 0] {
+        let mut i = 0;
+        while i < operators.len() {
+            if operators[i].precedence() == precedence {
+                let operator = operators.remove(i);
+                let right = operands.remove(i + 1);
+                let left = operands.remove(i);
+                operands.insert(
+                    i,
+                    UnresolvedStottrTerm::Expression(Box::new(UnresolvedStottrExpression {
+                        left,
+                        operator,
+                        right,
+                    })),
+                );
+            } else {
+                i += 1;
+            }
+        }
+    }
+    Ok((e, operands.remove(0)))
+}
+
+fn expression_operand(o: &str) -> IResult<&str, UnresolvedStottrTerm> {
+    let (o, operand) = alt((variable_as_term, constant_term_as_term))(o)?;
+    Ok((o, operand))
+}
+
+fn expression_operator(o: &str) -> IResult<&str, ExpressionOperator> {
+    let (o, operator) = alt((tag("*"), tag("/"), tag("+"), tag("-"), tag("||")))(o)?;
+    let operator = match operator {
+        "*" => ExpressionOperator::Multiply,
+        "/" => ExpressionOperator::Divide,
+        "+" => ExpressionOperator::Add,
+        "-" => ExpressionOperator::Subtract,
+        _ => ExpressionOperator::Concat,
+    };
+    Ok((o, operator))
+}
+
 fn variable_as_term(v: &str) -> IResult<&str, UnresolvedStottrTerm> {
     let (v, var) = variable(v)?;
     Ok((v, UnresolvedStottrTerm::Variable(var)))
@@ -682,17 +756,24 @@ fn sparql_base_as_directive(s: &str) -> IResult<&str, Directive> {
 }
 
 fn sparql_base(s: &str) -> IResult<&str, NamedNode> {
-    let (s, (_, nn)) = tuple((tag("BASE"), iri_ref))(s)?;
+    let (s, (_, _, nn)) = tuple((tag("BASE"), multispace0, absolute_iri_ref))(s)?;
     Ok((s, nn))
 }
 
 fn sparql_prefix(s: &str) -> IResult<&str, Prefix> {
-    let (s, (_, name, iri)) = tuple((tag("PREFIX"), pname_ns, iri_ref))(s)?;
+    let (s, (_, name, iri)) = tuple((tag("PREFIX"), pname_ns, absolute_iri_ref))(s)?;
     Ok((s, Prefix { name, iri }))
 }
 
 fn base(b: &str) -> IResult<&str, NamedNode> {
-    let (b, (_, nn)) = tuple((tag("BASE"), iri_ref))(b)?;
+    let (b, (_, _, _, nn, _, _)) = tuple((
+        multispace0,
+        tag("@base"),
+        multispace0,
+        absolute_iri_ref,
+        multispace0,
+        tag("."),
+    ))(b)?;
     Ok((b, nn))
 }
 
@@ -703,7 +784,7 @@ fn prefix_id(p: &str) -> IResult<&str, Prefix> {
         multispace0,
         pname_ns,
         multispace0,
-        iri_ref,
+        absolute_iri_ref,
         tag("."),
     ))(p)?;
     Ok((p, Prefix { name, iri }))
@@ -725,22 +806,32 @@ fn prefixed_name(p: &str) -> IResult<&str, PrefixedName> {
 }
 
 fn iri_ref_as_resolves(i: &str) -> IResult<&str, ResolvesToNamedNode> {
-    let (i, nn) = iri_ref(i)?;
-    Ok((i, ResolvesToNamedNode::NamedNode(nn)))
+    let (i, iri) = iri_ref(i)?;
+    //Relative IRIs are resolved against the base of the document
+    let rtnn = match NamedNode::new(&iri) {
+        Ok(nn) => ResolvesToNamedNode::NamedNode(nn),
+        Err(_) => ResolvesToNamedNode::RelativeIRI(iri),
+    };
+    Ok((i, rtnn))
 }
 
-fn iri_ref(i: &str) -> IResult<&str, NamedNode> {
+fn absolute_iri_ref(i: &str) -> IResult<&str, NamedNode> {
+    let (i, iri) = iri_ref(i)?;
+    let nn = NamedNode::new(iri).expect("Invalid IRI");
+    Ok((i, nn))
+}
+
+fn iri_ref(i: &str) -> IResult<&str, String> {
     let notin = r#" 
  <>"{}|^`\"#;
     let (i, (_, iri, _)) = tuple((tag("<"), many0(is_not(notin)), tag(">")))(i)?;
-    let nn = NamedNode::new(
+    Ok((
+        i,
         iri.iter()
             .map(|x| x.to_string())
             .collect::<Vec<String>>()
             .join(""),
-    )
-    .expect("Invalid IRI");
-    Ok((i, nn))
+    ))
 }
 
 fn pname_ns_as_prefixed_name(p: &str) -> IResult<&str, PrefixedName> {
@@ -884,7 +975,23 @@ fn colon_as_string(c: &str) -> IResult<&str, String> {
 fn test_iri_ref() {
     let s = "<http://example.org#>";
     let (r, nn) = iri_ref(s).finish().expect("Ok");
-    assert_eq!(nn, NamedNode::new_unchecked("http://example.org#"));
+    assert_eq!(nn, "http://example.org#");
+    assert_eq!(r, "");
+}
+
+#[test]
+fn test_relative_iri_ref() {
+    let s = "<thing#a>";
+    let (r, rtnn) = iri_ref_as_resolves(s).finish().expect("Ok");
+    assert_eq!(rtnn, ResolvesToNamedNode::RelativeIRI("thing#a".to_string()));
+    assert_eq!(r, "");
+}
+
+#[test]
+fn test_base() {
+    let s = "@base <http://example.org/base/> .";
+    let (r, nn) = base(s).finish().expect("Ok");
+    assert_eq!(nn, NamedNode::new_unchecked("http://example.org/base/"));
     assert_eq!(r, "");
 }
 
@@ -927,12 +1034,14 @@ fn test_instance() {
         }),
         argument_list: vec![
             UnresolvedArgument {
+                parameter: None,
                 list_expand: false,
                 term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(
                     UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked("person")),
                 )),
             },
             UnresolvedArgument {
+                parameter: None,
                 list_expand: false,
                 term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(
                     UnresolvedConstantLiteral::IRI(ResolvesToNamedNode::PrefixedName(
@@ -944,6 +1053,7 @@ fn test_instance() {
                 )),
             },
             UnresolvedArgument {
+                parameter: None,
                 list_expand: false,
                 term: UnresolvedStottrTerm::Variable(StottrVariable {
                     name: "var".to_string(),
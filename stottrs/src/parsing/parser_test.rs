@@ -77,6 +77,7 @@ fn test_easy_template() {
                 }),
                 argument_list: vec![
                     UnresolvedArgument {
+                        parameter: None,
                         list_expand: false,
                         term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(
                             UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked(
@@ -85,6 +86,7 @@ fn test_easy_template() {
                         )),
                     },
                     UnresolvedArgument {
+                        parameter: None,
                         list_expand: false,
                         term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(
                             UnresolvedConstantLiteral::IRI(ResolvesToNamedNode::PrefixedName(
@@ -809,6 +811,7 @@ fn test_spec_example_1() {
                     name: "template".to_string(),
                 }),
                 argument_list: vec![UnresolvedArgument {
+                    parameter: None,
                     list_expand: false,
                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(
                         vec![UnresolvedConstantTerm::Constant(
@@ -870,6 +873,7 @@ fn test_spec_example_2() {
                     name: "template".to_string(),
                 }),
                 argument_list: vec![UnresolvedArgument {
+                    parameter: None,
                     list_expand: false,
                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(
                         vec![UnresolvedConstantTerm::ConstantList(vec![
@@ -925,6 +929,7 @@ fn test_spec_example_3() {
                     name: "template".to_string(),
                 }),
                 argument_list: vec![UnresolvedArgument {
+                    parameter: None,
                     list_expand: false,
                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(
                         vec![UnresolvedConstantTerm::Constant(
@@ -1028,6 +1033,7 @@ fn test_spec_example_4() {
                             }),
                             argument_list: vec![
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: false,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                         value: "asdf".to_string(),
@@ -1037,6 +1043,7 @@ fn test_spec_example_4() {
                                     }))),
                                 },
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: false,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                         value: "asdf".to_string(),
@@ -1046,6 +1053,7 @@ fn test_spec_example_4() {
                                     }))),
                                 },
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: false,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                         value: "asdf".to_string(),
@@ -1062,6 +1070,7 @@ fn test_spec_example_4() {
                             template_name: ResolvesToNamedNode::NamedNode(NamedNode::new_unchecked("http://asdf" )),
                             argument_list: vec![
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: false,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                         value: "asdf".to_string(),
@@ -1070,6 +1079,7 @@ fn test_spec_example_4() {
                                     }))),
                                 },
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: false,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                         value: "asdf".to_string(),
@@ -1078,6 +1088,7 @@ fn test_spec_example_4() {
                                     }))),
                                 },
                                 UnresolvedArgument {
+                                    parameter: None,
                                     list_expand: true,
                                     term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(vec![
                                         UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
@@ -1112,10 +1123,12 @@ fn test_spec_example_4() {
                     }),
                     argument_list: vec![
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::Variable(StottrVariable { name: "pizza".to_string() }),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: true,
                             term: UnresolvedStottrTerm::Variable(StottrVariable { name: "toppings".to_string() }),
                         },
@@ -1129,6 +1142,7 @@ fn test_spec_example_4() {
                     }),
                     argument_list: vec![
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                 value: "1".to_string(),
@@ -1137,6 +1151,7 @@ fn test_spec_example_4() {
                             }))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                 value: "2".to_string(),
@@ -1145,6 +1160,7 @@ fn test_spec_example_4() {
                             }))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                 value: "4".to_string(),
@@ -1153,6 +1169,7 @@ fn test_spec_example_4() {
                             }))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                 value: "5".to_string(),
@@ -1166,6 +1183,7 @@ fn test_spec_example_4() {
                     list_expander: None,
                     template_name: ResolvesToNamedNode::NamedNode(NamedNode::new_unchecked("http://Template2.com")),
                     argument_list: vec![UnresolvedArgument {
+                        parameter: None,
                         list_expand: false,
                         term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                             value: "asdf".to_string(),
@@ -1185,6 +1203,7 @@ fn test_spec_example_4() {
                     }),
                     argument_list: vec![
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
                                 value: "asdf".to_string(),
@@ -1196,10 +1215,12 @@ fn test_spec_example_4() {
                             }))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::Variable(StottrVariable { name: "pizza".to_string() }),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: true,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(vec![
                                 UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::Literal(UnresolvedStottrLiteral {
@@ -1224,24 +1245,28 @@ fn test_spec_example_4() {
                     }),
                     argument_list: vec![
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked(
                                 "AnonymousBlankNode",
                             )))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked(
                                 "AnonymousBlankNode",
                             )))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: false,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked(
                                 "AnonymousBlankNode",
                             )))),
                         },
                         UnresolvedArgument {
+                            parameter: None,
                             list_expand: true,
                             term: UnresolvedStottrTerm::ConstantTerm(UnresolvedConstantTerm::ConstantList(vec![
                                 UnresolvedConstantTerm::Constant(UnresolvedConstantLiteral::BlankNode(BlankNode::new_unchecked("AnonymousBlankNode"))),
@@ -1,4 +1,4 @@
-use crate::ast::{Directive, ListExpanderType, StottrVariable};
+use crate::ast::{Directive, ExpressionOperator, ListExpanderType, StottrVariable};
 use oxrdf::{BlankNode, NamedNode};
 
 #[derive(PartialEq, Debug)]
@@ -80,6 +80,7 @@ pub struct PrefixedName {
 pub enum ResolvesToNamedNode {
     PrefixedName(PrefixedName),
     NamedNode(NamedNode),
+    RelativeIRI(String),
 }
 
 #[derive(PartialEq, Debug)]
@@ -96,6 +97,7 @@ pub struct UnresolvedAnnotation {
 
 #[derive(PartialEq, Debug)]
 pub struct UnresolvedArgument {
+    pub parameter: Option<String>,
     pub list_expand: bool,
     pub term: UnresolvedStottrTerm,
 }
@@ -105,6 +107,14 @@ pub enum UnresolvedStottrTerm {
     Variable(StottrVariable),
     ConstantTerm(UnresolvedConstantTerm),
     List(Vec<UnresolvedStottrTerm>),
+    Expression(Box<UnresolvedStottrExpression>),
+}
+
+#[derive(PartialEq, Debug)]
+pub struct UnresolvedStottrExpression {
+    pub left: UnresolvedStottrTerm,
+    pub operator: ExpressionOperator,
+    pub right: UnresolvedStottrTerm,
 }
 
 #[derive(PartialEq, Debug)]
@@ -1,6 +1,7 @@
 use crate::ast::{
     Annotation, Argument, ConstantLiteral, ConstantTerm, DefaultValue, Directive, Instance, PType,
-    Parameter, Signature, Statement, StottrDocument, StottrLiteral, StottrTerm, Template,
+    Parameter, Signature, Statement, StottrDocument, StottrExpression, StottrLiteral, StottrTerm,
+    Template,
 };
 use crate::constants::{
     OTTR_PREFIX, OTTR_PREFIX_IRI, RDFS_PREFIX, RDFS_PREFIX_IRI, RDF_PREFIX, RDF_PREFIX_IRI,
@@ -13,6 +14,7 @@ use crate::parsing::parsing_ast::{
     UnresolvedStottrDocument, UnresolvedStottrLiteral, UnresolvedStottrTerm, UnresolvedTemplate,
 };
 use log::warn;
+use oxiri::Iri;
 use oxrdf::{IriParseError, NamedNode};
 use std::collections::HashMap;
 use std::error::Error;
@@ -28,6 +30,8 @@ pub enum ResolutionErrorType {
     DuplicatedPrefixDefinition(String, String, String),
     BadCompositeIRIError(IriParseError),
     MissingPrefixError(String),
+    DuplicatedBaseDefinition(String, String),
+    MissingBaseError(String),
 }
 
 impl Display for ResolutionError {
@@ -46,6 +50,16 @@ impl Display for ResolutionError {
             ResolutionErrorType::MissingPrefixError(prefix) => {
                 write!(f, "Prefix {} is not defined", prefix)
             }
+            ResolutionErrorType::DuplicatedBaseDefinition(def1, def2) => {
+                write!(f, "Base has two definitions: {} and {}", def1, def2)
+            }
+            ResolutionErrorType::MissingBaseError(relative) => {
+                write!(
+                    f,
+                    "Relative IRI <{}> can not be resolved as no base is defined",
+                    relative
+                )
+            }
         }
     }
 }
@@ -57,40 +71,44 @@ pub(crate) fn resolve_document(
 ) -> Result<StottrDocument, ResolutionError> {
     let directives = unresolved_document.directives.clone();
     let mut prefix_map = build_prefix_map(&directives)?;
+    let base_iri = find_base_iri(&directives)?;
     let mut statements = vec![];
     for us in &unresolved_document.statements {
-        statements.push(resolve_statement(us, &mut prefix_map)?);
+        statements.push(resolve_statement(us, &mut prefix_map, &base_iri)?);
     }
     Ok(StottrDocument {
         directives,
         statements,
         prefix_map,
+        base_iri,
     })
 }
 
 fn resolve_statement(
     unresolved_statement: &UnresolvedStatement,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Statement, ResolutionError> {
     Ok(match unresolved_statement {
         UnresolvedStatement::Signature(s) => Statement::Template(Template {
-            signature: resolve_signature(s, prefix_map)?,
+            signature: resolve_signature(s, prefix_map, base_iri)?,
             pattern_list: vec![],
         }),
-        UnresolvedStatement::Template(t) => Statement::Template(resolve_template(t, prefix_map)?),
+        UnresolvedStatement::Template(t) => Statement::Template(resolve_template(t, prefix_map, base_iri)?),
         UnresolvedStatement::BaseTemplate(b) => {
-            Statement::Template(resolve_base_template(b, prefix_map)?)
+            Statement::Template(resolve_base_template(b, prefix_map, base_iri)?)
         }
-        UnresolvedStatement::Instance(i) => Statement::Instance(resolve_instance(i, prefix_map)?),
+        UnresolvedStatement::Instance(i) => Statement::Instance(resolve_instance(i, prefix_map, base_iri)?),
     })
 }
 
 fn resolve_base_template(
     unresolved_base_template: &UnresolvedBaseTemplate,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Template, ResolutionError> {
     Ok(Template {
-        signature: resolve_signature(&unresolved_base_template.signature, prefix_map)?,
+        signature: resolve_signature(&unresolved_base_template.signature, prefix_map, base_iri)?,
         pattern_list: vec![],
     })
 }
@@ -98,13 +116,14 @@ fn resolve_base_template(
 fn resolve_template(
     unresolved_template: &UnresolvedTemplate,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Template, ResolutionError> {
     let mut pattern_list = vec![];
     for ui in &unresolved_template.pattern_list {
-        pattern_list.push(resolve_instance(ui, prefix_map)?);
+        pattern_list.push(resolve_instance(ui, prefix_map, base_iri)?);
     }
     Ok(Template {
-        signature: resolve_signature(&unresolved_template.signature, prefix_map)?,
+        signature: resolve_signature(&unresolved_template.signature, prefix_map, base_iri)?,
         pattern_list,
     })
 }
@@ -112,16 +131,17 @@ fn resolve_template(
 fn resolve_signature(
     unresolved_signature: &UnresolvedSignature,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Signature, ResolutionError> {
     let mut parameter_list = vec![];
     for up in &unresolved_signature.parameter_list {
-        parameter_list.push(resolve_parameter(up, prefix_map)?);
+        parameter_list.push(resolve_parameter(up, prefix_map, base_iri)?);
     }
     let annotation_list;
     if let Some(unresolved_annotation_list) = &unresolved_signature.annotation_list {
         let mut some_annotation_list = vec![];
         for ua in unresolved_annotation_list {
-            some_annotation_list.push(resolve_annotation(ua, prefix_map)?);
+            some_annotation_list.push(resolve_annotation(ua, prefix_map, base_iri)?);
         }
         annotation_list = Some(some_annotation_list);
     } else {
@@ -131,7 +151,7 @@ fn resolve_signature(
 
 
     Ok(Signature {
-        template_name: resolve(&unresolved_signature.template_name, prefix_map)?,
+        template_name: resolve(&unresolved_signature.template_name, prefix_map, base_iri)?,
         template_prefixed_name: prefixed_name,
         parameter_list,
         annotation_list,
@@ -146,29 +166,34 @@ fn get_name(resolves_to_name: &ResolvesToNamedNode) -> String {
         ResolvesToNamedNode::NamedNode(nn) => {
             nn.to_string()
         }
+        ResolvesToNamedNode::RelativeIRI(relative) => {
+            format!("<{}>", relative)
+        }
     }
 }
 
 fn resolve_annotation(
     unresolved_annotation: &UnresolvedAnnotation,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Annotation, ResolutionError> {
     Ok(Annotation {
-        instance: resolve_instance(&unresolved_annotation.instance, prefix_map)?,
+        instance: resolve_instance(&unresolved_annotation.instance, prefix_map, base_iri)?,
     })
 }
 
 fn resolve_instance(
     unresolved_instance: &UnresolvedInstance,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Instance, ResolutionError> {
     let mut argument_list = vec![];
     for ua in &unresolved_instance.argument_list {
-        argument_list.push(resolve_argument(ua, prefix_map)?);
+        argument_list.push(resolve_argument(ua, prefix_map, base_iri)?);
     }
     Ok(Instance {
         list_expander: unresolved_instance.list_expander.clone(),
-        template_name: resolve(&unresolved_instance.template_name, prefix_map)?,
+        template_name: resolve(&unresolved_instance.template_name, prefix_map, base_iri)?,
         prefixed_template_name: get_name(&unresolved_instance.template_name),
         argument_list,
     })
@@ -177,44 +202,55 @@ fn resolve_instance(
 fn resolve_argument(
     unresolved_argument: &UnresolvedArgument,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Argument, ResolutionError> {
     Ok(Argument {
+        parameter: unresolved_argument.parameter.clone(),
         list_expand: unresolved_argument.list_expand,
-        term: resolve_stottr_term(&unresolved_argument.term, prefix_map)?,
+        term: resolve_stottr_term(&unresolved_argument.term, prefix_map, base_iri)?,
     })
 }
 
 fn resolve_stottr_term(
     unresolved_stottr_term: &UnresolvedStottrTerm,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<StottrTerm, ResolutionError> {
     Ok(match unresolved_stottr_term {
         UnresolvedStottrTerm::Variable(v) => StottrTerm::Variable(v.clone()),
         UnresolvedStottrTerm::ConstantTerm(t) => {
-            StottrTerm::ConstantTerm(resolve_constant_term(t, prefix_map)?)
+            StottrTerm::ConstantTerm(resolve_constant_term(t, prefix_map, base_iri)?)
         }
         UnresolvedStottrTerm::List(l) => {
             let mut terms = vec![];
             for ust in l {
-                terms.push(resolve_stottr_term(ust, prefix_map)?);
+                terms.push(resolve_stottr_term(ust, prefix_map, base_iri)?);
             }
             StottrTerm::List(terms)
         }
+        UnresolvedStottrTerm::Expression(e) => {
+            StottrTerm::Expression(Box::new(StottrExpression {
+                left: resolve_stottr_term(&e.left, prefix_map, base_iri)?,
+                operator: e.operator,
+                right: resolve_stottr_term(&e.right, prefix_map, base_iri)?,
+            }))
+        }
     })
 }
 
 fn resolve_constant_term(
     unresolved_constant_term: &UnresolvedConstantTerm,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<ConstantTerm, ResolutionError> {
     Ok(match unresolved_constant_term {
         UnresolvedConstantTerm::Constant(c) => {
-            ConstantTerm::Constant(resolve_constant_literal(c, prefix_map)?)
+            ConstantTerm::Constant(resolve_constant_literal(c, prefix_map, base_iri)?)
         }
         UnresolvedConstantTerm::ConstantList(cl) => {
             let mut constant_terms = vec![];
             for uct in cl {
-                constant_terms.push(resolve_constant_term(uct, prefix_map)?);
+                constant_terms.push(resolve_constant_term(uct, prefix_map, base_iri)?);
             }
             ConstantTerm::ConstantList(constant_terms)
         }
@@ -224,12 +260,13 @@ fn resolve_constant_term(
 fn resolve_constant_literal(
     unresolved_constant_literal: &UnresolvedConstantLiteral,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<ConstantLiteral, ResolutionError> {
     Ok(match unresolved_constant_literal {
-        UnresolvedConstantLiteral::IRI(iri) => ConstantLiteral::IRI(resolve(iri, prefix_map)?),
+        UnresolvedConstantLiteral::IRI(iri) => ConstantLiteral::IRI(resolve(iri, prefix_map, base_iri)?),
         UnresolvedConstantLiteral::BlankNode(bn) => ConstantLiteral::BlankNode(bn.clone()),
         UnresolvedConstantLiteral::Literal(lit) => {
-            ConstantLiteral::Literal(resolve_stottr_literal(lit, prefix_map)?)
+            ConstantLiteral::Literal(resolve_stottr_literal(lit, prefix_map, base_iri)?)
         }
         UnresolvedConstantLiteral::None => ConstantLiteral::None,
     })
@@ -238,6 +275,7 @@ fn resolve_constant_literal(
 fn resolve_stottr_literal(
     unresolved_stottr_literal: &UnresolvedStottrLiteral,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<StottrLiteral, ResolutionError> {
     Ok(StottrLiteral {
         value: unresolved_stottr_literal.value.clone(),
@@ -245,7 +283,7 @@ fn resolve_stottr_literal(
         data_type_iri: if let Some(unresolved_data_type_uri) =
             &unresolved_stottr_literal.data_type_iri
         {
-            Some(resolve(unresolved_data_type_uri, prefix_map)?)
+            Some(resolve(unresolved_data_type_uri, prefix_map, base_iri)?)
         } else {
             None
         },
@@ -255,18 +293,19 @@ fn resolve_stottr_literal(
 fn resolve_parameter(
     unresolved_parameter: &UnresolvedParameter,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<Parameter, ResolutionError> {
     Ok(Parameter {
         optional: unresolved_parameter.optional,
         non_blank: unresolved_parameter.non_blank,
         ptype: if let Some(uptype) = &unresolved_parameter.ptype {
-            Some(resolve_ptype(uptype, prefix_map)?)
+            Some(resolve_ptype(uptype, prefix_map, base_iri)?)
         } else {
             None
         },
         stottr_variable: unresolved_parameter.stottr_variable.clone(),
         default_value: if let Some(udefault_value) = &unresolved_parameter.default_value {
-            Some(resolve_default_value(udefault_value, prefix_map)?)
+            Some(resolve_default_value(udefault_value, prefix_map, base_iri)?)
         } else {
             None
         },
@@ -276,22 +315,24 @@ fn resolve_parameter(
 fn resolve_default_value(
     unresolved_default_value: &UnresolvedDefaultValue,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<DefaultValue, ResolutionError> {
     Ok(DefaultValue {
-        constant_term: resolve_constant_term(&unresolved_default_value.constant_term, prefix_map)?,
+        constant_term: resolve_constant_term(&unresolved_default_value.constant_term, prefix_map, base_iri)?,
     })
 }
 
 fn resolve_ptype(
     unresolved_ptype: &UnresolvedPType,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<PType, ResolutionError> {
     Ok(match unresolved_ptype {
-        UnresolvedPType::BasicType(b) => PType::BasicType(resolve(b, prefix_map)?, get_name(b)),
-        UnresolvedPType::LUBType(l) => PType::LUBType(Box::new(resolve_ptype(l, prefix_map)?)),
-        UnresolvedPType::ListType(l) => PType::ListType(Box::new(resolve_ptype(l, prefix_map)?)),
+        UnresolvedPType::BasicType(b) => PType::BasicType(resolve(b, prefix_map, base_iri)?, get_name(b)),
+        UnresolvedPType::LUBType(l) => PType::LUBType(Box::new(resolve_ptype(l, prefix_map, base_iri)?)),
+        UnresolvedPType::ListType(l) => PType::ListType(Box::new(resolve_ptype(l, prefix_map, base_iri)?)),
         UnresolvedPType::NEListType(l) => {
-            PType::NEListType(Box::new(resolve_ptype(l, prefix_map)?))
+            PType::NEListType(Box::new(resolve_ptype(l, prefix_map, base_iri)?))
         }
     })
 }
@@ -299,6 +340,7 @@ fn resolve_ptype(
 fn resolve(
     resolves_to_named_node: &ResolvesToNamedNode,
     prefix_map: &mut HashMap<String, NamedNode>,
+    base_iri: &Option<NamedNode>,
 ) -> Result<NamedNode, ResolutionError> {
     Ok(match resolves_to_named_node {
         ResolvesToNamedNode::PrefixedName(pn) => {
@@ -322,9 +364,49 @@ fn resolve(
             }
         }
         ResolvesToNamedNode::NamedNode(nn) => nn.clone(),
+        ResolvesToNamedNode::RelativeIRI(relative) => {
+            if let Some(base) = base_iri {
+                resolve_relative_iri(base, relative).map_err(|err| ResolutionError {
+                    kind: ResolutionErrorType::BadCompositeIRIError(err),
+                })?
+            } else {
+                return Err(ResolutionError {
+                    kind: ResolutionErrorType::MissingBaseError(relative.clone()),
+                });
+            }
+        }
     })
 }
 
+pub(crate) fn resolve_relative_iri(
+    base: &NamedNode,
+    relative: &str,
+) -> Result<NamedNode, IriParseError> {
+    let base = Iri::parse(base.as_str())?;
+    let resolved = base.resolve(relative)?;
+    Ok(NamedNode::new_unchecked(resolved.into_inner()))
+}
+
+fn find_base_iri(directives: &Vec<Directive>) -> Result<Option<NamedNode>, ResolutionError> {
+    let mut base_iri: Option<NamedNode> = None;
+    for d in directives {
+        if let Directive::Base(b) = d {
+            if let Some(existing) = &base_iri {
+                if existing != b {
+                    return Err(ResolutionError {
+                        kind: ResolutionErrorType::DuplicatedBaseDefinition(
+                            existing.as_str().to_string(),
+                            b.as_str().to_string(),
+                        ),
+                    });
+                }
+            }
+            base_iri = Some(b.clone());
+        }
+    }
+    Ok(base_iri)
+}
+
 fn build_prefix_map(
     directives: &Vec<Directive>,
 ) -> Result<HashMap<String, NamedNode>, ResolutionError> {
@@ -334,9 +416,7 @@ fn build_prefix_map(
             Directive::Prefix(p) => {
                 insert_or_raise(&p.name, &p.iri, &mut map)?;
             }
-            Directive::Base(b) => {
-                insert_or_raise("", b, &mut map)?;
-            }
+            Directive::Base(_) => {}
         }
     }
     let predefined = [
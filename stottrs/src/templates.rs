@@ -1,11 +1,11 @@
 use crate::ast::{
-    Instance, PType, Parameter, Signature, Statement, StottrDocument, StottrTerm, StottrVariable,
-    Template,
+    Argument, ConstantLiteral, ConstantTerm, Instance, PType, Parameter, Signature, Statement,
+    StottrDocument, StottrTerm, StottrVariable, Template,
 };
-use crate::constants::OTTR_TRIPLE;
-use crate::document::document_from_file;
+use crate::constants::{OTTR_TRIPLE, RESERVED_COLUMN_PREFIX};
+use crate::document::{document_from_file, wottr_document_from_file};
 use log::warn;
-use oxrdf::vocab::xsd;
+use oxrdf::vocab::{rdf, rdfs, xsd};
 use oxrdf::NamedNode;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
@@ -13,6 +13,12 @@ use std::fmt::{Display, Formatter};
 use std::fs::read_dir;
 use std::path::Path;
 
+mod dependencies;
+mod predicate_lineage;
+#[cfg(feature = "remote")]
+pub mod remote;
+mod stottr_write;
+
 #[derive(Debug)]
 pub struct TypingError {
     pub kind: TypingErrorType,
@@ -22,6 +28,13 @@ pub struct TypingError {
 pub enum TypingErrorType {
     InconsistentNumberOfArguments(String, String, usize, usize),
     IncompatibleTypes(String, StottrVariable, String, String),
+    IncompatibleRedefinition(String, String),
+    InvalidParameterName(String, String, String),
+    InvalidNamedArguments(String, String, String),
+    /// The chain of templates leading to a template that is not defined, which is last.
+    MissingDependency(Vec<String>),
+    /// The chain of templates using each other, starting and ending with the same template.
+    CyclicDependency(Vec<String>),
 }
 
 impl Display for TypingError {
@@ -41,6 +54,34 @@ impl Display for TypingError {
                     nn, var.name, given, expected
                 )
             }
+            TypingErrorType::IncompatibleRedefinition(nn, reason) => {
+                write!(
+                    f,
+                    "Template {} is already defined with an incompatible signature: {}",
+                    nn, reason
+                )
+            }
+            TypingErrorType::InvalidParameterName(nn, name, reason) => {
+                write!(f, "Template {} has parameter {} which {}", nn, name, reason)
+            }
+            TypingErrorType::InvalidNamedArguments(calling, template, reason) => {
+                write!(f, "Template {} called {} with {}", calling, template, reason)
+            }
+            TypingErrorType::MissingDependency(chain) => {
+                if chain.len() == 1 {
+                    write!(f, "Template {} is not defined", chain[0])
+                } else {
+                    write!(
+                        f,
+                        "Template {} is not defined, it is used through {}",
+                        chain.last().unwrap(),
+                        chain.join(" -> ")
+                    )
+                }
+            }
+            TypingErrorType::CyclicDependency(chain) => {
+                write!(f, "Templates use themselves through {}", chain.join(" -> "))
+            }
         }
     }
 }
@@ -52,6 +93,7 @@ pub struct TemplateDataset {
     pub templates: Vec<Template>,
     pub ground_instances: Vec<Instance>,
     pub prefix_map: HashMap<String, NamedNode>,
+    pub base_iri: Option<NamedNode>,
 }
 
 impl TemplateDataset {
@@ -60,7 +102,19 @@ impl TemplateDataset {
         let mut ground_instances = vec![];
         let mut prefix_map = HashMap::new();
         let mut defined_prefixes = HashSet::new();
+        let mut base_iri = None;
+        let mut conflicting_base = false;
         for d in &mut documents {
+            if let Some(b) = d.base_iri.take() {
+                match &base_iri {
+                    Some(existing) if existing != &b => {
+                        conflicting_base = true;
+                    }
+                    _ => {
+                        base_iri = Some(b);
+                    }
+                }
+            }
             for (k, v) in d.prefix_map.drain() {
                 if defined_prefixes.contains(&k) {
                     let mut remove = false;
@@ -89,10 +143,15 @@ impl TemplateDataset {
                 }
             }
         }
+        if conflicting_base {
+            warn!("Base has conflicting definitions across documents, relative IRIs in columns will not be resolved");
+            base_iri = None;
+        }
         let mut td = TemplateDataset {
             templates,
             ground_instances,
             prefix_map,
+            base_iri,
         };
         //TODO: Put in function, check not exists and consistent...
         let ottr_triple_subject = Parameter {
@@ -133,8 +192,12 @@ impl TemplateDataset {
             pattern_list: vec![],
         };
         td.templates.push(ottr_template);
-        //Todo: variable safe, no cycles, referential integrity, no duplicates, well founded
-        //Check ground instances also!!
+        for t in &td.templates {
+            check_parameter_names(&t.signature)?;
+        }
+        td.validate_dependencies()?;
+        td.order_named_arguments()?;
+        //Todo: variable safe, no duplicates, well founded
         td.infer_types()?;
         Ok(td)
     }
@@ -150,6 +213,9 @@ impl TemplateDataset {
                     if "stottr" == &extension {
                         let doc = document_from_file(f.path())?;
                         docs.push(doc);
+                    } else if "wottr" == &extension || "ttl" == &extension {
+                        let doc = wottr_document_from_file(f.path())?;
+                        docs.push(doc);
                     }
                 }
             }
@@ -158,7 +224,18 @@ impl TemplateDataset {
     }
 
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<TemplateDataset, Box<dyn Error>> {
-        let doc = document_from_file(path)?;
+        //Turtle files are assumed to contain wOTTR
+        let is_wottr = if let Some(e) = path.as_ref().extension() {
+            let extension = e.to_string_lossy().to_lowercase();
+            extension == "ttl" || extension == "wottr"
+        } else {
+            false
+        };
+        let doc = if is_wottr {
+            wottr_document_from_file(path)?
+        } else {
+            document_from_file(path)?
+        };
         Ok(TemplateDataset::new(vec![doc])?)
     }
 
@@ -171,6 +248,65 @@ impl TemplateDataset {
         None
     }
 
+    /// Adds the template, or replaces an existing template with the same name if the signatures are compatible.
+    /// The dataset is left unchanged if an error is returned.
+    pub fn upsert_template(&mut self, template: Template) -> Result<(), TypingError> {
+        let name = template.signature.template_name.as_str().to_string();
+        check_parameter_names(&template.signature)?;
+        //Type inference may update other templates, so we keep all of them in case of errors
+        let backup = self.templates.clone();
+        if let Some(existing) = self
+            .templates
+            .iter_mut()
+            .find(|t| t.signature.template_name.as_str() == name)
+        {
+            check_compatible_signatures(&existing.signature, &template.signature)?;
+            *existing = template;
+        } else {
+            self.templates.push(template);
+        }
+        let res = self
+            .validate_dependencies()
+            .and_then(|_| self.order_named_arguments())
+            .and_then(|_| self.infer_types());
+        if res.is_err() {
+            self.templates = backup;
+        }
+        res
+    }
+
+    //Arguments given by name are put in the order of the parameters, and optional parameters and parameters with
+    //default values that are not given arguments get none, so that instances keep working when parameters are added
+    fn order_named_arguments(&mut self) -> Result<(), TypingError> {
+        let signatures: HashMap<String, Signature> = self
+            .templates
+            .iter()
+            .map(|t| (t.signature.template_name.as_str().to_string(), t.signature.clone()))
+            .collect();
+        let order = |calling: &str, instance: &mut Instance| {
+            if let Some(signature) = signatures.get(instance.template_name.as_str()) {
+                order_arguments(calling, instance, signature)
+            } else {
+                Ok(())
+            }
+        };
+        for t in &mut self.templates {
+            let calling = t.signature.template_name.as_str().to_string();
+            for i in &mut t.pattern_list {
+                order(&calling, i)?;
+            }
+            if let Some(annotations) = &mut t.signature.annotation_list {
+                for a in annotations {
+                    order(&calling, &mut a.instance)?;
+                }
+            }
+        }
+        for i in &mut self.ground_instances {
+            order("ground instance", i)?;
+        }
+        Ok(())
+    }
+
     fn infer_types(&mut self) -> Result<(), TypingError> {
         let mut changed = true;
         while changed {
@@ -192,11 +328,90 @@ impl TemplateDataset {
     }
 }
 
+//Parameter names are column names during expansion, where columns under the reserved prefix are made for
+//blank nodes, language tags and expressions
+fn check_parameter_names(signature: &Signature) -> Result<(), TypingError> {
+    let mut names = HashSet::new();
+    for p in &signature.parameter_list {
+        let name = &p.stottr_variable.name;
+        let reason = if !names.insert(name) {
+            Some("is given more than once")
+        } else if name.starts_with(RESERVED_COLUMN_PREFIX) {
+            Some("starts with the prefix reserved for columns made during expansion")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(TypingError {
+                kind: TypingErrorType::InvalidParameterName(
+                    signature.template_name.as_str().to_string(),
+                    name.clone(),
+                    reason.to_string(),
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_compatible_signatures(existing: &Signature, new: &Signature) -> Result<(), TypingError> {
+    let name = existing.template_name.as_str().to_string();
+    if existing.parameter_list.len() != new.parameter_list.len() {
+        return Err(TypingError {
+            kind: TypingErrorType::IncompatibleRedefinition(
+                name,
+                format!(
+                    "{} parameters instead of {}",
+                    new.parameter_list.len(),
+                    existing.parameter_list.len()
+                ),
+            ),
+        });
+    }
+    for (e, n) in existing.parameter_list.iter().zip(new.parameter_list.iter()) {
+        if e.stottr_variable != n.stottr_variable {
+            return Err(TypingError {
+                kind: TypingErrorType::IncompatibleRedefinition(
+                    name,
+                    format!(
+                        "parameter {} instead of {}",
+                        n.stottr_variable.name, e.stottr_variable.name
+                    ),
+                ),
+            });
+        }
+        if let (Some(e_ptype), Some(n_ptype)) = (&e.ptype, &n.ptype) {
+            if e_ptype != n_ptype {
+                return Err(TypingError {
+                    kind: TypingErrorType::IncompatibleRedefinition(
+                        name,
+                        format!(
+                            "parameter {} has type {} instead of {}",
+                            n.stottr_variable.name, n_ptype, e_ptype
+                        ),
+                    ),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
 fn infer_template_types(
     template: &mut Template,
     templates: Vec<&Template>,
 ) -> Result<bool, TypingError> {
     let mut changed = false;
+    for p in &template.signature.parameter_list {
+        if let Some(default_value) = &p.default_value {
+            check_language_tagged_constant(
+                &template.signature.template_name,
+                &p.stottr_variable,
+                &p.ptype,
+                &default_value.constant_term,
+            )?;
+        }
+    }
     for i in &mut template.pattern_list {
         let other = *templates
             .iter()
@@ -253,14 +468,66 @@ fn infer_template_types(
                         }
                     }
                 }
-                StottrTerm::ConstantTerm(_) => {}
+                StottrTerm::ConstantTerm(ct) => {
+                    check_language_tagged_constant(
+                        &template.signature.template_name,
+                        &other_parameter.stottr_variable,
+                        &other_parameter.ptype,
+                        ct,
+                    )?;
+                }
                 StottrTerm::List(_) => {}
+                StottrTerm::Expression(_) => {}
             }
         }
     }
     Ok(changed)
 }
 
+//Language tagged literals have the type rdf:langString, which is not a subtype of xsd:string
+fn check_language_tagged_constant(
+    template_name: &NamedNode,
+    variable: &StottrVariable,
+    ptype: &Option<PType>,
+    constant_term: &ConstantTerm,
+) -> Result<(), TypingError> {
+    let ptype = match ptype {
+        Some(PType::ListType(inner)) | Some(PType::NEListType(inner)) => Some(inner.as_ref()),
+        Some(p) => Some(p),
+        None => None,
+    };
+    if let Some(PType::BasicType(nn, name)) = ptype {
+        if nn.as_ref() == rdf::LANG_STRING || nn.as_ref() == rdfs::LITERAL {
+            return Ok(());
+        }
+        let constants = match constant_term {
+            ConstantTerm::Constant(c) => vec![c],
+            ConstantTerm::ConstantList(inner) => inner
+                .iter()
+                .filter_map(|ct| match ct {
+                    ConstantTerm::Constant(c) => Some(c),
+                    ConstantTerm::ConstantList(_) => None,
+                })
+                .collect(),
+        };
+        for c in constants {
+            if let ConstantLiteral::Literal(lit) = c {
+                if lit.language.is_some() {
+                    return Err(TypingError {
+                        kind: TypingErrorType::IncompatibleTypes(
+                            template_name.as_str().to_string(),
+                            variable.clone(),
+                            "rdf:langString".to_string(),
+                            name.clone(),
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn lub_update(
     template_name: &NamedNode,
     variable: &StottrVariable,
@@ -343,3 +610,64 @@ fn lub(
         ),
     })
 }
+
+fn order_arguments(
+    calling: &str,
+    instance: &mut Instance,
+    signature: &Signature,
+) -> Result<(), TypingError> {
+    if instance.argument_list.iter().all(|a| a.parameter.is_none()) {
+        return Ok(());
+    }
+    let template_name = instance.template_name.as_str().to_string();
+    let error = |reason: String| TypingError {
+        kind: TypingErrorType::InvalidNamedArguments(
+            calling.to_string(),
+            template_name.clone(),
+            reason,
+        ),
+    };
+    let mut positional = vec![];
+    let mut named = HashMap::new();
+    for a in instance.argument_list.drain(..) {
+        if let Some(parameter) = &a.parameter {
+            if !signature
+                .parameter_list
+                .iter()
+                .any(|p| &p.stottr_variable.name == parameter)
+            {
+                return Err(error(format!("an argument for unknown parameter ?{}", parameter)));
+            }
+            if named.contains_key(parameter) {
+                return Err(error(format!("several arguments for parameter ?{}", parameter)));
+            }
+            named.insert(parameter.clone(), a);
+        } else if named.is_empty() {
+            positional.push(a);
+        } else {
+            return Err(error("an argument by position after an argument by name".to_string()));
+        }
+    }
+    let mut positional = positional.into_iter();
+    for p in &signature.parameter_list {
+        let name = &p.stottr_variable.name;
+        let argument = if let Some(a) = positional.next() {
+            if named.contains_key(name) {
+                return Err(error(format!("several arguments for parameter ?{}", name)));
+            }
+            a
+        } else if let Some(a) = named.remove(name) {
+            a
+        } else if p.optional || p.default_value.is_some() {
+            Argument {
+                parameter: None,
+                list_expand: false,
+                term: StottrTerm::ConstantTerm(ConstantTerm::Constant(ConstantLiteral::None)),
+            }
+        } else {
+            return Err(error(format!("no argument for non-optional parameter ?{}", name)));
+        };
+        instance.argument_list.push(argument);
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use super::{TemplateDataset, TypingError, TypingErrorType};
+use crate::ast::Template;
+use crate::constants::OTTR_TRIPLE;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    Visiting,
+    Visited,
+}
+
+impl TemplateDataset {
+    //Every instance must use a defined template, and templates may not use themselves through their patterns,
+    //as the expansion would never end. Errors contain the chain of templates leading to the problem.
+    pub(super) fn validate_dependencies(&self) -> Result<(), TypingError> {
+        let templates: HashMap<&str, &Template> = self
+            .templates
+            .iter()
+            .map(|t| (t.signature.template_name.as_str(), t))
+            .collect();
+        let mut states = HashMap::new();
+        for t in &self.templates {
+            let name = t.signature.template_name.as_str();
+            if !states.contains_key(name) {
+                visit(name, &templates, &mut states, &mut vec![])?;
+            }
+            if let Some(annotation_list) = &t.signature.annotation_list {
+                for a in annotation_list {
+                    let annotation_name = a.instance.template_name.as_str();
+                    if !templates.contains_key(annotation_name) {
+                        return Err(missing_template(vec![name, annotation_name]));
+                    }
+                }
+            }
+        }
+        for i in &self.ground_instances {
+            let name = i.template_name.as_str();
+            if !templates.contains_key(name) {
+                return Err(missing_template(vec![name]));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn visit<'a>(
+    name: &'a str,
+    templates: &HashMap<&'a str, &'a Template>,
+    states: &mut HashMap<&'a str, VisitState>,
+    chain: &mut Vec<&'a str>,
+) -> Result<(), TypingError> {
+    states.insert(name, VisitState::Visiting);
+    chain.push(name);
+    let template: &'a Template = *templates.get(name).unwrap();
+    for i in &template.pattern_list {
+        let used = i.template_name.as_str();
+        if used == OTTR_TRIPLE {
+            continue;
+        }
+        match states.get(used) {
+            Some(VisitState::Visited) => {}
+            Some(VisitState::Visiting) => {
+                let start = chain.iter().position(|t| *t == used).unwrap();
+                let mut cycle = chain[start..].to_vec();
+                cycle.push(used);
+                return Err(TypingError {
+                    kind: TypingErrorType::CyclicDependency(
+                        cycle.into_iter().map(|t| t.to_string()).collect(),
+                    ),
+                });
+            }
+            None => {
+                if !templates.contains_key(used) {
+                    let mut missing = chain.clone();
+                    missing.push(used);
+                    return Err(missing_template(missing));
+                }
+                visit(used, templates, states, chain)?;
+            }
+        }
+    }
+    chain.pop();
+    states.insert(name, VisitState::Visited);
+    Ok(())
+}
+
+fn missing_template(chain: Vec<&str>) -> TypingError {
+    TypingError {
+        kind: TypingErrorType::MissingDependency(chain.into_iter().map(|t| t.to_string()).collect()),
+    }
+}
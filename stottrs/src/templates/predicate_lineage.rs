@@ -0,0 +1,127 @@
+use super::TemplateDataset;
+use crate::ast::{ConstantLiteral, ConstantTerm, StottrTerm, Template};
+use crate::constants::OTTR_TRIPLE;
+use std::collections::{HashMap, HashSet};
+
+//Predicates a template can produce, both constant ones and the parameters used as verbs
+#[derive(Default, PartialEq)]
+struct Lineage {
+    predicates: HashSet<String>,
+    verb_parameters: HashSet<String>,
+}
+
+impl TemplateDataset {
+    /// Maps each template to the predicate IRIs it can produce, following nested instances.
+    /// A predicate passed as a constant argument is attributed to the template where the constant occurs
+    /// and to the templates calling it, predicates that only come from the data are not known.
+    pub fn predicates_produced(&self) -> HashMap<String, HashSet<String>> {
+        let mut lineages: HashMap<String, Lineage> = HashMap::new();
+        let mut triple_lineage = Lineage::default();
+        triple_lineage.verb_parameters.insert("verb".to_string());
+        lineages.insert(OTTR_TRIPLE.to_string(), triple_lineage);
+
+        //The sets only grow, so this terminates also when templates are cyclic
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for t in &self.templates {
+                let name = t.signature.template_name.as_str();
+                if name == OTTR_TRIPLE {
+                    continue;
+                }
+                let lineage = self.template_lineage(t, &lineages);
+                if lineages.get(name) != Some(&lineage) {
+                    lineages.insert(name.to_string(), lineage);
+                    changed = true;
+                }
+            }
+        }
+        lineages
+            .into_iter()
+            .filter(|(name, _)| name != OTTR_TRIPLE)
+            .map(|(name, lineage)| (name, lineage.predicates))
+            .collect()
+    }
+
+    /// The templates that can produce the predicate, sorted by name.
+    pub fn templates_producing(&self, predicate: &str) -> Vec<String> {
+        let mut templates: Vec<String> = self
+            .predicates_produced()
+            .into_iter()
+            .filter(|(_, predicates)| predicates.contains(predicate))
+            .map(|(name, _)| name)
+            .collect();
+        templates.sort();
+        templates
+    }
+
+    fn template_lineage(&self, template: &Template, lineages: &HashMap<String, Lineage>) -> Lineage {
+        let mut lineage = Lineage::default();
+        for i in &template.pattern_list {
+            let (called_template, called_lineage) = if let (Some(t), Some(l)) = (
+                self.get(i.template_name.as_str()),
+                lineages.get(i.template_name.as_str()),
+            ) {
+                (t, l)
+            } else {
+                continue;
+            };
+            lineage
+                .predicates
+                .extend(called_lineage.predicates.iter().cloned());
+            for (p, a) in called_template
+                .signature
+                .parameter_list
+                .iter()
+                .zip(i.argument_list.iter())
+            {
+                if called_lineage
+                    .verb_parameters
+                    .contains(&p.stottr_variable.name)
+                {
+                    add_term_lineage(&a.term, template, &mut lineage);
+                }
+            }
+        }
+        lineage
+    }
+}
+
+fn add_term_lineage(term: &StottrTerm, template: &Template, lineage: &mut Lineage) {
+    match term {
+        StottrTerm::Variable(v) => {
+            if let Some(p) = template
+                .signature
+                .parameter_list
+                .iter()
+                .find(|p| &p.stottr_variable == v)
+            {
+                lineage.verb_parameters.insert(v.name.clone());
+                if let Some(d) = &p.default_value {
+                    add_constant_term_predicates(&d.constant_term, &mut lineage.predicates);
+                }
+            }
+        }
+        StottrTerm::ConstantTerm(c) => add_constant_term_predicates(c, &mut lineage.predicates),
+        StottrTerm::List(l) => {
+            for t in l {
+                add_term_lineage(t, template, lineage);
+            }
+        }
+        StottrTerm::Expression(_) => {}
+    }
+}
+
+fn add_constant_term_predicates(constant_term: &ConstantTerm, predicates: &mut HashSet<String>) {
+    match constant_term {
+        ConstantTerm::Constant(ConstantLiteral::IRI(nn)) => {
+            predicates.insert(nn.as_str().to_string());
+        }
+        ConstantTerm::Constant(_) => {}
+        ConstantTerm::ConstantList(l) => {
+            for c in l {
+                add_constant_term_predicates(c, predicates);
+            }
+        }
+    }
+}
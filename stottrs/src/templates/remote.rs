@@ -0,0 +1,175 @@
+use super::{TemplateDataset, TypingError};
+use crate::ast::{Instance, Statement, StottrDocument};
+use crate::constants::OTTR_TRIPLE;
+use crate::document::{document_from_str, wottr_document_from_str};
+use crate::errors::MapperError;
+use log::debug;
+use std::collections::HashSet;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::PathBuf;
+use thiserror::Error;
+use uuid::Uuid;
+
+const ACCEPT: &str = "text/turtle, text/plain;q=0.5, */*;q=0.1";
+const STOTTR_EXTENSION: &str = "stottr";
+const WOTTR_EXTENSION: &str = "ttl";
+
+#[derive(Error, Debug)]
+pub enum RemoteTemplateError {
+    #[error("Fetching templates from {} responded with status {}", .0, .1)]
+    StatusError(String, u16),
+    #[error("Fetching templates from {} failed {}", .0, .1)]
+    TransportError(String, String),
+    #[error("Error using the template cache {}", .0)]
+    CacheError(std::io::Error),
+    #[error("Error reading the templates from {}: {}", .0, .1)]
+    DocumentError(String, MapperError),
+    #[error(transparent)]
+    TypingError(#[from] TypingError),
+}
+
+/// Fetches template documents over HTTP(S), e.g. from the OTTR template library at http://tpl.ottr.xyz
+/// Documents are read as wOTTR when they are served as Turtle or have a .ttl or .wottr extension, and as stOTTR otherwise.
+pub struct RemoteTemplateResolver {
+    agent: ureq::Agent,
+    /// Fetched documents are kept in this folder, and are read from it instead of being fetched again.
+    cache_folder: Option<PathBuf>,
+    /// Templates used by instances but not defined by the documents are fetched from their IRIs,
+    /// as in the OTTR template library, where the IRI of a template is the location of its document.
+    pub resolve_dependencies: bool,
+}
+
+impl RemoteTemplateResolver {
+    pub fn new(cache_folder: Option<PathBuf>) -> RemoteTemplateResolver {
+        RemoteTemplateResolver {
+            agent: ureq::agent(),
+            cache_folder,
+            resolve_dependencies: true,
+        }
+    }
+
+    /// Fetches the documents and, unless resolve_dependencies is unset, the documents of the templates they use.
+    pub fn template_dataset(&self, urls: &[&str]) -> Result<TemplateDataset, RemoteTemplateError> {
+        let mut fetched = HashSet::new();
+        let mut documents = vec![];
+        let mut to_fetch: Vec<String> = urls.iter().map(|u| document_url(u)).collect();
+        while !to_fetch.is_empty() {
+            for url in to_fetch.drain(..) {
+                if fetched.insert(url.clone()) {
+                    documents.push(self.fetch_document(&url)?);
+                }
+            }
+            if self.resolve_dependencies {
+                to_fetch = undefined_templates(&documents)
+                    .into_iter()
+                    .map(|t| document_url(&t))
+                    .filter(|u| !fetched.contains(u))
+                    .collect();
+            }
+        }
+        Ok(TemplateDataset::new(documents)?)
+    }
+
+    pub fn fetch_document(&self, url: &str) -> Result<StottrDocument, RemoteTemplateError> {
+        let (s, is_wottr) = self.fetch(url)?;
+        let doc = if is_wottr {
+            wottr_document_from_str(&s)
+        } else {
+            document_from_str(&s)
+        };
+        doc.map_err(|x| RemoteTemplateError::DocumentError(url.to_string(), x))
+    }
+
+    fn fetch(&self, url: &str) -> Result<(String, bool), RemoteTemplateError> {
+        let cache_path = |is_wottr: bool| {
+            self.cache_folder.as_ref().map(|folder| {
+                let extension = if is_wottr {
+                    WOTTR_EXTENSION
+                } else {
+                    STOTTR_EXTENSION
+                };
+                let name = Uuid::new_v5(&Uuid::NAMESPACE_URL, url.as_bytes()).simple();
+                folder.join(format!("{}.{}", name, extension))
+            })
+        };
+        for is_wottr in [false, true] {
+            if let Some(path) = cache_path(is_wottr) {
+                if path.exists() {
+                    debug!("Reading templates from {} from the cache", url);
+                    let s = read_to_string(path).map_err(RemoteTemplateError::CacheError)?;
+                    return Ok((s, is_wottr));
+                }
+            }
+        }
+
+        debug!("Fetching templates from {}", url);
+        let response = match self.agent.get(url).set("Accept", ACCEPT).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, _)) => {
+                return Err(RemoteTemplateError::StatusError(url.to_string(), status))
+            }
+            Err(e) => {
+                return Err(RemoteTemplateError::TransportError(
+                    url.to_string(),
+                    e.to_string(),
+                ))
+            }
+        };
+        let lowercase_url = url.to_lowercase();
+        let is_wottr = response.content_type().contains("turtle")
+            || lowercase_url.ends_with(".ttl")
+            || lowercase_url.ends_with(".wottr");
+        let s = response
+            .into_string()
+            .map_err(|x| RemoteTemplateError::TransportError(url.to_string(), x.to_string()))?;
+        if let Some(path) = cache_path(is_wottr) {
+            create_dir_all(path.parent().unwrap()).map_err(RemoteTemplateError::CacheError)?;
+            write(path, &s).map_err(RemoteTemplateError::CacheError)?;
+        }
+        Ok((s, is_wottr))
+    }
+}
+
+impl TemplateDataset {
+    /// Reads the templates of the document at the URL and the templates they use, see RemoteTemplateResolver.
+    pub fn from_url(
+        url: &str,
+        cache_folder: Option<PathBuf>,
+    ) -> Result<TemplateDataset, RemoteTemplateError> {
+        RemoteTemplateResolver::new(cache_folder).template_dataset(&[url])
+    }
+}
+
+//Templates in the same document share the IRI of the document up to the fragment
+fn document_url(url: &str) -> String {
+    url.split('#').next().unwrap().to_string()
+}
+
+fn undefined_templates(documents: &Vec<StottrDocument>) -> Vec<String> {
+    let mut defined = HashSet::new();
+    let mut used: Vec<&Instance> = vec![];
+    for d in documents {
+        for s in &d.statements {
+            match s {
+                Statement::Template(t) => {
+                    defined.insert(t.signature.template_name.as_str());
+                    used.extend(t.pattern_list.iter());
+                    if let Some(annotation_list) = &t.signature.annotation_list {
+                        used.extend(annotation_list.iter().map(|a| &a.instance));
+                    }
+                }
+                Statement::Instance(i) => {
+                    used.push(i);
+                }
+            }
+        }
+    }
+    let mut undefined = vec![];
+    for i in used {
+        let name = i.template_name.as_str();
+        if name != OTTR_TRIPLE && !defined.contains(name) && !undefined.iter().any(|u| u == name) {
+            undefined.push(name.to_string());
+        }
+    }
+    undefined
+}
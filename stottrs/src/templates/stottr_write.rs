@@ -0,0 +1,247 @@
+use super::TemplateDataset;
+use crate::ast::{
+    Argument, ConstantLiteral, ConstantTerm, Instance, PType, Parameter, StottrLiteral,
+    StottrTerm, Template,
+};
+use crate::constants::OTTR_TRIPLE;
+use oxrdf::vocab::xsd;
+use oxrdf::NamedNode;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+impl TemplateDataset {
+    /// Writes the templates and ground instances as a stOTTR document that can be parsed again.
+    /// Arguments given by name are written in the order of the parameters, and ottr:Triple is left out.
+    pub fn to_stottr_string(&self) -> String {
+        let mut prefixes = self.prefix_map.clone();
+        //Basic types must be prefixed names, so types with no suitable prefix get one
+        for t in &self.templates {
+            for p in &t.signature.parameter_list {
+                if let Some(ptype) = &p.ptype {
+                    add_type_prefixes(ptype, &mut prefixes);
+                }
+            }
+        }
+        let mut prefix_names: Vec<&String> = prefixes.keys().collect();
+        prefix_names.sort();
+
+        let mut s = String::new();
+        if let Some(base_iri) = &self.base_iri {
+            writeln!(s, "@base {} .", base_iri).unwrap();
+        }
+        for name in prefix_names {
+            writeln!(s, "@prefix {}: {}.", name, prefixes.get(name).unwrap()).unwrap();
+        }
+        for t in &self.templates {
+            if t.signature.template_name.as_str() == OTTR_TRIPLE {
+                continue;
+            }
+            s.push('\n');
+            write_template(&mut s, t, &prefixes);
+        }
+        if !self.ground_instances.is_empty() {
+            s.push('\n');
+        }
+        for i in &self.ground_instances {
+            write_instance(&mut s, i);
+            s.push_str(" .\n");
+        }
+        s
+    }
+}
+
+fn add_type_prefixes(ptype: &PType, prefixes: &mut HashMap<String, NamedNode>) {
+    match ptype {
+        PType::BasicType(nn, _) => {
+            if type_prefixed_name(nn.as_str(), prefixes).is_none() {
+                let mut i = prefixes.len();
+                while prefixes.contains_key(&format!("type{}", i)) {
+                    i += 1;
+                }
+                prefixes.insert(format!("type{}", i), nn.clone());
+            }
+        }
+        PType::LUBType(inner) | PType::ListType(inner) | PType::NEListType(inner) => {
+            add_type_prefixes(inner, prefixes)
+        }
+    }
+}
+
+//The prefix with the shortest local name is used, ties are broken by the name of the prefix
+fn type_prefixed_name(iri: &str, prefixes: &HashMap<String, NamedNode>) -> Option<String> {
+    let mut candidates: Vec<(usize, &String)> = prefixes
+        .iter()
+        .filter_map(|(name, ns)| {
+            let local = iri.strip_prefix(ns.as_str())?;
+            if local
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                Some((local.len(), name))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    let (_, name) = candidates.first()?;
+    let local = &iri[prefixes.get(*name).unwrap().as_str().len()..];
+    Some(format!("{}:{}", name, local))
+}
+
+fn write_template(s: &mut String, t: &Template, prefixes: &HashMap<String, NamedNode>) {
+    write!(s, "{} [", t.signature.template_name).unwrap();
+    for (idx, p) in t.signature.parameter_list.iter().enumerate() {
+        if idx > 0 {
+            s.push_str(", ");
+        }
+        write_parameter(s, p, prefixes);
+    }
+    s.push(']');
+    if let Some(annotation_list) = &t.signature.annotation_list {
+        for (idx, a) in annotation_list.iter().enumerate() {
+            if idx > 0 {
+                s.push(',');
+            }
+            s.push_str(" @@");
+            write_instance(s, &a.instance);
+        }
+    }
+    s.push_str(" :: {\n");
+    for (idx, i) in t.pattern_list.iter().enumerate() {
+        s.push_str("  ");
+        write_instance(s, i);
+        if idx + 1 != t.pattern_list.len() {
+            s.push_str(" ,");
+        }
+        s.push('\n');
+    }
+    s.push_str("} .\n");
+}
+
+fn write_parameter(s: &mut String, p: &Parameter, prefixes: &HashMap<String, NamedNode>) {
+    match (p.optional, p.non_blank) {
+        (true, true) => s.push_str("!?"),
+        (true, false) => s.push('?'),
+        (false, true) => s.push('!'),
+        (false, false) => {}
+    }
+    if p.optional || p.non_blank {
+        s.push(' ');
+    }
+    if let Some(ptype) = &p.ptype {
+        write_ptype(s, ptype, prefixes);
+        s.push(' ');
+    }
+    write!(s, "?{}", p.stottr_variable.name).unwrap();
+    if let Some(default_value) = &p.default_value {
+        s.push_str(" = ");
+        write_constant_term(s, &default_value.constant_term);
+    }
+}
+
+fn write_ptype(s: &mut String, ptype: &PType, prefixes: &HashMap<String, NamedNode>) {
+    match ptype {
+        PType::BasicType(nn, _) => {
+            s.push_str(&type_prefixed_name(nn.as_str(), prefixes).unwrap());
+        }
+        PType::LUBType(inner) => {
+            s.push_str("LUB<");
+            write_ptype(s, inner, prefixes);
+            s.push('>');
+        }
+        PType::ListType(inner) => {
+            s.push_str("List<");
+            write_ptype(s, inner, prefixes);
+            s.push('>');
+        }
+        PType::NEListType(inner) => {
+            s.push_str("NEList<");
+            write_ptype(s, inner, prefixes);
+            s.push('>');
+        }
+    }
+}
+
+fn write_instance(s: &mut String, i: &Instance) {
+    if let Some(le) = &i.list_expander {
+        write!(s, "{} | ", le).unwrap();
+    }
+    write!(s, "{}(", i.template_name).unwrap();
+    for (idx, a) in i.argument_list.iter().enumerate() {
+        if idx > 0 {
+            s.push_str(", ");
+        }
+        write_argument(s, a);
+    }
+    s.push(')');
+}
+
+//Arguments are ordered by parameter when the templates are read, so the names are not needed
+fn write_argument(s: &mut String, a: &Argument) {
+    if a.list_expand {
+        s.push_str("++ ");
+    }
+    write_term(s, &a.term);
+}
+
+fn write_term(s: &mut String, t: &StottrTerm) {
+    match t {
+        StottrTerm::Variable(v) => {
+            write!(s, "?{}", v.name).unwrap();
+        }
+        StottrTerm::ConstantTerm(ct) => write_constant_term(s, ct),
+        StottrTerm::List(l) => {
+            s.push('(');
+            for (idx, t) in l.iter().enumerate() {
+                if idx > 0 {
+                    s.push_str(", ");
+                }
+                write_term(s, t);
+            }
+            s.push(')');
+        }
+        StottrTerm::Expression(e) => {
+            write_term(s, &e.left);
+            write!(s, " {} ", e.operator).unwrap();
+            write_term(s, &e.right);
+        }
+    }
+}
+
+fn write_constant_term(s: &mut String, ct: &ConstantTerm) {
+    match ct {
+        ConstantTerm::Constant(c) => match c {
+            ConstantLiteral::IRI(nn) => write!(s, "{}", nn).unwrap(),
+            ConstantLiteral::BlankNode(bn) => write!(s, "{}", bn).unwrap(),
+            ConstantLiteral::Literal(lit) => write_literal(s, lit),
+            ConstantLiteral::None => s.push_str("none"),
+        },
+        ConstantTerm::ConstantList(l) => {
+            s.push('(');
+            for (idx, ct) in l.iter().enumerate() {
+                if idx > 0 {
+                    s.push_str(", ");
+                }
+                write_constant_term(s, ct);
+            }
+            s.push(')');
+        }
+    }
+}
+
+//Values are kept as written in the document, with escapes, so they are written as they are
+fn write_literal(s: &mut String, lit: &StottrLiteral) {
+    if let Some(language) = &lit.language {
+        write!(s, "\"{}\"@{}", lit.value, language).unwrap();
+    } else {
+        match &lit.data_type_iri {
+            Some(dt) if dt.as_ref() != xsd::STRING => {
+                write!(s, "\"{}\"^^{}", lit.value, dt).unwrap();
+            }
+            _ => {
+                write!(s, "\"{}\"", lit.value).unwrap();
+            }
+        }
+    }
+}
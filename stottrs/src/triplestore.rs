@@ -1,36 +1,95 @@
 pub(crate) mod conversion;
+pub mod chunk_metadata;
+pub mod custom_datatypes;
+pub mod deduplication;
+mod diagnostics;
+#[cfg(feature = "endpoint")]
+pub mod endpoint;
+pub mod export_size;
 mod export_triples;
+#[cfg(feature = "flight")]
+pub mod flight;
+mod ipc_write;
+pub mod join_hints;
+pub mod harmonize;
+pub mod incremental_export;
+pub mod iri_validation;
 pub mod native_parquet_write;
-mod ntriples_write;
+pub mod ntriples_write;
+mod object_index;
 mod parquet;
+mod partition;
+mod project_settings;
+pub mod recovery;
+pub mod retention;
+pub mod serializer;
 pub mod sparql;
+pub mod tenancy;
+mod turtle_write;
 
 use crate::mapping::RDFNodeType;
-use crate::triplestore::parquet::{property_to_filename, read_parquet, split_write_df, write_parquet};
+use crate::triplestore::chunk_metadata::ChunkMetadata;
+use crate::triplestore::custom_datatypes::CustomDatatype;
+use crate::triplestore::deduplication::DeduplicationStrategy;
+use crate::triplestore::harmonize::{harmonize_lazy_frames, harmonize_triples_df};
+use crate::triplestore::iri_validation::IriValidation;
+use crate::triplestore::join_hints::JoinHint;
+use crate::triplestore::recovery::InProgressManifest;
+use crate::triplestore::retention::RetentionPolicy;
+use crate::triplestore::sparql::query_cursors::QueryCursor;
+use crate::triplestore::parquet::{
+    property_to_filename, read_parquet, read_parquet_with_added_sequence, split_write_df,
+    write_sorted_parquet, ADDED_SEQUENCE_COLUMN,
+};
+use chrono::NaiveDateTime;
 use log::debug;
 use oxrdf::vocab::xsd;
-use polars::prelude::{concat, IntoLazy, LazyFrame};
+use polars::prelude::{col, concat, lit, IntoLazy, LazyFrame};
 use polars_core::datatypes::AnyValue;
 use polars_core::frame::{DataFrame, UniqueKeepStrategy};
-use polars_core::prelude::DataType;
+use polars_core::prelude::{DataType, IntoSeries, JoinType, NamedFrom, Schema, Utf8Chunked};
 use polars_core::series::Series;
 use rayon::iter::{IntoParallelRefIterator, ParallelDrainRange};
 use rayon::iter::ParallelIterator;
-use std::collections::HashMap;
-use std::fs::remove_file;
+use std::collections::{HashMap, HashSet};
+use std::fs::{remove_file, rename};
 use std::io;
 use std::path::Path;
 use std::time::Instant;
 use polars_core::utils::concat_df;
+use polars_core::prelude::IdxSize;
 use uuid::Uuid;
 use crate::mapping::errors::MappingError;
 
-const LANGUAGE_TAG_COLUMN: &str = "language_tag";
+pub(crate) const LANGUAGE_TAG_COLUMN: &str = "language_tag";
+const LANGUAGE_TAG_KEY_COLUMN: &str = "language_tag_key";
 
 pub struct Triplestore {
     deduplicated: bool,
     pub(crate) caching_folder: Option<String>,
     df_map: HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    named_graph_df_maps: HashMap<String, HashMap<String, HashMap<RDFNodeType, TripleTable>>>,
+    object_indexed_predicates: HashSet<String>,
+    retention_policies: HashMap<String, RetentionPolicy>,
+    pub(crate) harmonize_dtypes: bool,
+    pub(crate) collapse_language_tags: bool,
+    query_cursors: HashMap<String, QueryCursor>,
+    //Maximum number of triples in the graph of each tenant
+    tenant_quotas: HashMap<String, usize>,
+    //The graph of the tenant whose triples are the default graph while a tenant is queried or exported
+    tenant_graph: Option<String>,
+    //The time a query started, which is the value of NOW() in the query
+    query_time: Option<NaiveDateTime>,
+    join_hints: HashSet<JoinHint>,
+    pub(crate) deduplication_strategy: DeduplicationStrategy,
+    iri_validation: Option<IriValidation>,
+    predicate_iri_validations: HashMap<String, IriValidation>,
+    //Triples rejected because of invalid IRIs, until they are taken
+    iri_violations: Vec<DataFrame>,
+    //Literal datatypes that are not built in, keyed by the datatype IRI
+    pub(crate) custom_datatypes: HashMap<String, CustomDatatype>,
+    //Sequence number of the next call adding triples
+    added_sequence: u64,
 }
 
 pub struct TripleTable {
@@ -39,6 +98,18 @@ pub struct TripleTable {
     unique: bool,
     call_uuid: String,
     tmp_df: Option<DataFrame>,
+    //Rows of each chunk by object value
+    object_index: Option<HashMap<String, Vec<(usize, Vec<IdxSize>)>>>,
+    chunk_metadata: Vec<ChunkMetadata>,
+    //The number of chunks at the start of the table known to have no duplicates
+    unique_chunks: usize,
+    //The keys of the triples in these chunks, used by incremental deduplication
+    key_index: Option<DataFrame>,
+    //Sequence number of the last call adding triples to the table, which orders the triples of tables in memory
+    added_sequence: u64,
+    //Replaced whenever chunks are rewritten, so that exported chunks can be told apart from new ones.
+    //Each table gets its own, so a table that is removed and added again is not mistaken for the one exported.
+    generation: String,
 }
 
 impl TripleTable {
@@ -97,60 +168,114 @@ pub struct TriplesToAdd {
     pub(crate) language_tag: Option<String>,
     pub(crate) static_verb_column: Option<String>,
     pub has_unique_subset: bool,
+    pub(crate) globally_unique: bool,
+    pub(crate) graph: Option<String>,
+}
+
+/// Number of rows given and added for a predicate when triples are added to the store.
+/// Rows with a null subject or object are dropped, and duplicate rows are removed unless a unique subset is given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripleCountDelta {
+    pub predicate: String,
+    pub graph: Option<String>,
+    pub input_rows: usize,
+    pub null_rows: usize,
+    pub duplicate_rows: usize,
+    pub added_rows: usize,
+}
+
+impl TripleCountDelta {
+    fn merge(&mut self, other: &TripleCountDelta) {
+        self.input_rows += other.input_rows;
+        self.null_rows += other.null_rows;
+        self.duplicate_rows += other.duplicate_rows;
+        self.added_rows += other.added_rows;
+    }
+}
+
+pub(crate) fn merge_triple_counts(
+    counts: &mut Vec<TripleCountDelta>,
+    new_counts: Vec<TripleCountDelta>,
+) {
+    for c in new_counts {
+        if let Some(existing) = counts
+            .iter_mut()
+            .find(|x| x.predicate == c.predicate && x.graph == c.graph)
+        {
+            existing.merge(&c);
+        } else {
+            counts.push(c);
+        }
+    }
+    counts.sort_by(|x, y| x.predicate.cmp(&y.predicate).then(x.graph.cmp(&y.graph)));
 }
 
 pub struct TripleDF {
     df: DataFrame,
     predicate: String,
     object_type: RDFNodeType,
+    graph: Option<String>,
+    globally_unique: bool,
 }
 
 impl Triplestore {
     pub fn new(caching_folder: Option<String>) -> Triplestore {
         Triplestore {
             df_map: HashMap::new(),
+            named_graph_df_maps: HashMap::new(),
             deduplicated: true,
             caching_folder,
+            object_indexed_predicates: HashSet::new(),
+            retention_policies: HashMap::new(),
+            harmonize_dtypes: false,
+            collapse_language_tags: false,
+            query_cursors: HashMap::new(),
+            tenant_quotas: HashMap::new(),
+            tenant_graph: None,
+            query_time: None,
+            join_hints: HashSet::new(),
+            deduplication_strategy: DeduplicationStrategy::default(),
+            iri_validation: None,
+            predicate_iri_validations: HashMap::new(),
+            iri_violations: vec![],
+            custom_datatypes: HashMap::new(),
+            added_sequence: 1,
         }
     }
 
     pub fn deduplicate(&mut self) -> Result<(), MappingError> {
         let now = Instant::now();
-        for (predicate, map) in &mut self.df_map {
-            for (_, v) in map {
-                if !v.unique {
-                    if self.caching_folder.is_some() {
-                        let lf_results:Vec<Result<LazyFrame, MappingError>> = v.df_paths.as_ref().unwrap().par_iter().map(|x|read_parquet(x)).collect();
-                        let mut lfs = vec![];
-                        for lf_res in lf_results {
-                            lfs.push(lf_res?);
-                        }
-                        let unique_df = concat(lfs, true, true).unwrap().unique(None, UniqueKeepStrategy::First).collect().unwrap();
-                        //TODO: Implement trick with len to avoid IO
-                        let removed:Vec<Result<(), io::Error>> = v.df_paths.as_ref().unwrap().par_iter().map(|x| remove_file(Path::new(x))).collect();
-                        for r in removed {
-                            r.map_err(|x|MappingError::RemoveParquetFileError(x))?
-                        }
-                        let paths = split_write_df(self.caching_folder.as_ref().unwrap(), unique_df, predicate)?;
-                        v.df_paths = Some(paths);
-                        v.unique = true;
-                    } else {
-                        let drained: Vec<LazyFrame> = v.dfs.as_mut().unwrap().drain(..).map(|x| x.lazy()).collect();
-                        let mut lf = concat(drained.as_slice(), true, true).unwrap();
-                        lf = lf.unique(None, UniqueKeepStrategy::First);
-                        v.dfs.as_mut().unwrap().push(lf.collect().unwrap());
-                        v.unique = true;
-                    }
-                }
-            }
+        deduplicate_map(
+            &mut self.df_map,
+            &self.caching_folder,
+            self.harmonize_dtypes,
+            self.collapse_language_tags,
+            self.deduplication_strategy,
+        )?;
+        for (_, map) in &mut self.named_graph_df_maps {
+            deduplicate_map(
+                map,
+                &self.caching_folder,
+                self.harmonize_dtypes,
+                self.collapse_language_tags,
+                self.deduplication_strategy,
+            )?;
         }
+        //Retention policies are applied to the deduplicated triples, so that duplicates do not count towards row limits
+        self.apply_retention_policies_to_deduplicated()?;
         self.deduplicated = true;
         debug!("Deduplication took {} seconds", now.elapsed().as_secs_f64());
         Ok(())
     }
 
-    pub fn add_triples_vec(&mut self, mut ts: Vec<TriplesToAdd>, call_uuid: &String) -> Result<(), MappingError> {
-        let df_vecs_to_add: Vec<Vec<TripleDF>> = ts
+    /// Returns the number of rows given and added for each predicate.
+    pub fn add_triples_vec(
+        &mut self,
+        mut ts: Vec<TriplesToAdd>,
+        call_uuid: &String,
+    ) -> Result<Vec<TripleCountDelta>, MappingError> {
+        self.check_tenant_quotas(&ts)?;
+        let df_vecs_to_add: Vec<(Vec<TripleDF>, Vec<TripleCountDelta>)> = ts
             .par_drain(..)
             .map(|t| {
                 let TriplesToAdd {
@@ -159,22 +284,97 @@ impl Triplestore {
                     language_tag,
                     static_verb_column,
                     has_unique_subset,
+                    globally_unique,
+                    graph,
                 } = t;
-                let prepared_triples = prepare_triples(
+                let (mut prepared_triples, counts) = prepare_triples(
                     df,
                     &object_type,
                     &language_tag,
                     static_verb_column,
                     has_unique_subset,
+                    &graph,
                 );
-                prepared_triples
+                for tdf in &mut prepared_triples {
+                    tdf.globally_unique = globally_unique;
+                }
+                (prepared_triples, counts)
             })
             .collect();
-        let dfs_to_add = flatten(df_vecs_to_add);
+        let mut df_vecs = vec![];
+        let mut triple_counts = vec![];
+        for (dfs, counts) in df_vecs_to_add {
+            df_vecs.push(dfs);
+            merge_triple_counts(&mut triple_counts, counts);
+        }
+        let dfs_to_add = flatten(df_vecs);
+        let dfs_to_add = self.validate_iris(dfs_to_add, &mut triple_counts);
         self.add_triples_df(dfs_to_add, call_uuid)?;
+        Ok(triple_counts)
+    }
+
+    /// When set, chunks of a predicate with different data types are cast to a common supertype
+    /// when they are combined, instead of raising an error.
+    pub fn set_harmonize_dtypes(&mut self, harmonize_dtypes: bool) {
+        self.harmonize_dtypes = harmonize_dtypes;
+    }
+
+    /// Triples with the same subject and object but different language tags are distinct.
+    /// When set, such triples are collapsed into the first one added when deduplicating.
+    pub fn set_collapse_language_tags(&mut self, collapse_language_tags: bool) {
+        self.collapse_language_tags = collapse_language_tags;
+        if collapse_language_tags {
+            self.deduplicated = false;
+            for map in self.named_graph_df_maps.values_mut().chain(std::iter::once(&mut self.df_map)) {
+                for (rdf_node_type, tt) in map.values_mut().flat_map(|m| m.iter_mut()) {
+                    if rdf_node_type.is_lit_type(xsd::STRING) && tt.len() > 1 {
+                        tt.unique = false;
+                        tt.reset_key_index();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn named_graphs(&self) -> Vec<&String> {
+        let mut graphs: Vec<&String> = self.named_graph_df_maps.keys().collect();
+        graphs.sort();
+        graphs
+    }
+
+    //Formats and exports without graphs fail rather than leaving out the triples of named graphs
+    pub(crate) fn check_no_named_graphs(&self, format: &str) -> Result<(), MappingError> {
+        if self.named_graph_df_maps.values().any(|m| !m.is_empty()) {
+            return Err(MappingError::NamedGraphsNotSupported(format.to_string()));
+        }
         Ok(())
     }
 
+    pub(crate) fn graph_df_map(
+        &self,
+        graph: &Option<String>,
+    ) -> Option<&HashMap<String, HashMap<RDFNodeType, TripleTable>>> {
+        if let Some(g) = graph {
+            self.named_graph_df_maps.get(g)
+        } else {
+            Some(&self.df_map)
+        }
+    }
+
+    fn graph_df_map_mut(
+        &mut self,
+        graph: &Option<String>,
+    ) -> &mut HashMap<String, HashMap<RDFNodeType, TripleTable>> {
+        if let Some(g) = graph {
+            if !self.named_graph_df_maps.contains_key(g) {
+                self.named_graph_df_maps.insert(g.clone(), HashMap::new());
+            }
+            self.named_graph_df_maps.get_mut(g).unwrap()
+        } else {
+            &mut self.df_map
+        }
+    }
+
     fn add_triples_df(&mut self, triples_df: Vec<TripleDF>, call_uuid: &String) -> Result<(), MappingError> {
         if let Some(_) = &self.caching_folder {
             self.add_triples_df_with_folder(triples_df, call_uuid)?;
@@ -186,39 +386,71 @@ impl Triplestore {
 
     fn add_triples_df_with_folder(&mut self, mut triples_df: Vec<TripleDF>, call_uuid: &String) -> Result<(), MappingError>{
         let folder_path = Path::new(self.caching_folder.as_ref().unwrap());
-        let file_paths: Vec<(String, Result<_, _>, String, RDFNodeType)> = triples_df
-            .par_drain(..)
+        let mut triples_df: Vec<(TripleDF, String)> = triples_df
+            .drain(..)
             .map(|tdf| {
+                let file_name = format!(
+                    "{}_{}.parquet",
+                    property_to_filename(&tdf.predicate),
+                    Uuid::new_v4()
+                );
+                let file_path = folder_path.join(file_name).to_str().unwrap().to_string();
+                (tdf, file_path)
+            })
+            .collect();
+        let in_progress = InProgressManifest::create(
+            self.caching_folder.as_ref().unwrap(),
+            &triples_df.iter().map(|(_, p)| p.clone()).collect::<Vec<_>>(),
+        )?;
+        let added_sequence = self.added_sequence;
+        self.added_sequence += 1;
+        let file_paths: Vec<(String, Result<_, _>, ChunkMetadata, String, RDFNodeType, Option<String>, bool)> = triples_df
+            .par_drain(..)
+            .map(|(tdf, file_path)| {
                 let TripleDF {
                     mut df,
                     predicate,
                     object_type,
+                    graph,
+                    globally_unique,
                 } = tdf;
-                let file_name = format!(
-                    "{}_{}.parquet",
-                    property_to_filename(&predicate),
-                    Uuid::new_v4()
-                );
-                let mut file_path_buf = folder_path.to_path_buf();
-                file_path_buf.push(file_name);
-                let file_path = file_path_buf.as_path();
+                let metadata = ChunkMetadata::from_df(&df);
+                df.with_column(Series::new(
+                    ADDED_SEQUENCE_COLUMN,
+                    vec![added_sequence; df.height()],
+                ))
+                .unwrap();
+                let res = write_sorted_parquet(&mut df, Path::new(&file_path));
                 (
-                    file_path.to_str().unwrap().to_string(),
-                    write_parquet(&mut df, &file_path),
+                    file_path,
+                    res,
+                    metadata,
                     predicate,
                     object_type,
+                    graph,
+                    globally_unique,
                 )
             })
             .collect();
-        for (file_path, res, predicate, object_type) in file_paths {
+        let mut deduplicated = true;
+        let collapse_language_tags = self.collapse_language_tags;
+        for (file_path, res, metadata, predicate, object_type, graph, globally_unique) in file_paths {
             res?;
+            let df_map = self.graph_df_map_mut(&graph);
             //Safe to assume everything is unique
-            if let Some(m) = self.df_map.get_mut(&predicate) {
+            if let Some(m) = df_map.get_mut(&predicate) {
                 if let Some(v) = m.get_mut(&object_type) {
                     v.df_paths.as_mut().unwrap().push(file_path);
-                    v.unique = v.unique && (call_uuid == &v.call_uuid);
-                    if !v.unique {
-                        self.deduplicated = false;
+                    v.chunk_metadata.push(metadata);
+                    v.added_sequence = added_sequence;
+                    v.object_index = None;
+                    v.unique = v.unique
+                        && (globally_unique || call_uuid == &v.call_uuid)
+                        && !(collapse_language_tags && object_type.is_lit_type(xsd::STRING));
+                    if v.unique {
+                        v.unique_chunks = v.len();
+                    } else {
+                        deduplicated = false;
                     }
                 } else {
                     m.insert(
@@ -229,11 +461,17 @@ impl Triplestore {
                             unique: true,
                             call_uuid: call_uuid.clone(),
                             tmp_df:None,
+                            object_index: None,
+                            chunk_metadata: vec![metadata],
+                            unique_chunks: 1,
+                            key_index: None,
+                            added_sequence,
+                            generation: Uuid::new_v4().to_string(),
                         },
                     );
                 }
             } else {
-                self.df_map.insert(
+                df_map.insert(
                     predicate,
                     HashMap::from([(
                         object_type,
@@ -242,84 +480,321 @@ impl Triplestore {
                             df_paths: Some(vec![file_path]),
                             unique: true,
                             call_uuid: call_uuid.clone(),
-                            tmp_df:None
+                            tmp_df:None,
+                            object_index: None,
+                            chunk_metadata: vec![metadata],
+                            unique_chunks: 1,
+                            key_index: None,
+                            added_sequence,
+                            generation: Uuid::new_v4().to_string(),
                         },
                     )]),
                 );
             }
         }
+        in_progress.finish()?;
+        if !deduplicated {
+            self.deduplicated = false;
+        }
         Ok(())
     }
 
     fn add_triples_df_without_folder(&mut self, triples_df: Vec<TripleDF>, call_uuid: &String) {
+        let added_sequence = self.added_sequence;
+        self.added_sequence += 1;
+        let mut deduplicated = true;
+        let collapse_language_tags = self.collapse_language_tags;
         for TripleDF {
             df,
             predicate,
             object_type,
+            graph,
+            globally_unique,
         } in triples_df
         {
+            let df_map = self.graph_df_map_mut(&graph);
             //Safe to assume everything is unique
-            if let Some(m) = self.df_map.get_mut(&predicate) {
+            if let Some(m) = df_map.get_mut(&predicate) {
                 if let Some(v) = m.get_mut(&object_type) {
+                    v.chunk_metadata.push(ChunkMetadata::from_df(&df));
                     v.dfs.as_mut().unwrap().push(df);
-                    v.unique = v.unique && (call_uuid == &v.call_uuid);
-                    if !v.unique {
-                        self.deduplicated = false;
+                    v.added_sequence = added_sequence;
+                    v.object_index = None;
+                    v.unique = v.unique
+                        && (globally_unique || call_uuid == &v.call_uuid)
+                        && !(collapse_language_tags && object_type.is_lit_type(xsd::STRING));
+                    if v.unique {
+                        v.unique_chunks = v.len();
+                    } else {
+                        deduplicated = false;
                     }
                 } else {
                     m.insert(
                         object_type,
                         TripleTable {
+                            chunk_metadata: vec![ChunkMetadata::from_df(&df)],
                             dfs: Some(vec![df]),
                             df_paths: None,
                             unique: true,
                             call_uuid: call_uuid.clone(),
-                            tmp_df:None
+                            tmp_df:None,
+                            object_index: None,
+                            unique_chunks: 1,
+                            key_index: None,
+                            added_sequence,
+                            generation: Uuid::new_v4().to_string(),
                         },
                     );
                 }
             } else {
-                self.df_map.insert(
+                df_map.insert(
                     predicate,
                     HashMap::from([(
                         object_type,
                         TripleTable {
+                            chunk_metadata: vec![ChunkMetadata::from_df(&df)],
                             dfs: Some(vec![df]),
                             df_paths: None,
                             unique: true,
                             call_uuid: call_uuid.clone(),
                             tmp_df:None,
+                            object_index: None,
+                            unique_chunks: 1,
+                            key_index: None,
+                            added_sequence,
+                            generation: Uuid::new_v4().to_string(),
                         },
                     )]),
                 );
             }
         }
+        if !deduplicated {
+            self.deduplicated = false;
+        }
+    }
+
+    pub(crate) fn remove_triples(
+        &mut self,
+        df: DataFrame,
+        object_type: &RDFNodeType,
+        graph: &Option<String>,
+    ) -> Result<(), MappingError> {
+        if df.height() == 0 {
+            return Ok(());
+        }
+        let df_map = if let Some(g) = graph {
+            if let Some(m) = self.named_graph_df_maps.get_mut(g) {
+                m
+            } else {
+                return Ok(());
+            }
+        } else {
+            &mut self.df_map
+        };
+        let df = df.lazy().with_column(col("verb").cast(DataType::Utf8)).collect().unwrap();
+        let partitions = df.partition_by(["verb"]).unwrap();
+        for part in partitions {
+            let predicate;
+            {
+                let any_predicate = part.column("verb").unwrap().get(0);
+                if let AnyValue::Utf8(p) = any_predicate {
+                    predicate = p.to_string();
+                } else {
+                    return Err(MappingError::InvalidPredicateValue(any_predicate.to_string()));
+                }
+            }
+            let mut remove_table = false;
+            if let Some(m) = df_map.get_mut(&predicate) {
+                if let Some(tt) = m.get_mut(object_type) {
+                    let mut remove_columns = vec!["subject", "object"];
+                    if part.get_column_names().contains(&LANGUAGE_TAG_COLUMN) {
+                        remove_columns.push(LANGUAGE_TAG_COLUMN);
+                    }
+                    let to_remove = part.select(remove_columns).unwrap();
+                    if let Some(dfs) = &mut tt.dfs {
+                        let drained: Vec<DataFrame> = dfs.drain(..).collect();
+                        for d in drained {
+                            let remaining = anti_join_triples(d, &to_remove);
+                            if remaining.height() > 0 {
+                                dfs.push(remaining);
+                            }
+                        }
+                    } else if let Some(paths) = &mut tt.df_paths {
+                        //The paths of the table are only updated when all files are rewritten,
+                        //so that an error leaves the table with all its files
+                        let mut kept = vec![];
+                        let mut emptied = vec![];
+                        for p in paths.iter() {
+                            let d = read_parquet_with_added_sequence(p)?.collect().unwrap();
+                            let height = d.height();
+                            let mut remaining = anti_join_triples(d, &to_remove);
+                            if remaining.height() == 0 {
+                                emptied.push(p.clone());
+                            } else {
+                                if remaining.height() < height {
+                                    replace_parquet(&mut remaining, p)?;
+                                }
+                                kept.push(p.clone());
+                            }
+                        }
+                        *paths = kept;
+                        for p in emptied {
+                            remove_file(Path::new(&p)).map_err(|x| MappingError::RemoveParquetFileError(x))?;
+                        }
+                    }
+                    tt.object_index = None;
+                    tt.reset_key_index();
+                    tt.refresh_chunk_metadata()?;
+                    remove_table = tt.len() == 0;
+                }
+                if remove_table {
+                    m.remove(object_type);
+                }
+                if m.is_empty() {
+                    df_map.remove(&predicate);
+                }
+            }
+        }
+        if let Some(g) = graph {
+            if self.named_graph_df_maps.get(g).map_or(false, |m| m.is_empty()) {
+                self.named_graph_df_maps.remove(g);
+            }
+        }
+        Ok(())
     }
 }
 
+//The remaining triples are written to a temporary file that replaces the original,
+//so the original file is intact if the write fails
+fn replace_parquet(df: &mut DataFrame, path: &str) -> Result<(), MappingError> {
+    let tmp_path = format!("{}.tmp", path);
+    let folder = Path::new(path).parent().unwrap().to_str().unwrap();
+    let in_progress = InProgressManifest::create(folder, &[tmp_path.clone()])?;
+    write_sorted_parquet(df, Path::new(&tmp_path))?;
+    rename(&tmp_path, path).map_err(|x| MappingError::FileCreateIOError(x))?;
+    in_progress.finish()
+}
+
+//Language tags are part of the key of string triples, with a missing tag only matching a missing tag
+fn anti_join_triples(df: DataFrame, to_remove: &DataFrame) -> DataFrame {
+    let object_dtype = df.column("object").unwrap().dtype().clone();
+    let has_language_tags = df.get_column_names().contains(&LANGUAGE_TAG_COLUMN);
+    let mut to_remove_lf = to_remove
+        .clone()
+        .lazy()
+        .with_column(col("subject").cast(DataType::Utf8))
+        .with_column(col("object").cast(object_dtype));
+    let mut on = vec![col("subject"), col("object")];
+    let mut lf = df.lazy();
+    if has_language_tags {
+        let remove_tag = if to_remove.get_column_names().contains(&LANGUAGE_TAG_COLUMN) {
+            col(LANGUAGE_TAG_COLUMN).cast(DataType::Utf8).fill_null(lit(""))
+        } else {
+            lit("")
+        };
+        to_remove_lf = to_remove_lf.with_column(remove_tag.alias(LANGUAGE_TAG_KEY_COLUMN));
+        lf = lf.with_column(
+            col(LANGUAGE_TAG_COLUMN)
+                .fill_null(lit(""))
+                .alias(LANGUAGE_TAG_KEY_COLUMN),
+        );
+        on.push(col(LANGUAGE_TAG_KEY_COLUMN));
+    }
+    let mut lf = lf.join(to_remove_lf, on.clone(), on, JoinType::Anti);
+    if has_language_tags {
+        lf = lf.drop_columns([LANGUAGE_TAG_KEY_COLUMN]);
+    }
+    lf.collect().unwrap()
+}
+
+fn deduplicate_map(
+    df_map: &mut HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    caching_folder: &Option<String>,
+    harmonize_dtypes: bool,
+    collapse_language_tags: bool,
+    deduplication_strategy: DeduplicationStrategy,
+) -> Result<(), MappingError> {
+    for (predicate, map) in df_map {
+        for (rdf_node_type, v) in map {
+            if !v.unique {
+                let subset = unique_subset(rdf_node_type, collapse_language_tags);
+                if deduplication_strategy == DeduplicationStrategy::Incremental
+                    && v.deduplicate_incrementally(caching_folder, predicate, subset.clone())?
+                {
+                    continue;
+                }
+                if let Some(caching_folder) = caching_folder {
+                    let lf_results:Vec<Result<LazyFrame, MappingError>> = v.df_paths.as_ref().unwrap().par_iter().map(|x|read_parquet_with_added_sequence(x)).collect();
+                    let mut lfs = vec![];
+                    for lf_res in lf_results {
+                        lfs.push(lf_res?);
+                    }
+                    let lfs = harmonize_lazy_frames(lfs, predicate, harmonize_dtypes)?;
+                    let subset = subset_without_added_sequence(subset, &lfs[0].schema().unwrap());
+                    let unique_df = concat(lfs, true, true).unwrap().unique_stable(subset, UniqueKeepStrategy::First).collect().unwrap();
+                    //TODO: Implement trick with len to avoid IO
+                    let removed:Vec<Result<(), io::Error>> = v.df_paths.as_ref().unwrap().par_iter().map(|x| remove_file(Path::new(x))).collect();
+                    for r in removed {
+                        r.map_err(|x|MappingError::RemoveParquetFileError(x))?
+                    }
+                    let paths = split_write_df(caching_folder, unique_df, predicate)?;
+                    v.df_paths = Some(paths);
+                    v.refresh_chunk_metadata()?;
+                    v.unique = true;
+                    v.reset_key_index();
+                } else {
+                    let lfs: Vec<LazyFrame> = v.dfs.as_ref().unwrap().iter().map(|x| x.clone().lazy()).collect();
+                    //Checked before draining so that the table is left intact on errors
+                    let lfs = harmonize_lazy_frames(lfs, predicate, harmonize_dtypes)?;
+                    v.dfs.as_mut().unwrap().clear();
+                    let mut lf = concat(lfs.as_slice(), true, true).unwrap();
+                    //The order of the triples is kept so that writing the store is reproducible
+                    lf = lf.unique_stable(subset, UniqueKeepStrategy::First);
+                    v.dfs.as_mut().unwrap().push(lf.collect().unwrap());
+                    v.object_index = None;
+                    v.refresh_chunk_metadata()?;
+                    v.unique = true;
+                    v.reset_key_index();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn prepare_triples(
     mut df: DataFrame,
     object_type: &RDFNodeType,
     language_tag: &Option<String>,
     static_verb_column: Option<String>,
     has_unique_subset: bool,
-) -> Vec<TripleDF> {
+    graph: &Option<String>,
+) -> (Vec<TripleDF>, Vec<TripleCountDelta>) {
     let now = Instant::now();
     let mut out_df_vec = vec![];
+    let mut out_counts = vec![];
     if df.height() == 0 {
-        return vec![];
+        return (vec![], vec![]);
+    }
+    //Per-row language tags are in a column of the df, and take precedence over the language tag
+    let mut keep_columns = vec!["subject", "object"];
+    if df.get_column_names().contains(&LANGUAGE_TAG_COLUMN) {
+        keep_columns.push(LANGUAGE_TAG_COLUMN);
     }
     if let Some(static_verb_column) = static_verb_column {
-        df = df.select(["subject", "object"]).unwrap();
-        if let Some(tdf) = prepare_triples_df(
+        df = df.select(keep_columns.as_slice()).unwrap();
+        let (tdf_opt, counts) = prepare_triples_df(
             df,
             static_verb_column,
             object_type,
             language_tag,
             has_unique_subset,
-        ) {
+            graph,
+        );
+        if let Some(tdf) = tdf_opt {
             out_df_vec.push(tdf);
         }
+        out_counts.push(counts);
     } else {
         let partitions = df.partition_by(["verb"]).unwrap();
         for mut part in partitions {
@@ -332,23 +807,26 @@ pub fn prepare_triples(
                     panic!()
                 }
             }
-            part = part.select(["subject", "object"]).unwrap();
-            if let Some(tdf) = prepare_triples_df(
+            part = part.select(keep_columns.as_slice()).unwrap();
+            let (tdf_opt, counts) = prepare_triples_df(
                 part,
                 predicate,
                 object_type,
                 language_tag,
                 has_unique_subset,
-            ) {
+                graph,
+            );
+            if let Some(tdf) = tdf_opt {
                 out_df_vec.push(tdf);
             }
+            out_counts.push(counts);
         }
     }
     debug!(
         "Adding triples took {} seconds",
         now.elapsed().as_secs_f32()
     );
-    out_df_vec
+    (out_df_vec, out_counts)
 }
 
 fn prepare_triples_df(
@@ -357,49 +835,105 @@ fn prepare_triples_df(
     object_type: &RDFNodeType,
     language_tag: &Option<String>,
     has_unique_subset: bool,
-) -> Option<TripleDF> {
+    graph: &Option<String>,
+) -> (Option<TripleDF>, TripleCountDelta) {
     let now = Instant::now();
-    df = df.drop_nulls(None).unwrap();
+    let input_rows = df.height();
+    df = df
+        .drop_nulls(Some(&["subject".to_string(), "object".to_string()]))
+        .unwrap();
+    let mut counts = TripleCountDelta {
+        predicate: predicate.clone(),
+        graph: graph.clone(),
+        input_rows,
+        null_rows: input_rows - df.height(),
+        duplicate_rows: 0,
+        added_rows: 0,
+    };
     if df.height() == 0 {
-        return None;
+        return (None, counts);
     }
     debug!(
         "Prepare single triple df after drop null before it is added took {} seconds",
         now.elapsed().as_secs_f32()
     );
     if !has_unique_subset {
+        let height_before_unique = df.height();
         df = df.unique(None, UniqueKeepStrategy::First).unwrap();
+        counts.duplicate_rows = height_before_unique - df.height();
     }
+    counts.added_rows = df.height();
     debug!(
         "Prepare single triple df unique before it is added took {} seconds",
         now.elapsed().as_secs_f32()
     );
 
-    if let RDFNodeType::Literal(lit) = object_type {
-        if lit.as_ref() == xsd::STRING {
+    let has_language_tag_column = df.get_column_names().contains(&LANGUAGE_TAG_COLUMN);
+    if object_type.is_lit_type(xsd::STRING) {
+        if has_language_tag_column {
             if let Some(tag) = language_tag {
-                let lt_ser = Series::new_empty(LANGUAGE_TAG_COLUMN, &DataType::Utf8)
-                    .extend_constant(AnyValue::Utf8(tag), df.height())
-                    .unwrap();
-                df.with_column(lt_ser).unwrap();
-            } else {
-                let lt_ser = Series::full_null(LANGUAGE_TAG_COLUMN, df.height(), &DataType::Utf8);
-                df.with_column(lt_ser).unwrap();
+                let mut lt_ca: Utf8Chunked = df
+                    .column(LANGUAGE_TAG_COLUMN)
+                    .unwrap()
+                    .utf8()
+                    .unwrap()
+                    .into_iter()
+                    .map(|t| t.or(Some(tag.as_str())))
+                    .collect();
+                lt_ca.rename(LANGUAGE_TAG_COLUMN);
+                df.with_column(lt_ca.into_series()).unwrap();
             }
+        } else if let Some(tag) = language_tag {
+            let lt_ser = Series::new_empty(LANGUAGE_TAG_COLUMN, &DataType::Utf8)
+                .extend_constant(AnyValue::Utf8(tag), df.height())
+                .unwrap();
+            df.with_column(lt_ser).unwrap();
+        } else {
+            let lt_ser = Series::full_null(LANGUAGE_TAG_COLUMN, df.height(), &DataType::Utf8);
+            df.with_column(lt_ser).unwrap();
         }
+    } else if has_language_tag_column {
+        df = df.drop(LANGUAGE_TAG_COLUMN).unwrap();
     }
-    //TODO: add polars datatype harmonization here.
+    df = harmonize_triples_df(df, object_type);
     debug!(
         "Prepare single triple df before it is added took {} seconds",
         now.elapsed().as_secs_f32()
     );
-    Some(TripleDF {
-        df,
-        predicate,
-        object_type: object_type.clone(),
+    (
+        Some(TripleDF {
+            df,
+            predicate,
+            object_type: object_type.clone(),
+            graph: graph.clone(),
+            globally_unique: false,
+        }),
+        counts,
+    )
+}
+
+//The language tag is part of string literals, so it takes part in uniqueness unless tags are collapsed
+//The sequence numbers of cached chunks are not part of the triples, so triples added at different times are duplicates
+pub(crate) fn subset_without_added_sequence(subset: Option<Vec<String>>, schema: &Schema) -> Option<Vec<String>> {
+    subset.or_else(|| {
+        Some(
+            schema
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .filter(|name| name != ADDED_SEQUENCE_COLUMN)
+                .collect(),
+        )
     })
 }
 
+fn unique_subset(rdf_node_type: &RDFNodeType, collapse_language_tags: bool) -> Option<Vec<String>> {
+    if collapse_language_tags && rdf_node_type.is_lit_type(xsd::STRING) {
+        Some(vec!["subject".to_string(), "object".to_string()])
+    } else {
+        None
+    }
+}
+
 //From: https://users.rust-lang.org/t/flatten-a-vec-vec-t-to-a-vec-t/24526/3
 fn flatten<T>(nested: Vec<Vec<T>>) -> Vec<T> {
     nested.into_iter().flatten().collect()
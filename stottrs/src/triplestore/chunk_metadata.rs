@@ -0,0 +1,194 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::parquet::read_parquet;
+use polars::prelude::{col, IntoLazy, LazyFrame};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::DataType;
+use polars_core::series::Series;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkMetadata {
+    pub height: usize,
+    pub min_subject: Option<String>,
+    pub max_subject: Option<String>,
+}
+
+/// Sizes of a column of a chunk, computed when export sizes are estimated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnStats {
+    pub name: String,
+    pub dtype: DataType,
+    pub null_count: usize,
+    pub n_unique: usize,
+    /// Total length in bytes of the lexical forms of the values.
+    pub lexical_bytes: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct TripleTableInfo {
+    pub predicate: String,
+    pub graph: Option<String>,
+    pub object_type: RDFNodeType,
+    pub unique: bool,
+    pub in_memory: bool,
+    pub chunks: Vec<ChunkMetadata>,
+}
+
+impl TripleTableInfo {
+    pub fn height(&self) -> usize {
+        self.chunks.iter().map(|c| c.height).sum()
+    }
+}
+
+impl ChunkMetadata {
+    pub(crate) fn from_df(df: &DataFrame) -> ChunkMetadata {
+        let subjects = df.column("subject").unwrap().cast(&DataType::Utf8).unwrap();
+        let mut min_subject: Option<&str> = None;
+        let mut max_subject: Option<&str> = None;
+        for s in subjects.utf8().unwrap().into_iter().flatten() {
+            if min_subject.is_none() || s < min_subject.unwrap() {
+                min_subject = Some(s);
+            }
+            if max_subject.is_none() || s > max_subject.unwrap() {
+                max_subject = Some(s);
+            }
+        }
+        ChunkMetadata {
+            height: df.height(),
+            min_subject: min_subject.map(|x| x.to_string()),
+            max_subject: max_subject.map(|x| x.to_string()),
+        }
+    }
+
+    //Only the subject column is read
+    fn from_lazy_frame(lf: LazyFrame) -> Result<ChunkMetadata, MappingError> {
+        let df = lf
+            .select([col("subject")])
+            .collect()
+            .map_err(|x| MappingError::ReadParquetError(x))?;
+        Ok(ChunkMetadata::from_df(&df))
+    }
+
+    pub(crate) fn may_contain_subject(&self, subject: &str) -> bool {
+        if let (Some(min), Some(max)) = (&self.min_subject, &self.max_subject) {
+            min.as_str() <= subject && subject <= max.as_str()
+        } else {
+            false
+        }
+    }
+}
+
+impl ColumnStats {
+    pub(crate) fn from_series(series: &Series) -> ColumnStats {
+        //Types without a string representation are counted by their width
+        let lexical_bytes = match series.cast(&DataType::Utf8) {
+            Ok(lexical) => lexical
+                .utf8()
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .map(|x| x.len())
+                .sum(),
+            Err(_) => series.len() * 8,
+        };
+        ColumnStats {
+            name: series.name().to_string(),
+            dtype: series.dtype().clone(),
+            null_count: series.null_count(),
+            n_unique: series.n_unique().unwrap_or_else(|_| series.len()),
+            lexical_bytes,
+        }
+    }
+}
+
+impl Triplestore {
+    /// Describes the tables of the store without reading any triples.
+    pub fn table_infos(&self) -> Vec<TripleTableInfo> {
+        let mut infos = vec![];
+        add_table_infos(&self.df_map, &None, &mut infos);
+        for (g, map) in &self.named_graph_df_maps {
+            add_table_infos(map, &Some(g.clone()), &mut infos);
+        }
+        infos
+    }
+
+    /// The number of triples in the store, duplicates are counted if the store is not deduplicated.
+    pub fn count_triples(&self) -> usize {
+        self.table_infos().iter().map(|x| x.height()).sum()
+    }
+}
+
+fn add_table_infos(
+    df_map: &HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    graph: &Option<String>,
+    infos: &mut Vec<TripleTableInfo>,
+) {
+    for (predicate, map) in df_map {
+        for (object_type, tt) in map {
+            infos.push(TripleTableInfo {
+                predicate: predicate.clone(),
+                graph: graph.clone(),
+                object_type: object_type.clone(),
+                unique: tt.unique,
+                in_memory: tt.dfs.is_some(),
+                chunks: tt.chunk_metadata.clone(),
+            });
+        }
+    }
+}
+
+impl TripleTable {
+    pub(crate) fn refresh_chunk_metadata(&mut self) -> Result<(), MappingError> {
+        if let Some(dfs) = &self.dfs {
+            self.chunk_metadata = dfs.iter().map(ChunkMetadata::from_df).collect();
+        } else if let Some(paths) = &self.df_paths {
+            let mut chunk_metadata = vec![];
+            for p in paths {
+                chunk_metadata.push(ChunkMetadata::from_lazy_frame(read_parquet(p)?)?);
+            }
+            self.chunk_metadata = chunk_metadata;
+        }
+        Ok(())
+    }
+
+    /// Statistics of the columns of each chunk, reading one chunk at a time.
+    pub(crate) fn chunk_column_stats(&self) -> Result<Vec<Vec<ColumnStats>>, MappingError> {
+        let column_stats = |df: &DataFrame| -> Vec<ColumnStats> {
+            df.get_columns().iter().map(ColumnStats::from_series).collect()
+        };
+        let mut stats = vec![];
+        if let Some(dfs) = &self.dfs {
+            for df in dfs {
+                stats.push(column_stats(df));
+            }
+        } else if let Some(paths) = &self.df_paths {
+            for p in paths {
+                let df = read_parquet(p)?
+                    .collect()
+                    .map_err(|x| MappingError::ReadParquetError(x))?;
+                stats.push(column_stats(&df));
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Gets only the chunks that may contain the subject.
+    pub(crate) fn get_lazy_frames_with_subject(
+        &self,
+        subject: &str,
+    ) -> Result<Vec<LazyFrame>, MappingError> {
+        let mut lfs = vec![];
+        for (i, m) in self.chunk_metadata.iter().enumerate() {
+            if m.may_contain_subject(subject) {
+                if let Some(dfs) = &self.dfs {
+                    lfs.push(dfs.get(i).unwrap().clone().lazy());
+                } else if let Some(paths) = &self.df_paths {
+                    lfs.push(read_parquet(paths.get(i).unwrap())?);
+                }
+            }
+        }
+        Ok(lfs)
+    }
+}
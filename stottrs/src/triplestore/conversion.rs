@@ -1,8 +1,11 @@
 use chrono::{Datelike, Timelike};
 use crate::chrono::TimeZone as ChronoTimeZone;
-use polars_core::datatypes::{DataType, TimeZone};
+use polars_core::datatypes::{DataType, TimeUnit, TimeZone, Utf8Chunked};
+use polars_core::prelude::ChunkApply;
 use polars_core::series::{IntoSeries, Series};
-use crate::constants::{XSD_DATETIME_WITH_TZ_FORMAT, XSD_DATETIME_WITHOUT_TZ_FORMAT};
+use std::borrow::Cow;
+use crate::constants::{XSD_DATE_FORMAT, XSD_DATETIME_WITH_TZ_FORMAT, XSD_DATETIME_WITHOUT_TZ_FORMAT};
+use crate::literals::format_xsd_duration;
 
 pub fn convert_to_string(
     series: &Series,
@@ -11,7 +14,9 @@ pub fn convert_to_string(
 
     match series_data_type {
         DataType::Utf8 => {return None}
-        DataType::Date => {todo!()}
+        DataType::Date => {
+            return Some(series.date().unwrap().strftime(XSD_DATE_FORMAT).into_series())
+        }
         DataType::Datetime(_, tz_opt) => {
                 if let Some(tz) = tz_opt {
                     return Some(hack_format_timestamp_with_timezone(series, &mut tz.clone()))
@@ -23,7 +28,34 @@ pub fn convert_to_string(
                         .into_series())
                 }
             }
-        DataType::Duration(_) => {todo!()}
+        DataType::Duration(time_unit) => {
+            let nanos_per_unit = match time_unit {
+                TimeUnit::Nanoseconds => 1,
+                TimeUnit::Microseconds => 1_000,
+                TimeUnit::Milliseconds => 1_000_000,
+            };
+            let mut durations: Utf8Chunked = series
+                .duration()
+                .unwrap()
+                .0
+                .into_iter()
+                .map(|x| x.map(|x| format_xsd_duration(x * nanos_per_unit)))
+                .collect();
+            durations.rename(series.name());
+            return Some(durations.into_series())
+        }
+        DataType::Float32 | DataType::Float64 => {
+            //Infinities are written as INF in xsd
+            let floats = series.cast(&DataType::Utf8).unwrap();
+            let mut floats = floats.utf8().unwrap().apply(|x| match x {
+                "inf" => Cow::Borrowed("INF"),
+                "-inf" => Cow::Borrowed("-INF"),
+                _ => Cow::Borrowed(x),
+            });
+            //The objects are replaced by name when triples are written
+            floats.rename(series.name());
+            return Some(floats.into_series())
+        }
         DataType::Time => {todo!()}
         DataType::List(_) => {panic!("Not supported")}
         DataType::Categorical(_) => {panic!("Not supported")}
@@ -41,13 +73,12 @@ fn hack_format_timestamp_with_timezone(
 ) -> Series {
     let timezone_opt: Result<chrono_tz::Tz, _> = tz.parse();
     if let Ok(timezone) = timezone_opt {
-        let datetime_strings = Series::from_iter(
-            series
-                .datetime()
-                .unwrap()
-                .as_datetime_iter()
-                .map(|x| x.unwrap())
-                .map(|x| {
+        let mut datetime_strings: Utf8Chunked = series
+            .datetime()
+            .unwrap()
+            .as_datetime_iter()
+            .map(|x| {
+                x.map(|x| {
                     format!(
                         "{}",
                         timezone
@@ -55,11 +86,12 @@ fn hack_format_timestamp_with_timezone(
                             .and_hms_nano(x.hour(), x.minute(), x.second(), x.nanosecond())
                             .format(XSD_DATETIME_WITH_TZ_FORMAT)
                     )
-                }),
-        );
-
-        datetime_strings
+                })
+            })
+            .collect();
+        datetime_strings.rename(series.name());
+        datetime_strings.into_series()
     } else {
        panic!("Unknown timezone{}", tz.to_string());
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,75 @@
+use super::Triplestore;
+use crate::mapping::RDFNodeType;
+use oxrdf::NamedNode;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::DataType;
+use polars_core::series::Series;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Gives the lexical forms of a column of values of a custom datatype, as a column of strings of the same length.
+pub type LexicalFormatter = Arc<dyn Fn(&Series) -> Series + Send + Sync>;
+
+/// How literals of a datatype that is not built in, e.g. a datatype for WKT geometries, are stored and written.
+#[derive(Clone)]
+pub struct CustomDatatype {
+    /// Columns of parameters with the datatype are cast to this data type when templates are expanded.
+    pub dtype: DataType,
+    /// Used instead of casting the values to strings when triples and query solutions are written.
+    pub formatter: Option<LexicalFormatter>,
+}
+
+impl CustomDatatype {
+    pub fn new(dtype: DataType) -> CustomDatatype {
+        CustomDatatype {
+            dtype,
+            formatter: None,
+        }
+    }
+
+    pub fn with_formatter(mut self, formatter: LexicalFormatter) -> CustomDatatype {
+        self.formatter = Some(formatter);
+        self
+    }
+}
+
+impl Triplestore {
+    /// Registers a literal datatype, so that values of the datatype are stored as the dtype and written with the formatter.
+    /// Registering a datatype again replaces it, triples that are already stored are not cast.
+    pub fn register_datatype(&mut self, datatype: NamedNode, custom_datatype: CustomDatatype) {
+        self.custom_datatypes
+            .insert(datatype.as_str().to_string(), custom_datatype);
+    }
+
+    pub fn custom_datatype(&self, datatype: &str) -> Option<&CustomDatatype> {
+        self.custom_datatypes.get(datatype)
+    }
+}
+
+pub(crate) fn formatter_for<'a>(
+    custom_datatypes: &'a HashMap<String, CustomDatatype>,
+    rdf_node_type: &RDFNodeType,
+) -> Option<&'a LexicalFormatter> {
+    if let RDFNodeType::Literal(dt) = rdf_node_type {
+        custom_datatypes
+            .get(dt.as_str())
+            .and_then(|c| c.formatter.as_ref())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn format_series(series: &Series, formatter: &LexicalFormatter) -> Series {
+    let mut formatted = formatter(series).cast(&DataType::Utf8).unwrap();
+    formatted.rename(series.name());
+    formatted
+}
+
+//A copy of the frame where the column of the objects has the lexical forms, when there is a formatter
+pub(crate) fn format_objects(df: &DataFrame, formatter: Option<&LexicalFormatter>) -> Option<DataFrame> {
+    let formatter = formatter?;
+    let mut df = df.clone();
+    let objects = format_series(df.column("object").unwrap(), formatter);
+    df.with_column(objects).unwrap();
+    Some(df)
+}
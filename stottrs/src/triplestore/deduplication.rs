@@ -0,0 +1,177 @@
+use super::{subset_without_added_sequence, Triplestore, TripleTable, LANGUAGE_TAG_COLUMN};
+use crate::mapping::errors::MappingError;
+use crate::triplestore::harmonize::find_schema_mismatches;
+use crate::triplestore::parquet::{
+    property_to_filename, read_parquet_with_added_sequence, split_write_df,
+};
+use crate::triplestore::recovery::InProgressManifest;
+use polars::prelude::{col, concat, lit, IntoLazy, LazyFrame};
+use polars_core::frame::{DataFrame, UniqueKeepStrategy};
+use polars_core::prelude::{JoinType, SchemaRef};
+use std::fs::remove_file;
+use std::path::Path;
+use uuid::Uuid;
+
+//Null language tags are stored as empty strings in the index, so that they are equal when joining
+const KEY_LANGUAGE_TAG_COLUMN: &str = "key_language_tag";
+
+/// How tables with triples that may be duplicates are deduplicated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeduplicationStrategy {
+    /// All chunks of a table are concatenated and deduplicated,
+    /// and with a caching folder all the files of the table are written again.
+    Full,
+    /// Only the chunks added since the table was last deduplicated are deduplicated,
+    /// and compared against an index of the triples already in the table, which is kept in memory.
+    /// Repeated expansions followed by queries then only rewrite the new chunks.
+    /// The index holds the subject and object of every triple of the table, also when the triples are in a caching folder,
+    /// so it needs about as much memory as the table itself. Use Full for tables that do not fit in memory.
+    Incremental,
+}
+
+impl Default for DeduplicationStrategy {
+    fn default() -> Self {
+        DeduplicationStrategy::Full
+    }
+}
+
+impl Triplestore {
+    pub fn set_deduplication_strategy(&mut self, deduplication_strategy: DeduplicationStrategy) {
+        self.deduplication_strategy = deduplication_strategy;
+        if deduplication_strategy == DeduplicationStrategy::Full {
+            for map in self
+                .named_graph_df_maps
+                .values_mut()
+                .chain(std::iter::once(&mut self.df_map))
+            {
+                for tt in map.values_mut().flat_map(|m| m.values_mut()) {
+                    tt.key_index = None;
+                }
+            }
+        }
+    }
+}
+
+impl TripleTable {
+    //Called whenever chunks are rewritten, as the index may then refer to triples no longer in the table
+    pub(crate) fn reset_key_index(&mut self) {
+        self.key_index = None;
+        self.generation = Uuid::new_v4().to_string();
+        self.unique_chunks = if self.unique { self.len() } else { 0 };
+    }
+
+    //Returns false if the table must be fully deduplicated instead,
+    //which is the case when new chunks have other data types than the chunks before them
+    pub(crate) fn deduplicate_incrementally(
+        &mut self,
+        caching_folder: &Option<String>,
+        predicate: &str,
+        subset: Option<Vec<String>>,
+    ) -> Result<bool, MappingError> {
+        let unique_chunks = self.unique_chunks.min(self.len());
+        if unique_chunks == self.len() {
+            self.unique = true;
+            return Ok(true);
+        }
+        let mut lfs = self.chunk_lazy_frames()?;
+        let schemas: Vec<SchemaRef> = lfs.iter().map(|lf| lf.schema().unwrap()).collect();
+        if !find_schema_mismatches(&schemas).is_empty() {
+            return Ok(false);
+        }
+        let with_language_tag = subset.is_none() && schemas[0].get(LANGUAGE_TAG_COLUMN).is_some();
+        let mut keys = vec![col("subject"), col("object")];
+        if with_language_tag {
+            keys.push(col(KEY_LANGUAGE_TAG_COLUMN));
+        }
+        let with_key_columns = |lf: LazyFrame| {
+            if with_language_tag {
+                lf.with_column(
+                    col(LANGUAGE_TAG_COLUMN)
+                        .fill_null(lit(""))
+                        .alias(KEY_LANGUAGE_TAG_COLUMN),
+                )
+            } else {
+                lf
+            }
+        };
+
+        let unique_subset = subset_without_added_sequence(subset, &schemas[0]);
+        let new_lfs = lfs.split_off(unique_chunks);
+        if self.key_index.is_none() && unique_chunks > 0 {
+            let index = with_key_columns(concat(lfs, true, true).unwrap())
+                .select(keys.clone())
+                .collect()
+                .unwrap();
+            self.key_index = Some(index);
+        }
+        let mut new_lf = with_key_columns(
+            concat(new_lfs, true, true)
+                .unwrap()
+                .unique_stable(unique_subset, UniqueKeepStrategy::First),
+        );
+        if let Some(index) = &self.key_index {
+            new_lf = new_lf.join(index.clone().lazy(), keys.clone(), keys.clone(), JoinType::Anti);
+        }
+        let new_df = new_lf.collect().unwrap();
+        let new_keys = new_df.select(keys_names(with_language_tag)).unwrap();
+        let new_df = if with_language_tag {
+            new_df.drop(KEY_LANGUAGE_TAG_COLUMN).unwrap()
+        } else {
+            new_df
+        };
+        if let Some(dfs) = &mut self.dfs {
+            dfs.truncate(unique_chunks);
+            if new_df.height() > 0 {
+                dfs.push(new_df);
+            }
+        } else if let Some(paths) = &mut self.df_paths {
+            //The deduplicated chunks are written before the chunks they replace are removed,
+            //so that an error leaves the table with all its files
+            let caching_folder = caching_folder.as_ref().unwrap();
+            let new_paths = if new_df.height() > 0 {
+                split_write_df(caching_folder, new_df, &property_to_filename(predicate))?
+            } else {
+                vec![]
+            };
+            //The replaced files are listed until they are removed, so that an interrupted removal is finished by recovery
+            let in_progress = InProgressManifest::create(caching_folder, &paths[unique_chunks..])?;
+            let replaced: Vec<String> = paths.drain(unique_chunks..).collect();
+            paths.extend(new_paths);
+            for p in replaced {
+                remove_file(Path::new(&p)).map_err(|x| MappingError::RemoveParquetFileError(x))?;
+            }
+            in_progress.finish()?;
+        }
+        //The index is only extended when the table has the deduplicated chunks,
+        //as the new chunks would otherwise be compared against their own keys
+        if let Some(index) = &mut self.key_index {
+            index.vstack_mut(&new_keys).unwrap();
+            index.rechunk();
+        } else {
+            self.key_index = Some(new_keys);
+        }
+        self.object_index = None;
+        self.refresh_chunk_metadata()?;
+        self.unique = true;
+        self.unique_chunks = self.len();
+        Ok(true)
+    }
+
+    fn chunk_lazy_frames(&self) -> Result<Vec<LazyFrame>, MappingError> {
+        if let Some(dfs) = &self.dfs {
+            Ok(dfs.iter().map(|df| df.clone().lazy()).collect())
+        } else if let Some(paths) = &self.df_paths {
+            paths.iter().map(|p| read_parquet_with_added_sequence(p)).collect()
+        } else {
+            panic!("TripleTable in invalid state")
+        }
+    }
+}
+
+fn keys_names(with_language_tag: bool) -> Vec<&'static str> {
+    if with_language_tag {
+        vec!["subject", "object", KEY_LANGUAGE_TAG_COLUMN]
+    } else {
+        vec!["subject", "object"]
+    }
+}
@@ -0,0 +1,79 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::triplestore::sparql::solution_mapping::rdf_node_type_tag;
+use polars::prelude::{col, concat, lit};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{DataType, JoinType, NamedFrom};
+use polars_core::series::Series;
+
+impl Triplestore {
+    /// Finds subjects with objects of different types for the same predicate, e.g. the string "42" and the int 42,
+    /// which usually means that the sources disagree on the type of a column.
+    /// Returns a DataFrame with the columns predicate, subject, object and object_type, with the object as its lexical form
+    /// and a row for each object of the offending subjects.
+    pub fn find_mixed_object_types(&mut self) -> Result<DataFrame, MappingError> {
+        self.deduplicate()?;
+        let mut predicates: Vec<&String> = self
+            .df_map
+            .iter()
+            .filter(|(_, m)| m.len() > 1)
+            .map(|(p, _)| p)
+            .collect();
+        predicates.sort();
+        let mut offender_lfs = vec![];
+        for predicate in predicates {
+            let map = self.df_map.get(predicate).unwrap();
+            let mut lfs = vec![];
+            for (rdf_node_type, tt) in map {
+                let lf = concat(tt.get_lazy_frames()?, true, true).unwrap();
+                lfs.push(lf.select([
+                    col("subject").cast(DataType::Utf8),
+                    col("object").cast(DataType::Utf8),
+                    lit(rdf_node_type_tag(rdf_node_type)).alias("object_type"),
+                ]));
+            }
+            let lf = concat(lfs, true, true).unwrap();
+            let offending_subjects = lf
+                .clone()
+                .groupby([col("subject")])
+                .agg([col("object_type").n_unique().alias("n_object_types")])
+                .filter(col("n_object_types").gt(lit(1)))
+                .select([col("subject")]);
+            offender_lfs.push(
+                lf.join(
+                    offending_subjects,
+                    [col("subject")],
+                    [col("subject")],
+                    JoinType::Inner,
+                )
+                .select([
+                    lit(predicate.as_str()).alias("predicate"),
+                    col("subject"),
+                    col("object"),
+                    col("object_type"),
+                ]),
+            );
+        }
+        if offender_lfs.is_empty() {
+            return Ok(empty_offenders());
+        }
+        let df = concat(offender_lfs, true, true)
+            .unwrap()
+            .sort_by_exprs(
+                [col("predicate"), col("subject"), col("object_type"), col("object")],
+                [false, false, false, false],
+                false,
+            )
+            .collect()
+            .unwrap();
+        Ok(df)
+    }
+}
+
+fn empty_offenders() -> DataFrame {
+    let columns = ["predicate", "subject", "object", "object_type"]
+        .iter()
+        .map(|c| Series::new(c, Vec::<String>::new()))
+        .collect();
+    DataFrame::new(columns).unwrap()
+}
@@ -0,0 +1,262 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::triplestore::ntriples_write::DEFAULT_CHUNK_SIZE;
+use log::warn;
+use std::thread::sleep;
+use std::time::Duration;
+use thiserror::Error;
+
+const N_TRIPLES_CONTENT_TYPE: &str = "application/n-triples";
+const SPARQL_UPDATE_CONTENT_TYPE: &str = "application/sparql-update";
+
+#[derive(Error, Debug)]
+pub enum EndpointError {
+    #[error("Error exporting triples {}", .0)]
+    ExportError(MappingError),
+    #[error("Endpoint responded with status {}: {}", .0, .1)]
+    StatusError(u16, String),
+    #[error("Request to endpoint failed {}", .0)]
+    TransportError(String),
+}
+
+/// How triples are uploaded to the endpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndpointProtocol {
+    /// POST requests with N-Triples to the graph store, see https://www.w3.org/TR/sparql11-http-rdf-update/
+    /// Triples are added to the graph, existing triples are kept.
+    GraphStore,
+    /// INSERT DATA requests to a SPARQL update endpoint.
+    SparqlUpdate,
+}
+
+#[derive(Clone, Debug)]
+pub enum EndpointAuth {
+    Basic { username: String, password: String },
+    Bearer(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct PushOptions {
+    pub protocol: EndpointProtocol,
+    /// Number of triples in each request.
+    pub batch_size: usize,
+    /// Requests failing with a server error, a rate limit or a transport error are retried this many times.
+    pub max_retries: usize,
+    /// Time to wait before the first retry, doubled for each following retry.
+    pub initial_backoff: Duration,
+}
+
+impl Default for PushOptions {
+    fn default() -> Self {
+        PushOptions {
+            protocol: EndpointProtocol::GraphStore,
+            batch_size: 10_000,
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PushReport {
+    pub triples: usize,
+    pub batches: usize,
+    pub retries: usize,
+}
+
+impl Triplestore {
+    /// Uploads the triples of the default graph to the graph of the endpoint, or to its default graph,
+    /// with the default options. Stores with named graphs are rejected.
+    pub fn push_to_endpoint(
+        &mut self,
+        url: &str,
+        graph: Option<&str>,
+        auth: Option<&EndpointAuth>,
+    ) -> Result<PushReport, EndpointError> {
+        self.push_to_endpoint_with_options(url, graph, auth, &PushOptions::default())
+    }
+
+    /// Uploads the triples in batches, so that mapped data can be published without writing intermediate files.
+    /// The batches are made from the N-Triples lines of one chunk of triples at a time, so that the store is not
+    /// exported to memory. Batches already uploaded are not rolled back if a later batch fails.
+    pub fn push_to_endpoint_with_options(
+        &mut self,
+        url: &str,
+        graph: Option<&str>,
+        auth: Option<&EndpointAuth>,
+        options: &PushOptions,
+    ) -> Result<PushReport, EndpointError> {
+        let lines = self
+            .n_triples_lines(DEFAULT_CHUNK_SIZE)
+            .map_err(|x| EndpointError::ExportError(x))?;
+        let agent = ureq::agent();
+        let mut report = PushReport {
+            triples: 0,
+            batches: 0,
+            retries: 0,
+        };
+        let batch_size = options.batch_size.max(1);
+        let mut batch = Vec::with_capacity(batch_size);
+        for line in lines {
+            batch.push(line.map_err(|x| EndpointError::ExportError(x))?);
+            if batch.len() == batch_size {
+                push_batch(&agent, url, graph, auth, options, &batch, &mut report)?;
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            push_batch(&agent, url, graph, auth, options, &batch, &mut report)?;
+        }
+        Ok(report)
+    }
+}
+
+fn push_batch(
+    agent: &ureq::Agent,
+    url: &str,
+    graph: Option<&str>,
+    auth: Option<&EndpointAuth>,
+    options: &PushOptions,
+    batch: &[String],
+    report: &mut PushReport,
+) -> Result<(), EndpointError> {
+    let (request_url, content_type, body) = match options.protocol {
+        EndpointProtocol::GraphStore => (
+            graph_store_url(url, graph),
+            N_TRIPLES_CONTENT_TYPE,
+            n_triples(batch),
+        ),
+        EndpointProtocol::SparqlUpdate => (
+            url.to_string(),
+            SPARQL_UPDATE_CONTENT_TYPE,
+            insert_data(batch, graph),
+        ),
+    };
+    let mut backoff = options.initial_backoff;
+    let mut attempt = 0;
+    loop {
+        let mut request = agent
+            .post(&request_url)
+            .set("Content-Type", content_type);
+        if let Some(auth) = auth {
+            request = request.set("Authorization", &authorization(auth));
+        }
+        let error = match request.send_string(&body) {
+            Ok(_) => break,
+            Err(ureq::Error::Status(status, response)) => {
+                let retryable = status == 429 || status >= 500;
+                let error = EndpointError::StatusError(
+                    status,
+                    response.into_string().unwrap_or_default(),
+                );
+                if !retryable {
+                    return Err(error);
+                }
+                error
+            }
+            Err(e) => EndpointError::TransportError(e.to_string()),
+        };
+        if attempt >= options.max_retries {
+            return Err(error);
+        }
+        warn!("Retrying upload to {} in {:?} after error: {}", url, backoff, error);
+        sleep(backoff);
+        backoff *= 2;
+        attempt += 1;
+        report.retries += 1;
+    }
+    report.triples += batch.len();
+    report.batches += 1;
+    Ok(())
+}
+
+fn graph_store_url(url: &str, graph: Option<&str>) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    if let Some(graph) = graph {
+        format!("{}{}graph={}", url, separator, percent_encode(graph))
+    } else {
+        format!("{}{}default", url, separator)
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn n_triples(lines: &[String]) -> String {
+    let mut out = String::new();
+    for l in lines {
+        out.push_str(l);
+        out.push('\n');
+    }
+    out
+}
+
+fn insert_data(lines: &[String], graph: Option<&str>) -> String {
+    let triples = n_triples(lines);
+    if let Some(graph) = graph {
+        format!("INSERT DATA {{ GRAPH <{}> {{\n{}}} }}", graph, triples)
+    } else {
+        format!("INSERT DATA {{\n{}}}", triples)
+    }
+}
+
+fn authorization(auth: &EndpointAuth) -> String {
+    match auth {
+        EndpointAuth::Basic { username, password } => {
+            format!("Basic {}", base64(format!("{}:{}", username, password).as_bytes()))
+        }
+        EndpointAuth::Bearer(token) => format!("Bearer {}", token),
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16
+            | (*chunk.get(1).unwrap_or(&0) as u32) << 8
+            | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[test]
+fn test_endpoint_requests() {
+    assert_eq!(
+        graph_store_url("http://localhost:3030/ds/data", Some("http://example.net/g#1")),
+        "http://localhost:3030/ds/data?graph=http%3A%2F%2Fexample.net%2Fg%231"
+    );
+    assert_eq!(
+        graph_store_url("http://localhost:3030/ds/data", None),
+        "http://localhost:3030/ds/data?default"
+    );
+    assert_eq!(
+        authorization(&EndpointAuth::Basic {
+            username: "Aladdin".to_string(),
+            password: "open sesame".to_string()
+        }),
+        "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+    );
+    assert_eq!(base64(b"ab"), "YWI=");
+    let line = "<http://example.net/ns#a> <http://example.net/ns#p> \"b\" .".to_string();
+    assert_eq!(
+        insert_data(&[line], Some("http://example.net/g")),
+        "INSERT DATA { GRAPH <http://example.net/g> {\n<http://example.net/ns#a> <http://example.net/ns#p> \"b\" .\n} }"
+    );
+}
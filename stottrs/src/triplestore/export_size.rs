@@ -0,0 +1,173 @@
+use super::{TripleType, Triplestore};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::chunk_metadata::ColumnStats;
+use crate::triplestore::LANGUAGE_TAG_COLUMN;
+use polars_core::prelude::DataType;
+
+/// The formats the store can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    NTriples,
+    NQuads,
+    TriG,
+    Turtle,
+    NativeParquet,
+    Ipc,
+}
+
+impl Triplestore {
+    /// Estimates the number of bytes written when exporting the store in the format, using statistics
+    /// of the columns of each chunk. Chunks in the caching folder are read one at a time.
+    /// Text formats are estimated without prefixes or escaping, which makes the Turtle estimate an upper bound.
+    /// Parquet is estimated as dictionary encoded without compression, and IPC as plain Arrow columns.
+    /// Duplicates are counted if the store is not deduplicated.
+    pub fn estimate_export_size(&self, format: ExportFormat) -> Result<usize, MappingError> {
+        let mut size = 0;
+        let default_graph = std::iter::once((None, &self.df_map));
+        let named_graphs = self
+            .named_graph_df_maps
+            .iter()
+            .map(|(g, map)| (Some(g.as_str()), map));
+        for (graph, df_map) in default_graph.chain(named_graphs) {
+            let graph = match (graph, format) {
+                (None, _) => None,
+                (
+                    Some(g),
+                    ExportFormat::NQuads | ExportFormat::TriG | ExportFormat::Ipc,
+                ) => Some(g),
+                //Named graphs are not included in the other formats
+                (Some(_), _) => continue,
+            };
+            for (predicate, map) in df_map {
+                for (object_type, tt) in map {
+                    let chunk_column_stats = tt.chunk_column_stats()?;
+                    for (chunk, columns) in tt.chunk_metadata.iter().zip(&chunk_column_stats) {
+                        let rows = chunk.height;
+                        size += match format {
+                            ExportFormat::NTriples
+                            | ExportFormat::Turtle
+                            | ExportFormat::TriG => {
+                                text_chunk_size(rows, columns, predicate, object_type, None)
+                            }
+                            ExportFormat::NQuads => {
+                                text_chunk_size(rows, columns, predicate, object_type, graph)
+                            }
+                            ExportFormat::NativeParquet => columns
+                                .iter()
+                                .map(|c| parquet_column_size(c, rows))
+                                .sum(),
+                            ExportFormat::Ipc => {
+                                columns.iter().map(|c| ipc_column_size(c, rows)).sum()
+                            }
+                        };
+                    }
+                }
+            }
+        }
+        if format == ExportFormat::TriG {
+            //Each named graph is enclosed in "<graph> {\n" and "}\n"
+            size += self
+                .named_graph_df_maps
+                .keys()
+                .map(|g| g.len() + 7)
+                .sum::<usize>();
+        }
+        Ok(size)
+    }
+}
+
+//Size of the chunk written as lines of "<subject> <predicate> object <graph> .\n"
+fn text_chunk_size(
+    rows: usize,
+    columns: &[ColumnStats],
+    predicate: &str,
+    object_type: &RDFNodeType,
+    graph: Option<&str>,
+) -> usize {
+    let column = |name: &str| columns.iter().find(|c| c.name == name);
+    let lexical_bytes = |name: &str| column(name).map_or(0, |c| c.lexical_bytes);
+    let mut size = lexical_bytes("subject") + 2 * rows;
+    size += (predicate.len() + 3) * rows;
+    size += lexical_bytes("object") + rows;
+    size += match object_type.find_triple_type() {
+        TripleType::ObjectProperty => {
+            if object_type == &RDFNodeType::BlankNode {
+                0
+            } else {
+                2 * rows
+            }
+        }
+        TripleType::StringProperty => {
+            //Quotes, and an @ before each language tag
+            let tagged_rows = column(LANGUAGE_TAG_COLUMN).map_or(0, |c| rows - c.null_count);
+            2 * rows + lexical_bytes(LANGUAGE_TAG_COLUMN) + tagged_rows
+        }
+        TripleType::NonStringProperty => {
+            let datatype_len = if let RDFNodeType::Literal(l) = object_type {
+                l.as_str().len()
+            } else {
+                0
+            };
+            (datatype_len + 6) * rows
+        }
+    };
+    if let Some(g) = graph {
+        size += (g.len() + 3) * rows;
+    }
+    size + 3 * rows
+}
+
+fn parquet_column_size(stats: &ColumnStats, rows: usize) -> usize {
+    let non_null = rows - stats.null_count;
+    let value_width = if let Some(w) = fixed_width(&stats.dtype) {
+        w
+    } else if non_null > 0 {
+        //Lengths are stored with each string
+        stats.lexical_bytes / non_null + 4
+    } else {
+        0
+    };
+    let plain = value_width * non_null;
+    //A dictionary of the unique values, and bit packed indices into it
+    let index_bits = usize::BITS - stats.n_unique.leading_zeros();
+    let dictionary = value_width * stats.n_unique + bytes_for_bits(index_bits as usize * non_null);
+    plain.min(dictionary) + validity_size(stats, rows)
+}
+
+fn ipc_column_size(stats: &ColumnStats, rows: usize) -> usize {
+    let values = if let Some(w) = fixed_width(&stats.dtype) {
+        w * rows
+    } else {
+        //Offsets are 32 bit
+        stats.lexical_bytes + 4 * (rows + 1)
+    };
+    values + validity_size(stats, rows)
+}
+
+fn validity_size(stats: &ColumnStats, rows: usize) -> usize {
+    if stats.null_count > 0 {
+        bytes_for_bits(rows)
+    } else {
+        0
+    }
+}
+
+fn bytes_for_bits(bits: usize) -> usize {
+    bits / 8 + usize::from(bits % 8 != 0)
+}
+
+fn fixed_width(dtype: &DataType) -> Option<usize> {
+    match dtype {
+        DataType::Boolean | DataType::UInt8 | DataType::Int8 => Some(1),
+        DataType::UInt16 | DataType::Int16 => Some(2),
+        DataType::UInt32 | DataType::Int32 | DataType::Float32 | DataType::Date => Some(4),
+        DataType::UInt64
+        | DataType::Int64
+        | DataType::Float64
+        | DataType::Datetime(..)
+        | DataType::Duration(_)
+        | DataType::Time => Some(8),
+        _ => None,
+    }
+}
@@ -2,9 +2,10 @@ use super::Triplestore;
 use crate::mapping::errors::MappingError;
 use crate::mapping::RDFNodeType;
 use crate::triplestore::conversion::convert_to_string;
+use crate::triplestore::custom_datatypes::{format_series, formatter_for};
 use crate::triplestore::TripleType;
-use oxrdf::{Literal, NamedNode, Subject, Term, Triple};
-use polars_core::prelude::AnyValue;
+use oxrdf::{BlankNode, Literal, NamedNode, Subject, Term, Triple};
+use polars_core::prelude::{AnyValue, DataFrame, DataType, Series};
 
 impl Triplestore {
     pub fn object_property_triples<F, T>(
@@ -21,10 +22,12 @@ impl Triplestore {
                     for i in 0..v.len() {
                         let df = v.get_df(i)?;
                         if df.height() == 0 {
-                            return Ok(());
+                            continue;
                         }
-                        let mut subject_iterator = df.column("subject").unwrap().iter();
-                        let mut object_iterator = df.column("object").unwrap().iter();
+                        let subjects = utf8_column(df, "subject");
+                        let objects = utf8_column(df, "object");
+                        let mut subject_iterator = subjects.iter();
+                        let mut object_iterator = objects.iter();
                         for _ in 0..df.height() {
                             let s = anyutf8_to_str(subject_iterator.next().unwrap());
                             let o = anyutf8_to_str(object_iterator.next().unwrap());
@@ -53,10 +56,12 @@ impl Triplestore {
                     for i in 0..v.len() {
                         let df = v.get_df(i)?;
                         if df.height() == 0 {
-                            return Ok(());
+                            continue;
                         }
-                        let mut subject_iterator = df.column("subject").unwrap().iter();
-                        let mut data_iterator = df.column("object").unwrap().iter();
+                        let subjects = utf8_column(df, "subject");
+                        let objects = utf8_column(df, "object");
+                        let mut subject_iterator = subjects.iter();
+                        let mut data_iterator = objects.iter();
                         let mut language_tag_iterator = df.column("language_tag").unwrap().iter();
                         for _ in 0..df.height() {
                             let s = anyutf8_to_str(subject_iterator.next().unwrap());
@@ -95,13 +100,20 @@ impl Triplestore {
                     } else {
                         panic!("Should never happen")
                     };
+                    let formatter = formatter_for(&self.custom_datatypes, k);
                     for i in 0..v.len() {
                         let df = v.get_df(i)?;
                         if df.height() == 0 {
-                            return Ok(());
+                            continue;
                         }
-                        let mut subject_iterator = df.column("subject").unwrap().iter();
-                        let data_as_strings = convert_to_string(df.column("object").unwrap());
+                        let subjects = utf8_column(df, "subject");
+                        let mut subject_iterator = subjects.iter();
+                        let objects = df.column("object").unwrap();
+                        let data_as_strings = if let Some(formatter) = formatter {
+                            Some(format_series(objects, formatter))
+                        } else {
+                            convert_to_string(objects)
+                        };
                         if let Some(s) = data_as_strings {
                             let mut data_iterator = s.iter();
                             for _ in 0..df.height() {
@@ -125,15 +137,10 @@ impl Triplestore {
         Ok(())
     }
 
+    /// Stores with named graphs are rejected, as the triples can not carry their graph.
     pub fn export_oxrdf_triples(&mut self) -> Result<Vec<Triple>, MappingError> {
+        self.check_no_named_graphs("oxrdf triples")?;
         self.deduplicate()?;
-        fn subject_from_str(s: &str) -> Subject {
-            Subject::NamedNode(NamedNode::new_unchecked(s))
-        }
-        fn object_term_from_str(s: &str) -> Term {
-            Term::NamedNode(NamedNode::new_unchecked(s))
-        }
-
         fn object_triple_func(s: &str, v: &str, o: &str) -> Triple {
             let subject = subject_from_str(s);
             let verb = NamedNode::new_unchecked(v);
@@ -167,6 +174,59 @@ impl Triplestore {
     }
 }
 
+fn subject_from_str(s: &str) -> Subject {
+    if let Some(id) = s.strip_prefix("_:") {
+        Subject::BlankNode(BlankNode::new_unchecked(id))
+    } else {
+        Subject::NamedNode(NamedNode::new_unchecked(s))
+    }
+}
+
+fn object_term_from_str(s: &str) -> Term {
+    if let Some(id) = s.strip_prefix("_:") {
+        Term::BlankNode(BlankNode::new_unchecked(id))
+    } else {
+        Term::NamedNode(NamedNode::new_unchecked(s))
+    }
+}
+
+//Triples of a DataFrame with subject, verb and object columns, where the objects have the given type
+pub(crate) fn triples_df_to_oxrdf(df: &DataFrame, object_type: &RDFNodeType) -> Vec<Triple> {
+    let subjects = utf8_column(df, "subject");
+    let verbs = utf8_column(df, "verb");
+    let objects = df.column("object").unwrap();
+    let objects = convert_to_string(objects).unwrap_or_else(|| utf8_column(df, "object"));
+    let mut triples = vec![];
+    for ((s, v), o) in subjects
+        .utf8()
+        .unwrap()
+        .into_iter()
+        .zip(verbs.utf8().unwrap().into_iter())
+        .zip(objects.utf8().unwrap().into_iter())
+    {
+        if let (Some(s), Some(v), Some(o)) = (s, v, o) {
+            let object = match object_type {
+                RDFNodeType::IRI | RDFNodeType::BlankNode => object_term_from_str(o),
+                RDFNodeType::Literal(dt) => {
+                    Term::Literal(Literal::new_typed_literal(o, dt.clone()))
+                }
+                _ => panic!("Should never happen"),
+            };
+            triples.push(Triple::new(
+                subject_from_str(s),
+                NamedNode::new_unchecked(v),
+                object,
+            ));
+        }
+    }
+    triples
+}
+
+//Subjects and IRI objects may be categorical before the store is deduplicated
+fn utf8_column(df: &DataFrame, name: &str) -> Series {
+    df.column(name).unwrap().cast(&DataType::Utf8).unwrap()
+}
+
 fn anyutf8_to_str(a: AnyValue) -> &str {
     if let AnyValue::Utf8(s) = a {
         s
@@ -0,0 +1,464 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::chunk_metadata::TripleTableInfo;
+use crate::triplestore::sparql::solution_mapping::{rdf_node_type_from_tag, rdf_node_type_tag};
+use crate::triplestore::sparql::QueryResult;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::StreamReader;
+use arrow_ipc::writer::IpcWriteOptions;
+use arrow_schema::Schema;
+use futures::stream::BoxStream;
+use futures::{stream, StreamExt, TryStreamExt};
+use polars::prelude::{IpcStreamWriter, SerWriter};
+use polars_core::frame::DataFrame;
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tonic::{Request, Response, Status, Streaming};
+
+const SELECT_TICKET: &str = "select";
+const TRIPLES_TICKET: &str = "triples";
+
+/// Ticket for the solutions of a SPARQL SELECT query.
+/// Tickets are UTF-8 text, "select" followed by a line feed and the query.
+pub fn select_ticket(query: &str) -> Ticket {
+    Ticket {
+        ticket: format!("{}\n{}", SELECT_TICKET, query).into_bytes().into(),
+    }
+}
+
+/// Ticket for the subjects and objects of the triples of a predicate with the given type of object,
+/// i.e. "triples", the predicate and the object type ("IRI", "BlankNode" or the datatype IRI) on separate lines.
+/// The tickets of all predicates in the default graph are listed by ListFlights.
+pub fn triples_ticket(predicate: &str, object_type: &RDFNodeType) -> Ticket {
+    Ticket {
+        ticket: format!(
+            "{}\n{}\n{}",
+            TRIPLES_TICKET,
+            predicate,
+            rdf_node_type_tag(object_type)
+        )
+        .into_bytes()
+        .into(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum FlightTicket {
+    Select(String),
+    Triples(String, RDFNodeType),
+}
+
+fn parse_ticket(ticket: &[u8]) -> Result<FlightTicket, Status> {
+    let ticket = std::str::from_utf8(ticket)
+        .map_err(|_| Status::invalid_argument("Ticket is not valid UTF-8"))?;
+    match ticket.split_once('\n') {
+        Some((SELECT_TICKET, query)) => Ok(FlightTicket::Select(query.to_string())),
+        Some((TRIPLES_TICKET, rest)) => {
+            if let Some((predicate, tag)) = rest.split_once('\n') {
+                Ok(FlightTicket::Triples(
+                    predicate.to_string(),
+                    rdf_node_type_from_tag(tag),
+                ))
+            } else {
+                Err(Status::invalid_argument(
+                    "Triples ticket must have a predicate and an object type",
+                ))
+            }
+        }
+        _ => Err(Status::invalid_argument(
+            "Ticket must start with select or triples",
+        )),
+    }
+}
+
+/// Arrow Flight service streaming SELECT results and the triples of predicates from a triplestore,
+/// so that remote clients can pull mapped data without writing intermediate files.
+/// The store is locked on a blocking thread while a query or a chunk of triples is computed,
+/// and the triples of a predicate are streamed one chunk at a time.
+/// Triples added while a predicate is streamed may be left out of the stream.
+pub struct TriplestoreFlightService {
+    triplestore: Arc<Mutex<Triplestore>>,
+}
+
+impl TriplestoreFlightService {
+    pub fn new(triplestore: Arc<Mutex<Triplestore>>) -> TriplestoreFlightService {
+        TriplestoreFlightService { triplestore }
+    }
+
+    pub fn into_server(self) -> FlightServiceServer<TriplestoreFlightService> {
+        FlightServiceServer::new(self)
+    }
+}
+
+//Runs f with the locked store on a thread where blocking is allowed, so that the async runtime is not held up
+async fn with_triplestore<T: Send + 'static>(
+    triplestore: &Arc<Mutex<Triplestore>>,
+    f: impl FnOnce(&mut Triplestore) -> Result<T, Status> + Send + 'static,
+) -> Result<T, Status> {
+    let triplestore = triplestore.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut triplestore = triplestore
+            .lock()
+            .map_err(|_| Status::internal("The triplestore lock is poisoned"))?;
+        f(&mut *triplestore)
+    })
+    .await
+    .map_err(|e| Status::internal(e.to_string()))?
+}
+
+fn select_df(triplestore: &mut Triplestore, query: &str) -> Result<DataFrame, Status> {
+    match triplestore.query(query) {
+        Ok(QueryResult::Select(df)) => Ok(df),
+        Ok(_) => Err(Status::invalid_argument("Only SELECT queries can be streamed")),
+        Err(e) => Err(Status::invalid_argument(e.to_string())),
+    }
+}
+
+//The tables of the default graph are listed without reading their triples.
+//The number of records is only given for tables without duplicates, and is -1 otherwise.
+fn flight_infos(triplestore: &mut Triplestore) -> Result<Vec<FlightInfo>, Status> {
+    let mut table_infos: Vec<TripleTableInfo> = triplestore
+        .table_infos()
+        .into_iter()
+        .filter(|info| info.graph.is_none())
+        .collect();
+    table_infos.sort_by(|i1, i2| {
+        i1.predicate.cmp(&i2.predicate).then(
+            rdf_node_type_tag(&i1.object_type).cmp(&rdf_node_type_tag(&i2.object_type)),
+        )
+    });
+    let mut infos = vec![];
+    for info in table_infos {
+        let mut df = triplestore
+            .empty_triples_df(&info.predicate, &info.object_type)
+            .map_err(|e| Status::internal(e.to_string()))?
+            .unwrap();
+        let schema = record_batches(&mut df)?.0;
+        let schema = IpcMessage::try_from(SchemaAsIpc::new(&schema, &IpcWriteOptions::default()))
+            .map_err(|e| Status::internal(e.to_string()))?;
+        infos.push(FlightInfo {
+            schema: schema.0,
+            flight_descriptor: Some(FlightDescriptor::new_path(vec![
+                info.predicate.clone(),
+                rdf_node_type_tag(&info.object_type),
+            ])),
+            endpoint: vec![FlightEndpoint {
+                ticket: Some(triples_ticket(&info.predicate, &info.object_type)),
+                location: vec![],
+            }],
+            total_records: if info.unique {
+                info.height() as i64
+            } else {
+                -1
+            },
+            total_bytes: -1,
+        });
+    }
+    Ok(infos)
+}
+
+fn not_found(predicate: &str, object_type: &RDFNodeType) -> Status {
+    Status::not_found(format!(
+        "No triples for {} with object type {}",
+        predicate,
+        rdf_node_type_tag(object_type)
+    ))
+}
+
+impl Triplestore {
+    //An empty frame with the columns of the first chunk of the table in the default graph
+    fn empty_triples_df(
+        &self,
+        predicate: &str,
+        object_type: &RDFNodeType,
+    ) -> Result<Option<DataFrame>, MappingError> {
+        if let Some(tt) = self
+            .df_map
+            .get(predicate)
+            .and_then(|tables| tables.get(object_type))
+        {
+            let lf = tt.get_lazy_frames()?.into_iter().next().unwrap();
+            let df = lf
+                .slice(0, 0)
+                .collect()
+                .map_err(|x| MappingError::ReadParquetError(x))?;
+            Ok(Some(df))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn triples_chunks(&self, predicate: &str, object_type: &RDFNodeType) -> Option<usize> {
+        self.df_map
+            .get(predicate)
+            .and_then(|tables| tables.get(object_type))
+            .map(|tt| tt.len())
+    }
+
+    //None if the table no longer has the chunk
+    fn triples_chunk(
+        &mut self,
+        predicate: &str,
+        object_type: &RDFNodeType,
+        chunk: usize,
+    ) -> Result<Option<DataFrame>, MappingError> {
+        if let Some(tt) = self
+            .df_map
+            .get_mut(predicate)
+            .and_then(|tables| tables.get_mut(object_type))
+        {
+            if chunk < tt.len() {
+                let df = tt.get_df(chunk)?.clone();
+                tt.forget_tmp_df();
+                return Ok(Some(df));
+            }
+        }
+        Ok(None)
+    }
+}
+
+//Polars and the Flight crates use different Arrow implementations, so the data frame goes through the IPC stream format
+fn record_batches(df: &mut DataFrame) -> Result<(Schema, Vec<RecordBatch>), Status> {
+    let mut buffer = vec![];
+    IpcStreamWriter::new(&mut buffer)
+        .finish(df)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    let reader =
+        StreamReader::try_new(Cursor::new(buffer), None).map_err(|e| Status::internal(e.to_string()))?;
+    let schema = reader.schema().as_ref().clone();
+    let batches = reader
+        .collect::<Result<Vec<RecordBatch>, _>>()
+        .map_err(|e| Status::internal(e.to_string()))?;
+    Ok((schema, batches))
+}
+
+#[tonic::async_trait]
+impl FlightService for TriplestoreFlightService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("Handshake is not supported"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        let infos = with_triplestore(&self.triplestore, flight_infos).await?;
+        Ok(Response::new(stream::iter(infos.into_iter().map(Ok)).boxed()))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("Use ListFlights or tickets made by select_ticket"))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented("Schemas are given by ListFlights"))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let ticket = parse_ticket(&request.into_inner().ticket)?;
+        let flight_data = match ticket {
+            FlightTicket::Select(query) => {
+                let (schema, batches) = with_triplestore(&self.triplestore, move |t| {
+                    let mut df = select_df(t, &query)?;
+                    record_batches(&mut df)
+                })
+                .await?;
+                FlightDataEncoderBuilder::new()
+                    .with_schema(Arc::new(schema))
+                    .build(stream::iter(batches.into_iter().map(Ok)))
+                    .boxed()
+            }
+            FlightTicket::Triples(predicate, object_type) => {
+                //Deduplicated before the first chunk is read, so that the chunks have no triples in common
+                let (schema, chunks) = with_triplestore(&self.triplestore, {
+                    let predicate = predicate.clone();
+                    let object_type = object_type.clone();
+                    move |t| {
+                        t.deduplicate()
+                            .map_err(|e| Status::internal(e.to_string()))?;
+                        let mut df = t
+                            .empty_triples_df(&predicate, &object_type)
+                            .map_err(|e| Status::internal(e.to_string()))?
+                            .ok_or_else(|| not_found(&predicate, &object_type))?;
+                        let chunks = t.triples_chunks(&predicate, &object_type).unwrap();
+                        Ok((record_batches(&mut df)?.0, chunks))
+                    }
+                })
+                .await?;
+                let triplestore = self.triplestore.clone();
+                let batches = stream::iter(0..chunks)
+                    .then(move |chunk| {
+                        let triplestore = triplestore.clone();
+                        let predicate = predicate.clone();
+                        let object_type = object_type.clone();
+                        async move {
+                            with_triplestore(&triplestore, move |t| {
+                                match t
+                                    .triples_chunk(&predicate, &object_type, chunk)
+                                    .map_err(|e| Status::internal(e.to_string()))?
+                                {
+                                    Some(mut df) => Ok(record_batches(&mut df)?.1),
+                                    None => Ok(vec![]),
+                                }
+                            })
+                            .await
+                        }
+                    })
+                    .map_ok(|batches| stream::iter(batches.into_iter().map(Ok::<RecordBatch, Status>)))
+                    .try_flatten()
+                    .map_err(FlightError::Tonic);
+                FlightDataEncoderBuilder::new()
+                    .with_schema(Arc::new(schema))
+                    .build(batches)
+                    .boxed()
+            }
+        };
+        Ok(Response::new(flight_data.map_err(Status::from).boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("The service is read only"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("No actions are supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(stream::empty().boxed()))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("The service is read only"))
+    }
+}
+
+/// Serves the triplestore over Arrow Flight at the address until the server fails.
+pub async fn serve_flight(
+    triplestore: Arc<Mutex<Triplestore>>,
+    address: SocketAddr,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(TriplestoreFlightService::new(triplestore).into_server())
+        .serve(address)
+        .await
+}
+
+#[test]
+fn test_parse_tickets() {
+    let ticket = select_ticket("SELECT ?s WHERE {\n?s ?p ?o\n}");
+    assert_eq!(
+        parse_ticket(&ticket.ticket).unwrap(),
+        FlightTicket::Select("SELECT ?s WHERE {\n?s ?p ?o\n}".to_string())
+    );
+    let ticket = triples_ticket("http://example.net/ns#hasValue", &RDFNodeType::IRI);
+    assert_eq!(
+        parse_ticket(&ticket.ticket).unwrap(),
+        FlightTicket::Triples(
+            "http://example.net/ns#hasValue".to_string(),
+            RDFNodeType::IRI
+        )
+    );
+    assert!(parse_ticket(b"describe\n<http://example.net/ns#a>").is_err());
+}
+
+#[tokio::test]
+async fn test_list_flights_and_do_get() {
+    use crate::mapping::Mapping;
+    use arrow_flight::utils::flight_data_to_arrow_batch;
+    use polars_core::prelude::NamedFrom;
+    use polars_core::series::Series;
+    use std::collections::HashMap;
+
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(t_str, None).unwrap();
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a", "http://example.net/ns#b"]),
+        Series::new("count", [1i64, 2]),
+    ])
+    .unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let service = TriplestoreFlightService::new(Arc::new(Mutex::new(mapping.triplestore)));
+
+    let infos: Vec<FlightInfo> = service
+        .list_flights(Request::new(Criteria::default()))
+        .await
+        .unwrap()
+        .into_inner()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].total_records, 2);
+
+    let ticket = infos[0].endpoint[0].ticket.clone().unwrap();
+    let data: Vec<FlightData> = service
+        .do_get(Request::new(ticket))
+        .await
+        .unwrap()
+        .into_inner()
+        .try_collect()
+        .await
+        .unwrap();
+    //The schema is sent before the record batches
+    let schema = Arc::new(Schema::try_from(&data[0]).unwrap());
+    let rows: usize = data[1..]
+        .iter()
+        .map(|d| {
+            flight_data_to_arrow_batch(d, schema.clone(), &HashMap::new())
+                .unwrap()
+                .num_rows()
+        })
+        .sum();
+    assert_eq!(rows, 2);
+}
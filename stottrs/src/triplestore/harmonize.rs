@@ -0,0 +1,229 @@
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use oxrdf::vocab::xsd;
+use polars::prelude::{col, LazyFrame};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{DataType, SchemaRef};
+use polars_core::utils::get_supertype;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct SchemaMismatch {
+    pub chunk: usize,
+    pub column: String,
+    pub expected: DataType,
+    pub found: DataType,
+}
+
+/// Finds columns where the schemas differ from the first schema.
+pub(crate) fn find_schema_mismatches(schemas: &Vec<SchemaRef>) -> Vec<SchemaMismatch> {
+    let mut mismatches = vec![];
+    if let Some(first) = schemas.first() {
+        for (i, schema) in schemas.iter().enumerate().skip(1) {
+            for (name, dtype) in schema.iter() {
+                let expected = first.get(name).unwrap_or(&DataType::Null);
+                if expected != dtype {
+                    mismatches.push(SchemaMismatch {
+                        chunk: i,
+                        column: name.to_string(),
+                        expected: expected.clone(),
+                        found: dtype.clone(),
+                    });
+                }
+            }
+        }
+    }
+    mismatches
+}
+
+/// Finds a common data type for each column with mismatches, if one exists.
+pub(crate) fn find_supertypes(
+    schemas: &Vec<SchemaRef>,
+    mismatches: &Vec<SchemaMismatch>,
+) -> Result<HashMap<String, DataType>, SchemaMismatch> {
+    let mut supertypes = HashMap::new();
+    for m in mismatches {
+        if supertypes.contains_key(&m.column) {
+            continue;
+        }
+        let mut supertype: Option<DataType> = None;
+        for (i, schema) in schemas.iter().enumerate() {
+            if let Some(dtype) = schema.get(&m.column) {
+                if let Some(st) = &supertype {
+                    if let Some(new_st) = get_supertype(st, dtype) {
+                        supertype = Some(new_st);
+                    } else {
+                        return Err(SchemaMismatch {
+                            chunk: i,
+                            column: m.column.clone(),
+                            expected: st.clone(),
+                            found: dtype.clone(),
+                        });
+                    }
+                } else {
+                    supertype = Some(dtype.clone());
+                }
+            }
+        }
+        supertypes.insert(m.column.clone(), supertype.unwrap());
+    }
+    Ok(supertypes)
+}
+
+/// Checks that all chunks of a predicate have the same schema before they are concatenated.
+/// Columns that can be widened without losing information are always cast to their supertype,
+/// with harmonize set, other mismatching columns are cast to their supertype as well.
+pub(crate) fn harmonize_lazy_frames(
+    lfs: Vec<LazyFrame>,
+    predicate: &str,
+    harmonize: bool,
+) -> Result<Vec<LazyFrame>, MappingError> {
+    if lfs.len() < 2 {
+        return Ok(lfs);
+    }
+    let schemas: Vec<SchemaRef> = lfs.iter().map(|lf| lf.schema().unwrap()).collect();
+    let mismatches = find_schema_mismatches(&schemas);
+    if mismatches.is_empty() {
+        return Ok(lfs);
+    }
+    let supertypes = find_supertypes(&schemas, &mismatches);
+    let all_widening = if let Ok(supertypes) = &supertypes {
+        supertypes.iter().all(|(c, st)| {
+            schemas
+                .iter()
+                .all(|s| s.get(c).map_or(true, |dt| is_widening(dt, st)))
+        })
+    } else {
+        false
+    };
+    if !all_widening && !harmonize {
+        return Err(MappingError::ChunkSchemaMismatch(
+            predicate.to_string(),
+            mismatches,
+        ));
+    }
+    let supertypes = supertypes.map_err(|m| {
+        MappingError::ChunkSchemaMismatch(predicate.to_string(), vec![m])
+    })?;
+    Ok(lfs
+        .into_iter()
+        .map(|lf| {
+            lf.with_columns(
+                supertypes
+                    .iter()
+                    .map(|(c, dt)| col(c).cast(dt.clone()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect())
+}
+
+/// The data type used to store objects of an RDF node type, the inverse of how data types of columns are mapped to RDF literal types.
+/// Types with several possible representations, such as date times with time zones, have none.
+/// Decimals have none either, as casting them to floats would lose their exact values.
+pub(crate) fn canonical_datatype(rdf_node_type: &RDFNodeType) -> Option<DataType> {
+    let dt = match rdf_node_type {
+        RDFNodeType::IRI | RDFNodeType::BlankNode => DataType::Utf8,
+        RDFNodeType::Literal(l) => {
+            let l = l.as_ref();
+            if l == xsd::STRING {
+                DataType::Utf8
+            } else if l == xsd::BOOLEAN {
+                DataType::Boolean
+            } else if l == xsd::UNSIGNED_BYTE {
+                DataType::UInt8
+            } else if l == xsd::UNSIGNED_SHORT {
+                DataType::UInt16
+            } else if l == xsd::UNSIGNED_INT {
+                DataType::UInt32
+            } else if l == xsd::UNSIGNED_LONG
+                || l == xsd::NON_NEGATIVE_INTEGER
+                || l == xsd::POSITIVE_INTEGER
+            {
+                DataType::UInt64
+            } else if l == xsd::BYTE {
+                DataType::Int8
+            } else if l == xsd::SHORT {
+                DataType::Int16
+            } else if l == xsd::INT {
+                DataType::Int32
+            } else if l == xsd::LONG
+                || l == xsd::INTEGER
+                || l == xsd::NON_POSITIVE_INTEGER
+                || l == xsd::NEGATIVE_INTEGER
+            {
+                DataType::Int64
+            } else if l == xsd::FLOAT {
+                DataType::Float32
+            } else if l == xsd::DOUBLE {
+                DataType::Float64
+            } else if l == xsd::DATE {
+                DataType::Date
+            } else {
+                return None;
+            }
+        }
+        RDFNodeType::None | RDFNodeType::MultiType => return None,
+    };
+    Some(dt)
+}
+
+/// Casts the subject and object columns of triples to the data types used to store them,
+/// so that chunks of a predicate added from differently typed columns have the same schema.
+/// Objects are only cast when no information is lost, other mismatches are left to harmonize_lazy_frames.
+pub(crate) fn harmonize_triples_df(mut df: DataFrame, object_type: &RDFNodeType) -> DataFrame {
+    if let DataType::Categorical(_) = df.column("subject").unwrap().dtype() {
+        let subject = df.column("subject").unwrap().cast(&DataType::Utf8).unwrap();
+        df.with_column(subject).unwrap();
+    }
+    if let Some(canonical) = canonical_datatype(object_type) {
+        let object_dtype = df.column("object").unwrap().dtype().clone();
+        if object_dtype != canonical && is_widening(&object_dtype, &canonical) {
+            let object = df.column("object").unwrap().cast(&canonical).unwrap();
+            df.with_column(object).unwrap();
+        }
+    }
+    df
+}
+
+/// Checks if every value of a data type can be represented in another data type,
+/// such as when casting from Int32 to Int64, from Float32 to Float64 or from Categorical to Utf8.
+pub(crate) fn is_widening(from: &DataType, to: &DataType) -> bool {
+    if from == to {
+        return true;
+    }
+    match (from, to) {
+        (DataType::Null, _) => true,
+        (DataType::Categorical(_), DataType::Utf8) => true,
+        (DataType::Float32, DataType::Float64) => true,
+        (f, DataType::Float32) => integer_bits(f).map_or(false, |(_, bits)| bits <= 16),
+        (f, DataType::Float64) => integer_bits(f).map_or(false, |(_, bits)| bits <= 32),
+        (f, t) => {
+            if let (Some((from_signed, from_bits)), Some((to_signed, to_bits))) =
+                (integer_bits(f), integer_bits(t))
+            {
+                if from_signed == to_signed {
+                    from_bits <= to_bits
+                } else {
+                    !from_signed && from_bits < to_bits
+                }
+            } else {
+                false
+            }
+        }
+    }
+}
+
+fn integer_bits(dt: &DataType) -> Option<(bool, u8)> {
+    match dt {
+        DataType::UInt8 => Some((false, 8)),
+        DataType::UInt16 => Some((false, 16)),
+        DataType::UInt32 => Some((false, 32)),
+        DataType::UInt64 => Some((false, 64)),
+        DataType::Int8 => Some((true, 8)),
+        DataType::Int16 => Some((true, 16)),
+        DataType::Int32 => Some((true, 32)),
+        DataType::Int64 => Some((true, 64)),
+        _ => None,
+    }
+}
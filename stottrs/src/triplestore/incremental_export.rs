@@ -0,0 +1,85 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::custom_datatypes::formatter_for;
+use crate::triplestore::deduplication::DeduplicationStrategy;
+use crate::triplestore::ntriples_write::write_ntriples_for_table;
+use log::warn;
+use polars_core::POOL;
+use polars_utils::contention_pool::LowContentionPool;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The chunks of each table of the default graph that have been exported.
+/// The default watermark is before the first triple.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportWatermark {
+    tables: HashMap<(String, RDFNodeType), TableWatermark>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TableWatermark {
+    generation: String,
+    chunks: usize,
+}
+
+impl Triplestore {
+    /// Writes the triples of the default graph added after the watermark as N-Triples, and returns the watermark
+    /// of the written triples. Tables are deduplicated incrementally, so that triples exported earlier are not
+    /// rewritten. Tables that have been rewritten since the watermark, e.g. by removing triples or by retention
+    /// policies, are written in full.
+    pub fn write_n_triples_since<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        watermark: &ExportWatermark,
+        chunk_size: usize,
+    ) -> Result<ExportWatermark, MappingError> {
+        let deduplication_strategy = self.deduplication_strategy;
+        self.deduplication_strategy = DeduplicationStrategy::Incremental;
+        let deduplicated = self.deduplicate();
+        self.deduplication_strategy = deduplication_strategy;
+        deduplicated?;
+
+        let n_threads = POOL.current_num_threads();
+        let mut any_value_iter_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+        let mut write_buffer_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+        let mut new_watermark = ExportWatermark::default();
+        for (property, map) in &mut self.df_map {
+            for (rdf_node_type, tt) in map {
+                let key = (property.clone(), rdf_node_type.clone());
+                let from_chunk = match watermark.tables.get(&key) {
+                    Some(w) if w.generation == tt.generation && w.chunks <= tt.len() => w.chunks,
+                    Some(_) => {
+                        warn!(
+                            "Triples of {} were rewritten since the watermark and are exported in full",
+                            property
+                        );
+                        0
+                    }
+                    None => 0,
+                };
+                write_ntriples_for_table(
+                    tt,
+                    property,
+                    rdf_node_type,
+                    formatter_for(&self.custom_datatypes, rdf_node_type),
+                    from_chunk,
+                    None,
+                    writer,
+                    chunk_size,
+                    n_threads,
+                    &mut any_value_iter_pool,
+                    &mut write_buffer_pool,
+                )?;
+                new_watermark.tables.insert(
+                    key,
+                    TableWatermark {
+                        generation: tt.generation.clone(),
+                        chunks: tt.len(),
+                    },
+                );
+            }
+        }
+        Ok(new_watermark)
+    }
+}
@@ -0,0 +1,64 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::parquet::property_to_filename;
+use log::debug;
+use polars::prelude::{IpcWriter, SerWriter};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+impl Triplestore {
+    /// Writes one Arrow IPC file for each predicate and object type to the folder,
+    /// with the same file names as write_native_parquet.
+    /// Triples in named graphs are written to files prefixed with the name of the graph.
+    pub fn write_ipc(&mut self, path: &Path) -> Result<(), MappingError> {
+        let now = Instant::now();
+        if !path.exists() {
+            return Err(MappingError::PathDoesNotExist(
+                path.to_str().unwrap().to_string(),
+            ));
+        }
+        self.deduplicate()?;
+        write_ipc_map(&self.df_map, path, "")?;
+        for (graph, map) in &self.named_graph_df_maps {
+            let prefix = format!("{}_", property_to_filename(graph));
+            write_ipc_map(map, path, &prefix)?;
+        }
+        debug!("Writing IPC took {} seconds", now.elapsed().as_secs_f64());
+        Ok(())
+    }
+}
+
+fn write_ipc_map(
+    df_map: &HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    path: &Path,
+    prefix: &str,
+) -> Result<(), MappingError> {
+    for (property, tts) in df_map {
+        for (rdf_node_type, tt) in tts {
+            let filename = if let RDFNodeType::Literal(literal_type) = rdf_node_type {
+                format!(
+                    "{}{}_{}",
+                    prefix,
+                    property_to_filename(property),
+                    property_to_filename(literal_type.as_str())
+                )
+            } else {
+                format!("{}{}_object_property", prefix, property_to_filename(property))
+            };
+            for (i, lf) in tt.get_lazy_frames()?.into_iter().enumerate() {
+                let mut df = lf.collect().unwrap();
+                let mut file_path = PathBuf::from(path);
+                file_path.push(format!("{filename}_part_{i}.ipc"));
+                let file =
+                    File::create(file_path).map_err(|x| MappingError::FileCreateIOError(x))?;
+                IpcWriter::new(file)
+                    .finish(&mut df)
+                    .map_err(|x| MappingError::WriteIpcError(x))?;
+            }
+        }
+    }
+    Ok(())
+}
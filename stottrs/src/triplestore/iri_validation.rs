@@ -0,0 +1,195 @@
+use super::{TripleCountDelta, TripleDF, Triplestore};
+use crate::mapping::RDFNodeType;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::{BooleanChunked, DataType, NamedFrom};
+use polars_core::series::Series;
+use polars_core::utils::concat_df;
+
+/// Checks of the IRIs of triples added to the store.
+/// IRIs must have a scheme and no whitespace, control characters or characters such as < and > that are not allowed in IRIs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IriValidation {
+    /// The allowed schemes, e.g. http and https, compared without regard to case. All schemes are allowed when empty.
+    pub schemes: Vec<String>,
+    /// The maximum number of characters of an IRI.
+    pub max_length: Option<usize>,
+}
+
+impl IriValidation {
+    fn violation(&self, iri: &str) -> Option<&'static str> {
+        if let Some(max_length) = self.max_length {
+            if iri.chars().count() > max_length {
+                return Some("too long");
+            }
+        }
+        if iri.chars().any(is_disallowed) {
+            return Some("disallowed character");
+        }
+        let scheme = if let Some(scheme) = scheme(iri) {
+            scheme
+        } else {
+            return Some("missing scheme");
+        };
+        if !self.schemes.is_empty() && !self.schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme))
+        {
+            return Some("scheme not allowed");
+        }
+        None
+    }
+}
+
+impl Triplestore {
+    /// Validates the IRIs of the triples added to the store, except for predicates with their own validation.
+    /// Triples with an invalid subject, verb or IRI object are not added, and are kept as violations, see take_iri_violations.
+    pub fn set_iri_validation(&mut self, validation: Option<IriValidation>) {
+        self.iri_validation = validation;
+    }
+
+    /// Validates the IRIs of triples with the predicate with this validation instead of the validation of the store.
+    pub fn set_predicate_iri_validation(&mut self, predicate: &str, validation: IriValidation) {
+        self.predicate_iri_validations
+            .insert(predicate.to_string(), validation);
+    }
+
+    pub fn remove_predicate_iri_validation(&mut self, predicate: &str) {
+        self.predicate_iri_validations.remove(predicate);
+    }
+
+    /// Returns the triples that were not added because of invalid IRIs since the last call,
+    /// with the columns subject, verb, object, position (subject, verb or object) and violation.
+    /// A triple with several invalid IRIs has a row for each.
+    pub fn take_iri_violations(&mut self) -> DataFrame {
+        let violations: Vec<DataFrame> = self.iri_violations.drain(..).collect();
+        if violations.is_empty() {
+            violations_df(vec![], vec![], vec![], vec![], vec![])
+        } else {
+            concat_df(&violations).unwrap()
+        }
+    }
+
+    pub(crate) fn validate_iris(
+        &mut self,
+        triples_df: Vec<TripleDF>,
+        triple_counts: &mut Vec<TripleCountDelta>,
+    ) -> Vec<TripleDF> {
+        if self.iri_validation.is_none() && self.predicate_iri_validations.is_empty() {
+            return triples_df;
+        }
+        let mut valid = vec![];
+        for mut tdf in triples_df {
+            let validation = if let Some(validation) = self
+                .predicate_iri_validations
+                .get(&tdf.predicate)
+                .or(self.iri_validation.as_ref())
+            {
+                validation
+            } else {
+                valid.push(tdf);
+                continue;
+            };
+            if let Some((keep, violations)) = find_violations(&tdf, validation) {
+                let rejected = (&keep).into_iter().filter(|k| k == &Some(false)).count();
+                if let Some(counts) = triple_counts
+                    .iter_mut()
+                    .find(|c| c.predicate == tdf.predicate && c.graph == tdf.graph)
+                {
+                    counts.added_rows -= rejected.min(counts.added_rows);
+                }
+                self.iri_violations.push(violations);
+                tdf.df = tdf.df.filter(&keep).unwrap();
+            }
+            if tdf.df.height() > 0 {
+                valid.push(tdf);
+            }
+        }
+        valid
+    }
+}
+
+fn find_violations(tdf: &TripleDF, validation: &IriValidation) -> Option<(BooleanChunked, DataFrame)> {
+    let subjects = tdf.df.column("subject").unwrap().cast(&DataType::Utf8).unwrap();
+    let subjects = subjects.utf8().unwrap();
+    //Objects of other types are only included in the violations
+    let objects = tdf
+        .df
+        .column("object")
+        .unwrap()
+        .cast(&DataType::Utf8)
+        .unwrap_or_else(|_| Series::full_null("object", tdf.df.height(), &DataType::Utf8));
+    let objects = objects.utf8().unwrap();
+    let object_is_iri = tdf.object_type == RDFNodeType::IRI;
+    let verb_violation = validation.violation(&tdf.predicate);
+
+    let mut keep = vec![];
+    let mut violating_subjects = vec![];
+    let mut violating_objects = vec![];
+    let mut positions = vec![];
+    let mut reasons = vec![];
+    for (s, o) in subjects.into_iter().zip(objects.into_iter()) {
+        let mut row_violations = vec![];
+        if let Some(s) = s.filter(|s| !s.starts_with("_:")) {
+            if let Some(v) = validation.violation(s) {
+                row_violations.push(("subject", v));
+            }
+        }
+        if let Some(v) = verb_violation {
+            row_violations.push(("verb", v));
+        }
+        if object_is_iri {
+            if let Some(o) = o.filter(|o| !o.starts_with("_:")) {
+                if let Some(v) = validation.violation(o) {
+                    row_violations.push(("object", v));
+                }
+            }
+        }
+        keep.push(row_violations.is_empty());
+        for (position, reason) in row_violations {
+            violating_subjects.push(s.map(|s| s.to_string()));
+            violating_objects.push(o.map(|o| o.to_string()));
+            positions.push(position);
+            reasons.push(reason);
+        }
+    }
+    if positions.is_empty() {
+        return None;
+    }
+    let verbs = vec![tdf.predicate.as_str(); positions.len()];
+    Some((
+        BooleanChunked::new("keep", keep),
+        violations_df(violating_subjects, verbs, violating_objects, positions, reasons),
+    ))
+}
+
+fn violations_df(
+    subjects: Vec<Option<String>>,
+    verbs: Vec<&str>,
+    objects: Vec<Option<String>>,
+    positions: Vec<&str>,
+    reasons: Vec<&str>,
+) -> DataFrame {
+    DataFrame::new(vec![
+        Series::new("subject", subjects),
+        Series::new("verb", verbs),
+        Series::new("object", objects),
+        Series::new("position", positions),
+        Series::new("violation", reasons),
+    ])
+    .unwrap()
+}
+
+//A scheme is a letter followed by letters, digits, +, - and . before the first colon
+fn scheme(iri: &str) -> Option<&str> {
+    let (scheme, _) = iri.split_once(':')?;
+    let mut chars = scheme.chars();
+    if chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        Some(scheme)
+    } else {
+        None
+    }
+}
+
+fn is_disallowed(c: char) -> bool {
+    c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\')
+}
@@ -0,0 +1,172 @@
+use super::Triplestore;
+use oxrdf::Variable;
+use spargebra::term::{NamedNodePattern, TermPattern, TriplePattern};
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TriplePosition {
+    Subject,
+    Object,
+}
+
+/// Two predicates whose triples share nodes, because a template produces both with the same parameter or blank node,
+/// e.g. the subject of ex:hasAddress triples is the subject of ex:name triples.
+/// Joins on the shared position are expected to find matches, and are evaluated before joins without hints.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JoinHint {
+    pub left_predicate: String,
+    pub left_position: TriplePosition,
+    pub right_predicate: String,
+    pub right_position: TriplePosition,
+}
+
+impl JoinHint {
+    /// The hint is the same whichever predicate is given first.
+    pub fn new(
+        predicate: &str,
+        position: TriplePosition,
+        other_predicate: &str,
+        other_position: TriplePosition,
+    ) -> JoinHint {
+        let (left, right) = if (predicate, position) <= (other_predicate, other_position) {
+            ((predicate, position), (other_predicate, other_position))
+        } else {
+            ((other_predicate, other_position), (predicate, position))
+        };
+        JoinHint {
+            left_predicate: left.0.to_string(),
+            left_position: left.1,
+            right_predicate: right.0.to_string(),
+            right_position: right.1,
+        }
+    }
+}
+
+impl Triplestore {
+    /// Join hints are recorded when templates are expanded, and may also be added for triples from other sources.
+    pub fn add_join_hints(&mut self, join_hints: Vec<JoinHint>) {
+        self.join_hints.extend(join_hints);
+    }
+
+    /// The join hints of the store, sorted.
+    pub fn join_hints(&self) -> Vec<&JoinHint> {
+        let mut join_hints: Vec<&JoinHint> = self.join_hints.iter().collect();
+        join_hints.sort();
+        join_hints
+    }
+
+    //Keeps the order of the patterns, unless the next pattern shares no variables with the patterns before it.
+    //The pattern would then give a cross product, so a pattern with a join hint to the patterns before it is taken first,
+    //or else a pattern sharing a variable with them.
+    pub(crate) fn order_triple_patterns<'a>(
+        &self,
+        patterns: &'a [TriplePattern],
+        mut bound_variables: HashSet<String>,
+    ) -> Vec<&'a TriplePattern> {
+        let mut remaining: Vec<&TriplePattern> = patterns.iter().collect();
+        let mut ordered: Vec<&TriplePattern> = vec![];
+        while !remaining.is_empty() {
+            let next = if bound_variables.is_empty()
+                || shares_variable(remaining[0], &bound_variables)
+            {
+                0
+            } else {
+                remaining
+                    .iter()
+                    .position(|tp| ordered.iter().any(|o| self.has_join_hint(o, tp)))
+                    .or_else(|| {
+                        remaining
+                            .iter()
+                            .position(|tp| shares_variable(tp, &bound_variables))
+                    })
+                    .unwrap_or(0)
+            };
+            let tp = remaining.remove(next);
+            bound_variables.extend(variables(tp).into_iter().map(|v| v.as_str().to_string()));
+            ordered.push(tp);
+        }
+        ordered
+    }
+
+    fn has_join_hint(&self, left: &TriplePattern, right: &TriplePattern) -> bool {
+        if let (NamedNodePattern::NamedNode(left_predicate), NamedNodePattern::NamedNode(right_predicate)) =
+            (&left.predicate, &right.predicate)
+        {
+            for (left_variable, left_position) in variable_positions(left) {
+                for (right_variable, right_position) in variable_positions(right) {
+                    if left_variable == right_variable
+                        && self.join_hints.contains(&JoinHint::new(
+                            left_predicate.as_str(),
+                            left_position,
+                            right_predicate.as_str(),
+                            right_position,
+                        ))
+                    {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
+fn variable_positions(tp: &TriplePattern) -> Vec<(&Variable, TriplePosition)> {
+    let mut out = vec![];
+    if let TermPattern::Variable(v) = &tp.subject {
+        out.push((v, TriplePosition::Subject));
+    }
+    if let TermPattern::Variable(v) = &tp.object {
+        out.push((v, TriplePosition::Object));
+    }
+    out
+}
+
+fn variables(tp: &TriplePattern) -> Vec<&Variable> {
+    let mut out: Vec<&Variable> = variable_positions(tp).into_iter().map(|(v, _)| v).collect();
+    if let NamedNodePattern::Variable(v) = &tp.predicate {
+        out.push(v);
+    }
+    out
+}
+
+fn shares_variable(tp: &TriplePattern, variables_before: &HashSet<String>) -> bool {
+    variables(tp)
+        .into_iter()
+        .any(|v| variables_before.contains(v.as_str()))
+}
+
+#[test]
+fn test_order_triple_patterns_avoids_cross_products() {
+    use oxrdf::NamedNode;
+    let ex = |s: &str| NamedNode::new_unchecked(format!("http://example.net/ns#{}", s));
+    let tp = |s: &str, p: &str, o: &str| TriplePattern {
+        subject: TermPattern::Variable(Variable::new_unchecked(s)),
+        predicate: NamedNodePattern::NamedNode(ex(p)),
+        object: TermPattern::Variable(Variable::new_unchecked(o)),
+    };
+    let patterns = vec![
+        tp("p", "name", "name"),
+        tp("a", "street", "street"),
+        tp("p", "knows", "q"),
+        tp("p", "hasAddress", "a"),
+    ];
+    let mut triplestore = Triplestore::new(None);
+    let ordered = triplestore.order_triple_patterns(&patterns, HashSet::new());
+    assert_eq!(
+        ordered,
+        vec![&patterns[0], &patterns[2], &patterns[3], &patterns[1]]
+    );
+
+    triplestore.add_join_hints(vec![JoinHint::new(
+        "http://example.net/ns#hasAddress",
+        TriplePosition::Subject,
+        "http://example.net/ns#name",
+        TriplePosition::Subject,
+    )]);
+    let ordered = triplestore.order_triple_patterns(&patterns, HashSet::new());
+    assert_eq!(
+        ordered,
+        vec![&patterns[0], &patterns[3], &patterns[1], &patterns[2]]
+    );
+}
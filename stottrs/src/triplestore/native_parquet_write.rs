@@ -1,12 +1,20 @@
-use super::Triplestore;
+use super::{TripleTable, Triplestore};
+use std::collections::HashSet;
 use std::path::Path;
 use std::time::Instant;
 use log::debug;
-use rayon::iter::ParallelDrainRange;
-use rayon::iter::ParallelIterator;
+use polars_core::frame::DataFrame;
+use polars_core::prelude::NamedFrom;
+use polars_core::series::Series;
+use uuid::Uuid;
 use crate::mapping::errors::MappingError;
 use crate::mapping::RDFNodeType;
-use crate::triplestore::parquet::{property_to_filename, write_parquet};
+use crate::triplestore::parquet::{property_to_filename, read_parquet, write_parquet};
+use crate::triplestore::sparql::solution_mapping::{rdf_node_type_from_tag, rdf_node_type_tag};
+
+//Lists the file, predicate, object type and graph of each part, as file names do not contain the full predicates.
+//The graph is null for the default graph.
+const NATIVE_PARQUET_INDEX: &str = "_tables.parquet";
 
 impl Triplestore {
     pub fn write_native_parquet(&mut self, path: &Path) -> Result<(), MappingError>{
@@ -18,42 +26,143 @@ impl Triplestore {
 
         self.deduplicate()?;
 
-        let mut dfs_to_write = vec![];
-
-        for (property, tts) in &mut self.df_map {
-            for (rdf_node_type, tt) in tts {
-                let filename;
-                if let RDFNodeType::Literal(literal_type) = rdf_node_type {
-                    filename = format!(
-                        "{}_{}",
-                        property_to_filename(property),
-                        property_to_filename(literal_type.as_str())
-                    );
-                } else {
-                    filename = format!(
-                        "{}_object_property",
-                        property_to_filename(property),
-                    )
-                }
-                let file_path = path_buf.clone();
-                if let Some(_) = &self.caching_folder{ } else {
-                    for (i, df) in tt.dfs.as_mut().unwrap().iter_mut().enumerate() {
+        let mut index_files = vec![];
+        let mut index_predicates = vec![];
+        let mut index_object_types = vec![];
+        let mut index_graphs = vec![];
+        let mut used_filenames = HashSet::new();
+
+        let default_graph = std::iter::once((None, &self.df_map));
+        let named_graphs = self
+            .named_graph_df_maps
+            .iter()
+            .map(|(g, m)| (Some(g), m));
+        for (graph, df_map) in default_graph.chain(named_graphs) {
+            //Triples in named graphs are written to files prefixed with the name of the graph, as in write_ipc
+            let prefix = if let Some(g) = graph {
+                format!("{}_", property_to_filename(g))
+            } else {
+                "".to_string()
+            };
+            for (property, tts) in df_map {
+                for (rdf_node_type, tt) in tts {
+                    let mut filename;
+                    if let RDFNodeType::Literal(literal_type) = rdf_node_type {
+                        filename = format!(
+                            "{}{}_{}",
+                            prefix,
+                            property_to_filename(property),
+                            property_to_filename(literal_type.as_str())
+                        );
+                    } else {
+                        filename = format!(
+                            "{}{}_object_property",
+                            prefix,
+                            property_to_filename(property),
+                        )
+                    }
+                    //Different predicates may have the same file name
+                    if !used_filenames.insert(filename.clone()) {
+                        filename = format!("{}_{}", filename, used_filenames.len());
+                        used_filenames.insert(filename.clone());
+                    }
+                    //One part is read and written at a time, so that the store is not held in memory
+                    for i in 0..tt.len() {
                         let filename = format!("{filename}_part_{i}.parquet");
-                        let mut file_path = file_path.clone();
-                        file_path.push(filename);
-                        dfs_to_write.push((df, file_path));
+                        let mut file_path = path_buf.clone();
+                        file_path.push(&filename);
+                        let mut df = if let Some(dfs) = &tt.dfs {
+                            dfs.get(i).unwrap().clone()
+                        } else {
+                            read_parquet(tt.df_paths.as_ref().unwrap().get(i).unwrap())?
+                                .collect()
+                                .map_err(|x| MappingError::ReadParquetError(x))?
+                        };
+                        write_parquet(&mut df, file_path.as_path())?;
+                        index_files.push(filename);
+                        index_predicates.push(property.clone());
+                        index_object_types.push(rdf_node_type_tag(rdf_node_type));
+                        index_graphs.push(graph.cloned());
                     }
                 }
-
             }
         }
 
-        let results:Vec<Result<(), MappingError>> = dfs_to_write.par_drain(..).map(|(df, file_path)|write_parquet(df, file_path.as_path())).collect();
-        for r in results {
-            r?;
-        }
+        let mut index = DataFrame::new(vec![
+            Series::new("file", index_files),
+            Series::new("predicate", index_predicates),
+            Series::new("object_type", index_object_types),
+            Series::new("graph", index_graphs),
+        ])
+        .unwrap();
+        write_parquet(&mut index, path_buf.join(NATIVE_PARQUET_INDEX).as_path())?;
 
         debug!("Writing native parquet took {} seconds", now.elapsed().as_secs_f64());
         Ok(())
     }
+
+    /// Reads the triples written by write_native_parquet into a new triplestore, which keeps them in memory.
+    pub fn from_native_parquet(path: &Path) -> Result<Triplestore, MappingError> {
+        let now = Instant::now();
+        let index_path = path.join(NATIVE_PARQUET_INDEX);
+        if !index_path.exists() {
+            return Err(MappingError::PathDoesNotExist(
+                index_path.to_str().unwrap().to_string(),
+            ));
+        }
+        let index = read_parquet(&index_path.to_str().unwrap().to_string())?
+            .collect()
+            .map_err(|x| MappingError::ReadParquetError(x))?;
+        let files = index.column("file").unwrap().utf8().unwrap();
+        let predicates = index.column("predicate").unwrap().utf8().unwrap();
+        let object_types = index.column("object_type").unwrap().utf8().unwrap();
+        //Folders written before graphs were stored only have the default graph
+        let graphs: Vec<Option<&str>> = if let Ok(graphs) = index.column("graph") {
+            graphs.utf8().unwrap().into_iter().collect()
+        } else {
+            vec![None; index.height()]
+        };
+
+        let mut triplestore = Triplestore::new(None);
+        let call_uuid = Uuid::new_v4().to_string();
+        for (((file, predicate), object_type), graph) in files
+            .into_no_null_iter()
+            .zip(predicates.into_no_null_iter())
+            .zip(object_types.into_no_null_iter())
+            .zip(graphs.into_iter())
+        {
+            let file_path = path.join(file).to_str().unwrap().to_string();
+            let df = read_parquet(&file_path)?
+                .collect()
+                .map_err(|x| MappingError::ReadParquetError(x))?;
+            let tt = triplestore
+                .graph_df_map_mut(&graph.map(|g| g.to_string()))
+                .entry(predicate.to_string())
+                .or_default()
+                .entry(rdf_node_type_from_tag(object_type))
+                .or_insert_with(|| TripleTable {
+                    dfs: Some(vec![]),
+                    df_paths: None,
+                    unique: true,
+                    call_uuid: call_uuid.clone(),
+                    tmp_df: None,
+                    object_index: None,
+                    chunk_metadata: vec![],
+                    unique_chunks: 0,
+                    key_index: None,
+                    added_sequence: 0,
+                    generation: Uuid::new_v4().to_string(),
+                });
+            tt.dfs.as_mut().unwrap().push(df);
+        }
+        let default_graph = std::iter::once(&mut triplestore.df_map);
+        for df_map in default_graph.chain(triplestore.named_graph_df_maps.values_mut()) {
+            for tt in df_map.values_mut().flat_map(|m| m.values_mut()) {
+                tt.refresh_chunk_metadata()?;
+                tt.unique_chunks = tt.len();
+            }
+        }
+        debug!("Reading native parquet took {} seconds", now.elapsed().as_secs_f64());
+        Ok(triplestore)
+    }
 }
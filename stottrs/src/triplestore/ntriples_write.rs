@@ -21,18 +21,125 @@
 // SOFTWARE.
 use super::Triplestore;
 use crate::triplestore::conversion::convert_to_string;
-use crate::triplestore::TripleType;
+use crate::triplestore::custom_datatypes::{format_objects, formatter_for, CustomDatatype, LexicalFormatter};
+use crate::triplestore::{TripleTable, TripleType, LANGUAGE_TAG_COLUMN};
 use oxrdf::NamedNode;
 use polars::export::rayon::iter::{IntoParallelIterator, ParallelIterator};
 use polars::export::rayon::prelude::ParallelExtend;
-use polars::prelude::{AnyValue, DataFrame, Series};
+use polars::prelude::{col, AnyValue, DataFrame, DataType, IntoLazy, Series};
 use polars::series::SeriesIter;
 use polars_core::POOL;
 use polars_utils::contention_pool::LowContentionPool;
+use std::collections::{HashMap, VecDeque};
 use std::io::Write;
 use crate::mapping::errors::MappingError;
 use crate::mapping::RDFNodeType;
 use crate::triplestore::parquet::{read_parquet};
+#[cfg(feature = "gzip")]
+use flate2::write::GzEncoder;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    None,
+    #[cfg(feature = "gzip")]
+    Gzip,
+}
+
+#[derive(Clone, Debug)]
+pub struct NTriplesWriteOptions {
+    /// Number of rows each thread formats at a time, which bounds the memory used for buffers.
+    pub chunk_size: usize,
+    pub compression: Compression,
+}
+
+impl Default for NTriplesWriteOptions {
+    fn default() -> Self {
+        NTriplesWriteOptions {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            compression: Compression::None,
+        }
+    }
+}
+
+/// Produces the N-Triples lines of the default graph, reading one chunk of triples at a time.
+pub struct NTriplesLines<'a> {
+    tables: Vec<(&'a String, &'a RDFNodeType, &'a TripleTable)>,
+    custom_datatypes: &'a HashMap<String, CustomDatatype>,
+    chunk_size: usize,
+    table_idx: usize,
+    chunk_idx: usize,
+    current_df: Option<DataFrame>,
+    offset: usize,
+    lines: VecDeque<String>,
+}
+
+impl<'a> Iterator for NTriplesLines<'a> {
+    type Item = Result<String, MappingError>;
+
+    fn next(&mut self) -> Option<Result<String, MappingError>> {
+        match self.next_line() {
+            Ok(line) => line.map(Ok),
+            Err(e) => {
+                //No more lines are produced after an error
+                self.table_idx = self.tables.len();
+                self.current_df = None;
+                self.lines.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> NTriplesLines<'a> {
+    fn next_line(&mut self) -> Result<Option<String>, MappingError> {
+        while self.lines.is_empty() {
+            if let Some(df) = &self.current_df {
+                if self.offset < df.height() {
+                    let slice = df.slice(self.offset as i64, self.chunk_size);
+                    self.offset += self.chunk_size;
+                    let (property, rdf_node_type, _) = self.tables[self.table_idx];
+                    let formatter = formatter_for(self.custom_datatypes, rdf_node_type);
+                    let slice = format_objects(&slice, formatter).unwrap_or(slice);
+                    let mut buffer = vec![];
+                    write_ntriples_for_slice(&slice, property, rdf_node_type, &mut buffer);
+                    self.lines.extend(
+                        String::from_utf8(buffer)
+                            .unwrap()
+                            .lines()
+                            .map(|l| l.to_string()),
+                    );
+                    continue;
+                }
+                self.current_df = None;
+                self.chunk_idx += 1;
+            }
+            let (_, _, tt) = if let Some(t) = self.tables.get(self.table_idx) {
+                t
+            } else {
+                return Ok(None);
+            };
+            if self.chunk_idx >= tt.len() {
+                self.table_idx += 1;
+                self.chunk_idx = 0;
+                continue;
+            }
+            //Chunks in the caching folder are only read when the lines before them have been consumed
+            let df = if let Some(dfs) = &tt.dfs {
+                dfs.get(self.chunk_idx).unwrap().clone()
+            } else {
+                let path = tt.df_paths.as_ref().unwrap().get(self.chunk_idx).unwrap();
+                read_parquet(path)?
+                    .collect()
+                    .map_err(|x| MappingError::ReadParquetError(x))?
+            };
+            self.current_df = Some(df);
+            self.offset = 0;
+        }
+        Ok(self.lines.pop_front())
+    }
+}
 
 /// Utility to write to `&mut Vec<u8>` buffer
 struct StringWrap<'a>(pub &'a mut Vec<u8>);
@@ -45,61 +152,271 @@ impl<'a> std::fmt::Write for StringWrap<'a> {
 }
 
 impl Triplestore {
+    /// Stores with named graphs are rejected, as N-Triples can not represent them.
+    pub fn write_n_triples_with_options<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        options: &NTriplesWriteOptions,
+    ) -> Result<(), MappingError> {
+        match options.compression {
+            Compression::None => self.write_n_triples_all_dfs(writer, options.chunk_size),
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+                self.write_n_triples_all_dfs(&mut encoder, options.chunk_size)?;
+                encoder
+                    .finish()
+                    .map_err(|x| MappingError::WriteNTriplesError(x))?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns an iterator over the N-Triples lines, so that large graphs can be streamed without buffering them.
+    /// The iterator gives an error and stops if a chunk of triples in the caching folder can not be read.
+    pub fn n_triples_lines(&mut self, chunk_size: usize) -> Result<NTriplesLines<'_>, MappingError> {
+        self.check_no_named_graphs("N-Triples")?;
+        self.deduplicate()?;
+        let mut tables: Vec<(&String, &RDFNodeType, &TripleTable)> = self
+            .df_map
+            .iter()
+            .flat_map(|(property, map)| map.iter().map(move |(dt, tt)| (property, dt, tt)))
+            .collect();
+        tables.sort_by(|(p1, _, _), (p2, _, _)| p1.cmp(p2));
+        Ok(NTriplesLines {
+            tables,
+            custom_datatypes: &self.custom_datatypes,
+            chunk_size: chunk_size.max(1),
+            table_idx: 0,
+            chunk_idx: 0,
+            current_df: None,
+            offset: 0,
+            lines: VecDeque::new(),
+        })
+    }
+
     pub(crate) fn write_n_triples_all_dfs<W: Write + ?Sized>(
         &mut self,
         writer: &mut W,
         chunk_size: usize,
     ) -> Result<(), MappingError> {
+        self.check_no_named_graphs("N-Triples")?;
         self.deduplicate()?;
-        let n_threads = POOL.current_num_threads();
-        let mut any_value_iter_pool = LowContentionPool::<Vec<_>>::new(n_threads);
-        let mut write_buffer_pool = LowContentionPool::<Vec<_>>::new(n_threads);
-
-        for (property, map) in &mut self.df_map {
-            for (rdf_node_type, tt) in map {
-                let dt = if let RDFNodeType::Literal(dt) = rdf_node_type {Some(dt.clone())} else {None};
-                let triple_type = rdf_node_type.find_triple_type();
-                if let Some(dfs) = &mut tt.dfs {
-                    for df in dfs {
-                        df.as_single_chunk_par();
-                        write_ntriples_for_df(
-                            df,
-                            property,
-                            &dt,
-                            writer,
-                            chunk_size,
-                            triple_type.clone(),
-                            n_threads,
-                            &mut any_value_iter_pool,
-                            &mut write_buffer_pool,
-                        )?;
-                    }
-                } else if let Some(paths) = &tt.df_paths {
-                    for p in paths {
-                        let df = read_parquet(p)?.collect().unwrap();
-                        write_ntriples_for_df(
-                            &df,
-                            property,
-                            &dt,
-                            writer,
-                            chunk_size,
-                            triple_type.clone(),
-                            n_threads,
-                            &mut any_value_iter_pool,
-                            &mut write_buffer_pool,
-                        )?;
-                    }
-                }
-            }
+        write_ntriples_for_df_map(&mut self.df_map, &self.custom_datatypes, None, writer, chunk_size)
+    }
+
+    pub(crate) fn write_n_quads_all_dfs<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        chunk_size: usize,
+    ) -> Result<(), MappingError> {
+        self.deduplicate()?;
+        write_ntriples_for_df_map(&mut self.df_map, &self.custom_datatypes, None, writer, chunk_size)?;
+        let mut graphs: Vec<&String> = self.named_graph_df_maps.keys().collect();
+        graphs.sort();
+        let graphs: Vec<String> = graphs.into_iter().cloned().collect();
+        for g in graphs {
+            let map = self.named_graph_df_maps.get_mut(&g).unwrap();
+            write_ntriples_for_df_map(map, &self.custom_datatypes, Some(&g), writer, chunk_size)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_trig_all_dfs<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        chunk_size: usize,
+    ) -> Result<(), MappingError> {
+        self.deduplicate()?;
+        write_ntriples_for_df_map(&mut self.df_map, &self.custom_datatypes, None, writer, chunk_size)?;
+        let mut graphs: Vec<&String> = self.named_graph_df_maps.keys().collect();
+        graphs.sort();
+        let graphs: Vec<String> = graphs.into_iter().cloned().collect();
+        for g in graphs {
+            writeln!(writer, "<{}> {{", g).map_err(|x| MappingError::WriteNTriplesError(x))?;
+            let map = self.named_graph_df_maps.get_mut(&g).unwrap();
+            write_ntriples_for_df_map(map, &self.custom_datatypes, None, writer, chunk_size)?;
+            writeln!(writer, "}}").map_err(|x| MappingError::WriteNTriplesError(x))?;
         }
         Ok(())
     }
 }
 
+//Writes a DataFrame with subject, verb and object columns, the verb may vary between rows
+pub(crate) fn write_ntriples_for_triples_df<W: Write + ?Sized>(
+    df: DataFrame,
+    rdf_node_type: &RDFNodeType,
+    formatter: Option<&LexicalFormatter>,
+    writer: &mut W,
+    chunk_size: usize,
+) -> Result<(), MappingError> {
+    if df.height() == 0 {
+        return Ok(());
+    }
+    let df = format_objects(&df, formatter).unwrap_or(df);
+    let n_threads = POOL.current_num_threads();
+    let mut any_value_iter_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+    let mut write_buffer_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+    let dt = if let RDFNodeType::Literal(dt) = rdf_node_type {Some(dt.clone())} else {None};
+    let triple_type = rdf_node_type.find_triple_type();
+    let mut lf = df
+        .lazy()
+        .with_column(col("subject").cast(DataType::Utf8))
+        .with_column(col("verb").cast(DataType::Utf8));
+    if triple_type != TripleType::NonStringProperty {
+        lf = lf.with_column(col("object").cast(DataType::Utf8));
+    }
+    let df = lf.collect().unwrap();
+    for part in df.partition_by(["verb"]).unwrap() {
+        let verb = if let AnyValue::Utf8(v) = part.column("verb").unwrap().get(0) {
+            v.to_string()
+        } else {
+            panic!()
+        };
+        let mut part = part.select(["subject", "object"]).unwrap();
+        if triple_type == TripleType::StringProperty {
+            part.with_column(Series::full_null(LANGUAGE_TAG_COLUMN, part.height(), &DataType::Utf8))
+                .unwrap();
+        }
+        write_ntriples_for_df(
+            &part,
+            &verb,
+            &dt,
+            None,
+            writer,
+            chunk_size,
+            triple_type.clone(),
+            n_threads,
+            &mut any_value_iter_pool,
+            &mut write_buffer_pool,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_ntriples_for_slice(
+    df: &DataFrame,
+    property: &String,
+    rdf_node_type: &RDFNodeType,
+    buffer: &mut Vec<u8>,
+) {
+    let n_threads = POOL.current_num_threads();
+    let mut any_value_iter_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+    let mut write_buffer_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+    let dt = if let RDFNodeType::Literal(dt) = rdf_node_type {Some(dt.clone())} else {None};
+    //Writing to a Vec<u8> can not fail
+    write_ntriples_for_df(
+        df,
+        property,
+        &dt,
+        None,
+        buffer,
+        df.height().max(1),
+        rdf_node_type.find_triple_type(),
+        1,
+        &mut any_value_iter_pool,
+        &mut write_buffer_pool,
+    )
+    .unwrap();
+}
+
+//The graph is only written when it is given, i.e. when writing N-Quads
+fn write_ntriples_for_df_map<W: Write + ?Sized>(
+    df_map: &mut HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    custom_datatypes: &HashMap<String, CustomDatatype>,
+    graph: Option<&str>,
+    writer: &mut W,
+    chunk_size: usize,
+) -> Result<(), MappingError> {
+    let n_threads = POOL.current_num_threads();
+    let mut any_value_iter_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+    let mut write_buffer_pool = LowContentionPool::<Vec<_>>::new(n_threads);
+
+    //Tables are written in a fixed order, so that the same triples are written the same way
+    let mut tables: Vec<(&String, &RDFNodeType, &mut TripleTable)> = df_map
+        .iter_mut()
+        .flat_map(|(property, map)| map.iter_mut().map(move |(t, tt)| (property, t, tt)))
+        .collect();
+    tables.sort_by_cached_key(|(property, rdf_node_type, _)| {
+        (property.to_string(), format!("{:?}", rdf_node_type))
+    });
+    for (property, rdf_node_type, tt) in tables {
+        write_ntriples_for_table(
+            tt,
+            property,
+            rdf_node_type,
+            formatter_for(custom_datatypes, rdf_node_type),
+            0,
+            graph,
+            writer,
+            chunk_size,
+            n_threads,
+            &mut any_value_iter_pool,
+            &mut write_buffer_pool,
+        )?;
+    }
+    Ok(())
+}
+
+//Writes the chunks of the table starting at from_chunk
+pub(crate) fn write_ntriples_for_table<W: Write + ?Sized>(
+    tt: &mut TripleTable,
+    property: &String,
+    rdf_node_type: &RDFNodeType,
+    formatter: Option<&LexicalFormatter>,
+    from_chunk: usize,
+    graph: Option<&str>,
+    writer: &mut W,
+    chunk_size: usize,
+    n_threads: usize,
+    any_value_iter_pool: &mut LowContentionPool<Vec<SeriesIter>>,
+    write_buffer_pool: &mut LowContentionPool<Vec<u8>>,
+) -> Result<(), MappingError> {
+    let dt = if let RDFNodeType::Literal(dt) = rdf_node_type {Some(dt.clone())} else {None};
+    let triple_type = rdf_node_type.find_triple_type();
+    if let Some(dfs) = &mut tt.dfs {
+        for df in dfs.iter_mut().skip(from_chunk) {
+            df.as_single_chunk_par();
+            let formatted = format_objects(df, formatter);
+            write_ntriples_for_df(
+                formatted.as_ref().unwrap_or(&*df),
+                property,
+                &dt,
+                graph,
+                writer,
+                chunk_size,
+                triple_type.clone(),
+                n_threads,
+                any_value_iter_pool,
+                write_buffer_pool,
+            )?;
+        }
+    } else if let Some(paths) = &tt.df_paths {
+        for p in paths.iter().skip(from_chunk) {
+            let df = read_parquet(p)?.collect().unwrap();
+            let df = format_objects(&df, formatter).unwrap_or(df);
+            write_ntriples_for_df(
+                &df,
+                property,
+                &dt,
+                graph,
+                writer,
+                chunk_size,
+                triple_type.clone(),
+                n_threads,
+                any_value_iter_pool,
+                write_buffer_pool,
+            )?;
+        }
+    }
+    Ok(())
+}
+
     fn write_ntriples_for_df<W: Write + ?Sized>(
         df: &DataFrame,
         verb: &String,
         dt: &Option<NamedNode>,
+        graph: Option<&str>,
         writer: &mut W,
         chunk_size: usize,
         triple_type: TripleType,
@@ -169,17 +486,18 @@ impl Triplestore {
                     if !any_values.is_empty() {
                         match triple_type {
                             TripleType::ObjectProperty => {
-                                write_object_property_triple(&mut write_buffer, any_values, verb);
+                                write_object_property_triple(&mut write_buffer, any_values, verb, graph);
                             }
                             TripleType::StringProperty => {
-                                write_string_property_triple(&mut write_buffer, any_values, verb);
+                                write_string_property_triple(&mut write_buffer, any_values, verb, graph);
                             }
                             TripleType::NonStringProperty => {
                                 write_non_string_property_triple(
                                     &mut write_buffer,
                                     dt_str.unwrap(),
                                     any_values,
-                                    verb
+                                    verb,
+                                    graph,
                                 );
                             }
                         }
@@ -196,7 +514,7 @@ impl Triplestore {
             result_buf.par_extend(par_iter);
 
             for mut buf in result_buf.drain(..) {
-                let _ = writer.write(&buf).map_err(|x| MappingError::WriteNTriplesError(x));
+                writer.write_all(&buf).map_err(|x| MappingError::WriteNTriplesError(x))?;
                 buf.clear();
                 write_buffer_pool.set(buf);
             }
@@ -206,7 +524,7 @@ impl Triplestore {
         Ok(())
     }
 
-fn write_string_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>, v:&str) {
+fn write_string_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>, v:&str, g: Option<&str>) {
     let lang_opt = if let AnyValue::Utf8(lang) = any_values.pop().unwrap() {
         Some(lang)
     } else {
@@ -222,18 +540,17 @@ fn write_string_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>,
     } else {
         panic!()
     };
-    write!(f, "<{}>", s).unwrap();
+    write_node(f, s);
     write!(f, " <{}>", v).unwrap();
     write!(f, " \"{}\"", lex).unwrap();
     if let Some(lang) = lang_opt {
-        writeln!(f, "@{} .", lang).unwrap();
-    } else {
-        writeln!(f, " .").unwrap();
+        write!(f, "@{}", lang).unwrap();
     }
+    write_statement_end(f, g);
 }
 
 //Assumes that the data has been bulk-converted
-fn write_non_string_property_triple(f: &mut Vec<u8>, dt: &str, mut any_values: Vec<AnyValue>, v:&str) {
+fn write_non_string_property_triple(f: &mut Vec<u8>, dt: &str, mut any_values: Vec<AnyValue>, v:&str, g: Option<&str>) {
     let lex = if let AnyValue::Utf8(lex) = any_values.pop().unwrap() {
         lex
     } else {
@@ -244,13 +561,14 @@ fn write_non_string_property_triple(f: &mut Vec<u8>, dt: &str, mut any_values: V
     } else {
         panic!()
     };
-    write!(f, "<{}>", s).unwrap();
+    write_node(f, s);
     write!(f, " <{}>", v).unwrap();
     write!(f, " \"{}\"", lex).unwrap();
-    writeln!(f, "^^<{}> .", dt).unwrap();
+    write!(f, "^^<{}>", dt).unwrap();
+    write_statement_end(f, g);
 }
 
-fn write_object_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>, v:&str) {
+fn write_object_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>, v:&str, g: Option<&str>) {
     let o = if let AnyValue::Utf8(o) = any_values.pop().unwrap() {
         o
     } else {
@@ -261,7 +579,25 @@ fn write_object_property_triple(f: &mut Vec<u8>, mut any_values: Vec<AnyValue>,
     } else {
         panic!()
     };
-    write!(f, "<{}>", s).unwrap();
+    write_node(f, s);
     write!(f, " <{}>", v).unwrap();
-    writeln!(f, " <{}> .", o).unwrap();
+    write!(f, " ").unwrap();
+    write_node(f, o);
+    write_statement_end(f, g);
+}
+
+//Blank nodes are stored with their "_:" prefix
+fn write_node(f: &mut Vec<u8>, n: &str) {
+    if n.starts_with("_:") {
+        write!(f, "{}", n).unwrap();
+    } else {
+        write!(f, "<{}>", n).unwrap();
+    }
+}
+
+fn write_statement_end(f: &mut Vec<u8>, g: Option<&str>) {
+    if let Some(g) = g {
+        write!(f, " <{}>", g).unwrap();
+    }
+    writeln!(f, " .").unwrap();
 }
@@ -0,0 +1,101 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::RDFNodeType;
+use log::debug;
+use oxrdf::vocab::xsd;
+use polars::prelude::{IntoLazy, LazyFrame};
+use polars_core::prelude::{DataType, IdxCa, IdxSize};
+use std::collections::HashMap;
+use std::time::Instant;
+
+impl Triplestore {
+    /// Marks the predicate for object indexing.
+    /// The index is built the next time the store is queried, and dropped whenever triples are added.
+    pub fn add_object_index(&mut self, predicate: &str) {
+        self.object_indexed_predicates.insert(predicate.to_string());
+    }
+
+    pub fn remove_object_index(&mut self, predicate: &str) {
+        self.object_indexed_predicates.remove(predicate);
+        if let Some(m) = self.df_map.get_mut(predicate) {
+            for (_, tt) in m {
+                tt.object_index = None;
+            }
+        }
+    }
+
+    pub(crate) fn build_object_indexes(&mut self) {
+        let now = Instant::now();
+        for predicate in &self.object_indexed_predicates {
+            if let Some(m) = self.df_map.get_mut(predicate) {
+                for (rdf_node_type, tt) in m {
+                    if tt.object_index.is_none() && is_indexable(rdf_node_type) {
+                        tt.build_object_index();
+                    }
+                }
+            }
+        }
+        debug!(
+            "Building object indexes took {} seconds",
+            now.elapsed().as_secs_f64()
+        );
+    }
+}
+
+impl TripleTable {
+    fn build_object_index(&mut self) {
+        //Indexes are only kept for in-memory tables, reading cached parquet files would defeat the purpose.
+        if let Some(dfs) = &self.dfs {
+            if dfs.is_empty() {
+                return;
+            }
+            //Row indices are kept per chunk, so that lookups do not have to concatenate the chunks
+            let mut index: HashMap<String, Vec<(usize, Vec<IdxSize>)>> = HashMap::new();
+            for (chunk, df) in dfs.iter().enumerate() {
+                let objects = df.column("object").unwrap();
+                if objects.dtype() != &DataType::Utf8 {
+                    return;
+                }
+                for (i, o) in objects.utf8().unwrap().into_iter().enumerate() {
+                    if let Some(o) = o {
+                        if !index.contains_key(o) {
+                            index.insert(o.to_string(), vec![]);
+                        }
+                        let chunks = index.get_mut(o).unwrap();
+                        match chunks.last_mut() {
+                            Some((c, v)) if *c == chunk => v.push(i as IdxSize),
+                            _ => chunks.push((chunk, vec![i as IdxSize])),
+                        }
+                    }
+                }
+            }
+            self.object_index = Some(index);
+        }
+    }
+
+    /// Uses the object index, if it exists, to look up the rows where the object is the given value.
+    pub(crate) fn get_lazy_frames_with_object(&self, object: &str) -> Option<Vec<LazyFrame>> {
+        if let (Some(index), Some(dfs)) = (&self.object_index, &self.dfs) {
+            let mut lfs = vec![];
+            if let Some(chunks) = index.get(object) {
+                for (chunk, idx) in chunks {
+                    let idx_ca = IdxCa::from_vec("idx", idx.clone());
+                    lfs.push(dfs.get(*chunk).unwrap().take(&idx_ca).unwrap().lazy());
+                }
+            }
+            if lfs.is_empty() {
+                lfs.push(dfs.get(0).unwrap().head(Some(0)).lazy());
+            }
+            Some(lfs)
+        } else {
+            None
+        }
+    }
+}
+
+fn is_indexable(rdf_node_type: &RDFNodeType) -> bool {
+    match rdf_node_type {
+        RDFNodeType::IRI => true,
+        RDFNodeType::Literal(l) => l.as_ref() == xsd::STRING,
+        _ => false,
+    }
+}
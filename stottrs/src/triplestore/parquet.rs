@@ -1,13 +1,18 @@
+use crate::mapping::chunking::ChunkPolicy;
 use crate::mapping::errors::MappingError;
+use crate::triplestore::recovery::InProgressManifest;
 use nom::InputIter;
-use polars::prelude::{LazyFrame, ParallelStrategy, ParquetWriter, ScanArgsParquet};
+use polars::prelude::{col, lit, LazyFrame, ParallelStrategy, ParquetWriter, ScanArgsParquet};
 use polars_core::frame::DataFrame;
-use std::cmp::min;
+use polars_core::prelude::DataType;
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 const PARQUET_DF_SIZE: usize = 50_000_000;
+//Chunks in the caching folder are sorted by subject and merged when deduplicating, so the order in which triples
+//were added is kept in this column, which has the sequence number of the call that added the triples
+pub(crate) const ADDED_SEQUENCE_COLUMN: &str = "added_sequence";
 
 pub(crate) fn property_to_filename(property_name: &str) -> String {
     property_name
@@ -19,14 +24,43 @@ pub(crate) fn property_to_filename(property_name: &str) -> String {
 pub(crate) fn write_parquet(df: &mut DataFrame, file_path: &Path) -> Result<(), MappingError> {
     let file = File::create(file_path).map_err(|x| MappingError::FileCreateIOError(x))?;
     let mut writer = ParquetWriter::new(file);
-    writer = writer.with_row_group_size(Some(1_000));
+    writer = writer
+        .with_row_group_size(Some(1_000))
+        .with_statistics(true);
     writer
         .finish(df)
         .map_err(|x| MappingError::WriteParquetError(x))?;
     Ok(())
 }
 
+//Chunks in the caching folder are sorted by subject, so that the statistics of their row groups have narrow subject ranges,
+//and filters on subjects pushed down into scans of the files skip most row groups
+pub(crate) fn write_sorted_parquet(df: &mut DataFrame, file_path: &Path) -> Result<(), MappingError> {
+    let mut sorted = sort_by_subject(df);
+    write_parquet(&mut sorted, file_path)
+}
+
+fn sort_by_subject(df: &DataFrame) -> DataFrame {
+    df.sort(["subject"], false).unwrap()
+}
+
 pub(crate) fn read_parquet(file_path: &String) -> Result<LazyFrame, MappingError> {
+    Ok(scan_parquet(file_path)?.select([col("*").exclude([ADDED_SEQUENCE_COLUMN])]))
+}
+
+//Used when the chunks are written back to the caching folder, so that the sequence numbers are kept.
+//Files without the column, e.g. written before it was introduced, count as added first.
+pub(crate) fn read_parquet_with_added_sequence(file_path: &String) -> Result<LazyFrame, MappingError> {
+    let lf = scan_parquet(file_path)?;
+    let schema = lf.schema().map_err(|x| MappingError::ReadParquetError(x))?;
+    if schema.get(ADDED_SEQUENCE_COLUMN).is_some() {
+        Ok(lf)
+    } else {
+        Ok(lf.with_column(lit(0).cast(DataType::UInt64).alias(ADDED_SEQUENCE_COLUMN)))
+    }
+}
+
+fn scan_parquet(file_path: &String) -> Result<LazyFrame, MappingError> {
     LazyFrame::scan_parquet(
         Path::new(file_path),
         ScanArgsParquet {
@@ -46,22 +80,21 @@ pub(crate) fn split_write_df(
         df: DataFrame,
         predicate: &str,
     ) -> Result<Vec<String>, MappingError> {
-        let n_of_size = (df.estimated_size() / PARQUET_DF_SIZE) + 1;
-        let chunk_size = df.height() / n_of_size;
-        let mut offset = 0i64;
-        let mut paths = vec![];
-        loop {
-            let to_row = min(df.height(), offset as usize + chunk_size);
-            let mut df_slice = df.slice_par(offset, to_row);
-            let file_name = format!("{}_{}.parquet", predicate, Uuid::new_v4().to_string());
-            let path_buf: PathBuf = [caching_folder, &file_name].iter().collect();
-            let path = path_buf.as_path();
-            write_parquet(&mut df_slice, path)?;
-            paths.push(path.to_str().unwrap().to_string());
-            offset += chunk_size as i64;
-            if offset >= df.height() as i64 {
-                break;
-            }
+        //The slices of a sorted frame have subject ranges that do not overlap, so whole files are skipped as well
+        let df = sort_by_subject(&df);
+        let mut df_slices = ChunkPolicy::Bytes(PARQUET_DF_SIZE).split(&df);
+        let paths: Vec<String> = df_slices
+            .iter()
+            .map(|_| {
+                let file_name = format!("{}_{}.parquet", predicate, Uuid::new_v4().to_string());
+                let path_buf: PathBuf = [caching_folder, &file_name].iter().collect();
+                path_buf.to_str().unwrap().to_string()
+            })
+            .collect();
+        let in_progress = InProgressManifest::create(caching_folder, &paths)?;
+        for (df_slice, path) in df_slices.iter_mut().zip(&paths) {
+            write_parquet(df_slice, Path::new(path))?;
         }
+        in_progress.finish()?;
         Ok(paths)
     }
\ No newline at end of file
@@ -0,0 +1,78 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::RDFNodeType;
+use oxrdf::NamedNode;
+use std::collections::HashMap;
+
+impl Triplestore {
+    /// Splits the store into one store per group of predicates, e.g. to export each domain area in a separate process.
+    /// A predicate listed in several groups is placed in the first of them.
+    /// Predicates in no group are placed in an additional last store, if there are any.
+    pub fn partition_by_predicates(mut self, groups: Vec<Vec<NamedNode>>) -> Vec<Triplestore> {
+        let mut partitions = vec![];
+        for group in groups {
+            let predicates: Vec<String> = group.into_iter().map(|nn| nn.as_str().to_string()).collect();
+            partitions.push(self.split_off_predicates(&predicates));
+        }
+        let is_empty = self.df_map.is_empty()
+            && self.named_graph_df_maps.values().all(|m| m.is_empty());
+        if !is_empty {
+            self.named_graph_df_maps.retain(|_, m| !m.is_empty());
+            partitions.push(self);
+        }
+        partitions
+    }
+
+    fn split_off_predicates(&mut self, predicates: &[String]) -> Triplestore {
+        let mut partition = Triplestore::new(self.caching_folder.clone());
+        partition.deduplicated = self.deduplicated;
+        partition.harmonize_dtypes = self.harmonize_dtypes;
+        partition.collapse_language_tags = self.collapse_language_tags;
+        partition.deduplication_strategy = self.deduplication_strategy;
+        partition.iri_validation = self.iri_validation.clone();
+        partition.custom_datatypes = self.custom_datatypes.clone();
+        for predicate in predicates {
+            if let Some(policy) = self.retention_policies.get(predicate) {
+                partition
+                    .retention_policies
+                    .insert(predicate.clone(), policy.clone());
+            }
+            if let Some(validation) = self.predicate_iri_validations.get(predicate) {
+                partition
+                    .predicate_iri_validations
+                    .insert(predicate.clone(), validation.clone());
+            }
+            if self.object_indexed_predicates.contains(predicate) {
+                partition.object_indexed_predicates.insert(predicate.clone());
+            }
+        }
+        partition.join_hints = self
+            .join_hints
+            .iter()
+            .filter(|h| {
+                predicates.contains(&h.left_predicate) && predicates.contains(&h.right_predicate)
+            })
+            .cloned()
+            .collect();
+        partition.df_map = remove_predicates(&mut self.df_map, predicates);
+        for (graph, map) in &mut self.named_graph_df_maps {
+            let graph_map = remove_predicates(map, predicates);
+            if !graph_map.is_empty() {
+                partition.named_graph_df_maps.insert(graph.clone(), graph_map);
+            }
+        }
+        partition
+    }
+}
+
+fn remove_predicates(
+    df_map: &mut HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    predicates: &[String],
+) -> HashMap<String, HashMap<RDFNodeType, TripleTable>> {
+    let mut removed = HashMap::new();
+    for predicate in predicates {
+        if let Some(m) = df_map.remove(predicate) {
+            removed.insert(predicate.clone(), m);
+        }
+    }
+    removed
+}
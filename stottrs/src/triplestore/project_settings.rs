@@ -0,0 +1,168 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::triplestore::iri_validation::IriValidation;
+use crate::triplestore::parquet::{property_to_filename, write_sorted_parquet};
+use crate::triplestore::recovery::InProgressManifest;
+use crate::triplestore::retention::RetentionPolicy;
+use chrono::Duration;
+use std::fs::create_dir_all;
+use std::path::Path;
+use uuid::Uuid;
+
+impl Triplestore {
+    //Lines of the project options with the settings of the store, see Mapping::save_project.
+    //Custom datatypes have formatters and data types that can not be written, so stores with them are rejected.
+    pub(crate) fn project_settings(&self) -> Result<Vec<String>, MappingError> {
+        if !self.custom_datatypes.is_empty() {
+            return Err(MappingError::ProjectSettingNotSupported(
+                "custom datatypes".to_string(),
+            ));
+        }
+        let mut lines = vec![];
+        if let Some(caching_folder) = &self.caching_folder {
+            lines.push(format!("caching_folder\t{}", caching_folder));
+        }
+        let mut predicates: Vec<&String> = self.retention_policies.keys().collect();
+        predicates.sort();
+        for predicate in predicates {
+            match self.retention_policies.get(predicate).unwrap() {
+                RetentionPolicy::MaxRows(max_rows) => {
+                    lines.push(format!("retention\t{}\tmax_rows\t{}", predicate, max_rows));
+                }
+                RetentionPolicy::MaxAge {
+                    timestamp_predicate,
+                    max_age,
+                } => {
+                    lines.push(format!(
+                        "retention\t{}\tmax_age\t{}\t{}",
+                        predicate,
+                        timestamp_predicate,
+                        max_age.num_milliseconds()
+                    ));
+                }
+            }
+        }
+        if let Some(validation) = &self.iri_validation {
+            lines.push(format!("iri_validation\t{}", iri_validation_values(validation)));
+        }
+        let mut predicates: Vec<&String> = self.predicate_iri_validations.keys().collect();
+        predicates.sort();
+        for predicate in predicates {
+            let validation = self.predicate_iri_validations.get(predicate).unwrap();
+            lines.push(format!(
+                "predicate_iri_validation\t{}\t{}",
+                predicate,
+                iri_validation_values(validation)
+            ));
+        }
+        let mut graphs: Vec<&String> = self.tenant_quotas.keys().collect();
+        graphs.sort();
+        for graph in graphs {
+            lines.push(format!(
+                "tenant_quota\t{}\t{}",
+                graph,
+                self.tenant_quotas.get(graph).unwrap()
+            ));
+        }
+        Ok(lines)
+    }
+
+    //Returns None if the values are not a setting of the store. The caching folder is set with move_to_caching_folder.
+    pub(crate) fn read_project_setting(&mut self, values: &[&str]) -> Option<()> {
+        match values {
+            ["retention", predicate, "max_rows", max_rows] => {
+                self.set_retention_policy(predicate, RetentionPolicy::MaxRows(max_rows.parse().ok()?));
+            }
+            ["retention", predicate, "max_age", timestamp_predicate, max_age] => {
+                self.set_retention_policy(
+                    predicate,
+                    RetentionPolicy::MaxAge {
+                        timestamp_predicate: timestamp_predicate.to_string(),
+                        max_age: Duration::milliseconds(max_age.parse().ok()?),
+                    },
+                );
+            }
+            ["iri_validation", schemes, max_length] => {
+                self.set_iri_validation(Some(read_iri_validation(schemes, max_length)?));
+            }
+            ["predicate_iri_validation", predicate, schemes, max_length] => {
+                self.set_predicate_iri_validation(predicate, read_iri_validation(schemes, max_length)?);
+            }
+            ["tenant_quota", graph, max_triples] => {
+                self.tenant_quotas
+                    .insert(graph.to_string(), max_triples.parse().ok()?);
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    //Writes the triples held in memory to the caching folder, which is used for the triples added later
+    pub(crate) fn move_to_caching_folder(&mut self, caching_folder: &str) -> Result<(), MappingError> {
+        create_dir_all(caching_folder).map_err(|x| MappingError::FileCreateIOError(x))?;
+        let folder_path = Path::new(caching_folder);
+        let default_graph = std::iter::once(&mut self.df_map);
+        for df_map in default_graph.chain(self.named_graph_df_maps.values_mut()) {
+            for (predicate, map) in df_map.iter_mut() {
+                for tt in map.values_mut() {
+                    let dfs = if let Some(dfs) = &mut tt.dfs {
+                        dfs
+                    } else {
+                        continue;
+                    };
+                    let paths: Vec<String> = dfs
+                        .iter()
+                        .map(|_| {
+                            let file_name = format!(
+                                "{}_{}.parquet",
+                                property_to_filename(predicate),
+                                Uuid::new_v4()
+                            );
+                            folder_path.join(file_name).to_str().unwrap().to_string()
+                        })
+                        .collect();
+                    let in_progress = InProgressManifest::create(caching_folder, &paths)?;
+                    for (df, path) in dfs.iter_mut().zip(paths.iter()) {
+                        write_sorted_parquet(df, Path::new(path))?;
+                    }
+                    tt.dfs = None;
+                    tt.df_paths = Some(paths);
+                    tt.tmp_df = None;
+                    tt.object_index = None;
+                    in_progress.finish()?;
+                }
+            }
+        }
+        self.caching_folder = Some(caching_folder.to_string());
+        Ok(())
+    }
+}
+
+//Comma separated schemes and the maximum length, which is empty when there is none
+fn iri_validation_values(validation: &IriValidation) -> String {
+    format!(
+        "{}\t{}",
+        validation.schemes.join(","),
+        validation
+            .max_length
+            .map(|m| m.to_string())
+            .unwrap_or_default()
+    )
+}
+
+fn read_iri_validation(schemes: &str, max_length: &str) -> Option<IriValidation> {
+    let schemes = schemes
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect();
+    let max_length = if max_length.is_empty() {
+        None
+    } else {
+        Some(max_length.parse().ok()?)
+    };
+    Some(IriValidation {
+        schemes,
+        max_length,
+    })
+}
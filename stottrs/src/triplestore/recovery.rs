@@ -0,0 +1,88 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use std::fs::{create_dir_all, read_dir, read_to_string, remove_file, rename, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const MANIFEST_EXTENSION: &str = "inprogress";
+const QUARANTINE_FOLDER: &str = "quarantine";
+
+/// What is done with files that were being written when an expansion or deduplication was interrupted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecoveryAction {
+    Remove,
+    /// Moves the files to the quarantine subfolder of the caching folder, where they can be inspected.
+    Quarantine,
+}
+
+//Lists the parquet files that are about to be written to the caching folder.
+//The manifest is removed when the files belong to a table, so a manifest left in the folder means an interrupted write.
+pub(crate) struct InProgressManifest {
+    path: PathBuf,
+}
+
+impl InProgressManifest {
+    pub(crate) fn create(caching_folder: &str, file_paths: &[String]) -> Result<InProgressManifest, MappingError> {
+        let path: PathBuf = [
+            caching_folder,
+            &format!("{}.{}", Uuid::new_v4(), MANIFEST_EXTENSION),
+        ]
+        .iter()
+        .collect();
+        let mut file = File::create(&path).map_err(|x| MappingError::ManifestIOError(x))?;
+        for p in file_paths {
+            writeln!(file, "{}", p).map_err(|x| MappingError::ManifestIOError(x))?;
+        }
+        file.sync_all().map_err(|x| MappingError::ManifestIOError(x))?;
+        Ok(InProgressManifest { path })
+    }
+
+    pub(crate) fn finish(self) -> Result<(), MappingError> {
+        remove_file(&self.path).map_err(|x| MappingError::ManifestIOError(x))
+    }
+}
+
+impl Triplestore {
+    /// Removes or quarantines the files of writes to the caching folder that were interrupted, and returns their paths.
+    /// Should be called on startup, before a triplestore using the folder is created.
+    pub fn recover(caching_folder: &str, action: RecoveryAction) -> Result<Vec<String>, MappingError> {
+        if !Path::new(caching_folder).exists() {
+            return Ok(vec![]);
+        }
+        let entries = read_dir(caching_folder).map_err(|x| MappingError::ManifestIOError(x))?;
+        let mut manifests = vec![];
+        for entry in entries {
+            let path = entry.map_err(|x| MappingError::ManifestIOError(x))?.path();
+            if path.extension().map_or(false, |e| e == MANIFEST_EXTENSION) {
+                manifests.push(path);
+            }
+        }
+        manifests.sort();
+
+        let mut recovered = vec![];
+        for manifest in manifests {
+            let contents = read_to_string(&manifest).map_err(|x| MappingError::ManifestIOError(x))?;
+            for file_path in contents.lines().filter(|l| !l.is_empty()) {
+                let path = Path::new(file_path);
+                if !path.exists() {
+                    continue;
+                }
+                match action {
+                    RecoveryAction::Remove => {
+                        remove_file(path).map_err(|x| MappingError::RemoveParquetFileError(x))?;
+                    }
+                    RecoveryAction::Quarantine => {
+                        let quarantine: PathBuf = [caching_folder, QUARANTINE_FOLDER].iter().collect();
+                        create_dir_all(&quarantine).map_err(|x| MappingError::FolderCreateIOError(x))?;
+                        rename(path, quarantine.join(path.file_name().unwrap()))
+                            .map_err(|x| MappingError::ManifestIOError(x))?;
+                    }
+                }
+                recovered.push(file_path.to_string());
+            }
+            remove_file(&manifest).map_err(|x| MappingError::ManifestIOError(x))?;
+        }
+        Ok(recovered)
+    }
+}
@@ -0,0 +1,289 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::parquet::{
+    property_to_filename, read_parquet_with_added_sequence, split_write_df, ADDED_SEQUENCE_COLUMN,
+};
+use chrono::{Duration, Utc};
+use log::debug;
+use polars::prelude::{col, concat, lit, IntoLazy, LazyFrame};
+use polars_core::frame::{DataFrame, UniqueKeepStrategy};
+use polars_core::prelude::{DataType, JoinType, NamedFrom, TimeUnit};
+use polars_core::series::Series;
+use polars_core::utils::concat_df;
+use std::collections::HashMap;
+use std::fs::remove_file;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub enum RetentionPolicy {
+    /// Keep at most this many triples of the predicate, counting the triples of all object types.
+    /// The most recently added triples are kept. The triples of an object type held in memory count as added by the
+    /// last call adding to it, and of the triples added by the same call it is not specified which are kept when
+    /// there is a caching folder.
+    MaxRows(usize),
+    /// Keep only triples whose subject has a timestamp no older than max_age.
+    /// The timestamps are the xsd:dateTime objects of the timestamp predicate, which may be the predicate itself.
+    MaxAge {
+        timestamp_predicate: String,
+        max_age: Duration,
+    },
+}
+
+impl Triplestore {
+    /// Retention policies are applied when the store is deduplicated, or when calling apply_retention_policies.
+    pub fn set_retention_policy(&mut self, predicate: &str, policy: RetentionPolicy) {
+        self.retention_policies
+            .insert(predicate.to_string(), policy);
+    }
+
+    pub fn remove_retention_policy(&mut self, predicate: &str) {
+        self.retention_policies.remove(predicate);
+    }
+
+    /// Deduplicates the store, which applies the retention policies to the deduplicated triples.
+    /// Duplicates do not count towards row limits, and time based policies are applied before row limits.
+    pub fn apply_retention_policies(&mut self) -> Result<(), MappingError> {
+        self.deduplicate()
+    }
+
+    pub(crate) fn apply_retention_policies_to_deduplicated(&mut self) -> Result<(), MappingError> {
+        if self.retention_policies.is_empty() {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut policies: Vec<(String, RetentionPolicy)> = self
+            .retention_policies
+            .iter()
+            .map(|(p, r)| (p.clone(), r.clone()))
+            .collect();
+        //Time based policies first, so that the timestamps they rely on are not removed by row limits
+        policies.sort_by_key(|(_, r)| matches!(r, RetentionPolicy::MaxRows(_)));
+        apply_retention_policies_to_map(&mut self.df_map, &policies, &self.caching_folder)?;
+        for map in self.named_graph_df_maps.values_mut() {
+            apply_retention_policies_to_map(map, &policies, &self.caching_folder)?;
+        }
+        self.named_graph_df_maps.retain(|_, map| !map.is_empty());
+        debug!(
+            "Applying retention policies took {} seconds",
+            now.elapsed().as_secs_f64()
+        );
+        Ok(())
+    }
+}
+
+//The timestamps of time based policies are those in the same graph as the triples
+fn apply_retention_policies_to_map(
+    df_map: &mut HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    policies: &Vec<(String, RetentionPolicy)>,
+    caching_folder: &Option<String>,
+) -> Result<(), MappingError> {
+    for (predicate, policy) in policies {
+        let keep_subjects = if let RetentionPolicy::MaxAge {
+            timestamp_predicate,
+            max_age,
+        } = policy
+        {
+            Some(subjects_newer_than(df_map, timestamp_predicate, max_age)?)
+        } else {
+            None
+        };
+        if let Some(m) = df_map.get_mut(predicate) {
+            match policy {
+                RetentionPolicy::MaxRows(n) => {
+                    retain_most_recently_added(m, *n, caching_folder, predicate)?;
+                }
+                RetentionPolicy::MaxAge { .. } => {
+                    let keep_subjects = keep_subjects.as_ref().unwrap();
+                    for tt in m.values_mut() {
+                        tt.retain(caching_folder, predicate, |df| {
+                            let height = df.height();
+                            let retained = df
+                                .lazy()
+                                .join(
+                                    keep_subjects.clone().lazy(),
+                                    [col("subject")],
+                                    [col("subject")],
+                                    JoinType::Semi,
+                                )
+                                .collect()
+                                .unwrap();
+                            if retained.height() < height {
+                                Some(retained)
+                            } else {
+                                None
+                            }
+                        })?;
+                    }
+                }
+            }
+            m.retain(|_, tt| tt.len() > 0);
+            if m.is_empty() {
+                df_map.remove(predicate);
+            }
+        }
+    }
+    Ok(())
+}
+
+//The tables of the predicate are ordered by the sequence numbers of the calls adding the triples. Cached triples have
+//the sequence number of the call that added them, while triples in memory are in the order they were added and have
+//the sequence number of the last call adding to their table.
+fn retain_most_recently_added(
+    m: &mut HashMap<RDFNodeType, TripleTable>,
+    n: usize,
+    caching_folder: &Option<String>,
+    predicate: &str,
+) -> Result<(), MappingError> {
+    let object_types: Vec<RDFNodeType> = m.keys().cloned().collect();
+    let mut sequence_dfs = vec![];
+    for (i, object_type) in object_types.iter().enumerate() {
+        let sequences = m.get(object_type).unwrap().added_sequences()?;
+        let height = sequences.len();
+        sequence_dfs.push(
+            DataFrame::new(vec![
+                Series::new("table", vec![i as u32; height]),
+                sequences,
+                Series::new("position", (0..height as u32).collect::<Vec<u32>>()),
+            ])
+            .unwrap(),
+        );
+    }
+    if sequence_dfs.is_empty() {
+        return Ok(());
+    }
+    let sequences = concat_df(sequence_dfs.iter()).unwrap();
+    if sequences.height() <= n {
+        return Ok(());
+    }
+    let kept = sequences
+        .sort([ADDED_SEQUENCE_COLUMN, "position"], vec![true, true])
+        .unwrap()
+        .head(Some(n));
+    let kept_tables = kept.column("table").unwrap().u32().unwrap();
+    let kept_positions = kept.column("position").unwrap().u32().unwrap();
+    let mut masks: Vec<Vec<bool>> = sequence_dfs.iter().map(|df| vec![false; df.height()]).collect();
+    for (t, p) in kept_tables.into_no_null_iter().zip(kept_positions.into_no_null_iter()) {
+        masks[t as usize][p as usize] = true;
+    }
+    for (object_type, mask) in object_types.iter().zip(masks) {
+        let tt = m.get_mut(object_type).unwrap();
+        tt.retain(caching_folder, predicate, |df| {
+            if mask.iter().all(|k| *k) {
+                None
+            } else {
+                let mask = Series::new("mask", mask.as_slice());
+                Some(df.filter(mask.bool().unwrap()).unwrap())
+            }
+        })?;
+    }
+    Ok(())
+}
+
+fn subjects_newer_than(
+    df_map: &HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    timestamp_predicate: &str,
+    max_age: &Duration,
+) -> Result<DataFrame, MappingError> {
+    let cutoff = (Utc::now() - *max_age).timestamp_millis();
+    let mut lfs = vec![];
+    if let Some(m) = df_map.get(timestamp_predicate) {
+        for (rdf_node_type, tt) in m {
+            if let RDFNodeType::Literal(_) = rdf_node_type {
+                for lf in tt.get_lazy_frames()? {
+                    lfs.push(lf);
+                }
+            }
+        }
+    }
+    if lfs.is_empty() {
+        return Ok(DataFrame::new(vec![Series::new_empty("subject", &DataType::Utf8)]).unwrap());
+    }
+    let lfs: Vec<LazyFrame> = lfs
+        .into_iter()
+        .map(|lf| {
+            lf.filter(
+                col("object")
+                    .cast(DataType::Datetime(TimeUnit::Milliseconds, None))
+                    .cast(DataType::Int64)
+                    .gt_eq(lit(cutoff)),
+            )
+            .select([col("subject")])
+        })
+        .collect();
+    Ok(concat(lfs, true, true)
+        .unwrap()
+        .unique(None, UniqueKeepStrategy::First)
+        .collect()
+        .unwrap())
+}
+
+impl TripleTable {
+    //The sequence numbers of the rows of the table, in the order in which retain reads them
+    fn added_sequences(&self) -> Result<Series, MappingError> {
+        if let Some(dfs) = &self.dfs {
+            let height = dfs.iter().map(|df| df.height()).sum();
+            Ok(Series::new(ADDED_SEQUENCE_COLUMN, vec![self.added_sequence; height]))
+        } else if let Some(paths) = &self.df_paths {
+            let mut lfs = vec![];
+            for p in paths.iter() {
+                lfs.push(read_parquet_with_added_sequence(p)?.select([col(ADDED_SEQUENCE_COLUMN)]));
+            }
+            if lfs.is_empty() {
+                return Ok(Series::new_empty(ADDED_SEQUENCE_COLUMN, &DataType::UInt64));
+            }
+            let df = concat(lfs, true, true)
+                .unwrap()
+                .collect()
+                .map_err(|x| MappingError::ReadParquetError(x))?;
+            Ok(df.column(ADDED_SEQUENCE_COLUMN).unwrap().cast(&DataType::UInt64).unwrap())
+        } else {
+            panic!("TripleTable in invalid state")
+        }
+    }
+
+    //Replaces the contents of the table if f returns a new DataFrame
+    fn retain<F: FnOnce(DataFrame) -> Option<DataFrame>>(
+        &mut self,
+        caching_folder: &Option<String>,
+        predicate: &str,
+        f: F,
+    ) -> Result<(), MappingError> {
+        if let Some(dfs) = &mut self.dfs {
+            if dfs.is_empty() {
+                return Ok(());
+            }
+            if let Some(df) = f(concat_df(dfs.iter()).unwrap()) {
+                dfs.clear();
+                if df.height() > 0 {
+                    dfs.push(df);
+                }
+                self.object_index = None;
+            }
+        } else if let Some(paths) = &mut self.df_paths {
+            if paths.is_empty() {
+                return Ok(());
+            }
+            let mut lfs = vec![];
+            for p in paths.iter() {
+                lfs.push(read_parquet_with_added_sequence(p)?);
+            }
+            let df = concat(lfs, true, true).unwrap().collect().unwrap();
+            if let Some(df) = f(df) {
+                for p in paths.drain(..) {
+                    remove_file(Path::new(&p)).map_err(|x| MappingError::RemoveParquetFileError(x))?;
+                }
+                if df.height() > 0 {
+                    paths.extend(split_write_df(
+                        caching_folder.as_ref().unwrap(),
+                        df,
+                        &property_to_filename(predicate),
+                    )?);
+                }
+            }
+        }
+        self.reset_key_index();
+        self.refresh_chunk_metadata()
+    }
+}
@@ -0,0 +1,109 @@
+use super::Triplestore;
+use crate::mapping::errors::MappingError;
+use crate::triplestore::ntriples_write::{NTriplesWriteOptions, DEFAULT_CHUNK_SIZE};
+use oxrdf::NamedNode;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Writes the triples of a store in some format.
+/// Implementations outside of the crate can read the triples with e.g. export_oxrdf_triples, n_triples_lines or
+/// object_property_triples, and are used with SerializationFormat::Custom.
+pub trait TripleSerializer {
+    fn serialize(
+        &self,
+        triplestore: &mut Triplestore,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError>;
+}
+
+/// Writes the default graph as N-Triples, stores with named graphs are rejected.
+#[derive(Clone, Debug, Default)]
+pub struct NTriplesSerializer {
+    pub options: NTriplesWriteOptions,
+}
+
+/// Writes the default graph and the named graphs as N-Quads.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NQuadsSerializer;
+
+/// Writes the default graph and the named graphs as TriG.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TriGSerializer;
+
+/// Writes the default graph as Turtle, using the prefixes for IRIs. Stores with named graphs are rejected.
+#[derive(Clone, Debug, Default)]
+pub struct TurtleSerializer {
+    pub prefix_map: HashMap<String, NamedNode>,
+}
+
+impl TripleSerializer for NTriplesSerializer {
+    fn serialize(
+        &self,
+        triplestore: &mut Triplestore,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        triplestore.write_n_triples_with_options(sink, &self.options)
+    }
+}
+
+impl TripleSerializer for NQuadsSerializer {
+    fn serialize(
+        &self,
+        triplestore: &mut Triplestore,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        triplestore.write_n_quads_all_dfs(sink, DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl TripleSerializer for TriGSerializer {
+    fn serialize(
+        &self,
+        triplestore: &mut Triplestore,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        triplestore.write_trig_all_dfs(sink, DEFAULT_CHUNK_SIZE)
+    }
+}
+
+impl TripleSerializer for TurtleSerializer {
+    fn serialize(
+        &self,
+        triplestore: &mut Triplestore,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        triplestore.write_turtle_all_dfs(sink, DEFAULT_CHUNK_SIZE, &self.prefix_map)
+    }
+}
+
+/// The format of Triplestore::write, where Custom is any other serializer.
+pub enum SerializationFormat {
+    NTriples(NTriplesSerializer),
+    NQuads,
+    TriG,
+    Turtle(TurtleSerializer),
+    Custom(Box<dyn TripleSerializer>),
+}
+
+impl SerializationFormat {
+    pub fn serializer(&self) -> &dyn TripleSerializer {
+        match self {
+            SerializationFormat::NTriples(s) => s,
+            SerializationFormat::NQuads => &NQuadsSerializer,
+            SerializationFormat::TriG => &TriGSerializer,
+            SerializationFormat::Turtle(s) => s,
+            SerializationFormat::Custom(s) => s.as_ref(),
+        }
+    }
+}
+
+impl Triplestore {
+    /// Writes the triples to the sink in the format.
+    pub fn write(
+        &mut self,
+        format: &SerializationFormat,
+        sink: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        format.serializer().serialize(self, sink)
+    }
+}
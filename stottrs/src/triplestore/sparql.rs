@@ -1,50 +1,137 @@
 pub mod errors;
+mod hyperloglog;
 pub(crate) mod lazy_aggregate;
-mod lazy_expressions;
+pub(crate) mod lazy_expressions;
 mod lazy_graph_patterns;
 mod lazy_order;
-mod query_context;
+pub mod query_context;
+pub mod query_cursors;
+#[cfg(feature = "serde")]
+pub mod serde_rows;
 pub mod solution_mapping;
+pub mod sparql_results;
 mod sparql_to_polars;
 
 use crate::triplestore::sparql::query_context::Context;
-use oxrdf::{NamedNode, Variable};
+use chrono::Utc;
+use oxrdf::vocab::xsd;
+use oxrdf::{NamedNode, Triple, Variable};
 use std::collections::HashMap;
+use std::io::Write;
 
 use super::Triplestore;
 use crate::literals::sparql_literal_to_any_value;
 use crate::mapping::RDFNodeType;
 use crate::triplestore::sparql::errors::SparqlError;
-use crate::triplestore::sparql::solution_mapping::SolutionMappings;
-use crate::triplestore::TriplesToAdd;
+use crate::triplestore::sparql::solution_mapping::{
+    language_tag_column, multi_type_tag_column, rdf_node_type_from_tag, SolutionMappings,
+};
+use crate::triplestore::custom_datatypes::formatter_for;
+use crate::triplestore::export_triples::triples_df_to_oxrdf;
+use crate::triplestore::ntriples_write::write_ntriples_for_triples_df;
+use crate::triplestore::{TriplesToAdd, LANGUAGE_TAG_COLUMN};
 use polars::frame::DataFrame;
-use polars::prelude::{col, IntoLazy};
-use polars_core::prelude::{DataType, Series, UniqueKeepStrategy};
+use polars::prelude::{col, concat, lit, IntoLazy};
+use polars_core::prelude::{AnyValue, DataType, IdxSize, NamedFrom, Series, UniqueKeepStrategy};
 use polars_core::toggle_string_cache;
 use spargebra::term::{NamedNodePattern, TermPattern, TriplePattern};
+use spargebra::algebra::GraphPattern;
 use spargebra::Query;
 use uuid::Uuid;
 
+const CONSTRUCT_BATCH_SIZE: usize = 100_000;
+
 pub enum QueryResult {
     Select(DataFrame),
     Construct(Vec<(DataFrame, RDFNodeType)>),
+    Describe(Vec<(DataFrame, RDFNodeType)>),
+}
+
+impl QueryResult {
+    /// The solutions of a SELECT query.
+    pub fn as_select(&self) -> Option<&DataFrame> {
+        if let QueryResult::Select(df) = self {
+            Some(df)
+        } else {
+            None
+        }
+    }
+
+    /// The solutions of a SELECT query.
+    pub fn into_df(self) -> Option<DataFrame> {
+        if let QueryResult::Select(df) = self {
+            Some(df)
+        } else {
+            None
+        }
+    }
+
+    /// The triples of a CONSTRUCT or DESCRIBE query, with literals typed by the datatypes of their objects.
+    pub fn into_triples(self) -> Option<Vec<Triple>> {
+        match self {
+            QueryResult::Select(_) => None,
+            QueryResult::Construct(dfs) | QueryResult::Describe(dfs) => Some(
+                dfs.iter()
+                    .flat_map(|(df, object_type)| triples_df_to_oxrdf(df, object_type))
+                    .collect(),
+            ),
+        }
+    }
 }
 
 impl Triplestore {
     pub fn query(&mut self, query: &str) -> Result<QueryResult, SparqlError> {
         let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
-        self.query_parsed(&query)
+        self.query_parsed(&query, false)
     }
 
-    fn query_parsed(&mut self, query: &Query) -> Result<QueryResult, SparqlError> {
+    //Like query, but DESCRIBE queries also include the triples where the described resources are objects.
+    pub fn query_describe_with_objects(&mut self, query: &str) -> Result<QueryResult, SparqlError> {
+        let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
+        self.query_parsed(&query, true)
+    }
+
+    /// Evaluates a graph pattern, optionally constrained by existing solution mappings.
+    /// Custom graph pattern operators can use this to evaluate their operands, and combine the results
+    /// using the methods of SolutionMappings.
+    pub fn evaluate_graph_pattern(
+        &mut self,
+        graph_pattern: &GraphPattern,
+        solution_mappings: Option<SolutionMappings>,
+        context: &Context,
+    ) -> Result<SolutionMappings, SparqlError> {
+        self.prepare_for_query()?;
+        self.lazy_graph_pattern(graph_pattern, solution_mappings, context)
+    }
+
+    fn prepare_for_query(&mut self) -> Result<(), SparqlError> {
         if !self.deduplicated {
             self.deduplicate()
                 .map_err(|x| SparqlError::DeduplicationError(x))?;
         }
+        self.build_object_indexes();
+        self.query_time = Some(Utc::now().naive_utc());
         toggle_string_cache(true);
+        Ok(())
+    }
+
+    fn query_parsed(&mut self, query: &Query, describe_objects: bool) -> Result<QueryResult, SparqlError> {
+        self.prepare_for_query()?;
         let context = Context::new();
         match query {
-            Query::Select {
+            Query::Select { pattern, .. } => {
+                let (mut df, rdf_node_types) = self.select_parsed(pattern, &context)?;
+                //Language tags are only kept in results written in the SPARQL results formats
+                for v in rdf_node_types.keys() {
+                    let tag_column = language_tag_column(v);
+                    if df.get_column_names().contains(&tag_column.as_str()) {
+                        df = df.drop(&tag_column).unwrap();
+                    }
+                }
+                Ok(QueryResult::Select(df))
+            }
+            Query::Construct {
+                template,
                 dataset: _,
                 pattern,
                 base_iri: _,
@@ -52,51 +139,152 @@ impl Triplestore {
                 let SolutionMappings {
                     mappings,
                     columns: _,
-                    rdf_node_types: _,
+                    rdf_node_types,
                 } = self.lazy_graph_pattern(&pattern, None, &context)?;
-                let df = mappings.collect().unwrap();
-                let mut cats = vec![];
-                for c in df.columns(df.get_column_names()).unwrap() {
-                    if let DataType::Categorical(_) = c.dtype() {
-                        cats.push(c.name().to_string());
-                    }
-                }
-                let mut lf = df.lazy();
-                for c in cats {
-                    lf = lf.with_column(col(&c).cast(DataType::Utf8))
+                let df = mappings
+                    .collect()
+                    .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+                let call_id = Uuid::new_v4().simple().to_string();
+                let mut dfs = vec![];
+                for t in template {
+                    dfs.extend(triple_to_df(&df, &rdf_node_types, t, &call_id, 0)?);
                 }
-
-                Ok(QueryResult::Select(lf.collect().unwrap()))
+                Ok(QueryResult::Construct(dfs))
             }
-            Query::Construct {
-                template,
+            Query::Describe {
                 dataset: _,
                 pattern,
                 base_iri: _,
             } => {
                 let SolutionMappings {
                     mappings,
-                    columns: _,
+                    columns,
                     rdf_node_types,
                 } = self.lazy_graph_pattern(&pattern, None, &context)?;
-                let df = mappings.collect().unwrap();
-                let mut dfs = vec![];
-                for t in template {
-                    dfs.push(triple_to_df(&df, &rdf_node_types, t)?);
+                let df = mappings
+                    .collect()
+                    .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+                let mut iri_series = vec![];
+                for c in &columns {
+                    if rdf_node_types.get(c) == Some(&RDFNodeType::IRI) {
+                        let mut ser = df.column(c).unwrap().cast(&DataType::Utf8).unwrap();
+                        ser.rename("iri");
+                        iri_series.push(ser);
+                    }
                 }
-                Ok(QueryResult::Construct(dfs))
+                let mut iris = Series::new_empty("iri", &DataType::Utf8);
+                for ser in &iri_series {
+                    iris.append(ser).unwrap();
+                }
+                let iris = iris.drop_nulls().unique().unwrap();
+                Ok(QueryResult::Describe(self.describe_iris(&iris, describe_objects, &None)?))
             }
             _ => Err(SparqlError::QueryTypeNotSupported),
         }
     }
 
+    //The solutions of a SELECT query with the types of the variables, and the language tag columns of variables that have them
+    pub(crate) fn select_parsed(
+        &mut self,
+        pattern: &GraphPattern,
+        context: &Context,
+    ) -> Result<(DataFrame, HashMap<String, RDFNodeType>), SparqlError> {
+        let SolutionMappings {
+            mappings,
+            columns: _,
+            rdf_node_types,
+        } = self.lazy_graph_pattern(pattern, None, context)?;
+        let df = mappings
+            .collect()
+            .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+        let mut cats = vec![];
+        for c in df.columns(df.get_column_names()).unwrap() {
+            if let DataType::Categorical(_) = c.dtype() {
+                cats.push(c.name().to_string());
+            }
+        }
+        let mut lf = df.lazy();
+        for c in cats {
+            lf = lf.with_column(col(&c).cast(DataType::Utf8))
+        }
+
+        let df = lf
+            .collect()
+            .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+        Ok((df, rdf_node_types))
+    }
+
+    //Finds all triples of the graph where the IRIs occur as subjects, and optionally as objects.
+    //DESCRIBE queries describe the IRIs in the default graph.
+    pub fn describe_iris(
+        &self,
+        iris: &Series,
+        include_objects: bool,
+        graph: &Option<String>,
+    ) -> Result<Vec<(DataFrame, RDFNodeType)>, SparqlError> {
+        let mut dfs = vec![];
+        let df_map = if let Some(df_map) = self.graph_df_map(graph) {
+            df_map
+        } else {
+            return Ok(dfs);
+        };
+        for (predicate, map) in df_map {
+            for (rdf_node_type, tt) in map {
+                let lf = concat(
+                    tt.get_lazy_frames()
+                        .map_err(|x| SparqlError::TripleTableReadError(x))?,
+                    true,
+                    true,
+                )
+                .map_err(|x| SparqlError::DescribeError(predicate.clone(), x))?;
+                let mut matches = col("subject").is_in(lit(iris.clone()));
+                if include_objects && rdf_node_type == &RDFNodeType::IRI {
+                    matches = matches.or(col("object").is_in(lit(iris.clone())));
+                }
+                let df = lf
+                    .filter(matches)
+                    .select([
+                        col("subject"),
+                        lit(predicate.as_str()).alias("verb"),
+                        col("object"),
+                    ])
+                    .collect()
+                    .map_err(|x| SparqlError::DescribeError(predicate.clone(), x))?;
+                if df.height() > 0 {
+                    dfs.push((df, rdf_node_type.clone()));
+                }
+            }
+        }
+        Ok(dfs)
+    }
+
     pub fn construct_update(&mut self, query: &str) -> Result<(), SparqlError> {
         let call_uuid = Uuid::new_v4().to_string();
+        let all_triples_to_add = self.construct_triples_to_add(query)?;
+        self.add_triples_vec(all_triples_to_add, &call_uuid)
+            .map_err(|x| SparqlError::StoreTriplesError(x))?;
+        Ok(())
+    }
+
+    /// Creates a store with only the triples produced by a construct query, e.g. to keep a derived graph apart from
+    /// the source data. The store is held in memory, and uses the custom datatypes of this store.
+    pub fn construct_to_new_store(&mut self, query: &str) -> Result<Triplestore, SparqlError> {
+        let call_uuid = Uuid::new_v4().to_string();
+        let all_triples_to_add = self.construct_triples_to_add(query)?;
+        let mut triplestore = Triplestore::new(None);
+        triplestore.custom_datatypes = self.custom_datatypes.clone();
+        triplestore
+            .add_triples_vec(all_triples_to_add, &call_uuid)
+            .map_err(|x| SparqlError::StoreTriplesError(x))?;
+        Ok(triplestore)
+    }
+
+    fn construct_triples_to_add(&mut self, query: &str) -> Result<Vec<TriplesToAdd>, SparqlError> {
         let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
         if let Query::Construct { .. } = &query {
-            let res = self.query_parsed(&query)?;
+            let res = self.query_parsed(&query, false)?;
             match res {
-                QueryResult::Select(_) => {
+                QueryResult::Select(_) | QueryResult::Describe(_) => {
                     panic!("Should never happen")
                 }
                 QueryResult::Construct(dfs) => {
@@ -108,10 +296,102 @@ impl Triplestore {
                             language_tag: None,
                             static_verb_column: None,
                             has_unique_subset: false,
+                            globally_unique: false,
+                            graph: None,
                         });
                     }
-                    self.add_triples_vec(all_triples_to_add, &call_uuid)
-                        .map_err(|x| SparqlError::StoreTriplesError(x))?;
+                    Ok(all_triples_to_add)
+                }
+            }
+        } else {
+            Err(SparqlError::QueryTypeNotSupported)
+        }
+    }
+
+    /// Writes the triples produced by a construct query as N-Triples without creating the full result in memory.
+    /// The solutions of the query pattern are evaluated and written in batches, so duplicate triples are only removed
+    /// within a batch. The pattern is evaluated again for each batch, which trades time for memory.
+    pub fn construct_to_writer(
+        &mut self,
+        query: &str,
+        writer: &mut dyn Write,
+    ) -> Result<(), SparqlError> {
+        let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
+        if let Query::Construct {
+            template, pattern, ..
+        } = &query
+        {
+            self.prepare_for_query()?;
+            let context = Context::new();
+            let SolutionMappings {
+                mappings,
+                columns: _,
+                rdf_node_types,
+            } = self.lazy_graph_pattern(&pattern, None, &context)?;
+            let call_id = Uuid::new_v4().simple().to_string();
+            let mut offset = 0;
+            loop {
+                let batch = mappings
+                    .clone()
+                    .slice(offset as i64, CONSTRUCT_BATCH_SIZE as IdxSize)
+                    .collect()
+                    .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+                if batch.height() == 0 {
+                    break;
+                }
+                for t in template {
+                    //Triples without variables are only written once
+                    if offset > 0 && !triple_has_variable(t) {
+                        continue;
+                    }
+                    for (triples_df, dt) in
+                        triple_to_df(&batch, &rdf_node_types, t, &call_id, offset)?
+                    {
+                        write_ntriples_for_triples_df(
+                            triples_df,
+                            &dt,
+                            formatter_for(&self.custom_datatypes, &dt),
+                            writer,
+                            1024,
+                        )
+                        .map_err(|x| SparqlError::WriteError(x))?;
+                    }
+                }
+                if batch.height() < CONSTRUCT_BATCH_SIZE {
+                    break;
+                }
+                offset += CONSTRUCT_BATCH_SIZE;
+            }
+            Ok(())
+        } else {
+            Err(SparqlError::QueryTypeNotSupported)
+        }
+    }
+
+    //Removes the triples produced by the construct query from the store.
+    pub fn deconstruct_update(&mut self, query: &str) -> Result<(), SparqlError> {
+        self.deconstruct_update_in_graph(query, None)
+    }
+
+    //Removes the triples produced by the construct query from the named graph, or from the default graph.
+    pub fn deconstruct_update_in_graph(
+        &mut self,
+        query: &str,
+        graph: Option<&str>,
+    ) -> Result<(), SparqlError> {
+        let graph = graph.map(|g| g.to_string());
+        let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
+        if let Query::Construct { .. } = &query {
+            let res = self.query_parsed(&query, false)?;
+            match res {
+                QueryResult::Select(_) | QueryResult::Describe(_) => {
+                    panic!("Should never happen")
+                }
+                QueryResult::Construct(dfs) => {
+                    for (df, dt) in dfs {
+                        self.remove_triples(df, &dt, &graph)
+                            .map_err(|x| SparqlError::RemoveTriplesError(x))?;
+                    }
                     Ok(())
                 }
             }
@@ -121,31 +401,93 @@ impl Triplestore {
     }
 }
 
+//Blank nodes in the template are fresh for each solution, the offset is the row number of the first solution in df
+//Objects with values of several types give a DataFrame for each type
 fn triple_to_df(
     df: &DataFrame,
     rdf_node_types: &HashMap<String, RDFNodeType>,
     t: &TriplePattern,
-) -> Result<(DataFrame, RDFNodeType), SparqlError> {
+    call_id: &str,
+    offset: usize,
+) -> Result<Vec<(DataFrame, RDFNodeType)>, SparqlError> {
     let len = if triple_has_variable(t) {
         df.height()
     } else {
         1
     };
-    let (subj_ser, _) = term_pattern_series(df, rdf_node_types, &t.subject, "subject", len);
+    let (subj_ser, _) =
+        term_pattern_series(df, rdf_node_types, &t.subject, "subject", len, call_id, offset);
     let (verb_ser, _) = named_node_pattern_series(df, rdf_node_types, &t.predicate, "verb", len);
-    let (obj_ser, dt) = term_pattern_series(df, rdf_node_types, &t.object, "object", len);
-    let df = DataFrame::new(vec![subj_ser, verb_ser, obj_ser])
-        .unwrap()
-        .unique(None, UniqueKeepStrategy::First)
-        .unwrap();
-    Ok((df, dt))
+    let (obj_ser, dt) =
+        term_pattern_series(df, rdf_node_types, &t.object, "object", len, call_id, offset);
+    let mut triples_df = DataFrame::new(vec![subj_ser, verb_ser, obj_ser]).unwrap();
+    let mut out = vec![];
+    if let (TermPattern::Variable(v), RDFNodeType::MultiType) = (&t.object, &dt) {
+        let mut tag_ser = df.column(&multi_type_tag_column(v.as_str())).unwrap().clone();
+        tag_ser.rename("tag");
+        triples_df.with_column(tag_ser.clone()).unwrap();
+        let tags = tag_ser.unique().unwrap();
+        for tag in tags.utf8().unwrap().into_no_null_iter() {
+            let tag_df = triples_df
+                .clone()
+                .lazy()
+                .filter(col("tag").eq(lit(tag)))
+                .select([col("subject"), col("verb"), col("object")])
+                .collect()
+                .unwrap();
+            let tag_dt = rdf_node_type_from_tag(tag);
+            out.push((lexical_objects_to_native(tag_df, &tag_dt), tag_dt));
+        }
+    } else {
+        //Strings keep the language tags of the variable, so that removed triples are matched on them
+        if let (TermPattern::Variable(v), true) = (&t.object, dt.is_lit_type(xsd::STRING)) {
+            if let Ok(tags) = df.column(&language_tag_column(v.as_str())) {
+                let mut tags = tags.cast(&DataType::Utf8).unwrap();
+                tags.rename(LANGUAGE_TAG_COLUMN);
+                triples_df.with_column(tags).unwrap();
+            }
+        }
+        out.push((triples_df, dt));
+    }
+    //Triples with unbound terms are not constructed
+    let terms = ["subject".to_string(), "verb".to_string(), "object".to_string()];
+    for (triples_df, _) in out.iter_mut() {
+        *triples_df = triples_df
+            .drop_nulls(Some(&terms))
+            .unwrap()
+            .unique(None, UniqueKeepStrategy::First)
+            .unwrap();
+    }
+    Ok(out)
+}
+
+//Objects of variables with several types are lexical forms
+fn lexical_objects_to_native(mut df: DataFrame, dt: &RDFNodeType) -> DataFrame {
+    if let RDFNodeType::Literal(l) = dt {
+        if l.as_ref() != xsd::STRING {
+            let any_values: Vec<AnyValue> = df
+                .column("object")
+                .unwrap()
+                .utf8()
+                .unwrap()
+                .into_iter()
+                .map(|x| match x {
+                    Some(x) => sparql_literal_to_any_value(&x.to_string(), &Some(l.clone())).0,
+                    None => AnyValue::Null,
+                })
+                .collect();
+            df.with_column(Series::from_any_values("object", &any_values).unwrap())
+                .unwrap();
+        }
+    }
+    df
 }
 
 fn triple_has_variable(t: &TriplePattern) -> bool {
-    if let TermPattern::Variable(_) = t.subject {
+    if let TermPattern::Variable(_) | TermPattern::BlankNode(_) = t.subject {
         return true;
     }
-    if let TermPattern::Variable(_) = t.object {
+    if let TermPattern::Variable(_) | TermPattern::BlankNode(_) = t.object {
         return true;
     }
     return false;
@@ -157,11 +499,16 @@ fn term_pattern_series(
     tp: &TermPattern,
     name: &str,
     len: usize,
+    call_id: &str,
+    offset: usize,
 ) -> (Series, RDFNodeType) {
     match tp {
         TermPattern::NamedNode(nn) => named_node_series(nn, name, len),
-        TermPattern::BlankNode(_) => {
-            unimplemented!("Blank node term pattern not supported")
+        TermPattern::BlankNode(bn) => {
+            let labels: Vec<String> = (offset..offset + len)
+                .map(|i| format!("_:{}_{}_{}", bn.as_str(), call_id, i))
+                .collect();
+            (Series::new(name, labels), RDFNodeType::BlankNode)
         }
         TermPattern::Literal(lit) => {
             let (anyvalue, dt) = sparql_literal_to_any_value(
@@ -1,7 +1,9 @@
+use polars::prelude::PolarsError;
 use spargebra::ParseError;
 use thiserror::Error;
 use crate::mapping::errors::MappingError;
 use crate::mapping::RDFNodeType;
+use crate::triplestore::harmonize::SchemaMismatch;
 
 #[derive(Error, Debug)]
 pub enum SparqlError {
@@ -18,5 +20,31 @@ pub enum SparqlError {
     #[error("Read dataframe error {}", .0)]
     TripleTableReadError(MappingError),
     #[error("Error storing triples {}", .0)]
-    StoreTriplesError(MappingError)
+    StoreTriplesError(MappingError),
+    #[error("Error removing triples {}", .0)]
+    RemoveTriplesError(MappingError),
+    #[error("Error writing triples {}", .0)]
+    WriteError(MappingError),
+    #[error("Solution mappings have different schemas in context {}: {:?}", .0, .1)]
+    SchemaMismatch(String, Vec<SchemaMismatch>),
+    #[error("Error evaluating query in context {}: {}", .0, .1)]
+    EvaluationError(String, PolarsError),
+    #[error("Error describing resources using triples with predicate {}: {}", .0, .1)]
+    DescribeError(String, PolarsError),
+    #[error("Query cursor {} does not exist or has expired", .0)]
+    UnknownQueryCursor(String),
+    #[error("Argument {} of function {} must be a constant in context {}", .0, .1, .2)]
+    ConstantArgumentRequired(usize, String, String),
+    #[error("Invalid literal {} with datatype {}", .0, .1)]
+    InvalidLiteral(String, String),
+    #[error("Literals with datatype {} are not supported in queries", .0)]
+    LiteralDatatypeNotSupported(String),
+    #[error("Custom aggregate {} is not supported in context {}", .0, .1)]
+    UnsupportedAggregate(String, String),
+    #[error("Custom function {} is not supported in context {}", .0, .1)]
+    UnsupportedFunction(String, String),
+    #[error("Invalid regular expression flags {} in context {}", .0, .1)]
+    InvalidRegexFlags(String, String),
+    #[error("Error writing query results {}", .0)]
+    ResultsWriteError(std::io::Error),
 }
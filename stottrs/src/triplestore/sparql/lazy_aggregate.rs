@@ -5,7 +5,9 @@ use crate::triplestore::sparql::query_context::{Context, PathEntry};
 use crate::triplestore::sparql::solution_mapping::SolutionMappings;
 use oxrdf::vocab::xsd;
 use oxrdf::Variable;
-use polars::prelude::{col, DataType, Expr, GetOutput, IntoSeries};
+use crate::constants::APPROX_COUNT_DISTINCT;
+use crate::triplestore::sparql::hyperloglog::HyperLogLog;
+use polars::prelude::{col, concat_str, count, lit, DataType, Expr, GetOutput, NamedFrom, Series};
 use spargebra::algebra::AggregateExpression;
 
 pub struct AggregateReturn {
@@ -37,24 +39,31 @@ impl Triplestore {
                         solution_mappings,
                         column_context.as_ref().unwrap(),
                     )?;
+                    //Unbound values are not counted
                     if *distinct {
-                        out_expr = col(column_context.as_ref().unwrap().as_str()).n_unique();
+                        out_expr = col(column_context.as_ref().unwrap().as_str())
+                            .drop_nulls()
+                            .n_unique();
                     } else {
-                        out_expr = col(column_context.as_ref().unwrap().as_str()).count();
+                        out_expr = col(column_context.as_ref().unwrap().as_str())
+                            .is_not_null()
+                            .sum();
                     }
                 } else {
                     output_solution_mappings = solution_mappings;
                     column_context = None;
-                    let all_proper_column_names: Vec<String> = output_solution_mappings
-                        .columns
-                        .iter()
-                        .map(|x| x.clone())
-                        .collect();
-                    let columns_expr = Expr::Columns(all_proper_column_names);
                     if *distinct {
-                        out_expr = columns_expr.n_unique();
+                        let mut all_proper_column_names: Vec<&String> =
+                            output_solution_mappings.columns.iter().collect();
+                        all_proper_column_names.sort();
+                        //Distinct solutions are counted using a string representation of each row
+                        let row_exprs: Vec<Expr> = all_proper_column_names
+                            .into_iter()
+                            .map(|c| col(c).cast(DataType::Utf8).fill_null(lit("\u{0}")))
+                            .collect();
+                        out_expr = concat_str(row_exprs, "\u{1f}").n_unique();
                     } else {
-                        out_expr = columns_expr.unique();
+                        out_expr = count();
                     }
                 }
             }
@@ -78,6 +87,7 @@ impl Triplestore {
 
                 if *distinct {
                     out_expr = col(column_context.as_ref().unwrap().as_str())
+                        .drop_nulls()
                         .unique()
                         .sum();
                 } else {
@@ -100,6 +110,7 @@ impl Triplestore {
 
                 if *distinct {
                     out_expr = col(column_context.as_ref().unwrap().as_str())
+                        .drop_nulls()
                         .unique()
                         .mean();
                 } else {
@@ -146,32 +157,18 @@ impl Triplestore {
                 let use_sep = if let Some(sep) = separator {
                     sep.to_string()
                 } else {
-                    "".to_string()
+                    " ".to_string()
                 };
-                if *distinct {
-                    out_expr = col(column_context.as_ref().unwrap().as_str())
-                        .cast(DataType::Utf8)
-                        .list()
-                        .apply(
-                            move |s| {
-                                Ok(s.unique_stable()
-                                    .expect("Unique stable error")
-                                    .str_concat(use_sep.as_str())
-                                    .into_series())
-                            },
-                            GetOutput::from_type(DataType::Utf8),
-                        )
-                        .first();
-                } else {
-                    out_expr = col(column_context.as_ref().unwrap().as_str())
-                        .cast(DataType::Utf8)
-                        .list()
-                        .apply(
-                            move |s| Ok(s.str_concat(use_sep.as_str()).into_series()),
-                            GetOutput::from_type(DataType::Utf8),
-                        )
-                        .first();
-                }
+                let distinct = *distinct;
+                out_expr = col(column_context.as_ref().unwrap().as_str())
+                    .drop_nulls()
+                    .cast(DataType::Utf8)
+                    .list()
+                    .apply(
+                        move |s| Ok(group_concat(&s, use_sep.as_str(), distinct)),
+                        GetOutput::from_type(DataType::Utf8),
+                    )
+                    .first();
             }
             AggregateExpression::Sample { expr, .. } => {
                 column_context = Some(context.extension_with(PathEntry::AggregationOperation));
@@ -183,14 +180,39 @@ impl Triplestore {
                 )?;
                 out_rdf_node_type = rdf_node_type_from_context(column_context.as_ref().unwrap(), &output_solution_mappings).clone();
 
-                out_expr = col(column_context.as_ref().unwrap().as_str()).first();
+                //Any value is allowed, but we prefer a bound one
+                out_expr = col(column_context.as_ref().unwrap().as_str())
+                    .drop_nulls()
+                    .first();
             }
             AggregateExpression::Custom {
                 name,
-                expr: _,
+                expr,
                 distinct: _,
             } => {
-                panic!("Custom aggregation {} not supported", name);
+                if name.as_str() == APPROX_COUNT_DISTINCT {
+                    column_context = Some(context.extension_with(PathEntry::AggregationOperation));
+                    output_solution_mappings = self.lazy_expression(
+                        expr,
+                        solution_mappings,
+                        column_context.as_ref().unwrap(),
+                    )?;
+                    out_rdf_node_type = RDFNodeType::Literal(xsd::UNSIGNED_LONG.into_owned());
+                    out_expr = col(column_context.as_ref().unwrap().as_str())
+                        .drop_nulls()
+                        .cast(DataType::Utf8)
+                        .list()
+                        .apply(
+                            |s| Ok(approx_count_distinct(&s)),
+                            GetOutput::from_type(DataType::UInt64),
+                        )
+                        .first();
+                } else {
+                    return Err(SparqlError::UnsupportedAggregate(
+                        name.as_str().to_string(),
+                        context.as_str().to_string(),
+                    ));
+                }
             }
         }
         out_expr = out_expr.alias(variable.as_str());
@@ -203,6 +225,54 @@ impl Triplestore {
     }
 }
 
+//HyperLogLog estimate for each group, the series contains either the values of a group or a list per group
+fn approx_count_distinct(s: &Series) -> Series {
+    if let DataType::List(_) = s.dtype() {
+        let estimates: Vec<Option<u64>> = s
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.map(|inner| estimate_utf8(&inner)))
+            .collect();
+        Series::new(s.name(), estimates)
+    } else {
+        Series::new(s.name(), [estimate_utf8(s)])
+    }
+}
+
+//Concatenation for each group, the series contains either the values of a group or a list per group
+fn group_concat(s: &Series, separator: &str, distinct: bool) -> Series {
+    if let DataType::List(_) = s.dtype() {
+        let concatenated: Vec<Option<String>> = s
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.map(|inner| concat_utf8(&inner, separator, distinct)))
+            .collect();
+        Series::new(s.name(), concatenated)
+    } else {
+        Series::new(s.name(), [concat_utf8(s, separator, distinct)])
+    }
+}
+
+fn concat_utf8(s: &Series, separator: &str, distinct: bool) -> String {
+    let s = if distinct {
+        s.unique_stable().unwrap()
+    } else {
+        s.clone()
+    };
+    let values: Vec<&str> = s.utf8().unwrap().into_iter().flatten().collect();
+    values.join(separator)
+}
+
+fn estimate_utf8(s: &Series) -> u64 {
+    let mut hll = HyperLogLog::new();
+    for v in s.utf8().unwrap().into_iter().flatten() {
+        hll.insert(v);
+    }
+    hll.estimate()
+}
+
 fn rdf_node_type_from_context<'a>(
     context: &'_ Context,
     solution_mappings: &'a SolutionMappings,
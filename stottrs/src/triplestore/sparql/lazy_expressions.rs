@@ -2,18 +2,21 @@ mod exists_helper;
 
 use super::Triplestore;
 
-use oxrdf::vocab::xsd;
+use chrono::Utc;
+use oxrdf::vocab::{rdf, xsd};
 use polars::datatypes::DataType;
 use polars::functions::concat_str;
 use polars::lazy::dsl::is_not_null;
-use polars::prelude::{col, Expr, LiteralValue, Operator, Series, UniqueKeepStrategy, IntoLazy};
-use polars_core::prelude::IntoSeries;
+use polars::prelude::{col, lit, when, Expr, GetOutput, IntoLazy, LiteralValue, Operator, Series, UniqueKeepStrategy, Utf8NameSpaceImpl};
+use polars_core::prelude::{BooleanChunked, IntoSeries, NamedFrom, Utf8Chunked};
 use spargebra::algebra::{Expression, Function};
+use uuid::Uuid;
 use crate::mapping::RDFNodeType;
+use crate::triplestore::harmonize::canonical_datatype;
 use crate::triplestore::sparql::errors::SparqlError;
 use crate::triplestore::sparql::lazy_expressions::exists_helper::rewrite_exists_graph_pattern;
 use crate::triplestore::sparql::query_context::{Context, PathEntry};
-use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use crate::triplestore::sparql::solution_mapping::{language_tag_column, multi_type_tag_column, SolutionMappings};
 use crate::triplestore::sparql::sparql_to_polars::{sparql_literal_to_polars_literal_value, sparql_named_node_to_polars_literal_value};
 
 impl Triplestore {
@@ -34,7 +37,7 @@ impl Triplestore {
             }
             Expression::Literal(lit) => {
                 solution_mappings.mappings = solution_mappings.mappings.with_column(
-                    Expr::Literal(sparql_literal_to_polars_literal_value(lit))
+                    Expr::Literal(sparql_literal_to_polars_literal_value(lit)?)
                         .alias(context.as_str()),
                 );
                 solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(lit.datatype().into_owned()));
@@ -489,12 +492,16 @@ impl Triplestore {
                     &exists_context,
                 )?;
                 let SolutionMappings {mappings, columns, mut rdf_node_types } = output_solution_mappings;
-                let mut df = mappings.collect().unwrap();
+                let mut df = mappings
+                    .collect()
+                    .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
                 let exists_df = exists_lf
                     .select([col(&exists_context.as_str())])
                     .unique(None, UniqueKeepStrategy::First)
                     .collect()
-                    .expect("Collect lazy exists error");
+                    .map_err(|x| {
+                        SparqlError::EvaluationError(exists_context.as_str().to_string(), x)
+                    })?;
                 let mut ser = Series::from(
                     df.column(&exists_context.as_str())
                         .unwrap()
@@ -604,7 +611,9 @@ impl Triplestore {
                         )?;
                     output_solution_mappings.mappings = output_solution_mappings.mappings
                         .collect()
-                        .unwrap()
+                        .map_err(|x| {
+                            SparqlError::EvaluationError(arg_context.as_str().to_string(), x)
+                        })?
                         .lazy(); //TODO: workaround for stack overflow - post bug?
                 }
                 match func {
@@ -616,9 +625,10 @@ impl Triplestore {
                                 col(&first_context.as_str())
                                     .dt()
                                     .year()
+                                    .cast(DataType::Int64)
                                     .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::UNSIGNED_INT.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
                     }
                     Function::Month => {
                         assert_eq!(args.len(), 1);
@@ -628,9 +638,10 @@ impl Triplestore {
                                 col(&first_context.as_str())
                                     .dt()
                                     .month()
+                                    .cast(DataType::Int64)
                                     .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::UNSIGNED_INT.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
                     }
                     Function::Day => {
                         assert_eq!(args.len(), 1);
@@ -640,9 +651,10 @@ impl Triplestore {
                                 col(&first_context.as_str())
                                     .dt()
                                     .day()
+                                    .cast(DataType::Int64)
                                     .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::UNSIGNED_INT.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
                     }
                     Function::Hours => {
                         assert_eq!(args.len(), 1);
@@ -652,9 +664,10 @@ impl Triplestore {
                                 col(&first_context.as_str())
                                     .dt()
                                     .hour()
+                                    .cast(DataType::Int64)
                                     .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::UNSIGNED_INT.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
                     }
                     Function::Minutes => {
                         assert_eq!(args.len(), 1);
@@ -664,9 +677,10 @@ impl Triplestore {
                                 col(&first_context.as_str())
                                     .dt()
                                     .minute()
+                                    .cast(DataType::Int64)
                                     .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::UNSIGNED_INT.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
                     }
                     Function::Seconds => {
                         assert_eq!(args.len(), 1);
@@ -690,50 +704,361 @@ impl Triplestore {
                         let existing_type = output_solution_mappings.rdf_node_types.get(first_context.as_str()).unwrap();
                         output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), existing_type.clone());
                     }
-                    Function::Ceil => {
+                    Function::Concat => {
+                        let SolutionMappings { mappings, columns, rdf_node_types } = output_solution_mappings;
+                        let mut inner_df = mappings.collect().map_err(|x| {
+                            SparqlError::EvaluationError(context.as_str().to_string(), x)
+                        })?;
+                        let mut concat_series = if args_contexts.is_empty() {
+                            Series::new("", vec![""; inner_df.height()])
+                        } else {
+                            let series = args_contexts
+                                .iter()
+                                .map(|c| inner_df.column(c.as_str()).unwrap().cast(&DataType::Utf8).unwrap())
+                                .collect::<Vec<Series>>();
+                            concat_str(series.as_slice(), "").unwrap().into_series()
+                        };
+                        concat_series.rename(context.as_str());
+                        inner_df.with_column(concat_series).unwrap();
+                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, rdf_node_types);
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
+                    }
+                    Function::Str => {
                         assert_eq!(args.len(), 1);
                         let first_context = args_contexts.get(0).unwrap();
                         output_solution_mappings.mappings =
                             output_solution_mappings.mappings.with_column(
-                                col(&first_context.as_str()).ceil().alias(context.as_str()),
+                                col(first_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
                     }
-                    Function::Floor => {
+                    Function::Iri => {
                         assert_eq!(args.len(), 1);
                         let first_context = args_contexts.get(0).unwrap();
+                        //IRIs are stored as strings, so the lexical form of the argument is the IRI
                         output_solution_mappings.mappings =
                             output_solution_mappings.mappings.with_column(
-                                col(&first_context.as_str()).floor().alias(context.as_str()),
+                                col(first_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .alias(context.as_str()),
                             );
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::INTEGER.into_owned()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::IRI);
                     }
-                    Function::Concat => {
-                        assert!(args.len() > 1);
-                        let SolutionMappings { mappings, columns, rdf_node_types: datatypes } = output_solution_mappings;
-                        let mut inner_df = mappings.collect().unwrap();
-                        let series = args_contexts
-                            .iter()
-                            .map(|c| inner_df.column(c.as_str()).unwrap().clone())
-                            .collect::<Vec<Series>>();
-                        let mut concat_series =
-                            concat_str(series.as_slice(), "").unwrap().into_series();
-                        concat_series.rename(context.as_str());
-                        inner_df.with_column(concat_series).unwrap();
-                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, datatypes);
+                    Function::StrDt => {
+                        assert_eq!(args.len(), 2);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let datatype = if let Some(Expression::NamedNode(nn)) = args.get(1) {
+                            nn.clone()
+                        } else {
+                            return Err(SparqlError::ConstantArgumentRequired(2, func.to_string(), context.as_str().to_string()));
+                        };
+                        let rdf_node_type = RDFNodeType::Literal(datatype);
+                        let string_expr = col(first_context.as_str()).cast(DataType::Utf8);
+                        //Lexical forms that are not valid for the datatype give unbound values
+                        let expr = match canonical_datatype(&rdf_node_type) {
+                            Some(DataType::Boolean) => when(string_expr.clone().eq(lit("true")).or(string_expr.clone().eq(lit("1"))))
+                                .then(lit(true))
+                                .otherwise(
+                                    when(string_expr.clone().eq(lit("false")).or(string_expr.eq(lit("0"))))
+                                        .then(lit(false))
+                                        .otherwise(Expr::Literal(LiteralValue::Null).cast(DataType::Boolean)),
+                                ),
+                            Some(dt) => string_expr.cast(dt),
+                            //Datatypes without a canonical column type keep the lexical form
+                            None => string_expr,
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), rdf_node_type);
+                    }
+                    Function::BNode => {
+                        assert!(args.len() <= 1);
+                        let SolutionMappings { mappings, columns, rdf_node_types } = output_solution_mappings;
+                        let mut inner_df = mappings.collect().map_err(|x| {
+                            SparqlError::EvaluationError(context.as_str().to_string(), x)
+                        })?;
+                        let strings = args_contexts
+                            .get(0)
+                            .map(|c| inner_df.column(c.as_str()).unwrap().cast(&DataType::Utf8).unwrap());
+                        let mut labels: Utf8Chunked = if let Some(strings) = &strings {
+                            //The same string gives the same blank node within a solution of the query
+                            let namespace = Uuid::new_v5(
+                                &Uuid::NAMESPACE_OID,
+                                self.query_time.map(|t| t.timestamp_nanos()).unwrap_or_default().to_string().as_bytes(),
+                            );
+                            strings
+                                .utf8()
+                                .unwrap()
+                                .into_iter()
+                                .enumerate()
+                                .map(move |(i, s)| {
+                                    s.map(|s| {
+                                        let name = format!("{}:{}", i, s);
+                                        format!("_:{}", Uuid::new_v5(&namespace, name.as_bytes()).simple())
+                                    })
+                                })
+                                .collect()
+                        } else {
+                            (0..inner_df.height())
+                                .map(|_| Some(format!("_:{}", Uuid::new_v4().simple())))
+                                .collect()
+                        };
+                        labels.rename(context.as_str());
+                        inner_df.with_column(labels.into_series()).unwrap();
+                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, rdf_node_types);
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::BlankNode);
+                    }
+                    Function::Uuid | Function::StrUuid => {
+                        assert_eq!(args.len(), 0);
+                        let SolutionMappings { mappings, columns, rdf_node_types } = output_solution_mappings;
+                        let mut inner_df = mappings.collect().map_err(|x| {
+                            SparqlError::EvaluationError(context.as_str().to_string(), x)
+                        })?;
+                        let (prefix, rdf_node_type) = if matches!(func, Function::Uuid) {
+                            ("urn:uuid:", RDFNodeType::IRI)
+                        } else {
+                            ("", RDFNodeType::Literal(xsd::STRING.into_owned()))
+                        };
+                        let mut uuids: Utf8Chunked = (0..inner_df.height())
+                            .map(|_| Some(format!("{}{}", prefix, Uuid::new_v4().hyphenated())))
+                            .collect();
+                        uuids.rename(context.as_str());
+                        inner_df.with_column(uuids.into_series()).unwrap();
+                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, rdf_node_types);
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), rdf_node_type);
+                    }
+                    Function::EncodeForUri => {
+                        assert_eq!(args.len(), 1);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let SolutionMappings { mappings, columns, rdf_node_types } = output_solution_mappings;
+                        let mut inner_df = mappings.collect().map_err(|x| {
+                            SparqlError::EvaluationError(context.as_str().to_string(), x)
+                        })?;
+                        let strings = inner_df.column(first_context.as_str()).unwrap().cast(&DataType::Utf8).unwrap();
+                        let mut encoded: Utf8Chunked = strings
+                            .utf8()
+                            .unwrap()
+                            .into_iter()
+                            .map(|s| s.map(encode_for_uri))
+                            .collect();
+                        encoded.rename(context.as_str());
+                        inner_df.with_column(encoded.into_series()).unwrap();
+                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, rdf_node_types);
                         output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
                     }
-                    Function::Round => {
+                    Function::Ceil | Function::Floor | Function::Round => {
                         assert_eq!(args.len(), 1);
                         let first_context = args_contexts.get(0).unwrap();
+                        let existing_type = output_solution_mappings.rdf_node_types.get(first_context.as_str()).unwrap().clone();
+                        //Integers are already rounded, and the result has the type of the argument
+                        let expr = if existing_type.is_integer() {
+                            col(first_context.as_str())
+                        } else {
+                            let rounded = match func {
+                                Function::Ceil => col(first_context.as_str()).ceil(),
+                                Function::Floor => col(first_context.as_str()).floor(),
+                                //Halves are rounded towards positive infinity
+                                _ => (col(first_context.as_str()) + lit(0.5)).floor(),
+                            };
+                            if existing_type.is_float() {
+                                rounded.cast(DataType::Float32)
+                            } else {
+                                rounded
+                            }
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), existing_type);
+                    }
+                    Function::Now => {
+                        assert_eq!(args.len(), 0);
+                        let now = self.query_time.unwrap_or_else(|| Utc::now().naive_utc());
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(lit(now).alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::DATE_TIME.into_owned()));
+                    }
+                    Function::Lang => {
+                        assert_eq!(args.len(), 1);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let lang_expr = match output_solution_mappings.rdf_node_types.get(first_context.as_str()).unwrap() {
+                            RDFNodeType::Literal(_) | RDFNodeType::MultiType => {
+                                language_tag_expr(args.get(0).unwrap(), first_context, &output_solution_mappings)
+                                    .map(|e| e.fill_null(lit("")))
+                                    .unwrap_or_else(|| lit(""))
+                            }
+                            _ => Expr::Literal(LiteralValue::Null).cast(DataType::Utf8),
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(lang_expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
+                    }
+                    Function::LangMatches => {
+                        assert_eq!(args.len(), 2);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let second_context = args_contexts.get(1).unwrap();
+                        let SolutionMappings { mappings, columns, rdf_node_types } = output_solution_mappings;
+                        let mut inner_df = mappings.collect().map_err(|x| {
+                            SparqlError::EvaluationError(context.as_str().to_string(), x)
+                        })?;
+                        let tags = inner_df.column(first_context.as_str()).unwrap().cast(&DataType::Utf8).unwrap();
+                        let ranges = inner_df.column(second_context.as_str()).unwrap().cast(&DataType::Utf8).unwrap();
+                        let mut matches: BooleanChunked = tags
+                            .utf8()
+                            .unwrap()
+                            .into_iter()
+                            .zip(ranges.utf8().unwrap().into_iter())
+                            .map(|(t, r)| match (t, r) {
+                                (Some(t), Some(r)) => Some(lang_matches(t, r)),
+                                _ => None,
+                            })
+                            .collect();
+                        matches.rename(context.as_str());
+                        inner_df.with_column(matches.into_series()).unwrap();
+                        output_solution_mappings = SolutionMappings::new(inner_df.lazy(), columns, rdf_node_types);
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::BOOLEAN.into_owned()));
+                    }
+                    Function::StrLang => {
+                        assert_eq!(args.len(), 2);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let second_context = args_contexts.get(1).unwrap();
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_columns([
+                                col(first_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .alias(context.as_str()),
+                                col(second_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .alias(&language_tag_column(context.as_str())),
+                            ]);
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
+                    }
+                    Function::Datatype => {
+                        assert_eq!(args.len(), 1);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let lang_expr = language_tag_expr(args.get(0).unwrap(), first_context, &output_solution_mappings);
+                        let datatype_expr = match output_solution_mappings.rdf_node_types.get(first_context.as_str()).unwrap() {
+                            RDFNodeType::Literal(l) => Some(lit(l.as_str())),
+                            RDFNodeType::MultiType => {
+                                //The type column of the variable has the datatype of the literals
+                                if let Expression::Variable(v) = args.get(0).unwrap() {
+                                    let tag_col = col(&multi_type_tag_column(v.as_str()));
+                                    Some(
+                                        when(tag_col.clone().eq(lit("IRI")).or(tag_col.clone().eq(lit("BlankNode"))))
+                                            .then(Expr::Literal(LiteralValue::Null).cast(DataType::Utf8))
+                                            .otherwise(tag_col),
+                                    )
+                                } else {
+                                    None
+                                }
+                            }
+                            _ => None,
+                        };
+                        let datatype_expr = match (datatype_expr, lang_expr) {
+                            (Some(d), Some(l)) => when(l.is_not_null())
+                                .then(lit(rdf::LANG_STRING.as_str()))
+                                .otherwise(d),
+                            (Some(d), None) => d,
+                            (None, _) => Expr::Literal(LiteralValue::Null).cast(DataType::Utf8),
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(datatype_expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::IRI);
+                    }
+                    Function::StrStarts | Function::StrEnds | Function::Contains => {
+                        assert_eq!(args.len(), 2);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let sub = constant_string_argument(args, 1, func, context)?;
+                        let string_expr = col(first_context.as_str()).cast(DataType::Utf8);
+                        let expr = match func {
+                            Function::StrStarts => string_expr.str().starts_with(&sub),
+                            Function::StrEnds => string_expr.str().ends_with(&sub),
+                            _ => string_expr.str().contains_literal(&sub),
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::BOOLEAN.into_owned()));
+                    }
+                    Function::SubStr => {
+                        assert!(args.len() == 2 || args.len() == 3);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let start = constant_integer_argument(args, 1, func, context)?;
+                        let length = if args.len() == 3 {
+                            Some(constant_integer_argument(args, 2, func, context)?)
+                        } else {
+                            None
+                        };
+                        //Positions are counted from 1, and characters before the first position count towards the length
+                        let (offset, length) = if start < 1 {
+                            (0, length.map(|l| (l + start - 1).max(0)))
+                        } else {
+                            (start - 1, length.map(|l| l.max(0)))
+                        };
+                        let lang_expr = language_tag_expr(args.get(0).unwrap(), first_context, &output_solution_mappings);
                         output_solution_mappings.mappings =
                             output_solution_mappings.mappings.with_column(
-                                col(&first_context.as_str())
-                                    .round(0)
+                                col(first_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .map(
+                                        move |s| {
+                                            Ok(s.utf8()?
+                                                .str_slice(offset, length.map(|l| l as u64))?
+                                                .into_series())
+                                        },
+                                        GetOutput::from_type(DataType::Utf8),
+                                    )
                                     .alias(context.as_str()),
                             );
-                        let existing_type = output_solution_mappings.rdf_node_types.get(first_context.as_str()).unwrap();
-                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), existing_type.clone());
+                        if let Some(lang_expr) = lang_expr {
+                            output_solution_mappings.mappings = output_solution_mappings.mappings.with_column(lang_expr.alias(&language_tag_column(context.as_str())));
+                        }
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
+                    }
+                    Function::Regex => {
+                        assert!(args.len() == 2 || args.len() == 3);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let pattern = constant_string_argument(args, 1, func, context)?;
+                        let flags = if args.len() == 3 {
+                            constant_string_argument(args, 2, func, context)?
+                        } else {
+                            "".to_string()
+                        };
+                        let (pattern, literal) = regex_pattern(&pattern, &flags, context)?;
+                        let string_expr = col(first_context.as_str()).cast(DataType::Utf8);
+                        let expr = if literal {
+                            string_expr.str().contains_literal(&pattern)
+                        } else {
+                            string_expr.str().contains(&pattern)
+                        };
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(expr.alias(context.as_str()));
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::BOOLEAN.into_owned()));
+                    }
+                    Function::Replace => {
+                        assert!(args.len() == 3 || args.len() == 4);
+                        let first_context = args_contexts.get(0).unwrap();
+                        let pattern = constant_string_argument(args, 1, func, context)?;
+                        let replacement = constant_string_argument(args, 2, func, context)?;
+                        let flags = if args.len() == 4 {
+                            constant_string_argument(args, 3, func, context)?
+                        } else {
+                            "".to_string()
+                        };
+                        let (pattern, literal) = regex_pattern(&pattern, &flags, context)?;
+                        let lang_expr = language_tag_expr(args.get(0).unwrap(), first_context, &output_solution_mappings);
+                        output_solution_mappings.mappings =
+                            output_solution_mappings.mappings.with_column(
+                                col(first_context.as_str())
+                                    .cast(DataType::Utf8)
+                                    .str()
+                                    .replace_all(lit(pattern), lit(replacement), literal)
+                                    .alias(context.as_str()),
+                            );
+                        if let Some(lang_expr) = lang_expr {
+                            output_solution_mappings.mappings = output_solution_mappings.mappings.with_column(lang_expr.alias(&language_tag_column(context.as_str())));
+                        }
+                        output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
                     }
                     Function::Custom(nn) => {
                         let iri = nn.as_str();
@@ -759,19 +1084,23 @@ impl Triplestore {
                                 );
                             output_solution_mappings.rdf_node_types.insert(context.as_str().to_string(), RDFNodeType::Literal(xsd::STRING.into_owned()));
                         }  else {
-                            todo!("{:?}", nn)
+                            return Err(SparqlError::UnsupportedFunction(
+                                iri.to_string(),
+                                context.as_str().to_string(),
+                            ));
                         }
                     }
                     _ => {
                         todo!()
                     }
                 }
-                output_solution_mappings.mappings = output_solution_mappings.mappings.drop_columns(
-                    args_contexts
-                        .iter()
-                        .map(|x| x.as_str())
-                        .collect::<Vec<&str>>(),
-                );
+                let mut drop_cols: Vec<String> = args_contexts.iter().map(|x| x.as_str().to_string()).collect();
+                for c in &args_contexts {
+                    if output_solution_mappings.has_language_tags(c.as_str()) {
+                        drop_cols.push(language_tag_column(c.as_str()));
+                    }
+                }
+                output_solution_mappings.mappings = output_solution_mappings.mappings.drop_columns(drop_cols);
                 output_solution_mappings
             }
         };
@@ -779,6 +1108,92 @@ impl Triplestore {
     }
 }
 
+//The language tags of the values of an argument, if it can have any
+fn language_tag_expr(arg: &Expression, arg_context: &Context, solution_mappings: &SolutionMappings) -> Option<Expr> {
+    match arg {
+        Expression::Variable(v) if solution_mappings.has_language_tags(v.as_str()) => {
+            Some(col(&language_tag_column(v.as_str())))
+        }
+        Expression::Literal(l) => l.language().map(lit),
+        _ if solution_mappings.has_language_tags(arg_context.as_str()) => {
+            Some(col(&language_tag_column(arg_context.as_str())))
+        }
+        _ => None,
+    }
+}
+
+fn constant_string_argument(args: &[Expression], i: usize, function: &Function, context: &Context) -> Result<String, SparqlError> {
+    if let Some(Expression::Literal(l)) = args.get(i) {
+        Ok(l.value().to_string())
+    } else {
+        Err(SparqlError::ConstantArgumentRequired(i + 1, function.to_string(), context.as_str().to_string()))
+    }
+}
+
+fn constant_integer_argument(args: &[Expression], i: usize, function: &Function, context: &Context) -> Result<i64, SparqlError> {
+    if let Some(Expression::Literal(l)) = args.get(i) {
+        //Positions that are not integers are rounded
+        if let Ok(f) = l.value().parse::<f64>() {
+            return Ok(f.round() as i64);
+        }
+    }
+    Err(SparqlError::ConstantArgumentRequired(i + 1, function.to_string(), context.as_str().to_string()))
+}
+
+//A pattern with the flags of the SPARQL regex functions, and whether the pattern should be matched literally
+fn regex_pattern(pattern: &str, flags: &str, context: &Context) -> Result<(String, bool), SparqlError> {
+    let mut inline_flags = String::new();
+    let mut literal = false;
+    for f in flags.chars() {
+        match f {
+            'i' | 'm' | 's' | 'x' => inline_flags.push(f),
+            'q' => literal = true,
+            _ => return Err(SparqlError::InvalidRegexFlags(flags.to_string(), context.as_str().to_string())),
+        }
+    }
+    if inline_flags.is_empty() {
+        Ok((pattern.to_string(), literal))
+    } else if literal {
+        Ok((format!("(?{}){}", inline_flags, escape_regex(pattern)), false))
+    } else {
+        Ok((format!("(?{}){}", inline_flags, pattern), false))
+    }
+}
+
+fn escape_regex(pattern: &str) -> String {
+    let mut escaped = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if "\\.+*?()|[]{}^$#&-~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+//Percent encodes all characters except the unreserved characters of RFC3986
+pub(crate) fn encode_for_uri(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+//Basic filtering of RFC4647, as used by the SPARQL function langMatches
+fn lang_matches(tag: &str, range: &str) -> bool {
+    if range == "*" {
+        return !tag.is_empty();
+    }
+    let tag = tag.to_lowercase();
+    let range = range.to_lowercase();
+    tag == range || tag.starts_with(&format!("{}-", range))
+}
+
 fn binop_type(left_type:&RDFNodeType, right_type:&RDFNodeType) -> RDFNodeType {
     if let (RDFNodeType::Literal(left_lit), RDFNodeType::Literal(right_lit)) = (left_type, right_type) {
         if left_lit.as_ref() == xsd::DOUBLE {
@@ -11,6 +11,7 @@ mod union;
 mod values;
 mod triple;
 mod path;
+mod graph;
 
 use super::Triplestore;
 use crate::triplestore::sparql::errors::SparqlError;
@@ -30,16 +31,22 @@ impl Triplestore {
 
         match graph_pattern {
             GraphPattern::Bgp { patterns } => {
+                let bound_variables = solution_mappings
+                    .as_ref()
+                    .map(|sm| sm.columns.clone())
+                    .unwrap_or_default();
                 let mut updated_solution_mappings = solution_mappings;
                 let bgp_context = context.extension_with(PathEntry::BGP);
-                for tp in patterns {
+                for tp in self.order_triple_patterns(patterns, bound_variables) {
                     updated_solution_mappings = Some(self.lazy_triple_pattern(
                         updated_solution_mappings,
                         tp,
                         &bgp_context,
                     )?)
                 }
-                Ok(updated_solution_mappings.unwrap())
+                Ok(updated_solution_mappings.unwrap_or_else(|| {
+                    SolutionMappings::single_empty_solution(bgp_context.as_str())
+                }))
             },
             GraphPattern::Path { subject, path, object } => {
                 self.lazy_path(subject, path, object, solution_mappings, context)
@@ -58,8 +65,8 @@ impl Triplestore {
             GraphPattern::Union { left, right } => {
                 self.lazy_union(left, right, solution_mappings, context)
             }
-            GraphPattern::Graph { name: _, inner: _ } => {
-                unimplemented!("Graphs not supported")
+            GraphPattern::Graph { name, inner } => {
+                self.lazy_graph(name, inner, solution_mappings, context)
             }
             GraphPattern::Extend {
                 inner,
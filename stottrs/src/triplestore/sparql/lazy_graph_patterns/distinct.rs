@@ -14,11 +14,11 @@ impl Triplestore {
         context: &Context,
     ) -> Result<SolutionMappings, SparqlError> {
         debug!("Processing distinct graph pattern");
-        let SolutionMappings { mappings, columns, rdf_node_types: datatypes } = self.lazy_graph_pattern(
+        let SolutionMappings { mappings, columns, rdf_node_types } = self.lazy_graph_pattern(
             inner,
             solution_mappings,
             &context.extension_with(PathEntry::DistinctInner),
         )?;
-        Ok( SolutionMappings::new(mappings.unique_stable(None, UniqueKeepStrategy::First), columns, datatypes))
+        Ok( SolutionMappings::new(mappings.unique_stable(None, UniqueKeepStrategy::First), columns, rdf_node_types))
     }
 }
@@ -4,7 +4,9 @@ use spargebra::algebra::{Expression, GraphPattern};
 use log::debug;
 use crate::triplestore::sparql::errors::SparqlError;
 use crate::triplestore::sparql::query_context::{Context, PathEntry};
-use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::sparql::solution_mapping::{language_tag_column, multi_type_tag_column, SolutionMappings};
+use polars::prelude::{col, lit};
 
 impl Triplestore {
     pub(crate) fn lazy_extend(
@@ -24,9 +26,38 @@ impl Triplestore {
 
         output_solution_mappings = self.lazy_expression(expression, output_solution_mappings, &expression_context)?;
         output_solution_mappings.mappings = output_solution_mappings.mappings.rename([expression_context.as_str()], &[variable.as_str()]);
+        if let Expression::Variable(v) = expression {
+            if output_solution_mappings.has_language_tags(v.as_str()) {
+                output_solution_mappings.mappings = output_solution_mappings.mappings.with_column(
+                    col(&language_tag_column(v.as_str())).alias(&language_tag_column(variable.as_str())),
+                );
+            }
+            if output_solution_mappings.rdf_node_types.get(v.as_str()) == Some(&RDFNodeType::MultiType) {
+                output_solution_mappings.mappings = output_solution_mappings.mappings.with_column(
+                    col(&multi_type_tag_column(v.as_str())).alias(&multi_type_tag_column(variable.as_str())),
+                );
+            }
+        } else if let Some(language) = language_tag_of_literal(expression) {
+            output_solution_mappings.mappings = output_solution_mappings.mappings.with_column(
+                lit(language).alias(&language_tag_column(variable.as_str())),
+            );
+        } else if output_solution_mappings.has_language_tags(expression_context.as_str()) {
+            output_solution_mappings.mappings = output_solution_mappings.mappings.rename(
+                [language_tag_column(expression_context.as_str())],
+                [language_tag_column(variable.as_str())],
+            );
+        }
         let existing_rdf_node_type = output_solution_mappings.rdf_node_types.remove(expression_context.as_str()).unwrap();
         output_solution_mappings.rdf_node_types.insert(variable.as_str().to_string(), existing_rdf_node_type);
         output_solution_mappings.columns.insert(variable.as_str().to_string());
         Ok(output_solution_mappings)
     }
 }
+
+fn language_tag_of_literal(expression: &Expression) -> Option<&str> {
+    if let Expression::Literal(l) = expression {
+        l.language()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,83 @@
+use super::Triplestore;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::sparql::errors::SparqlError;
+use crate::triplestore::sparql::query_context::{Context, PathEntry};
+use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use log::debug;
+use polars::prelude::{col, concat, lit};
+use spargebra::algebra::GraphPattern;
+use spargebra::term::NamedNodePattern;
+use std::collections::{HashMap, HashSet};
+
+impl Triplestore {
+    pub(crate) fn lazy_graph(
+        &self,
+        name: &NamedNodePattern,
+        inner: &GraphPattern,
+        solution_mappings: Option<SolutionMappings>,
+        context: &Context,
+    ) -> Result<SolutionMappings, SparqlError> {
+        debug!("Processing graph graph pattern");
+        let inner_context = context.extension_with(PathEntry::GraphInner);
+        match name {
+            NamedNodePattern::NamedNode(nn) => self.lazy_graph_pattern(
+                inner,
+                solution_mappings,
+                &inner_context.with_graph(Some(nn.as_str().to_string())),
+            ),
+            NamedNodePattern::Variable(v) => {
+                let graphs = self.named_graphs();
+                if graphs.is_empty() {
+                    //No named graphs, so we produce the right columns with no rows
+                    let mut sm =
+                        self.lazy_graph_pattern(inner, solution_mappings, &inner_context)?;
+                    if !sm.columns.contains(v.as_str()) {
+                        sm.mappings = sm.mappings.with_column(lit("").alias(v.as_str()));
+                        sm.columns.insert(v.as_str().to_string());
+                        sm.rdf_node_types
+                            .insert(v.as_str().to_string(), RDFNodeType::IRI);
+                    }
+                    sm.mappings = sm.mappings.filter(lit(false));
+                    return Ok(sm);
+                }
+
+                let mut mappings = vec![];
+                let mut columns = HashSet::new();
+                let mut rdf_node_types = HashMap::new();
+                for g in graphs {
+                    let SolutionMappings {
+                        mappings: mut graph_mappings,
+                        columns: mut graph_columns,
+                        rdf_node_types: mut graph_rdf_node_types,
+                    } = self.lazy_graph_pattern(
+                        inner,
+                        solution_mappings.clone(),
+                        &inner_context.with_graph(Some(g.clone())),
+                    )?;
+                    if graph_columns.contains(v.as_str()) {
+                        graph_mappings =
+                            graph_mappings.filter(col(v.as_str()).eq(lit(g.as_str())));
+                    } else {
+                        graph_mappings =
+                            graph_mappings.with_column(lit(g.as_str()).alias(v.as_str()));
+                        graph_columns.insert(v.as_str().to_string());
+                        graph_rdf_node_types.insert(v.as_str().to_string(), RDFNodeType::IRI);
+                    }
+                    mappings.push(graph_mappings);
+                    columns.extend(graph_columns);
+                    for (k, t) in graph_rdf_node_types {
+                        if !rdf_node_types.contains_key(&k) {
+                            rdf_node_types.insert(k, t);
+                        }
+                    }
+                }
+                let output_mappings = concat(mappings, true, true).expect("Concat problem");
+                Ok(SolutionMappings::new(
+                    output_mappings,
+                    columns,
+                    rdf_node_types,
+                ))
+            }
+        }
+    }
+}
@@ -45,11 +45,14 @@ impl Triplestore {
                 aggregate_inner_contexts.push(aggregate_inner_context);
             }
         }
-        let SolutionMappings { mut mappings, mut columns, rdf_node_types: mut datatypes } = output_solution_mappings;
-        let grouped_mappings = mappings.groupby(by.as_slice());
-
-        mappings = grouped_mappings
-            .agg(aggregate_expressions.as_slice())
+        let SolutionMappings { mut mappings, mut columns, mut rdf_node_types } = output_solution_mappings;
+        //Aggregation without GROUP BY gives a single group
+        if by.is_empty() {
+            mappings = mappings.select(aggregate_expressions.as_slice());
+        } else {
+            mappings = mappings.groupby(by.as_slice()).agg(aggregate_expressions.as_slice());
+        }
+        mappings = mappings
             .drop_columns(
                 aggregate_inner_contexts
                     .iter()
@@ -57,7 +60,7 @@ impl Triplestore {
                     .collect::<Vec<&str>>(),
             );
         for (k,v) in new_rdf_node_types {
-            datatypes.insert(k.as_str().to_string(),v);
+            rdf_node_types.insert(k.as_str().to_string(),v);
         }
         columns.clear();
         for v in variables {
@@ -66,6 +69,6 @@ impl Triplestore {
         for (v, _) in aggregates {
             columns.insert(v.as_str().to_string());
         }
-        Ok(SolutionMappings::new(mappings, columns, datatypes))
+        Ok(SolutionMappings::new(mappings, columns, rdf_node_types))
     }
 }
@@ -26,38 +26,36 @@ impl Triplestore {
             .map(|i| context.extension_with(PathEntry::OrderByExpression(i as u16)))
             .collect();
         let mut asc_ordering = vec![];
-        let mut inner_contexts = vec![];
+        let mut sort_columns = vec![];
         for i in 0..expression.len() {
-            let (ordering_solution_mappings, reverse, inner_context) = self
+            let inner_context = order_expression_contexts.get(i).unwrap();
+            let (ordering_solution_mappings, asc, expression_sort_columns) = self
                 .lazy_order_expression(
                     expression.get(i).unwrap(),
                     output_solution_mappings,
-                    order_expression_contexts.get(i).unwrap(),
+                    inner_context,
                 )?;
             output_solution_mappings = ordering_solution_mappings;
-            inner_contexts.push(inner_context);
-            asc_ordering.push(reverse);
+            for c in expression_sort_columns {
+                sort_columns.push(c);
+                asc_ordering.push(asc);
+            }
         }
         let SolutionMappings {
             mut mappings,
             columns,
-            rdf_node_types: datatypes,
+            rdf_node_types,
         } = output_solution_mappings;
 
         mappings = mappings.sort_by_exprs(
-            inner_contexts
+            sort_columns
                 .iter()
-                .map(|c| col(c.as_str()))
+                .map(|c| col(c))
                 .collect::<Vec<Expr>>(),
             asc_ordering.iter().map(|asc| !asc).collect::<Vec<bool>>(),
             true,
         );
-        mappings = mappings.drop_columns(
-            inner_contexts
-                .iter()
-                .map(|x| x.as_str())
-                .collect::<Vec<&str>>(),
-        );
-        Ok(SolutionMappings::new(mappings, columns, datatypes))
+        mappings = mappings.drop_columns(sort_columns);
+        Ok(SolutionMappings::new(mappings, columns, rdf_node_types))
     }
 }
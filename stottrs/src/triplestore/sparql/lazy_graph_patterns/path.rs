@@ -62,7 +62,7 @@ impl Triplestore {
         let out_soo;
         let out_dt;
 
-        let cat_df_map = self.create_unique_cat_dfs(ppe, Some(subject), Some(object))?;
+        let cat_df_map = self.create_unique_cat_dfs(ppe, Some(subject), Some(object), context)?;
         let max_index = find_max_index(cat_df_map.values());
         if create_sparse {
             let SparsePathReturn { sparmat, soo, dt } =
@@ -99,11 +99,12 @@ impl Triplestore {
                 .unwrap();
             out_df = out_df.select(["subject", "object"]).unwrap();
         } else {
-            let DFPathReturn { df, soo, dt } = df_path(ppe, &cat_df_map, max_index);
+            let DFPathReturn { df, soo, dt } = df_path(ppe, &cat_df_map, max_index, context)?;
             out_df = df;
             out_soo = soo;
             out_dt = dt;
         }
+        out_df = filter_constant_endpoints(out_df, subject, object, context)?;
         let mut var_cols = vec![];
         if let TermPattern::Variable(v) = subject {
             var_cols.push(v.as_str().to_string());
@@ -178,17 +179,17 @@ impl Triplestore {
 
             return Ok(mappings);
         } else {
-            let mut datatypes = HashMap::new();
+            let mut rdf_node_types = HashMap::new();
             if let TermPattern::Variable(v) = subject {
-                datatypes.insert(v.as_str().to_string(), RDFNodeType::IRI);
+                rdf_node_types.insert(v.as_str().to_string(), RDFNodeType::IRI);
             }
             if let TermPattern::Variable(v) = object {
-                datatypes.insert(v.as_str().to_string(), RDFNodeType::IRI);
+                rdf_node_types.insert(v.as_str().to_string(), RDFNodeType::IRI);
             }
             return Ok(SolutionMappings {
                 mappings: out_df.lazy(),
                 columns: var_cols.into_iter().map(|x| x.to_string()).collect(),
-                rdf_node_types: datatypes,
+                rdf_node_types,
             });
         }
     }
@@ -198,10 +199,11 @@ impl Triplestore {
         ppe: &PropertyPathExpression,
         subject: Option<&TermPattern>,
         object: Option<&TermPattern>,
+        context: &Context,
     ) -> Result<HashMap<String, DataFrame>, SparqlError> {
         match ppe {
             PropertyPathExpression::NamedNode(nn) => {
-                let df = self.get_single_nn_df(nn.as_str(), subject, object)?;
+                let df = self.get_single_nn_df(nn.as_str(), subject, object, context)?;
                 if let Some(df) = df {
                     let unique_cat_df = df_with_cats(df)
                         .unique(None, UniqueKeepStrategy::First)
@@ -217,36 +219,35 @@ impl Triplestore {
                 }
             }
             PropertyPathExpression::Reverse(inner) => {
-                self.create_unique_cat_dfs(inner, object, subject)
+                self.create_unique_cat_dfs(inner, object, subject, context)
             }
             PropertyPathExpression::Sequence(left, right) => {
-                let mut left_df_map = self.create_unique_cat_dfs(left, subject, None)?;
-                let right_df_map = self.create_unique_cat_dfs(right, None, object)?;
-                left_df_map.extend(right_df_map);
-                Ok(left_df_map)
+                let left_df_map = self.create_unique_cat_dfs(left, subject, None, context)?;
+                let right_df_map = self.create_unique_cat_dfs(right, None, object, context)?;
+                Ok(merge_cat_df_maps(left_df_map, right_df_map))
             }
             PropertyPathExpression::Alternative(left, right) => {
-                let mut left_df_map =
-                    self.create_unique_cat_dfs(left, subject.clone(), object.clone())?;
-                let right_df_map = self.create_unique_cat_dfs(right, subject, object)?;
-                left_df_map.extend(right_df_map);
-                Ok(left_df_map)
-            }
-            PropertyPathExpression::ZeroOrMore(inner) => {
-                self.create_unique_cat_dfs(inner, subject, object)
+                let left_df_map = self.create_unique_cat_dfs(left, subject, object, context)?;
+                let right_df_map = self.create_unique_cat_dfs(right, subject, object, context)?;
+                Ok(merge_cat_df_maps(left_df_map, right_df_map))
             }
-            PropertyPathExpression::OneOrMore(inner) => {
-                self.create_unique_cat_dfs(inner, subject, object)
-            }
-            PropertyPathExpression::ZeroOrOne(inner) => {
-                self.create_unique_cat_dfs(inner, subject, object)
+            //The intermediate steps of the path may have any subject and object
+            PropertyPathExpression::ZeroOrMore(inner)
+            | PropertyPathExpression::OneOrMore(inner)
+            | PropertyPathExpression::ZeroOrOne(inner) => {
+                self.create_unique_cat_dfs(inner, None, None, context)
             }
             PropertyPathExpression::NegatedPropertySet(nns) => {
                 let lookup: Vec<_> = nns.iter().map(|x| x.as_str().to_string()).collect();
                 let mut dfs = vec![];
-                for nn in self.df_map.keys() {
+                let predicates: Vec<&String> = if let Some(m) = self.graph_df_map(&context.graph) {
+                    m.keys().collect()
+                } else {
+                    vec![]
+                };
+                for nn in predicates {
                     if !lookup.contains(nn) {
-                        let df = self.get_single_nn_df(nn, subject, object)?;
+                        let df = self.get_single_nn_df(nn, subject, object, context)?;
                         if let Some(df) = df {
                             dfs.push(df_with_cats(df));
                         }
@@ -275,8 +276,9 @@ impl Triplestore {
         nn: &str,
         subject: Option<&TermPattern>,
         object: Option<&TermPattern>,
+        context: &Context,
     ) -> Result<Option<DataFrame>, SparqlError> {
-        let map_opt = self.df_map.get(nn);
+        let map_opt = self.graph_df_map(&context.graph).and_then(|m| m.get(nn));
         if let Some(m) = map_opt {
             if m.is_empty() {
                 panic!("Empty map should never happen");
@@ -302,7 +304,7 @@ impl Triplestore {
                     } else if let TermPattern::Literal(l) = subject {
                         lf = lf.filter(
                             col("subject")
-                                .eq(Expr::Literal(sparql_literal_to_polars_literal_value(l))),
+                                .eq(Expr::Literal(sparql_literal_to_polars_literal_value(l)?)),
                         )
                     }
                 }
@@ -315,11 +317,14 @@ impl Triplestore {
                     } else if let TermPattern::Literal(l) = object {
                         lf = lf.filter(
                             col("object")
-                                .eq(Expr::Literal(sparql_literal_to_polars_literal_value(l))),
+                                .eq(Expr::Literal(sparql_literal_to_polars_literal_value(l)?)),
                         )
                     }
                 }
-                Ok(Some(lf.collect().unwrap()))
+                let df = lf
+                    .collect()
+                    .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))?;
+                Ok(Some(df))
             }
         } else {
             Ok(None)
@@ -327,6 +332,51 @@ impl Triplestore {
     }
 }
 
+//The same predicate may occur several times in a path with different constraints, so we keep the union
+fn merge_cat_df_maps(
+    mut left: HashMap<String, DataFrame>,
+    right: HashMap<String, DataFrame>,
+) -> HashMap<String, DataFrame> {
+    for (k, df) in right {
+        if let Some(existing) = left.remove(&k) {
+            let merged = concat_df([&existing, &df])
+                .unwrap()
+                .unique(None, UniqueKeepStrategy::First)
+                .unwrap();
+            left.insert(k, merged);
+        } else {
+            left.insert(k, df);
+        }
+    }
+    left
+}
+
+//Constant endpoints are not enforced when computing paths with repetition, so we filter afterwards
+fn filter_constant_endpoints(
+    df: DataFrame,
+    subject: &TermPattern,
+    object: &TermPattern,
+    context: &Context,
+) -> Result<DataFrame, SparqlError> {
+    let mut lf = df.lazy();
+    for (c, tp) in [("subject", subject), ("object", object)] {
+        let value = match tp {
+            TermPattern::NamedNode(nn) => Some(sparql_named_node_to_polars_literal_value(nn)),
+            TermPattern::Literal(l) => Some(sparql_literal_to_polars_literal_value(l)?),
+            _ => None,
+        };
+        if let Some(value) = value {
+            lf = lf.filter(
+                col(c)
+                    .cast(DataType::Utf8)
+                    .eq(Expr::Literal(value).cast(DataType::Utf8)),
+            );
+        }
+    }
+    lf.collect()
+        .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))
+}
+
 fn find_lookup(map: &HashMap<String, DataFrame>) -> DataFrame {
     let mut all_values = vec![];
     for (k, v) in map {
@@ -358,8 +408,10 @@ fn df_path(
     ppe: &PropertyPathExpression,
     cat_df_map: &HashMap<String, DataFrame>,
     max_index: u32,
-) -> DFPathReturn {
-    match ppe {
+    context: &Context,
+) -> Result<DFPathReturn, SparqlError> {
+    let evaluation_error = |x| SparqlError::EvaluationError(context.as_str().to_string(), x);
+    Ok(match ppe {
         PropertyPathExpression::NamedNode(nn) => {
             let df = cat_df_map.get(nn.as_str()).unwrap();
             DFPathReturn {
@@ -369,12 +421,12 @@ fn df_path(
             }
         }
         PropertyPathExpression::Reverse(inner) => {
-            let DFPathReturn { df, soo, dt } = df_path(inner, cat_df_map, max_index);
+            let DFPathReturn { df, soo, dt } = df_path(inner, cat_df_map, max_index, context)?;
             let df = df
                 .lazy()
                 .rename(["subject", "object"], ["object", "subject"])
                 .collect()
-                .unwrap();
+                .map_err(evaluation_error)?;
             DFPathReturn {
                 df,
                 soo: soo.flip(),
@@ -386,19 +438,23 @@ fn df_path(
                 df: mut df_left,
                 soo: _,
                 dt: _,
-            } = df_path(left, cat_df_map, max_index);
+            } = df_path(left, cat_df_map, max_index, context)?;
             let DFPathReturn {
                 df: mut df_right,
                 soo: soo_right,
                 dt: dt_right,
-            } = df_path(left, cat_df_map, max_index);
+            } = df_path(right, cat_df_map, max_index, context)?;
             df_left.rename("object", "on").unwrap();
             df_right.rename("subject", "on").unwrap();
             df_left = df_left.sort(vec!["on"], vec![false]).unwrap();
             df_right = df_right.sort(vec!["on"], vec![false]).unwrap();
             let df = df_left
                 .join(&df_right, ["on"], ["on"], JoinType::Inner, None)
-                .unwrap();
+                .map_err(evaluation_error)?
+                .select(["subject", "object"])
+                .unwrap()
+                .unique(None, UniqueKeepStrategy::First)
+                .map_err(evaluation_error)?;
             DFPathReturn {
                 df,
                 soo: soo_right,
@@ -410,17 +466,17 @@ fn df_path(
                 df: df_left,
                 soo: soo_left,
                 dt: dt_left,
-            } = df_path(left, cat_df_map, max_index);
+            } = df_path(left, cat_df_map, max_index, context)?;
             let DFPathReturn {
                 df: df_right,
                 soo: _soo_right,
                 dt: _dt_right,
-            } = df_path(left, cat_df_map, max_index);
+            } = df_path(right, cat_df_map, max_index, context)?;
             let df = df_left
                 .vstack(&df_right)
-                .unwrap()
+                .map_err(evaluation_error)?
                 .unique(None, UniqueKeepStrategy::First)
-                .unwrap();
+                .map_err(evaluation_error)?;
             DFPathReturn {
                 df,
                 soo: soo_left,
@@ -428,19 +484,21 @@ fn df_path(
             }
         }
         PropertyPathExpression::ZeroOrOne(inner) => {
-            let DFPathReturn { df, soo, dt } = df_path(inner, cat_df_map, max_index);
-            let mut all_subjects = Series::from_iter(0..max_index)
-                .cast(&DataType::Categorical(None))
-                .unwrap();
+            let DFPathReturn { df, soo, dt } = df_path(inner, cat_df_map, max_index, context)?;
+            //Every node of the relevant predicates is connected to itself by a path of length zero
+            let mut all_subjects = find_lookup(cat_df_map)
+                .column("value")
+                .unwrap()
+                .clone();
             all_subjects.rename("subject");
-            let mut all_objects = Series::from_iter(0..max_index)
-                .cast(&DataType::Categorical(None))
-                .unwrap();
+            let mut all_objects = all_subjects.clone();
             all_objects.rename("object");
             let id_df = DataFrame::new(vec![all_subjects, all_objects]).unwrap();
-            let df = concat_df([&df, &id_df]).unwrap();
+            let df = concat_df([&df, &id_df]).map_err(evaluation_error)?;
             DFPathReturn {
-                df: df.unique(None, UniqueKeepStrategy::First).unwrap(),
+                df: df
+                    .unique(None, UniqueKeepStrategy::First)
+                    .map_err(evaluation_error)?,
                 soo,
                 dt,
             }
@@ -456,7 +514,7 @@ fn df_path(
         _ => {
             panic!("Should never happen")
         }
-    }
+    })
 }
 
 fn df_with_cats(df: DataFrame) -> DataFrame {
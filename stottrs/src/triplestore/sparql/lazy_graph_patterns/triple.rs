@@ -2,19 +2,24 @@ use super::Triplestore;
 use crate::mapping::RDFNodeType;
 use crate::triplestore::sparql::errors::SparqlError;
 use crate::triplestore::sparql::query_context::Context;
-use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use crate::triplestore::sparql::solution_mapping::{
+    language_tag_column, language_tag_join_columns, multi_type_tag_column, rdf_node_type_tag,
+    SolutionMappings,
+};
+use crate::triplestore::{TripleTable, LANGUAGE_TAG_COLUMN};
 use crate::triplestore::sparql::sparql_to_polars::{
     sparql_literal_to_polars_literal_value, sparql_named_node_to_polars_literal_value,
 };
 use log::warn;
 use oxrdf::vocab::xsd;
 use polars::prelude::IntoLazy;
-use polars::prelude::{col, concat, lit, Expr};
+use polars::prelude::{col, concat, lit, Expr, LazyFrame, LiteralValue};
 use polars_core::datatypes::DataType;
 use polars_core::frame::DataFrame;
 use polars_core::prelude::JoinType;
 use polars_core::series::Series;
-use spargebra::term::{NamedNodePattern, TermPattern, TriplePattern};
+use spargebra::term::{Literal, NamedNodePattern, TermPattern, TriplePattern};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::collections::{HashMap, HashSet};
 
 impl Triplestore {
@@ -22,241 +27,479 @@ impl Triplestore {
         &self,
         solution_mappings: Option<SolutionMappings>,
         triple_pattern: &TriplePattern,
-        _context: &Context,
+        context: &Context,
     ) -> Result<SolutionMappings, SparqlError> {
-        match &triple_pattern.predicate {
+        //Only the tables with the type of a constant object or an object variable bound to a single type can match
+        let bound_type = if let (TermPattern::Variable(v), Some(mappings)) =
+            (&triple_pattern.object, &solution_mappings)
+        {
+            mappings.rdf_node_types.get(v.as_str()).cloned()
+        } else {
+            None
+        };
+        let may_match = |dt: &RDFNodeType| {
+            object_may_have_type(&triple_pattern.object, dt)
+                && match &bound_type {
+                    None | Some(RDFNodeType::None) | Some(RDFNodeType::MultiType) => true,
+                    Some(bound) => bound == dt,
+                }
+        };
+        let df_map = self.graph_df_map(&context.graph);
+        let tables: Vec<(&str, &RDFNodeType, &TripleTable)> = match &triple_pattern.predicate {
             NamedNodePattern::NamedNode(n) => {
-                let map_opt = self.df_map.get(n.as_str());
-                if let Some(m) = map_opt {
+                if let Some(m) = df_map.and_then(|m| m.get(n.as_str())) {
                     if m.is_empty() {
                         panic!("Empty map should never happen");
-                    } else if m.len() > 1 {
-                        todo!("Multiple datatypes not supported yet")
-                    } else {
-                        let (dt, tt) = m.iter().next().unwrap();
-                        assert!(tt.unique, "Should be deduplicated");
-                        let mut lf = concat(
-                            tt.get_lazy_frames()
-                                .map_err(|x| SparqlError::TripleTableReadError(x))?,
-                            true,
-                            true,
-                        )
-                        .unwrap()
-                        .select([col("subject"), col("object")]);
-                        let mut var_cols = vec![];
-                        let mut str_cols = vec![];
-                        match &triple_pattern.subject {
-                            TermPattern::NamedNode(nn) => {
-                                lf = lf
-                                    .filter(col("subject").eq(Expr::Literal(
-                                        sparql_named_node_to_polars_literal_value(nn),
-                                    )))
-                                    .drop_columns(["subject"])
-                            }
-                            TermPattern::Literal(lit) => {
-                                lf = lf
-                                    .filter(col("subject").eq(Expr::Literal(
-                                        sparql_literal_to_polars_literal_value(lit),
-                                    )))
-                                    .drop_columns(["subject"])
-                            }
-                            TermPattern::Variable(var) => {
-                                lf = lf.rename(["subject"], [var.as_str()]);
-                                str_cols.push(var.as_str().to_string());
-                                var_cols.push(var.as_str().to_string());
-                            }
-                            _ => {
-                                todo!("No support for {}", &triple_pattern.object)
-                            }
-                        }
-                        match &triple_pattern.object {
-                            TermPattern::NamedNode(nn) => {
-                                lf = lf
-                                    .filter(col("object").eq(Expr::Literal(
-                                        sparql_named_node_to_polars_literal_value(nn),
-                                    )))
-                                    .drop_columns(["object"])
-                            }
-                            TermPattern::Literal(lit) => {
-                                lf = lf
-                                    .filter(col("object").eq(Expr::Literal(
-                                        sparql_literal_to_polars_literal_value(lit),
-                                    )))
-                                    .drop_columns(["object"])
-                            }
-                            TermPattern::Variable(var) => {
-                                lf = lf.rename(["object"], [var.as_str()]);
-                                var_cols.push(var.as_str().to_string());
-                                match dt {
-                                    RDFNodeType::IRI => {
-                                        str_cols.push(var.as_str().to_string());
-                                    }
-                                    RDFNodeType::Literal(lit) => {
-                                        if lit.as_ref() == xsd::STRING {
-                                            str_cols.push(var.as_str().to_string());
-                                        }
-                                    }
-                                    _ => {
-                                        panic!("No support for datatype {:?}", dt)
-                                    }
-                                }
-                            }
-                            TermPattern::BlankNode(bn) => {
-                                lf = lf.rename(["object"], [bn.as_str()]);
-                                var_cols.push(bn.as_str().to_string());
-                                match dt {
-                                    RDFNodeType::IRI => {
-                                        str_cols.push(bn.as_str().to_string());
-                                    }
-                                    RDFNodeType::Literal(lit) => {
-                                        if lit.as_ref() == xsd::STRING {
-                                            str_cols.push(bn.as_str().to_string());
-                                        }
-                                    }
-                                    _ => {
-                                        panic!("No support for datatype {:?}", dt)
-                                    }
-                                }
-                            }
-                        }
-                        if let Some(mut mappings) = solution_mappings {
-                            let join_cols: Vec<String> = var_cols
-                                .clone()
-                                .into_iter()
-                                .filter(|x| mappings.columns.contains(x))
-                                .collect();
+                    }
+                    m.iter()
+                        .filter(|(dt, _)| may_match(dt))
+                        .map(|(dt, tt)| (n.as_str(), dt, tt))
+                        .collect()
+                } else {
+                    vec![]
+                }
+            }
+            NamedNodePattern::Variable(_) => df_map
+                .iter()
+                .flat_map(|m| m.iter())
+                .flat_map(|(predicate, m)| {
+                    m.iter()
+                        .filter(|(dt, _)| may_match(dt))
+                        .map(move |(dt, tt)| (predicate.as_str(), dt, tt))
+                })
+                .collect(),
+        };
+        if tables.is_empty() {
+            warn!("Could not find triples for predicate {:?}", triple_pattern.predicate);
+            return empty_triple_pattern(solution_mappings, triple_pattern, context);
+        }
+        let (mut lf, var_cols, mut str_cols, dt) = tables_lazy_frame(tables, triple_pattern)?;
+        if let Some(mut mappings) = solution_mappings {
+            let mut join_cols: Vec<String> = var_cols
+                .clone()
+                .into_iter()
+                .filter(|x| mappings.columns.contains(x))
+                .collect();
+
+            for s in str_cols.drain(..) {
+                if join_cols.contains(&s) {
+                    lf = lf.with_column(col(&s).cast(DataType::Categorical(None)));
+                    mappings.mappings = mappings
+                        .mappings
+                        .with_column(col(&s).cast(DataType::Categorical(None)));
+                }
+            }
 
-                            for s in str_cols {
-                                if join_cols.contains(&s) {
-                                    lf = lf.with_column(col(&s).cast(DataType::Categorical(None)));
-                                    mappings.mappings = mappings
-                                        .mappings
-                                        .with_column(col(&s).cast(DataType::Categorical(None)));
-                                }
-                            }
+            //Values of variables with several types must also have the same type
+            if let (TermPattern::Variable(v), RDFNodeType::MultiType) =
+                (&triple_pattern.object, &dt)
+            {
+                if join_cols.contains(&v.as_str().to_string())
+                    && mappings.rdf_node_types.get(v.as_str())
+                        == Some(&RDFNodeType::MultiType)
+                {
+                    join_cols.push(multi_type_tag_column(v.as_str()));
+                }
+            }
 
-                            let join_on: Vec<Expr> = join_cols.iter().map(|x| col(x)).collect();
+            join_cols.extend(language_tag_join_columns(
+                &mappings.mappings,
+                &lf,
+                &join_cols,
+            ));
 
-                            if join_on.is_empty() {
-                                mappings.mappings = mappings.mappings.join(
-                                    lf,
-                                    join_on.as_slice(),
-                                    join_on.as_slice(),
-                                    JoinType::Cross,
-                                );
-                            } else {
-                                let join_col_exprs: Vec<Expr> =
-                                    join_cols.iter().map(|x| col(x)).collect();
-                                let all_false = [false].repeat(join_cols.len());
-                                lf = lf.sort_by_exprs(
-                                    join_col_exprs.as_slice(),
-                                    all_false.as_slice(),
-                                    false,
-                                );
-                                mappings.mappings = mappings.mappings.sort_by_exprs(
-                                    join_col_exprs.as_slice(),
-                                    all_false.as_slice(),
-                                    false,
-                                );
-                                mappings.mappings = mappings.mappings.join(
-                                    lf,
-                                    join_on.as_slice(),
-                                    join_on.as_slice(),
-                                    JoinType::Inner,
-                                );
-                            }
-                            //Update mapping columns
-                            for c in &var_cols {
-                                mappings.columns.insert(c.to_string());
-                            }
-                            if let TermPattern::Variable(v) = &triple_pattern.subject {
-                                mappings
-                                    .rdf_node_types
-                                    .insert(v.as_str().to_string(), RDFNodeType::IRI);
-                            }
-                            if let TermPattern::Variable(v) = &triple_pattern.object {
-                                mappings
-                                    .rdf_node_types
-                                    .insert(v.as_str().to_string(), dt.clone());
-                            }
+            let join_on: Vec<Expr> = join_cols.iter().map(|x| col(x)).collect();
 
-                            return Ok(mappings);
-                        } else {
-                            let mut datatypes = HashMap::new();
-                            if let TermPattern::Variable(v) = &triple_pattern.subject {
-                                datatypes.insert(v.as_str().to_string(), RDFNodeType::IRI);
-                            }
-                            if let TermPattern::Variable(v) = &triple_pattern.object {
-                                datatypes.insert(v.as_str().to_string(), dt.clone());
-                            }
-                            return Ok(SolutionMappings {
-                                mappings: lf,
-                                columns: var_cols.into_iter().map(|x| x.to_string()).collect(),
-                                rdf_node_types: datatypes,
-                            });
-                        }
-                    }
-                } else {
-                    warn!("Could not find triples for predicate {:?}", n);
-                    let mut out_columns = HashSet::new();
-                    let mut out_datatypes = HashMap::new();
-                    if let TermPattern::Variable(v) = &triple_pattern.subject {
-                        out_columns.insert(v.as_str().to_string());
-                        out_datatypes.insert(v.as_str().to_string(), RDFNodeType::None);
-                    }
-                    if let TermPattern::Variable(v) = &triple_pattern.object {
-                        out_columns.insert(v.as_str().to_string());
-                        out_datatypes.insert(v.as_str().to_string(), RDFNodeType::None);
+            if join_on.is_empty() {
+                mappings.mappings = mappings.mappings.join(
+                    lf,
+                    join_on.as_slice(),
+                    join_on.as_slice(),
+                    JoinType::Cross,
+                );
+            } else {
+                let join_col_exprs: Vec<Expr> =
+                    join_cols.iter().map(|x| col(x)).collect();
+                let all_false = [false].repeat(join_cols.len());
+                lf = lf.sort_by_exprs(
+                    join_col_exprs.as_slice(),
+                    all_false.as_slice(),
+                    false,
+                );
+                mappings.mappings = mappings.mappings.sort_by_exprs(
+                    join_col_exprs.as_slice(),
+                    all_false.as_slice(),
+                    false,
+                );
+                mappings.mappings = mappings.mappings.join(
+                    lf,
+                    join_on.as_slice(),
+                    join_on.as_slice(),
+                    JoinType::Inner,
+                );
+            }
+            //Update mapping columns
+            for c in &var_cols {
+                mappings.columns.insert(c.to_string());
+            }
+            mappings
+                .rdf_node_types
+                .extend(pattern_rdf_node_types(triple_pattern, dt));
+            Ok(mappings)
+        } else {
+            Ok(SolutionMappings {
+                mappings: lf,
+                columns: var_cols.into_iter().map(|x| x.to_string()).collect(),
+                rdf_node_types: pattern_rdf_node_types(triple_pattern, dt),
+            })
+        }
+    }
+}
+
+fn pattern_rdf_node_types(
+    triple_pattern: &TriplePattern,
+    object_type: RDFNodeType,
+) -> HashMap<String, RDFNodeType> {
+    let mut rdf_node_types = HashMap::new();
+    if let TermPattern::Variable(v) = &triple_pattern.subject {
+        rdf_node_types.insert(v.as_str().to_string(), RDFNodeType::IRI);
+    }
+    if let NamedNodePattern::Variable(v) = &triple_pattern.predicate {
+        rdf_node_types.insert(v.as_str().to_string(), RDFNodeType::IRI);
+    }
+    if let TermPattern::Variable(v) = &triple_pattern.object {
+        rdf_node_types.insert(v.as_str().to_string(), object_type);
+    }
+    rdf_node_types
+}
+
+//The union of the triples of the tables matching the pattern. The tables are read in parallel, as patterns with a
+//predicate variable may match thousands of tables.
+fn tables_lazy_frame(
+    mut tables: Vec<(&str, &RDFNodeType, &TripleTable)>,
+    triple_pattern: &TriplePattern,
+) -> Result<(LazyFrame, Vec<String>, Vec<String>, RDFNodeType), SparqlError> {
+    tables.sort_by(|(p1, dt1, _), (p2, dt2, _)| {
+        p1.cmp(p2)
+            .then(rdf_node_type_tag(dt1).cmp(&rdf_node_type_tag(dt2)))
+    });
+    let mut dts: Vec<&RDFNodeType> = tables.iter().map(|(_, dt, _)| *dt).collect();
+    dts.dedup();
+    let dt = if dts.iter().all(|dt| dt == &dts[0]) {
+        dts[0].clone()
+    } else {
+        RDFNodeType::MultiType
+    };
+    let object_col = match &triple_pattern.object {
+        TermPattern::Variable(v) => Some(v.as_str()),
+        TermPattern::BlankNode(bn) => Some(bn.as_str()),
+        _ => None,
+    };
+    let has_language_tags = object_col.is_some() && dts.iter().any(|dt| dt.is_lit_type(xsd::STRING));
+    let table_lfs: Vec<Result<(LazyFrame, Vec<String>, Vec<String>), SparqlError>> = tables
+        .par_iter()
+        .map(|(predicate, table_dt, tt)| {
+            let (mut lf, mut var_cols, mut str_cols) =
+                triple_table_lazy_frame(tt, table_dt, triple_pattern)?;
+            if let NamedNodePattern::Variable(v) = &triple_pattern.predicate {
+                lf = lf.with_column(lit(*predicate).alias(v.as_str()));
+                var_cols.push(v.as_str().to_string());
+                str_cols.push(v.as_str().to_string());
+            }
+            if dt == RDFNodeType::MultiType {
+                //The objects of each type are stored as lexical forms, tagged with their type
+                let object_col = object_col.expect("Constant objects only match one type");
+                let mut cast_cols = vec![
+                    lit(rdf_node_type_tag(table_dt)).alias(&multi_type_tag_column(object_col)),
+                ];
+                for c in &var_cols {
+                    cast_cols.push(col(c).cast(DataType::Utf8));
+                }
+                if has_language_tags && !table_dt.is_lit_type(xsd::STRING) {
+                    cast_cols.push(
+                        Expr::Literal(LiteralValue::Null)
+                            .cast(DataType::Utf8)
+                            .alias(&language_tag_column(object_col)),
+                    );
+                }
+                lf = lf.with_columns(cast_cols);
+                if !str_cols.iter().any(|c| c == object_col) {
+                    str_cols.push(object_col.to_string());
+                }
+            }
+            Ok((lf, var_cols, str_cols))
+        })
+        .collect();
+    let mut lfs = vec![];
+    let mut var_cols = vec![];
+    let mut str_cols = vec![];
+    for r in table_lfs {
+        let (lf, table_var_cols, table_str_cols) = r?;
+        lfs.push(lf);
+        var_cols = table_var_cols;
+        str_cols = table_str_cols;
+    }
+    let lf = if lfs.len() == 1 {
+        lfs.remove(0)
+    } else {
+        concat(lfs, true, true).unwrap()
+    };
+    Ok((lf, var_cols, str_cols, dt))
+}
+
+//Triples of the table matching the pattern, with a column for each variable of the pattern
+fn triple_table_lazy_frame(
+    tt: &TripleTable,
+    dt: &RDFNodeType,
+    triple_pattern: &TriplePattern,
+) -> Result<(LazyFrame, Vec<String>, Vec<String>), SparqlError> {
+    assert!(tt.unique, "Should be deduplicated");
+    let indexed_lfs = match &triple_pattern.object {
+        TermPattern::NamedNode(nn) if dt == &RDFNodeType::IRI => {
+            tt.get_lazy_frames_with_object(nn.as_str())
+        }
+        TermPattern::Literal(l)
+            if dt.is_lit_type(xsd::STRING)
+                && (l.datatype() == xsd::STRING || l.language().is_some()) =>
+        {
+            tt.get_lazy_frames_with_object(l.value())
+        }
+        _ => None,
+    };
+    let lfs = if let Some(lfs) = indexed_lfs {
+        lfs
+    } else if let TermPattern::NamedNode(nn) = &triple_pattern.subject {
+        //Chunks are pruned using their subject ranges
+        let lfs = tt
+            .get_lazy_frames_with_subject(nn.as_str())
+            .map_err(|x| SparqlError::TripleTableReadError(x))?;
+        if lfs.is_empty() {
+            let lf = tt
+                .get_lazy_frames()
+                .map_err(|x| SparqlError::TripleTableReadError(x))?
+                .remove(0);
+            vec![lf.limit(0)]
+        } else {
+            lfs
+        }
+    } else {
+        tt.get_lazy_frames()
+            .map_err(|x| SparqlError::TripleTableReadError(x))?
+    };
+    let mut select_cols = vec![col("subject"), col("object")];
+    //The language tags of strings follow the object variable
+    if dt.is_lit_type(xsd::STRING) {
+        match &triple_pattern.object {
+            TermPattern::Variable(v) => {
+                select_cols.push(col(LANGUAGE_TAG_COLUMN).alias(&language_tag_column(v.as_str())));
+            }
+            TermPattern::BlankNode(bn) => {
+                select_cols.push(col(LANGUAGE_TAG_COLUMN).alias(&language_tag_column(bn.as_str())));
+            }
+            _ => {}
+        }
+    }
+    //Filters on constants are applied to each chunk, so that they are pushed down into the scans of cached parquet files
+    let lfs = if let Some(constant_filter) = constant_filter(triple_pattern)? {
+        lfs.into_iter()
+            .map(|lf| lf.filter(constant_filter.clone()))
+            .collect()
+    } else {
+        lfs
+    };
+    let mut lf = concat(
+        lfs,
+        true,
+        true,
+    )
+    .unwrap()
+    .select(select_cols.as_slice());
+    let mut var_cols = vec![];
+    let mut str_cols = vec![];
+    match &triple_pattern.subject {
+        TermPattern::NamedNode(nn) => {
+            lf = lf
+                .filter(col("subject").eq(Expr::Literal(
+                    sparql_named_node_to_polars_literal_value(nn),
+                )))
+                .drop_columns(["subject"])
+        }
+        TermPattern::Literal(lit) => {
+            lf = lf
+                .filter(col("subject").eq(Expr::Literal(
+                    sparql_literal_to_polars_literal_value(lit)?,
+                )))
+                .drop_columns(["subject"])
+        }
+        TermPattern::Variable(var) => {
+            lf = lf.rename(["subject"], [var.as_str()]);
+            str_cols.push(var.as_str().to_string());
+            var_cols.push(var.as_str().to_string());
+        }
+        TermPattern::BlankNode(bn) => {
+            lf = lf.rename(["subject"], [bn.as_str()]);
+            str_cols.push(bn.as_str().to_string());
+            var_cols.push(bn.as_str().to_string());
+        }
+    }
+    match &triple_pattern.object {
+        TermPattern::NamedNode(nn) => {
+            lf = lf
+                .filter(col("object").eq(Expr::Literal(
+                    sparql_named_node_to_polars_literal_value(nn),
+                )))
+                .drop_columns(["object"])
+        }
+        TermPattern::Literal(_) => {
+            //Already filtered by the constant filter, which also compares language tags
+            lf = lf.drop_columns(["object"])
+        }
+        TermPattern::Variable(var) => {
+            lf = lf.rename(["object"], [var.as_str()]);
+            var_cols.push(var.as_str().to_string());
+            match dt {
+                RDFNodeType::IRI | RDFNodeType::BlankNode => {
+                    str_cols.push(var.as_str().to_string());
+                }
+                RDFNodeType::Literal(lit) => {
+                    if lit.as_ref() == xsd::STRING {
+                        str_cols.push(var.as_str().to_string());
                     }
-                    let mut variables: Vec<&String> = out_columns.iter().collect();
-                    variables.sort();
-                    if let Some(SolutionMappings {
-                        mut mappings,
-                        mut columns,
-                        rdf_node_types: mut datatypes,
-                    }) = solution_mappings
-                    {
-                        mappings = mappings.filter(lit(false));
-                        let overlap: Vec<&String> = columns.intersection(&out_columns).collect();
-                        if overlap.is_empty() {
-                            return Ok(SolutionMappings::new(mappings, columns, datatypes));
-                        }
-                        let mut series = vec![];
-                        for c in &variables {
-                            if !columns.contains(*c) {
-                                series.push(Series::new_empty(&c, &DataType::Null));
-                            }
-                        }
-                        let join_on: Vec<Expr> = overlap.into_iter().map(|x| col(x)).collect();
-                        let out_lf = DataFrame::new(series).unwrap().lazy();
-                        mappings = mappings.join(
-                            out_lf,
-                            join_on.as_slice(),
-                            join_on.as_slice(),
-                            JoinType::Cross,
-                        );
-                        for (k, v) in out_datatypes {
-                            if !datatypes.contains_key(&k) {
-                                datatypes.insert(k, v);
-                            }
-                        }
-                        columns.extend(out_columns);
-                        Ok(SolutionMappings::new(mappings, columns, datatypes))
-                    } else {
-                        let mut series = vec![];
-                        for var in variables {
-                            let mut new_series = Series::new_empty(var, &DataType::Null);
-                            new_series.rename(var);
-                            series.push(new_series);
-                        }
-                        let out_lf = DataFrame::new(series).unwrap().lazy();
-                        Ok(SolutionMappings::new(out_lf, out_columns, out_datatypes))
+                }
+                _ => {
+                    panic!("No support for datatype {:?}", dt)
+                }
+            }
+        }
+        TermPattern::BlankNode(bn) => {
+            lf = lf.rename(["object"], [bn.as_str()]);
+            var_cols.push(bn.as_str().to_string());
+            match dt {
+                RDFNodeType::IRI | RDFNodeType::BlankNode => {
+                    str_cols.push(bn.as_str().to_string());
+                }
+                RDFNodeType::Literal(lit) => {
+                    if lit.as_ref() == xsd::STRING {
+                        str_cols.push(bn.as_str().to_string());
                     }
                 }
+                _ => {
+                    panic!("No support for datatype {:?}", dt)
+                }
             }
-            NamedNodePattern::Variable(..) => {
-                todo!("Not supported yet")
+        }
+    }
+    Ok((lf, var_cols, str_cols))
+}
+
+fn object_may_have_type(object: &TermPattern, dt: &RDFNodeType) -> bool {
+    match object {
+        TermPattern::NamedNode(_) => dt == &RDFNodeType::IRI,
+        TermPattern::Literal(l) if l.language().is_some() => dt.is_lit_type(xsd::STRING),
+        TermPattern::Literal(l) => dt.is_lit_type(l.datatype()),
+        TermPattern::BlankNode(_) | TermPattern::Variable(_) => true,
+    }
+}
+
+fn empty_triple_pattern(
+    solution_mappings: Option<SolutionMappings>,
+    triple_pattern: &TriplePattern,
+    context: &Context,
+) -> Result<SolutionMappings, SparqlError> {
+    let mut out_columns = HashSet::new();
+    let mut out_datatypes = HashMap::new();
+    if let TermPattern::Variable(v) = &triple_pattern.subject {
+        out_columns.insert(v.as_str().to_string());
+        out_datatypes.insert(v.as_str().to_string(), RDFNodeType::None);
+    }
+    if let NamedNodePattern::Variable(v) = &triple_pattern.predicate {
+        out_columns.insert(v.as_str().to_string());
+        out_datatypes.insert(v.as_str().to_string(), RDFNodeType::None);
+    }
+    if let TermPattern::Variable(v) = &triple_pattern.object {
+        out_columns.insert(v.as_str().to_string());
+        out_datatypes.insert(v.as_str().to_string(), RDFNodeType::None);
+    }
+    let mut variables: Vec<&String> = out_columns.iter().collect();
+    variables.sort();
+    if let Some(SolutionMappings {
+        mut mappings,
+        columns,
+        rdf_node_types,
+    }) = solution_mappings
+    {
+        mappings = mappings.filter(lit(false));
+        let overlap: Vec<&String> = columns.intersection(&out_columns).collect();
+        if overlap.is_empty() {
+            return Ok(SolutionMappings::new(mappings, columns, rdf_node_types));
+        }
+        let mut series = vec![];
+        for c in &variables {
+            if !columns.contains(*c) {
+                series.push(Series::new_empty(&c, &DataType::Null));
             }
         }
+        let join_on: Vec<Expr> = overlap.into_iter().map(|x| col(x)).collect();
+        let out_lf = DataFrame::new(series).unwrap().lazy();
+        mappings = mappings.join(
+            out_lf,
+            join_on.as_slice(),
+            join_on.as_slice(),
+            JoinType::Cross,
+        );
+        let mut output_solution_mappings =
+            SolutionMappings::new(mappings, columns, rdf_node_types);
+        output_solution_mappings.merge_datatypes_compatible(out_datatypes, context)?;
+        output_solution_mappings.merge_columns(out_columns);
+        Ok(output_solution_mappings)
+    } else {
+        let mut series = vec![];
+        for var in variables {
+            let mut new_series = Series::new_empty(var, &DataType::Null);
+            new_series.rename(var);
+            series.push(new_series);
+        }
+        let out_lf = DataFrame::new(series).unwrap().lazy();
+        Ok(SolutionMappings::new(out_lf, out_columns, out_datatypes))
     }
 }
+
+fn constant_filter(triple_pattern: &TriplePattern) -> Result<Option<Expr>, SparqlError> {
+    let constant = |term: &TermPattern| -> Result<Option<Expr>, SparqlError> {
+        Ok(match term {
+            TermPattern::NamedNode(nn) => Some(Expr::Literal(
+                sparql_named_node_to_polars_literal_value(nn),
+            )),
+            TermPattern::Literal(l) => Some(Expr::Literal(sparql_literal_to_polars_literal_value(l)?)),
+            _ => None,
+        })
+    };
+    let subject_filter = constant(&triple_pattern.subject)?.map(|s| col("subject").eq(s));
+    let object_filter = match &triple_pattern.object {
+        TermPattern::Literal(l) => Some(literal_object_filter(l)?),
+        o => constant(o)?.map(|o| col("object").eq(o)),
+    };
+    Ok(match (subject_filter, object_filter) {
+        (Some(s), Some(o)) => Some(s.and(o)),
+        (s, o) => s.or(o),
+    })
+}
+
+//Strings with a language tag only match literals with the same language tag, and simple literals only match
+//strings without a language tag. Language tags are compared case-insensitively.
+fn literal_object_filter(l: &Literal) -> Result<Expr, SparqlError> {
+    Ok(if let Some(language) = l.language() {
+        col("object").eq(lit(l.value())).and(
+            col(LANGUAGE_TAG_COLUMN)
+                .str()
+                .to_lowercase()
+                .eq(lit(language.to_lowercase())),
+        )
+    } else if l.datatype() == xsd::STRING {
+        col("object")
+            .eq(lit(l.value()))
+            .and(col(LANGUAGE_TAG_COLUMN).is_null())
+    } else {
+        col("object").eq(Expr::Literal(sparql_literal_to_polars_literal_value(l)?))
+    })
+}
@@ -1,10 +1,11 @@
 use super::Triplestore;
-use polars::prelude::concat;
+use polars::prelude::{col, concat, DataType, SchemaRef};
 use spargebra::algebra::GraphPattern;
 use log::debug;
 use crate::triplestore::sparql::errors::SparqlError;
 use crate::triplestore::sparql::query_context::{Context, PathEntry};
 use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use crate::triplestore::harmonize::{find_schema_mismatches, find_supertypes, SchemaMismatch};
 
 impl Triplestore {
     pub(crate) fn lazy_union(
@@ -18,11 +19,7 @@ impl Triplestore {
         let left_context = context.extension_with(PathEntry::UnionLeftSide);
         let right_context = context.extension_with(PathEntry::UnionRightSide);
 
-        let SolutionMappings {
-            mappings: left_mappings,
-            columns: mut left_columns,
-            rdf_node_types: mut left_datatypes,
-        } = self
+        let mut left_solution_mappings = self
             .lazy_graph_pattern(
                 &left,
                 solution_mappings.clone(),
@@ -33,7 +30,7 @@ impl Triplestore {
         let SolutionMappings {
             mappings: right_mappings,
             columns: right_columns,
-            rdf_node_types: mut right_datatypes,
+            rdf_node_types: right_rdf_node_types,
         } = self
             .lazy_graph_pattern(
                 right,
@@ -42,20 +39,46 @@ impl Triplestore {
             )
             ?;
 
-        let output_mappings =
-            concat(vec![left_mappings, right_mappings], true, true).expect("Concat problem");
-        left_columns.extend(right_columns);
-        for (v, dt) in right_datatypes.drain() {
-            if let Some(left_dt) = left_datatypes.get(&v) {
-                assert_eq!(&dt, left_dt);
-            } else {
-                left_datatypes.insert(v, dt);
+        left_solution_mappings.merge_datatypes_compatible(right_rdf_node_types, context)?;
+        left_solution_mappings.merge_columns(right_columns);
+        let SolutionMappings {
+            mappings: left_mappings,
+            columns,
+            rdf_node_types,
+        } = left_solution_mappings;
+        let mut to_concat = vec![left_mappings, right_mappings];
+        let schemas: Vec<SchemaRef> = to_concat.iter().map(|lf| lf.schema().unwrap()).collect();
+        let mismatches: Vec<SchemaMismatch> = find_schema_mismatches(&schemas)
+            .into_iter()
+            .filter(|m| m.expected != DataType::Null)
+            .collect();
+        if !mismatches.is_empty() {
+            if !self.harmonize_dtypes {
+                return Err(SparqlError::SchemaMismatch(
+                    context.as_str().to_string(),
+                    mismatches,
+                ));
             }
+            let supertypes = find_supertypes(&schemas, &mismatches).map_err(|m| {
+                SparqlError::SchemaMismatch(context.as_str().to_string(), vec![m])
+            })?;
+            to_concat = to_concat
+                .into_iter()
+                .map(|lf| {
+                    lf.with_columns(
+                        supertypes
+                            .iter()
+                            .map(|(c, dt)| col(c).cast(dt.clone()))
+                            .collect::<Vec<_>>(),
+                    )
+                })
+                .collect();
         }
+        let output_mappings = concat(to_concat, true, true).expect("Concat problem");
         Ok(SolutionMappings::new(
             output_mappings,
-            left_columns,
-            left_datatypes,
+            columns,
+            rdf_node_types,
         ))
     }
 }
@@ -19,7 +19,7 @@ impl Triplestore {
         for i in 0..variables.len() {
             col_vecs.insert(i, vec![]);
         }
-        let mut datatypes = HashMap::new();
+        let mut rdf_node_types = HashMap::new();
         for (i,row) in bindings.iter().enumerate() {
             for (j, col) in row.iter().enumerate() {
                 if let Some(gt) = col {
@@ -27,9 +27,9 @@ impl Triplestore {
                     match gt {
                         GroundTerm::NamedNode(nn) => {
                             if i == 0 {
-                                datatypes.insert(j, RDFNodeType::IRI);
+                                rdf_node_types.insert(j, RDFNodeType::IRI);
                             } else {
-                                if datatypes.get(&j).unwrap() != &RDFNodeType::IRI {
+                                if rdf_node_types.get(&j).unwrap() != &RDFNodeType::IRI {
                                     todo!("No support yet for values of same variables having different types")
                                 }
                             }
@@ -38,9 +38,9 @@ impl Triplestore {
                         GroundTerm::Literal(lit) => {
                             let dt = lit.datatype().into_owned();
                             if i == 0 {
-                                datatypes.insert(j, RDFNodeType::Literal(dt.clone()));
+                                rdf_node_types.insert(j, RDFNodeType::Literal(dt.clone()));
                             } else {
-                                let existing =  datatypes.get(&j).unwrap();
+                                let existing =  rdf_node_types.get(&j).unwrap();
                                 match existing {
                                     RDFNodeType::Literal(l) => {
                                         if l != &dt {
@@ -58,8 +58,8 @@ impl Triplestore {
                         _ => {unimplemented!()}
                     }
                     if i + 1 == bindings.len() {
-                        if !datatypes.contains_key(&j) {
-                            datatypes.insert(j, RDFNodeType::None);
+                        if !rdf_node_types.contains_key(&j) {
+                            rdf_node_types.insert(j, RDFNodeType::None);
                         }
                     }
                     col_vecs.get_mut(&j).unwrap().push(AnyValue::Null);
@@ -78,7 +78,7 @@ impl Triplestore {
             for v in variables {
                 mappings.columns.insert(v.as_str().to_string());
             }
-            for (k,v) in datatypes {
+            for (k,v) in rdf_node_types {
                 let var = variables.get(k).unwrap();
                 mappings.rdf_node_types.insert(var.as_str().to_string(), v);
             }
@@ -90,7 +90,7 @@ impl Triplestore {
             Ok(mappings)
         } else {
             let mut out_datatypes = HashMap::new();
-            for (k,v) in datatypes {
+            for (k,v) in rdf_node_types {
                 let var = variables.get(k).unwrap();
                 out_datatypes.insert(var.as_str().to_string(), v);
             }
@@ -1,33 +1,94 @@
-use spargebra::algebra::OrderExpression;
+use polars::prelude::{col, lit, when, DataType, Expr, LiteralValue};
+use spargebra::algebra::{Expression, OrderExpression};
+use crate::mapping::{numeric_datatypes, RDFNodeType};
 use crate::triplestore::sparql::errors::SparqlError;
 use crate::triplestore::sparql::query_context::{Context, PathEntry};
-use crate::triplestore::sparql::solution_mapping::SolutionMappings;
+use crate::triplestore::sparql::solution_mapping::{multi_type_tag_column, SolutionMappings};
 use super::Triplestore;
 
+//Ranks of the kinds of terms, unbound values are ordered first and literals last as in SPARQL
+const UNBOUND_RANK: i32 = 0;
+const BLANK_NODE_RANK: i32 = 1;
+const IRI_RANK: i32 = 2;
+const NUMERIC_RANK: i32 = 3;
+const LITERAL_RANK: i32 = 4;
+
 impl Triplestore {
+    /// Evaluates the order expression, and returns whether it is ascending and the columns to sort on.
+    /// The kind of term is sorted on first, so that unbound values, blank nodes, IRIs and literals are not mixed,
+    /// and numbers of different datatypes are compared by value.
     pub fn lazy_order_expression(
         &self,
         oexpr: &OrderExpression,
         solution_mappings: SolutionMappings,
         context: &Context,
-    ) -> Result<(SolutionMappings, bool, Context), SparqlError> {
-        match oexpr {
-            OrderExpression::Asc(expr) => {
-                let inner_context = context.extension_with(PathEntry::OrderingOperation);
-                Ok((
-                    self.lazy_expression(expr, solution_mappings, &inner_context)?,
-                    true,
-                    inner_context,
-                ))
+    ) -> Result<(SolutionMappings, bool, Vec<String>), SparqlError> {
+        let (expr, ascending) = match oexpr {
+            OrderExpression::Asc(expr) => (expr, true),
+            OrderExpression::Desc(expr) => (expr, false),
+        };
+        let inner_context = context.extension_with(PathEntry::OrderingOperation);
+        let mut output_solution_mappings =
+            self.lazy_expression(expr, solution_mappings, &inner_context)?;
+        let value = col(inner_context.as_str());
+        let rdf_node_type = output_solution_mappings
+            .rdf_node_types
+            .get(inner_context.as_str())
+            .unwrap();
+        let multi_type_tag = match (rdf_node_type, expr) {
+            (RDFNodeType::MultiType, Expression::Variable(v)) => {
+                Some(col(&multi_type_tag_column(v.as_str())))
             }
-            OrderExpression::Desc(expr) => {
-                let inner_context = context.extension_with(PathEntry::OrderingOperation);
-                Ok((
-                    self.lazy_expression(expr, solution_mappings, &inner_context)?,
-                    false,
-                    inner_context,
-                ))
+            _ => None,
+        };
+        let rank = if let Some(tag) = &multi_type_tag {
+            let is_numeric = numeric_datatypes()
+                .map(|dt| tag.clone().eq(lit(dt.as_str())))
+                .reduce(|a, b| a.or(b))
+                .unwrap();
+            when(tag.clone().eq(lit("BlankNode")))
+                .then(lit(BLANK_NODE_RANK))
+                .otherwise(
+                    when(tag.clone().eq(lit("IRI")))
+                        .then(lit(IRI_RANK))
+                        .otherwise(
+                            when(is_numeric)
+                                .then(lit(NUMERIC_RANK))
+                                .otherwise(lit(LITERAL_RANK)),
+                        ),
+                )
+        } else {
+            match rdf_node_type {
+                RDFNodeType::IRI | RDFNodeType::BlankNode => {
+                    when(value.clone().cast(DataType::Utf8).str().starts_with("_:"))
+                        .then(lit(BLANK_NODE_RANK))
+                        .otherwise(lit(IRI_RANK))
+                }
+                t if t.is_numeric() => lit(NUMERIC_RANK),
+                _ => lit(LITERAL_RANK),
             }
+        };
+        let rank = when(value.clone().is_null())
+            .then(lit(UNBOUND_RANK))
+            .otherwise(rank);
+
+        let rank_column = format!("{}_rank", inner_context.as_str());
+        let mut sort_columns = vec![rank_column.clone()];
+        let mut sort_exprs = vec![rank.clone().alias(&rank_column)];
+        if multi_type_tag.is_some() {
+            //Values of several types are lexical forms, so numbers are compared by value in a separate column
+            let number_column = format!("{}_number", inner_context.as_str());
+            sort_exprs.push(
+                when(rank.eq(lit(NUMERIC_RANK)))
+                    .then(value.cast(DataType::Float64))
+                    .otherwise(Expr::Literal(LiteralValue::Null).cast(DataType::Float64))
+                    .alias(&number_column),
+            );
+            sort_columns.push(number_column);
         }
+        sort_columns.push(inner_context.as_str().to_string());
+        output_solution_mappings.mappings =
+            output_solution_mappings.mappings.with_columns(sort_exprs);
+        Ok((output_solution_mappings, ascending, sort_columns))
     }
 }
@@ -261,6 +261,7 @@ impl fmt::Display for PathEntry {
 pub struct Context {
     string_rep: String,
     pub path: Vec<PathEntry>,
+    pub graph: Option<String>,
 }
 
 impl Context {
@@ -436,6 +437,7 @@ impl Context {
         Context {
             string_rep: "".to_string(),
             path: vec![],
+            graph: None,
         }
     }
 
@@ -460,6 +462,16 @@ impl Context {
         let entry_rep = p.to_string();
         string_rep += entry_rep.as_str();
         path.push(p);
-        Context { path, string_rep }
+        Context {
+            path,
+            string_rep,
+            graph: self.graph.clone(),
+        }
+    }
+
+    pub fn with_graph(&self, graph: Option<String>) -> Context {
+        let mut ctx = self.clone();
+        ctx.graph = graph;
+        ctx
     }
 }
@@ -0,0 +1,77 @@
+use super::{QueryResult, Triplestore};
+use crate::triplestore::sparql::errors::SparqlError;
+use polars::frame::DataFrame;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Result of a SELECT query kept by the triplestore, so that it can be paged through without running the query again.
+pub(crate) struct QueryCursor {
+    df: DataFrame,
+    ttl: Duration,
+    expires: Instant,
+}
+
+/// Rows of a query result from an offset, with the total number of rows so that a result browser can show the page count.
+#[derive(Debug, Clone)]
+pub struct ResultPage {
+    pub df: DataFrame,
+    pub offset: usize,
+    pub total_rows: usize,
+}
+
+impl Triplestore {
+    /// Runs a SELECT query and keeps the result under the returned handle.
+    /// The result is a snapshot: triples added after the cursor is opened are not in it.
+    /// The cursor is evicted when it has not been used for the time to live.
+    pub fn open_query_cursor(&mut self, query: &str, ttl: Duration) -> Result<String, SparqlError> {
+        self.evict_expired_query_cursors();
+        let df = match self.query(query)? {
+            QueryResult::Select(df) => df,
+            QueryResult::Construct(_) | QueryResult::Describe(_) => {
+                return Err(SparqlError::QueryTypeNotSupported)
+            }
+        };
+        let handle = Uuid::new_v4().simple().to_string();
+        self.query_cursors.insert(
+            handle.clone(),
+            QueryCursor {
+                df,
+                ttl,
+                expires: Instant::now() + ttl,
+            },
+        );
+        Ok(handle)
+    }
+
+    /// Fetches at most limit rows from the offset of the result kept under the handle, and renews its time to live.
+    pub fn fetch_query_page(
+        &mut self,
+        handle: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<ResultPage, SparqlError> {
+        self.evict_expired_query_cursors();
+        let cursor = self
+            .query_cursors
+            .get_mut(handle)
+            .ok_or_else(|| SparqlError::UnknownQueryCursor(handle.to_string()))?;
+        cursor.expires = Instant::now() + cursor.ttl;
+        let total_rows = cursor.df.height();
+        let offset = offset.min(total_rows);
+        Ok(ResultPage {
+            df: cursor.df.slice(offset as i64, limit),
+            offset,
+            total_rows,
+        })
+    }
+
+    /// Releases the result kept under the handle, returns false if there was no such cursor.
+    pub fn close_query_cursor(&mut self, handle: &str) -> bool {
+        self.query_cursors.remove(handle).is_some()
+    }
+
+    fn evict_expired_query_cursors(&mut self) {
+        let now = Instant::now();
+        self.query_cursors.retain(|_, c| c.expires > now);
+    }
+}
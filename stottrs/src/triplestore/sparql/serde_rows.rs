@@ -0,0 +1,105 @@
+use super::Triplestore;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::sparql::errors::SparqlError;
+use crate::triplestore::sparql::sparql_results::variable_terms;
+use oxrdf::vocab::xsd;
+use oxrdf::Term;
+use polars::frame::DataFrame;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// A term of a solution, serialized as in the SPARQL 1.1 Query Results JSON format,
+/// e.g. {"type":"literal","value":"1","datatype":"http://www.w3.org/2001/XMLSchema#long"}.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum RowTerm {
+    #[serde(rename = "uri")]
+    Iri { value: String },
+    #[serde(rename = "bnode")]
+    BlankNode { value: String },
+    #[serde(rename = "literal")]
+    Literal {
+        value: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        datatype: Option<String>,
+        #[serde(rename = "xml:lang", skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+    },
+}
+
+/// The bound variables of a solution and their terms, serialized as a map.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SolutionRow(pub BTreeMap<String, RowTerm>);
+
+/// Converts the solutions of a SELECT query to rows in batches, so that only a batch of rows is converted at a time.
+pub struct SolutionRows {
+    df: DataFrame,
+    variables: Vec<String>,
+    rdf_node_types: HashMap<String, RDFNodeType>,
+    batch_size: usize,
+    offset: usize,
+}
+
+impl Iterator for SolutionRows {
+    type Item = Vec<SolutionRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.df.height() {
+            return None;
+        }
+        let batch = self.df.slice(self.offset as i64, self.batch_size);
+        self.offset += batch.height();
+        let mut rows = vec![SolutionRow::default(); batch.height()];
+        for v in &self.variables {
+            let terms = variable_terms(&batch, v, self.rdf_node_types.get(v).unwrap());
+            for (row, term) in rows.iter_mut().zip(terms) {
+                if let Some(term) = term {
+                    row.0.insert(v.clone(), row_term(term));
+                }
+            }
+        }
+        Some(rows)
+    }
+}
+
+impl Triplestore {
+    /// Runs a SELECT query and gives the solutions as serializable rows, batch_size rows at a time.
+    /// Unbound variables are left out of the rows.
+    pub fn query_serde_rows(
+        &mut self,
+        query: &str,
+        batch_size: usize,
+    ) -> Result<SolutionRows, SparqlError> {
+        let (df, variables, rdf_node_types) = self.query_select_variables(query)?;
+        Ok(SolutionRows {
+            df,
+            variables,
+            rdf_node_types,
+            batch_size: batch_size.max(1),
+            offset: 0,
+        })
+    }
+}
+
+//Simple literals and literals with a language tag have no datatype, as in the results format
+fn row_term(term: Term) -> RowTerm {
+    match term {
+        Term::NamedNode(nn) => RowTerm::Iri {
+            value: nn.into_string(),
+        },
+        Term::BlankNode(bn) => RowTerm::BlankNode {
+            value: bn.into_string(),
+        },
+        Term::Literal(l) => {
+            let (value, datatype, language) = l.destruct();
+            let datatype = datatype
+                .filter(|dt| language.is_none() && dt.as_ref() != xsd::STRING)
+                .map(|dt| dt.into_string());
+            RowTerm::Literal {
+                value,
+                datatype,
+                language,
+            }
+        }
+    }
+}
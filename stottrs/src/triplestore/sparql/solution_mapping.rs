@@ -1,7 +1,16 @@
 use std::collections::{HashMap, HashSet};
-use polars::prelude::LazyFrame;
+use log::warn;
+use polars::frame::DataFrame;
+use polars::series::Series;
+use polars::prelude::{col, lit, Expr, IntoLazy, LazyFrame, NamedFrom};
+use polars_core::prelude::JoinType;
+use oxrdf::NamedNode;
 use crate::mapping::RDFNodeType;
+use crate::triplestore::sparql::errors::SparqlError;
+use crate::triplestore::sparql::query_context::Context;
 
+/// Solutions of a graph pattern, with a column for each variable and the RDF node type of each variable.
+/// Custom graph pattern operators can be built by combining solution mappings with the methods below.
 #[derive(Clone)]
 pub struct SolutionMappings {
     pub mappings: LazyFrame,
@@ -10,11 +19,264 @@ pub struct SolutionMappings {
 }
 
 impl SolutionMappings {
-    pub fn new(mappings: LazyFrame, columns:HashSet<String>, datatypes: HashMap<String, RDFNodeType>) -> SolutionMappings {
+    pub fn new(mappings: LazyFrame, columns:HashSet<String>, rdf_node_types: HashMap<String, RDFNodeType>) -> SolutionMappings {
         SolutionMappings {
             mappings,
             columns,
-            rdf_node_types: datatypes
+            rdf_node_types
         }
     }
-}
\ No newline at end of file
+
+    /// Creates solution mappings with a variable for each column of the DataFrame.
+    pub fn from_df(df: DataFrame, rdf_node_types: HashMap<String, RDFNodeType>) -> SolutionMappings {
+        let columns = df
+            .get_column_names()
+            .into_iter()
+            .map(|x| x.to_string())
+            .collect();
+        SolutionMappings::new(df.lazy(), columns, rdf_node_types)
+    }
+
+    /// The single solution without any variables, which is the solution of the empty graph pattern.
+    /// A frame without columns has no rows, so the solution has a placeholder column that is not a variable.
+    pub fn single_empty_solution(placeholder_column: &str) -> SolutionMappings {
+        let df = DataFrame::new(vec![Series::new(placeholder_column, [true])]).unwrap();
+        SolutionMappings::new(df.lazy(), HashSet::new(), HashMap::new())
+    }
+
+    /// Whether the values of the variable have a column with their language tags.
+    pub fn has_language_tags(&self, variable: &str) -> bool {
+        has_language_tag_column(&self.mappings, variable)
+    }
+
+    pub fn merge_columns(&mut self, columns: HashSet<String>) {
+        self.columns.extend(columns);
+    }
+
+    /// Adds the types of variables from other solution mappings that are combined with these,
+    /// failing if a variable has incompatible types on the two sides.
+    pub fn merge_datatypes_compatible(
+        &mut self,
+        rdf_node_types: HashMap<String, RDFNodeType>,
+        context: &Context,
+    ) -> Result<(), SparqlError> {
+        for (var, dt) in rdf_node_types {
+            let merged = if let Some(existing) = self.rdf_node_types.get(&var) {
+                if let Some(merged) = compatible_rdf_node_type(existing, &dt) {
+                    merged
+                } else {
+                    return Err(SparqlError::InconsistentDatatypes(
+                        var,
+                        existing.clone(),
+                        dt,
+                        context.as_str().to_string(),
+                    ));
+                }
+            } else {
+                dt
+            };
+            self.rdf_node_types.insert(var, merged);
+        }
+        Ok(())
+    }
+
+    /// Joins with other solution mappings on the variables they share, or a cross join if there are none.
+    pub fn join(self, other: SolutionMappings, context: &Context) -> Result<SolutionMappings, SparqlError> {
+        self.join_on_shared(other, JoinType::Inner, context)
+    }
+
+    /// Keeps all solutions, extended with compatible solutions from the other solution mappings where there are any.
+    pub fn left_join(self, other: SolutionMappings, context: &Context) -> Result<SolutionMappings, SparqlError> {
+        self.join_on_shared(other, JoinType::Left, context)
+    }
+
+    /// Removes the solutions that are compatible with a solution of the other solution mappings.
+    /// Solutions are only removed if the two sides share a variable.
+    pub fn minus(mut self, other: SolutionMappings) -> SolutionMappings {
+        let mut join_on = shared_columns(&self.columns, &other.columns);
+        if !join_on.is_empty() {
+            join_on.extend(language_tag_join_columns(&self.mappings, &other.mappings, &join_on));
+            let join_on_cols: Vec<Expr> = join_on.iter().map(|x| col(x)).collect();
+            let all_false = [false].repeat(join_on_cols.len());
+            let other_mappings = other.mappings.sort_by_exprs(join_on_cols.as_slice(), all_false.as_slice(), false);
+            self.mappings = self.mappings.sort_by_exprs(
+                join_on_cols.as_slice(),
+                all_false.as_slice(),
+                false,
+            );
+            self.mappings = self.mappings.join(other_mappings, join_on_cols.as_slice(), join_on_cols.as_slice(), JoinType::Anti);
+        }
+        self
+    }
+
+    /// Keeps the solutions where the boolean expression is true.
+    pub fn filter(mut self, expr: Expr) -> SolutionMappings {
+        self.mappings = self.mappings.filter(expr);
+        self
+    }
+
+    /// Adds a variable with the values of the expression.
+    pub fn extend(mut self, variable: &str, expr: Expr, rdf_node_type: RDFNodeType) -> SolutionMappings {
+        self.mappings = self.mappings.with_column(expr.alias(variable));
+        self.columns.insert(variable.to_string());
+        self.rdf_node_types.insert(variable.to_string(), rdf_node_type);
+        self
+    }
+
+    /// Keeps only the given variables, in the given order.
+    /// Variables with values of several types keep their type column.
+    pub fn project(mut self, variables: &[String]) -> SolutionMappings {
+        let mut cols: Vec<Expr> = variables.iter().map(|c| col(c)).collect();
+        for v in variables {
+            if self.rdf_node_types.get(v) == Some(&RDFNodeType::MultiType) {
+                cols.push(col(&multi_type_tag_column(v)));
+            }
+            //Language tags are kept so that results can be written with them
+            if self.has_language_tags(v) {
+                cols.push(col(&language_tag_column(v)));
+            }
+        }
+        self.mappings = self.mappings.select(cols.as_slice());
+        let mut rdf_node_types = HashMap::new();
+        for v in variables {
+            if let Some(dt) = self.rdf_node_types.remove(v) {
+                rdf_node_types.insert(v.clone(), dt);
+            } else {
+                warn!("Datatypes does not contain {}", v);
+            }
+        }
+        SolutionMappings::new(self.mappings, variables.iter().cloned().collect(), rdf_node_types)
+    }
+
+    /// Removes all solutions, keeping the variables and their types.
+    pub fn empty(mut self) -> SolutionMappings {
+        self.mappings = self.mappings.filter(lit(false));
+        self
+    }
+
+    /// Computes the solutions.
+    pub fn collect(self, context: &Context) -> Result<DataFrame, SparqlError> {
+        self.mappings
+            .collect()
+            .map_err(|x| SparqlError::EvaluationError(context.as_str().to_string(), x))
+    }
+
+    fn join_on_shared(
+        mut self,
+        other: SolutionMappings,
+        join_type: JoinType,
+        context: &Context,
+    ) -> Result<SolutionMappings, SparqlError> {
+        let SolutionMappings {
+            mappings: mut other_mappings,
+            columns: other_columns,
+            rdf_node_types: other_rdf_node_types,
+        } = other;
+        let mut join_on = shared_columns(&self.columns, &other_columns);
+        join_on.extend(language_tag_join_columns(&self.mappings, &other_mappings, &join_on));
+        let join_on_cols: Vec<Expr> = join_on.iter().map(|x| col(x)).collect();
+
+        if join_on.is_empty() {
+            self.mappings = self.mappings.join(
+                other_mappings,
+                join_on_cols.as_slice(),
+                join_on_cols.as_slice(),
+                JoinType::Cross,
+            )
+        } else {
+            let all_false = [false].repeat(join_on_cols.len());
+            other_mappings = other_mappings.sort_by_exprs(join_on_cols.as_slice(), all_false.as_slice(), false);
+            self.mappings = self.mappings.sort_by_exprs(
+                join_on_cols.as_slice(),
+                all_false.as_slice(),
+                false,
+            );
+            self.mappings = self.mappings.join(
+                other_mappings,
+                join_on_cols.as_slice(),
+                join_on_cols.as_slice(),
+                join_type,
+            )
+        }
+        self.merge_columns(other_columns);
+        self.merge_datatypes_compatible(other_rdf_node_types, context)?;
+        Ok(self)
+    }
+}
+
+fn shared_columns(left: &HashSet<String>, right: &HashSet<String>) -> Vec<String> {
+    let mut shared: Vec<String> = left.intersection(right).cloned().collect();
+    shared.sort();
+    shared
+}
+
+//Strings with different language tags are different terms, so the tags of shared variables are joined on as well
+pub(crate) fn language_tag_join_columns(
+    left: &LazyFrame,
+    right: &LazyFrame,
+    join_on: &[String],
+) -> Vec<String> {
+    join_on
+        .iter()
+        .filter(|v| has_language_tag_column(left, v) && has_language_tag_column(right, v))
+        .map(|v| language_tag_column(v))
+        .collect()
+}
+
+fn has_language_tag_column(mappings: &LazyFrame, variable: &str) -> bool {
+    mappings
+        .schema()
+        .unwrap()
+        .get(&language_tag_column(variable))
+        .is_some()
+}
+
+/// Finds the type of a variable bound by two sets of solution mappings, if the types are compatible.
+/// Variables from patterns without any matching triples have no type, and are compatible with any type.
+/// Variables with values of several types are compatible with any type.
+pub fn compatible_rdf_node_type(left: &RDFNodeType, right: &RDFNodeType) -> Option<RDFNodeType> {
+    if left == right || right == &RDFNodeType::None {
+        Some(left.clone())
+    } else if left == &RDFNodeType::None {
+        Some(right.clone())
+    } else if left == &RDFNodeType::MultiType || right == &RDFNodeType::MultiType {
+        Some(RDFNodeType::MultiType)
+    } else {
+        None
+    }
+}
+
+/// The column with the type of each value of a variable with values of several types.
+pub fn multi_type_tag_column(variable: &str) -> String {
+    format!("{}{}", variable, MULTI_TYPE_TAG_SUFFIX)
+}
+
+const MULTI_TYPE_TAG_SUFFIX: &str = "|rdf_node_type";
+
+/// The column with the language tag of each value of a variable with string values, null for values without a tag.
+pub fn language_tag_column(variable: &str) -> String {
+    format!("{}{}", variable, LANGUAGE_TAG_SUFFIX)
+}
+
+const LANGUAGE_TAG_SUFFIX: &str = "|language_tag";
+
+/// The tag of a type in the type column of a variable with values of several types.
+pub fn rdf_node_type_tag(rdf_node_type: &RDFNodeType) -> String {
+    match rdf_node_type {
+        RDFNodeType::IRI => "IRI".to_string(),
+        RDFNodeType::BlankNode => "BlankNode".to_string(),
+        RDFNodeType::Literal(l) => l.as_str().to_string(),
+        RDFNodeType::None => "None".to_string(),
+        RDFNodeType::MultiType => "MultiType".to_string(),
+    }
+}
+
+pub fn rdf_node_type_from_tag(tag: &str) -> RDFNodeType {
+    match tag {
+        "IRI" => RDFNodeType::IRI,
+        "BlankNode" => RDFNodeType::BlankNode,
+        "None" => RDFNodeType::None,
+        "MultiType" => RDFNodeType::MultiType,
+        l => RDFNodeType::Literal(NamedNode::new_unchecked(l)),
+    }
+}
@@ -0,0 +1,398 @@
+use super::Triplestore;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::conversion::convert_to_string;
+use crate::triplestore::custom_datatypes::{format_series, formatter_for};
+use crate::triplestore::sparql::errors::SparqlError;
+use crate::triplestore::sparql::query_context::Context;
+use crate::triplestore::sparql::solution_mapping::{language_tag_column, multi_type_tag_column};
+use oxrdf::vocab::xsd;
+use oxrdf::{BlankNode, Literal, NamedNode, Term, Variable};
+use polars::frame::DataFrame;
+use polars_core::prelude::{DataType, Series};
+use spargebra::Query;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The SPARQL 1.1 Query Results formats, see https://www.w3.org/TR/sparql11-results-json/,
+/// https://www.w3.org/TR/rdf-sparql-XMLres/ and https://www.w3.org/TR/sparql11-results-csv-tsv/
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SparqlResultsFormat {
+    Json,
+    Xml,
+    /// Values only, without the type of the terms, for spreadsheets.
+    Csv,
+    /// Terms written as in Turtle, so IRIs are bracketed and literals quoted with their datatype or language tag.
+    Tsv,
+}
+
+impl SparqlResultsFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            SparqlResultsFormat::Json => "application/sparql-results+json",
+            SparqlResultsFormat::Xml => "application/sparql-results+xml",
+            SparqlResultsFormat::Csv => "text/csv",
+            SparqlResultsFormat::Tsv => "text/tab-separated-values",
+        }
+    }
+}
+
+//The terms of the solutions, one column of optional terms for each variable
+struct SolutionTerms {
+    variables: Vec<Variable>,
+    columns: Vec<Vec<Option<Term>>>,
+    height: usize,
+}
+
+impl Triplestore {
+    /// Runs a SELECT query and writes the solutions in a SPARQL results format,
+    /// with the type of each term, the datatypes of literals and their language tags.
+    /// Unbound variables are left out of the solutions.
+    pub fn query_to_sparql_results<W: Write + ?Sized>(
+        &mut self,
+        query: &str,
+        format: SparqlResultsFormat,
+        writer: &mut W,
+    ) -> Result<(), SparqlError> {
+        let solution_terms = self.query_solution_terms(query)?;
+        match format {
+            SparqlResultsFormat::Json => write_json(&solution_terms, writer),
+            SparqlResultsFormat::Xml => write_xml(&solution_terms, writer),
+            SparqlResultsFormat::Csv => write_csv(&solution_terms, writer),
+            SparqlResultsFormat::Tsv => write_tsv(&solution_terms, writer),
+        }
+        .map_err(|x| SparqlError::ResultsWriteError(x))
+    }
+
+    /// Runs a SELECT query and gives the solutions as maps from the bound variables to RDF terms,
+    /// so that IRIs, blank nodes and literals are told apart without parsing the values of the data frame.
+    pub fn query_oxrdf_solutions(
+        &mut self,
+        query: &str,
+    ) -> Result<Vec<HashMap<Variable, Term>>, SparqlError> {
+        let SolutionTerms {
+            variables,
+            mut columns,
+            height,
+        } = self.query_solution_terms(query)?;
+        let mut solutions = vec![HashMap::new(); height];
+        for (v, column) in variables.into_iter().zip(columns.drain(..)) {
+            for (solution, term) in solutions.iter_mut().zip(column) {
+                if let Some(term) = term {
+                    solution.insert(v.clone(), term);
+                }
+            }
+        }
+        Ok(solutions)
+    }
+
+    fn query_solution_terms(&mut self, query: &str) -> Result<SolutionTerms, SparqlError> {
+        let (df, variables, rdf_node_types) = self.query_select_variables(query)?;
+        let columns = variables
+            .iter()
+            .map(|v| variable_terms(&df, v, rdf_node_types.get(v).unwrap()))
+            .collect();
+        Ok(SolutionTerms {
+            variables: variables
+                .iter()
+                .map(|v| Variable::new_unchecked(v))
+                .collect(),
+            columns,
+            height: df.height(),
+        })
+    }
+
+    //The solutions of a SELECT query, with the columns of the variables in the order of the data frame
+    pub(crate) fn query_select_variables(
+        &mut self,
+        query: &str,
+    ) -> Result<(DataFrame, Vec<String>, HashMap<String, RDFNodeType>), SparqlError> {
+        let query = Query::parse(query, None).map_err(|x| SparqlError::ParseError(x))?;
+        if let Query::Select { pattern, .. } = &query {
+            self.prepare_for_query()?;
+            let context = Context::new();
+            let (mut df, rdf_node_types) = self.select_parsed(pattern, &context)?;
+            let variables: Vec<String> = df
+                .get_column_names()
+                .into_iter()
+                .filter(|c| rdf_node_types.contains_key(*c))
+                .map(|c| c.to_string())
+                .collect();
+            //Values of custom datatypes are given by their lexical forms
+            for v in &variables {
+                let rdf_node_type = rdf_node_types.get(v).unwrap();
+                if let Some(formatter) = formatter_for(&self.custom_datatypes, rdf_node_type) {
+                    let formatted = format_series(df.column(v).unwrap(), formatter);
+                    df.with_column(formatted).unwrap();
+                }
+            }
+            Ok((df, variables, rdf_node_types))
+        } else {
+            Err(SparqlError::QueryTypeNotSupported)
+        }
+    }
+}
+
+pub(crate) fn variable_terms(df: &DataFrame, variable: &str, rdf_node_type: &RDFNodeType) -> Vec<Option<Term>> {
+    let series = df.column(variable).unwrap();
+    let series = convert_to_string(series).unwrap_or_else(|| series.clone());
+    let values = series.utf8().unwrap();
+    let utf8_column = |name: &str| {
+        df.column(name)
+            .ok()
+            .map(|s| s.cast(&DataType::Utf8).unwrap())
+    };
+    let language_tags = utf8_column(&language_tag_column(variable));
+    let type_tags = utf8_column(&multi_type_tag_column(variable));
+    //Missing tag columns are read as columns of nulls
+    let tags = |s: &Option<Series>| -> Vec<Option<String>> {
+        if let Some(s) = s {
+            s.utf8().unwrap().into_iter().map(|t| t.map(|t| t.to_string())).collect()
+        } else {
+            vec![None; values.len()]
+        }
+    };
+    values
+        .into_iter()
+        .zip(tags(&language_tags))
+        .zip(tags(&type_tags))
+        .map(|((value, language_tag), type_tag)| {
+            let value = value?;
+            let type_tag = type_tag.as_deref();
+            let datatype = match (rdf_node_type, type_tag) {
+                (RDFNodeType::MultiType, Some("IRI" | "BlankNode"))
+                | (RDFNodeType::IRI | RDFNodeType::BlankNode, _) => {
+                    return Some(iri_or_blank_node(value))
+                }
+                (RDFNodeType::MultiType, Some(tag)) => NamedNode::new_unchecked(tag),
+                (RDFNodeType::Literal(dt), _) => dt.clone(),
+                _ => xsd::STRING.into_owned(),
+            };
+            let literal = if let Some(language_tag) = language_tag {
+                Literal::new_language_tagged_literal_unchecked(value, language_tag)
+            } else {
+                Literal::new_typed_literal(value, datatype)
+            };
+            Some(Term::Literal(literal))
+        })
+        .collect()
+}
+
+//Blank nodes are stored with the _: prefix of their labels
+fn iri_or_blank_node(value: &str) -> Term {
+    if let Some(label) = value.strip_prefix("_:") {
+        Term::BlankNode(BlankNode::new_unchecked(label))
+    } else {
+        Term::NamedNode(NamedNode::new_unchecked(value))
+    }
+}
+
+//Simple literals and literals with a language tag are written without a datatype
+fn written_datatype(literal: &Literal) -> Option<&str> {
+    if literal.language().is_some() || literal.datatype() == xsd::STRING {
+        None
+    } else {
+        Some(literal.datatype().as_str())
+    }
+}
+
+fn write_json<W: Write + ?Sized>(solution_terms: &SolutionTerms, writer: &mut W) -> std::io::Result<()> {
+    let vars: Vec<String> = solution_terms
+        .variables
+        .iter()
+        .map(|v| json_string(v.as_str()))
+        .collect();
+    write!(
+        writer,
+        "{{\"head\":{{\"vars\":[{}]}},\"results\":{{\"bindings\":[",
+        vars.join(",")
+    )?;
+    for i in 0..solution_terms.height {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        let mut bindings = vec![];
+        for (v, column) in solution_terms.variables.iter().zip(&solution_terms.columns) {
+            let term = match &column[i] {
+                None => continue,
+                Some(Term::NamedNode(nn)) => {
+                    format!("{{\"type\":\"uri\",\"value\":{}}}", json_string(nn.as_str()))
+                }
+                Some(Term::BlankNode(bn)) => {
+                    format!("{{\"type\":\"bnode\",\"value\":{}}}", json_string(bn.as_str()))
+                }
+                Some(Term::Literal(literal)) => {
+                    let mut term = format!(
+                        "{{\"type\":\"literal\",\"value\":{}",
+                        json_string(literal.value())
+                    );
+                    if let Some(datatype) = written_datatype(literal) {
+                        term.push_str(&format!(",\"datatype\":{}", json_string(datatype)));
+                    }
+                    if let Some(language_tag) = literal.language() {
+                        term.push_str(&format!(",\"xml:lang\":{}", json_string(language_tag)));
+                    }
+                    term.push('}');
+                    term
+                }
+            };
+            bindings.push(format!("{}:{}", json_string(v.as_str()), term));
+        }
+        write!(writer, "{{{}}}", bindings.join(","))?;
+    }
+    write!(writer, "]}}}}")
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_xml<W: Write + ?Sized>(solution_terms: &SolutionTerms, writer: &mut W) -> std::io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(writer, "<sparql xmlns=\"http://www.w3.org/2005/sparql-results#\">")?;
+    writeln!(writer, "  <head>")?;
+    for v in &solution_terms.variables {
+        writeln!(writer, "    <variable name=\"{}\"/>", xml_escape(v.as_str()))?;
+    }
+    writeln!(writer, "  </head>")?;
+    writeln!(writer, "  <results>")?;
+    for i in 0..solution_terms.height {
+        writeln!(writer, "    <result>")?;
+        for (v, column) in solution_terms.variables.iter().zip(&solution_terms.columns) {
+            let term = match &column[i] {
+                None => continue,
+                Some(Term::NamedNode(nn)) => format!("<uri>{}</uri>", xml_escape(nn.as_str())),
+                Some(Term::BlankNode(bn)) => format!("<bnode>{}</bnode>", xml_escape(bn.as_str())),
+                Some(Term::Literal(literal)) => {
+                    let attribute = if let Some(datatype) = written_datatype(literal) {
+                        format!(" datatype=\"{}\"", xml_escape(datatype))
+                    } else if let Some(language_tag) = literal.language() {
+                        format!(" xml:lang=\"{}\"", xml_escape(language_tag))
+                    } else {
+                        "".to_string()
+                    };
+                    format!("<literal{}>{}</literal>", attribute, xml_escape(literal.value()))
+                }
+            };
+            writeln!(
+                writer,
+                "      <binding name=\"{}\">{}</binding>",
+                xml_escape(v.as_str()),
+                term
+            )?;
+        }
+        writeln!(writer, "    </result>")?;
+    }
+    writeln!(writer, "  </results>")?;
+    writeln!(writer, "</sparql>")
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+//Lines end with CRLF, and fields with commas, quotes or line breaks are quoted
+fn write_csv<W: Write + ?Sized>(solution_terms: &SolutionTerms, writer: &mut W) -> std::io::Result<()> {
+    let vars: Vec<String> = solution_terms
+        .variables
+        .iter()
+        .map(|v| csv_field(v.as_str()))
+        .collect();
+    write!(writer, "{}\r\n", vars.join(","))?;
+    for i in 0..solution_terms.height {
+        let fields: Vec<String> = solution_terms
+            .columns
+            .iter()
+            .map(|column| match &column[i] {
+                None => "".to_string(),
+                Some(Term::NamedNode(nn)) => csv_field(nn.as_str()),
+                Some(Term::BlankNode(bn)) => format!("_:{}", bn.as_str()),
+                Some(Term::Literal(literal)) => csv_field(literal.value()),
+            })
+            .collect();
+        write!(writer, "{}\r\n", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(|c: char| matches!(c, '"' | ',' | '\r' | '\n')) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn write_tsv<W: Write + ?Sized>(solution_terms: &SolutionTerms, writer: &mut W) -> std::io::Result<()> {
+    let vars: Vec<String> = solution_terms
+        .variables
+        .iter()
+        .map(|v| format!("?{}", v.as_str()))
+        .collect();
+    writeln!(writer, "{}", vars.join("\t"))?;
+    for i in 0..solution_terms.height {
+        let fields: Vec<String> = solution_terms
+            .columns
+            .iter()
+            .map(|column| match &column[i] {
+                None => "".to_string(),
+                Some(Term::NamedNode(nn)) => format!("<{}>", nn.as_str()),
+                Some(Term::BlankNode(bn)) => format!("_:{}", bn.as_str()),
+                Some(Term::Literal(literal)) => {
+                    let mut field = tsv_string(literal.value());
+                    if let Some(language_tag) = literal.language() {
+                        field.push('@');
+                        field.push_str(language_tag);
+                    } else if let Some(datatype) = written_datatype(literal) {
+                        field.push_str(&format!("^^<{}>", datatype));
+                    }
+                    field
+                }
+            })
+            .collect();
+        writeln!(writer, "{}", fields.join("\t"))?;
+    }
+    Ok(())
+}
+
+//Tabs and line breaks would end the field or the line, so they are escaped as in Turtle strings
+fn tsv_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
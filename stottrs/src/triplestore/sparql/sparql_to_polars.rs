@@ -1,12 +1,16 @@
 use oxrdf::vocab::xsd;
 use oxrdf::{Literal, NamedNode, Term};
-use polars::export::chrono::{DateTime, NaiveDateTime, Utc};
-use polars::prelude::{LiteralValue, NamedFrom, Series, TimeUnit};
+use crate::literals::parse_xsd_duration;
+use crate::triplestore::sparql::errors::SparqlError;
+use polars::export::chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use polars::prelude::{DataType, LiteralValue, NamedFrom, Series, TimeUnit};
 use std::str::FromStr;
 
-pub(crate) fn sparql_term_to_polars_literal_value(term: &Term) -> polars::prelude::LiteralValue {
+pub(crate) fn sparql_term_to_polars_literal_value(
+    term: &Term,
+) -> Result<polars::prelude::LiteralValue, SparqlError> {
     match term {
-        Term::NamedNode(named_node) => sparql_named_node_to_polars_literal_value(named_node),
+        Term::NamedNode(named_node) => Ok(sparql_named_node_to_polars_literal_value(named_node)),
         Term::Literal(lit) => sparql_literal_to_polars_literal_value(lit),
         _ => {
             panic!("Not supported")
@@ -18,36 +22,63 @@ pub(crate) fn sparql_named_node_to_polars_literal_value(named_node: &NamedNode)
     LiteralValue::Utf8(named_node.as_str().to_string())
 }
 
-pub(crate) fn sparql_literal_to_polars_literal_value(lit: &Literal) -> LiteralValue {
+//Literals with values that are not valid for their datatype, e.g. "300"^^xsd:unsignedByte, give an error
+pub(crate) fn sparql_literal_to_polars_literal_value(lit: &Literal) -> Result<LiteralValue, SparqlError> {
     let datatype = lit.datatype();
     let value = lit.value();
+    let invalid = || SparqlError::InvalidLiteral(value.to_string(), datatype.as_str().to_string());
     let literal_value = if datatype == xsd::STRING {
         LiteralValue::Utf8(value.to_string())
     } else if datatype == xsd::UNSIGNED_INT {
-        let u = u32::from_str(value).expect("Integer parsing error");
+        let u = u32::from_str(value).map_err(|_| invalid())?;
         LiteralValue::UInt32(u)
     } else if datatype == xsd::UNSIGNED_LONG {
-        let u = u64::from_str(value).expect("Integer parsing error");
+        let u = u64::from_str(value).map_err(|_| invalid())?;
         LiteralValue::UInt64(u)
-    } else if datatype == xsd::INTEGER {
-        let i = i64::from_str(value).expect("Integer parsing error");
+    } else if datatype == xsd::UNSIGNED_SHORT {
+        let u = u16::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::UInt16(u)
+    } else if datatype == xsd::UNSIGNED_BYTE {
+        let u = u8::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::UInt8(u)
+    } else if datatype == xsd::NON_NEGATIVE_INTEGER || datatype == xsd::POSITIVE_INTEGER {
+        let u = u64::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::UInt64(u)
+    } else if datatype == xsd::INTEGER
+        || datatype == xsd::NON_POSITIVE_INTEGER
+        || datatype == xsd::NEGATIVE_INTEGER
+    {
+        let i = i64::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Int64(i)
     } else if datatype == xsd::LONG {
-        let i = i64::from_str(value).expect("Integer parsing error");
+        let i = i64::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Int64(i)
     } else if datatype == xsd::INT {
-        let i = i32::from_str(value).expect("Integer parsing error");
+        let i = i32::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Int32(i)
+    } else if datatype == xsd::SHORT {
+        let i = i16::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::Int16(i)
+    } else if datatype == xsd::BYTE {
+        let i = i8::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::Int8(i)
     } else if datatype == xsd::DOUBLE {
-        let d = f64::from_str(value).expect("Integer parsing error");
+        let d = f64::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Float64(d)
     } else if datatype == xsd::FLOAT {
-        let f = f32::from_str(value).expect("Integer parsing error");
+        let f = f32::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Float32(f)
     } else if datatype == xsd::BOOLEAN {
-        let b = bool::from_str(value).expect("Boolean parsing error");
+        let b = bool::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Boolean(b)
-    } else if datatype == xsd::DATE_TIME {
+    } else if datatype == xsd::DATE {
+        //Compared with date columns as the start of the day
+        let d = NaiveDate::from_str(value).map_err(|_| invalid())?;
+        LiteralValue::DateTime(d.and_hms(0, 0, 0), TimeUnit::Milliseconds)
+    } else if datatype == xsd::DURATION || datatype == xsd::DAY_TIME_DURATION {
+        let nanos = parse_xsd_duration(value).ok_or_else(invalid)?;
+        LiteralValue::Duration(Duration::nanoseconds(nanos), TimeUnit::Nanoseconds)
+    } else if datatype == xsd::DATE_TIME || datatype == xsd::DATE_TIME_STAMP {
         let dt_without_tz = value.parse::<NaiveDateTime>();
         if let Ok(dt) = dt_without_tz {
             LiteralValue::DateTime(dt, TimeUnit::Nanoseconds)
@@ -56,16 +87,18 @@ pub(crate) fn sparql_literal_to_polars_literal_value(lit: &Literal) -> LiteralVa
             if let Ok(dt) = dt_without_tz {
                 LiteralValue::DateTime(dt.naive_utc(), TimeUnit::Nanoseconds)
             } else {
-                panic!("Could not parse datetime: {}", value);
+                return Err(invalid());
             }
         }
     } else if datatype == xsd::DECIMAL {
-        let d = f64::from_str(value).expect("Decimal parsing error");
+        let d = f64::from_str(value).map_err(|_| invalid())?;
         LiteralValue::Float64(d)
     } else {
-        todo!("Not implemented!")
+        return Err(SparqlError::LiteralDatatypeNotSupported(
+            datatype.as_str().to_string(),
+        ));
     };
-    literal_value
+    Ok(literal_value)
 }
 
 fn polars_literal_values_to_series(literal_values: Vec<LiteralValue>, name: &str) -> Series {
@@ -205,9 +238,22 @@ fn polars_literal_values_to_series(literal_values: Vec<LiteralValue>, name: &str
                 );
                 s
             }
-            LiteralValue::Duration(_, _) => {
-                todo!()
-            }
+            LiteralValue::Duration(_, t) => Series::new(
+                name,
+                literal_values
+                    .into_iter()
+                    .map(|x| {
+                        if let LiteralValue::Duration(d, t_prime) = x {
+                            assert_eq!(t, &t_prime);
+                            d.num_nanoseconds().unwrap()
+                        } else {
+                            panic!("Not possible")
+                        }
+                    })
+                    .collect::<Vec<i64>>(),
+            )
+            .cast(&DataType::Duration(TimeUnit::Nanoseconds))
+            .unwrap(),
             LiteralValue::Series(_) => {
                 todo!()
             }
@@ -342,9 +388,22 @@ fn polars_literal_values_to_series(literal_values: Vec<LiteralValue>, name: &str
                         .collect::<Vec<Option<NaiveDateTime>>>(),
                 )
             }
-            LiteralValue::Duration(_, _) => {
-                todo!()
-            }
+            LiteralValue::Duration(_, t) => Series::new(
+                name,
+                literal_values
+                    .into_iter()
+                    .map(|x| {
+                        if let LiteralValue::Duration(d, t_prime) = x {
+                            assert_eq!(t, &t_prime);
+                            d.num_nanoseconds()
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<Option<i64>>>(),
+            )
+            .cast(&DataType::Duration(TimeUnit::Nanoseconds))
+            .unwrap(),
             LiteralValue::Series(_) => {
                 todo!()
             }
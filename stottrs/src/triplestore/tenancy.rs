@@ -0,0 +1,239 @@
+use super::{TripleTable, Triplestore, TriplesToAdd};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::sparql::errors::SparqlError;
+use crate::triplestore::sparql::QueryResult;
+use oxrdf::Triple;
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::{Deref, DerefMut};
+
+const TENANT_GRAPH_PREFIX: &str = "urn:stottrs:tenant:";
+
+/// The named graph holding the triples of a tenant.
+/// Triples are added for a tenant by expanding templates into this graph, see Mapping::expand_for_tenant.
+pub fn tenant_graph(tenant: &str) -> String {
+    format!("{}{}", TENANT_GRAPH_PREFIX, tenant)
+}
+
+impl Triplestore {
+    /// The tenants that have triples in the store, sorted by name.
+    pub fn tenants(&self) -> Vec<String> {
+        let mut tenants: Vec<String> = self
+            .named_graph_df_maps
+            .keys()
+            .filter_map(|g| g.strip_prefix(TENANT_GRAPH_PREFIX))
+            .map(|t| t.to_string())
+            .collect();
+        tenants.sort();
+        tenants
+    }
+
+    /// Limits the number of triples of the tenant. Triples that would take the tenant over the quota are not added.
+    /// Duplicates are counted until the store is deduplicated, so a batch may be rejected although some of it is already stored.
+    pub fn set_tenant_quota(&mut self, tenant: &str, max_triples: usize) {
+        self.tenant_quotas.insert(tenant_graph(tenant), max_triples);
+    }
+
+    pub fn remove_tenant_quota(&mut self, tenant: &str) {
+        self.tenant_quotas.remove(&tenant_graph(tenant));
+    }
+
+    /// The number of triples of the tenant, duplicates are counted if the store is not deduplicated.
+    pub fn tenant_triple_count(&self, tenant: &str) -> usize {
+        self.graph_triple_count(&tenant_graph(tenant))
+    }
+
+    /// Removes all triples of the tenant, returns false if the tenant had none.
+    pub fn remove_tenant(&mut self, tenant: &str) -> bool {
+        self.named_graph_df_maps
+            .remove(&tenant_graph(tenant))
+            .is_some()
+    }
+
+    /// Runs a query against the triples of the tenant only, as if they were the default graph of the store.
+    /// Triples constructed by CONSTRUCT updates in the query are added for the tenant.
+    pub fn query_tenant(&mut self, tenant: &str, query: &str) -> Result<QueryResult, SparqlError> {
+        self.with_tenant(tenant, |t| t.query(query))
+    }
+
+    pub fn export_tenant_oxrdf_triples(&mut self, tenant: &str) -> Result<Vec<Triple>, MappingError> {
+        self.with_tenant(tenant, |t| t.export_oxrdf_triples())
+    }
+
+    pub fn write_tenant_n_triples(
+        &mut self,
+        tenant: &str,
+        buffer: &mut dyn Write,
+    ) -> Result<(), MappingError> {
+        self.with_tenant(tenant, |t| t.write_n_triples_all_dfs(buffer, 1024))
+    }
+
+    pub(crate) fn check_tenant_quotas(&self, ts: &[TriplesToAdd]) -> Result<(), MappingError> {
+        if self.tenant_quotas.is_empty() {
+            return Ok(());
+        }
+        let mut incoming: HashMap<&String, usize> = HashMap::new();
+        for t in ts {
+            //While the triples of a tenant are the default graph, triples added to it are added for the tenant
+            if let Some(g) = t.graph.as_ref().or(self.tenant_graph.as_ref()) {
+                if self.tenant_quotas.contains_key(g) {
+                    *incoming.entry(g).or_default() += t.df.height();
+                }
+            }
+        }
+        for (g, rows) in incoming {
+            let quota = *self.tenant_quotas.get(g).unwrap();
+            let stored = self.graph_triple_count(g);
+            if stored + rows > quota {
+                return Err(MappingError::TenantQuotaExceeded(
+                    g.strip_prefix(TENANT_GRAPH_PREFIX).unwrap().to_string(),
+                    quota,
+                    stored + rows,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn graph_triple_count(&self, graph: &str) -> usize {
+        let map = if self.tenant_graph.as_deref() == Some(graph) {
+            Some(&self.df_map)
+        } else {
+            self.named_graph_df_maps.get(graph)
+        };
+        map.map_or(0, |m| {
+            m.values()
+                .flat_map(|m| m.values())
+                .flat_map(|tt| tt.chunk_metadata.iter())
+                .map(|c| c.height)
+                .sum()
+        })
+    }
+
+    //The triples of the tenant are swapped in as the default graph, and the other graphs are hidden while f runs.
+    //The store is restored when the scope is dropped, also if f panics.
+    fn with_tenant<T>(&mut self, tenant: &str, f: impl FnOnce(&mut Triplestore) -> T) -> T {
+        let mut scope = TenantScope::new(self, tenant_graph(tenant));
+        f(&mut *scope)
+    }
+}
+
+struct TenantScope<'a> {
+    triplestore: &'a mut Triplestore,
+    graph: String,
+    df_map: HashMap<String, HashMap<RDFNodeType, TripleTable>>,
+    named_graph_df_maps: HashMap<String, HashMap<String, HashMap<RDFNodeType, TripleTable>>>,
+    deduplicated: bool,
+}
+
+impl<'a> TenantScope<'a> {
+    fn new(triplestore: &'a mut Triplestore, graph: String) -> TenantScope<'a> {
+        let tenant_map = triplestore
+            .named_graph_df_maps
+            .remove(&graph)
+            .unwrap_or_default();
+        let df_map = std::mem::replace(&mut triplestore.df_map, tenant_map);
+        let named_graph_df_maps = std::mem::take(&mut triplestore.named_graph_df_maps);
+        let deduplicated = triplestore.deduplicated;
+        triplestore.tenant_graph = Some(graph.clone());
+        TenantScope {
+            triplestore,
+            graph,
+            df_map,
+            named_graph_df_maps,
+            deduplicated,
+        }
+    }
+}
+
+impl<'a> Deref for TenantScope<'a> {
+    type Target = Triplestore;
+
+    fn deref(&self) -> &Triplestore {
+        &*self.triplestore
+    }
+}
+
+impl<'a> DerefMut for TenantScope<'a> {
+    fn deref_mut(&mut self) -> &mut Triplestore {
+        &mut *self.triplestore
+    }
+}
+
+impl<'a> Drop for TenantScope<'a> {
+    fn drop(&mut self) {
+        let tenant_map = std::mem::replace(
+            &mut self.triplestore.df_map,
+            std::mem::take(&mut self.df_map),
+        );
+        self.triplestore.named_graph_df_maps = std::mem::take(&mut self.named_graph_df_maps);
+        if !tenant_map.is_empty() {
+            self.triplestore
+                .named_graph_df_maps
+                .insert(self.graph.clone(), tenant_map);
+        }
+        self.triplestore.tenant_graph = None;
+        //Only the tenant is deduplicated when f deduplicates the store
+        self.triplestore.deduplicated = self.deduplicated && self.triplestore.deduplicated;
+    }
+}
+
+#[test]
+fn test_with_tenant_checks_quota_and_restores_store_on_panic() {
+    use polars::prelude::{DataFrame, NamedFrom, Series};
+
+    let triples = |subjects: &[&str], graph: Option<String>| TriplesToAdd {
+        df: DataFrame::new(vec![
+            Series::new("subject", subjects),
+            Series::new("object", subjects),
+        ])
+        .unwrap(),
+        object_type: RDFNodeType::IRI,
+        language_tag: None,
+        static_verb_column: Some("http://example.net/ns#p".to_string()),
+        has_unique_subset: false,
+        globally_unique: false,
+        graph,
+    };
+    let call_uuid = "test".to_string();
+    let mut triplestore = Triplestore::new(None);
+    triplestore
+        .add_triples_vec(vec![triples(&["http://example.net/ns#d"], None)], &call_uuid)
+        .unwrap();
+    triplestore
+        .add_triples_vec(
+            vec![triples(&["http://example.net/ns#a1"], Some(tenant_graph("acme")))],
+            &call_uuid,
+        )
+        .unwrap();
+    triplestore.set_tenant_quota("acme", 2);
+
+    //Triples added to the default graph while the tenant is swapped in count against its quota
+    let res = triplestore.with_tenant("acme", |t| {
+        t.add_triples_vec(
+            vec![triples(
+                &["http://example.net/ns#a2", "http://example.net/ns#a3"],
+                None,
+            )],
+            &call_uuid,
+        )
+    });
+    assert!(matches!(res, Err(MappingError::TenantQuotaExceeded(..))));
+    triplestore
+        .with_tenant("acme", |t| {
+            t.add_triples_vec(vec![triples(&["http://example.net/ns#a2"], None)], &call_uuid)
+        })
+        .unwrap();
+    assert_eq!(triplestore.tenant_triple_count("acme"), 2);
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        triplestore.with_tenant("acme", |_| panic!("Panic while the tenant is swapped in"))
+    }));
+    assert!(res.is_err());
+    assert_eq!(triplestore.tenants(), vec!["acme".to_string()]);
+    assert_eq!(triplestore.tenant_triple_count("acme"), 2);
+    assert!(triplestore.tenant_graph.is_none());
+    assert_eq!(triplestore.df_map.len(), 1);
+    assert_eq!(triplestore.export_tenant_oxrdf_triples("acme").unwrap().len(), 2);
+}
@@ -0,0 +1,345 @@
+use super::{TripleTable, Triplestore};
+use crate::mapping::errors::MappingError;
+use crate::mapping::RDFNodeType;
+use crate::triplestore::conversion::convert_to_string;
+use crate::triplestore::custom_datatypes::{format_objects, formatter_for, LexicalFormatter};
+use crate::triplestore::{TripleType, LANGUAGE_TAG_COLUMN};
+use oxrdf::vocab::{rdf, xsd};
+use oxrdf::NamedNode;
+use polars::prelude::{IntoLazy, LazyFrame};
+use polars_core::frame::DataFrame;
+use polars_core::prelude::IdxSize;
+use polars_core::series::Series;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::io::Write;
+
+impl Triplestore {
+    /// Writes the default graph with the objects of each subject grouped by predicate, using the prefixes for IRIs,
+    /// and with rdf:type first in each subject block.
+    /// Each chunk of the predicate tables is sorted on subject, and the chunks are merged while they are read a batch
+    /// at a time, so that each subject block is written once. Chunks in the caching folder are already sorted,
+    /// in-memory chunks are sorted into copies.
+    /// Stores with named graphs are rejected, as Turtle can not represent them.
+    pub(crate) fn write_turtle_all_dfs<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        chunk_size: usize,
+        prefix_map: &HashMap<String, NamedNode>,
+    ) -> Result<(), MappingError> {
+        self.check_no_named_graphs("Turtle")?;
+        self.deduplicate()?;
+        let mut prefixes: Vec<(&String, &NamedNode)> = prefix_map.iter().collect();
+        prefixes.sort_by(|a, b| a.0.cmp(b.0));
+        let mut buf = vec![];
+        for (pre, nn) in &prefixes {
+            writeln!(buf, "@prefix {}: <{}> .", pre, nn.as_str()).unwrap();
+        }
+        writeln!(buf).unwrap();
+        writer
+            .write_all(&buf)
+            .map_err(|x| MappingError::WriteNTriplesError(x))?;
+        buf.clear();
+        //Longest namespaces first, so that the most specific prefix is used
+        let mut namespaces: Vec<(&str, &str)> = prefixes
+            .iter()
+            .map(|(pre, nn)| (pre.as_str(), nn.as_str()))
+            .collect();
+        namespaces.sort_by_key(|(_, ns)| usize::MAX - ns.len());
+
+        let mut verbs = vec![];
+        let mut cursors = vec![];
+        for (predicate, map) in &self.df_map {
+            let verb = if predicate.as_str() == rdf::TYPE.as_str() {
+                "a".to_string()
+            } else {
+                compact_iri(predicate, &namespaces)
+            };
+            verbs.push(verb);
+            for (rdf_node_type, tt) in map {
+                let formatter = formatter_for(&self.custom_datatypes, rdf_node_type);
+                for sorted in lazy_frames_sorted_by_subject(tt)? {
+                    cursors.push(ChunkCursor {
+                        sorted,
+                        offset: 0,
+                        exhausted: false,
+                        rows: VecDeque::new(),
+                        verb: verbs.len() - 1,
+                        rdf_node_type,
+                        formatter,
+                    });
+                }
+            }
+        }
+
+        //The cursors ordered by the subject of their next row
+        let mut heap = BinaryHeap::new();
+        for (i, c) in cursors.iter_mut().enumerate() {
+            if let Some(s) = c.next_subject(chunk_size, &namespaces)? {
+                heap.push(Reverse((s.to_string(), i)));
+            }
+        }
+        let mut buffered_triples = 0;
+        while let Some(Reverse((subject, _))) = heap.peek().cloned() {
+            let mut rows = vec![];
+            while let Some(Reverse((s, i))) = heap.peek() {
+                if s != &subject {
+                    break;
+                }
+                let i = *i;
+                heap.pop();
+                let c = cursors.get_mut(i).unwrap();
+                loop {
+                    let same_subject = c
+                        .next_subject(chunk_size, &namespaces)?
+                        .map(|s| s == subject.as_str());
+                    match same_subject {
+                        Some(true) => {
+                            let (_, o) = c.rows.pop_front().unwrap();
+                            rows.push((c.verb, o));
+                        }
+                        Some(false) => {
+                            let next = c.rows.front().unwrap().0.clone();
+                            heap.push(Reverse((next, i)));
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+            }
+            //rdf:type first, even when other verbs sort before "a", such as full IRIs
+            rows.sort_by(|(va, oa), (vb, ob)| {
+                (verbs[*va] != "a", &verbs[*va], oa).cmp(&(verbs[*vb] != "a", &verbs[*vb], ob))
+            });
+            write_subject_block(&mut buf, &compact_iri(&subject, &namespaces), &verbs, &rows);
+            buffered_triples += rows.len();
+            if buffered_triples >= chunk_size {
+                writer
+                    .write_all(&buf)
+                    .map_err(|x| MappingError::WriteNTriplesError(x))?;
+                buf.clear();
+                buffered_triples = 0;
+            }
+        }
+        writer
+            .write_all(&buf)
+            .map_err(|x| MappingError::WriteNTriplesError(x))
+    }
+}
+
+//Cached chunks are written sorted by subject
+fn lazy_frames_sorted_by_subject(tt: &TripleTable) -> Result<Vec<LazyFrame>, MappingError> {
+    if let Some(dfs) = &tt.dfs {
+        Ok(dfs
+            .iter()
+            .map(|df| df.sort(["subject"], false).unwrap().lazy())
+            .collect())
+    } else {
+        tt.get_lazy_frames()
+    }
+}
+
+//A chunk of a predicate table sorted by subject, read one batch at a time with the objects rendered as Turtle terms
+struct ChunkCursor<'a> {
+    sorted: LazyFrame,
+    offset: usize,
+    exhausted: bool,
+    //Subjects as IRIs, so that they have the order of the chunk, and rendered objects
+    rows: VecDeque<(String, String)>,
+    verb: usize,
+    rdf_node_type: &'a RDFNodeType,
+    formatter: Option<&'a LexicalFormatter>,
+}
+
+impl ChunkCursor<'_> {
+    //The subject of the next row, reading the next batch when the current one is used up
+    fn next_subject(
+        &mut self,
+        batch_size: usize,
+        namespaces: &Vec<(&str, &str)>,
+    ) -> Result<Option<&str>, MappingError> {
+        if self.rows.is_empty() && !self.exhausted {
+            self.read_batch(batch_size, namespaces)?;
+        }
+        Ok(self.rows.front().map(|(s, _)| s.as_str()))
+    }
+
+    fn read_batch(
+        &mut self,
+        batch_size: usize,
+        namespaces: &Vec<(&str, &str)>,
+    ) -> Result<(), MappingError> {
+        let df = self
+            .sorted
+            .clone()
+            .slice(self.offset as i64, batch_size as IdxSize)
+            .collect()
+            .map_err(|x| MappingError::ReadParquetError(x))?;
+        self.offset += df.height();
+        self.exhausted = df.height() < batch_size;
+        if df.height() == 0 {
+            return Ok(());
+        }
+        let df = format_objects(&df, self.formatter).unwrap_or(df);
+        let triple_type = self.rdf_node_type.find_triple_type();
+        let objects = render_objects(&df, self.rdf_node_type, &triple_type, namespaces);
+        let subjects = df.column("subject").unwrap().utf8().unwrap();
+        for (s, o) in subjects.into_iter().zip(objects.utf8().unwrap().into_iter()) {
+            self.rows
+                .push_back((s.unwrap().to_string(), o.unwrap().to_string()));
+        }
+        Ok(())
+    }
+}
+
+//Writes the sorted triples of a subject as "s p1 o1 ,\n        o2 ;\n    p2 o3 .\n"
+fn write_subject_block(buf: &mut Vec<u8>, subject: &str, verbs: &[String], rows: &[(usize, String)]) {
+    let mut last_verb = None;
+    for (v, o) in rows {
+        let verb = &verbs[*v];
+        if last_verb.is_none() {
+            write!(buf, "{} {} {}", subject, verb, o).unwrap();
+        } else if last_verb != Some(*v) {
+            write!(buf, " ;\n    {} {}", verb, o).unwrap();
+        } else {
+            write!(buf, " ,\n        {}", o).unwrap();
+        }
+        last_verb = Some(*v);
+    }
+    writeln!(buf, " .").unwrap();
+}
+
+fn render_objects(
+    df: &DataFrame,
+    rdf_node_type: &RDFNodeType,
+    triple_type: &TripleType,
+    namespaces: &Vec<(&str, &str)>,
+) -> Series {
+    let objects = df.column("object").unwrap();
+    let objects = if let Some(s) = convert_to_string(objects) {
+        s
+    } else {
+        objects.clone()
+    };
+    let objects_iter = objects.utf8().unwrap().into_iter();
+    let mut rendered: Series = match triple_type {
+        TripleType::ObjectProperty => objects_iter
+            .map(|o| compact_iri(o.unwrap(), namespaces))
+            .collect(),
+        TripleType::StringProperty => objects_iter
+            .zip(df.column(LANGUAGE_TAG_COLUMN).unwrap().utf8().unwrap().into_iter())
+            .map(|(o, l)| {
+                if let Some(l) = l {
+                    format!("\"{}\"@{}", escape_literal(o.unwrap()), l)
+                } else {
+                    format!("\"{}\"", escape_literal(o.unwrap()))
+                }
+            })
+            .collect(),
+        TripleType::NonStringProperty => {
+            let dt = if let RDFNodeType::Literal(dt) = rdf_node_type {
+                compact_iri(dt.as_str(), namespaces)
+            } else {
+                panic!("Must have datatype for non string property")
+            };
+            let shorthand = if let RDFNodeType::Literal(dt) = rdf_node_type {
+                shorthand_literal_form(dt.as_str())
+            } else {
+                None
+            };
+            objects_iter
+                .map(|o| {
+                    let o = o.unwrap();
+                    match shorthand {
+                        Some(is_shorthand) if is_shorthand(o) => o.to_string(),
+                        _ => format!("\"{}\"^^{}", escape_literal(o), dt),
+                    }
+                })
+                .collect()
+        }
+    };
+    rendered.rename("object");
+    rendered
+}
+
+//Literals of these datatypes can be written without quotes and datatype when the lexical form has the Turtle syntax of the datatype
+fn shorthand_literal_form(datatype: &str) -> Option<fn(&str) -> bool> {
+    if datatype == xsd::INTEGER.as_str() {
+        Some(is_turtle_integer)
+    } else if datatype == xsd::DECIMAL.as_str() {
+        Some(is_turtle_decimal)
+    } else if datatype == xsd::DOUBLE.as_str() {
+        Some(is_turtle_double)
+    } else if datatype == xsd::BOOLEAN.as_str() {
+        Some(|o| o == "true" || o == "false")
+    } else {
+        None
+    }
+}
+
+fn is_turtle_integer(o: &str) -> bool {
+    let digits = o.strip_prefix(['+', '-']).unwrap_or(o);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_turtle_decimal(o: &str) -> bool {
+    let unsigned = o.strip_prefix(['+', '-']).unwrap_or(o);
+    if let Some((whole, fraction)) = unsigned.split_once('.') {
+        !fraction.is_empty()
+            && whole.chars().all(|c| c.is_ascii_digit())
+            && fraction.chars().all(|c| c.is_ascii_digit())
+    } else {
+        false
+    }
+}
+
+//Doubles need an exponent, otherwise they would be read as decimals or integers
+fn is_turtle_double(o: &str) -> bool {
+    if let Some((mantissa, exponent)) = o.split_once(['e', 'E']) {
+        let mantissa_unsigned = mantissa.strip_prefix(['+', '-']).unwrap_or(mantissa);
+        let mantissa_ok = match mantissa_unsigned.split_once('.') {
+            Some((whole, fraction)) => {
+                !(whole.is_empty() && fraction.is_empty())
+                    && whole.chars().all(|c| c.is_ascii_digit())
+                    && fraction.chars().all(|c| c.is_ascii_digit())
+            }
+            None => is_turtle_integer(mantissa_unsigned),
+        };
+        mantissa_ok && is_turtle_integer(exponent)
+    } else {
+        false
+    }
+}
+
+fn compact_iri(iri: &str, namespaces: &Vec<(&str, &str)>) -> String {
+    if iri.starts_with("_:") {
+        return iri.to_string();
+    }
+    for (pre, ns) in namespaces {
+        if let Some(local) = iri.strip_prefix(ns) {
+            if is_valid_local_name(local) {
+                return format!("{}:{}", pre, local);
+            }
+        }
+    }
+    format!("<{}>", iri)
+}
+
+//Conservative, names that are not matched here are written as full IRIs
+fn is_valid_local_name(local: &str) -> bool {
+    if let Some(first) = local.chars().next() {
+        if first == '-' || first.is_ascii_digit() {
+            return false;
+        }
+    }
+    local
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn escape_literal(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
@@ -0,0 +1,496 @@
+pub mod errors;
+
+use crate::ast::{
+    Annotation, Argument, ConstantLiteral, ConstantTerm, DefaultValue, Instance, ListExpanderType,
+    PType, Parameter, Signature, Statement, StottrDocument, StottrLiteral, StottrTerm,
+    StottrVariable, Template,
+};
+use crate::constants::{
+    OTTR_ANNOTATION, OTTR_ARGUMENTS, OTTR_BASE_TEMPLATE, OTTR_CROSS, OTTR_DEFAULT, OTTR_LIST,
+    OTTR_LIST_EXPAND, OTTR_LUB, OTTR_MODIFIER, OTTR_NE_LIST, OTTR_NONE, OTTR_NON_BLANK, OTTR_OF,
+    OTTR_OPTIONAL, OTTR_PARAMETERS, OTTR_PATTERN, OTTR_PREFIX, OTTR_PREFIX_IRI, OTTR_SIGNATURE,
+    OTTR_TEMPLATE, OTTR_TYPE, OTTR_VALUE, OTTR_VALUES, OTTR_VARIABLE, OTTR_ZIP_MAX, OTTR_ZIP_MIN,
+    RDFS_PREFIX, RDFS_PREFIX_IRI, RDF_PREFIX, RDF_PREFIX_IRI, XSD_PREFIX, XSD_PREFIX_IRI,
+};
+use crate::wottr::errors::{WottrError, WottrErrorKind};
+use oxrdf::vocab::{rdf, xsd};
+use oxrdf::{
+    BlankNode, Graph, Literal, NamedNode, NamedNodeRef, Subject, SubjectRef, Term, TermRef, Triple,
+};
+use std::collections::{HashMap, HashSet};
+use rio_api::parser::TriplesParser;
+use rio_turtle::TurtleParser;
+
+pub fn triples_from_turtle_str(s: &str) -> Result<Vec<Triple>, WottrError> {
+    let mut triples = vec![];
+    TurtleParser::new(s.as_bytes(), None)
+        .parse_all(&mut |x| {
+            let subject = match x.subject {
+                rio_api::model::Subject::NamedNode(nn) => {
+                    Subject::NamedNode(NamedNode::new_unchecked(nn.iri))
+                }
+                rio_api::model::Subject::BlankNode(bn) => {
+                    Subject::BlankNode(BlankNode::new_unchecked(bn.id))
+                }
+                rio_api::model::Subject::Triple(t) => {
+                    return Err(WottrError {
+                        kind: WottrErrorKind::UnsupportedTerm(t.to_string()),
+                    });
+                }
+            };
+            let predicate = NamedNode::new_unchecked(x.predicate.iri);
+            let object = match x.object {
+                rio_api::model::Term::NamedNode(nn) => {
+                    Term::NamedNode(NamedNode::new_unchecked(nn.iri))
+                }
+                rio_api::model::Term::BlankNode(bn) => {
+                    Term::BlankNode(BlankNode::new_unchecked(bn.id))
+                }
+                rio_api::model::Term::Literal(l) => Term::Literal(match l {
+                    rio_api::model::Literal::Simple { value } => Literal::new_simple_literal(value),
+                    rio_api::model::Literal::LanguageTaggedString { value, language } => {
+                        Literal::new_language_tagged_literal_unchecked(value, language)
+                    }
+                    rio_api::model::Literal::Typed { value, datatype } => {
+                        Literal::new_typed_literal(value, NamedNode::new_unchecked(datatype.iri))
+                    }
+                }),
+                rio_api::model::Term::Triple(t) => {
+                    return Err(WottrError {
+                        kind: WottrErrorKind::UnsupportedTerm(t.to_string()),
+                    });
+                }
+            };
+            triples.push(Triple::new(subject, predicate, object));
+            Ok(()) as Result<(), WottrError>
+        })?;
+    Ok(triples)
+}
+
+pub fn document_from_wottr_str(s: &str) -> Result<StottrDocument, WottrError> {
+    let triples = triples_from_turtle_str(s)?;
+    let mut graph = Graph::new();
+    for t in &triples {
+        graph.insert(t);
+    }
+    document_from_graph(&graph)
+}
+
+/// Reads templates, signatures and base templates as well as ground instances from wOTTR.
+pub fn document_from_graph(graph: &Graph) -> Result<StottrDocument, WottrError> {
+    let mut statements = vec![];
+    for template_type in [OTTR_TEMPLATE, OTTR_BASE_TEMPLATE, OTTR_SIGNATURE] {
+        for s in graph.subjects_for_predicate_object(
+            rdf::TYPE,
+            TermRef::NamedNode(NamedNodeRef::new_unchecked(template_type)),
+        ) {
+            statements.push(Statement::Template(template_from_node(graph, s)?));
+        }
+    }
+    for i in instances_from_graph(graph)? {
+        statements.push(Statement::Instance(i));
+    }
+    let mut prefix_map = HashMap::new();
+    for (pre, iri) in predefined_prefixes() {
+        prefix_map.insert(pre.to_string(), NamedNode::new_unchecked(iri));
+    }
+    Ok(StottrDocument {
+        directives: vec![],
+        statements,
+        prefix_map,
+        base_iri: None,
+    })
+}
+
+pub fn instances_from_triples(triples: &Vec<Triple>) -> Result<Vec<Instance>, WottrError> {
+    let mut graph = Graph::new();
+    for t in triples {
+        graph.insert(t);
+    }
+    instances_from_graph(&graph)
+}
+
+pub fn instances_from_graph(graph: &Graph) -> Result<Vec<Instance>, WottrError> {
+    //Instances in template patterns and annotations are not ground instances
+    let mut not_ground = HashSet::new();
+    for p in [OTTR_PATTERN, OTTR_ANNOTATION] {
+        for t in graph.triples_for_predicate(NamedNodeRef::new_unchecked(p)) {
+            not_ground.insert(t.object);
+        }
+    }
+    let mut instances = vec![];
+    for t in graph.triples_for_predicate(NamedNodeRef::new_unchecked(OTTR_OF)) {
+        if not_ground.contains(&TermRef::from(t.subject)) {
+            continue;
+        }
+        instances.push(instance_from_node(graph, t.subject, t.object, &HashMap::new())?);
+    }
+    Ok(instances)
+}
+
+fn template_from_node(graph: &Graph, node: SubjectRef) -> Result<Template, WottrError> {
+    let template_name = if let SubjectRef::NamedNode(nn) = node {
+        nn.into_owned()
+    } else {
+        return Err(WottrError {
+            kind: WottrErrorKind::InvalidTemplateName(node.to_string()),
+        });
+    };
+
+    let mut variables = HashMap::new();
+    let mut parameter_list = vec![];
+    if let Some(parameters) =
+        graph.object_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_PARAMETERS))
+    {
+        for p in rdf_list(graph, parameters)? {
+            let p_subject = term_as_subject(p)?;
+            let variable = if let Some(variable) = graph
+                .object_for_subject_predicate(p_subject, NamedNodeRef::new_unchecked(OTTR_VARIABLE))
+            {
+                variable
+            } else {
+                return Err(WottrError {
+                    kind: WottrErrorKind::MissingParameterVariable(p.to_string()),
+                });
+            };
+            let name = variable_name(variable)?;
+            variables.insert(variable, name.clone());
+
+            let ptype = if let Some(t) =
+                graph.object_for_subject_predicate(p_subject, NamedNodeRef::new_unchecked(OTTR_TYPE))
+            {
+                Some(ptype_from_term(graph, t)?)
+            } else {
+                None
+            };
+            let default_value = if let Some(d) = graph
+                .object_for_subject_predicate(p_subject, NamedNodeRef::new_unchecked(OTTR_DEFAULT))
+            {
+                Some(DefaultValue {
+                    constant_term: constant_term_from_term(graph, d)?,
+                })
+            } else {
+                None
+            };
+            let modifiers: Vec<TermRef> = graph
+                .objects_for_subject_predicate(p_subject, NamedNodeRef::new_unchecked(OTTR_MODIFIER))
+                .collect();
+            parameter_list.push(Parameter {
+                optional: modifiers.contains(&TermRef::NamedNode(NamedNodeRef::new_unchecked(
+                    OTTR_OPTIONAL,
+                ))),
+                non_blank: modifiers.contains(&TermRef::NamedNode(NamedNodeRef::new_unchecked(
+                    OTTR_NON_BLANK,
+                ))),
+                ptype,
+                stottr_variable: StottrVariable { name },
+                default_value,
+            });
+        }
+    }
+
+    let mut pattern_list = vec![];
+    for i in graph.objects_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_PATTERN)) {
+        let i_subject = term_as_subject(i)?;
+        if let Some(of) =
+            graph.object_for_subject_predicate(i_subject, NamedNodeRef::new_unchecked(OTTR_OF))
+        {
+            pattern_list.push(instance_from_node(graph, i_subject, of, &variables)?);
+        } else {
+            return Err(WottrError {
+                kind: WottrErrorKind::InvalidTemplateName(i.to_string()),
+            });
+        }
+    }
+
+    let mut annotation_list = vec![];
+    for a in graph.objects_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_ANNOTATION)) {
+        let a_subject = term_as_subject(a)?;
+        if let Some(of) =
+            graph.object_for_subject_predicate(a_subject, NamedNodeRef::new_unchecked(OTTR_OF))
+        {
+            annotation_list.push(Annotation {
+                instance: instance_from_node(graph, a_subject, of, &variables)?,
+            });
+        } else {
+            return Err(WottrError {
+                kind: WottrErrorKind::InvalidTemplateName(a.to_string()),
+            });
+        }
+    }
+
+    Ok(Template {
+        signature: Signature {
+            template_prefixed_name: template_name.to_string(),
+            template_name,
+            parameter_list,
+            annotation_list: if annotation_list.is_empty() {
+                None
+            } else {
+                Some(annotation_list)
+            },
+        },
+        pattern_list,
+    })
+}
+
+fn instance_from_node(
+    graph: &Graph,
+    node: SubjectRef,
+    template: TermRef,
+    variables: &HashMap<TermRef, String>,
+) -> Result<Instance, WottrError> {
+    let template_name = if let TermRef::NamedNode(nn) = template {
+        nn.into_owned()
+    } else {
+        return Err(WottrError {
+            kind: WottrErrorKind::InvalidTemplateName(template.to_string()),
+        });
+    };
+    let list_expander = if let Some(modifier) =
+        graph.object_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_MODIFIER))
+    {
+        Some(list_expander_from_term(modifier)?)
+    } else {
+        None
+    };
+
+    let mut argument_list = vec![];
+    if let Some(values) =
+        graph.object_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_VALUES))
+    {
+        for v in rdf_list(graph, values)? {
+            argument_list.push(Argument {
+                parameter: None,
+                list_expand: false,
+                term: stottr_term_from_term(graph, v, variables)?,
+            });
+        }
+    } else if let Some(arguments) =
+        graph.object_for_subject_predicate(node, NamedNodeRef::new_unchecked(OTTR_ARGUMENTS))
+    {
+        for a in rdf_list(graph, arguments)? {
+            let a_subject = term_as_subject(a)?;
+            let value = if let Some(value) =
+                graph.object_for_subject_predicate(a_subject, NamedNodeRef::new_unchecked(OTTR_VALUE))
+            {
+                value
+            } else {
+                return Err(WottrError {
+                    kind: WottrErrorKind::MissingArgumentValue(a.to_string()),
+                });
+            };
+            let list_expand = graph
+                .objects_for_subject_predicate(a_subject, NamedNodeRef::new_unchecked(OTTR_MODIFIER))
+                .any(|m| m == TermRef::NamedNode(NamedNodeRef::new_unchecked(OTTR_LIST_EXPAND)));
+            argument_list.push(Argument {
+                parameter: None,
+                list_expand,
+                term: stottr_term_from_term(graph, value, variables)?,
+            });
+        }
+    } else {
+        return Err(WottrError {
+            kind: WottrErrorKind::MissingArguments(node.to_string()),
+        });
+    }
+
+    Ok(Instance {
+        list_expander,
+        prefixed_template_name: template_name.to_string(),
+        template_name,
+        argument_list,
+    })
+}
+
+fn list_expander_from_term(term: TermRef) -> Result<ListExpanderType, WottrError> {
+    if let TermRef::NamedNode(nn) = term {
+        match nn.as_str() {
+            OTTR_CROSS => return Ok(ListExpanderType::Cross),
+            OTTR_ZIP_MIN => return Ok(ListExpanderType::ZipMin),
+            OTTR_ZIP_MAX => return Ok(ListExpanderType::ZipMax),
+            _ => {}
+        }
+    }
+    Err(WottrError {
+        kind: WottrErrorKind::InvalidModifier(term.to_string()),
+    })
+}
+
+fn ptype_from_term(graph: &Graph, term: TermRef) -> Result<PType, WottrError> {
+    match term {
+        TermRef::NamedNode(nn) => Ok(PType::BasicType(nn.into_owned(), prefixed_name(nn))),
+        TermRef::BlankNode(_) => {
+            let elements = rdf_list(graph, term)?;
+            if elements.len() == 2 {
+                let inner = Box::new(ptype_from_term(graph, *elements.get(1).unwrap())?);
+                if let TermRef::NamedNode(nn) = elements.get(0).unwrap() {
+                    match nn.as_str() {
+                        OTTR_LIST => return Ok(PType::ListType(inner)),
+                        OTTR_NE_LIST => return Ok(PType::NEListType(inner)),
+                        OTTR_LUB => return Ok(PType::LUBType(inner)),
+                        _ => {}
+                    }
+                }
+            }
+            Err(WottrError {
+                kind: WottrErrorKind::InvalidParameterType(term.to_string()),
+            })
+        }
+        _ => Err(WottrError {
+            kind: WottrErrorKind::InvalidParameterType(term.to_string()),
+        }),
+    }
+}
+
+//Variables are usually blank nodes, we use their labels or the local name of the IRI as the variable name
+fn variable_name(term: TermRef) -> Result<String, WottrError> {
+    match term {
+        TermRef::BlankNode(bn) => Ok(bn.as_str().to_string()),
+        TermRef::NamedNode(nn) => {
+            let iri = nn.as_str();
+            let local = iri.rsplit(|c| c == '#' || c == '/').next().unwrap();
+            Ok(local.to_string())
+        }
+        _ => Err(WottrError {
+            kind: WottrErrorKind::MissingParameterVariable(term.to_string()),
+        }),
+    }
+}
+
+fn prefixed_name(nn: NamedNodeRef) -> String {
+    for (pre, iri) in predefined_prefixes() {
+        if let Some(local) = nn.as_str().strip_prefix(iri) {
+            return format!("{}:{}", pre, local);
+        }
+    }
+    nn.to_string()
+}
+
+fn predefined_prefixes() -> [(&'static str, &'static str); 4] {
+    [
+        (RDFS_PREFIX, RDFS_PREFIX_IRI),
+        (RDF_PREFIX, RDF_PREFIX_IRI),
+        (XSD_PREFIX, XSD_PREFIX_IRI),
+        (OTTR_PREFIX, OTTR_PREFIX_IRI),
+    ]
+}
+
+fn stottr_term_from_term(
+    graph: &Graph,
+    term: TermRef,
+    variables: &HashMap<TermRef, String>,
+) -> Result<StottrTerm, WottrError> {
+    if let Some(name) = variables.get(&term) {
+        return Ok(StottrTerm::Variable(StottrVariable { name: name.clone() }));
+    }
+    if let TermRef::BlankNode(bn) = term {
+        if graph
+            .object_for_subject_predicate(SubjectRef::BlankNode(bn), rdf::FIRST)
+            .is_some()
+        {
+            let mut inner = vec![];
+            for t in rdf_list(graph, term)? {
+                inner.push(stottr_term_from_term(graph, t, variables)?);
+            }
+            if inner.iter().all(|x| matches!(x, StottrTerm::ConstantTerm(_))) {
+                return Ok(StottrTerm::ConstantTerm(ConstantTerm::ConstantList(
+                    inner
+                        .into_iter()
+                        .map(|x| {
+                            if let StottrTerm::ConstantTerm(ct) = x {
+                                ct
+                            } else {
+                                panic!("Should never happen")
+                            }
+                        })
+                        .collect(),
+                )));
+            } else {
+                return Ok(StottrTerm::List(inner));
+            }
+        }
+    }
+    Ok(StottrTerm::ConstantTerm(constant_term_from_term(graph, term)?))
+}
+
+fn constant_term_from_term(graph: &Graph, term: TermRef) -> Result<ConstantTerm, WottrError> {
+    Ok(match term {
+        TermRef::NamedNode(nn) => {
+            if nn == rdf::NIL {
+                ConstantTerm::ConstantList(vec![])
+            } else if nn.as_str() == OTTR_NONE {
+                ConstantTerm::Constant(ConstantLiteral::None)
+            } else {
+                ConstantTerm::Constant(ConstantLiteral::IRI(nn.into_owned()))
+            }
+        }
+        TermRef::BlankNode(bn) => {
+            if graph
+                .object_for_subject_predicate(SubjectRef::BlankNode(bn), rdf::FIRST)
+                .is_some()
+            {
+                let mut inner = vec![];
+                for t in rdf_list(graph, term)? {
+                    inner.push(constant_term_from_term(graph, t)?);
+                }
+                ConstantTerm::ConstantList(inner)
+            } else {
+                ConstantTerm::Constant(ConstantLiteral::BlankNode(bn.into_owned()))
+            }
+        }
+        TermRef::Literal(l) => {
+            let (language, data_type_iri) = if let Some(language) = l.language() {
+                (Some(language.to_string()), xsd::STRING.into_owned())
+            } else {
+                (None, l.datatype().into_owned())
+            };
+            ConstantTerm::Constant(ConstantLiteral::Literal(StottrLiteral {
+                value: l.value().to_string(),
+                language,
+                data_type_iri: Some(data_type_iri),
+            }))
+        }
+        _ => {
+            return Err(WottrError {
+                kind: WottrErrorKind::UnsupportedTerm(term.to_string()),
+            })
+        }
+    })
+}
+
+fn rdf_list<'a>(graph: &'a Graph, head: TermRef<'a>) -> Result<Vec<TermRef<'a>>, WottrError> {
+    let mut elements = vec![];
+    let mut current = head;
+    //A list where a node is its own rest, directly or through other nodes, never ends
+    let mut visited = HashSet::new();
+    while current != TermRef::NamedNode(rdf::NIL) {
+        if !visited.insert(current) {
+            return Err(WottrError {
+                kind: WottrErrorKind::InvalidList(head.to_string()),
+            });
+        }
+        let current_subject = term_as_subject(current)?;
+        let first = graph.object_for_subject_predicate(current_subject, rdf::FIRST);
+        let rest = graph.object_for_subject_predicate(current_subject, rdf::REST);
+        if let (Some(first), Some(rest)) = (first, rest) {
+            elements.push(first);
+            current = rest;
+        } else {
+            return Err(WottrError {
+                kind: WottrErrorKind::InvalidList(head.to_string()),
+            });
+        }
+    }
+    Ok(elements)
+}
+
+fn term_as_subject(term: TermRef) -> Result<SubjectRef, WottrError> {
+    match term {
+        TermRef::NamedNode(nn) => Ok(SubjectRef::NamedNode(nn)),
+        TermRef::BlankNode(bn) => Ok(SubjectRef::BlankNode(bn)),
+        _ => Err(WottrError {
+            kind: WottrErrorKind::InvalidList(term.to_string()),
+        }),
+    }
+}
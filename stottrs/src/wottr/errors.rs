@@ -0,0 +1,65 @@
+use rio_turtle::TurtleError;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum WottrErrorKind {
+    TurtleParsingError(String),
+    InvalidTemplateName(String),
+    MissingArguments(String),
+    MissingArgumentValue(String),
+    InvalidList(String),
+    InvalidModifier(String),
+    MissingParameterVariable(String),
+    InvalidParameterType(String),
+    UnsupportedTerm(String),
+}
+
+#[derive(Debug)]
+pub struct WottrError {
+    pub(crate) kind: WottrErrorKind,
+}
+
+impl Display for WottrError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match &self.kind {
+            WottrErrorKind::TurtleParsingError(s) => {
+                write!(f, "Could not parse turtle: {}", s)
+            }
+            WottrErrorKind::InvalidTemplateName(s) => {
+                write!(f, "Instance refers to {} which is not a valid template name", s)
+            }
+            WottrErrorKind::MissingArguments(s) => {
+                write!(f, "Instance {} has neither ottr:values nor ottr:arguments", s)
+            }
+            WottrErrorKind::MissingArgumentValue(s) => {
+                write!(f, "Argument {} has no ottr:value", s)
+            }
+            WottrErrorKind::InvalidList(s) => {
+                write!(f, "Term {} is not a well formed RDF list", s)
+            }
+            WottrErrorKind::InvalidModifier(s) => {
+                write!(f, "Modifier {} is not recognized", s)
+            }
+            WottrErrorKind::MissingParameterVariable(s) => {
+                write!(f, "Parameter {} has no valid ottr:variable", s)
+            }
+            WottrErrorKind::InvalidParameterType(s) => {
+                write!(f, "Term {} is not a valid parameter type", s)
+            }
+            WottrErrorKind::UnsupportedTerm(s) => {
+                write!(f, "Term {} is not supported, such as an RDF-star triple", s)
+            }
+        }
+    }
+}
+
+impl Error for WottrError {}
+
+impl From<TurtleError> for WottrError {
+    fn from(e: TurtleError) -> Self {
+        WottrError {
+            kind: WottrErrorKind::TurtleParsingError(e.to_string()),
+        }
+    }
+}
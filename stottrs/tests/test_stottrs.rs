@@ -4,17 +4,57 @@ extern crate core;
 mod utils;
 
 use crate::utils::triples_from_file;
-use stottrs::mapping::{ExpandOptions, Mapping};
-use oxrdf::{Literal, NamedNode, Subject, Term, Triple};
+use stottrs::document::document_from_str;
+use stottrs::mapping::errors::MappingError;
+use stottrs::mapping::{
+    Coercion, CoercionPolicy, DerivedId, DerivedIdFormat, DuplicateColumns, ExpandOptions,
+    ExpansionStrategy, Mapping, RDFNodeType, Sample, SampleSize, SanitizePolicy, SanitizedColumn, StopReason, TemplateKey,
+    TemplateRows, ValidationMode,
+};
+use stottrs::mapping::backpressure::{BackpressureHook, ExpansionStage};
+use stottrs::mapping::chunking::ChunkPolicy;
+use stottrs::mapping::hierarchy::Hierarchy;
+use stottrs::mapping::coverage::{PatternCoverage, TemplateCoverage};
+use stottrs::mapping::units::{UnitAnnotation, UnitMode};
+use stottrs::mapping::wide_to_long::WideToLong;
+use stottrs::templates::{TemplateDataset, TypingErrorType};
+use stottrs::triplestore::custom_datatypes::{CustomDatatype, LexicalFormatter};
+use stottrs::triplestore::deduplication::DeduplicationStrategy;
+use stottrs::triplestore::export_size::ExportFormat;
+use stottrs::triplestore::incremental_export::ExportWatermark;
+use stottrs::triplestore::iri_validation::IriValidation;
+use stottrs::triplestore::join_hints::{JoinHint, TriplePosition};
+use stottrs::triplestore::ntriples_write::NTriplesWriteOptions;
+use stottrs::triplestore::recovery::RecoveryAction;
+use stottrs::triplestore::retention::RetentionPolicy;
+use stottrs::triplestore::serializer::{
+    NTriplesSerializer, SerializationFormat, TripleSerializer, TurtleSerializer,
+};
+use stottrs::triplestore::Triplestore;
+use stottrs::triplestore::sparql::errors::SparqlError;
+use stottrs::triplestore::sparql::query_context::Context;
+use stottrs::triplestore::sparql::solution_mapping::SolutionMappings;
+use stottrs::triplestore::sparql::sparql_results::SparqlResultsFormat;
+use stottrs::triplestore::sparql::QueryResult;
+use oxrdf::vocab::xsd;
+use oxrdf::{Literal, NamedNode, Subject, Term, Triple, Variable};
+use spargebra::Query;
 use polars::frame::DataFrame;
 use polars::series::Series;
-use polars_core::prelude::{AnyValue, TimeUnit};
+use polars_core::prelude::{AnyValue, ChunkApply, DataType, IntoSeries, NamedFrom, TimeUnit};
 use rstest::*;
 use serial_test::serial;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
-use polars::prelude::{col, IntoLazy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use polars::prelude::{
+    col, lit, IntoLazy, IpcReader, IpcWriter, ParquetReader, ParquetWriter, SerReader, SerWriter,
+};
 
 #[fixture]
 fn testdata_path() -> PathBuf {
@@ -179,279 +219,1224 @@ fn test_string_language_tag_cases() {
 
 #[rstest]
 #[serial]
-fn test_const_list_case() {
+fn test_language_tags_from_column() {
     let t_str = r#"
     @prefix ex:<http://example.net/ns#>.
 
-    ex:ExampleTemplate [xsd:anyURI ?var1]
+    ex:Label [?thing, ?label]
       :: {
-        cross | ottr:Triple(?var1, ex:hasNumber, ++(1,2))
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ex:Label(?myThing, ?myLabel)
       } .
     "#;
 
-    let mut v1 = Series::from_iter([
-        "http://example.net/ns#OneThing",
-        "http://example.net/ns#AnotherThing",
-    ]);
-    v1.rename("var1");
-    let series = [v1];
-    let df = DataFrame::from_iter(series);
+    let my_thing = Series::new(
+        "myThing",
+        [
+            "http://example.net/ns#one",
+            "http://example.net/ns#two",
+            "http://example.net/ns#three",
+        ],
+    );
+    let my_label = Series::new("myLabel", ["en", "to", "drei"]);
+    let lang = Series::new("lang", [Some("en"), Some("nb"), None]);
+    let df = DataFrame::new(vec![my_thing, my_label, lang]).unwrap();
 
     let mut mapping = Mapping::from_str(&t_str, None).unwrap();
-    let _report = mapping
+    mapping
         .expand(
             "http://example.net/ns#ExampleTemplate",
-            df,
-            Default::default(),
+            df.clone(),
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("myLabel".to_string(), "de".to_string())])),
+                language_tag_columns: Some(HashMap::from([(
+                    "myLabel".to_string(),
+                    "lang".to_string(),
+                )])),
+                ..Default::default()
+            },
         )
         .expect("");
-    let triples = mapping.export_oxrdf_triples().unwrap();
-    //println!("{:?}", triples);
-    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
-    let expected_triples_set = HashSet::from([
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "1",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "2",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked(
-                "http://example.net/ns#AnotherThing",
-            )),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "1",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked(
-                "http://example.net/ns#AnotherThing",
-            )),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "2",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
-            )),
-        },
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, value: &str, language: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(s)),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#label"),
+        object: Term::Literal(Literal::new_language_tagged_literal_unchecked(value, language)),
+    };
+    let expected = HashSet::from([
+        triple("http://example.net/ns#one", "en", "en"),
+        triple("http://example.net/ns#two", "to", "nb"),
+        triple("http://example.net/ns#three", "drei", "de"),
     ]);
-    assert_eq!(expected_triples_set, actual_triples_set);
+    assert_eq!(triples, expected);
+
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert!(nt.contains("<http://example.net/ns#two> <http://example.net/ns#label> \"to\"@nb ."));
+
+    let bad_lang = Series::new("lang", [Some("en"), Some("not a tag"), None]);
+    let mut bad_df = df.clone();
+    bad_df.with_column(bad_lang).unwrap();
+    assert!(mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            bad_df,
+            ExpandOptions {
+                language_tag_columns: Some(HashMap::from([(
+                    "myLabel".to_string(),
+                    "lang".to_string(),
+                )])),
+                ..Default::default()
+            },
+        )
+        .is_err());
+
+    assert!(mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tag_columns: Some(HashMap::from([(
+                    "myThing".to_string(),
+                    "lang".to_string(),
+                )])),
+                ..Default::default()
+            },
+        )
+        .is_err());
 }
 
 #[rstest]
 #[serial]
-fn test_nested_templates() {
-    let stottr = r#"
-@prefix ex:<http://example.net/ns#>.
-ex:ExampleTemplate [?myVar1 , ?myVar2] :: {
-    ex:Nested(?myVar1),  
-    ottr:Triple(ex:anObject, ex:hasOtherNumber, ?myVar2)
-  } .
-ex:Nested [?myVar] :: {
-    ottr:Triple(ex:anObject, ex:hasNumber, ?myVar)
-} .
-"#;
-    let mut mapping = Mapping::from_str(&stottr, None).unwrap();
-    let mut v1 = Series::from_iter(&[1, 2i32]);
-    v1.rename("myVar1");
-    let mut v2 = Series::from_iter(&[3, 4i32]);
-    v2.rename("myVar2");
-    let series = [v1, v2];
-    let df = DataFrame::from_iter(series);
-    let _report = mapping
+fn test_count_distinct_and_approx_count_distinct() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?a, xsd:anyURI ?b]
+      :: {
+        ottr:Triple(?a, ex:hasB, ?b)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "a",
+            [
+                "http://example.net/ns#a1",
+                "http://example.net/ns#a2",
+                "http://example.net/ns#a3",
+            ],
+        ),
+        Series::new(
+            "b",
+            [
+                "http://example.net/ns#b1",
+                "http://example.net/ns#b1",
+                "http://example.net/ns#b2",
+            ],
+        ),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    //The union gives each solution twice
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX fn:<https://github.com/magbak/stOTTRs/Functions#>
+    SELECT (COUNT(*) AS ?n) (COUNT(DISTINCT *) AS ?n_rows) (COUNT(DISTINCT ?b) AS ?n_b)
+    (fn:approxCountDistinct(DISTINCT ?a) AS ?approx_n_a) WHERE {
+        { ?a ex:hasB ?b } UNION { ?a ex:hasB ?b }
+    }
+    "#,
+        )
+        .unwrap();
+    let df = res.into_df().unwrap();
+    let value = |name: &str| {
+        df.column(name)
+            .unwrap()
+            .cast(&DataType::UInt64)
+            .unwrap()
+            .u64()
+            .unwrap()
+            .get(0)
+            .unwrap()
+    };
+    assert_eq!(value("n"), 6);
+    assert_eq!(value("n_rows"), 3);
+    assert_eq!(value("n_b"), 2);
+    assert_eq!(value("approx_n_a"), 3);
+
+    let res = mapping.triplestore.query(
+        r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT (<http://example.net/ns#unknownAggregate>(DISTINCT ?a) AS ?n) WHERE {
+        ?a ex:hasB ?b
+    }
+    "#,
+    );
+    assert!(matches!(res, Err(SparqlError::UnsupportedAggregate(..))));
+}
+
+#[rstest]
+#[serial]
+fn test_query_with_invalid_literal_gives_error() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("count", [1i64]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    for query in [
+        r#"PREFIX ex:<http://example.net/ns#>
+        PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+        SELECT ?thing WHERE { ?thing ex:count ?count FILTER(?count = "300"^^xsd:unsignedByte) }"#,
+        r#"PREFIX ex:<http://example.net/ns#>
+        PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+        SELECT ?thing WHERE { ?thing ex:count "one"^^xsd:long }"#,
+        r#"PREFIX ex:<http://example.net/ns#>
+        PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+        SELECT ?thing WHERE { ?thing ex:count ?count BIND("P1Y2X"^^xsd:duration AS ?d) }"#,
+    ] {
+        let res = mapping.triplestore.query(query);
+        assert!(matches!(res, Err(SparqlError::InvalidLiteral(..))), "{}", query);
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_language_tag_functions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        [
+            "http://example.net/ns#one",
+            "http://example.net/ns#two",
+            "http://example.net/ns#three",
+        ],
+    );
+    let my_label = Series::new("myLabel", ["colour", "farge", "Farbe"]);
+    let lang = Series::new("lang", [Some("en-GB"), Some("nb"), None]);
+    let df = DataFrame::new(vec![my_thing, my_label, lang]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tag_columns: Some(HashMap::from([(
+                    "myLabel".to_string(),
+                    "lang".to_string(),
+                )])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?lang ?dt ?en ?fr WHERE {
+        ?thing ex:label ?label .
+        BIND(LANG(?label) AS ?lang)
+        BIND(DATATYPE(?label) AS ?dt)
+        BIND(LANGMATCHES(LANG(?label), "EN") AS ?en)
+        BIND(STRLANG(?label, "fr") AS ?frLabel)
+        BIND(LANG(?frLabel) AS ?fr)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let df = df.sort(["thing"], vec![false]).unwrap();
+        let mut thing = Series::from_iter([
+            "http://example.net/ns#one",
+            "http://example.net/ns#three",
+            "http://example.net/ns#two",
+        ]);
+        thing.rename("thing");
+        let mut lang = Series::from_iter(["en-GB", "", "nb"]);
+        lang.rename("lang");
+        let mut dt = Series::from_iter([
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString",
+            "http://www.w3.org/2001/XMLSchema#string",
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString",
+        ]);
+        dt.rename("dt");
+        let mut en = Series::from_iter([true, false, false]);
+        en.rename("en");
+        let mut fr = Series::from_iter(["fr", "fr", "fr"]);
+        fr.rename("fr");
+        let expected_df = DataFrame::new(vec![thing, lang, dt, en, fr]).unwrap();
+        assert_eq!(df, expected_df);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE {
+        ?thing ex:label ?label .
+        FILTER(LANGMATCHES(LANG(?label), "*"))
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 2);
+    } else {
+        panic!("Expected select result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_string_functions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        [
+            "http://example.net/ns#one",
+            "http://example.net/ns#two",
+            "http://example.net/ns#three",
+        ],
+    );
+    let my_label = Series::new("myLabel", ["Pump-101", "pump-202", "Valve-303"]);
+    let df = DataFrame::new(vec![my_thing, my_label]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
         .expand(
             "http://example.net/ns#ExampleTemplate",
             df,
             Default::default(),
         )
         .unwrap();
-    let triples = mapping.export_oxrdf_triples().unwrap();
-    //println!("{:?}", triples);
-    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
-    let expected_triples_set = HashSet::from([
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "1",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "2",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasOtherNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "3",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
-            )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasOtherNumber"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "4",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
-            )),
-        },
-    ]);
-    assert_eq!(expected_triples_set, actual_triples_set);
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?starts ?ends ?contains ?sub ?replaced WHERE {
+        ?thing ex:label ?label .
+        FILTER(REGEX(?label, "^pump", "i"))
+        BIND(STRSTARTS(?label, "Pump") AS ?starts)
+        BIND(STRENDS(?label, "202") AS ?ends)
+        BIND(CONTAINS(?label, "-1") AS ?contains)
+        BIND(SUBSTR(?label, 6, 2) AS ?sub)
+        BIND(REPLACE(?label, "([a-z]+)-([0-9]+)", "$2:$1", "i") AS ?replaced)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let df = df.sort(["thing"], vec![false]).unwrap();
+        let mut thing = Series::from_iter(["http://example.net/ns#one", "http://example.net/ns#two"]);
+        thing.rename("thing");
+        let mut starts = Series::from_iter([true, false]);
+        starts.rename("starts");
+        let mut ends = Series::from_iter([false, true]);
+        ends.rename("ends");
+        let mut contains = Series::from_iter([true, false]);
+        contains.rename("contains");
+        let mut sub = Series::from_iter(["10", "20"]);
+        sub.rename("sub");
+        let mut replaced = Series::from_iter(["101:Pump", "202:pump"]);
+        replaced.rename("replaced");
+        let expected_df = DataFrame::new(vec![thing, starts, ends, contains, sub, replaced]).unwrap();
+        assert_eq!(df, expected_df);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping.triplestore.query(
+        r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE {
+        ?thing ex:label ?label .
+        FILTER(REGEX(?label, "pump", "z"))
+    }
+    "#,
+    );
+    assert!(matches!(res, Err(SparqlError::InvalidRegexFlags(..))));
 }
 
-// ?Date
-// ?Time
-// ?Duration_sec
-// ?List_Utf8
+#[rstest]
+#[serial]
+fn test_sparql_term_construction_functions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        ["http://example.net/ns#one", "http://example.net/ns#two"],
+    );
+    let my_label = Series::new("myLabel", ["Pump 101", "Valve/202"]);
+    let df = DataFrame::new(vec![my_thing, my_label]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    mapping
+        .triplestore
+        .construct_update(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+    CONSTRUCT { ?asset ex:describes ?thing . ?asset ex:code ?code . ?asset ex:id ?id } WHERE {
+        ?thing ex:label ?label .
+        BIND(IRI(CONCAT("http://example.net/asset/", ENCODE_FOR_URI(?label))) AS ?asset)
+        BIND(STRDT(SUBSTR(?label, 6), xsd:integer) AS ?code)
+        BIND(UUID() AS ?id)
+    }
+    "#,
+        )
+        .unwrap();
+    let triples: Vec<Triple> = mapping.export_oxrdf_triples().unwrap();
+    let describes = NamedNode::new_unchecked("http://example.net/ns#describes");
+    let code = NamedNode::new_unchecked("http://example.net/ns#code");
+    let id = NamedNode::new_unchecked("http://example.net/ns#id");
+    let pump = Subject::NamedNode(NamedNode::new_unchecked("http://example.net/asset/Pump%20101"));
+    let valve = Subject::NamedNode(NamedNode::new_unchecked("http://example.net/asset/Valve%2F202"));
+    let triples: HashSet<Triple> = triples.into_iter().collect();
+    assert!(triples.contains(&Triple::new(
+        pump.clone(),
+        describes.clone(),
+        Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#one")),
+    )));
+    assert!(triples.contains(&Triple::new(
+        valve.clone(),
+        describes,
+        Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#two")),
+    )));
+    assert!(triples.contains(&Triple::new(
+        pump,
+        code.clone(),
+        Term::Literal(Literal::new_typed_literal("101", xsd::INTEGER)),
+    )));
+    //The code of the valve is not a valid integer, so it is unbound
+    assert!(!triples.iter().any(|t| t.subject == valve && t.predicate == code));
+    let ids: Vec<&Triple> = triples.iter().filter(|t| t.predicate == id).collect();
+    assert_eq!(ids.len(), 2);
+    for t in ids {
+        if let Term::NamedNode(nn) = &t.object {
+            assert!(nn.as_str().starts_with("urn:uuid:"));
+        } else {
+            panic!("Expected an IRI");
+        }
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?first ?second ?other WHERE {
+        ?thing ex:label ?label .
+        BIND(BNODE(?label) AS ?first)
+        BIND(BNODE(?label) AS ?second)
+        BIND(BNODE() AS ?other)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 2);
+        let first = df.column("first").unwrap();
+        assert_eq!(first, df.column("second").unwrap());
+        assert_eq!(first.n_unique().unwrap(), 2);
+        assert!(first.utf8().unwrap().into_no_null_iter().all(|b| b.starts_with("_:")));
+        assert_eq!(df.column("other").unwrap().n_unique().unwrap(), 2);
+    } else {
+        panic!("Expected select result");
+    }
+}
 
 #[rstest]
 #[serial]
-fn test_derived_datatypes() {
-    let stottr = r#"
-@prefix ex:<http://example.net/ns#>.
-ex:ExampleTemplate [
-?Boolean,
-?UInt32,
-?UInt64,
-?Int32,
-?Int64,
-?Float32,
-?Float64,
-?Utf8,
-?Datetime_ms_tz,
-?Datetime_ms
-] :: {
-    ottr:Triple(ex:anObject, ex:hasVal, ?Boolean),
-    ottr:Triple(ex:anObject, ex:hasVal, ?UInt32),
-    ottr:Triple(ex:anObject, ex:hasVal, ?UInt64),
-    ottr:Triple(ex:anObject, ex:hasVal, ?Int32),
-    ottr:Triple(ex:anObject, ex:hasVal, ?Int64),
-    ottr:Triple(ex:anotherObject, ex:hasValVal, ?Float32),
-    ottr:Triple(ex:anotherObject, ex:hasValVal, ?Float64),
-    ottr:Triple(ex:yetAnotherObject, ex:hasString, ?Utf8),
-    ottr:Triple(ex:yetAnotherObject, ex:hasDateTime, ?Datetime_ms_tz),
-    ottr:Triple(ex:yetAnotherObject, ex:hasDateTime, ?Datetime_ms)
-  } .
-"#;
-    let mut mapping = Mapping::from_str(&stottr, None).unwrap();
-    let mut boolean = Series::from_iter(&[true, false]);
-    boolean.rename("Boolean");
-    let mut uint32 = Series::from_iter(&[5u32, 6u32]);
-    uint32.rename("UInt32");
-    let mut uint64 = Series::from_iter(&[7u64, 8u64]);
-    uint64.rename("UInt64");
-    let mut int32 = Series::from_iter(&[-13i32, -14i32]);
-    int32.rename("Int32");
-    let mut int64 = Series::from_iter(&[-15i64, -16i64]);
-    int64.rename("Int64");
-    let mut float32 = Series::from_iter(&[17.18f32, 19.20f32]);
-    float32.rename("Float32");
-    let mut float64 = Series::from_iter(&[21.22f64, 23.24f64]);
-    float64.rename("Float64");
-    let mut utf8 = Series::from_iter(["abcde", "fghij"]);
-    utf8.rename("Utf8");
-    let datetime_ms_tz = Series::from_any_values(
-        "Datetime_ms_tz",
-        &[
-            AnyValue::Datetime(
-                1656842780123,
-                TimeUnit::Milliseconds,
-                &Some("Europe/Oslo".to_string()),
-            ),
-            AnyValue::Datetime(
-                1656842781456,
-                TimeUnit::Milliseconds,
-                &Some("Europe/Oslo".to_string()),
-            ),
-        ],
-    )
+fn test_sparql_bind_computed_values() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:long ?myCount]
+      :: {
+        ottr:Triple(?myThing, ex:count, ?myCount)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        ["http://example.net/ns#one", "http://example.net/ns#two"],
+    );
+    let my_count = Series::new("myCount", [3i64, 5i64]);
+    let df = DataFrame::new(vec![my_thing, my_count]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+
+    //Binding without any triple patterns gives a single solution
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    SELECT ?x ?y WHERE {
+        BIND(1 AS ?x)
+        BIND(?x + 1 AS ?y)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let mut x = Series::from_iter([1i64]);
+        x.rename("x");
+        let mut y = Series::from_iter([2i64]);
+        y.rename("y");
+        assert_eq!(df, DataFrame::new(vec![x, y]).unwrap());
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?doubled ?kind WHERE {
+        BIND("counted" AS ?kind)
+        ?thing ex:count ?count .
+        BIND(?count * 2 AS ?doubled)
+    } ORDER BY DESC(?doubled)
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let mut thing = Series::from_iter(["http://example.net/ns#two", "http://example.net/ns#one"]);
+        thing.rename("thing");
+        let mut doubled = Series::from_iter([10i64, 6i64]);
+        doubled.rename("doubled");
+        let mut kind = Series::from_iter(["counted", "counted"]);
+        kind.rename("kind");
+        assert_eq!(df, DataFrame::new(vec![thing, doubled, kind]).unwrap());
+    } else {
+        panic!("Expected select result");
+    }
+
+    //The bound variable has the datatype of the expression when it is constructed
+    mapping
+        .triplestore
+        .construct_update(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    CONSTRUCT { ?thing ex:doubled ?doubled } WHERE {
+        ?thing ex:count ?count .
+        BIND(?count * 2 AS ?doubled)
+    }
+    "#,
+        )
+        .unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    assert!(triples.contains(&Triple::new(
+        NamedNode::new_unchecked("http://example.net/ns#one"),
+        NamedNode::new_unchecked("http://example.net/ns#doubled"),
+        Literal::new_typed_literal("6", xsd::INTEGER),
+    )));
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_order_by_mixed_types() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:long ?myLong, xsd:double ?myDouble, xsd:string ?myString, xsd:anyURI ?myIRI]
+      :: {
+        ottr:Triple(ex:a, ex:value, ?myLong),
+        ottr:Triple(ex:b, ex:value, ?myDouble),
+        ottr:Triple(ex:c, ex:value, ?myString),
+        ottr:Triple(ex:d, ex:value, ?myIRI),
+        ottr:Triple(ex:a, ex:kind, "thing"),
+        ottr:Triple(ex:b, ex:kind, "thing"),
+        ottr:Triple(ex:c, ex:kind, "thing"),
+        ottr:Triple(ex:d, ex:kind, "thing"),
+        ottr:Triple(ex:e, ex:kind, "thing")
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new("myLong", [10i64]),
+        Series::new("myDouble", [2.5f64]),
+        Series::new("myString", ["apple"]),
+        Series::new("myIRI", ["http://example.net/ns#thing"]),
+    ])
     .unwrap();
-    let datetime_ms = Series::from_any_values(
-        "Datetime_ms",
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+
+    let ordered_subjects = |mapping: &mut Mapping, order: &str| -> Vec<String> {
+        let query = format!(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?v WHERE {{
+        ?s ex:kind ?k .
+        OPTIONAL {{ ?s ex:value ?v }}
+    }} ORDER BY {}
+    "#,
+            order
+        );
+        if let QueryResult::Select(df) = mapping.triplestore.query(&query).unwrap() {
+            df.column("s")
+                .unwrap()
+                .utf8()
+                .unwrap()
+                .into_no_null_iter()
+                .map(|s| s.strip_prefix("http://example.net/ns#").unwrap().to_string())
+                .collect()
+        } else {
+            panic!("Expected select result");
+        }
+    };
+    //Unbound values first, then IRIs, then numbers by value and then other literals
+    assert_eq!(
+        ordered_subjects(&mut mapping, "?v ?s"),
+        vec!["e", "d", "b", "a", "c"]
+    );
+    assert_eq!(
+        ordered_subjects(&mut mapping, "DESC(?v) ?s"),
+        vec!["c", "a", "b", "d", "e"]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_numeric_and_date_functions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:double ?myValue, xsd:long ?myCount, xsd:dateTime ?myTimestamp]
+      :: {
+        ottr:Triple(?myThing, ex:value, ?myValue),
+        ottr:Triple(?myThing, ex:count, ?myCount),
+        ottr:Triple(?myThing, ex:timestamp, ?myTimestamp)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        ["http://example.net/ns#one", "http://example.net/ns#two"],
+    );
+    let my_value = Series::new("myValue", [-2.5f64, 2.4f64]);
+    let my_count = Series::new("myCount", [-3i64, 4i64]);
+    let my_timestamp = Series::from_any_values(
+        "myTimestamp",
         &[
             AnyValue::Datetime(1656842790789, TimeUnit::Milliseconds, &None),
-            AnyValue::Datetime(1656842791101, TimeUnit::Milliseconds, &None),
+            AnyValue::Datetime(1672531199000, TimeUnit::Milliseconds, &None),
         ],
     )
     .unwrap();
+    let df = DataFrame::new(vec![my_thing, my_value, my_count, my_timestamp]).unwrap();
 
-    let series = [
-        boolean,
-        uint32,
-        uint64,
-        int32,
-        int64,
-        float32,
-        float64,
-        utf8,
-        datetime_ms_tz,
-        datetime_ms,
-    ];
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?abs ?ceil ?floor ?round ?roundCount ?year ?month ?day ?hours WHERE {
+        ?thing ex:value ?value .
+        ?thing ex:count ?count .
+        ?thing ex:timestamp ?timestamp .
+        BIND(ABS(?value) AS ?abs)
+        BIND(CEIL(?value) AS ?ceil)
+        BIND(FLOOR(?value) AS ?floor)
+        BIND(ROUND(?value) AS ?round)
+        BIND(ROUND(?count) AS ?roundCount)
+        BIND(YEAR(?timestamp) AS ?year)
+        BIND(MONTH(?timestamp) AS ?month)
+        BIND(DAY(?timestamp) AS ?day)
+        BIND(HOURS(?timestamp) AS ?hours)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let df = df.sort(["thing"], vec![false]).unwrap();
+        let mut thing = Series::from_iter(["http://example.net/ns#one", "http://example.net/ns#two"]);
+        thing.rename("thing");
+        let mut abs = Series::from_iter([2.5f64, 2.4f64]);
+        abs.rename("abs");
+        let mut ceil = Series::from_iter([-2.0f64, 3.0f64]);
+        ceil.rename("ceil");
+        let mut floor = Series::from_iter([-3.0f64, 2.0f64]);
+        floor.rename("floor");
+        let mut round = Series::from_iter([-2.0f64, 2.0f64]);
+        round.rename("round");
+        let mut round_count = Series::from_iter([-3i64, 4i64]);
+        round_count.rename("roundCount");
+        let mut year = Series::from_iter([2022i64, 2022i64]);
+        year.rename("year");
+        let mut month = Series::from_iter([7i64, 12i64]);
+        month.rename("month");
+        let mut day = Series::from_iter([3i64, 31i64]);
+        day.rename("day");
+        let mut hours = Series::from_iter([10i64, 23i64]);
+        hours.rename("hours");
+        let expected_df = DataFrame::new(vec![
+            thing,
+            abs,
+            ceil,
+            floor,
+            round,
+            round_count,
+            year,
+            month,
+            day,
+            hours,
+        ])
+        .unwrap();
+        assert_eq!(df, expected_df);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    CONSTRUCT { ?thing ex:year ?year . ?thing ex:checked ?now } WHERE {
+        ?thing ex:timestamp ?timestamp .
+        BIND(YEAR(?timestamp) AS ?year)
+        BIND(NOW() AS ?now)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Construct(dfs) = res {
+        let mut checked = None;
+        for (df, object_type) in dfs {
+            if object_type == RDFNodeType::Literal(xsd::INTEGER.into_owned()) {
+                assert_eq!(df.height(), 2);
+            } else {
+                assert_eq!(object_type, RDFNodeType::Literal(xsd::DATE_TIME.into_owned()));
+                checked = Some(df);
+            }
+        }
+        //All rows get the same time, which is when the query started
+        let checked = checked.unwrap();
+        assert_eq!(checked.height(), 2);
+        assert_eq!(checked.column("object").unwrap().null_count(), 0);
+        assert_eq!(checked.column("object").unwrap().n_unique().unwrap(), 1);
+    } else {
+        panic!("Expected construct result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_lang_string_constants_and_defaults() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Label [xsd:anyURI ?thing, rdf:langString ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, rdf:langString ?myLabel = "hei"@nb]
+      :: {
+        ex:Label(?myThing, ?myLabel) ,
+        ex:Label(?myThing, "hallo"@de)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        ["http://example.net/ns#one", "http://example.net/ns#two"],
+    );
+    let my_label = Series::new("myLabel", [Some("hello"), None]);
+    let df = DataFrame::new(vec![my_thing, my_label]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("myLabel".to_string(), "en".to_string())])),
+                ..Default::default()
+            },
+        )
+        .expect("");
+    let instances = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+
+    [] ottr:of ex:Label ;
+       ottr:values ( ex:three "tre"@nb ) .
+    [] ottr:of ex:Label ;
+       ottr:values ( ex:four "vier"@de ) .
+    "#;
+    mapping.expand_turtle_instances(instances).unwrap();
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, value: &str, language: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(s)),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#label"),
+        object: Term::Literal(Literal::new_language_tagged_literal_unchecked(value, language)),
+    };
+    let expected = HashSet::from([
+        triple("http://example.net/ns#one", "hello", "en"),
+        triple("http://example.net/ns#two", "hei", "nb"),
+        triple("http://example.net/ns#one", "hallo", "de"),
+        triple("http://example.net/ns#two", "hallo", "de"),
+        triple("http://example.net/ns#three", "tre", "nb"),
+        triple("http://example.net/ns#four", "vier", "de"),
+    ]);
+    assert_eq!(triples, expected);
+
+    let not_lang_string = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Label [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing]
+      :: {
+        ex:Label(?myThing, "hallo"@de)
+      } .
+    "#;
+    assert!(Mapping::from_str(&not_lang_string, None).is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_language_aware_deduplication() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [?myString, ?myOtherString]
+      :: {
+        ottr:Triple(ex:anObject, ex:hasString, ?myString) ,
+        ottr:Triple(ex:anObject, ex:hasString, ?myOtherString)
+      } .
+    "#;
+
+    let mut my_string = Series::from_iter(["one"]);
+    my_string.rename("myString");
+    let mut my_other_string = Series::from_iter(["one"]);
+    my_other_string.rename("myOtherString");
+    let df = DataFrame::from_iter([my_string, my_other_string]);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([
+                    ("myString".to_string(), "en".to_string()),
+                    ("myOtherString".to_string(), "nb".to_string()),
+                ])),
+                ..Default::default()
+            },
+        )
+        .expect("");
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    assert_eq!(triples.len(), 2);
+
+    mapping.triplestore.set_collapse_language_tags(true);
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    assert_eq!(triples.len(), 1);
+}
+
+#[rstest]
+#[serial]
+fn test_const_list_case() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?var1]
+      :: {
+        cross | ottr:Triple(?var1, ex:hasNumber, ++(1,2))
+      } .
+    "#;
+
+    let mut v1 = Series::from_iter([
+        "http://example.net/ns#OneThing",
+        "http://example.net/ns#AnotherThing",
+    ]);
+    v1.rename("var1");
+    let series = [v1];
     let df = DataFrame::from_iter(series);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
     let _report = mapping
         .expand(
             "http://example.net/ns#ExampleTemplate",
             df,
             Default::default(),
         )
-        .unwrap();
-    let mut actual_triples = mapping.export_oxrdf_triples().unwrap();
-    let mut expected_triples = vec![
+        .expect("");
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    //println!("{:?}", triples);
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([
         Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
             object: Term::Literal(Literal::new_typed_literal(
-                "true",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#boolean"),
+                "1",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
             )),
         },
         Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
             object: Term::Literal(Literal::new_typed_literal(
-                "false",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#boolean"),
+                "2",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
             )),
         },
         Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
-            object: Term::Literal(Literal::new_typed_literal(
-                "5",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedInt"),
+            subject: Subject::NamedNode(NamedNode::new_unchecked(
+                "http://example.net/ns#AnotherThing",
             )),
-        },
-        Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
             object: Term::Literal(Literal::new_typed_literal(
-                "6",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedInt"),
+                "1",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
             )),
         },
         Triple {
-            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
-            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            subject: Subject::NamedNode(NamedNode::new_unchecked(
+                "http://example.net/ns#AnotherThing",
+            )),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
             object: Term::Literal(Literal::new_typed_literal(
-                "7",
-                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedLong"),
+                "2",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#integer"),
+            )),
+        },
+    ]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_nested_templates() {
+    let stottr = r#"
+@prefix ex:<http://example.net/ns#>.
+ex:ExampleTemplate [?myVar1 , ?myVar2] :: {
+    ex:Nested(?myVar1),  
+    ottr:Triple(ex:anObject, ex:hasOtherNumber, ?myVar2)
+  } .
+ex:Nested [?myVar] :: {
+    ottr:Triple(ex:anObject, ex:hasNumber, ?myVar)
+} .
+"#;
+    let mut mapping = Mapping::from_str(&stottr, None).unwrap();
+    let mut v1 = Series::from_iter(&[1, 2i32]);
+    v1.rename("myVar1");
+    let mut v2 = Series::from_iter(&[3, 4i32]);
+    v2.rename("myVar2");
+    let series = [v1, v2];
+    let df = DataFrame::from_iter(series);
+    let _report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    //println!("{:?}", triples);
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "1",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "2",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasOtherNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "3",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasOtherNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "4",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int"),
+            )),
+        },
+    ]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_predicates_produced() {
+    let stottr = r#"
+@prefix ex:<http://example.net/ns#>.
+ex:Outer [?o] :: {
+    ex:Via(ex:viaProp, ?o),
+    ottr:Triple(ex:anObject, ex:direct, ?o)
+  } .
+ex:Via [?p, ?o] :: {
+    ottr:Triple(ex:anObject, ?p, ?o)
+} .
+ex:Nested [?o] :: {
+    ottr:Triple(ex:anObject, ex:hasNumber, ?o)
+} .
+"#;
+    let dataset = TemplateDataset::new(vec![document_from_str(stottr).unwrap()]).unwrap();
+    let produced = dataset.predicates_produced();
+    assert_eq!(
+        produced.get("http://example.net/ns#Outer").unwrap(),
+        &HashSet::from([
+            "http://example.net/ns#viaProp".to_string(),
+            "http://example.net/ns#direct".to_string()
+        ])
+    );
+    assert!(produced.get("http://example.net/ns#Via").unwrap().is_empty());
+    assert_eq!(
+        produced.get("http://example.net/ns#Nested").unwrap(),
+        &HashSet::from(["http://example.net/ns#hasNumber".to_string()])
+    );
+    assert_eq!(
+        dataset.templates_producing("http://example.net/ns#viaProp"),
+        vec!["http://example.net/ns#Outer".to_string()]
+    );
+    assert!(dataset
+        .templates_producing("http://example.net/ns#unused")
+        .is_empty());
+}
+
+// ?Date
+// ?Time
+// ?Duration_sec
+// ?List_Utf8
+
+#[rstest]
+#[serial]
+fn test_derived_datatypes() {
+    let stottr = r#"
+@prefix ex:<http://example.net/ns#>.
+ex:ExampleTemplate [
+?Boolean,
+?UInt32,
+?UInt64,
+?Int32,
+?Int64,
+?Float32,
+?Float64,
+?Utf8,
+?Datetime_ms_tz,
+?Datetime_ms
+] :: {
+    ottr:Triple(ex:anObject, ex:hasVal, ?Boolean),
+    ottr:Triple(ex:anObject, ex:hasVal, ?UInt32),
+    ottr:Triple(ex:anObject, ex:hasVal, ?UInt64),
+    ottr:Triple(ex:anObject, ex:hasVal, ?Int32),
+    ottr:Triple(ex:anObject, ex:hasVal, ?Int64),
+    ottr:Triple(ex:anotherObject, ex:hasValVal, ?Float32),
+    ottr:Triple(ex:anotherObject, ex:hasValVal, ?Float64),
+    ottr:Triple(ex:yetAnotherObject, ex:hasString, ?Utf8),
+    ottr:Triple(ex:yetAnotherObject, ex:hasDateTime, ?Datetime_ms_tz),
+    ottr:Triple(ex:yetAnotherObject, ex:hasDateTime, ?Datetime_ms)
+  } .
+"#;
+    let mut mapping = Mapping::from_str(&stottr, None).unwrap();
+    let mut boolean = Series::from_iter(&[true, false]);
+    boolean.rename("Boolean");
+    let mut uint32 = Series::from_iter(&[5u32, 6u32]);
+    uint32.rename("UInt32");
+    let mut uint64 = Series::from_iter(&[7u64, 8u64]);
+    uint64.rename("UInt64");
+    let mut int32 = Series::from_iter(&[-13i32, -14i32]);
+    int32.rename("Int32");
+    let mut int64 = Series::from_iter(&[-15i64, -16i64]);
+    int64.rename("Int64");
+    let mut float32 = Series::from_iter(&[17.18f32, 19.20f32]);
+    float32.rename("Float32");
+    let mut float64 = Series::from_iter(&[21.22f64, 23.24f64]);
+    float64.rename("Float64");
+    let mut utf8 = Series::from_iter(["abcde", "fghij"]);
+    utf8.rename("Utf8");
+    let datetime_ms_tz = Series::from_any_values(
+        "Datetime_ms_tz",
+        &[
+            AnyValue::Datetime(
+                1656842780123,
+                TimeUnit::Milliseconds,
+                &Some("Europe/Oslo".to_string()),
+            ),
+            AnyValue::Datetime(
+                1656842781456,
+                TimeUnit::Milliseconds,
+                &Some("Europe/Oslo".to_string()),
+            ),
+        ],
+    )
+    .unwrap();
+    let datetime_ms = Series::from_any_values(
+        "Datetime_ms",
+        &[
+            AnyValue::Datetime(1656842790789, TimeUnit::Milliseconds, &None),
+            AnyValue::Datetime(1656842791101, TimeUnit::Milliseconds, &None),
+        ],
+    )
+    .unwrap();
+
+    let series = [
+        boolean,
+        uint32,
+        uint64,
+        int32,
+        int64,
+        float32,
+        float64,
+        utf8,
+        datetime_ms_tz,
+        datetime_ms,
+    ];
+    let df = DataFrame::from_iter(series);
+    let _report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let mut actual_triples = mapping.export_oxrdf_triples().unwrap();
+    let mut expected_triples = vec![
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "true",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#boolean"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "false",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#boolean"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "5",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedInt"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "6",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedInt"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasVal"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "7",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#unsignedLong"),
             )),
         },
         Triple {
@@ -950,3 +1935,6216 @@ fn test_default_list() {
     ]);
     assert_eq!(expected_triples_set, actual_triples_set);
 }
+
+#[rstest]
+#[serial]
+fn test_expand_turtle_instances() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:hasNumber, ?myVar2)
+      } .
+    "#;
+    let instances = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+    @prefix xsd:<http://www.w3.org/2001/XMLSchema#>.
+
+    [] ottr:of ex:ExampleTemplate ;
+       ottr:values ( ex:OneThing "1"^^xsd:long ) .
+    [] ottr:of ex:ExampleTemplate ;
+       ottr:arguments ( [ ottr:value ex:AnotherThing ] [ ottr:value "2"^^xsd:long ] ) .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.expand_turtle_instances(instances).unwrap();
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "1",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked(
+                "http://example.net/ns#AnotherThing",
+            )),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+            object: Term::Literal(Literal::new_typed_literal(
+                "2",
+                NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+            )),
+        },
+    ]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_expand_into_named_graph() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+
+    let mut v1 = Series::from_iter(["http://example.net/ns#OneThing"]);
+    v1.rename("myVar1");
+    let series = [v1];
+    let df = DataFrame::from_iter(series);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let _report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                graph: Some("http://example.net/ns#aGraph".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("");
+    //The triples of named graphs can not be exported as triples
+    assert!(mapping.export_oxrdf_triples().is_err());
+
+    let mut buffer = vec![];
+    mapping.write_n_quads(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let expected = "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#OneThing> <http://example.net/ns#aGraph> .\n";
+    assert_eq!(expected, actual);
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_graph_patterns() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ex:anObject)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    for (thing, graph) in [
+        ("A", None),
+        ("B", Some("http://example.net/ns#graph1")),
+        ("C", Some("http://example.net/ns#graph2")),
+    ] {
+        let df = DataFrame::new(vec![Series::new(
+            "thing",
+            [format!("http://example.net/ns#{}", thing)],
+        )])
+        .unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                ExpandOptions {
+                    graph: graph.map(|g| g.to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE { GRAPH ex:graph1 { ?thing ex:relatesTo ex:anObject } }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 1);
+        assert_eq!(
+            df.column("thing").unwrap().get(0),
+            AnyValue::Utf8("http://example.net/ns#B")
+        );
+    } else {
+        panic!("Expected SELECT results");
+    }
+
+    //The default graph is not one of the named graphs
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?g WHERE { GRAPH ?g { ?thing ex:relatesTo ex:anObject } }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let mut rows: Vec<(String, String)> = df
+            .column("thing")
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .zip(df.column("g").unwrap().utf8().unwrap().into_iter())
+            .map(|(t, g)| (t.unwrap().to_string(), g.unwrap().to_string()))
+            .collect();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "http://example.net/ns#B".to_string(),
+                    "http://example.net/ns#graph1".to_string()
+                ),
+                (
+                    "http://example.net/ns#C".to_string(),
+                    "http://example.net/ns#graph2".to_string()
+                ),
+            ]
+        );
+    } else {
+        panic!("Expected SELECT results");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_wottr_template() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+    @prefix xsd:<http://www.w3.org/2001/XMLSchema#>.
+    @prefix rdf:<http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+
+    ex:ExampleTemplate a ottr:Template ;
+      ottr:parameters ( [ ottr:variable _:myVar1 ; ottr:type xsd:anyURI ]
+                        [ ottr:variable _:myVar2 ; ottr:type xsd:long ] ) ;
+      ottr:pattern [ ottr:of ottr:Triple ;
+                     ottr:values ( _:myVar1 ex:hasNumber _:myVar2 ) ] .
+    "#;
+
+    let mut v1 = Series::from_iter(["http://example.net/ns#OneThing"]);
+    v1.rename("myVar1");
+    let mut v2 = Series::from_iter([1i64]);
+    v2.rename("myVar2");
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+
+    let mut mapping = Mapping::from_wottr_str(&t_str, None).unwrap();
+    let _report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+        object: Term::Literal(Literal::new_typed_literal(
+            "1",
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+        )),
+    }]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_wottr_cyclic_list_is_an_error() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+    @prefix rdf:<http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+
+    ex:ExampleTemplate a ottr:Template ;
+      ottr:parameters ( [ ottr:variable _:myVar1 ] ) ;
+      ottr:pattern [ ottr:of ottr:Triple ;
+                     ottr:values _:values ] .
+    _:values rdf:first _:myVar1 ; rdf:rest _:rest .
+    _:rest rdf:first ex:hasSource ; rdf:rest _:values .
+    "#;
+    let error = Mapping::from_wottr_str(&t_str, None).err().unwrap();
+    assert!(error.to_string().contains("well formed RDF list"), "{}", error);
+}
+
+#[rstest]
+#[serial]
+fn test_wottr_rdf_star_is_an_error() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+
+    ex:ExampleTemplate a ottr:Template ;
+      ottr:parameters ( [ ottr:variable _:myVar1 ] ) ;
+      ottr:pattern [ ottr:of ottr:Triple ;
+                     ottr:values ( _:myVar1 ex:hasSource << ex:a ex:b ex:c >> ) ] .
+    "#;
+    let error = Mapping::from_wottr_str(&t_str, None).err().unwrap();
+    assert!(error.to_string().contains("is not supported"), "{}", error);
+}
+
+#[rstest]
+#[serial]
+fn test_max_rows_retention_policy() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.triplestore.set_retention_policy(
+        "http://example.net/ns#relatesTo",
+        RetentionPolicy::MaxRows(2),
+    );
+    for thing in ["OneThing", "AnotherThing", "ThirdThing"] {
+        let iri = format!("http://example.net/ns#{}", thing);
+        let mut v1 = Series::from_iter([iri.as_str()]);
+        v1.rename("myVar1");
+        let df = DataFrame::new(vec![v1]).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                Default::default(),
+            )
+            .expect("");
+    }
+    mapping.triplestore.apply_retention_policies().unwrap();
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+            object: Term::NamedNode(NamedNode::new_unchecked(
+                "http://example.net/ns#AnotherThing",
+            )),
+        },
+        Triple {
+            subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+            predicate: NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+            object: Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#ThirdThing")),
+        },
+    ]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_max_rows_retention_policy_keeps_order_with_caching_folder() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(?myVar1, ex:relatesTo, ex:anObject)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_max_rows_retention_policy");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping.triplestore.set_retention_policy(
+            "http://example.net/ns#relatesTo",
+            RetentionPolicy::MaxRows(2),
+        );
+        //Added in the opposite order of the subjects, so that sorting by subject changes the order
+        for (i, thing) in ["c", "b", "a"].iter().enumerate() {
+            let iri = format!("http://example.net/ns#{}", thing);
+            let df = DataFrame::new(vec![Series::new("myVar1", &[iri.as_str()])]).unwrap();
+            mapping
+                .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+                .unwrap();
+            if i == 1 {
+                //Merges the chunks added so far
+                mapping.triplestore.deduplicate().unwrap();
+            }
+        }
+        mapping.triplestore.apply_retention_policies().unwrap();
+        let triples: HashSet<Triple> =
+            HashSet::from_iter(mapping.export_oxrdf_triples().unwrap().into_iter());
+        let expected: HashSet<Triple> = ["b", "a"]
+            .iter()
+            .map(|thing| {
+                Triple::new(
+                    NamedNode::new_unchecked(format!("http://example.net/ns#{}", thing)),
+                    NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+                    NamedNode::new_unchecked("http://example.net/ns#anObject"),
+                )
+            })
+            .collect();
+        assert_eq!(expected, triples);
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_max_rows_retention_policy_counts_deduplicated_triples_of_all_object_types() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:IriTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+
+    ex:StringTemplate [xsd:string ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_max_rows_all_object_types");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping.triplestore.set_retention_policy(
+            "http://example.net/ns#relatesTo",
+            RetentionPolicy::MaxRows(2),
+        );
+        //The string is added twice, and the duplicate does not count towards the limit
+        for (template, value) in [
+            ("IriTemplate", "http://example.net/ns#a"),
+            ("IriTemplate", "http://example.net/ns#c"),
+            ("StringTemplate", "b"),
+            ("StringTemplate", "b"),
+        ] {
+            let df = DataFrame::new(vec![Series::new("myVar1", &[value])]).unwrap();
+            mapping
+                .expand(
+                    &format!("http://example.net/ns#{}", template),
+                    df,
+                    Default::default(),
+                )
+                .unwrap();
+        }
+        mapping.triplestore.apply_retention_policies().unwrap();
+        let triples: HashSet<Triple> =
+            HashSet::from_iter(mapping.export_oxrdf_triples().unwrap().into_iter());
+        let expected = HashSet::from([
+            Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#anObject"),
+                NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+                NamedNode::new_unchecked("http://example.net/ns#c"),
+            ),
+            Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#anObject"),
+                NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+                Literal::new_simple_literal("b"),
+            ),
+        ]);
+        assert_eq!(expected, triples);
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_max_age_retention_policy() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Measurement [xsd:anyURI ?measurement, ?timestamp, xsd:double ?value]
+      :: {
+        ottr:Triple(?measurement, ex:timestamp, ?timestamp),
+        ottr:Triple(?measurement, ex:value, ?value)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_max_age_retention_policy");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+    let now = chrono::Utc::now().timestamp_millis();
+    let two_days_ago = now - chrono::Duration::days(2).num_milliseconds();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping.triplestore.set_retention_policy(
+            "http://example.net/ns#value",
+            RetentionPolicy::MaxAge {
+                timestamp_predicate: "http://example.net/ns#timestamp".to_string(),
+                max_age: chrono::Duration::hours(1),
+            },
+        );
+        let timestamps = Series::from_any_values(
+            "timestamp",
+            &[
+                AnyValue::Datetime(now, TimeUnit::Milliseconds, &None),
+                AnyValue::Datetime(two_days_ago, TimeUnit::Milliseconds, &None),
+            ],
+        )
+        .unwrap();
+        let df = DataFrame::new(vec![
+            Series::new(
+                "measurement",
+                &["http://example.net/ns#new", "http://example.net/ns#old"],
+            ),
+            timestamps,
+            Series::new("value", &[1.0f64, 2.0]),
+        ])
+        .unwrap();
+        mapping
+            .expand("http://example.net/ns#Measurement", df, Default::default())
+            .unwrap();
+        mapping.triplestore.apply_retention_policies().unwrap();
+
+        let query = r#"
+        PREFIX ex:<http://example.net/ns#>
+        SELECT ?m WHERE { ?m ex:value ?v }
+        "#;
+        if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+            assert_eq!(df.height(), 1);
+            assert_eq!(
+                df.column("m").unwrap().get(0),
+                AnyValue::Utf8("http://example.net/ns#new")
+            );
+        } else {
+            panic!("Expected SELECT results");
+        }
+        //The timestamps themselves have no policy
+        let query = r#"
+        PREFIX ex:<http://example.net/ns#>
+        SELECT ?m WHERE { ?m ex:timestamp ?t }
+        "#;
+        if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+            assert_eq!(df.height(), 2);
+        } else {
+            panic!("Expected SELECT results");
+        }
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_retention_policy_in_named_graph() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.triplestore.set_retention_policy(
+        "http://example.net/ns#relatesTo",
+        RetentionPolicy::MaxRows(1),
+    );
+    for thing in ["OneThing", "AnotherThing"] {
+        let iri = format!("http://example.net/ns#{}", thing);
+        let df = DataFrame::new(vec![Series::new("myVar1", &[iri.as_str()])]).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                ExpandOptions {
+                    graph: Some("http://example.net/ns#aGraph".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    mapping.triplestore.apply_retention_policies().unwrap();
+
+    let mut buffer = vec![];
+    mapping.write_n_quads(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let expected = "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#AnotherThing> <http://example.net/ns#aGraph> .\n";
+    assert_eq!(expected, actual);
+}
+
+#[rstest]
+#[serial]
+fn test_chunk_metadata() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:anyURI ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:relatesTo, ?myVar2)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    for (s, o) in [("B", "C"), ("A", "D")] {
+        let mut v1 = Series::from_iter([format!("http://example.net/ns#{}", s).as_str()]);
+        v1.rename("myVar1");
+        let mut v2 = Series::from_iter([format!("http://example.net/ns#{}", o).as_str()]);
+        v2.rename("myVar2");
+        let df = DataFrame::new(vec![v1, v2]).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                Default::default(),
+            )
+            .expect("");
+    }
+    assert_eq!(mapping.triplestore.count_triples(), 2);
+    let infos = mapping.triplestore.table_infos();
+    assert_eq!(infos.len(), 1);
+    let chunks = &infos.get(0).unwrap().chunks;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(
+        chunks.get(1).unwrap().min_subject.as_deref(),
+        Some("http://example.net/ns#A")
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_estimate_export_size() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel, xsd:long ?myNumber]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel),
+        ottr:Triple(?myThing, ex:number, ?myNumber),
+        ottr:Triple(?myThing, ex:related, ex:Other)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_estimate_export_size");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let my_thing = Series::new(
+            "myThing",
+            ["http://example.net/ns#one", "http://example.net/ns#two"],
+        );
+        let my_label = Series::new("myLabel", ["one", "two"]);
+        let my_number = Series::new("myNumber", [1i64, 22]);
+        let lang = Series::new("lang", [Some("en"), None]);
+        let df = DataFrame::new(vec![my_thing, my_label, my_number, lang]).unwrap();
+
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                ExpandOptions {
+                    language_tag_columns: Some(HashMap::from([(
+                        "myLabel".to_string(),
+                        "lang".to_string(),
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let estimate = mapping
+            .triplestore
+            .estimate_export_size(ExportFormat::NTriples)
+            .unwrap();
+        let mut buf = vec![];
+        mapping.write_n_triples(&mut buf).unwrap();
+        assert_eq!(estimate, buf.len());
+
+        let table_infos = mapping.triplestore.table_infos();
+        assert!(table_infos.iter().all(|info| info.height() == 2));
+
+        let parquet_estimate = mapping
+            .triplestore
+            .estimate_export_size(ExportFormat::NativeParquet)
+            .unwrap();
+        assert!(parquet_estimate > 0);
+        assert!(parquet_estimate < estimate);
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_declared_globally_unique_key() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:string ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:hasName, ?myVar2)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .declare_key(
+            "ex:ExampleTemplate",
+            TemplateKey {
+                unique_subsets: vec![vec!["myVar1".to_string()]],
+                globally_unique: true,
+            },
+        )
+        .unwrap();
+    for thing in ["OneThing", "AnotherThing"] {
+        let mut v1 = Series::from_iter([format!("http://example.net/ns#{}", thing).as_str()]);
+        v1.rename("myVar1");
+        let mut v2 = Series::from_iter([thing]);
+        v2.rename("myVar2");
+        let df = DataFrame::new(vec![v1, v2]).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                Default::default(),
+            )
+            .expect("");
+    }
+    let infos = mapping.triplestore.table_infos();
+    assert!(infos.iter().all(|x| x.unique));
+    assert_eq!(mapping.triplestore.count_triples(), 2);
+    assert!(mapping
+        .declare_key(
+            "ex:ExampleTemplate",
+            TemplateKey {
+                unique_subsets: vec![vec!["notAVariable".to_string()]],
+                globally_unique: false,
+            },
+        )
+        .is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_construct_to_writer() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut v1 = Series::from_iter([
+        "http://example.net/ns#OneThing",
+        "http://example.net/ns#AnotherThing",
+    ]);
+    v1.rename("myVar1");
+    let df = DataFrame::new(vec![v1]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    let mut buf = vec![];
+    mapping
+        .triplestore
+        .construct_to_writer(
+            "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ?o ex:relatedFrom ?s } WHERE { ?s ex:relatesTo ?o }",
+            &mut buf,
+        )
+        .unwrap();
+    let mut lines: Vec<&str> = std::str::from_utf8(&buf).unwrap().lines().collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "<http://example.net/ns#AnotherThing> <http://example.net/ns#relatedFrom> <http://example.net/ns#anObject> .",
+            "<http://example.net/ns#OneThing> <http://example.net/ns#relatedFrom> <http://example.net/ns#anObject> .",
+        ]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_deconstruct_update_delete_data() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_deconstruct_delete_data");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        let df = DataFrame::new(vec![Series::new(
+            "myVar1",
+            &["http://example.net/ns#OneThing", "http://example.net/ns#AnotherThing"],
+        )])
+        .unwrap();
+        mapping
+            .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+            .unwrap();
+        mapping
+            .triplestore
+            .deconstruct_update(
+                "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ex:anObject ex:relatesTo ex:OneThing } WHERE {}",
+            )
+            .unwrap();
+        let triples = mapping.export_oxrdf_triples().unwrap();
+        assert_eq!(
+            triples,
+            vec![Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#anObject"),
+                NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+                NamedNode::new_unchecked("http://example.net/ns#AnotherThing"),
+            )]
+        );
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_deconstruct_update_matches_language_tags() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("myThing", ["http://example.net/ns#a"; 3]),
+        Series::new("myLabel", ["x"; 3]),
+        Series::new("lang", [Some("en"), Some("nb"), None]),
+    ])
+    .unwrap();
+    let caching_folder = std::env::temp_dir().join("stottrs_deconstruct_language_tags");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df.clone(),
+                ExpandOptions {
+                    language_tag_columns: Some(HashMap::from([(
+                        "myLabel".to_string(),
+                        "lang".to_string(),
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let delete = |lang: &str| {
+            format!(
+                r#"PREFIX ex:<http://example.net/ns#>
+                CONSTRUCT {{ ?s ex:label ?l }} WHERE {{ ?s ex:label ?l FILTER(lang(?l) = "{}") }}"#,
+                lang
+            )
+        };
+        mapping.triplestore.deconstruct_update(&delete("en")).unwrap();
+        mapping.triplestore.deconstruct_update(&delete("")).unwrap();
+        let triples = mapping.export_oxrdf_triples().unwrap();
+        assert_eq!(
+            triples,
+            vec![Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#a"),
+                NamedNode::new_unchecked("http://example.net/ns#label"),
+                Literal::new_language_tagged_literal_unchecked("x", "nb"),
+            )]
+        );
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_construct_update_insert_data() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_construct_insert_data");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        let df = DataFrame::new(vec![Series::new("myVar1", &["http://example.net/ns#OneThing"])])
+            .unwrap();
+        mapping
+            .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+            .unwrap();
+        let insert = "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ex:anObject ex:relatesTo ex:AnotherThing } WHERE {}";
+        mapping.triplestore.construct_update(insert).unwrap();
+        let triples: HashSet<Triple> =
+            HashSet::from_iter(mapping.export_oxrdf_triples().unwrap().into_iter());
+        assert_eq!(triples.len(), 2);
+        assert!(triples.contains(&Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#anObject"),
+            NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+            NamedNode::new_unchecked("http://example.net/ns#AnotherThing"),
+        )));
+
+        //Removing the inserted triple gives back the original store
+        mapping.triplestore.deconstruct_update(insert).unwrap();
+        let triples = mapping.export_oxrdf_triples().unwrap();
+        assert_eq!(
+            triples,
+            vec![Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#anObject"),
+                NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+                NamedNode::new_unchecked("http://example.net/ns#OneThing"),
+            )]
+        );
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_deconstruct_update_delete_where() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:long ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:deprecatedRelation, ex:anObject),
+        ottr:Triple(?myVar1, ex:hasNumber, ?myVar2)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_deconstruct_delete_where");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        let df = DataFrame::new(vec![
+            Series::new(
+                "myVar1",
+                &["http://example.net/ns#OneThing", "http://example.net/ns#AnotherThing"],
+            ),
+            Series::new("myVar2", &[1i64, 2]),
+        ])
+        .unwrap();
+        mapping
+            .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+            .unwrap();
+        //All triples of one predicate, and the triples of another predicate matching a filter
+        mapping
+            .triplestore
+            .deconstruct_update(
+                r#"PREFIX ex:<http://example.net/ns#>
+                CONSTRUCT { ?s ex:deprecatedRelation ?o . ?s ex:hasNumber ?n }
+                WHERE { ?s ex:deprecatedRelation ?o . ?s ex:hasNumber ?n . FILTER(?n > 1) }"#,
+            )
+            .unwrap();
+        mapping
+            .triplestore
+            .deconstruct_update(
+                r#"PREFIX ex:<http://example.net/ns#>
+                CONSTRUCT { ?s ex:deprecatedRelation ?o } WHERE { ?s ex:deprecatedRelation ?o }"#,
+            )
+            .unwrap();
+        let triples = mapping.export_oxrdf_triples().unwrap();
+        assert_eq!(
+            triples,
+            vec![Triple::new(
+                NamedNode::new_unchecked("http://example.net/ns#OneThing"),
+                NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+                Literal::new_typed_literal("1", xsd::LONG),
+            )]
+        );
+        let query = "PREFIX ex:<http://example.net/ns#> SELECT ?s WHERE { ?s ex:deprecatedRelation ?o }";
+        if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+            assert_eq!(df.height(), 0);
+        } else {
+            panic!("Expected SELECT results");
+        }
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_upsert_template() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let redefined_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:isRelatedTo, ?myVar1)
+      } .
+    "#;
+    let incompatible_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?otherVar]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?otherVar)
+      } .
+    "#;
+    let template_from_str = |s: &str| {
+        let dataset = TemplateDataset::new(vec![document_from_str(s).unwrap()]).unwrap();
+        dataset
+            .get("http://example.net/ns#ExampleTemplate")
+            .unwrap()
+            .clone()
+    };
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .upsert_template(template_from_str(redefined_str))
+        .unwrap();
+    assert!(mapping
+        .upsert_template(template_from_str(incompatible_str))
+        .is_err());
+    let mut v1 = Series::from_iter(["http://example.net/ns#OneThing"]);
+    v1.rename("myVar1");
+    let df = DataFrame::new(vec![v1]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    let actual_triples_set: HashSet<Triple> = HashSet::from_iter(triples.into_iter());
+    let expected_triples_set = HashSet::from([Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#anObject")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#isRelatedTo"),
+        object: Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#OneThing")),
+    }]);
+    assert_eq!(expected_triples_set, actual_triples_set);
+}
+
+#[rstest]
+#[serial]
+fn test_write_turtle() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:string ?myVar2]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1),
+        ottr:Triple(ex:anObject, ex:hasName, ?myVar2)
+      } .
+    "#;
+
+    let mut v1 = Series::from_iter([
+        "http://example.net/ns#OneThing",
+        "http://example.net/ns#AnotherThing",
+    ]);
+    v1.rename("myVar1");
+    let mut v2 = Series::from_iter(["A \"quoted\" name", "A \"quoted\" name"]);
+    v2.rename("myVar2");
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    let mut buffer = vec![];
+    mapping.write_turtle(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    assert!(actual.contains("@prefix ex: <http://example.net/ns#> .\n"));
+    assert!(actual.contains(
+        "ex:anObject ex:hasName \"A \\\"quoted\\\" name\" ;\n    ex:relatesTo ex:AnotherThing ,\n        ex:OneThing .\n"
+    ));
+    assert!(actual.ends_with(" .\n"));
+}
+
+#[rstest]
+#[serial]
+fn test_write_turtle_merges_chunks_by_subject() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:anyURI ?other]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ?other) ,
+        ottr:Triple(?other, ex:relatedFrom, ?thing)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_write_turtle_merges_chunks");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        //Each expansion adds a chunk to each table
+        for (things, others) in [(["b", "a"], ["c", "b"]), (["a", "c"], ["d", "a"])] {
+            let iri = |x: &str| format!("http://example.net/ns#{}", x);
+            let df = DataFrame::new(vec![
+                Series::new("thing", things.map(iri).to_vec()),
+                Series::new("other", others.map(iri).to_vec()),
+            ])
+            .unwrap();
+            mapping
+                .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+                .unwrap();
+        }
+        let mut buffer = vec![];
+        mapping.write_turtle(&mut buffer).unwrap();
+        let actual = String::from_utf8(buffer).unwrap();
+        let body = actual.split("\n\n").nth(1).unwrap();
+        assert_eq!(
+            body,
+            "ex:a ex:relatedFrom ex:c ;\n    ex:relatesTo ex:b ,\n        ex:d .\n\
+             ex:b ex:relatedFrom ex:a ;\n    ex:relatesTo ex:c .\n\
+             ex:c ex:relatedFrom ex:b ;\n    ex:relatesTo ex:a .\n\
+             ex:d ex:relatedFrom ex:a .\n"
+        );
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_write_turtle_groups_subjects_and_abbreviates_literals() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix rdf:<http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+    @prefix xsd:<http://www.w3.org/2001/XMLSchema#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:integer ?myCount, xsd:boolean ?myActive, xsd:double ?myWeight]
+      :: {
+        ottr:Triple(?myThing, ex:weight, ?myWeight),
+        ottr:Triple(?myThing, ex:count, ?myCount),
+        ottr:Triple(?myThing, rdf:type, ex:Pump),
+        ottr:Triple(?myThing, ex:active, ?myActive)
+      } .
+    "#;
+
+    let my_thing = Series::new(
+        "myThing",
+        ["http://example.net/ns#p2", "http://example.net/ns#p1"],
+    );
+    let my_count = Series::new("myCount", [-4i64, 3i64]);
+    let my_active = Series::new("myActive", [false, true]);
+    let my_weight = Series::new("myWeight", [1.25f64, 2.5f64]);
+    let df = DataFrame::new(vec![my_thing, my_count, my_active, my_weight]).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let mut buffer = vec![];
+    mapping.write_turtle(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let body = actual.split("\n\n").nth(1).unwrap();
+    assert_eq!(
+        body,
+        "ex:p1 a ex:Pump ;\n    ex:active true ;\n    ex:count 3 ;\n    ex:weight \"2.5\"^^xsd:double .\n\
+         ex:p2 a ex:Pump ;\n    ex:active false ;\n    ex:count -4 ;\n    ex:weight \"1.25\"^^xsd:double .\n"
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_write_turtle_writes_type_first() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix rdf:<http://www.w3.org/1999/02/22-rdf-syntax-ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing]
+      :: {
+        ottr:Triple(?myThing, <http://other.example.org/label>, "pump"),
+        ottr:Triple(?myThing, rdf:type, ex:Pump)
+      } .
+    "#;
+    let df = DataFrame::new(vec![Series::new("myThing", ["http://example.net/ns#p1"])]).unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let mut buffer = vec![];
+    mapping.write_turtle(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let body = actual.split("\n\n").nth(1).unwrap();
+    assert_eq!(
+        body,
+        "ex:p1 a ex:Pump ;\n    <http://other.example.org/label> \"pump\" .\n"
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_write_turtle_rejects_named_graphs() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let df = DataFrame::new(vec![Series::new(
+        "myVar1",
+        ["http://example.net/ns#OneThing"],
+    )])
+    .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                graph: Some("http://example.net/ns#aGraph".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut buffer = vec![];
+    let error = mapping.write_turtle(&mut buffer).err().unwrap();
+    assert!(error.to_string().contains("named graphs"), "{}", error);
+}
+
+#[rstest]
+#[serial]
+fn test_triple_exports_reject_named_graphs() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let df = DataFrame::new(vec![Series::new(
+        "myVar1",
+        ["http://example.net/ns#OneThing"],
+    )])
+    .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                graph: Some("http://example.net/ns#aGraph".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut buffer = vec![];
+    let error = mapping.write_n_triples(&mut buffer).err().unwrap();
+    assert!(error.to_string().contains("named graphs"), "{}", error);
+    assert!(buffer.is_empty());
+    assert!(mapping.n_triples_lines(1024).is_err());
+    let error = mapping.export_oxrdf_triples().err().unwrap();
+    assert!(error.to_string().contains("named graphs"), "{}", error);
+}
+
+#[rstest]
+#[serial]
+fn test_deconstruct_update_in_named_graph() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+    let caching_folder = std::env::temp_dir().join("stottrs_deconstruct_named_graph");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        let df = DataFrame::new(vec![Series::new(
+            "myVar1",
+            &["http://example.net/ns#OneThing", "http://example.net/ns#AnotherThing"],
+        )])
+        .unwrap();
+        mapping
+            .expand("http://example.net/ns#ExampleTemplate", df.clone(), Default::default())
+            .unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                ExpandOptions {
+                    graph: Some("http://example.net/ns#aGraph".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let delete = "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ex:anObject ex:relatesTo ex:OneThing } WHERE {}";
+        mapping
+            .triplestore
+            .deconstruct_update_in_graph(delete, Some("http://example.net/ns#aGraph"))
+            .unwrap();
+        let mut buffer = vec![];
+        mapping.write_n_quads(&mut buffer).unwrap();
+        let mut quads: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        quads.sort();
+        assert_eq!(
+            quads,
+            vec![
+                "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#AnotherThing> .",
+                "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#AnotherThing> <http://example.net/ns#aGraph> .",
+                "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#OneThing> .",
+            ]
+        );
+
+        //Removing the last triple of the graph removes the graph
+        let delete = "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ex:anObject ex:relatesTo ex:AnotherThing } WHERE {}";
+        mapping
+            .triplestore
+            .deconstruct_update_in_graph(delete, Some("http://example.net/ns#aGraph"))
+            .unwrap();
+        assert!(mapping.triplestore.named_graphs().is_empty());
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_fresh_blank_nodes() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:string ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:hasAddress, _:address),
+        ottr:Triple(_:address, ex:hasStreet, ?myVar2)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut v1 = Series::from_iter([
+        "http://example.net/ns#OneThing",
+        "http://example.net/ns#AnotherThing",
+    ]);
+    v1.rename("myVar1");
+    let mut v2 = Series::from_iter(["Main Street", "Side Street"]);
+    v2.rename("myVar2");
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    let mut addresses = HashSet::new();
+    for t in &triples {
+        if t.predicate.as_str() == "http://example.net/ns#hasAddress" {
+            assert!(matches!(t.subject, Subject::NamedNode(_)));
+            if let Term::BlankNode(bn) = &t.object {
+                addresses.insert(bn.clone());
+            } else {
+                panic!("Expected a blank node");
+            }
+        }
+    }
+    assert_eq!(addresses.len(), 2);
+    for t in &triples {
+        if t.predicate.as_str() == "http://example.net/ns#hasStreet" {
+            if let Subject::BlankNode(bn) = &t.subject {
+                assert!(addresses.contains(bn));
+            } else {
+                panic!("Expected a blank node");
+            }
+        }
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            "PREFIX ex:<http://example.net/ns#> SELECT ?s ?street WHERE { ?s ex:hasAddress _:a . _:a ex:hasStreet ?street }",
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 2);
+        assert_eq!(df.get_column_names(), vec!["s", "street"]);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert_eq!(nt.lines().filter(|l| l.starts_with("_:address_")).count(), 2);
+}
+
+#[rstest]
+#[serial]
+fn test_sampled_expansion() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:long ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:hasNumber, ?myVar2)
+      } .
+    "#;
+
+    let sampled_triples = |size: SampleSize| {
+        let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+        let iris: Vec<String> = (0..1000)
+            .map(|i| format!("http://example.net/ns#Thing{}", i))
+            .collect();
+        let mut v1 = Series::from_iter(iris.iter().map(|x| x.as_str()));
+        v1.rename("myVar1");
+        let mut v2 = Series::from_iter(0..1000i64);
+        v2.rename("myVar2");
+        let df = DataFrame::new(vec![v1, v2]).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df,
+                ExpandOptions {
+                    sample: Some(Sample { size, seed: 42 }),
+                    ..Default::default()
+                },
+            )
+            .expect("");
+        let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+        triples
+    };
+    let rows = sampled_triples(SampleSize::Rows(10));
+    assert_eq!(rows.len(), 10);
+    assert_eq!(rows, sampled_triples(SampleSize::Rows(10)));
+    assert_eq!(sampled_triples(SampleSize::Rows(2000)).len(), 1000);
+
+    let fraction = sampled_triples(SampleSize::Fraction(0.1));
+    assert!(fraction.len() > 50 && fraction.len() < 150);
+    assert_eq!(fraction, sampled_triples(SampleSize::Fraction(0.1)));
+}
+
+#[rstest]
+#[serial]
+fn test_default_values() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Categorized [xsd:anyURI ?thing, ? xsd:anyURI ?category = ex:DefaultCategory]
+      :: {
+        ottr:Triple(?thing, ex:hasCategory, ?category)
+      } .
+
+    ex:Uncategorized [xsd:anyURI ?thing]
+      :: {
+        ex:Categorized(?thing, none)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    mapping
+        .expand(
+            "http://example.net/ns#Categorized",
+            DataFrame::new(vec![thing]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let mut thing = Series::from_iter(["http://example.net/ns#B", "http://example.net/ns#C"]);
+    thing.rename("thing");
+    let category = Series::new(
+        "category",
+        [Some("http://example.net/ns#SpecialCategory"), None],
+    );
+    mapping
+        .expand(
+            "http://example.net/ns#Categorized",
+            DataFrame::new(vec![thing, category]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let mut thing = Series::from_iter(["http://example.net/ns#D"]);
+    thing.rename("thing");
+    mapping
+        .expand(
+            "http://example.net/ns#Uncategorized",
+            DataFrame::new(vec![thing]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, o: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasCategory"),
+        object: Term::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", o))),
+    };
+    let expected = HashSet::from([
+        triple("A", "DefaultCategory"),
+        triple("B", "SpecialCategory"),
+        triple("C", "DefaultCategory"),
+        triple("D", "DefaultCategory"),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_triple_count_deltas() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, ? xsd:string ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:hasName, ?myVar2)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let v1 = Series::new(
+        "myVar1",
+        [
+            "http://example.net/ns#A",
+            "http://example.net/ns#B",
+            "http://example.net/ns#B",
+            "http://example.net/ns#C",
+            "http://example.net/ns#D",
+        ],
+    );
+    let v2 = Series::new("myVar2", [Some("a"), Some("b"), Some("b"), None, Some("d")]);
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .expect("");
+    assert_eq!(report.triple_counts.len(), 1);
+    let counts = &report.triple_counts[0];
+    assert_eq!(counts.predicate, "http://example.net/ns#hasName");
+    assert_eq!(counts.graph, None);
+    assert_eq!(counts.input_rows, 5);
+    assert_eq!(counts.null_rows, 1);
+    assert_eq!(counts.duplicate_rows, 1);
+    assert_eq!(counts.added_rows, 3);
+}
+
+#[rstest]
+#[serial]
+fn test_optional_and_non_blank_parameters() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Named [xsd:anyURI ?thing, xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:hasName, ?name),
+        ottr:Triple(?thing, ex:type, ex:NamedThing)
+      } .
+
+    ex:Thing [xsd:anyURI ?thing, ? xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:type, ex:Thing),
+        ex:Named(?thing, ?name)
+      } .
+
+    ex:NonBlank [! xsd:anyURI ?thing]
+      :: {
+        ottr:Triple(?thing, ex:type, ex:NonBlankThing)
+      } .
+
+    ex:WithBlank [xsd:anyURI ?thing]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, _:b),
+        ex:NonBlank(_:b)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let thing = Series::new(
+        "thing",
+        ["http://example.net/ns#A", "http://example.net/ns#B"],
+    );
+    let name = Series::new("name", [Some("a"), None]);
+    mapping
+        .expand(
+            "http://example.net/ns#Thing",
+            DataFrame::new(vec![thing, name]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let ex = |s: &str| NamedNode::new_unchecked(format!("http://example.net/ns#{}", s));
+    let triple = |s: &str, p: &str, o: Term| Triple {
+        subject: Subject::NamedNode(ex(s)),
+        predicate: ex(p),
+        object: o,
+    };
+    let expected = HashSet::from([
+        triple("A", "type", Term::NamedNode(ex("Thing"))),
+        triple("B", "type", Term::NamedNode(ex("Thing"))),
+        triple("A", "type", Term::NamedNode(ex("NamedThing"))),
+        triple("A", "hasName", Term::Literal(Literal::new_simple_literal("a"))),
+    ]);
+    assert_eq!(triples, expected);
+
+    let thing = Series::new("thing", ["_:x"]);
+    assert!(mapping
+        .expand(
+            "http://example.net/ns#NonBlank",
+            DataFrame::new(vec![thing]).unwrap(),
+            Default::default(),
+        )
+        .is_err());
+
+    let thing = Series::new("thing", ["http://example.net/ns#C"]);
+    assert!(mapping
+        .expand(
+            "http://example.net/ns#WithBlank",
+            DataFrame::new(vec![thing]).unwrap(),
+            Default::default(),
+        )
+        .is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_expand_prefixed_names() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1, xsd:anyURI ?myVar2]
+      :: {
+        ottr:Triple(?myVar1, ex:relatesTo, ?myVar2)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let v1 = Series::new("myVar1", ["ex:thing1", "http://example.net/ns#thing2"]);
+    let v2 = Series::new("myVar2", ["ex:thing3", "urn:uuid:1234"]);
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                expand_prefixed_names: true,
+                ..Default::default()
+            },
+        )
+        .expect("");
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, o: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(s)),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#relatesTo"),
+        object: Term::NamedNode(NamedNode::new_unchecked(o)),
+    };
+    let expected = HashSet::from([
+        triple("http://example.net/ns#thing1", "http://example.net/ns#thing3"),
+        triple("http://example.net/ns#thing2", "urn:uuid:1234"),
+    ]);
+    assert_eq!(triples, expected);
+
+    let v1 = Series::new("myVar1", ["unknown:thing1"]);
+    let v2 = Series::new("myVar2", ["ex:thing3"]);
+    let df = DataFrame::new(vec![v1, v2]).unwrap();
+    assert!(mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                expand_prefixed_names: true,
+                ..Default::default()
+            },
+        )
+        .is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_base_resolves_relative_iris() {
+    let t_str = r#"
+    @base <http://example.net/ns/> .
+
+    <ExampleTemplate> [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(?myVar1, <relatesTo>, <../other#thing>)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    assert_eq!(
+        mapping.base_iri(),
+        Some(&NamedNode::new_unchecked("http://example.net/ns/"))
+    );
+    let v1 = Series::new("myVar1", ["thing1", "http://example.org/thing2"]);
+    let df = DataFrame::new(vec![v1]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns/ExampleTemplate",
+            df,
+            ExpandOptions {
+                expand_prefixed_names: true,
+                ..Default::default()
+            },
+        )
+        .expect("");
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(s)),
+        predicate: NamedNode::new_unchecked("http://example.net/ns/relatesTo"),
+        object: Term::NamedNode(NamedNode::new_unchecked("http://example.net/other#thing")),
+    };
+    let expected = HashSet::from([
+        triple("http://example.net/ns/thing1"),
+        triple("http://example.org/thing2"),
+    ]);
+    assert_eq!(triples, expected);
+
+    let no_base = r#"
+    <ExampleTemplate> [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(?myVar1, <relatesTo>, <thing>)
+      } .
+    "#;
+    assert!(Mapping::from_str(&no_base, None).is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_annotation_expansion() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Provenance [xsd:anyURI ?template, xsd:string ?author]
+      :: {
+        ottr:Triple(?template, ex:author, ?author)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1] @@ex:Provenance(ex:ExampleTemplate, "Jane")
+      :: {
+        ottr:Triple(ex:anObject, ex:relatesTo, ?myVar1)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let v1 = Series::new("myVar1", ["http://example.net/ns#OneThing"]);
+    let df = DataFrame::new(vec![v1]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                annotation_graph: Some("http://example.net/ns#provenance".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("");
+
+    let mut buffer = vec![];
+    mapping.write_n_quads(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let mut lines: Vec<&str> = actual.lines().collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "<http://example.net/ns#ExampleTemplate> <http://example.net/ns#author> \"Jane\" <http://example.net/ns#provenance> .",
+            "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#OneThing> .",
+        ]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_derived_value_arguments() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Inner [xsd:anyURI ?x, xsd:long ?y]
+      :: {
+        ottr:Triple(?x, ex:value, ?y)
+      } .
+
+    ex:Outer [xsd:anyURI ?a, xsd:long ?b]
+      :: {
+        ex:Inner(?a, ?b * 2 + 1),
+        ottr:Triple(?a, ex:label, "n" || ?b)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut a = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    a.rename("a");
+    let mut b = Series::from_iter([1i64, 2]);
+    b.rename("b");
+    mapping
+        .expand(
+            "http://example.net/ns#Outer",
+            DataFrame::new(vec![a, b]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, p: &str, o: Literal| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked(format!("http://example.net/ns#{}", p)),
+        object: Term::Literal(o),
+    };
+    let long = NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long");
+    let expected = HashSet::from([
+        triple("A", "value", Literal::new_typed_literal("3", long.clone())),
+        triple("B", "value", Literal::new_typed_literal("5", long)),
+        triple("A", "label", Literal::new_simple_literal("n1")),
+        triple("B", "label", Literal::new_simple_literal("n2")),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_partition_by_predicates() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myVar1]
+      :: {
+        ottr:Triple(?myVar1, ex:first, ex:anObject),
+        ottr:Triple(?myVar1, ex:second, ex:anObject),
+        ottr:Triple(?myVar1, ex:third, ex:anObject)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut v1 = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    v1.rename("myVar1");
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            DataFrame::new(vec![v1]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let triplestore = std::mem::replace(&mut mapping.triplestore, Triplestore::new(None));
+    let predicate = |p: &str| NamedNode::new_unchecked(format!("http://example.net/ns#{}", p));
+    let mut partitions = triplestore.partition_by_predicates(vec![
+        vec![predicate("first")],
+        vec![predicate("second"), predicate("first")],
+    ]);
+    assert_eq!(partitions.len(), 3);
+    let mut partition_predicates = vec![];
+    for p in &mut partitions {
+        let triples = p.export_oxrdf_triples().unwrap();
+        assert_eq!(triples.len(), 2);
+        let predicates: HashSet<NamedNode> = triples.into_iter().map(|t| t.predicate).collect();
+        partition_predicates.push(predicates);
+    }
+    assert_eq!(
+        partition_predicates,
+        vec![
+            HashSet::from([predicate("first")]),
+            HashSet::from([predicate("second")]),
+            HashSet::from([predicate("third")]),
+        ]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_expand_from_csv(testdata_path: PathBuf) {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let mut csv_file_path = testdata_path.clone();
+    csv_file_path.push("actual_csv_case.csv");
+    let mut csv_file = File::create(csv_file_path.as_path()).expect("could not open file");
+    writeln!(csv_file, "thing,number").unwrap();
+    for i in 0..1000 {
+        writeln!(csv_file, "http://example.net/ns#thing{},{}", i, i).unwrap();
+    }
+    drop(csv_file);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let report = mapping
+        .expand_from_csv(
+            "http://example.net/ns#ExampleTemplate",
+            csv_file_path.as_path(),
+            Default::default(),
+        )
+        .expect("");
+    assert_eq!(report.triple_counts.len(), 1);
+    assert_eq!(report.triple_counts[0].added_rows, 1000);
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    assert_eq!(triples.len(), 1000);
+    assert!(triples.contains(&Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#thing42")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+        object: Term::Literal(Literal::new_typed_literal(
+            "42",
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+        )),
+    }));
+}
+
+#[rstest]
+#[serial]
+fn test_expand_from_parquet(testdata_path: PathBuf) {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let mut parquet_folder_path = testdata_path.clone();
+    parquet_folder_path.push("actual_parquet_case");
+    std::fs::create_dir_all(parquet_folder_path.as_path()).unwrap();
+    for part in 0..2 {
+        let things: Vec<String> = (0..500)
+            .map(|i| format!("http://example.net/ns#thing{}", part * 500 + i))
+            .collect();
+        let mut thing = Series::from_iter(things.iter().map(|x| x.as_str()));
+        thing.rename("thing");
+        let mut number = Series::from_iter((0..500).map(|i| (part * 500 + i) as i64));
+        number.rename("number");
+        let mut df = DataFrame::new(vec![thing, number]).unwrap();
+        let mut file_path = parquet_folder_path.clone();
+        file_path.push(format!("part_{}.parquet", part));
+        let file = File::create(file_path.as_path()).expect("could not open file");
+        ParquetWriter::new(file).finish(&mut df).unwrap();
+    }
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut glob_path = parquet_folder_path.clone();
+    glob_path.push("*.parquet");
+    let report = mapping
+        .expand_from_parquet(
+            "http://example.net/ns#ExampleTemplate",
+            glob_path.to_str().unwrap(),
+            Default::default(),
+        )
+        .expect("");
+    assert_eq!(report.triple_counts[0].added_rows, 1000);
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    assert_eq!(triples.len(), 1000);
+    assert!(triples.contains(&Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#thing742")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+        object: Term::Literal(Literal::new_typed_literal(
+            "742",
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+        )),
+    }));
+}
+
+#[rstest]
+#[serial]
+fn test_datatypes_harmonized_when_added() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([1i32]);
+    number.rename("number");
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            DataFrame::new(vec![thing, number]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+    let mut thing = Series::from_iter(["http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([2i64]);
+    number.rename("number");
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            DataFrame::new(vec![thing, number]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    mapping.triplestore.deduplicate().unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, n: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+        object: Term::Literal(Literal::new_typed_literal(
+            n,
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#long"),
+        )),
+    };
+    let expected = HashSet::from([triple("A", "1"), triple("B", "2")]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_datatypes_widened_without_canonical_datatype() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:short ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([1i32]);
+    number.rename("number");
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            DataFrame::new(vec![thing, number]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+    let mut thing = Series::from_iter(["http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([2i64]);
+    number.rename("number");
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            DataFrame::new(vec![thing, number]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    mapping.triplestore.deduplicate().unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, n: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasNumber"),
+        object: Term::Literal(Literal::new_typed_literal(
+            n,
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#short"),
+        )),
+    };
+    let expected = HashSet::from([triple("A", "1"), triple("B", "2")]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_ipc_roundtrip(testdata_path: PathBuf) {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let mut ipc_folder_path = testdata_path.clone();
+    ipc_folder_path.push("actual_ipc_case");
+    std::fs::create_dir_all(ipc_folder_path.as_path()).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut number = Series::from_iter([1i64, 2]);
+    number.rename("number");
+    let mut df = DataFrame::new(vec![thing, number]).unwrap();
+    let mut input_file_path = ipc_folder_path.clone();
+    input_file_path.push("input.ipc");
+    let file = File::create(input_file_path.as_path()).expect("could not open file");
+    IpcWriter::new(file).finish(&mut df).unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand_from_ipc(
+            "http://example.net/ns#ExampleTemplate",
+            input_file_path.to_str().unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let mut output_folder_path = ipc_folder_path.clone();
+    output_folder_path.push("output");
+    std::fs::create_dir_all(output_folder_path.as_path()).unwrap();
+    mapping
+        .write_ipc(output_folder_path.to_str().unwrap())
+        .unwrap();
+    let mut output_file_path = output_folder_path.clone();
+    output_file_path.push("httpexamplenetnshasNumber_httpwwww3org2001XMLSchemalong_part_0.ipc");
+    let output_file = File::open(output_file_path.as_path()).expect("Could not open file");
+    let output_df = IpcReader::new(output_file)
+        .finish()
+        .unwrap()
+        .sort(["subject"], vec![false])
+        .unwrap();
+    let mut expected_subject = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    expected_subject.rename("subject");
+    let mut expected_object = Series::from_iter([1i64, 2]);
+    expected_object.rename("object");
+    let expected_df = DataFrame::new(vec![expected_subject, expected_object]).unwrap();
+    assert_eq!(output_df, expected_df);
+}
+
+#[rstest]
+#[serial]
+fn test_constant_list_of_predicates() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix skos:<http://www.w3.org/2004/02/skos/core#>.
+    @prefix rdfs:<http://www.w3.org/2000/01/rdf-schema#>.
+
+    ex:Labelled [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        cross | ottr:Triple(?thing, ++(skos:prefLabel, rdfs:label), ?label)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut label = Series::from_iter(["A thing", "B thing"]);
+    label.rename("label");
+    mapping
+        .expand(
+            "http://example.net/ns#Labelled",
+            DataFrame::new(vec![thing, label]).unwrap(),
+            Default::default(),
+        )
+        .expect("");
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, p: &str, o: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked(p),
+        object: Term::Literal(Literal::new_simple_literal(o)),
+    };
+    let pref_label = "http://www.w3.org/2004/02/skos/core#prefLabel";
+    let label = "http://www.w3.org/2000/01/rdf-schema#label";
+    let expected = HashSet::from([
+        triple("A", pref_label, "A thing"),
+        triple("A", label, "A thing"),
+        triple("B", pref_label, "B thing"),
+        triple("B", label, "B thing"),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_expand_wide_timeseries() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Temperature [xsd:anyURI ?id, ?timestamp, ?value]
+      :: {
+        ottr:Triple(?id, ex:hasTemperature, ?value)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut sensor = Series::from_iter(["http://example.net/ns#S1", "http://example.net/ns#S2"]);
+    sensor.rename("sensor");
+    let mut ts = Series::from_iter([1i64, 2]);
+    ts.rename("ts");
+    let temperature = Series::new("temperature", [Some(20.5f64), None]);
+    let pressure = Series::new("pressure", [Some(1i32), Some(2)]);
+    let df = DataFrame::new(vec![sensor, ts, temperature, pressure]).unwrap();
+    let wide_to_long = WideToLong {
+        id_column: "sensor".to_string(),
+        timestamp_column: "ts".to_string(),
+        templates: HashMap::from([(
+            "temperature".to_string(),
+            "http://example.net/ns#Temperature".to_string(),
+        )]),
+        predicate_prefix: Some("http://example.net/ns#".to_string()),
+    };
+    let report = mapping
+        .expand_wide(df, &wide_to_long, Default::default())
+        .expect("");
+    let added = |p: &str| {
+        report
+            .triple_counts
+            .iter()
+            .find(|c| c.predicate == format!("http://example.net/ns#{}", p))
+            .unwrap()
+            .added_rows
+    };
+    assert_eq!(added("hasTemperature"), 1);
+    assert_eq!(added("pressure"), 2);
+    assert_eq!(added("timestamp"), 2);
+    assert_eq!(added("value"), 2);
+
+    let triples = mapping.export_oxrdf_triples().unwrap();
+    assert!(triples.contains(&Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#S1")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasTemperature"),
+        object: Term::Literal(Literal::new_typed_literal(
+            "20.5",
+            NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#double"),
+        )),
+    }));
+    let values: HashSet<Term> = triples
+        .into_iter()
+        .filter(|t| t.predicate.as_str() == "http://example.net/ns#value")
+        .map(|t| t.object)
+        .collect();
+    let int = NamedNode::new_unchecked("http://www.w3.org/2001/XMLSchema#int");
+    assert_eq!(
+        values,
+        HashSet::from([
+            Term::Literal(Literal::new_typed_literal("1", int.clone())),
+            Term::Literal(Literal::new_typed_literal("2", int)),
+        ])
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_sanitize_strings() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let df = || {
+        let mut thing = Series::from_iter([
+            "http://example.net/ns#A",
+            "http://example.net/ns#B\u{7}",
+            "http://example.net/ns#C",
+        ]);
+        thing.rename("thing");
+        let mut label = Series::from_iter(["First\nline", "Bell\u{7}", "Null\u{0}"]);
+        label.rename("label");
+        DataFrame::new(vec![thing, label]).unwrap()
+    };
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let error = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df(),
+            ExpandOptions {
+                sanitize_strings: Some(SanitizePolicy::Error),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert!(matches!(error, MappingError::MalformedStrings(c, rows) if c == "label" && rows == vec![1, 2]));
+
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df(),
+            ExpandOptions {
+                sanitize_strings: Some(SanitizePolicy::Clean),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        report.sanitized_columns,
+        vec![
+            SanitizedColumn {
+                column: "label".to_string(),
+                rows: vec![1, 2],
+            },
+            SanitizedColumn {
+                column: "thing".to_string(),
+                rows: vec![1],
+            },
+        ]
+    );
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |s: &str, o: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked(format!("http://example.net/ns#{}", s))),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#label"),
+        object: Term::Literal(Literal::new_simple_literal(o)),
+    };
+    let expected = HashSet::from([
+        triple("A", "First\nline"),
+        triple("B", "Bell"),
+        triple("C", "Null"),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_query_evaluation_error_is_returned() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut label = Series::from_iter(["not a date"]);
+    label.rename("label");
+    let df = DataFrame::new(vec![thing, label]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let error = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE {?thing ex:label ?label FILTER(YEAR(?label) > 2000)}
+    "#,
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(error, SparqlError::EvaluationError(..)));
+}
+
+#[rstest]
+#[serial]
+fn test_union_with_missing_predicate_is_compatible() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut label = Series::from_iter(["a", "b"]);
+    label.rename("label");
+    let df = DataFrame::new(vec![thing, label]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?label WHERE {{?thing ex:label ?label} UNION {?thing ex:missing ?label}}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 2);
+    } else {
+        panic!("Expected select result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_custom_operator_with_solution_mappings() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label) ,
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut label = Series::from_iter(["a", "b"]);
+    label.rename("label");
+    let mut number = Series::from_iter([1i64, 2]);
+    number.rename("number");
+    let df = DataFrame::new(vec![thing, label, number]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    let pattern = |q: &str| {
+        if let Query::Select { pattern, .. } = Query::parse(q, None).unwrap() {
+            pattern
+        } else {
+            panic!("Expected select query")
+        }
+    };
+    let context = Context::new();
+    let labels = mapping
+        .triplestore
+        .evaluate_graph_pattern(
+            &pattern("PREFIX ex:<http://example.net/ns#> SELECT ?thing ?label WHERE {?thing ex:label ?label}"),
+            None,
+            &context,
+        )
+        .unwrap();
+    let numbers = mapping
+        .triplestore
+        .evaluate_graph_pattern(
+            &pattern("PREFIX ex:<http://example.net/ns#> SELECT ?thing ?number WHERE {?thing ex:hasNumber ?number}"),
+            None,
+            &context,
+        )
+        .unwrap();
+    let df = labels
+        .join(numbers, &context)
+        .unwrap()
+        .filter(col("number").gt(lit(1)))
+        .project(&["label".to_string()])
+        .collect(&context)
+        .unwrap();
+    assert_eq!(
+        df.column("label")
+            .unwrap()
+            .cast(&DataType::Utf8)
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap(),
+        Some("b")
+    );
+    assert_eq!(df.height(), 1);
+
+    let extended = SolutionMappings::from_df(df, HashMap::new());
+    assert!(extended.columns.contains("label"));
+}
+
+struct StopAfterStoring {
+    stored: AtomicUsize,
+    max_stored: usize,
+}
+
+impl BackpressureHook for StopAfterStoring {
+    fn before_stage(&self, stage: &ExpansionStage, _rows: usize) -> bool {
+        if stage == &ExpansionStage::StoreTriples {
+            self.stored.fetch_add(1, Ordering::SeqCst) < self.max_stored
+        } else {
+            true
+        }
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_row_limit_and_backpressure(testdata_path: PathBuf) {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+    let df = || {
+        let mut thing = Series::from_iter(
+            (0..10).map(|i| format!("http://example.net/ns#thing{}", i)),
+        );
+        thing.rename("thing");
+        let mut number = Series::from_iter(0..10i64);
+        number.rename("number");
+        DataFrame::new(vec![thing, number]).unwrap()
+    };
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df(),
+            ExpandOptions {
+                row_limit: Some(4),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(report.rows_expanded, 4);
+    assert_eq!(report.stopped, Some(StopReason::RowLimit));
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 4);
+
+    let mut csv_file_path = testdata_path.clone();
+    csv_file_path.push("actual_backpressure_case.csv");
+    let mut csv_file = File::create(csv_file_path.as_path()).expect("could not open file");
+    writeln!(csv_file, "thing,number").unwrap();
+    for i in 0..150_000 {
+        writeln!(csv_file, "http://example.net/ns#thing{},{}", i, i).unwrap();
+    }
+    drop(csv_file);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let report = mapping
+        .expand_from_csv(
+            "http://example.net/ns#ExampleTemplate",
+            csv_file_path.as_path(),
+            ExpandOptions {
+                backpressure: Some(Arc::new(StopAfterStoring {
+                    stored: AtomicUsize::new(0),
+                    max_stored: 1,
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        report.stopped,
+        Some(StopReason::Backpressure(ExpansionStage::StoreTriples))
+    );
+    //Only the triples of the first batch are stored
+    assert!(report.rows_expanded > 0 && report.rows_expanded < 150_000);
+    assert_eq!(
+        mapping.export_oxrdf_triples().unwrap().len(),
+        report.rows_expanded
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_chunk_policy() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+    let df = || {
+        let mut thing = Series::from_iter(
+            (0..10).map(|i| format!("http://example.net/ns#thing{}", i)),
+        );
+        thing.rename("thing");
+        let mut number = Series::from_iter(0..10i64);
+        number.rename("number");
+        DataFrame::new(vec![thing, number]).unwrap()
+    };
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df(),
+            ExpandOptions {
+                chunk_policy: Some(ChunkPolicy::Rows(3)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(report.rows_expanded, 10);
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 10);
+
+    //Each chunk is stored separately, so the expansion stops after the second chunk of three rows
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df(),
+            ExpandOptions {
+                chunk_policy: Some(ChunkPolicy::Rows(3)),
+                backpressure: Some(Arc::new(StopAfterStoring {
+                    stored: AtomicUsize::new(0),
+                    max_stored: 2,
+                })),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(report.rows_expanded, 6);
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 6);
+}
+
+#[rstest]
+#[serial]
+fn test_query_predicate_with_several_object_types() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:IRITemplate [xsd:anyURI ?thing, xsd:anyURI ?value]
+      :: {
+        ottr:Triple(?thing, ex:value, ?value)
+      } .
+
+    ex:StringTemplate [xsd:anyURI ?thing, xsd:string ?value]
+      :: {
+        ottr:Triple(?thing, ex:value, ?value)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut value = Series::from_iter(["http://example.net/ns#B"]);
+    value.rename("value");
+    mapping
+        .expand(
+            "http://example.net/ns#IRITemplate",
+            DataFrame::new(vec![thing.clone(), value]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    let mut value = Series::from_iter(["b"]);
+    value.rename("value");
+    mapping
+        .expand(
+            "http://example.net/ns#StringTemplate",
+            DataFrame::new(vec![thing, value]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?value WHERE {?thing ex:value ?value}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let df = df
+            .sort(["value|rdf_node_type"], vec![false])
+            .unwrap();
+        let mut thing = Series::from_iter([
+            "http://example.net/ns#A",
+            "http://example.net/ns#A",
+        ]);
+        thing.rename("thing");
+        let mut value = Series::from_iter(["http://example.net/ns#B", "b"]);
+        value.rename("value");
+        let mut tag = Series::from_iter(["IRI", "http://www.w3.org/2001/XMLSchema#string"]);
+        tag.rename("value|rdf_node_type");
+        let expected_df = DataFrame::new(vec![thing, value, tag]).unwrap();
+        assert_eq!(df, expected_df);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE {?thing ex:value "b"}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 1);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    CONSTRUCT {?thing ex:copy ?value} WHERE {?thing ex:value ?value}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Construct(dfs) = res {
+        let mut dts: Vec<RDFNodeType> = dfs.into_iter().map(|(_, dt)| dt).collect();
+        dts.sort_by_key(|x| format!("{:?}", x));
+        assert_eq!(
+            dts,
+            vec![
+                RDFNodeType::IRI,
+                RDFNodeType::Literal(xsd::STRING.into_owned())
+            ]
+        );
+    } else {
+        panic!("Expected construct result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_find_mixed_object_types() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:StringTemplate [xsd:anyURI ?thing, xsd:string ?value]
+      :: {
+        ottr:Triple(?thing, ex:value, ?value)
+      } .
+
+    ex:IntTemplate [xsd:anyURI ?thing, xsd:int ?value]
+      :: {
+        ottr:Triple(?thing, ex:value, ?value)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut value = Series::from_iter(["42", "43"]);
+    value.rename("value");
+    mapping
+        .expand(
+            "http://example.net/ns#StringTemplate",
+            DataFrame::new(vec![thing, value]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#C"]);
+    thing.rename("thing");
+    let mut value = Series::from_iter([42i32, 44]);
+    value.rename("value");
+    mapping
+        .expand(
+            "http://example.net/ns#IntTemplate",
+            DataFrame::new(vec![thing, value]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    let df = mapping.triplestore.find_mixed_object_types().unwrap();
+    let mut predicate = Series::from_iter([
+        "http://example.net/ns#value",
+        "http://example.net/ns#value",
+    ]);
+    predicate.rename("predicate");
+    let mut subject = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#A"]);
+    subject.rename("subject");
+    let mut object = Series::from_iter(["42", "42"]);
+    object.rename("object");
+    let mut object_type = Series::from_iter([
+        "http://www.w3.org/2001/XMLSchema#int",
+        "http://www.w3.org/2001/XMLSchema#string",
+    ]);
+    object_type.rename("object_type");
+    let expected_df = DataFrame::new(vec![predicate, subject, object, object_type]).unwrap();
+    assert_eq!(df, expected_df);
+}
+
+#[rstest]
+#[serial]
+fn test_derived_ids_are_stable() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Measurement [xsd:anyURI ?sensor, xsd:anyURI ?measurement, xsd:long ?time, xsd:double ?value]
+      :: {
+        ottr:Triple(?sensor, ex:hasMeasurement, ?measurement) ,
+        ottr:Triple(?measurement, ex:time, ?time) ,
+        ottr:Triple(?measurement, ex:value, ?value)
+      } .
+    "#;
+    let expand = |format: DerivedIdFormat| {
+        let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+        let mut sensor = Series::from_iter(["http://example.net/ns#S1", "http://example.net/ns#S1"]);
+        sensor.rename("sensor");
+        let mut time = Series::from_iter([1i64, 2]);
+        time.rename("time");
+        let mut value = Series::from_iter([0.5f64, 0.7]);
+        value.rename("value");
+        let df = DataFrame::new(vec![sensor, time, value]).unwrap();
+        let mut derived_ids = HashMap::new();
+        derived_ids.insert(
+            "measurement".to_string(),
+            DerivedId {
+                from_columns: vec!["sensor".to_string(), "time".to_string()],
+                prefix: "http://example.net/ns#m_".to_string(),
+                format,
+            },
+        );
+        mapping
+            .expand(
+                "http://example.net/ns#Measurement",
+                df,
+                ExpandOptions {
+                    derived_ids: Some(derived_ids),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let triples: HashSet<Triple> = mapping
+            .export_oxrdf_triples()
+            .unwrap()
+            .into_iter()
+            .collect();
+        triples
+    };
+    let triples = expand(DerivedIdFormat::Ulid);
+    assert_eq!(triples.len(), 6);
+    assert_eq!(triples, expand(DerivedIdFormat::Ulid));
+    assert!(triples.contains(&Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#S1")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasMeasurement"),
+        object: Term::NamedNode(NamedNode::new_unchecked(
+            "http://example.net/ns#m_1WZ4D17ADTAZD9J9P766NEAV3E"
+        )),
+    }));
+    let triples = expand(DerivedIdFormat::Uuid);
+    assert!(triples.contains(&Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#S1")),
+        predicate: NamedNode::new_unchecked("http://example.net/ns#hasMeasurement"),
+        object: Term::NamedNode(NamedNode::new_unchecked(
+            "http://example.net/ns#m_3cf91a13-a9ba-57da-9926-c731aae56c6e"
+        )),
+    }));
+}
+
+#[rstest]
+#[serial]
+fn test_date_and_duration_literals() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ?date, ?duration]
+      :: {
+        ottr:Triple(?thing, ex:date, ?date) ,
+        ottr:Triple(?thing, ex:duration, ?duration)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A", "http://example.net/ns#B"]);
+    thing.rename("thing");
+    let mut date = Series::from_iter([19000i32, 19001])
+        .cast(&DataType::Date)
+        .unwrap();
+    date.rename("date");
+    let mut duration = Series::from_iter([5_400_500i64, 86_400_000])
+        .cast(&DataType::Duration(TimeUnit::Milliseconds))
+        .unwrap();
+    duration.rename("duration");
+    let df = DataFrame::new(vec![thing, date, duration]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    let mut buffer = vec![];
+    mapping.write_n_triples(&mut buffer).unwrap();
+    let mut lines: Vec<String> = String::from_utf8(buffer)
+        .unwrap()
+        .lines()
+        .map(|x| x.to_string())
+        .collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "<http://example.net/ns#A> <http://example.net/ns#date> \"2022-01-08\"^^<http://www.w3.org/2001/XMLSchema#date> .",
+            "<http://example.net/ns#A> <http://example.net/ns#duration> \"PT1H30M0.5S\"^^<http://www.w3.org/2001/XMLSchema#duration> .",
+            "<http://example.net/ns#B> <http://example.net/ns#date> \"2022-01-09\"^^<http://www.w3.org/2001/XMLSchema#date> .",
+            "<http://example.net/ns#B> <http://example.net/ns#duration> \"P1D\"^^<http://www.w3.org/2001/XMLSchema#duration> .",
+        ]
+    );
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+    SELECT ?thing WHERE {
+        ?thing ex:date ?date .
+        ?thing ex:duration ?duration .
+        FILTER(?date > "2022-01-08"^^xsd:date && ?duration > "PT2H"^^xsd:duration)
+    }
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        let mut thing = Series::from_iter(["http://example.net/ns#B"]);
+        thing.rename("thing");
+        assert_eq!(df, DataFrame::new(vec![thing]).unwrap());
+    } else {
+        panic!("Expected select result");
+    }
+
+    let mut roundtrip = Mapping::from_str(&t_str, None).unwrap();
+    roundtrip
+        .expand_turtle_instances(
+            r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+    @prefix xsd:<http://www.w3.org/2001/XMLSchema#>.
+
+    [] ottr:of ex:ExampleTemplate ;
+       ottr:values ( ex:A "2022-01-08"^^xsd:date "PT1H30M0.5S"^^xsd:duration ) .
+    "#,
+        )
+        .unwrap();
+    let mut buffer = vec![];
+    roundtrip.write_n_triples(&mut buffer).unwrap();
+    let mut roundtrip_lines: Vec<String> = String::from_utf8(buffer)
+        .unwrap()
+        .lines()
+        .map(|x| x.to_string())
+        .collect();
+    roundtrip_lines.sort();
+    assert_eq!(roundtrip_lines, lines[0..2].to_vec());
+}
+
+#[rstest]
+#[serial]
+fn test_integer_subtype_and_decimal_literals() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:byte ?byte, xsd:nonNegativeInteger ?count, xsd:decimal ?amount]
+      :: {
+        ottr:Triple(?thing, ex:byte, ?byte) ,
+        ottr:Triple(?thing, ex:count, ?count) ,
+        ottr:Triple(?thing, ex:amount, ?amount) ,
+        ottr:Triple(?thing, ex:constant, "7"^^xsd:unsignedShort)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut byte = Series::from_iter([3i32]).cast(&DataType::Int8).unwrap();
+    byte.rename("byte");
+    let mut count = Series::from_iter([10u64]);
+    count.rename("count");
+    let mut amount = Series::from_iter([1.5f64]);
+    amount.rename("amount");
+    let df = DataFrame::new(vec![thing.clone(), byte, count.clone(), amount.clone()]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |p: &str, o: &str, dt: &str| Triple {
+        subject: Subject::NamedNode(NamedNode::new_unchecked("http://example.net/ns#A")),
+        predicate: NamedNode::new_unchecked(format!("http://example.net/ns#{}", p)),
+        object: Term::Literal(Literal::new_typed_literal(
+            o,
+            NamedNode::new_unchecked(format!("http://www.w3.org/2001/XMLSchema#{}", dt)),
+        )),
+    };
+    let expected = HashSet::from([
+        triple("byte", "3", "byte"),
+        triple("count", "10", "nonNegativeInteger"),
+        triple("amount", "1.5", "decimal"),
+        triple("constant", "7", "unsignedShort"),
+    ]);
+    assert_eq!(triples, expected);
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+    SELECT ?thing WHERE {?thing ex:byte "3"^^xsd:byte}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.height(), 1);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let mut byte = Series::from_iter(["3"]);
+    byte.rename("byte");
+    let df = DataFrame::new(vec![thing, byte, count, amount]).unwrap();
+    let res = mapping.expand("http://example.net/ns#ExampleTemplate", df, Default::default());
+    assert!(matches!(
+        res,
+        Err(MappingError::ColumnDataTypeMismatch(..))
+    ));
+}
+
+#[rstest]
+#[serial]
+fn test_decimals_from_integer_columns_are_not_cast_to_floats() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:decimal ?amount]
+      :: {
+        ottr:Triple(?thing, ex:amount, ?amount)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let thing = Series::new("thing", ["http://example.net/ns#A"]);
+    let amount = Series::new("amount", [16_777_217i32]);
+    let df = DataFrame::new(vec![thing, amount]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let res = mapping
+        .triplestore
+        .query(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?amount WHERE {?thing ex:amount ?amount}
+    "#,
+        )
+        .unwrap();
+    if let QueryResult::Select(df) = res {
+        assert_eq!(df.column("amount").unwrap().dtype(), &DataType::Int32);
+        assert_eq!(
+            df.column("amount").unwrap().get(0),
+            AnyValue::Int32(16_777_217)
+        );
+    } else {
+        panic!("Expected select result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_export_oxrdf_triples_roundtrip(testdata_path: PathBuf) {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ?label, ?name, ?flag, ?small, ?big, ?ratio, ?limit, ?timestamp, ?date]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label) ,
+        ottr:Triple(?thing, ex:name, ?name) ,
+        ottr:Triple(?thing, ex:flag, ?flag) ,
+        ottr:Triple(?thing, ex:small, ?small) ,
+        ottr:Triple(?thing, ex:big, ?big) ,
+        ottr:Triple(?thing, ex:ratio, ?ratio) ,
+        ottr:Triple(?thing, ex:limit, ?limit) ,
+        ottr:Triple(?thing, ex:timestamp, ?timestamp) ,
+        ottr:Triple(?thing, ex:date, ?date) ,
+        ottr:Triple(?thing, ex:related, _:related)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let mut thing = Series::from_iter(["http://example.net/ns#A"]);
+    thing.rename("thing");
+    let mut label = Series::from_iter(["Eh"]);
+    label.rename("label");
+    let mut name = Series::from_iter(["A"]);
+    name.rename("name");
+    let mut flag = Series::from_iter([true]);
+    flag.rename("flag");
+    let mut small = Series::from_iter([-3i32]);
+    small.rename("small");
+    let mut big = Series::from_iter([18_000_000_000_000_000_000u64]);
+    big.rename("big");
+    let mut ratio = Series::from_iter([1.5f32]);
+    ratio.rename("ratio");
+    let mut limit = Series::from_iter([f64::INFINITY]);
+    limit.rename("limit");
+    let timestamp = Series::from_any_values(
+        "timestamp",
+        &[AnyValue::Datetime(
+            1656842780123,
+            TimeUnit::Milliseconds,
+            &Some("Europe/Oslo".to_string()),
+        )],
+    )
+    .unwrap();
+    let mut date = Series::from_iter([19000i32]).cast(&DataType::Date).unwrap();
+    date.rename("date");
+    let df = DataFrame::new(vec![
+        thing, label, name, flag, small, big, ratio, limit, timestamp, date,
+    ])
+    .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let objects: HashMap<String, Term> = triples
+        .iter()
+        .map(|t| (t.predicate.as_str().to_string(), t.object.clone()))
+        .collect();
+    let typed = |o: &str, dt: &str| {
+        Term::Literal(Literal::new_typed_literal(
+            o,
+            NamedNode::new_unchecked(format!("http://www.w3.org/2001/XMLSchema#{}", dt)),
+        ))
+    };
+    let object = |p: &str| objects.get(&format!("http://example.net/ns#{}", p)).unwrap().clone();
+    assert_eq!(
+        object("label"),
+        Term::Literal(Literal::new_language_tagged_literal_unchecked("Eh", "en"))
+    );
+    assert_eq!(object("name"), Term::Literal(Literal::new_simple_literal("A")));
+    assert_eq!(object("flag"), typed("true", "boolean"));
+    assert_eq!(object("small"), typed("-3", "int"));
+    assert_eq!(object("big"), typed("18000000000000000000", "unsignedLong"));
+    assert_eq!(object("ratio"), typed("1.5", "float"));
+    assert_eq!(object("limit"), typed("INF", "double"));
+    assert_eq!(
+        object("timestamp"),
+        typed("2022-07-03T10:06:20.123+02:00", "dateTimeStamp")
+    );
+    assert_eq!(object("date"), typed("2022-01-08", "date"));
+    assert!(matches!(object("related"), Term::BlankNode(_)));
+
+    //The triples are the same when written as N-Triples and parsed
+    let mut file_path = testdata_path.clone();
+    file_path.push("actual_export_roundtrip.nt");
+    let mut actual_file = File::create(file_path.as_path()).unwrap();
+    mapping.write_n_triples(&mut actual_file).unwrap();
+    let parsed = triples_from_file(File::open(file_path.as_path()).unwrap());
+    assert_eq!(parsed, triples);
+}
+
+#[rstest]
+#[serial]
+fn test_query_cursor_pages() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+
+    let things: Vec<String> = (0..25)
+        .map(|i| format!("http://example.net/ns#thing{}", i))
+        .collect();
+    let thing = Series::new("thing", things);
+    let number = Series::new("number", (0..25).collect::<Vec<i64>>());
+    let df = DataFrame::new(vec![thing, number]).unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?number WHERE { ?thing ex:hasNumber ?number } ORDER BY ?number
+    "#;
+    let handle = mapping
+        .triplestore
+        .open_query_cursor(query, Duration::from_secs(60))
+        .unwrap();
+    let page = mapping.triplestore.fetch_query_page(&handle, 10, 10).unwrap();
+    assert_eq!(page.total_rows, 25);
+    assert_eq!(page.offset, 10);
+    assert_eq!(page.df.height(), 10);
+    assert_eq!(page.df.column("number").unwrap().get(0), AnyValue::Int64(10));
+    let last_page = mapping.triplestore.fetch_query_page(&handle, 20, 10).unwrap();
+    assert_eq!(last_page.df.height(), 5);
+    let past_end = mapping.triplestore.fetch_query_page(&handle, 30, 10).unwrap();
+    assert_eq!(past_end.df.height(), 0);
+    assert_eq!(past_end.offset, 25);
+
+    assert!(mapping.triplestore.close_query_cursor(&handle));
+    assert!(matches!(
+        mapping.triplestore.fetch_query_page(&handle, 0, 10),
+        Err(SparqlError::UnknownQueryCursor(_))
+    ));
+
+    let expiring = mapping
+        .triplestore
+        .open_query_cursor(query, Duration::from_millis(1))
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(10));
+    assert!(mapping.triplestore.fetch_query_page(&expiring, 0, 10).is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_tenant_isolation_and_quotas() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:string ?myLabel]
+      :: {
+        ottr:Triple(?myThing, ex:label, ?myLabel)
+      } .
+    "#;
+    let label_df = |things: &[&str], labels: &[&str]| {
+        DataFrame::new(vec![
+            Series::new("myThing", things),
+            Series::new("myLabel", labels),
+        ])
+        .unwrap()
+    };
+    let template = "http://example.net/ns#ExampleTemplate";
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            template,
+            label_df(&["http://example.net/ns#shared"], &["Shared"]),
+            Default::default(),
+        )
+        .unwrap();
+    mapping
+        .expand_for_tenant(
+            "acme",
+            template,
+            label_df(
+                &["http://example.net/ns#a1", "http://example.net/ns#a2"],
+                &["Acme one", "Acme two"],
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    mapping
+        .expand_for_tenant(
+            "globex",
+            template,
+            label_df(&["http://example.net/ns#g1"], &["Globex one"]),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        mapping.triplestore.tenants(),
+        vec!["acme".to_string(), "globex".to_string()]
+    );
+    assert_eq!(mapping.triplestore.tenant_triple_count("acme"), 2);
+
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?label WHERE { ?thing ex:label ?label }
+    "#;
+    let tenant_labels = |mapping: &mut Mapping, tenant: &str| -> Vec<String> {
+        if let QueryResult::Select(df) = mapping.triplestore.query_tenant(tenant, query).unwrap() {
+            if df.height() == 0 {
+                return vec![];
+            }
+            let mut labels: Vec<String> = df
+                .column("label")
+                .unwrap()
+                .utf8()
+                .unwrap()
+                .into_no_null_iter()
+                .map(|x| x.to_string())
+                .collect();
+            labels.sort();
+            labels
+        } else {
+            panic!("Expected select result");
+        }
+    };
+    assert_eq!(tenant_labels(&mut mapping, "acme"), vec!["Acme one", "Acme two"]);
+    assert_eq!(tenant_labels(&mut mapping, "globex"), vec!["Globex one"]);
+    assert!(tenant_labels(&mut mapping, "initech").is_empty());
+    //The default graph is not changed by tenant queries
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.height(), 1);
+    } else {
+        panic!("Expected select result");
+    }
+
+    let triples = mapping
+        .triplestore
+        .export_tenant_oxrdf_triples("globex")
+        .unwrap();
+    assert_eq!(
+        triples,
+        vec![Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#g1"),
+            NamedNode::new_unchecked("http://example.net/ns#label"),
+            Literal::new_simple_literal("Globex one"),
+        )]
+    );
+
+    mapping.triplestore.set_tenant_quota("globex", 2);
+    mapping
+        .expand_for_tenant(
+            "globex",
+            template,
+            label_df(&["http://example.net/ns#g2"], &["Globex two"]),
+            Default::default(),
+        )
+        .unwrap();
+    let res = mapping.expand_for_tenant(
+        "globex",
+        template,
+        label_df(&["http://example.net/ns#g3"], &["Globex three"]),
+        Default::default(),
+    );
+    assert!(matches!(res, Err(MappingError::TenantQuotaExceeded(..))));
+    assert_eq!(mapping.triplestore.tenant_triple_count("globex"), 2);
+    //Other tenants are not limited by the quota
+    mapping
+        .expand_for_tenant(
+            "acme",
+            template,
+            label_df(&["http://example.net/ns#a3"], &["Acme three"]),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(mapping.triplestore.tenant_triple_count("acme"), 3);
+
+    assert!(mapping.triplestore.remove_tenant("acme"));
+    assert!(!mapping.triplestore.remove_tenant("acme"));
+    assert_eq!(mapping.triplestore.tenants(), vec!["globex".to_string()]);
+}
+
+#[rstest]
+#[serial]
+fn test_expansion_coverage_report() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Named [xsd:anyURI ?thing, xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:hasName, ?name),
+        ottr:Triple(?thing, ex:type, ex:NamedThing)
+      } .
+
+    ex:Thing [xsd:anyURI ?thing, ? xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:type, ex:Thing),
+        ex:Named(?thing, ?name)
+      } .
+    "#;
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let thing = Series::new(
+        "thing",
+        ["http://example.net/ns#A", "http://example.net/ns#B"],
+    );
+    let name = Series::new("name", [None::<&str>, None]);
+    let report = mapping
+        .expand(
+            "http://example.net/ns#Thing",
+            DataFrame::new(vec![thing, name]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    let template = |t: &str, triples: usize| TemplateCoverage {
+        template: format!("http://example.net/ns#{}", t),
+        triples,
+    };
+    let pattern = |t: &str, pattern_index: usize, instance_template: &str, triples: usize| {
+        PatternCoverage {
+            template: format!("http://example.net/ns#{}", t),
+            pattern_index,
+            instance_template: instance_template.to_string(),
+            triples,
+        }
+    };
+    let triple_template = "http://ns.ottr.xyz/0.4/Triple";
+    assert_eq!(
+        report.coverage.templates,
+        vec![template("Named", 0), template("Thing", 2)]
+    );
+    assert_eq!(
+        report.coverage.patterns,
+        vec![
+            pattern("Named", 0, triple_template, 0),
+            pattern("Named", 1, triple_template, 0),
+            pattern("Thing", 0, triple_template, 2),
+            pattern("Thing", 1, "http://example.net/ns#Named", 0),
+        ]
+    );
+    assert_eq!(
+        report.coverage.dead_templates(),
+        vec![&template("Named", 0)]
+    );
+    assert_eq!(report.coverage.dead_patterns().len(), 3);
+
+    let thing = Series::new("thing", ["http://example.net/ns#C"]);
+    let name = Series::new("name", ["c"]);
+    let report = mapping
+        .expand(
+            "http://example.net/ns#Thing",
+            DataFrame::new(vec![thing, name]).unwrap(),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        report.coverage.templates,
+        vec![template("Named", 2), template("Thing", 3)]
+    );
+    assert!(report.coverage.dead_patterns().is_empty());
+}
+
+#[rstest]
+#[serial]
+fn test_write_sparql_json_and_xml_results() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, xsd:long ?count, xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count),
+        ottr:Triple(?thing, ex:name, ?name)
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#one"]),
+        Series::new("label", ["one"]),
+        Series::new("count", [3i64]),
+        Series::new("name", ["Thing \"1\" & <more>"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?label ?count ?name WHERE {
+        ?s ex:label ?label .
+        ?s ex:count ?count .
+        ?s ex:name ?name .
+    }
+    "#;
+
+    let mut json = vec![];
+    mapping
+        .triplestore
+        .query_to_sparql_results(query, SparqlResultsFormat::Json, &mut json)
+        .unwrap();
+    let expected_json = concat!(
+        r#"{"head":{"vars":["s","label","count","name"]},"results":{"bindings":[{"#,
+        r#""s":{"type":"uri","value":"http://example.net/ns#one"},"#,
+        r#""label":{"type":"literal","value":"one","xml:lang":"en"},"#,
+        r#""count":{"type":"literal","value":"3","datatype":"http://www.w3.org/2001/XMLSchema#long"},"#,
+        r#""name":{"type":"literal","value":"Thing \"1\" & <more>"}}]}}"#
+    );
+    assert_eq!(String::from_utf8(json).unwrap(), expected_json);
+
+    let mut xml = vec![];
+    mapping
+        .triplestore
+        .query_to_sparql_results(query, SparqlResultsFormat::Xml, &mut xml)
+        .unwrap();
+    let xml = String::from_utf8(xml).unwrap();
+    assert!(xml.contains(r#"<variable name="label"/>"#));
+    assert!(xml.contains(r#"<binding name="s"><uri>http://example.net/ns#one</uri></binding>"#));
+    assert!(xml.contains(r#"<binding name="label"><literal xml:lang="en">one</literal></binding>"#));
+    assert!(xml.contains(
+        r#"<binding name="count"><literal datatype="http://www.w3.org/2001/XMLSchema#long">3</literal></binding>"#
+    ));
+    assert!(xml.contains(
+        r#"<binding name="name"><literal>Thing &quot;1&quot; &amp; &lt;more&gt;</literal></binding>"#
+    ));
+
+    //The language tags are not in the data frame of the result
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.get_column_names(), vec!["s", "label", "count", "name"]);
+    } else {
+        panic!("Expected select result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_join_hints_from_template_nesting() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Address [xsd:anyURI ?owner, xsd:string ?street]
+      :: {
+        ottr:Triple(?owner, ex:hasAddress, _:a),
+        ottr:Triple(_:a, ex:street, ?street)
+      } .
+
+    ex:Person [xsd:anyURI ?person, xsd:string ?name, xsd:string ?street]
+      :: {
+        ottr:Triple(?person, ex:name, ?name),
+        ex:Address(?person, ?street)
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new(
+            "person",
+            ["http://example.net/ns#ann", "http://example.net/ns#bob"],
+        ),
+        Series::new("name", ["Ann", "Bob"]),
+        Series::new("street", ["Storgata", "Lillegata"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#Person", df, Default::default())
+        .unwrap();
+    let ex = |s: &str| format!("http://example.net/ns#{}", s);
+    assert_eq!(
+        mapping.triplestore.join_hints(),
+        vec![
+            &JoinHint::new(
+                &ex("name"),
+                TriplePosition::Subject,
+                &ex("hasAddress"),
+                TriplePosition::Subject
+            ),
+            &JoinHint::new(
+                &ex("street"),
+                TriplePosition::Subject,
+                &ex("hasAddress"),
+                TriplePosition::Object
+            ),
+        ]
+    );
+
+    //The second pattern shares no variables with the first, the pattern with a join hint is evaluated before it
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?name ?street WHERE {
+        ?p ex:name ?name .
+        ?a ex:street ?street .
+        ?p ex:hasAddress ?a .
+    } ORDER BY ?name
+    "#;
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.height(), 2);
+        let streets: Vec<&str> = df
+            .column("street")
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(streets, vec!["Storgata", "Lillegata"]);
+    } else {
+        panic!("Expected select result");
+    }
+}
+
+#[rstest]
+#[serial]
+fn test_expand_hierarchical_identifiers() {
+    let mut mapping = Mapping::from_str("", None).unwrap();
+    let hierarchy = Hierarchy {
+        path_column: "tag".to_string(),
+        separator: "/".to_string(),
+        iri_prefix: "http://example.net/plant/".to_string(),
+        predicate_prefix: Some("http://example.net/ns#".to_string()),
+    };
+    let df = DataFrame::new(vec![Series::new(
+        "tag",
+        [Some("/Site/Area 1/Pump"), Some("/Site/Area 1/Valve"), None],
+    )])
+    .unwrap();
+    mapping
+        .expand_hierarchy(df, &hierarchy, Default::default())
+        .unwrap();
+    assert_eq!(
+        hierarchy.node_iri("/Site/Area 1/Pump"),
+        Some("http://example.net/plant/Site/Area%201/Pump".to_string())
+    );
+    assert_eq!(hierarchy.node_iri("/"), None);
+
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let node = |path: &str| NamedNode::new_unchecked(hierarchy.node_iri(path).unwrap());
+    let ex = |s: &str| NamedNode::new_unchecked(format!("http://example.net/ns#{}", s));
+    let triple = |path: &str, p: &str, o: Term| Triple {
+        subject: Subject::NamedNode(node(path)),
+        predicate: ex(p),
+        object: o,
+    };
+    let level = |l: i64| Term::Literal(Literal::new_typed_literal(l.to_string(), xsd::LONG));
+    let name = |n: &str| Term::Literal(Literal::new_simple_literal(n));
+    let expected = HashSet::from([
+        triple("Site", "name", name("Site")),
+        triple("Site", "level", level(1)),
+        triple("Site/Area 1", "name", name("Area 1")),
+        triple("Site/Area 1", "level", level(2)),
+        triple("Site/Area 1", "hasParent", Term::NamedNode(node("Site"))),
+        triple("Site/Area 1/Pump", "name", name("Pump")),
+        triple("Site/Area 1/Pump", "level", level(3)),
+        triple("Site/Area 1/Pump", "hasParent", Term::NamedNode(node("Site/Area 1"))),
+        triple("Site/Area 1/Valve", "name", name("Valve")),
+        triple("Site/Area 1/Valve", "level", level(3)),
+        triple("Site/Area 1/Valve", "hasParent", Term::NamedNode(node("Site/Area 1"))),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_query_oxrdf_solutions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#one"]),
+        Series::new("label", ["one"]),
+        Series::new("count", [3i64]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?label ?count ?missing WHERE {
+        ?s ex:label ?label .
+        ?s ex:count ?count .
+        OPTIONAL { ?s ex:missing ?missing }
+    }
+    "#;
+    let solutions = mapping.triplestore.query_oxrdf_solutions(query).unwrap();
+    let expected = HashMap::from([
+        (
+            Variable::new_unchecked("s"),
+            Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#one")),
+        ),
+        (
+            Variable::new_unchecked("label"),
+            Term::Literal(Literal::new_language_tagged_literal_unchecked("one", "en")),
+        ),
+        (
+            Variable::new_unchecked("count"),
+            Term::Literal(Literal::new_typed_literal("3", xsd::LONG)),
+        ),
+    ]);
+    assert_eq!(solutions, vec![expected]);
+}
+
+#[rstest]
+#[serial]
+fn test_incremental_deduplication() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ?label, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = |things: &[&str], labels: &[&str], counts: &[i64]| {
+        DataFrame::new(vec![
+            Series::new("thing", things),
+            Series::new("label", labels),
+            Series::new("count", counts),
+        ])
+        .unwrap()
+    };
+    let options = || ExpandOptions {
+        language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+        ..Default::default()
+    };
+    let caching_folder = std::env::temp_dir().join("stottrs_incremental_deduplication");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .triplestore
+            .set_deduplication_strategy(DeduplicationStrategy::Incremental);
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(
+                    &["http://example.net/ns#a", "http://example.net/ns#b"],
+                    &["a", "b"],
+                    &[1, 2],
+                ),
+                options(),
+            )
+            .unwrap();
+        assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 4);
+
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(
+                    &["http://example.net/ns#b", "http://example.net/ns#c", "http://example.net/ns#c"],
+                    &["b", "c", "c"],
+                    &[2, 3, 3],
+                ),
+                options(),
+            )
+            .unwrap();
+        assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 6);
+
+        //The same label with another language tag is another triple
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(&["http://example.net/ns#a"], &["a"], &[1]),
+                ExpandOptions {
+                    language_tags: Some(HashMap::from([("label".to_string(), "nb".to_string())])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let triples = mapping.export_oxrdf_triples().unwrap();
+        assert_eq!(triples.len(), 7);
+        assert!(triples.contains(&Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#a"),
+            NamedNode::new_unchecked("http://example.net/ns#label"),
+            Literal::new_language_tagged_literal_unchecked("a", "nb"),
+        )));
+
+        let query = r#"
+        PREFIX ex:<http://example.net/ns#>
+        SELECT ?s ?count WHERE { ?s ex:count ?count }
+        "#;
+        if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+            assert_eq!(df.height(), 3);
+        } else {
+            panic!("Expected SELECT results");
+        }
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_recover_interrupted_caching_folder_writes() {
+    let caching_folder = std::env::temp_dir().join("stottrs_recover_caching_folder");
+    let _ = std::fs::remove_dir_all(&caching_folder);
+    std::fs::create_dir_all(&caching_folder).unwrap();
+    let folder = caching_folder.to_str().unwrap().to_string();
+
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("count", [1i64]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, Some(folder.clone())).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    //Completed writes leave no manifests behind
+    assert!(Triplestore::recover(&folder, RecoveryAction::Remove)
+        .unwrap()
+        .is_empty());
+    let n_files = std::fs::read_dir(&caching_folder).unwrap().count();
+    assert_eq!(n_files, 1);
+
+    //A write interrupted after the manifest and one of its files were written
+    let orphan = caching_folder.join("httpexamplenetnscount_orphan.parquet");
+    let never_written = caching_folder.join("httpexamplenetnscount_never_written.parquet");
+    std::fs::write(&orphan, b"PAR1").unwrap();
+    let mut manifest = File::create(caching_folder.join("interrupted.inprogress")).unwrap();
+    writeln!(manifest, "{}", orphan.to_str().unwrap()).unwrap();
+    writeln!(manifest, "{}", never_written.to_str().unwrap()).unwrap();
+    drop(manifest);
+
+    let recovered = Triplestore::recover(&folder, RecoveryAction::Quarantine).unwrap();
+    assert_eq!(recovered, vec![orphan.to_str().unwrap().to_string()]);
+    assert!(!orphan.exists());
+    assert!(caching_folder
+        .join("quarantine")
+        .join("httpexamplenetnscount_orphan.parquet")
+        .exists());
+    assert!(!caching_folder.join("interrupted.inprogress").exists());
+    assert!(Triplestore::recover(&folder, RecoveryAction::Remove)
+        .unwrap()
+        .is_empty());
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_caching_folder_chunks_sorted_by_subject() {
+    let caching_folder = std::env::temp_dir().join("stottrs_sorted_caching_folder");
+    let _ = std::fs::remove_dir_all(&caching_folder);
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let things: Vec<String> = (0..5000)
+        .rev()
+        .map(|i| format!("http://example.net/ns#thing{:05}", i))
+        .collect();
+    let counts: Vec<i64> = (0..5000).rev().collect();
+    let df = DataFrame::new(vec![
+        Series::new("thing", things),
+        Series::new("count", counts),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(
+        &t_str,
+        Some(caching_folder.to_str().unwrap().to_string()),
+    )
+    .unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    for entry in std::fs::read_dir(&caching_folder).unwrap() {
+        let path = entry.unwrap().path();
+        let df = ParquetReader::new(File::open(&path).unwrap())
+            .finish()
+            .unwrap();
+        let subjects: Vec<&str> = df
+            .column("subject")
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.unwrap())
+            .collect();
+        let mut sorted = subjects.clone();
+        sorted.sort();
+        assert_eq!(subjects, sorted);
+    }
+
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?count WHERE { ex:thing04242 ex:count ?count }
+    "#;
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.height(), 1);
+        assert_eq!(df.column("count").unwrap().get(0), AnyValue::Int64(4242));
+    } else {
+        panic!("Expected SELECT results");
+    }
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    PREFIX xsd:<http://www.w3.org/2001/XMLSchema#>
+    SELECT ?s WHERE { ?s ex:count "17"^^xsd:long }
+    "#;
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(
+            df.column("s").unwrap().get(0),
+            AnyValue::Utf8("http://example.net/ns#thing00017")
+        );
+    } else {
+        panic!("Expected SELECT results");
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_query_result_accessors() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?name, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:name, ?name),
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("name", ["A"]),
+        Series::new("count", [7i64]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    let select = mapping
+        .triplestore
+        .query("PREFIX ex:<http://example.net/ns#> SELECT ?s WHERE { ?s ex:name ?name }")
+        .unwrap();
+    assert_eq!(select.as_select().unwrap().height(), 1);
+    assert!(select.into_triples().is_none());
+
+    let construct = mapping
+        .triplestore
+        .query(
+            r#"PREFIX ex:<http://example.net/ns#>
+            CONSTRUCT { ?s ex:label ?name . ?s ex:total ?count } WHERE { ?s ex:name ?name . ?s ex:count ?count }"#,
+        )
+        .unwrap();
+    assert!(construct.as_select().is_none());
+    let triples: HashSet<Triple> = construct.into_triples().unwrap().into_iter().collect();
+    let expected = HashSet::from([
+        Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#a"),
+            NamedNode::new_unchecked("http://example.net/ns#label"),
+            Literal::new_simple_literal("A"),
+        ),
+        Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#a"),
+            NamedNode::new_unchecked("http://example.net/ns#total"),
+            Literal::new_typed_literal("7", xsd::LONG),
+        ),
+    ]);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_nested_template_column_collisions() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Inner [xsd:anyURI ?object, xsd:anyURI ?subject]
+      :: {
+        ottr:Triple(?subject, ex:relatesTo, ?object)
+      } .
+
+    ex:Twice [xsd:anyURI ?a, xsd:anyURI ?b]
+      :: {
+        ottr:Triple(?a, ex:sameAs, ?b)
+      } .
+
+    ex:Outer [xsd:anyURI ?subject, xsd:anyURI ?object]
+      :: {
+        ex:Inner(?subject, ?object),
+        ex:Twice(?subject, ?subject)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("subject", ["http://example.net/ns#s"]),
+        Series::new("object", ["http://example.net/ns#o"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#Outer", df, Default::default())
+        .unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let ex = |s: &str| NamedNode::new_unchecked(format!("http://example.net/ns#{}", s));
+    let expected = HashSet::from([
+        Triple::new(ex("o"), ex("relatesTo"), ex("s")),
+        Triple::new(ex("s"), ex("sameAs"), ex("s")),
+    ]);
+    assert_eq!(triples, expected);
+
+    let duplicate_parameters = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Duplicate [?a, ?a]
+      :: {
+        ottr:Triple(?a, ex:p, ?a)
+      } .
+    "#;
+    assert!(Mapping::from_str(&duplicate_parameters, None).is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_triple_pattern_with_predicate_variable() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:anyURI ?other, xsd:string ?label, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:relatedTo, ?other),
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#one", "http://example.net/ns#two"]),
+        Series::new("other", ["http://example.net/ns#two", "http://example.net/ns#one"]),
+        Series::new("label", ["one", "two"]),
+        Series::new("count", [1i64, 2]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions::default(),
+        )
+        .unwrap();
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?p ?o WHERE {
+        ex:one ?p ?o .
+    }
+    "#;
+    let mut solutions: Vec<(String, String)> = mapping
+        .triplestore
+        .query_oxrdf_solutions(query)
+        .unwrap()
+        .into_iter()
+        .map(|s| {
+            (
+                s.get(&Variable::new_unchecked("p")).unwrap().to_string(),
+                s.get(&Variable::new_unchecked("o")).unwrap().to_string(),
+            )
+        })
+        .collect();
+    solutions.sort();
+    assert_eq!(
+        solutions,
+        vec![
+            (
+                "<http://example.net/ns#count>".to_string(),
+                "\"1\"^^<http://www.w3.org/2001/XMLSchema#long>".to_string()
+            ),
+            (
+                "<http://example.net/ns#label>".to_string(),
+                "\"one\"".to_string()
+            ),
+            (
+                "<http://example.net/ns#relatedTo>".to_string(),
+                "<http://example.net/ns#two>".to_string()
+            ),
+        ]
+    );
+
+    //Only the tables with the type of the constant object are scanned
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?p WHERE {
+        ?s ?p ex:one .
+    }
+    "#;
+    let solutions = mapping.triplestore.query_oxrdf_solutions(query).unwrap();
+    let expected = HashMap::from([
+        (
+            Variable::new_unchecked("s"),
+            Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#two")),
+        ),
+        (
+            Variable::new_unchecked("p"),
+            Term::NamedNode(NamedNode::new_unchecked("http://example.net/ns#relatedTo")),
+        ),
+    ]);
+    assert_eq!(solutions, vec![expected]);
+}
+
+#[rstest]
+#[serial]
+fn test_write_n_triples_since_watermark() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = |things: &[&str], counts: &[i64]| {
+        DataFrame::new(vec![Series::new("thing", things), Series::new("count", counts)]).unwrap()
+    };
+    let write_since = |mapping: &mut Mapping, watermark: &ExportWatermark| {
+        let mut buffer = vec![];
+        let watermark = mapping.write_n_triples_since(&mut buffer, watermark).unwrap();
+        let mut lines: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        lines.sort();
+        (lines, watermark)
+    };
+    let caching_folder = std::env::temp_dir().join("stottrs_write_n_triples_since");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(&["http://example.net/ns#a", "http://example.net/ns#b"], &[1, 2]),
+                ExpandOptions::default(),
+            )
+            .unwrap();
+        let (lines, watermark) = write_since(&mut mapping, &ExportWatermark::default());
+        assert_eq!(lines.len(), 2);
+
+        //Triples exported before are not written again, also when they are added again
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(&["http://example.net/ns#b", "http://example.net/ns#c"], &[2, 3]),
+                ExpandOptions::default(),
+            )
+            .unwrap();
+        let (lines, watermark) = write_since(&mut mapping, &watermark);
+        assert_eq!(
+            lines,
+            vec![
+                "<http://example.net/ns#c> <http://example.net/ns#count> \"3\"^^<http://www.w3.org/2001/XMLSchema#long> ."
+                    .to_string()
+            ]
+        );
+
+        let (lines, _) = write_since(&mut mapping, &watermark);
+        assert!(lines.is_empty());
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_write_n_triples_since_watermark_after_table_is_added_again() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = |things: &[&str], counts: &[i64]| {
+        DataFrame::new(vec![Series::new("thing", things), Series::new("count", counts)]).unwrap()
+    };
+    let caching_folder = std::env::temp_dir().join("stottrs_write_n_triples_since_added_again");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(&["http://example.net/ns#a", "http://example.net/ns#b"], &[1, 2]),
+                ExpandOptions::default(),
+            )
+            .unwrap();
+        let mut buffer = vec![];
+        let watermark = mapping
+            .write_n_triples_since(&mut buffer, &ExportWatermark::default())
+            .unwrap();
+
+        //Removing all the triples removes the table, the table added next has as many chunks as the exported one
+        mapping
+            .triplestore
+            .deconstruct_update(
+                "PREFIX ex:<http://example.net/ns#> CONSTRUCT { ?s ex:count ?c } WHERE { ?s ex:count ?c }",
+            )
+            .unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(&["http://example.net/ns#c", "http://example.net/ns#d"], &[3, 4]),
+                ExpandOptions::default(),
+            )
+            .unwrap();
+        let mut buffer = vec![];
+        mapping
+            .write_n_triples_since(&mut buffer, &watermark)
+            .unwrap();
+        let mut lines: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                "<http://example.net/ns#c> <http://example.net/ns#count> \"3\"^^<http://www.w3.org/2001/XMLSchema#long> .",
+                "<http://example.net/ns#d> <http://example.net/ns#count> \"4\"^^<http://www.w3.org/2001/XMLSchema#long> .",
+            ]
+        );
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_n_triples_lines_and_write_options() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            ["http://example.net/ns#a", "http://example.net/ns#b", "http://example.net/ns#c"],
+        ),
+        Series::new("label", ["a", "b", "c"]),
+        Series::new("count", [1i64, 2, 3]),
+    ])
+    .unwrap();
+    let caching_folder = std::env::temp_dir().join("stottrs_n_triples_lines");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for folder in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        let mut mapping = Mapping::from_str(&t_str, folder).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df.clone(),
+                ExpandOptions::default(),
+            )
+            .unwrap();
+        let mut buffer = vec![];
+        mapping.write_n_triples(&mut buffer).unwrap();
+        let mut expected: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        expected.sort();
+        assert_eq!(expected.len(), 6);
+
+        let mut lines: Vec<String> = mapping
+            .n_triples_lines(2)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        lines.sort();
+        assert_eq!(lines, expected);
+
+        let mut buffer = vec![];
+        mapping
+            .write_n_triples_with_options(
+                &mut buffer,
+                &NTriplesWriteOptions {
+                    chunk_size: 1,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut lines: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        lines.sort();
+        assert_eq!(lines, expected);
+    }
+
+    //Chunks that can not be read give an error instead of a panic
+    let mut mapping = Mapping::from_str(&t_str, Some(caching_folder.to_str().unwrap().to_string())).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, ExpandOptions::default())
+        .unwrap();
+    let mut lines = mapping.n_triples_lines(2).unwrap();
+    for entry in std::fs::read_dir(&caching_folder).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(false, |e| e == "parquet") {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+    assert!(matches!(lines.next(), Some(Err(MappingError::ReadParquetError(_)))));
+    assert!(lines.next().is_none());
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_triple_pattern_with_language_tagged_literal() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let df = |thing: &str, label: &str| {
+        DataFrame::new(vec![Series::new("thing", [thing]), Series::new("label", [label])]).unwrap()
+    };
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    for (thing, label, language_tag) in [
+        ("http://example.net/ns#a", "pumpe", Some("nb")),
+        ("http://example.net/ns#b", "pumpe", Some("da")),
+        ("http://example.net/ns#c", "pumpe", None),
+    ] {
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df(thing, label),
+                ExpandOptions {
+                    language_tags: language_tag
+                        .map(|l| HashMap::from([("label".to_string(), l.to_string())])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    let mut subjects = |object: &str| {
+        let query = format!(
+            r#"
+            PREFIX ex:<http://example.net/ns#>
+            SELECT ?s WHERE {{ ?s ex:label {} }}
+            "#,
+            object
+        );
+        if let QueryResult::Select(df) = mapping.triplestore.query(&query).unwrap() {
+            let mut subjects: Vec<String> = df
+                .column("s")
+                .unwrap()
+                .utf8()
+                .unwrap()
+                .into_iter()
+                .map(|s| s.unwrap().to_string())
+                .collect();
+            subjects.sort();
+            subjects
+        } else {
+            panic!("Expected SELECT results")
+        }
+    };
+    assert_eq!(subjects("\"pumpe\"@nb"), vec!["http://example.net/ns#a"]);
+    assert_eq!(subjects("\"pumpe\"@NB"), vec!["http://example.net/ns#a"]);
+    assert_eq!(subjects("\"pumpe\""), vec!["http://example.net/ns#c"]);
+    assert!(subjects("\"pumpe\"@en").is_empty());
+}
+
+#[rstest]
+#[serial]
+fn test_native_parquet_round_trip() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ?label, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count),
+        ottr:Triple(?thing, <http://example.net/ns/count>, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a", "http://example.net/ns#b"]),
+        Series::new("label", ["a", "b"]),
+        Series::new("count", [1i64, 2]),
+    ])
+    .unwrap();
+    let folder = std::env::temp_dir().join("stottrs_native_parquet_round_trip");
+    let caching_folder = std::env::temp_dir().join("stottrs_native_parquet_round_trip_cache");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+
+    for caching in [None, Some(caching_folder.to_str().unwrap().to_string())] {
+        std::fs::create_dir_all(&folder).unwrap();
+        let mut mapping = Mapping::from_str(&t_str, caching).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#ExampleTemplate",
+                df.clone(),
+                ExpandOptions {
+                    language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        mapping
+            .write_native_parquet(folder.to_str().unwrap())
+            .unwrap();
+        let mut expected = mapping.export_oxrdf_triples().unwrap();
+        expected.sort_by_key(|t| t.to_string());
+
+        //ex:count and <http://example.net/ns/count> have the same file name
+        let mut triplestore = Triplestore::from_native_parquet(&folder).unwrap();
+        let mut triples = triplestore.export_oxrdf_triples().unwrap();
+        triples.sort_by_key(|t| t.to_string());
+        assert_eq!(triples.len(), 6);
+        assert_eq!(triples, expected);
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_native_parquet_round_trip_with_named_graphs() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a", "http://example.net/ns#b"]),
+        Series::new("count", [1i64, 2]),
+    ])
+    .unwrap();
+    let folder = std::env::temp_dir().join("stottrs_native_parquet_named_graphs");
+    std::fs::create_dir_all(&folder).unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df.clone(), Default::default())
+        .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df.head(Some(1)),
+            ExpandOptions {
+                graph: Some("http://example.net/ns#aGraph".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    mapping
+        .write_native_parquet(folder.to_str().unwrap())
+        .unwrap();
+    let mut expected = vec![];
+    mapping.write_n_quads(&mut expected).unwrap();
+    let mut expected: Vec<String> = String::from_utf8(expected)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    expected.sort();
+
+    let mut triplestore = Triplestore::from_native_parquet(&folder).unwrap();
+    assert_eq!(
+        triplestore.named_graphs(),
+        vec!["http://example.net/ns#aGraph"]
+    );
+    let mut actual = vec![];
+    triplestore
+        .write(&SerializationFormat::NQuads, &mut actual)
+        .unwrap();
+    let mut actual: Vec<String> = String::from_utf8(actual)
+        .unwrap()
+        .lines()
+        .map(|l| l.to_string())
+        .collect();
+    actual.sort();
+    assert_eq!(actual.len(), 3);
+    assert_eq!(actual, expected);
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_mapping_report_template_rows() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Named [xsd:anyURI ?thing, xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:hasName, ?name)
+      } .
+
+    ex:Thing [xsd:anyURI ?thing, ? xsd:string ?name, ? xsd:string ?alias]
+      :: {
+        ottr:Triple(?thing, ex:type, ex:Thing),
+        ex:Named(?thing, ?name),
+        ex:Named(?thing, ?alias)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            ["http://example.net/ns#A", "http://example.net/ns#B", "http://example.net/ns#C"],
+        ),
+        Series::new("name", [Some("a"), None, Some("c")]),
+        Series::new("alias", [Some("a"), Some("b"), None]),
+    ])
+    .unwrap();
+    let report = mapping
+        .expand("http://example.net/ns#Thing", df, Default::default())
+        .unwrap();
+    //Both instances of ex:Named are counted
+    assert_eq!(
+        report.template_rows,
+        vec![
+            TemplateRows {
+                template: "http://example.net/ns#Named".to_string(),
+                rows: 6
+            },
+            TemplateRows {
+                template: "http://example.net/ns#Thing".to_string(),
+                rows: 3
+            },
+        ]
+    );
+    let counts = report
+        .triple_counts
+        .iter()
+        .find(|c| c.predicate == "http://example.net/ns#hasName")
+        .unwrap();
+    assert_eq!(counts.input_rows, 6);
+    assert_eq!(counts.null_rows, 2);
+    assert!(report.elapsed > std::time::Duration::ZERO);
+}
+
+#[rstest]
+#[serial]
+fn test_validate_without_expanding() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count),
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let df = DataFrame::new(vec![
+        Series::new("thing", [Some("http://example.net/ns#a"), None]),
+        Series::new("count", ["1", "2"]),
+        Series::new("other", ["x", "y"]),
+    ])
+    .unwrap();
+    let report = mapping
+        .validate("http://example.net/ns#ExampleTemplate", &df, &Default::default())
+        .unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(report.errors.len(), 4);
+    assert!(matches!(&report.errors[0], MappingError::NonOptionalColumnHasNull(c, _) if c == "thing"));
+    assert!(matches!(&report.errors[1], MappingError::ColumnDataTypeMismatch(c, ..) if c == "count"));
+    assert!(matches!(&report.errors[2], MappingError::MissingParameterColumn(c) if c == "label"));
+    assert!(matches!(&report.errors[3], MappingError::ContainsIrrelevantColumns(c) if c == &vec!["other".to_string()]));
+    assert!(mapping.export_oxrdf_triples().unwrap().is_empty());
+
+    let report = mapping
+        .validate_dataframe("http://example.net/ns#ExampleTemplate", &df)
+        .unwrap();
+    assert_eq!(report.errors.len(), 4);
+    assert_eq!(report.missing_columns, vec!["label".to_string()]);
+    assert_eq!(report.extra_columns, vec!["other".to_string()]);
+    assert_eq!(report.type_mismatches.len(), 1);
+    assert_eq!(report.type_mismatches[0].column, "count");
+    assert_eq!(report.type_mismatches[0].data_type, DataType::Utf8);
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("count", [1i64]),
+        Series::new("label", ["a"]),
+    ])
+    .unwrap();
+    let report = mapping
+        .validate("http://example.net/ns#ExampleTemplate", &df, &Default::default())
+        .unwrap();
+    assert!(report.is_valid());
+}
+
+#[rstest]
+#[serial]
+fn test_declared_units() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix qudt:<http://qudt.org/schema/qudt/>.
+
+    ex:Measurement [xsd:anyURI ?quantity, xsd:double ?value, xsd:string ?label]
+      :: {
+        ottr:Triple(?quantity, qudt:numericValue, ?value),
+        ottr:Triple(?quantity, ex:label, ?label)
+      } .
+
+    ex:Reading [xsd:anyURI ?sensor, xsd:double ?reading]
+      :: {
+        ottr:Triple(?sensor, ex:reading, ?reading)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let deg_c = NamedNode::new_unchecked("http://qudt.org/vocab/unit/DEG_C");
+    mapping
+        .declare_unit(
+            "http://example.net/ns#Measurement",
+            "value",
+            UnitAnnotation {
+                unit: deg_c.clone(),
+                mode: UnitMode::UnitTriple,
+            },
+        )
+        .unwrap();
+    mapping
+        .declare_unit(
+            "http://example.net/ns#Reading",
+            "reading",
+            UnitAnnotation {
+                unit: deg_c.clone(),
+                mode: UnitMode::Datatype,
+            },
+        )
+        .unwrap();
+    assert!(matches!(
+        mapping.declare_unit(
+            "http://example.net/ns#Measurement",
+            "label",
+            UnitAnnotation {
+                unit: deg_c.clone(),
+                mode: UnitMode::UnitTriple,
+            },
+        ),
+        Err(MappingError::UnitForNonNumericParameter(..))
+    ));
+    assert!(matches!(
+        mapping.declare_unit(
+            "http://example.net/ns#Measurement",
+            "nothing",
+            UnitAnnotation {
+                unit: deg_c,
+                mode: UnitMode::UnitTriple,
+            },
+        ),
+        Err(MappingError::UnknownUnitParameter(..))
+    ));
+
+    let df = DataFrame::new(vec![
+        Series::new("quantity", ["http://example.net/ns#q"]),
+        Series::new("value", [21.5f64]),
+        Series::new("label", ["temperature"]),
+    ])
+    .unwrap();
+    mapping
+        .expand("http://example.net/ns#Measurement", df, Default::default())
+        .unwrap();
+    let df = DataFrame::new(vec![
+        Series::new("sensor", ["http://example.net/ns#s"]),
+        Series::new("reading", [22.5f64]),
+    ])
+    .unwrap();
+    mapping
+        .expand("http://example.net/ns#Reading", df, Default::default())
+        .unwrap();
+
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert!(nt.contains(
+        "<http://example.net/ns#q> <http://qudt.org/schema/qudt/unit> <http://qudt.org/vocab/unit/DEG_C> ."
+    ));
+    assert!(nt.contains(
+        "<http://example.net/ns#s> <http://example.net/ns#reading> \"22.5\"^^<http://qudt.org/vocab/unit/DEG_C> ."
+    ));
+    assert!(!nt.contains("<http://example.net/ns#s> <http://qudt.org/schema/qudt/unit>"));
+}
+
+#[rstest]
+#[serial]
+fn test_strict_validation_rejects_null_arguments() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Labelled [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ? xsd:string ?label]
+      :: {
+        ex:Labelled(?thing, ?label)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a", "http://example.net/ns#b"]),
+        Series::new("label", [Some("a"), None]),
+    ])
+    .unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df.clone(), Default::default())
+        .unwrap();
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 1);
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df,
+        ExpandOptions {
+            validation: ValidationMode::Strict,
+            ..Default::default()
+        },
+    );
+    match res {
+        Err(MappingError::NullArgumentForNonOptionalParameter(column, template, parameter, rows)) => {
+            assert_eq!(column, "label");
+            assert_eq!(template, "http://example.net/ns#Labelled");
+            assert_eq!(parameter, "label");
+            assert_eq!(rows.height(), 1);
+        }
+        _ => panic!("Expected a null argument error"),
+    }
+    assert!(mapping.export_oxrdf_triples().unwrap().is_empty());
+}
+
+#[rstest]
+#[serial]
+fn test_seeded_blank_nodes_are_reproducible() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Measured [xsd:anyURI ?thing, xsd:double ?value]
+      :: {
+        ottr:Triple(?thing, ex:hasMeasurement, _:m),
+        ex:Measurement(_:m, ?value)
+      } .
+
+    ex:Measurement [?m, xsd:double ?value]
+      :: {
+        ottr:Triple(?m, ex:value, ?value),
+        ottr:Triple(?m, ex:unit, _:u)
+      } .
+    "#;
+    let write = |seed: Option<u64>| {
+        let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+        mapping.set_seed(seed);
+        for value in [1.5f64, 2.5] {
+            let df = DataFrame::new(vec![
+                Series::new("thing", ["http://example.net/ns#a"]),
+                Series::new("value", [value]),
+            ])
+            .unwrap();
+            mapping
+                .expand("http://example.net/ns#Measured", df, Default::default())
+                .unwrap();
+        }
+        let mut buf = vec![];
+        mapping.write_n_triples(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+    let seeded = write(Some(42));
+    assert_eq!(seeded.lines().count(), 6);
+    assert_eq!(seeded, write(Some(42)));
+    assert_ne!(seeded, write(Some(43)));
+    assert_ne!(write(None), write(None));
+}
+
+#[rstest]
+#[serial]
+fn test_coercion_casts_columns_to_parameter_types() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count, xsd:long ?other]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count),
+        ottr:Triple(?thing, ex:other, ?other)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("count", ["42"]),
+        Series::new("other", [7i32]),
+    ])
+    .unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df.clone(),
+        Default::default(),
+    );
+    assert!(matches!(res, Err(MappingError::ColumnDataTypeMismatch(c, ..)) if c == "count"));
+
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df.clone(),
+        ExpandOptions {
+            coercion: Some(Coercion {
+                policy: CoercionPolicy::Cast,
+                columns: HashMap::from([("count".to_string(), CoercionPolicy::Never)]),
+            }),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(res, Err(MappingError::ColumnDataTypeMismatch(c, ..)) if c == "count"));
+
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                coercion: Some(Coercion {
+                    policy: CoercionPolicy::Cast,
+                    columns: Default::default(),
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert!(nt.contains(
+        "<http://example.net/ns#a> <http://example.net/ns#count> \"42\"^^<http://www.w3.org/2001/XMLSchema#long> ."
+    ));
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("count", ["forty-two"]),
+        Series::new("other", [7i32]),
+    ])
+    .unwrap();
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df,
+        ExpandOptions {
+            coercion: Some(Coercion {
+                policy: CoercionPolicy::Cast,
+                columns: Default::default(),
+            }),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(res, Err(MappingError::ColumnCoercionFailed(c, ..)) if c == "count"));
+}
+
+#[cfg(feature = "serde")]
+#[rstest]
+#[serial]
+fn test_query_serde_rows_in_batches() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            [
+                "http://example.net/ns#one",
+                "http://example.net/ns#two",
+                "http://example.net/ns#three",
+            ],
+        ),
+        Series::new("count", [1i64, 2, 3]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?count WHERE {
+        ?s ex:count ?count .
+    } ORDER BY ?count
+    "#;
+    let batches: Vec<_> = mapping
+        .triplestore
+        .query_serde_rows(query, 2)
+        .unwrap()
+        .collect();
+    assert_eq!(batches.iter().map(|b| b.len()).collect::<Vec<_>>(), vec![2, 1]);
+    let json = serde_json::to_string(&batches[0][0]).unwrap();
+    assert_eq!(
+        json,
+        r#"{"count":{"type":"literal","value":"1","datatype":"http://www.w3.org/2001/XMLSchema#long"},"s":{"type":"uri","value":"http://example.net/ns#one"}}"#
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_named_arguments() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Library [xsd:anyURI ?thing, ? xsd:string ?comment = "No comment", xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:comment, ?comment)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?a, xsd:string ?b]
+      :: {
+        ex:Library(?label = ?b, ?thing = ?a)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("a", ["http://example.net/ns#a"]),
+        Series::new("b", ["A"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+    let triples: HashSet<Triple> = mapping.export_oxrdf_triples().unwrap().into_iter().collect();
+    let triple = |p: &str, o: &str| {
+        Triple::new(
+            NamedNode::new_unchecked("http://example.net/ns#a"),
+            NamedNode::new_unchecked(format!("http://example.net/ns#{}", p)),
+            Literal::new_simple_literal(o),
+        )
+    };
+    assert_eq!(
+        triples,
+        HashSet::from([triple("label", "A"), triple("comment", "No comment")])
+    );
+
+    let bad_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Library [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+
+    ex:ExampleTemplate [xsd:anyURI ?a, xsd:string ?b]
+      :: {
+        ex:Library(?thing = ?a, ?name = ?b)
+      } .
+    "#;
+    assert!(Mapping::from_str(&bad_str, None).is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_duplicate_columns() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, ? xsd:string ?label_1]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:otherLabel, ?label_1)
+      } .
+    "#;
+    let df = DataFrame::new_no_checks(vec![
+        Series::new("thing", ["http://example.net/ns#a"]),
+        Series::new("label", ["first"]),
+        Series::new("label", ["second"]),
+    ]);
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df.clone(),
+        Default::default(),
+    );
+    assert!(matches!(res, Err(MappingError::DuplicateColumns(c)) if c == vec!["label".to_string()]));
+
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df.clone(),
+        ExpandOptions {
+            duplicate_columns: DuplicateColumns::Select(HashMap::from([("label".to_string(), 2)])),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(res, Err(MappingError::InvalidDuplicateColumnSelection(c, 2, 2)) if c == "label"));
+
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df.clone(),
+            ExpandOptions {
+                duplicate_columns: DuplicateColumns::Suffix,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert!(nt.contains("<http://example.net/ns#a> <http://example.net/ns#label> \"first\" ."));
+    assert!(nt.contains("<http://example.net/ns#a> <http://example.net/ns#otherLabel> \"second\" ."));
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                duplicate_columns: DuplicateColumns::Select(HashMap::from([("label".to_string(), 1)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut buf = vec![];
+    mapping.write_n_triples(&mut buf).unwrap();
+    let nt = std::str::from_utf8(&buf).unwrap();
+    assert!(nt.contains("<http://example.net/ns#a> <http://example.net/ns#label> \"second\" ."));
+    assert!(!nt.contains("first"));
+}
+
+#[rstest]
+#[serial]
+fn test_save_and_load_project() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Measured [xsd:anyURI ?thing, xsd:double ?value, ? xsd:string ?label = "unnamed", ? ?other]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ex:Measurement(?value = ?value, ?thing = ?thing)
+      } .
+
+    ex:Measurement [! xsd:anyURI ?thing, xsd:double ?value, List<xsd:string> ?tags = ("a", "b")]
+      :: {
+        ottr:Triple(?thing, ex:value, ?value),
+        cross | ottr:Triple(?thing, ex:tag, ++?tags)
+      } .
+    "#;
+    let df = |thing: &str, value: f64| {
+        DataFrame::new(vec![
+            Series::new("thing", [thing]),
+            Series::new("value", [value]),
+        ])
+        .unwrap()
+    };
+    let folder = std::env::temp_dir().join("stottrs_save_and_load_project");
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.set_seed(Some(42));
+    mapping
+        .declare_key(
+            "http://example.net/ns#Measured",
+            TemplateKey {
+                unique_subsets: vec![vec!["thing".to_string()]],
+                globally_unique: false,
+            },
+        )
+        .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#Measured",
+            df("http://example.net/ns#a", 1.5),
+            Default::default(),
+        )
+        .unwrap();
+    mapping
+        .save_project(folder.to_str().unwrap())
+        .unwrap();
+
+    let mut loaded = Mapping::load_project(folder.to_str().unwrap()).unwrap();
+    std::fs::remove_dir_all(&folder).unwrap();
+    assert_eq!(
+        loaded.template_dataset().templates.len(),
+        mapping.template_dataset().templates.len()
+    );
+    for m in [&mut mapping, &mut loaded] {
+        m.expand(
+            "http://example.net/ns#Measured",
+            df("http://example.net/ns#b", 2.5),
+            Default::default(),
+        )
+        .unwrap();
+    }
+    let mut expected = mapping.export_oxrdf_triples().unwrap();
+    expected.sort_by_key(|t| t.to_string());
+    let mut triples = loaded.export_oxrdf_triples().unwrap();
+    triples.sort_by_key(|t| t.to_string());
+    assert_eq!(triples.len(), 8);
+    assert_eq!(triples, expected);
+}
+
+#[rstest]
+#[serial]
+fn test_save_and_load_project_with_store_settings() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ex:anObject)
+      } .
+    "#;
+    let df = |thing: &str| DataFrame::new(vec![Series::new("thing", [thing])]).unwrap();
+    let folder = std::env::temp_dir().join("stottrs_save_and_load_project_settings");
+    let caching_folder = std::env::temp_dir().join("stottrs_save_and_load_project_settings_cache");
+    std::fs::create_dir_all(&caching_folder).unwrap();
+    let mut mapping =
+        Mapping::from_str(&t_str, Some(caching_folder.to_str().unwrap().to_string())).unwrap();
+    mapping.triplestore.set_iri_validation(Some(IriValidation {
+        schemes: vec!["http".to_string()],
+        max_length: None,
+    }));
+    mapping.triplestore.set_retention_policy(
+        "http://example.net/ns#relatesTo",
+        RetentionPolicy::MaxRows(10),
+    );
+    mapping.triplestore.set_tenant_quota("acme", 1);
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df("http://example.net/ns#a"),
+            Default::default(),
+        )
+        .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df("http://example.net/ns#b"),
+            ExpandOptions {
+                graph: Some("http://example.net/ns#aGraph".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    mapping
+        .save_project(folder.to_str().unwrap())
+        .unwrap();
+    let options = std::fs::read_to_string(folder.join("options.tsv")).unwrap();
+
+    let mut loaded = Mapping::load_project(folder.to_str().unwrap()).unwrap();
+    std::fs::remove_dir_all(&folder).unwrap();
+    let quads = |m: &mut Mapping| {
+        let mut buffer = vec![];
+        m.write_n_quads(&mut buffer).unwrap();
+        let mut quads: Vec<String> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        quads.sort();
+        quads
+    };
+    assert_eq!(quads(&mut loaded), quads(&mut mapping));
+    assert_eq!(quads(&mut loaded).len(), 2);
+
+    //The settings are saved again as they were loaded
+    loaded
+        .save_project(folder.to_str().unwrap())
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(folder.join("options.tsv")).unwrap(),
+        options
+    );
+    std::fs::remove_dir_all(&folder).unwrap();
+
+    //The loaded store validates IRIs and checks the quota of the tenant
+    loaded
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df("ftp://example.net/c"),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(loaded.triplestore.take_iri_violations().height(), 1);
+    loaded
+        .expand_for_tenant(
+            "acme",
+            "http://example.net/ns#ExampleTemplate",
+            df("http://example.net/ns#d"),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(loaded
+        .expand_for_tenant(
+            "acme",
+            "http://example.net/ns#ExampleTemplate",
+            df("http://example.net/ns#e"),
+            Default::default(),
+        )
+        .is_err());
+    std::fs::remove_dir_all(&caching_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_save_project_rejects_custom_datatypes() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ex:anObject)
+      } .
+    "#;
+    let folder = std::env::temp_dir().join("stottrs_save_project_custom_datatypes");
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.triplestore.register_datatype(
+        NamedNode::new_unchecked("http://example.net/ns#unitValue"),
+        CustomDatatype::new(DataType::Float64),
+    );
+    let error = mapping
+        .save_project(folder.to_str().unwrap())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("custom datatypes"), "{}", error);
+    assert!(!folder.exists());
+}
+
+#[cfg(feature = "endpoint")]
+#[rstest]
+#[serial]
+fn test_push_to_endpoint_retries_unavailable_endpoint() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use stottrs::triplestore::endpoint::PushOptions;
+
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            ["http://example.net/ns#a", "http://example.net/ns#b", "http://example.net/ns#c"],
+        ),
+        Series::new("label", ["a", "b", "c"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions::default(),
+        )
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/store", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(vec![]));
+    let server_bodies = bodies.clone();
+    std::thread::spawn(move || {
+        for (i, stream) in listener.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some(l) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = l.trim().parse().unwrap();
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let status = if i == 0 {
+                "503 Service Unavailable"
+            } else {
+                server_bodies
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(body).unwrap());
+                "200 OK"
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+        }
+    });
+
+    let options = PushOptions {
+        batch_size: 2,
+        initial_backoff: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let report = mapping
+        .triplestore
+        .push_to_endpoint_with_options(&url, None, None, &options)
+        .unwrap();
+    assert_eq!(report.retries, 1);
+    assert_eq!(report.batches, 2);
+    assert_eq!(report.triples, 3);
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 2);
+    assert_eq!(bodies.iter().map(|b| b.lines().count()).sum::<usize>(), 3);
+}
+
+#[cfg(feature = "remote")]
+#[rstest]
+#[serial]
+fn test_remote_template_resolver_fetches_dependencies_and_caches() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use stottrs::templates::remote::RemoteTemplateResolver;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let a = format!(
+        r#"
+    @prefix ex:<http://example.net/ns#>.
+    ex:A [xsd:anyURI ?thing] :: {{
+        <{}/lib/b#B>(?thing)
+      }} .
+    "#,
+        base
+    );
+    let b = format!(
+        r#"
+    @prefix ex:<http://example.net/ns#>.
+    <{}/lib/b#B> [xsd:anyURI ?thing] :: {{
+        ottr:Triple(?thing, ex:hasB, ex:b)
+      }} .
+    "#,
+        base
+    );
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server_requests = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request_line = String::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            reader.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            server_requests.fetch_add(1, Ordering::SeqCst);
+            let (status, body) = match request_line.split(' ').nth(1).unwrap() {
+                "/lib/a" => ("200 OK", a.as_str()),
+                "/lib/b" => ("200 OK", b.as_str()),
+                _ => ("404 Not Found", ""),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    let cache_folder = std::env::temp_dir().join("stottrs_remote_template_cache");
+    let _ = std::fs::remove_dir_all(&cache_folder);
+    let url = format!("{}/lib/a", base);
+    let resolver = RemoteTemplateResolver::new(Some(cache_folder.clone()));
+    let dataset = resolver.template_dataset(&[&url]).unwrap();
+    assert!(dataset.get("http://example.net/ns#A").is_some());
+    assert!(dataset.get(&format!("{}/lib/b#B", base)).is_some());
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    let dataset = TemplateDataset::from_url(&url, Some(cache_folder.clone())).unwrap();
+    assert_eq!(dataset.templates.len(), 3);
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+    let mut resolver = RemoteTemplateResolver::new(None);
+    resolver.resolve_dependencies = false;
+    assert!(resolver
+        .template_dataset(&[&format!("{}/lib/missing", base)])
+        .is_err());
+    std::fs::remove_dir_all(&cache_folder).unwrap();
+}
+
+#[rstest]
+#[serial]
+fn test_iri_validation_rejects_invalid_triples() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:anyURI ?other, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ?other),
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            ["http://example.net/ns#a", "urn:example:c", "http://example.net/ns#e"],
+        ),
+        Series::new(
+            "other",
+            ["http://example.net/ns#b", "http://example.net/ns#d", "http://example.net/ns#f g"],
+        ),
+        Series::new("label", ["a", "c", "e"]),
+    ])
+    .unwrap();
+
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.triplestore.set_iri_validation(Some(IriValidation {
+        schemes: vec!["http".to_string(), "https".to_string()],
+        max_length: Some(100),
+    }));
+    mapping
+        .triplestore
+        .set_predicate_iri_validation("http://example.net/ns#label", IriValidation::default());
+    let report = mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let added = |predicate: &str| {
+        report
+            .triple_counts
+            .iter()
+            .find(|c| c.predicate == predicate)
+            .unwrap()
+            .added_rows
+    };
+    assert_eq!(added("http://example.net/ns#relatesTo"), 1);
+    assert_eq!(added("http://example.net/ns#label"), 3);
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 4);
+
+    let violations = mapping
+        .triplestore
+        .take_iri_violations()
+        .sort(["subject"], vec![false])
+        .unwrap();
+    let column = |c: &str| -> Vec<Option<String>> {
+        violations
+            .column(c)
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.map(|s| s.to_string()))
+            .collect()
+    };
+    assert_eq!(
+        column("subject"),
+        vec![
+            Some("http://example.net/ns#e".to_string()),
+            Some("urn:example:c".to_string())
+        ]
+    );
+    assert_eq!(
+        column("position"),
+        vec![Some("object".to_string()), Some("subject".to_string())]
+    );
+    assert_eq!(
+        column("violation"),
+        vec![
+            Some("disallowed character".to_string()),
+            Some("scheme not allowed".to_string())
+        ]
+    );
+    assert_eq!(mapping.triplestore.take_iri_violations().height(), 0);
+}
+
+#[rstest]
+#[serial]
+fn test_wottr_template_annotations() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+    @prefix ottr:<http://ns.ottr.xyz/0.4/>.
+    @prefix xsd:<http://www.w3.org/2001/XMLSchema#>.
+
+    ex:Provenance a ottr:Template ;
+      ottr:parameters ( [ ottr:variable _:template ; ottr:type xsd:anyURI ]
+                        [ ottr:variable _:author ; ottr:type xsd:string ] ) ;
+      ottr:pattern [ ottr:of ottr:Triple ;
+                     ottr:values ( _:template ex:author _:author ) ] .
+
+    ex:ExampleTemplate a ottr:Template ;
+      ottr:parameters ( [ ottr:variable _:myVar1 ; ottr:type xsd:anyURI ] ) ;
+      ottr:annotation [ ottr:of ex:Provenance ;
+                        ottr:values ( ex:ExampleTemplate "Jane" ) ] ;
+      ottr:pattern [ ottr:of ottr:Triple ;
+                     ottr:values ( ex:anObject ex:relatesTo _:myVar1 ) ] .
+    "#;
+
+    let mut mapping = Mapping::from_wottr_str(&t_str, None).unwrap();
+    let v1 = Series::new("myVar1", ["http://example.net/ns#OneThing"]);
+    let df = DataFrame::new(vec![v1]).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                annotation_graph: Some("http://example.net/ns#provenance".to_string()),
+                ..Default::default()
+            },
+        )
+        .expect("");
+
+    let mut buffer = vec![];
+    mapping.write_n_quads(&mut buffer).unwrap();
+    let actual = String::from_utf8(buffer).unwrap();
+    let mut lines: Vec<&str> = actual.lines().collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "<http://example.net/ns#ExampleTemplate> <http://example.net/ns#author> \"Jane\" <http://example.net/ns#provenance> .",
+            "<http://example.net/ns#anObject> <http://example.net/ns#relatesTo> <http://example.net/ns#OneThing> .",
+        ]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_optional_cohorts_expansion() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Described [xsd:anyURI ?thing, ? xsd:string ?label = "unnamed", ? xsd:double ?weight, ? xsd:string ?unit]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:node, _:n),
+        ex:Weight(?thing, ?weight, ?unit)
+      } .
+
+    ex:Weight [xsd:anyURI ?thing, xsd:double ?weight, xsd:string ?unit]
+      :: {
+        ottr:Triple(?thing, ex:weight, ?weight),
+        ottr:Triple(?thing, ex:unit, ?unit)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "thing",
+            [
+                "http://example.net/ns#a",
+                "http://example.net/ns#b",
+                "http://example.net/ns#c",
+                "http://example.net/ns#d",
+            ],
+        ),
+        Series::new("label", [Some("A"), None, Some("C"), None]),
+        Series::new("weight", [Some(1.0), None, None, Some(4.0)]),
+        Series::new("unit", [Some("kg"), None, Some("g"), Some("g")]),
+    ])
+    .unwrap();
+    let expand = |strategy| {
+        let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+        mapping
+            .expand(
+                "http://example.net/ns#Described",
+                df.clone(),
+                ExpandOptions {
+                    strategy,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut buf = vec![];
+        mapping.write_n_triples(&mut buf).unwrap();
+        let mut lines: Vec<String> = std::str::from_utf8(&buf)
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        lines.sort();
+        lines
+    };
+    let full = expand(ExpansionStrategy::Full);
+    let cohorts = expand(ExpansionStrategy::OptionalCohorts);
+    let without_blank_nodes =
+        |lines: &Vec<String>| -> Vec<String> { lines.iter().filter(|l| !l.contains("_:")).cloned().collect() };
+    assert_eq!(without_blank_nodes(&full), without_blank_nodes(&cohorts));
+    assert!(cohorts.contains(
+        &"<http://example.net/ns#b> <http://example.net/ns#label> \"unnamed\" .".to_string()
+    ));
+    assert!(!cohorts
+        .iter()
+        .any(|l| l.starts_with("<http://example.net/ns#c> <http://example.net/ns#unit>")));
+
+    //Each row has its own blank node, also when the rows are in different cohorts
+    let blank_nodes: HashSet<&str> = cohorts
+        .iter()
+        .filter(|l| l.contains("<http://example.net/ns#node>"))
+        .map(|l| l.split(' ').nth(2).unwrap())
+        .collect();
+    assert_eq!(blank_nodes.len(), 4);
+}
+
+#[rstest]
+#[serial]
+fn test_template_dependency_validation() {
+    let missing_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Outer [xsd:anyURI ?a]
+      :: {
+        ex:Middle(?a)
+      } .
+
+    ex:Middle [xsd:anyURI ?a]
+      :: {
+        ottr:Triple(?a, ex:p, ex:o),
+        ex:Inner(?a)
+      } .
+    "#;
+    let doc = document_from_str(missing_str).unwrap();
+    let err = TemplateDataset::new(vec![doc]).unwrap_err();
+    assert!(matches!(&err.kind, TypingErrorType::MissingDependency(chain) if chain == &vec![
+        "http://example.net/ns#Outer".to_string(),
+        "http://example.net/ns#Middle".to_string(),
+        "http://example.net/ns#Inner".to_string(),
+    ]));
+    assert_eq!(
+        err.to_string(),
+        "Template http://example.net/ns#Inner is not defined, it is used through http://example.net/ns#Outer -> http://example.net/ns#Middle -> http://example.net/ns#Inner"
+    );
+
+    let cyclic_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Start [xsd:anyURI ?a]
+      :: {
+        ex:Ping(?a)
+      } .
+
+    ex:Ping [xsd:anyURI ?a]
+      :: {
+        ex:Pong(?a)
+      } .
+
+    ex:Pong [xsd:anyURI ?a]
+      :: {
+        ottr:Triple(?a, ex:p, ex:o),
+        ex:Ping(?a)
+      } .
+    "#;
+    let doc = document_from_str(cyclic_str).unwrap();
+    let err = TemplateDataset::new(vec![doc]).unwrap_err();
+    assert!(matches!(&err.kind, TypingErrorType::CyclicDependency(chain) if chain == &vec![
+        "http://example.net/ns#Ping".to_string(),
+        "http://example.net/ns#Pong".to_string(),
+        "http://example.net/ns#Ping".to_string(),
+    ]));
+
+    let ground_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Undefined(ex:a) .
+    "#;
+    let doc = document_from_str(ground_str).unwrap();
+    let err = TemplateDataset::new(vec![doc]).unwrap_err();
+    assert!(matches!(&err.kind, TypingErrorType::MissingDependency(chain) if chain == &vec![
+        "http://example.net/ns#Undefined".to_string(),
+    ]));
+}
+
+#[rstest]
+#[serial]
+fn test_write_with_serializers() {
+    struct SubjectCountSerializer;
+
+    impl TripleSerializer for SubjectCountSerializer {
+        fn serialize(
+            &self,
+            triplestore: &mut Triplestore,
+            sink: &mut dyn Write,
+        ) -> Result<(), MappingError> {
+            let mut subjects = HashSet::new();
+            for t in triplestore.export_oxrdf_triples()? {
+                subjects.insert(t.subject.to_string());
+            }
+            writeln!(sink, "{}", subjects.len()).map_err(MappingError::WriteNTriplesError)
+        }
+    }
+
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:relatesTo, ex:Other)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#a", "http://example.net/ns#b"]),
+        Series::new("label", ["A", "B"]),
+    ])
+    .unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+
+    let mut expected = vec![];
+    mapping.write_n_triples(&mut expected).unwrap();
+    let mut actual = vec![];
+    mapping
+        .write(
+            &SerializationFormat::NTriples(NTriplesSerializer::default()),
+            &mut actual,
+        )
+        .unwrap();
+    assert_eq!(actual, expected);
+
+    let mut actual = vec![];
+    let turtle = TurtleSerializer {
+        prefix_map: HashMap::from([(
+            "ex".to_string(),
+            NamedNode::new_unchecked("http://example.net/ns#"),
+        )]),
+    };
+    mapping
+        .triplestore
+        .write(&SerializationFormat::Turtle(turtle), &mut actual)
+        .unwrap();
+    let turtle = std::str::from_utf8(&actual).unwrap();
+    assert!(turtle.starts_with("@prefix ex: <http://example.net/ns#> ."));
+    assert!(turtle.contains("ex:a"));
+
+    let mut actual = vec![];
+    mapping
+        .write(
+            &SerializationFormat::Custom(Box::new(SubjectCountSerializer)),
+            &mut actual,
+        )
+        .unwrap();
+    assert_eq!(std::str::from_utf8(&actual).unwrap(), "2\n");
+}
+
+#[rstest]
+#[serial]
+fn test_trust_schema_cache() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?count]
+      :: {
+        ottr:Triple(?thing, ex:count, ?count)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let options = ExpandOptions {
+        trust_schema_cache: true,
+        ..Default::default()
+    };
+    let batch = |things: Vec<Option<&str>>, counts: Series| {
+        DataFrame::new(vec![Series::new("thing", things), counts]).unwrap()
+    };
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            batch(vec![Some("http://example.net/ns#a")], Series::new("count", [1i64])),
+            options.clone(),
+        )
+        .unwrap();
+
+    //The null in the non-optional column is not detected, as the schema is the same
+    let with_null = batch(
+        vec![Some("http://example.net/ns#b"), None],
+        Series::new("count", [2i64, 3]),
+    );
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        with_null.clone(),
+        Default::default(),
+    );
+    assert!(matches!(res, Err(MappingError::NonOptionalColumnHasNull(..))));
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            with_null,
+            options.clone(),
+        )
+        .unwrap();
+    assert_eq!(mapping.export_oxrdf_triples().unwrap().len(), 2);
+
+    //Another schema is validated again
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        batch(vec![Some("http://example.net/ns#c")], Series::new("count", ["4"])),
+        options,
+    );
+    assert!(matches!(res, Err(MappingError::ColumnDataTypeMismatch(..))));
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_csv_tsv_results() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:string ?label, xsd:long ?count, xsd:string ?name]
+      :: {
+        ottr:Triple(?thing, ex:label, ?label),
+        ottr:Triple(?thing, ex:count, ?count),
+        ottr:Triple(?thing, ex:name, ?name)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#one"]),
+        Series::new("label", ["one"]),
+        Series::new("count", [3i64]),
+        Series::new("name", ["Thing \"1\",\tfirst\nline"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions {
+                language_tags: Some(HashMap::from([("label".to_string(), "en".to_string())])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?label ?count ?name WHERE {
+        ?s ex:label ?label .
+        ?s ex:count ?count .
+        ?s ex:name ?name .
+    }
+    "#;
+
+    let mut csv = vec![];
+    mapping
+        .triplestore
+        .query_to_sparql_results(query, SparqlResultsFormat::Csv, &mut csv)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "s,label,count,name\r\nhttp://example.net/ns#one,one,3,\"Thing \"\"1\"\",\tfirst\nline\"\r\n"
+    );
+
+    let mut tsv = vec![];
+    mapping
+        .triplestore
+        .query_to_sparql_results(query, SparqlResultsFormat::Tsv, &mut tsv)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(tsv).unwrap(),
+        concat!(
+            "?s\t?label\t?count\t?name\n",
+            "<http://example.net/ns#one>\t\"one\"@en\t\"3\"^^<http://www.w3.org/2001/XMLSchema#long>\t",
+            "\"Thing \\\"1\\\",\\tfirst\\nline\"\n"
+        )
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_custom_datatypes() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, ex:unitValue ?weight]
+      :: {
+        ottr:Triple(?thing, ex:weight, ?weight)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#one"]),
+        Series::new("weight", ["1.5"]),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let formatter: LexicalFormatter = Arc::new(|s: &Series| {
+        s.cast(&DataType::Utf8)
+            .unwrap()
+            .utf8()
+            .unwrap()
+            .apply(|v| Cow::Owned(format!("{} kg", v)))
+            .into_series()
+    });
+    mapping.triplestore.register_datatype(
+        NamedNode::new_unchecked("http://example.net/ns#unitValue"),
+        CustomDatatype::new(DataType::Float64).with_formatter(formatter),
+    );
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            ExpandOptions::default(),
+        )
+        .unwrap();
+
+    let mut out = vec![];
+    mapping.write_n_triples(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "<http://example.net/ns#one> <http://example.net/ns#weight> \"1.5 kg\"^^<http://example.net/ns#unitValue> .\n"
+    );
+
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?s ?weight WHERE {
+        ?s ex:weight ?weight .
+    }
+    "#;
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.column("weight").unwrap().dtype(), &DataType::Float64);
+    } else {
+        panic!("Expected select results");
+    }
+    let mut tsv = vec![];
+    mapping
+        .triplestore
+        .query_to_sparql_results(query, SparqlResultsFormat::Tsv, &mut tsv)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(tsv).unwrap(),
+        "?s\t?weight\n<http://example.net/ns#one>\t\"1.5 kg\"^^<http://example.net/ns#unitValue>\n"
+    );
+
+    let df = DataFrame::new(vec![
+        Series::new("thing", ["http://example.net/ns#two"]),
+        Series::new("weight", ["heavy"]),
+    ])
+    .unwrap();
+    let res = mapping.expand(
+        "http://example.net/ns#ExampleTemplate",
+        df,
+        ExpandOptions::default(),
+    );
+    assert!(res.is_err());
+}
+
+#[rstest]
+#[serial]
+fn test_construct_to_new_store() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?myThing, xsd:anyURI ?myPart]
+      :: {
+        ottr:Triple(?myThing, ex:hasPart, ?myPart)
+      } .
+    "#;
+    let df = DataFrame::new(vec![
+        Series::new(
+            "myThing",
+            ["http://example.net/ns#one", "http://example.net/ns#two"],
+        ),
+        Series::new(
+            "myPart",
+            ["http://example.net/ns#two", "http://example.net/ns#three"],
+        ),
+    ])
+    .unwrap();
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping
+        .expand(
+            "http://example.net/ns#ExampleTemplate",
+            df,
+            Default::default(),
+        )
+        .unwrap();
+    let mut inferred = mapping
+        .triplestore
+        .construct_to_new_store(
+            r#"
+    PREFIX ex:<http://example.net/ns#>
+    CONSTRUCT { ?part ex:partOf ?thing } WHERE {
+        ?thing ex:hasPart ?part .
+    }
+    "#,
+        )
+        .unwrap();
+    let part_of = NamedNode::new_unchecked("http://example.net/ns#partOf");
+    let has_part = NamedNode::new_unchecked("http://example.net/ns#hasPart");
+    let inferred_triples: HashSet<Triple> =
+        inferred.export_oxrdf_triples().unwrap().into_iter().collect();
+    let expected: HashSet<Triple> = [("two", "one"), ("three", "two")]
+        .into_iter()
+        .map(|(s, o)| {
+            Triple::new(
+                NamedNode::new_unchecked(format!("http://example.net/ns#{}", s)),
+                part_of.clone(),
+                NamedNode::new_unchecked(format!("http://example.net/ns#{}", o)),
+            )
+        })
+        .collect();
+    assert_eq!(inferred_triples, expected);
+
+    let source_triples = mapping.export_oxrdf_triples().unwrap();
+    assert_eq!(source_triples.len(), 2);
+    assert!(source_triples.iter().all(|t| t.predicate == has_part));
+
+    let res = mapping.triplestore.construct_to_new_store(
+        r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing WHERE { ?thing ex:hasPart ?part . }
+    "#,
+    );
+    assert!(matches!(res, Err(SparqlError::QueryTypeNotSupported)));
+}
+
+#[rstest]
+#[serial]
+fn test_object_index_matches_unindexed_queries() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:anyURI ?other, xsd:string ?label, xsd:long ?number]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ?other) ,
+        ottr:Triple(?thing, ex:label, ?label) ,
+        ottr:Triple(?thing, ex:hasNumber, ?number)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    //Two expansions give the tables two chunks each
+    for (things, others, labels, numbers) in [
+        (["A", "B"], ["X", "Y"], ["a", "b"], [1i64, 2]),
+        (["C", "D"], ["X", "Z"], ["a", "c"], [1i64, 3]),
+    ] {
+        let iri = |x: &str| format!("http://example.net/ns#{}", x);
+        let thing = Series::new("thing", things.map(iri).to_vec());
+        let other = Series::new("other", others.map(iri).to_vec());
+        let label = Series::new("label", labels);
+        let number = Series::new("number", numbers);
+        let df = DataFrame::new(vec![thing, other, label, number]).unwrap();
+        mapping
+            .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+            .unwrap();
+    }
+
+    let queries = [
+        r#"PREFIX ex:<http://example.net/ns#>
+        SELECT ?thing WHERE { ?thing ex:relatesTo ex:X }"#,
+        r#"PREFIX ex:<http://example.net/ns#>
+        SELECT ?thing WHERE { ?thing ex:relatesTo ex:Nothing }"#,
+        r#"PREFIX ex:<http://example.net/ns#>
+        SELECT ?thing WHERE { ?thing ex:label "a" }"#,
+        r#"PREFIX ex:<http://example.net/ns#>
+        SELECT ?thing WHERE { ?thing ex:hasNumber 1 }"#,
+    ];
+    let query_things = |mapping: &mut Mapping| -> Vec<Vec<String>> {
+        queries
+            .iter()
+            .map(|q| {
+                if let QueryResult::Select(df) = mapping.triplestore.query(q).unwrap() {
+                    let mut things: Vec<String> = df
+                        .column("thing")
+                        .unwrap()
+                        .utf8()
+                        .unwrap()
+                        .into_iter()
+                        .map(|t| t.unwrap().to_string())
+                        .collect();
+                    things.sort();
+                    things
+                } else {
+                    panic!("Expected SELECT results");
+                }
+            })
+            .collect()
+    };
+    let unindexed = query_things(&mut mapping);
+    mapping.triplestore.add_object_index("http://example.net/ns#relatesTo");
+    mapping.triplestore.add_object_index("http://example.net/ns#label");
+    //No index is built for the non-string objects of this predicate
+    mapping.triplestore.add_object_index("http://example.net/ns#hasNumber");
+    let indexed = query_things(&mut mapping);
+    assert_eq!(indexed, unindexed);
+    let a_and_c = vec![
+        "http://example.net/ns#A".to_string(),
+        "http://example.net/ns#C".to_string(),
+    ];
+    assert_eq!(
+        indexed,
+        vec![a_and_c.clone(), vec![], a_and_c.clone(), a_and_c]
+    );
+}
+
+#[rstest]
+#[serial]
+fn test_sparql_describe() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:ExampleTemplate [xsd:anyURI ?thing, xsd:anyURI ?other, xsd:string ?label]
+      :: {
+        ottr:Triple(?thing, ex:relatesTo, ?other) ,
+        ottr:Triple(?thing, ex:label, ?label)
+      } .
+    "#;
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    let thing = Series::new(
+        "thing",
+        ["http://example.net/ns#A", "http://example.net/ns#B"],
+    );
+    let other = Series::new(
+        "other",
+        ["http://example.net/ns#B", "http://example.net/ns#C"],
+    );
+    let label = Series::new("label", ["a", "b"]);
+    let df = DataFrame::new(vec![thing, other, label]).unwrap();
+    mapping
+        .expand("http://example.net/ns#ExampleTemplate", df, Default::default())
+        .unwrap();
+
+    let described_triples = |res: QueryResult| -> HashSet<(String, String, String)> {
+        if let QueryResult::Describe(dfs) = res {
+            let mut triples = HashSet::new();
+            for (df, _) in dfs {
+                let column = |c: &str| -> Vec<String> {
+                    df.column(c)
+                        .unwrap()
+                        .cast(&DataType::Utf8)
+                        .unwrap()
+                        .utf8()
+                        .unwrap()
+                        .into_iter()
+                        .map(|x| x.unwrap().to_string())
+                        .collect()
+                };
+                for ((s, v), o) in column("subject")
+                    .into_iter()
+                    .zip(column("verb"))
+                    .zip(column("object"))
+                {
+                    triples.insert((s, v, o));
+                }
+            }
+            triples
+        } else {
+            panic!("Expected DESCRIBE results");
+        }
+    };
+    let ex = |x: &str| format!("http://example.net/ns#{}", x);
+    let b_as_subject = HashSet::from([
+        (ex("B"), ex("relatesTo"), ex("C")),
+        (ex("B"), ex("label"), "b".to_string()),
+    ]);
+
+    let res = mapping
+        .triplestore
+        .query("PREFIX ex:<http://example.net/ns#> DESCRIBE ex:B")
+        .unwrap();
+    assert_eq!(described_triples(res), b_as_subject);
+
+    let res = mapping
+        .triplestore
+        .query(
+            r#"PREFIX ex:<http://example.net/ns#>
+        DESCRIBE ?thing WHERE { ?thing ex:label "b" }"#,
+        )
+        .unwrap();
+    assert_eq!(described_triples(res), b_as_subject);
+
+    let res = mapping
+        .triplestore
+        .query_describe_with_objects("PREFIX ex:<http://example.net/ns#> DESCRIBE ex:B")
+        .unwrap();
+    let mut b_as_subject_or_object = b_as_subject.clone();
+    b_as_subject_or_object.insert((ex("A"), ex("relatesTo"), ex("B")));
+    assert_eq!(described_triples(res), b_as_subject_or_object);
+}
+
+#[rstest]
+#[serial]
+fn test_union_with_conflicting_object_dtypes() {
+    let t_str = r#"
+    @prefix ex:<http://example.net/ns#>.
+
+    ex:Start [xsd:anyURI ?thing, ?time]
+      :: {
+        ottr:Triple(?thing, ex:start, ?time)
+      } .
+
+    ex:End [xsd:anyURI ?thing, ?time]
+      :: {
+        ottr:Triple(?thing, ex:end, ?time)
+      } .
+    "#;
+    let query = r#"
+    PREFIX ex:<http://example.net/ns#>
+    SELECT ?thing ?time WHERE {{?thing ex:start ?time} UNION {?thing ex:end ?time}}
+    "#;
+    let expand = |mapping: &mut Mapping, template: &str, time_dtype: DataType| {
+        let thing = Series::new(
+            "thing",
+            ["http://example.net/ns#A", "http://example.net/ns#B"],
+        );
+        let time = Series::new("time", [1_000_000_000_000i64, 2_000_000_000_000])
+            .cast(&time_dtype)
+            .unwrap();
+        let df = DataFrame::new(vec![thing, time]).unwrap();
+        mapping.expand(template, df, Default::default()).unwrap();
+    };
+
+    //Datetimes with different time units have a common supertype
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    expand(
+        &mut mapping,
+        "http://example.net/ns#Start",
+        DataType::Datetime(TimeUnit::Milliseconds, None),
+    );
+    expand(
+        &mut mapping,
+        "http://example.net/ns#End",
+        DataType::Datetime(TimeUnit::Nanoseconds, None),
+    );
+    let error = mapping.triplestore.query(query).err().unwrap();
+    assert!(matches!(error, SparqlError::SchemaMismatch(..)));
+    let error_text = error.to_string();
+    assert!(
+        error_text.starts_with("Solution mappings have different schemas"),
+        "{}",
+        error_text
+    );
+    assert!(error_text.contains("column: \"time\""), "{}", error_text);
+
+    mapping.triplestore.set_harmonize_dtypes(true);
+    if let QueryResult::Select(df) = mapping.triplestore.query(query).unwrap() {
+        assert_eq!(df.height(), 4);
+        assert!(matches!(
+            df.column("time").unwrap().dtype(),
+            DataType::Datetime(_, None)
+        ));
+    } else {
+        panic!("Expected SELECT results");
+    }
+
+    //Datetimes in different time zones have none
+    let mut mapping = Mapping::from_str(&t_str, None).unwrap();
+    mapping.triplestore.set_harmonize_dtypes(true);
+    expand(
+        &mut mapping,
+        "http://example.net/ns#Start",
+        DataType::Datetime(TimeUnit::Milliseconds, Some("UTC".to_string())),
+    );
+    expand(
+        &mut mapping,
+        "http://example.net/ns#End",
+        DataType::Datetime(TimeUnit::Milliseconds, Some("Europe/Oslo".to_string())),
+    );
+    let error = mapping.triplestore.query(query).err().unwrap();
+    assert!(matches!(error, SparqlError::SchemaMismatch(..)));
+    let error_text = error.to_string();
+    assert!(error_text.contains("column: \"time\""), "{}", error_text);
+    assert!(error_text.contains("Europe/Oslo"), "{}", error_text);
+}
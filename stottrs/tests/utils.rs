@@ -14,17 +14,22 @@ pub fn triples_from_file(file: File) -> HashSet<Triple> {
         .parse_all(&mut |x| {
             let subject = match x.subject {
                 rio_api::model::Subject::NamedNode(nn) => {
-                    Subject::NamedNode(NamedNode::new_unchecked(nn.to_string()))
+                    Subject::NamedNode(NamedNode::new_unchecked(nn.iri))
                 }
                 rio_api::model::Subject::BlankNode(bn) => {
-                    Subject::BlankNode(BlankNode::new_unchecked(bn.to_string()))
+                    Subject::BlankNode(BlankNode::new_unchecked(bn.id))
                 }
                 rio_api::model::Subject::Triple(_) => {
                     unimplemented!("Not supported")
                 }
             };
-            let predicate = NamedNode::new_unchecked(x.predicate.to_string());
-            let object = Term::from_str(&x.object.to_string()).unwrap();
+            let predicate = NamedNode::new_unchecked(x.predicate.iri);
+            //Terms are not parsed from blank nodes
+            let object = if let rio_api::model::Term::BlankNode(bn) = x.object {
+                Term::BlankNode(BlankNode::new_unchecked(bn.id))
+            } else {
+                Term::from_str(&x.object.to_string()).unwrap()
+            };
             let t = Triple {
                 subject,
                 predicate,
@@ -0,0 +1,200 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use polars::prelude::{CsvWriter, SerWriter};
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use stottrs::mapping::{ExpandOptions, Mapping};
+use stottrs::triplestore::sparql::QueryResult;
+use stottrs::triplestore::Triplestore;
+
+#[derive(Parser)]
+#[command(name = "stottrs", version, about = "Map tabular data to RDF using OTTR templates")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Expand a template with the rows of a CSV, parquet or Arrow IPC file
+    Expand {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Output file for N-Triples, or output folder for parquet
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Ntriples)]
+        format: OutputFormat,
+    },
+    /// Expand a template, or read a folder written by expand with the parquet format,
+    /// and run a SPARQL SELECT query against the triples, writing the solutions as CSV
+    Query {
+        /// Folder with triples written by expand with the parquet format, queried instead of expanding a template
+        #[arg(
+            long,
+            conflicts_with = "templates",
+            required_unless_present = "templates"
+        )]
+        store: Option<PathBuf>,
+        /// A stOTTR document, or a folder of documents
+        #[arg(short, long, requires_all = ["template", "input"])]
+        templates: Option<PathBuf>,
+        /// Name or IRI of the template to expand
+        #[arg(long)]
+        template: Option<String>,
+        /// Input file, parquet and IPC inputs may also be glob patterns
+        #[arg(short, long)]
+        input: Option<String>,
+        /// Folder where triples are cached on disk during expansion
+        #[arg(long)]
+        caching_folder: Option<String>,
+        /// The SPARQL query, prefix with @ to read it from a file
+        #[arg(short, long)]
+        query: String,
+        /// Output file for the CSV, standard output if not given
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Args)]
+struct InputArgs {
+    /// A stOTTR document, or a folder of documents
+    #[arg(short, long)]
+    templates: PathBuf,
+    /// Name or IRI of the template to expand
+    #[arg(long)]
+    template: String,
+    /// Input file, parquet and IPC inputs may also be glob patterns
+    #[arg(short, long)]
+    input: String,
+    /// Folder where triples are cached on disk during expansion
+    #[arg(long)]
+    caching_folder: Option<String>,
+}
+
+#[derive(Clone, ValueEnum)]
+enum OutputFormat {
+    Ntriples,
+    Parquet,
+}
+
+fn main() {
+    env_logger::init();
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Expand {
+            input,
+            output,
+            format,
+        } => expand(input, output, format),
+        Command::Query {
+            store,
+            templates,
+            template,
+            input,
+            caching_folder,
+            query: q,
+            output,
+        } => {
+            //The template and input are required with the templates
+            let input = templates.map(|templates| InputArgs {
+                templates,
+                template: template.unwrap(),
+                input: input.unwrap(),
+                caching_folder,
+            });
+            query(store, input, q, output)
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn expand(
+    input: InputArgs,
+    output: Option<PathBuf>,
+    format: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut mapping = mapping_from_input(&input)?;
+    match format {
+        OutputFormat::Ntriples => {
+            let mut writer = output_writer(output.as_deref())?;
+            mapping.write_n_triples(&mut writer)?;
+            writer.flush()?;
+        }
+        OutputFormat::Parquet => {
+            let folder = output.ok_or("An output folder is required for parquet output")?;
+            fs::create_dir_all(&folder)?;
+            mapping.write_native_parquet(folder.to_str().unwrap())?;
+        }
+    }
+    Ok(())
+}
+
+fn query(
+    store: Option<PathBuf>,
+    input: Option<InputArgs>,
+    query: String,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let mut triplestore = if let Some(store) = store {
+        Triplestore::from_native_parquet(&store)?
+    } else {
+        mapping_from_input(&input.ok_or("Either a store or templates are required")?)?.triplestore
+    };
+    let query = if let Some(query_path) = query.strip_prefix('@') {
+        fs::read_to_string(query_path)?
+    } else {
+        query
+    };
+    match triplestore.query(&query)? {
+        QueryResult::Select(mut df) => {
+            let mut writer = output_writer(output.as_deref())?;
+            CsvWriter::new(&mut writer)
+                .has_header(true)
+                .finish(&mut df)?;
+            writer.flush()?;
+            Ok(())
+        }
+        _ => Err("Only SELECT queries can be written as CSV".into()),
+    }
+}
+
+fn mapping_from_input(input: &InputArgs) -> Result<Mapping, Box<dyn Error>> {
+    let mut mapping = if input.templates.is_dir() {
+        Mapping::from_folder(&input.templates, input.caching_folder.clone())?
+    } else {
+        Mapping::from_file(&input.templates, input.caching_folder.clone())?
+    };
+    let options = ExpandOptions::default();
+    let lowercase_input = input.input.to_lowercase();
+    if lowercase_input.ends_with(".csv") {
+        mapping.expand_from_csv(&input.template, &input.input, options)?;
+    } else if lowercase_input.ends_with(".parquet") {
+        mapping.expand_from_parquet(&input.template, &input.input, options)?;
+    } else if lowercase_input.ends_with(".ipc")
+        || lowercase_input.ends_with(".arrow")
+        || lowercase_input.ends_with(".feather")
+    {
+        mapping.expand_from_ipc(&input.template, &input.input, options)?;
+    } else {
+        return Err(format!(
+            "Could not determine the format of input {}, expected a .csv, .parquet or .ipc file",
+            input.input
+        )
+        .into());
+    }
+    Ok(mapping)
+}
+
+fn output_writer(output: Option<&Path>) -> Result<Box<dyn Write>, Box<dyn Error>> {
+    Ok(match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(stdout())),
+    })
+}
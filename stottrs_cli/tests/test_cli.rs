@@ -0,0 +1,134 @@
+use rstest::*;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[fixture]
+fn testdata_path() -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push("stottrs_cli_testdata");
+    fs::create_dir_all(&path).unwrap();
+    let stottr = r#"
+@prefix ex:<http://example.net/ns#>.
+ex:ExampleTemplate [xsd:anyURI ?thing, xsd:long ?number] :: {
+  ottr:Triple(?thing, ex:hasNumber, ?number)
+} .
+"#;
+    fs::write(path.join("templates.stottr"), stottr).unwrap();
+    fs::write(
+        path.join("input.csv"),
+        "thing,number\nhttp://example.net/ns#A,1\nhttp://example.net/ns#B,2\n",
+    )
+    .unwrap();
+    path
+}
+
+#[rstest]
+fn test_expand_csv_to_n_triples(testdata_path: PathBuf) {
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("expand")
+        .arg("--templates")
+        .arg(testdata_path.join("templates.stottr"))
+        .arg("--template")
+        .arg("http://example.net/ns#ExampleTemplate")
+        .arg("--input")
+        .arg(testdata_path.join("input.csv"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let mut lines: Vec<String> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|x| x.to_string())
+        .collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        vec![
+            "<http://example.net/ns#A> <http://example.net/ns#hasNumber> \"1\"^^<http://www.w3.org/2001/XMLSchema#long> .",
+            "<http://example.net/ns#B> <http://example.net/ns#hasNumber> \"2\"^^<http://www.w3.org/2001/XMLSchema#long> .",
+        ]
+    );
+}
+
+#[rstest]
+fn test_query_to_csv(testdata_path: PathBuf) {
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("query")
+        .arg("--templates")
+        .arg(testdata_path.join("templates.stottr"))
+        .arg("--template")
+        .arg("http://example.net/ns#ExampleTemplate")
+        .arg("--input")
+        .arg(testdata_path.join("input.csv"))
+        .arg("--query")
+        .arg("PREFIX ex:<http://example.net/ns#> SELECT ?a WHERE {?a ex:hasNumber ?n FILTER(?n > 1)}")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "a\nhttp://example.net/ns#B\n"
+    );
+}
+
+#[rstest]
+fn test_expand_to_parquet_and_query_store(testdata_path: PathBuf) {
+    let store = testdata_path.join("store");
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("expand")
+        .arg("--templates")
+        .arg(testdata_path.join("templates.stottr"))
+        .arg("--template")
+        .arg("http://example.net/ns#ExampleTemplate")
+        .arg("--input")
+        .arg(testdata_path.join("input.csv"))
+        .arg("--format")
+        .arg("parquet")
+        .arg("--output")
+        .arg(&store)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("query")
+        .arg("--store")
+        .arg(&store)
+        .arg("--query")
+        .arg("PREFIX ex:<http://example.net/ns#> SELECT ?a WHERE {?a ex:hasNumber ?n FILTER(?n > 1)}")
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&store).unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "a\nhttp://example.net/ns#B\n"
+    );
+}
+
+#[rstest]
+fn test_query_without_store_or_templates_fails() {
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("query")
+        .arg("--query")
+        .arg("SELECT ?a WHERE {?a ?b ?c}")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
+
+#[rstest]
+fn test_unknown_input_format_fails(testdata_path: PathBuf) {
+    let output = Command::new(env!("CARGO_BIN_EXE_stottrs"))
+        .arg("expand")
+        .arg("--templates")
+        .arg(testdata_path.join("templates.stottr"))
+        .arg("--template")
+        .arg("http://example.net/ns#ExampleTemplate")
+        .arg("--input")
+        .arg(testdata_path.join("input.txt"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}